@@ -21,6 +21,23 @@
           extensions = [ "rust-analyzer" "rust-src" ];
         };
 
+        # Node.js build shell for SvelteKit/Vite sites (used by worker)
+        nodeBuildShell = nodejs: pkgs.mkShell {
+          buildInputs = [
+            nodejs
+            # For repos with pnpm-lock.yaml or yarn.lock
+            pkgs.pnpm
+            pkgs.yarn-berry
+            pkgs.git
+            pkgs.cacert
+          ];
+
+          shellHook = ''
+            export SSL_CERT_FILE=${pkgs.cacert}/etc/ssl/certs/ca-bundle.crt
+            export NODE_OPTIONS="--max-old-space-size=4096"
+          '';
+        };
+
         # Configure crane with our toolchain
         craneLib = (crane.mkLib pkgs).overrideToolchain rustToolchain;
 
@@ -55,6 +72,8 @@
         catapult = craneLib.buildPackage (commonArgs // {
           inherit cargoArtifacts;
           doCheck = false; # Tests require testcontainers/postgres
+          # No .git in the sandbox; embedded in the /version endpoint
+          CATAPULT_GIT_COMMIT = self.rev or self.dirtyRev or "unknown";
         });
 
       in {
@@ -239,6 +258,7 @@
                   description = "Catapult Worker Server";
                   wantedBy = [ "multi-user.target" ];
                   after = [ "network.target" "podman.socket" ];
+                  path = [ pkgs.git pkgs.git-lfs ];
 
                   serviceConfig = {
                     Type = "simple";
@@ -313,35 +333,23 @@
             '';
           };
 
-          # Build environment for SvelteKit projects (used by worker)
-          sveltekit = pkgs.mkShell {
-            buildInputs = with pkgs; [
-              nodejs_22
-              nodePackages.npm
-              git
-              cacert
-            ];
-
-            shellHook = ''
-              export SSL_CERT_FILE=${pkgs.cacert}/etc/ssl/certs/ca-bundle.crt
-              export NODE_OPTIONS="--max-old-space-size=4096"
-            '';
-          };
-
-          # Build environment for Vite projects (used by worker)
-          vite = pkgs.mkShell {
-            buildInputs = with pkgs; [
-              nodejs_22
-              nodePackages.npm
-              git
-              cacert
-            ];
-
-            shellHook = ''
-              export SSL_CERT_FILE=${pkgs.cacert}/etc/ssl/certs/ca-bundle.crt
-              export NODE_OPTIONS="--max-old-space-size=4096"
-            '';
-          };
+          # Build environments for SvelteKit and Vite projects (used by worker)
+          # The unsuffixed shells use Node.js 22; `-node<version>` shells are
+          # selected by `node_version` in .deploy.json
+          sveltekit = nodeBuildShell pkgs.nodejs_22;
+          sveltekit-node20 = nodeBuildShell pkgs.nodejs_20;
+          sveltekit-node22 = nodeBuildShell pkgs.nodejs_22;
+          vite = nodeBuildShell pkgs.nodejs_22;
+          vite-node20 = nodeBuildShell pkgs.nodejs_20;
+          vite-node22 = nodeBuildShell pkgs.nodejs_22;
+
+          # Build environments for Docusaurus and VitePress documentation sites
+          docusaurus = nodeBuildShell pkgs.nodejs_22;
+          docusaurus-node20 = nodeBuildShell pkgs.nodejs_20;
+          docusaurus-node22 = nodeBuildShell pkgs.nodejs_22;
+          vitepress = nodeBuildShell pkgs.nodejs_22;
+          vitepress-node20 = nodeBuildShell pkgs.nodejs_20;
+          vitepress-node22 = nodeBuildShell pkgs.nodejs_22;
 
           # Build environment for Zola static sites (used by worker)
           zola = pkgs.mkShell {
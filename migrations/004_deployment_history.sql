@@ -0,0 +1,33 @@
+-- Deployment history table
+-- One row per dispatched build job. The latest row per site_id describes
+-- what the worker is (or should be) serving, so cleanup can find every
+-- active site for a repo or org without re-reading .deploy.json.
+
+CREATE TABLE IF NOT EXISTS deployment_history (
+  id SERIAL PRIMARY KEY,
+  job_id UUID NOT NULL UNIQUE,
+  github_org VARCHAR(255) NOT NULL,
+  github_repo VARCHAR(255) NOT NULL,
+  pr_number INTEGER,                        -- NULL for main branch deployments
+  site_id VARCHAR(255) NOT NULL,            -- e.g. 'nullislabs-website-pr-42'
+  zone VARCHAR(50) NOT NULL,                -- Worker zone the site lives on
+  domain VARCHAR(255) NOT NULL,             -- Fully resolved hostname
+  commit_sha VARCHAR(40) NOT NULL,
+  status VARCHAR(20) NOT NULL DEFAULT 'pending',  -- pending/building/success/failed/cleaned
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for finding a repo's deployments
+CREATE INDEX IF NOT EXISTS idx_deployment_history_repo
+  ON deployment_history(LOWER(github_org), LOWER(github_repo));
+
+-- Index for finding the latest deployment of a site
+CREATE INDEX IF NOT EXISTS idx_deployment_history_site
+  ON deployment_history(site_id, created_at DESC);
+
+-- Trigger to auto-update updated_at
+DROP TRIGGER IF EXISTS update_deployment_history_updated_at ON deployment_history;
+CREATE TRIGGER update_deployment_history_updated_at
+  BEFORE UPDATE ON deployment_history
+  FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
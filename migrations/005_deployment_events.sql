@@ -0,0 +1,14 @@
+-- Deployment events table
+-- Timestamped lifecycle phases (cloned, building, deployed, ...) reported by
+-- the worker for each build job, giving a timeline for debugging slow phases.
+
+CREATE TABLE IF NOT EXISTS deployment_events (
+  id BIGSERIAL PRIMARY KEY,
+  job_id UUID NOT NULL,
+  phase VARCHAR(50) NOT NULL,               -- cloned/building/deployed/caddy_configured/cloudflare_configured
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for reading a job's timeline in order
+CREATE INDEX IF NOT EXISTS idx_deployment_events_job
+  ON deployment_events(job_id, id);
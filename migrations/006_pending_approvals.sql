@@ -0,0 +1,16 @@
+-- Pending approvals table
+-- Main branch deployments to zones that require approval are held here
+-- instead of being dispatched. An operator approves them via the admin API;
+-- unapproved ones expire after the approval window.
+
+CREATE TABLE IF NOT EXISTS pending_approvals (
+  job_id UUID PRIMARY KEY,
+  installation_id BIGINT NOT NULL,          -- For a fresh clone token on approval
+  job JSONB NOT NULL,                       -- BuildJob to dispatch, without git_token
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMPTZ NOT NULL
+);
+
+-- Index for the expiry sweep
+CREATE INDEX IF NOT EXISTS idx_pending_approvals_expires
+  ON pending_approvals(expires_at);
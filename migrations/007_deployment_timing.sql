@@ -0,0 +1,7 @@
+-- Build timing for deployments
+-- started_at is set by the first `building` status from the worker,
+-- completed_at by the final `success`/`failed` status. PR comments report
+-- the difference as the build duration.
+
+ALTER TABLE deployment_history ADD COLUMN IF NOT EXISTS started_at TIMESTAMPTZ;
+ALTER TABLE deployment_history ADD COLUMN IF NOT EXISTS completed_at TIMESTAMPTZ;
@@ -0,0 +1,9 @@
+-- Per-org deployment quotas
+-- NULL means unlimited. Active deployments are sites whose latest deployment
+-- hasn't been cleaned up; disk usage sums the size workers report for them.
+
+ALTER TABLE authorized_orgs ADD COLUMN IF NOT EXISTS max_active_deployments INTEGER;
+ALTER TABLE authorized_orgs ADD COLUMN IF NOT EXISTS max_total_mb BIGINT;
+
+-- Size of the deployed site files, reported by the worker on success
+ALTER TABLE deployment_history ADD COLUMN IF NOT EXISTS size_bytes BIGINT;
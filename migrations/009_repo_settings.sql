@@ -0,0 +1,17 @@
+-- Per-repository settings toggled by operators
+-- Deploy settings come from .deploy.json; this table only holds runtime
+-- switches. A paused repository keeps its config and deployment history, but
+-- pushes and PR updates are acknowledged without dispatching builds.
+
+CREATE TABLE IF NOT EXISTS repo_settings (
+  id SERIAL PRIMARY KEY,
+  github_org VARCHAR(255) NOT NULL,
+  github_repo VARCHAR(255) NOT NULL,
+  paused BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- GitHub names are case-insensitive
+CREATE UNIQUE INDEX IF NOT EXISTS idx_repo_settings_repo
+  ON repo_settings(LOWER(github_org), LOWER(github_repo));
@@ -0,0 +1,6 @@
+-- How a deployment was triggered
+-- `webhook` for pushes and PRs, `artifact` for prebuilt archives deployed via
+-- the admin API, and `manual` for commit previews requested by an operator.
+
+ALTER TABLE deployment_history
+  ADD COLUMN IF NOT EXISTS deployment_type VARCHAR(20) NOT NULL DEFAULT 'webhook';
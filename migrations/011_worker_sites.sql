@@ -0,0 +1,14 @@
+-- Sites each worker reports it is serving
+-- Workers periodically send their full inventory, scanned from the site
+-- metadata in their sites directory. Each report replaces the zone's rows, so
+-- sites a worker no longer has disappear here too.
+
+CREATE TABLE IF NOT EXISTS worker_sites (
+  zone VARCHAR(50) NOT NULL,
+  site_id VARCHAR(255) NOT NULL,
+  domain VARCHAR(255) NOT NULL,
+  size_bytes BIGINT NOT NULL,
+  deployed_at TIMESTAMPTZ,
+  reported_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (zone, site_id)
+);
@@ -0,0 +1,12 @@
+-- Audit log of admin API actions
+-- One row per successful state-changing admin request, newest rows having the
+-- highest id. The payload holds the request parameters (never secrets).
+
+CREATE TABLE IF NOT EXISTS admin_audit (
+  id BIGSERIAL PRIMARY KEY,
+  actor VARCHAR(255) NOT NULL,              -- Admin key identity
+  action VARCHAR(64) NOT NULL,              -- e.g. 'auth.upsert', 'worker.disable'
+  target VARCHAR(255) NOT NULL,             -- Org, zone, repo or job ID acted on
+  payload JSONB,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,21 @@
+-- Mirrored workers for high availability
+-- A zone may have mirror workers next to its primary. Main branch deployments
+-- go to all of them; everything else still goes to the primary alone.
+
+ALTER TABLE workers DROP CONSTRAINT IF EXISTS workers_environment_key;
+ALTER TABLE workers ADD COLUMN IF NOT EXISTS mirror BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_workers_environment_endpoint
+  ON workers(environment, endpoint);
+
+-- Outcome of a mirrored deployment on each of its workers
+-- succeeded stays NULL until the worker reports a final status. The
+-- deployment's own status follows the quorum of these rows.
+CREATE TABLE IF NOT EXISTS deployment_mirrors (
+  job_id UUID NOT NULL,
+  worker_id INTEGER NOT NULL REFERENCES workers(id) ON DELETE CASCADE,
+  succeeded BOOLEAN,
+  error_message TEXT,
+  reported_at TIMESTAMPTZ,
+  PRIMARY KEY (job_id, worker_id)
+);
@@ -0,0 +1,18 @@
+-- Deployment event outbox
+-- Written in the same transaction as each deployment status change, so an
+-- event exists exactly when the change does. Central's publisher delivers
+-- rows to the configured sinks and deletes them once every sink accepted.
+
+CREATE TABLE IF NOT EXISTS deployment_outbox (
+  id BIGSERIAL PRIMARY KEY,
+  job_id UUID NOT NULL,
+  payload JSONB NOT NULL,                   -- Deployment as it stood after the change
+  attempts INTEGER NOT NULL DEFAULT 0,
+  last_error TEXT,
+  next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for claiming due events in order
+CREATE INDEX IF NOT EXISTS idx_deployment_outbox_due
+  ON deployment_outbox(next_attempt_at, id);
@@ -0,0 +1,7 @@
+-- Per-org repository filters
+-- Glob patterns (`*` any run of characters, `?` one character) matched
+-- case-insensitively against repo names. An empty allowlist allows every
+-- repo; a repo matching the denylist is rejected even if it is allowed.
+
+ALTER TABLE authorized_orgs ADD COLUMN IF NOT EXISTS repo_allowlist TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE authorized_orgs ADD COLUMN IF NOT EXISTS repo_denylist TEXT[] NOT NULL DEFAULT '{}';
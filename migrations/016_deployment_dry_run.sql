@@ -0,0 +1,5 @@
+-- Dry-run deployments
+-- A dry run is cloned and built but never published, so it doesn't replace
+-- the site's live deployment: queries for a site's current state skip it.
+
+ALTER TABLE deployment_history ADD COLUMN IF NOT EXISTS dry_run BOOLEAN NOT NULL DEFAULT FALSE;
@@ -83,6 +83,27 @@ in
         description = "Address and port to listen on";
       };
 
+      adminListenAddress = mkOption {
+        type = types.nullOr types.str;
+        default = null;
+        example = "127.0.0.1:8081";
+        description = "Separate address for the admin API (null serves it on listenAddress)";
+      };
+
+      adminSocketPath = mkOption {
+        type = types.nullOr types.str;
+        default = null;
+        example = "/run/catapult/admin.sock";
+        description = "UNIX socket for the admin API instead of a TCP address (mode 0660, owned by the central user and group)";
+      };
+
+      adminApiKeys = mkOption {
+        type = types.listOf types.str;
+        default = [ ];
+        example = [ "alice:write:<sha256 hex>" "dashboard:read:<sha256 hex>" ];
+        description = "Named admin API keys as name:scope:sha256, where scope is read or write";
+      };
+
       databaseUrl = mkOption {
         type = types.str;
         description = "PostgreSQL connection URL";
@@ -94,6 +115,27 @@ in
         description = "GitHub App ID";
       };
 
+      githubHost = mkOption {
+        type = types.str;
+        default = "github.com";
+        example = "github.example.com";
+        description = "Host repository clone URLs must use (GitHub Enterprise host, optionally with port)";
+      };
+
+      githubApiBase = mkOption {
+        type = types.str;
+        default = "https://api.github.com";
+        example = "https://github.example.com/api/v3";
+        description = "GitHub REST API base URL (GitHub Enterprise Server serves it under /api/v3)";
+      };
+
+      githubUserAgent = mkOption {
+        type = types.nullOr types.str;
+        default = null;
+        example = "catapult (ops@example.com)";
+        description = "User-Agent for GitHub API requests (null uses catapult/<version>)";
+      };
+
       githubPrivateKeyFile = mkOption {
         type = types.path;
         description = "Path to GitHub App private key PEM file";
@@ -104,9 +146,151 @@ in
         description = "Path to file containing GitHub webhook secret";
       };
 
+      extraGithubApps = mkOption {
+        type = types.attrsOf (types.submodule {
+          options = {
+            privateKeyFile = mkOption {
+              type = types.path;
+              description = "Path to the app's private key PEM file";
+            };
+            webhookSecretFile = mkOption {
+              type = types.path;
+              description = "Path to file containing the app's webhook secret";
+            };
+          };
+        });
+        default = { };
+        example = {
+          "654321" = {
+            privateKeyFile = "/var/lib/catapult/second-app-key.pem";
+            webhookSecretFile = "/var/lib/catapult/second-app-webhook-secret";
+          };
+        };
+        description = "Further GitHub Apps by app ID, for repos installed under an app other than githubAppId";
+      };
+
       workerSharedSecretFile = mkOption {
-        type = types.path;
-        description = "Path to file containing worker shared secret";
+        type = types.nullOr types.path;
+        default = null;
+        description = "Path to file containing the worker shared secret, used for zones without their own secret";
+      };
+
+      workerSecretFiles = mkOption {
+        type = types.attrsOf types.path;
+        default = { };
+        example = {
+          nullislabs = "/var/lib/catapult/worker-secret-nullislabs";
+        };
+        description = "Per-zone worker secret files; each worker then holds only its own zone's secret";
+      };
+
+      signingKeyFile = mkOption {
+        type = types.nullOr types.path;
+        default = null;
+        description = "Ed25519 private key (PKCS#8 PEM) to sign dispatches to zones in workerPublicKeyFiles";
+      };
+
+      workerPublicKeyFiles = mkOption {
+        type = types.attrsOf types.path;
+        default = { };
+        example = {
+          nullislabs = "/etc/catapult/worker-nullislabs.pub.pem";
+        };
+        description = "Per-zone worker Ed25519 public keys; these zones use signingKeyFile instead of a shared secret";
+      };
+
+      signatureMaxAgeSecs = mkOption {
+        type = types.ints.between 1 3600;
+        default = 300;
+        description = "Seconds a signed request from a worker stays valid, for slow networks";
+      };
+
+      signatureMaxSkewSecs = mkOption {
+        type = types.ints.between 0 600;
+        default = 60;
+        description = "Seconds a a worker request timestamp may be ahead of the local clock";
+      };
+
+      defaultDeployConfig = mkOption {
+        type = types.nullOr types.attrs;
+        default = null;
+        example = {
+          default = { zone = "nullislabs"; domain_pattern = "{repo}.nullislabs.io"; };
+          orgs.acme = { zone = "acme"; domain_pattern = "{repo}.acme.dev"; };
+        };
+        description = "Deploy config defaults (global and per-org) layered under .deploy.json files";
+      };
+
+      workerSettings = mkOption {
+        type = types.nullOr (types.attrsOf types.attrs);
+        default = null;
+        example = {
+          nullislabs = { cloudflare_tunnel_id = "your-tunnel-id"; keep_releases = 5; };
+        };
+        description = "Settings per zone that workers fetch at startup, overriding their local config";
+      };
+
+      approvalRequiredZones = mkOption {
+        type = types.listOf types.str;
+        default = [ ];
+        example = [ "production" ];
+        description = "Zones whose main branch deployments wait for approval via the admin API";
+      };
+
+      approvalTimeoutSecs = mkOption {
+        type = types.int;
+        default = 86400;
+        description = "Seconds a deployment waits for approval before it expires";
+      };
+
+      maxPreviewAgeSecs = mkOption {
+        type = types.nullOr types.int;
+        default = null;
+        description = "Seconds after which a PR preview is cleaned up even if the PR is still open (null keeps previews until the PR closes)";
+      };
+
+      prCleanupGraceSecs = mkOption {
+        type = types.nullOr types.int;
+        default = null;
+        example = 300;
+        description = "Seconds to wait before cleaning up a closed PR's preview, cancelled if the PR is reopened first (null cleans up right away); requires maxPreviewAgeSecs";
+      };
+
+      stuckDeploymentTimeoutSecs = mkOption {
+        type = types.int;
+        default = 3600;
+        description = "Seconds a deployment may stay pending or building before its worker is asked for the outcome";
+      };
+
+      commentFooter = mkOption {
+        type = types.bool;
+        default = true;
+        description = "Append build duration and a commit link to PR deployment comments";
+      };
+
+      commitStatuses = mkOption {
+        type = types.bool;
+        default = false;
+        description = "Report deployments as GitHub commit statuses (needs the app's Commit statuses permission)";
+      };
+
+      commitStatusContext = mkOption {
+        type = types.str;
+        default = "catapult";
+        example = "catapult/{environment}";
+        description = "Commit status context; {environment} becomes preview or production";
+      };
+
+      httpConnectTimeoutSecs = mkOption {
+        type = types.int;
+        default = 10;
+        description = "Timeout in seconds for establishing outbound HTTP connections";
+      };
+
+      httpRequestTimeoutSecs = mkOption {
+        type = types.int;
+        default = 30;
+        description = "Timeout in seconds for each outbound HTTP request, including the response";
       };
 
       logLevel = mkOption {
@@ -134,6 +318,63 @@ in
           nullispl = "https://deployer.nullis.pl";
         };
       };
+
+      mirrors = mkOption {
+        type = types.attrsOf (types.listOf types.str);
+        default = { };
+        description = ''
+          Mirror worker endpoints by zone name.
+          Main branch deployments go to the zone's worker and all its mirrors.
+        '';
+        example = {
+          nullislabs = [ "https://deployer2.nullislabs.io" ];
+        };
+      };
+
+      mirrorQuorum = mkOption {
+        type = types.nullOr types.ints.positive;
+        default = null;
+        description = "Workers that must succeed for a mirrored deployment to succeed (null is a majority)";
+      };
+
+      workerHealthCheck = {
+        path = mkOption {
+          type = types.str;
+          default = "/health";
+          description = "Path Central probes on each worker";
+        };
+
+        method = mkOption {
+          type = types.str;
+          default = "GET";
+          description = "HTTP method of the worker health probe";
+        };
+
+        headerFile = mkOption {
+          type = types.nullOr types.path;
+          default = null;
+          description = "File containing a 'Name: value' header sent with the probe (e.g. a proxy token)";
+        };
+
+        healthyStatuses = mkOption {
+          type = types.listOf types.ints.positive;
+          default = [ ];
+          example = [ 200 204 ];
+          description = "Status codes counted as healthy (empty accepts any 2xx)";
+        };
+      };
+
+      eventWebhookUrlFile = mkOption {
+        type = types.nullOr types.path;
+        default = null;
+        description = "File containing a URL that receives every deployment status change as JSON";
+      };
+
+      slackWebhookUrlFile = mkOption {
+        type = types.nullOr types.path;
+        default = null;
+        description = "File containing a Slack incoming webhook URL for deployment status changes";
+      };
     };
 
     # ==================== WORKER OPTIONS ====================
@@ -170,9 +411,47 @@ in
         example = "https://catapult.example.com";
       };
 
+      zone = mkOption {
+        type = types.nullOr types.str;
+        default = null;
+        example = "nxm";
+        description = "Zone this worker serves; when set, the worker reports its deployed sites to Central";
+      };
+
+      inventoryIntervalSecs = mkOption {
+        type = types.int;
+        default = 300;
+        description = "Seconds between site inventory reports to Central";
+      };
+
       workerSharedSecretFile = mkOption {
-        type = types.path;
-        description = "Path to file containing worker shared secret";
+        type = types.nullOr types.path;
+        default = null;
+        description = "Path to file containing worker shared secret (required without signingKeyFile)";
+      };
+
+      signingKeyFile = mkOption {
+        type = types.nullOr types.path;
+        default = null;
+        description = "Ed25519 private key (PKCS#8 PEM) to sign requests to Central with, instead of the shared secret";
+      };
+
+      centralPublicKeyFile = mkOption {
+        type = types.nullOr types.path;
+        default = null;
+        description = "Central's Ed25519 public key, required with signingKeyFile";
+      };
+
+      signatureMaxAgeSecs = mkOption {
+        type = types.ints.between 1 3600;
+        default = 300;
+        description = "Seconds a signed request from Central stays valid, for slow networks";
+      };
+
+      signatureMaxSkewSecs = mkOption {
+        type = types.ints.between 0 600;
+        default = 60;
+        description = "Seconds a Central request timestamp may be ahead of the local clock";
       };
 
       podmanSocket = mkOption {
@@ -187,24 +466,90 @@ in
         description = "URL of Caddy admin API";
       };
 
+      caddyAdminApis = mkOption {
+        type = types.attrsOf types.str;
+        default = { };
+        example = { staging = "http://localhost:2020"; };
+        description = "Per-zone Caddy admin APIs, overriding caddyAdminApi for that zone's sites";
+      };
+
       sitesDir = mkOption {
         type = types.path;
         default = "/var/www/sites";
         description = "Directory where sites are deployed";
       };
 
+      sitesDirPerZone = mkOption {
+        type = types.bool;
+        default = false;
+        description = "Deploy sites into sitesDir/<zone>/<site_id> for per-tenant disk accounting";
+      };
+
+      keepReleases = mkOption {
+        type = types.ints.unsigned;
+        default = 3;
+        description = "Previous releases kept per site after a deploy (0 keeps none)";
+      };
+
+      routeRestoreConcurrency = mkOption {
+        type = types.ints.positive;
+        default = 8;
+        description = "Sites whose Caddy routes are restored at once when the worker starts";
+      };
+
+      deployHealthCheckSecs = mkOption {
+        type = types.ints.unsigned;
+        default = 0;
+        description = "Wait up to this long for a deployed URL to answer 2xx before reporting success (0 disables the check)";
+      };
+
       useContainers = mkOption {
         type = types.bool;
         default = true;
         description = "Use container isolation for builds";
       };
 
+      buildNetworkDryRun = mkOption {
+        type = types.bool;
+        default = false;
+        description = "Only log the build network's iptables commands, for the system config to apply; the rules are still verified";
+      };
+
       buildImage = mkOption {
         type = types.str;
         default = "nixos/nix:latest";
         description = "Container image for builds";
       };
 
+      nix = {
+        extraSubstituters = mkOption {
+          type = types.listOf types.str;
+          default = [ ];
+          example = [ "https://cache.example.com" ];
+          description = "Binary caches passed to nix develop as extra-substituters";
+        };
+
+        extraTrustedPublicKeys = mkOption {
+          type = types.listOf types.str;
+          default = [ ];
+          example = [ "cache.example.com-1:AAAA..." ];
+          description = "Signing keys passed to nix develop as extra-trusted-public-keys";
+        };
+
+        flakeRegistry = mkOption {
+          type = types.nullOr types.str;
+          default = null;
+          description = "Flake registry path or URL used instead of the global registry";
+        };
+
+        catapultFlakeMirror = mkOption {
+          type = types.nullOr types.str;
+          default = null;
+          example = "git+https://git.example.com/mirrors/catapult";
+          description = "Flake reference replacing github:nullisLabs/catapult for build environments";
+        };
+      };
+
       containerMemoryLimit = mkOption {
         type = types.int;
         default = 4294967296; # 4GB
@@ -223,6 +568,94 @@ in
         description = "PID limit for build containers";
       };
 
+      containerMaxMemoryLimit = mkOption {
+        type = types.int;
+        default = 8589934592; # 8GB
+        description = "Upper bound for per-repo memory overrides (memory_mb in .deploy.json) in bytes";
+      };
+
+      containerMaxCpuQuota = mkOption {
+        type = types.int;
+        default = 400000; # 4 CPUs
+        description = "Upper bound for per-repo CPU overrides (cpu_cores in .deploy.json)";
+      };
+
+      containerMaxPidsLimit = mkOption {
+        type = types.int;
+        default = 4096;
+        description = "Upper bound for per-repo PID limit overrides (pids_limit in .deploy.json)";
+      };
+
+      httpConnectTimeoutSecs = mkOption {
+        type = types.int;
+        default = 10;
+        description = "Timeout in seconds for establishing outbound HTTP connections";
+      };
+
+      httpRequestTimeoutSecs = mkOption {
+        type = types.int;
+        default = 30;
+        description = "Timeout in seconds for each outbound HTTP request, including the response";
+      };
+
+      cloneTimeoutSecs = mkOption {
+        type = types.int;
+        default = 300;
+        description = "Timeout in seconds for each git clone/fetch/checkout and artifact download; hung git processes are killed";
+      };
+
+      maxArtifactBytes = mkOption {
+        type = types.int;
+        default = 1024 * 1024 * 1024;
+        description = "Maximum size in bytes of a prebuilt artifact archive and of its unpacked contents";
+      };
+
+      maxRepoSizeMb = mkOption {
+        type = types.int;
+        default = 2048;
+        description = "Maximum size in MB of a cloned repository checkout; larger clones fail the build and are removed (0 disables the check)";
+      };
+
+      buildWaitTimeoutSecs = mkOption {
+        type = types.int;
+        default = 900;
+        description = "Longest a synchronous /build?wait=true request waits before returning 202 with the build still running";
+      };
+
+      allowedSources = mkOption {
+        type = types.listOf types.str;
+        default = [ ];
+        example = [ "203.0.113.10" "2001:db8::/32" ];
+        description = "IPs/CIDRs allowed to call /build and /cleanup (empty allows all); /health stays open";
+      };
+
+      buildCommandAllowlist = mkOption {
+        type = types.listOf types.str;
+        default = [ ];
+        example = [ "npm run build(:[a-z]+)?" "zola build" ];
+        description = "Regexes a repository's build_command must fully match (empty allows any command)";
+      };
+
+      buildCommandRestrictedOrgs = mkOption {
+        type = types.listOf types.str;
+        default = [ ];
+        example = [ "contractor-org" ];
+        description = "Orgs buildCommandAllowlist applies to (empty applies it to every org)";
+      };
+
+      hostBuildOrgs = mkOption {
+        type = types.listOf types.str;
+        default = [ ];
+        example = [ "nullisLabs" ];
+        description = "Orgs whose configs may set isolation = \"host\" to build without a container";
+      };
+
+      trustForwardedFor = mkOption {
+        type = types.bool;
+        default = false;
+        description = "Match allowedSources against X-Forwarded-For (only behind a proxy that sets it)";
+      };
+
       logLevel = mkOption {
         type = types.str;
         default = "catapult=info,tower_http=info";
@@ -262,6 +695,26 @@ in
           default = "http://localhost:8080";
           description = "Local service URL for tunnel routing (where Caddy listens)";
         };
+
+        tunnels = mkOption {
+          type = types.attrsOf types.str;
+          default = { };
+          example = { staging = "staging-tunnel-id"; production = "prod-tunnel-id|http://localhost:8081"; };
+          description = "Per-environment tunnels (tunnel ID, optionally followed by |service_url), overriding tunnelId for that zone";
+        };
+
+        dnsMode = mkOption {
+          type = types.enum [ "tunnel" "direct" ];
+          default = "tunnel";
+          description = "tunnel: CNAME to tunnelId plus ingress rules; direct: A/AAAA records to originIps, no tunnel";
+        };
+
+        originIps = mkOption {
+          type = types.listOf types.str;
+          default = [ ];
+          example = [ "203.0.113.10" "2001:db8::10" ];
+          description = "Public worker addresses for A/AAAA records in direct mode";
+        };
       };
     };
   };
@@ -287,9 +740,46 @@ in
         environment = {
           RUST_LOG = cfg.central.logLevel;
           DATABASE_URL = cfg.central.databaseUrl;
+          SIGNATURE_MAX_AGE_SECS = toString cfg.central.signatureMaxAgeSecs;
+          SIGNATURE_MAX_SKEW_SECS = toString cfg.central.signatureMaxSkewSecs;
           GITHUB_APP_ID = toString cfg.central.githubAppId;
           GITHUB_PRIVATE_KEY_PATH = cfg.central.githubPrivateKeyFile;
+          GITHUB_HOST = cfg.central.githubHost;
+          GITHUB_API_BASE = cfg.central.githubApiBase;
           LISTEN_ADDR = cfg.central.listenAddress;
+          APPROVAL_TIMEOUT_SECS = toString cfg.central.approvalTimeoutSecs;
+          STUCK_DEPLOYMENT_TIMEOUT_SECS = toString cfg.central.stuckDeploymentTimeoutSecs;
+          COMMENT_FOOTER = if cfg.central.commentFooter then "true" else "false";
+          COMMIT_STATUSES = if cfg.central.commitStatuses then "true" else "false";
+          COMMIT_STATUS_CONTEXT = cfg.central.commitStatusContext;
+          HTTP_CONNECT_TIMEOUT_SECS = toString cfg.central.httpConnectTimeoutSecs;
+          HTTP_REQUEST_TIMEOUT_SECS = toString cfg.central.httpRequestTimeoutSecs;
+          WORKER_HEALTH_PATH = cfg.central.workerHealthCheck.path;
+          WORKER_HEALTH_METHOD = cfg.central.workerHealthCheck.method;
+        } // lib.optionalAttrs (cfg.central.workerHealthCheck.healthyStatuses != [ ]) {
+          WORKER_HEALTH_STATUSES = lib.concatMapStringsSep "," toString cfg.central.workerHealthCheck.healthyStatuses;
+        } // lib.optionalAttrs (cfg.central.adminListenAddress != null) {
+          ADMIN_LISTEN_ADDR = cfg.central.adminListenAddress;
+        } // lib.optionalAttrs (cfg.central.adminSocketPath != null) {
+          ADMIN_SOCKET_PATH = cfg.central.adminSocketPath;
+        } // lib.optionalAttrs (cfg.central.adminApiKeys != [ ]) {
+          ADMIN_API_KEYS = lib.concatStringsSep "," cfg.central.adminApiKeys;
+        } // lib.optionalAttrs (cfg.central.githubUserAgent != null) {
+          GITHUB_USER_AGENT = cfg.central.githubUserAgent;
+        } // lib.optionalAttrs (cfg.central.approvalRequiredZones != [ ]) {
+          APPROVAL_REQUIRED_ZONES = lib.concatStringsSep "," cfg.central.approvalRequiredZones;
+        } // lib.optionalAttrs (cfg.central.maxPreviewAgeSecs != null) {
+          MAX_PREVIEW_AGE_SECS = toString cfg.central.maxPreviewAgeSecs;
+        } // lib.optionalAttrs (cfg.central.prCleanupGraceSecs != null) {
+          PR_CLEANUP_GRACE_SECS = toString cfg.central.prCleanupGraceSecs;
+        } // lib.optionalAttrs (cfg.central.defaultDeployConfig != null) {
+          DEFAULT_DEPLOY_CONFIG_PATH = toString (pkgs.writeText "catapult-default-deploy.json"
+            (builtins.toJSON cfg.central.defaultDeployConfig));
+        } // lib.optionalAttrs (cfg.central.workerSettings != null) {
+          WORKER_SETTINGS_PATH = toString (pkgs.writeText "catapult-worker-settings.json"
+            (builtins.toJSON cfg.central.workerSettings));
+        } // lib.optionalAttrs (cfg.central.mirrorQuorum != null) {
+          MIRROR_QUORUM = toString cfg.central.mirrorQuorum;
         };
 
         serviceConfig = {
@@ -299,12 +789,24 @@ in
           ExecStart = "${cfg.central.package}/bin/catapult central";
           Restart = "always";
           RestartSec = 5;
+          # Writable home for the admin socket under ProtectSystem
+          RuntimeDirectory = "catapult";
 
           # Load secrets from files
           LoadCredential = [
             "webhook-secret:${cfg.central.githubWebhookSecretFile}"
+          ] ++ lib.optionals (cfg.central.workerSharedSecretFile != null) [
             "worker-secret:${cfg.central.workerSharedSecretFile}"
-          ];
+          ] ++ lib.optionals (cfg.central.signingKeyFile != null) [
+            "signing-key:${cfg.central.signingKeyFile}"
+          ] ++ lib.optionals (cfg.central.workerHealthCheck.headerFile != null) [
+            "worker-health-header:${cfg.central.workerHealthCheck.headerFile}"
+          ] ++ lib.optionals (cfg.central.eventWebhookUrlFile != null) [
+            "event-webhook-url:${cfg.central.eventWebhookUrlFile}"
+          ] ++ lib.optionals (cfg.central.slackWebhookUrlFile != null) [
+            "slack-webhook-url:${cfg.central.slackWebhookUrlFile}"
+          ] ++ lib.mapAttrsToList (zone: file: "worker-secret-${zone}:${file}") cfg.central.workerSecretFiles
+            ++ lib.mapAttrsToList (appId: app: "webhook-secret-${appId}:${app.webhookSecretFile}") cfg.central.extraGithubApps;
 
           # Security hardening
           NoNewPrivileges = true;
@@ -326,13 +828,46 @@ in
 
         # Read secrets and set environment variables
         script = let
-          # Build --worker arguments from config
+          # Build --worker and --mirror arguments from config
           workerArgs = lib.concatStringsSep " " (
             lib.mapAttrsToList (zone: endpoint: "--worker ${zone}=${endpoint}") cfg.central.workers
+            ++ lib.concatLists (lib.mapAttrsToList
+              (zone: endpoints: map (endpoint: "--mirror ${zone}=${endpoint}") endpoints)
+              cfg.central.mirrors)
+          );
+          extraApps = lib.concatStringsSep "," (
+            lib.mapAttrsToList (appId: app: "${appId}:${toString app.privateKeyFile}:$(cat $CREDENTIALS_DIRECTORY/webhook-secret-${appId})") cfg.central.extraGithubApps
+          );
+          workerSecrets = lib.concatStringsSep "," (
+            lib.mapAttrsToList (zone: _: "${zone}=$(cat $CREDENTIALS_DIRECTORY/worker-secret-${zone})") cfg.central.workerSecretFiles
+          );
+          workerPublicKeys = lib.concatStringsSep "," (
+            lib.mapAttrsToList (zone: file: "${zone}=${toString file}") cfg.central.workerPublicKeyFiles
           );
         in ''
           export GITHUB_WEBHOOK_SECRET="$(cat $CREDENTIALS_DIRECTORY/webhook-secret)"
-          export WORKER_SHARED_SECRET="$(cat $CREDENTIALS_DIRECTORY/worker-secret)"
+          ${lib.optionalString (cfg.central.extraGithubApps != { }) ''
+            export GITHUB_EXTRA_APPS="${extraApps}"
+          ''}
+          ${lib.optionalString (cfg.central.workerSharedSecretFile != null) ''
+            export WORKER_SHARED_SECRET="$(cat $CREDENTIALS_DIRECTORY/worker-secret)"
+          ''}
+          ${lib.optionalString (cfg.central.workerSecretFiles != { }) ''
+            export WORKER_SECRETS="${workerSecrets}"
+          ''}
+          ${lib.optionalString (cfg.central.signingKeyFile != null) ''
+            export CENTRAL_SIGNING_KEY_FILE="$CREDENTIALS_DIRECTORY/signing-key"
+            export WORKER_PUBLIC_KEYS="${workerPublicKeys}"
+          ''}
+          ${lib.optionalString (cfg.central.workerHealthCheck.headerFile != null) ''
+            export WORKER_HEALTH_HEADER="$(cat $CREDENTIALS_DIRECTORY/worker-health-header)"
+          ''}
+          ${lib.optionalString (cfg.central.eventWebhookUrlFile != null) ''
+            export EVENT_WEBHOOK_URL="$(cat $CREDENTIALS_DIRECTORY/event-webhook-url)"
+          ''}
+          ${lib.optionalString (cfg.central.slackWebhookUrlFile != null) ''
+            export SLACK_WEBHOOK_URL="$(cat $CREDENTIALS_DIRECTORY/slack-webhook-url)"
+          ''}
           exec ${cfg.central.package}/bin/catapult central ${workerArgs}
         '';
       };
@@ -374,23 +909,69 @@ in
         wants = [ "podman.socket" ];
         wantedBy = [ "multi-user.target" ];
 
+        # Repositories are cloned on the host; git-lfs for repos setting git_lfs;
+        # iptables to set up and check the build network's isolation rules
+        path = [ pkgs.git pkgs.git-lfs pkgs.iptables ];
+
         environment = {
           RUST_LOG = cfg.worker.logLevel;
           CENTRAL_URL = cfg.worker.centralUrl;
+          SIGNATURE_MAX_AGE_SECS = toString cfg.worker.signatureMaxAgeSecs;
+          SIGNATURE_MAX_SKEW_SECS = toString cfg.worker.signatureMaxSkewSecs;
           LISTEN_ADDR = cfg.worker.listenAddress;
           PODMAN_SOCKET = cfg.worker.podmanSocket;
           CADDY_ADMIN_API = cfg.worker.caddyAdminApi;
           SITES_DIR = cfg.worker.sitesDir;
+          SITES_DIR_PER_ZONE = if cfg.worker.sitesDirPerZone then "true" else "false";
+          KEEP_RELEASES = toString cfg.worker.keepReleases;
+          ROUTE_RESTORE_CONCURRENCY = toString cfg.worker.routeRestoreConcurrency;
+          INVENTORY_INTERVAL_SECS = toString cfg.worker.inventoryIntervalSecs;
+          DEPLOY_HEALTH_CHECK_SECS = toString cfg.worker.deployHealthCheckSecs;
           USE_CONTAINERS = if cfg.worker.useContainers then "true" else "false";
+          BUILD_NETWORK_DRY_RUN = if cfg.worker.buildNetworkDryRun then "true" else "false";
           BUILD_IMAGE = cfg.worker.buildImage;
           CONTAINER_MEMORY_LIMIT = toString cfg.worker.containerMemoryLimit;
           CONTAINER_CPU_QUOTA = toString cfg.worker.containerCpuQuota;
           CONTAINER_PIDS_LIMIT = toString cfg.worker.containerPidsLimit;
+          CONTAINER_MAX_MEMORY_LIMIT = toString cfg.worker.containerMaxMemoryLimit;
+          CONTAINER_MAX_CPU_QUOTA = toString cfg.worker.containerMaxCpuQuota;
+          CONTAINER_MAX_PIDS_LIMIT = toString cfg.worker.containerMaxPidsLimit;
+          CLONE_TIMEOUT_SECS = toString cfg.worker.cloneTimeoutSecs;
+          MAX_ARTIFACT_BYTES = toString cfg.worker.maxArtifactBytes;
+          MAX_REPO_SIZE_MB = toString cfg.worker.maxRepoSizeMb;
+          BUILD_WAIT_TIMEOUT_SECS = toString cfg.worker.buildWaitTimeoutSecs;
+          HTTP_CONNECT_TIMEOUT_SECS = toString cfg.worker.httpConnectTimeoutSecs;
+          HTTP_REQUEST_TIMEOUT_SECS = toString cfg.worker.httpRequestTimeoutSecs;
+          TRUST_FORWARDED_FOR = if cfg.worker.trustForwardedFor then "true" else "false";
+        } // lib.optionalAttrs (cfg.worker.nix.extraSubstituters != [ ]) {
+          NIX_EXTRA_SUBSTITUTERS = concatStringsSep " " cfg.worker.nix.extraSubstituters;
+        } // lib.optionalAttrs (cfg.worker.nix.extraTrustedPublicKeys != [ ]) {
+          NIX_EXTRA_TRUSTED_PUBLIC_KEYS = concatStringsSep " " cfg.worker.nix.extraTrustedPublicKeys;
+        } // lib.optionalAttrs (cfg.worker.nix.flakeRegistry != null) {
+          NIX_FLAKE_REGISTRY = cfg.worker.nix.flakeRegistry;
+        } // lib.optionalAttrs (cfg.worker.nix.catapultFlakeMirror != null) {
+          CATAPULT_FLAKE_MIRROR = cfg.worker.nix.catapultFlakeMirror;
+        } // lib.optionalAttrs (cfg.worker.caddyAdminApis != { }) {
+          CADDY_ADMIN_APIS = concatStringsSep "," (mapAttrsToList (zone: url: "${zone}=${url}") cfg.worker.caddyAdminApis);
+        } // lib.optionalAttrs (cfg.worker.zone != null) {
+          WORKER_ZONE = cfg.worker.zone;
+        } // lib.optionalAttrs (cfg.worker.allowedSources != [ ]) {
+          ALLOWED_SOURCES = concatStringsSep "," cfg.worker.allowedSources;
+        } // lib.optionalAttrs (cfg.worker.buildCommandAllowlist != [ ]) {
+          BUILD_COMMAND_ALLOWLIST_PATH = toString (pkgs.writeText "catapult-build-command-allowlist"
+            (concatStringsSep "\n" cfg.worker.buildCommandAllowlist));
+          BUILD_COMMAND_RESTRICTED_ORGS = concatStringsSep "," cfg.worker.buildCommandRestrictedOrgs;
+          HOST_BUILD_ORGS = concatStringsSep "," cfg.worker.hostBuildOrgs;
         } // lib.optionalAttrs cfg.worker.cloudflare.enable {
           CLOUDFLARE_ACCOUNT_ID = cfg.worker.cloudflare.accountId;
           CLOUDFLARE_ZONE_ID = cfg.worker.cloudflare.zoneId;
           CLOUDFLARE_TUNNEL_ID = cfg.worker.cloudflare.tunnelId;
           CLOUDFLARE_SERVICE_URL = cfg.worker.cloudflare.serviceUrl;
+          CLOUDFLARE_DNS_MODE = cfg.worker.cloudflare.dnsMode;
+        } // lib.optionalAttrs (cfg.worker.cloudflare.enable && cfg.worker.cloudflare.originIps != [ ]) {
+          CLOUDFLARE_ORIGIN_IPS = concatStringsSep "," cfg.worker.cloudflare.originIps;
+        } // lib.optionalAttrs (cfg.worker.cloudflare.enable && cfg.worker.cloudflare.tunnels != { }) {
+          CLOUDFLARE_TUNNELS = concatStringsSep "," (mapAttrsToList (env: tunnel: "${env}=${tunnel}") cfg.worker.cloudflare.tunnels);
         };
 
         serviceConfig = {
@@ -402,12 +983,17 @@ in
           RestartSec = 5;
 
           # Load secrets from files
-          LoadCredential = [
+          LoadCredential = lib.optionals (cfg.worker.workerSharedSecretFile != null) [
             "worker-secret:${cfg.worker.workerSharedSecretFile}"
+          ] ++ lib.optionals (cfg.worker.signingKeyFile != null) [
+            "signing-key:${cfg.worker.signingKeyFile}"
           ] ++ lib.optionals (cfg.worker.cloudflare.enable && cfg.worker.cloudflare.apiTokenFile != null) [
             "cloudflare-token:${cfg.worker.cloudflare.apiTokenFile}"
           ];
 
+          # iptables needs CAP_NET_ADMIN, or /ready can't verify build isolation
+          AmbientCapabilities = [ "CAP_NET_ADMIN" ];
+
           # Worker needs more permissions for Podman
           NoNewPrivileges = true;
           ProtectHome = true;
@@ -424,7 +1010,13 @@ in
 
         # Read secrets and set environment variables
         script = ''
-          export WORKER_SHARED_SECRET="$(cat $CREDENTIALS_DIRECTORY/worker-secret)"
+          ${lib.optionalString (cfg.worker.workerSharedSecretFile != null) ''
+            export WORKER_SHARED_SECRET="$(cat $CREDENTIALS_DIRECTORY/worker-secret)"
+          ''}
+          ${lib.optionalString (cfg.worker.signingKeyFile != null) ''
+            export WORKER_SIGNING_KEY_FILE="$CREDENTIALS_DIRECTORY/signing-key"
+            export CENTRAL_PUBLIC_KEY_FILE="${toString cfg.worker.centralPublicKeyFile}"
+          ''}
           ${lib.optionalString (cfg.worker.cloudflare.enable && cfg.worker.cloudflare.apiTokenFile != null) ''
             export CLOUDFLARE_API_TOKEN="$(cat $CREDENTIALS_DIRECTORY/cloudflare-token)"
           ''}
@@ -0,0 +1,206 @@
+//! Admin API keys
+//!
+//! Besides the primary `ADMIN_API_KEY`, operators can configure named keys
+//! (stored as SHA-256 hashes) so each teammate or tool has its own key that
+//! can be revoked on its own. Read-only keys may only call the listing
+//! endpoints. The matching key's name is recorded as the actor in the audit log.
+
+use anyhow::{Context, Result};
+use derive_more::Display;
+use sha2::{Digest, Sha256};
+
+use crate::shared::auth::constant_time_eq;
+
+/// Name the primary `ADMIN_API_KEY` is known by
+pub const PRIMARY_KEY_NAME: &str = "admin";
+
+/// What an admin key may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum AdminScope {
+    /// Listing endpoints only
+    #[display("read")]
+    Read,
+    /// Every admin endpoint
+    #[display("write")]
+    Write,
+}
+
+impl AdminScope {
+    /// Whether a key with this scope may call an endpoint requiring `required`
+    pub fn allows(self, required: AdminScope) -> bool {
+        self == AdminScope::Write || required == AdminScope::Read
+    }
+}
+
+impl std::str::FromStr for AdminScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            other => anyhow::bail!(
+                "Unknown admin key scope '{}', expected read or write",
+                other
+            ),
+        }
+    }
+}
+
+/// The key an admin request was made with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminIdentity {
+    pub name: String,
+    pub scope: AdminScope,
+}
+
+#[derive(Debug, Clone)]
+struct AdminKey {
+    name: String,
+    scope: AdminScope,
+    sha256: Vec<u8>,
+}
+
+/// Every key accepted by the admin API
+#[derive(Debug, Clone)]
+pub struct AdminKeys {
+    keys: Vec<AdminKey>,
+}
+
+impl AdminKeys {
+    /// Combine the primary key (full access) with named `name:scope:sha256` entries
+    ///
+    /// Entries are separated by commas or newlines. At least one key is required.
+    pub fn new(primary: Option<&str>, named: &str) -> Result<Self> {
+        let mut keys: Vec<AdminKey> = primary
+            .filter(|key| !key.is_empty())
+            .map(|key| AdminKey {
+                name: PRIMARY_KEY_NAME.to_string(),
+                scope: AdminScope::Write,
+                sha256: Sha256::digest(key.as_bytes()).to_vec(),
+            })
+            .into_iter()
+            .collect();
+
+        for entry in named.split([',', '\n']).map(str::trim) {
+            if entry.is_empty() {
+                continue;
+            }
+
+            let mut parts = entry.splitn(3, ':').map(str::trim);
+            let (Some(name), Some(scope), Some(hash)) = (parts.next(), parts.next(), parts.next())
+            else {
+                anyhow::bail!(
+                    "Invalid admin key entry for '{}', expected 'name:scope:sha256'",
+                    entry.split(':').next().unwrap_or_default()
+                );
+            };
+
+            if name.is_empty() {
+                anyhow::bail!("Empty admin key name");
+            }
+            let sha256 = hex::decode(hash)
+                .ok()
+                .filter(|hash| hash.len() == 32)
+                .with_context(|| format!("Admin key '{}' must be a hex SHA-256 hash", name))?;
+            if keys.iter().any(|key| key.name == name) {
+                anyhow::bail!("Duplicate admin key name '{}'", name);
+            }
+
+            keys.push(AdminKey {
+                name: name.to_string(),
+                scope: scope.parse()?,
+                sha256,
+            });
+        }
+
+        if keys.is_empty() {
+            anyhow::bail!("ADMIN_API_KEY or ADMIN_API_KEYS environment variable required");
+        }
+
+        Ok(Self { keys })
+    }
+
+    /// Identify the key a request presented, if it's one of ours
+    pub fn identify(&self, presented: &str) -> Option<AdminIdentity> {
+        let sha256 = Sha256::digest(presented.as_bytes());
+        self.keys
+            .iter()
+            .find(|key| constant_time_eq(&key.sha256, &sha256))
+            .map(|key| AdminIdentity {
+                name: key.name.clone(),
+                scope: key.scope,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(key: &str) -> String {
+        hex::encode(Sha256::digest(key.as_bytes()))
+    }
+
+    #[test]
+    fn test_identify_multiple_keys() {
+        let keys = AdminKeys::new(
+            Some("primary-key"),
+            &format!(
+                "alice:write:{},\ndashboard:read:{}",
+                hash("alice-key"),
+                hash("dashboard-key")
+            ),
+        )
+        .unwrap();
+
+        let identify = |key| keys.identify(key).map(|id| (id.name, id.scope));
+        assert_eq!(
+            identify("primary-key"),
+            Some(("admin".to_string(), AdminScope::Write))
+        );
+        assert_eq!(
+            identify("alice-key"),
+            Some(("alice".to_string(), AdminScope::Write))
+        );
+        assert_eq!(
+            identify("dashboard-key"),
+            Some(("dashboard".to_string(), AdminScope::Read))
+        );
+        // Hashes themselves aren't keys
+        let alice_hash = hash("alice-key");
+        assert_eq!(identify(&alice_hash), None);
+        assert_eq!(identify("revoked-key"), None);
+    }
+
+    #[test]
+    fn test_named_keys_without_primary() {
+        let keys = AdminKeys::new(None, &format!("ci:read:{}", hash("ci-key"))).unwrap();
+        assert!(keys.identify("ci-key").is_some());
+        assert!(keys.identify("").is_none());
+
+        assert!(AdminKeys::new(None, "").is_err());
+        assert!(AdminKeys::new(Some(""), "").is_err());
+    }
+
+    #[test]
+    fn test_invalid_named_keys() {
+        let h = hash("key");
+        assert!(AdminKeys::new(None, "alice:write").is_err());
+        assert!(AdminKeys::new(None, &format!(":write:{}", h)).is_err());
+        assert!(AdminKeys::new(None, &format!("alice:admin:{}", h)).is_err());
+        assert!(AdminKeys::new(None, "alice:write:not-hex").is_err());
+        assert!(AdminKeys::new(None, &format!("alice:write:{}", &h[..32])).is_err());
+        assert!(AdminKeys::new(None, &format!("a:read:{},a:write:{}", h, h)).is_err());
+        // The primary key's name is taken
+        assert!(AdminKeys::new(Some("primary"), &format!("admin:read:{}", h)).is_err());
+    }
+
+    #[test]
+    fn test_scope_allows() {
+        assert!(AdminScope::Write.allows(AdminScope::Write));
+        assert!(AdminScope::Write.allows(AdminScope::Read));
+        assert!(AdminScope::Read.allows(AdminScope::Read));
+        assert!(!AdminScope::Read.allows(AdminScope::Write));
+    }
+}
@@ -1,5 +1,8 @@
 pub mod models;
 pub mod queries;
 
-pub use models::AuthorizedOrg;
+pub use models::{
+    AdminAction, AuthorizedOrg, DeploymentEvent, DeploymentHistory, DeploymentType, OutboxEvent,
+    QuotaExceeded, Worker, WorkerHealth,
+};
 pub use queries::*;
@@ -1,17 +1,24 @@
 use chrono::{DateTime, Utc};
+use derive_more::Display;
+use serde::Serialize;
 use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A worker is considered unhealthy if it hasn't been seen for this long
+///
+/// The worker monitor checks every 30 seconds, so this allows a few missed checks.
+pub const WORKER_STALE_AFTER_SECS: i64 = 90;
 
 /// Worker registration record
 #[derive(Debug, Clone, FromRow)]
 pub struct Worker {
     #[allow(dead_code)]
     pub id: i32,
-    #[allow(dead_code)]
     pub environment: String,
     pub endpoint: String,
-    #[allow(dead_code)]
     pub enabled: bool,
-    #[allow(dead_code)]
+    /// Mirror of the zone's primary worker, sent main branch deployments only
+    pub mirror: bool,
     pub last_seen: Option<DateTime<Utc>>,
     #[allow(dead_code)]
     pub created_at: DateTime<Utc>,
@@ -19,6 +26,181 @@ pub struct Worker {
     pub updated_at: DateTime<Utc>,
 }
 
+impl Worker {
+    /// Compute the worker's health from its enabled flag and last_seen timestamp
+    pub fn health(&self, now: DateTime<Utc>) -> WorkerHealth {
+        if !self.enabled {
+            return WorkerHealth::Disabled;
+        }
+
+        match self.last_seen {
+            Some(last_seen) if (now - last_seen).num_seconds() <= WORKER_STALE_AFTER_SECS => {
+                WorkerHealth::Healthy
+            }
+            Some(_) => WorkerHealth::Unhealthy,
+            None => WorkerHealth::Unknown,
+        }
+    }
+}
+
+/// One worker's final result for a mirrored deployment (None until reported)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRow)]
+pub struct MirrorResult {
+    pub worker_id: i32,
+    pub succeeded: Option<bool>,
+}
+
+/// Computed worker health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerHealth {
+    /// Seen recently
+    Healthy,
+    /// Not seen within the staleness window
+    Unhealthy,
+    /// Never seen
+    Unknown,
+    /// Disabled by an operator or removed from config
+    Disabled,
+}
+
+/// A site a worker reported serving in its latest inventory
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct WorkerSite {
+    pub zone: String,
+    pub site_id: String,
+    pub domain: String,
+    pub size_bytes: i64,
+    pub deployed_at: Option<DateTime<Utc>>,
+    /// When the worker last reported the site
+    pub reported_at: DateTime<Utc>,
+}
+
+/// Deployment history record (one per dispatched build job)
+#[derive(Debug, Clone, FromRow)]
+pub struct DeploymentHistory {
+    #[allow(dead_code)]
+    pub id: i32,
+    pub job_id: Uuid,
+    pub github_org: String,
+    pub github_repo: String,
+    pub pr_number: Option<i32>,
+    pub site_id: String,
+    pub zone: String,
+    pub domain: String,
+    pub commit_sha: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// When the worker reported the build starting
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the worker reported the final status
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Size of the deployed site files, as reported by the worker
+    pub size_bytes: Option<i64>,
+    /// How the deployment was triggered (see [`DeploymentType`])
+    pub deployment_type: String,
+    /// Built without being published
+    pub dry_run: bool,
+}
+
+/// How a deployment was triggered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum DeploymentType {
+    /// Push to the main branch or PR event
+    #[display("webhook")]
+    Webhook,
+    /// Prebuilt archive deployed via the admin API
+    #[display("artifact")]
+    Artifact,
+    /// Commit preview requested via the admin API
+    #[display("manual")]
+    Manual,
+}
+
+/// State-changing admin API action, as recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum AdminAction {
+    #[display("auth.upsert")]
+    AuthUpsert,
+    #[display("auth.delete")]
+    AuthDelete,
+    #[display("worker.enable")]
+    WorkerEnable,
+    #[display("worker.disable")]
+    WorkerDisable,
+    #[display("repo.pause")]
+    RepoPause,
+    #[display("repo.resume")]
+    RepoResume,
+    #[display("repo.rebuild")]
+    RepoRebuild,
+    #[display("deployment.approve")]
+    DeploymentApprove,
+    #[display("deployment.promote")]
+    DeploymentPromote,
+    #[display("deployment.cancel")]
+    DeploymentCancel,
+    #[display("deployment.artifact")]
+    DeploymentArtifact,
+    #[display("deployment.preview")]
+    DeploymentPreview,
+    #[display("deployment.cleanup")]
+    DeploymentCleanup,
+}
+
+/// Admin audit log record
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminAuditEntry {
+    pub id: i64,
+    pub actor: String,
+    pub action: String,
+    pub target: String,
+    pub payload: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DeploymentHistory {
+    /// How long the build took, once it has finished
+    pub fn build_duration(&self) -> Option<std::time::Duration> {
+        let started = self.started_at?;
+        (self.completed_at? - started).to_std().ok()
+    }
+}
+
+/// Deployment lifecycle event record
+#[derive(Debug, Clone, FromRow)]
+pub struct DeploymentEvent {
+    #[allow(dead_code)]
+    pub id: i64,
+    #[allow(dead_code)]
+    pub job_id: Uuid,
+    pub phase: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Deployment event waiting in the outbox for delivery
+#[derive(Debug, Clone)]
+pub struct OutboxEvent {
+    pub id: i64,
+    pub job_id: Uuid,
+    /// The deployment as it stood after the status change
+    pub payload: serde_json::Value,
+    /// Deliveries that failed so far
+    pub attempts: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Operator settings for a repository
+#[derive(Debug, Clone, FromRow)]
+pub struct RepoSettings {
+    pub github_org: String,
+    pub github_repo: String,
+    /// Webhooks are acknowledged but no builds are dispatched
+    pub paused: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
 /// Authorized organization record
 #[derive(Debug, Clone, FromRow)]
 pub struct AuthorizedOrg {
@@ -27,18 +209,80 @@ pub struct AuthorizedOrg {
     pub zones: Vec<String>,
     pub domain_patterns: Vec<String>,
     pub enabled: bool,
+    /// Maximum number of active deployments (None is unlimited)
+    pub max_active_deployments: Option<i32>,
+    /// Maximum total size of active deployments in MB (None is unlimited)
+    pub max_total_mb: Option<i64>,
+    /// Repo name globs allowed to deploy (empty allows every repo)
+    pub repo_allowlist: Vec<String>,
+    /// Repo name globs never allowed to deploy, even if allowlisted
+    pub repo_denylist: Vec<String>,
     #[allow(dead_code)]
     pub created_at: DateTime<Utc>,
     #[allow(dead_code)]
     pub updated_at: DateTime<Utc>,
 }
 
+/// Resources an org's active deployments currently use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromRow)]
+pub struct OrgUsage {
+    /// Sites whose latest deployment hasn't been cleaned up
+    pub active_deployments: i64,
+    /// Reported size of those sites' files
+    pub total_bytes: i64,
+}
+
+/// Why a deployment was rejected by an org quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum QuotaExceeded {
+    #[error("the organization already has {active} of {max} active deployments")]
+    ActiveDeployments { active: i64, max: i32 },
+    #[error("the organization's deployments use {used_mb} MB of its {max_mb} MB disk quota")]
+    TotalSize { used_mb: i64, max_mb: i64 },
+}
+
 impl AuthorizedOrg {
     /// Check if this org is authorized to deploy to a zone
     pub fn can_use_zone(&self, zone: &str) -> bool {
         self.zones.iter().any(|z| z.eq_ignore_ascii_case(zone))
     }
 
+    /// Check if this org's repo filters allow a repo to deploy
+    ///
+    /// The denylist takes precedence over the allowlist.
+    pub fn can_deploy_repo(&self, repo: &str) -> bool {
+        let matches = |patterns: &[String]| patterns.iter().any(|p| glob_matches(p, repo));
+        !matches(&self.repo_denylist)
+            && (self.repo_allowlist.is_empty() || matches(&self.repo_allowlist))
+    }
+
+    /// Check whether one more deployment fits within this org's quotas
+    ///
+    /// Redeploying a site that is already active doesn't add a deployment, so
+    /// only the disk quota applies to it.
+    pub fn check_quota(&self, usage: &OrgUsage, already_active: bool) -> Result<(), QuotaExceeded> {
+        if let Some(max) = self.max_active_deployments
+            && !already_active
+            && usage.active_deployments >= i64::from(max)
+        {
+            return Err(QuotaExceeded::ActiveDeployments {
+                active: usage.active_deployments,
+                max,
+            });
+        }
+
+        // The new build's size is unknown until it's deployed, so only reject
+        // once the quota is already used up
+        if let Some(max_mb) = self.max_total_mb {
+            let used_mb = usage.total_bytes / (1024 * 1024);
+            if usage.total_bytes >= max_mb.saturating_mul(1024 * 1024) {
+                return Err(QuotaExceeded::TotalSize { used_mb, max_mb });
+            }
+        }
+
+        Ok(())
+    }
+
     /// Check if this org is authorized to use a domain
     pub fn can_use_domain(&self, domain: &str) -> bool {
         let domain_lower = domain.to_lowercase();
@@ -57,6 +301,39 @@ impl AuthorizedOrg {
     }
 }
 
+/// Case-insensitive glob match: `*` matches any run of characters, `?` one
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it was tried at
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,11 +345,80 @@ mod tests {
             zones: zones.into_iter().map(String::from).collect(),
             domain_patterns: domain_patterns.into_iter().map(String::from).collect(),
             enabled: true,
+            max_active_deployments: None,
+            max_total_mb: None,
+            repo_allowlist: Vec::new(),
+            repo_denylist: Vec::new(),
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         }
     }
 
+    fn make_worker(enabled: bool, last_seen: Option<DateTime<Utc>>) -> Worker {
+        Worker {
+            id: 1,
+            environment: "production".to_string(),
+            endpoint: "https://worker.example.com".to_string(),
+            enabled,
+            mirror: false,
+            last_seen,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_worker_health() {
+        let now = chrono::Utc::now();
+
+        assert_eq!(
+            make_worker(true, Some(now - chrono::Duration::seconds(10))).health(now),
+            WorkerHealth::Healthy
+        );
+        assert_eq!(
+            make_worker(true, Some(now - chrono::Duration::seconds(600))).health(now),
+            WorkerHealth::Unhealthy
+        );
+        assert_eq!(make_worker(true, None).health(now), WorkerHealth::Unknown);
+        assert_eq!(
+            make_worker(false, Some(now)).health(now),
+            WorkerHealth::Disabled
+        );
+    }
+
+    #[test]
+    fn test_check_quota() {
+        const MB: i64 = 1024 * 1024;
+        let usage = |active_deployments, total_mb: i64| OrgUsage {
+            active_deployments,
+            total_bytes: total_mb * MB,
+        };
+
+        let mut auth = make_auth_org(vec![], vec![]);
+        assert!(auth.check_quota(&usage(1000, 1_000_000), false).is_ok());
+
+        auth.max_active_deployments = Some(3);
+        assert!(auth.check_quota(&usage(2, 0), false).is_ok());
+        assert_eq!(
+            auth.check_quota(&usage(3, 0), false),
+            Err(QuotaExceeded::ActiveDeployments { active: 3, max: 3 })
+        );
+        // Redeploying an active site doesn't need another slot
+        assert!(auth.check_quota(&usage(3, 0), true).is_ok());
+
+        auth.max_total_mb = Some(500);
+        assert!(auth.check_quota(&usage(1, 499), false).is_ok());
+        let err = auth.check_quota(&usage(1, 500), true).unwrap_err();
+        assert_eq!(
+            err,
+            QuotaExceeded::TotalSize {
+                used_mb: 500,
+                max_mb: 500
+            }
+        );
+        assert!(err.to_string().contains("500 MB"));
+    }
+
     #[test]
     fn test_can_use_zone() {
         let auth = make_auth_org(vec!["production", "staging"], vec![]);
@@ -95,6 +441,53 @@ mod tests {
         assert!(!auth.can_use_domain("sub.example.com")); // No wildcard
     }
 
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("website", "website"));
+        assert!(glob_matches("Website", "WEBSITE"));
+        assert!(!glob_matches("website", "website-old"));
+
+        assert!(glob_matches("*", "anything"));
+        assert!(glob_matches("docs-*", "docs-api"));
+        assert!(glob_matches("docs-*", "docs-"));
+        assert!(!glob_matches("docs-*", "api-docs"));
+        assert!(glob_matches("*-site", "marketing-site"));
+        assert!(glob_matches("*-site*", "marketing-site-v2"));
+        assert!(glob_matches("a*b*c", "aXXbYYbZZc"));
+        assert!(!glob_matches("a*b*c", "aXXbYYcZZ"));
+
+        assert!(glob_matches("v?", "v2"));
+        assert!(!glob_matches("v?", "v"));
+        assert!(!glob_matches("v?", "v10"));
+        assert!(glob_matches("v*?", "v10"));
+    }
+
+    #[test]
+    fn test_can_deploy_repo() {
+        let mut auth = make_auth_org(vec!["production"], vec!["*.example.com"]);
+
+        // No filters allow every repo
+        assert!(auth.can_deploy_repo("anything"));
+
+        auth.repo_allowlist = vec!["website".to_string(), "docs-*".to_string()];
+        assert!(auth.can_deploy_repo("website"));
+        assert!(auth.can_deploy_repo("Docs-API"));
+        assert!(!auth.can_deploy_repo("internal-tools"));
+
+        // The denylist wins over a matching allowlist entry
+        auth.repo_denylist = vec!["docs-private*".to_string()];
+        assert!(auth.can_deploy_repo("docs-api"));
+        assert!(!auth.can_deploy_repo("docs-private"));
+        assert!(!auth.can_deploy_repo("docs-private-notes"));
+
+        // A denylist alone excludes repos from an otherwise open org
+        auth.repo_allowlist.clear();
+        auth.repo_denylist = vec!["*-archive".to_string(), "secrets".to_string()];
+        assert!(auth.can_deploy_repo("website"));
+        assert!(!auth.can_deploy_repo("website-archive"));
+        assert!(!auth.can_deploy_repo("Secrets"));
+    }
+
     #[test]
     fn test_can_use_domain_wildcard() {
         let auth = make_auth_org(vec![], vec!["*.example.com"]);
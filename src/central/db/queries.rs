@@ -1,21 +1,93 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use super::models::{AuthorizedOrg, Worker};
+use crate::shared::{BuildJob, DeploymentPhase, InventorySite, JobStatus};
 
-/// Get worker endpoint for an environment (zone)
+use super::models::{
+    AdminAction, AdminAuditEntry, AuthorizedOrg, DeploymentEvent, DeploymentHistory,
+    DeploymentType, MirrorResult, OrgUsage, OutboxEvent, RepoSettings, Worker, WorkerSite,
+};
+
+/// Verify the database is reachable by running `SELECT 1` within a timeout
+pub async fn ping(pool: &PgPool, timeout: Duration) -> Result<()> {
+    tokio::time::timeout(timeout, sqlx::query("SELECT 1").execute(pool))
+        .await
+        .context("Database ping timed out")??;
+
+    Ok(())
+}
+
+/// Get the primary worker endpoint for an environment (zone)
 pub async fn get_worker(pool: &PgPool, environment: &str) -> Result<Option<Worker>> {
     let worker = sqlx::query_as::<_, Worker>(
         r#"
-        SELECT id, environment, endpoint, enabled, last_seen, created_at, updated_at
+        SELECT id, environment, endpoint, enabled, mirror, last_seen, created_at, updated_at
+        FROM workers
+        WHERE environment = $1 AND enabled = true AND mirror = false
+        "#,
+    )
+    .bind(environment)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(worker)
+}
+
+/// Get every enabled worker of an environment, primary first then its mirrors
+pub async fn get_workers(pool: &PgPool, environment: &str) -> Result<Vec<Worker>> {
+    let workers = sqlx::query_as::<_, Worker>(
+        r#"
+        SELECT id, environment, endpoint, enabled, mirror, last_seen, created_at, updated_at
         FROM workers
         WHERE environment = $1 AND enabled = true
+        ORDER BY mirror, id
+        "#,
+    )
+    .bind(environment)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(workers)
+}
+
+/// List all workers, including disabled ones
+pub async fn list_workers(pool: &PgPool) -> Result<Vec<Worker>> {
+    let workers = sqlx::query_as::<_, Worker>(
+        r#"
+        SELECT id, environment, endpoint, enabled, mirror, last_seen, created_at, updated_at
+        FROM workers
+        ORDER BY environment, mirror, id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(workers)
+}
+
+/// Enable or disable a zone's workers, mirrors included
+///
+/// Returns one updated worker, or None if no worker exists for the environment.
+pub async fn set_worker_enabled(
+    pool: &PgPool,
+    environment: &str,
+    enabled: bool,
+) -> Result<Option<Worker>> {
+    let worker = sqlx::query_as::<_, Worker>(
+        r#"
+        UPDATE workers
+        SET enabled = $2, updated_at = NOW()
+        WHERE environment = $1
+        RETURNING id, environment, endpoint, enabled, mirror, last_seen, created_at, updated_at
         "#,
     )
     .bind(environment)
+    .bind(enabled)
     .fetch_optional(pool)
     .await?;
 
@@ -23,7 +95,6 @@ pub async fn get_worker(pool: &PgPool, environment: &str) -> Result<Option<Worke
 }
 
 /// Update worker last_seen timestamp (for heartbeat/health checks)
-#[allow(dead_code)]
 pub async fn update_worker_heartbeat(pool: &PgPool, environment: &str) -> Result<bool> {
     let result = sqlx::query(
         r#"
@@ -39,51 +110,252 @@ pub async fn update_worker_heartbeat(pool: &PgPool, environment: &str) -> Result
     Ok(result.rows_affected() > 0)
 }
 
+/// Update last_seen of one of a zone's workers, telling mirrors apart
+pub async fn update_endpoint_heartbeat(
+    pool: &PgPool,
+    environment: &str,
+    endpoint: &str,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE workers
+        SET last_seen = NOW()
+        WHERE environment = $1 AND endpoint = $2 AND enabled = true
+        "#,
+    )
+    .bind(environment)
+    .bind(endpoint)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
 /// Sync workers from configuration to database
 ///
 /// - Upserts workers that are in the config (creates new or updates existing)
-/// - Disables workers that are no longer in the config
-/// - Returns the number of workers synced
-pub async fn sync_workers(pool: &PgPool, workers: &HashMap<String, String>) -> Result<usize> {
+/// - Disables workers that are no longer in the config, including old
+///   endpoints of a zone
+/// - Returns the number of workers synced, mirrors included
+pub async fn sync_workers(
+    pool: &PgPool,
+    workers: &HashMap<String, String>,
+    mirrors: &HashMap<String, Vec<String>>,
+) -> Result<usize> {
+    let configured: Vec<(&str, &str, bool)> = workers
+        .iter()
+        .map(|(environment, endpoint)| (environment.as_str(), endpoint.as_str(), false))
+        .chain(mirrors.iter().flat_map(|(environment, endpoints)| {
+            endpoints
+                .iter()
+                .map(move |endpoint| (environment.as_str(), endpoint.as_str(), true))
+        }))
+        .collect();
+
     let mut tx = pool.begin().await?;
 
     // Upsert each worker from config
-    for (environment, endpoint) in workers {
+    for (environment, endpoint, mirror) in &configured {
         sqlx::query(
             r#"
-            INSERT INTO workers (environment, endpoint, enabled, last_seen)
-            VALUES ($1, $2, true, NOW())
-            ON CONFLICT (environment) DO UPDATE SET
-                endpoint = EXCLUDED.endpoint,
+            INSERT INTO workers (environment, endpoint, enabled, mirror, last_seen)
+            VALUES ($1, $2, true, $3, NOW())
+            ON CONFLICT (environment, endpoint) DO UPDATE SET
                 enabled = true,
+                mirror = EXCLUDED.mirror,
                 last_seen = NOW(),
                 updated_at = NOW()
             "#,
         )
         .bind(environment)
         .bind(endpoint)
+        .bind(mirror)
         .execute(&mut *tx)
         .await?;
     }
 
     // Disable workers that are not in the config
-    if !workers.is_empty() {
-        let environments: Vec<&str> = workers.keys().map(|s| s.as_str()).collect();
+    if !configured.is_empty() {
+        let environments: Vec<&str> = configured.iter().map(|(env, _, _)| *env).collect();
+        let endpoints: Vec<&str> = configured
+            .iter()
+            .map(|(_, endpoint, _)| *endpoint)
+            .collect();
         sqlx::query(
             r#"
             UPDATE workers
             SET enabled = false, updated_at = NOW()
-            WHERE environment != ALL($1) AND enabled = true
+            WHERE (environment, endpoint) NOT IN (
+                SELECT * FROM UNNEST($1::text[], $2::text[])
+            )
+            AND enabled = true
             "#,
         )
         .bind(&environments)
+        .bind(&endpoints)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(configured.len())
+}
+
+// ==================== Deployment Mirrors ====================
+
+/// Record the workers a mirrored deployment was dispatched to
+pub async fn record_mirror_dispatch(pool: &PgPool, job_id: Uuid, worker_ids: &[i32]) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO deployment_mirrors (job_id, worker_id)
+        SELECT $1, UNNEST($2::int[])
+        ON CONFLICT (job_id, worker_id) DO NOTHING
+        "#,
+    )
+    .bind(job_id)
+    .bind(worker_ids)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Every worker's result for a deployment, in worker order (empty unless mirrored)
+pub async fn list_mirror_results(pool: &PgPool, job_id: Uuid) -> Result<Vec<MirrorResult>> {
+    let results = sqlx::query_as::<_, MirrorResult>(
+        r#"
+        SELECT worker_id, succeeded
+        FROM deployment_mirrors
+        WHERE job_id = $1
+        ORDER BY worker_id
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(results)
+}
+
+/// Record one worker's final result for a mirrored deployment
+///
+/// Returns every worker's result as it stood before this one, in worker
+/// order, or None if the job isn't mirrored on that worker. The job's rows
+/// are locked until the result is stored, so concurrent reports see each
+/// other in turn.
+pub async fn record_mirror_result(
+    pool: &PgPool,
+    job_id: Uuid,
+    worker_id: i32,
+    succeeded: bool,
+    error_message: Option<&str>,
+) -> Result<Option<Vec<MirrorResult>>> {
+    let mut tx = pool.begin().await?;
+
+    let previous = sqlx::query_as::<_, MirrorResult>(
+        r#"
+        SELECT worker_id, succeeded
+        FROM deployment_mirrors
+        WHERE job_id = $1
+        ORDER BY worker_id
+        FOR UPDATE
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if !previous.iter().any(|result| result.worker_id == worker_id) {
+        return Ok(None);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE deployment_mirrors
+        SET succeeded = $3, error_message = $4, reported_at = NOW()
+        WHERE job_id = $1 AND worker_id = $2
+        "#,
+    )
+    .bind(job_id)
+    .bind(worker_id)
+    .bind(succeeded)
+    .bind(error_message)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Some(previous))
+}
+
+// ==================== Worker Sites ====================
+
+/// Replace a zone's site inventory with the sites its worker reported
+///
+/// Upserts every reported site and deletes the zone's other rows, returning
+/// how many sites vanished since the previous report.
+pub async fn replace_worker_sites(
+    pool: &PgPool,
+    zone: &str,
+    sites: &[InventorySite],
+) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+
+    for site in sites {
+        sqlx::query(
+            r#"
+            INSERT INTO worker_sites (zone, site_id, domain, size_bytes, deployed_at, reported_at)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            ON CONFLICT (zone, site_id) DO UPDATE SET
+                domain = EXCLUDED.domain,
+                size_bytes = EXCLUDED.size_bytes,
+                deployed_at = EXCLUDED.deployed_at,
+                reported_at = NOW()
+            "#,
+        )
+        .bind(zone)
+        .bind(&site.site_id)
+        .bind(&site.domain)
+        .bind(i64::try_from(site.size_bytes).unwrap_or(i64::MAX))
+        .bind(site.deployed_at)
         .execute(&mut *tx)
         .await?;
     }
 
+    let site_ids: Vec<&str> = sites.iter().map(|site| site.site_id.as_str()).collect();
+    let removed = sqlx::query(
+        r#"
+        DELETE FROM worker_sites
+        WHERE zone = $1 AND site_id != ALL($2)
+        "#,
+    )
+    .bind(zone)
+    .bind(&site_ids)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
     tx.commit().await?;
 
-    Ok(workers.len())
+    Ok(removed)
+}
+
+/// List the sites a zone's worker last reported, by site ID
+pub async fn list_worker_sites(pool: &PgPool, zone: &str) -> Result<Vec<WorkerSite>> {
+    let sites = sqlx::query_as::<_, WorkerSite>(
+        r#"
+        SELECT zone, site_id, domain, size_bytes, deployed_at, reported_at
+        FROM worker_sites
+        WHERE zone = $1
+        ORDER BY site_id
+        "#,
+    )
+    .bind(zone)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sites)
 }
 
 // ==================== Job Context ====================
@@ -146,17 +418,722 @@ pub async fn get_job_context(pool: &PgPool, job_id: Uuid) -> Result<Option<JobCo
     Ok(context)
 }
 
+// ==================== Deployment History ====================
+
+/// Columns selected for `DeploymentHistory`
+const DEPLOYMENT_COLUMNS: &str = "id, job_id, github_org, github_repo, pr_number, site_id, zone, \
+     domain, commit_sha, status, created_at, updated_at, started_at, completed_at, size_bytes, \
+     deployment_type, dry_run";
+
+/// Outbox payload for a deployment's status change, built from a
+/// `deployment_history` row and its `previous_status` (NULL when recorded)
+const STATUS_EVENT_PAYLOAD: &str = "jsonb_build_object(
+    'job_id', job_id,
+    'org', github_org,
+    'repo', github_repo,
+    'pr_number', pr_number,
+    'site_id', site_id,
+    'zone', zone,
+    'domain', domain,
+    'commit_sha', commit_sha,
+    'deployment_type', deployment_type,
+    'dry_run', dry_run,
+    'previous_status', previous_status,
+    'status', status,
+    'updated_at', updated_at
+)";
+
+/// Columns selected for `AuthorizedOrg`
+const ORG_COLUMNS: &str = "id, github_org, zones, domain_patterns, enabled, \
+     max_active_deployments, max_total_mb, repo_allowlist, repo_denylist, created_at, updated_at";
+
+/// A deployment about to be recorded
+#[derive(Debug, Clone)]
+pub struct NewDeployment<'a> {
+    pub job_id: Uuid,
+    pub org: &'a str,
+    pub repo: &'a str,
+    pub pr_number: Option<u32>,
+    pub site_id: &'a str,
+    pub zone: &'a str,
+    pub domain: &'a str,
+    pub commit_sha: &'a str,
+    pub deployment_type: DeploymentType,
+    /// Built without being published
+    pub dry_run: bool,
+}
+
+/// Record a dispatched deployment, queueing its first event in the outbox
+pub async fn record_deployment(pool: &PgPool, deployment: &NewDeployment<'_>) -> Result<()> {
+    sqlx::query(&format!(
+        r#"
+        WITH recorded AS (
+            INSERT INTO deployment_history
+                (job_id, github_org, github_repo, pr_number, site_id, zone, domain, commit_sha,
+                 status, deployment_type, dry_run)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'pending', $9, $10)
+            ON CONFLICT (job_id) DO NOTHING
+            RETURNING *, NULL::text AS previous_status
+        )
+        INSERT INTO deployment_outbox (job_id, payload)
+        SELECT job_id, {STATUS_EVENT_PAYLOAD}
+        FROM recorded
+        "#
+    ))
+    .bind(deployment.job_id)
+    .bind(deployment.org)
+    .bind(deployment.repo)
+    .bind(deployment.pr_number.map(|n| n as i32))
+    .bind(deployment.site_id)
+    .bind(deployment.zone)
+    .bind(deployment.domain)
+    .bind(deployment.commit_sha)
+    .bind(deployment.deployment_type.to_string())
+    .bind(deployment.dry_run)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Update the status of a deployment by job_id
+///
+/// A change of status also queues a deployment event in the outbox, in the
+/// same transaction, so the event can't be lost or sent for a change that
+/// never happened. A finished deployment keeps its final status, so a late
+/// progress report leaves it untouched. Returns whether the status changed:
+/// false for repeated or late reports, and for jobs that aren't a tracked
+/// deployment (e.g. a cleanup job).
+pub async fn update_deployment_status(
+    pool: &PgPool,
+    job_id: Uuid,
+    status: JobStatus,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let previous: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT status
+        FROM deployment_history
+        WHERE job_id = $1
+        FOR UPDATE
+        "#,
+    )
+    .bind(job_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(previous) = previous else {
+        return Ok(false);
+    };
+
+    let finished = JobStatus::FINAL
+        .iter()
+        .any(|final_status| final_status.to_string() == previous);
+    if finished && !status.is_final() {
+        return Ok(false);
+    }
+    let changed = previous != status.to_string();
+
+    sqlx::query(
+        r#"
+        UPDATE deployment_history
+        SET status = $2,
+            started_at = CASE WHEN $2 = 'building' THEN COALESCE(started_at, NOW())
+                              ELSE started_at END,
+            completed_at = CASE WHEN $2 IN ('success', 'failed', 'cancelled') THEN NOW()
+                                ELSE completed_at END,
+            updated_at = NOW()
+        WHERE job_id = $1
+        "#,
+    )
+    .bind(job_id)
+    .bind(status.to_string())
+    .execute(&mut *tx)
+    .await?;
+
+    // Repeated progress reports don't change the status and aren't events
+    if changed {
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO deployment_outbox (job_id, payload)
+            SELECT job_id, {STATUS_EVENT_PAYLOAD}
+            FROM (
+                SELECT *, $2::text AS previous_status
+                FROM deployment_history
+                WHERE job_id = $1
+            ) AS changed
+            "#
+        ))
+        .bind(job_id)
+        .bind(&previous)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(changed)
+}
+
+/// Get a deployment by job_id
+pub async fn get_deployment(pool: &PgPool, job_id: Uuid) -> Result<Option<DeploymentHistory>> {
+    let deployment = sqlx::query_as::<_, DeploymentHistory>(&format!(
+        r#"
+        SELECT {DEPLOYMENT_COLUMNS}
+        FROM deployment_history
+        WHERE job_id = $1
+        "#
+    ))
+    .bind(job_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(deployment)
+}
+
+/// Get the most recent deployment of a site, ignoring dry runs
+pub async fn get_latest_site_deployment(
+    pool: &PgPool,
+    site_id: &str,
+) -> Result<Option<DeploymentHistory>> {
+    let deployment = sqlx::query_as::<_, DeploymentHistory>(&format!(
+        r#"
+        SELECT {DEPLOYMENT_COLUMNS}
+        FROM deployment_history
+        WHERE site_id = $1
+          AND NOT dry_run
+        ORDER BY created_at DESC, id DESC
+        LIMIT 1
+        "#
+    ))
+    .bind(site_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(deployment)
+}
+
+/// List active deployments for a repository
+///
+/// A site is active if its latest deployment hasn't been cleaned up (dry
+/// runs never touch the site, so they don't count). Returns the latest
+/// deployment for each active site.
+pub async fn list_active_deployments(
+    pool: &PgPool,
+    org: &str,
+    repo: &str,
+) -> Result<Vec<DeploymentHistory>> {
+    let deployments = sqlx::query_as::<_, DeploymentHistory>(&format!(
+        r#"
+        SELECT * FROM (
+            SELECT DISTINCT ON (site_id) {DEPLOYMENT_COLUMNS}
+            FROM deployment_history
+            WHERE LOWER(github_org) = LOWER($1)
+              AND LOWER(github_repo) = LOWER($2)
+              AND NOT dry_run
+            ORDER BY site_id, created_at DESC, id DESC
+        ) latest
+        WHERE status != 'cleaned'
+        ORDER BY site_id
+        "#
+    ))
+    .bind(org)
+    .bind(repo)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deployments)
+}
+
+/// Filters selecting active deployments (unset filters match everything)
+#[derive(Debug, Clone, Default)]
+pub struct DeploymentFilter {
+    /// GitHub organization (case-insensitive)
+    pub org: Option<String>,
+    /// Repository name (case-insensitive)
+    pub repo: Option<String>,
+    /// Only sites whose latest deployment was created before this time
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only sites whose latest deployment has this status
+    pub status: Option<JobStatus>,
+}
+
+/// List active deployments, the latest per site, matching every set filter
+pub async fn list_active_deployments_matching(
+    pool: &PgPool,
+    filter: &DeploymentFilter,
+) -> Result<Vec<DeploymentHistory>> {
+    let deployments = sqlx::query_as::<_, DeploymentHistory>(&format!(
+        r#"
+        SELECT * FROM (
+            SELECT DISTINCT ON (site_id) {DEPLOYMENT_COLUMNS}
+            FROM deployment_history
+            WHERE ($1::TEXT IS NULL OR LOWER(github_org) = LOWER($1))
+              AND ($2::TEXT IS NULL OR LOWER(github_repo) = LOWER($2))
+              AND NOT dry_run
+            ORDER BY site_id, created_at DESC, id DESC
+        ) latest
+        WHERE status != 'cleaned'
+          AND ($3::TIMESTAMPTZ IS NULL OR created_at < $3)
+          AND ($4::TEXT IS NULL OR status = $4)
+        ORDER BY site_id
+        "#
+    ))
+    .bind(filter.org.as_deref())
+    .bind(filter.repo.as_deref())
+    .bind(filter.created_before)
+    .bind(filter.status.map(|status| status.to_string()))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deployments)
+}
+
+/// List active deployments for every repository in an organization
+pub async fn list_active_deployments_for_org(
+    pool: &PgPool,
+    org: &str,
+) -> Result<Vec<DeploymentHistory>> {
+    let deployments = sqlx::query_as::<_, DeploymentHistory>(&format!(
+        r#"
+        SELECT * FROM (
+            SELECT DISTINCT ON (site_id) {DEPLOYMENT_COLUMNS}
+            FROM deployment_history
+            WHERE LOWER(github_org) = LOWER($1)
+              AND NOT dry_run
+            ORDER BY site_id, created_at DESC, id DESC
+        ) latest
+        WHERE status != 'cleaned'
+        ORDER BY site_id
+        "#
+    ))
+    .bind(org)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deployments)
+}
+
+/// Record the size of a deployment's site files
+pub async fn record_deployment_size(pool: &PgPool, job_id: Uuid, size_bytes: u64) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE deployment_history
+        SET size_bytes = $2, updated_at = NOW()
+        WHERE job_id = $1
+        "#,
+    )
+    .bind(job_id)
+    .bind(i64::try_from(size_bytes).unwrap_or(i64::MAX))
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Count an org's active deployments and the disk they use
+///
+/// Each active site counts with the size of its most recent deployment that
+/// reported one, so a site being rebuilt still counts its live files.
+pub async fn get_org_usage(pool: &PgPool, org: &str) -> Result<OrgUsage> {
+    let usage = sqlx::query_as::<_, OrgUsage>(
+        r#"
+        SELECT COUNT(*) AS active_deployments,
+               COALESCE(SUM(sized.size_bytes), 0)::BIGINT AS total_bytes
+        FROM (
+            SELECT DISTINCT ON (site_id) site_id, status
+            FROM deployment_history
+            WHERE LOWER(github_org) = LOWER($1)
+              AND NOT dry_run
+            ORDER BY site_id, created_at DESC, id DESC
+        ) latest
+        LEFT JOIN LATERAL (
+            SELECT size_bytes
+            FROM deployment_history sized
+            WHERE sized.site_id = latest.site_id AND sized.size_bytes IS NOT NULL
+            ORDER BY sized.created_at DESC, sized.id DESC
+            LIMIT 1
+        ) sized ON true
+        WHERE latest.status != 'cleaned'
+        "#,
+    )
+    .bind(org)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(usage)
+}
+
+/// Mark every deployment of a site as cleaned up
+///
+/// Each deployment's change is queued in the outbox along with it.
+pub async fn mark_site_cleaned(pool: &PgPool, site_id: &str) -> Result<u64> {
+    let result = sqlx::query(&format!(
+        r#"
+        WITH previous AS (
+            SELECT job_id, status
+            FROM deployment_history
+            WHERE site_id = $1 AND status != 'cleaned'
+            FOR UPDATE
+        ), updated AS (
+            UPDATE deployment_history AS deployment
+            SET status = 'cleaned', updated_at = NOW()
+            FROM previous
+            WHERE deployment.job_id = previous.job_id
+            RETURNING deployment.*, previous.status AS previous_status
+        )
+        INSERT INTO deployment_outbox (job_id, payload)
+        SELECT job_id, {STATUS_EVENT_PAYLOAD}
+        FROM updated
+        "#
+    ))
+    .bind(site_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ==================== Pending Approvals ====================
+
+/// A held deployment waiting for approval
+#[derive(Debug, Clone)]
+pub struct PendingApproval {
+    pub installation_id: i64,
+    /// Job to dispatch; `git_token` is empty and must be filled in
+    pub job: BuildJob,
+}
+
+/// Record a deployment held for approval instead of dispatching it
+///
+/// The job is stored without its clone token, which would expire long before
+/// most approvals. The deployment is recorded as `pending_approval`, with
+/// its first event queued in the outbox.
+pub async fn record_pending_approval(
+    pool: &PgPool,
+    deployment: &NewDeployment<'_>,
+    installation_id: u64,
+    job: &BuildJob,
+    expires_in: Duration,
+) -> Result<()> {
+    let job = BuildJob {
+        git_token: String::new(),
+        ..job.clone()
+    };
+    let job = serde_json::to_string(&job).context("Failed to serialize build job")?;
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(&format!(
+        r#"
+        WITH recorded AS (
+            INSERT INTO deployment_history
+                (job_id, github_org, github_repo, pr_number, site_id, zone, domain, commit_sha,
+                 status, deployment_type, dry_run)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'pending_approval', $9, $10)
+            RETURNING *, NULL::text AS previous_status
+        )
+        INSERT INTO deployment_outbox (job_id, payload)
+        SELECT job_id, {STATUS_EVENT_PAYLOAD}
+        FROM recorded
+        "#
+    ))
+    .bind(deployment.job_id)
+    .bind(deployment.org)
+    .bind(deployment.repo)
+    .bind(deployment.pr_number.map(|n| n as i32))
+    .bind(deployment.site_id)
+    .bind(deployment.zone)
+    .bind(deployment.domain)
+    .bind(deployment.commit_sha)
+    .bind(deployment.deployment_type.to_string())
+    .bind(deployment.dry_run)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO pending_approvals (job_id, installation_id, job, expires_at)
+        VALUES ($1, $2, $3::jsonb, NOW() + make_interval(secs => $4))
+        "#,
+    )
+    .bind(deployment.job_id)
+    .bind(installation_id as i64)
+    .bind(job)
+    .bind(expires_in.as_secs_f64())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Approve a held deployment, returning the job to dispatch
+///
+/// Removes the approval and moves the deployment to `pending` atomically,
+/// queueing the change in the outbox, so a job is only ever approved once.
+/// Returns None if the job isn't awaiting approval or its window has passed.
+pub async fn take_pending_approval(pool: &PgPool, job_id: Uuid) -> Result<Option<PendingApproval>> {
+    let row: Option<(i64, String)> = sqlx::query_as(&format!(
+        r#"
+        WITH approved AS (
+            DELETE FROM pending_approvals
+            WHERE job_id = $1 AND expires_at > NOW()
+            RETURNING job_id, installation_id, job
+        ), previous AS (
+            SELECT job_id, status
+            FROM deployment_history
+            WHERE job_id IN (SELECT job_id FROM approved)
+        ), updated AS (
+            UPDATE deployment_history AS deployment
+            SET status = 'pending', updated_at = NOW()
+            FROM previous
+            WHERE deployment.job_id = previous.job_id
+            RETURNING deployment.*, previous.status AS previous_status
+        ), queued AS (
+            INSERT INTO deployment_outbox (job_id, payload)
+            SELECT job_id, {STATUS_EVENT_PAYLOAD}
+            FROM updated
+        )
+        SELECT installation_id, job::text FROM approved
+        "#
+    ))
+    .bind(job_id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|(installation_id, job)| {
+        let job = serde_json::from_str(&job).context("Failed to parse held build job")?;
+        Ok(PendingApproval {
+            installation_id,
+            job,
+        })
+    })
+    .transpose()
+}
+
+/// Expire held deployments whose approval window has passed
+///
+/// Each change is queued in the outbox along with it. Returns the job_ids
+/// marked `expired`.
+pub async fn expire_pending_approvals(pool: &PgPool) -> Result<Vec<Uuid>> {
+    let expired: Vec<(Uuid,)> = sqlx::query_as(&format!(
+        r#"
+        WITH expired AS (
+            DELETE FROM pending_approvals
+            WHERE expires_at <= NOW()
+            RETURNING job_id
+        ), previous AS (
+            SELECT job_id, status
+            FROM deployment_history
+            WHERE job_id IN (SELECT job_id FROM expired)
+        ), updated AS (
+            UPDATE deployment_history AS deployment
+            SET status = 'expired', updated_at = NOW()
+            FROM previous
+            WHERE deployment.job_id = previous.job_id
+            RETURNING deployment.*, previous.status AS previous_status
+        ), queued AS (
+            INSERT INTO deployment_outbox (job_id, payload)
+            SELECT job_id, {STATUS_EVENT_PAYLOAD}
+            FROM updated
+        )
+        SELECT job_id FROM updated
+        "#
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(expired.into_iter().map(|(job_id,)| job_id).collect())
+}
+
+/// Deployments still `pending` or `building` with no status update for `older_than`
+///
+/// These most likely lost their worker's final status update.
+pub async fn list_stuck_deployments(
+    pool: &PgPool,
+    older_than: Duration,
+) -> Result<Vec<DeploymentHistory>> {
+    let deployments = sqlx::query_as::<_, DeploymentHistory>(&format!(
+        r#"
+        SELECT {DEPLOYMENT_COLUMNS}
+        FROM deployment_history
+        WHERE status IN ('pending', 'building')
+          AND updated_at < NOW() - make_interval(secs => $1)
+        ORDER BY updated_at
+        "#
+    ))
+    .bind(older_than.as_secs_f64())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deployments)
+}
+
+/// List the latest deployment of every PR preview that hasn't been cleaned up
+pub async fn list_active_previews(pool: &PgPool) -> Result<Vec<DeploymentHistory>> {
+    let deployments = sqlx::query_as::<_, DeploymentHistory>(&format!(
+        r#"
+        SELECT * FROM (
+            SELECT DISTINCT ON (site_id) {DEPLOYMENT_COLUMNS}
+            FROM deployment_history
+            WHERE pr_number IS NOT NULL
+              AND NOT dry_run
+            ORDER BY site_id, created_at DESC, id DESC
+        ) latest
+        WHERE status != 'cleaned'
+        ORDER BY created_at
+        "#
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deployments)
+}
+
+// ==================== Deployment Events ====================
+
+/// Append a lifecycle event to a deployment's timeline
+pub async fn record_deployment_event(
+    pool: &PgPool,
+    job_id: Uuid,
+    phase: DeploymentPhase,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO deployment_events (job_id, phase)
+        VALUES ($1, $2)
+        "#,
+    )
+    .bind(job_id)
+    .bind(phase.to_string())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// List a deployment's lifecycle events in the order they were recorded
+pub async fn list_deployment_events(pool: &PgPool, job_id: Uuid) -> Result<Vec<DeploymentEvent>> {
+    let events = sqlx::query_as::<_, DeploymentEvent>(
+        r#"
+        SELECT id, job_id, phase, created_at
+        FROM deployment_events
+        WHERE job_id = $1
+        ORDER BY id
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+// ==================== Deployment Outbox ====================
+
+/// Outbox row, with the payload as JSON text
+#[derive(sqlx::FromRow)]
+struct OutboxRow {
+    id: i64,
+    job_id: Uuid,
+    payload: String,
+    attempts: i32,
+    created_at: DateTime<Utc>,
+}
+
+/// Claim up to `limit` outbox events that are due for delivery, oldest first
+///
+/// Claimed events aren't due again until `lease` passes, so an event whose
+/// delivery is cut short by a crash is retried once the lease runs out.
+pub async fn claim_outbox_events(
+    pool: &PgPool,
+    limit: i64,
+    lease: Duration,
+) -> Result<Vec<OutboxEvent>> {
+    let mut rows = sqlx::query_as::<_, OutboxRow>(
+        r#"
+        UPDATE deployment_outbox
+        SET next_attempt_at = NOW() + make_interval(secs => $2)
+        WHERE id IN (
+            SELECT id
+            FROM deployment_outbox
+            WHERE next_attempt_at <= NOW()
+            ORDER BY id
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, job_id, payload::text AS payload, attempts, created_at
+        "#,
+    )
+    .bind(limit)
+    .bind(lease.as_secs_f64())
+    .fetch_all(pool)
+    .await?;
+    rows.sort_by_key(|row| row.id);
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(OutboxEvent {
+                id: row.id,
+                job_id: row.job_id,
+                payload: serde_json::from_str(&row.payload)
+                    .context("Failed to parse outbox payload")?,
+                attempts: row.attempts,
+                created_at: row.created_at,
+            })
+        })
+        .collect()
+}
+
+/// Remove an outbox event every sink accepted
+pub async fn delete_outbox_event(pool: &PgPool, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM deployment_outbox WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Record a failed delivery and make the event due again after `retry_in`
+pub async fn retry_outbox_event(
+    pool: &PgPool,
+    id: i64,
+    retry_in: Duration,
+    error: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE deployment_outbox
+        SET attempts = attempts + 1,
+            last_error = $3,
+            next_attempt_at = NOW() + make_interval(secs => $2)
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(retry_in.as_secs_f64())
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 // ==================== Authorization ====================
 
 /// Get authorized org by GitHub org name (case-insensitive)
 pub async fn get_authorized_org(pool: &PgPool, github_org: &str) -> Result<Option<AuthorizedOrg>> {
-    let org = sqlx::query_as::<_, AuthorizedOrg>(
+    let org = sqlx::query_as::<_, AuthorizedOrg>(&format!(
         r#"
-        SELECT id, github_org, zones, domain_patterns, enabled, created_at, updated_at
+        SELECT {ORG_COLUMNS}
         FROM authorized_orgs
         WHERE LOWER(github_org) = LOWER($1) AND enabled = true
-        "#,
-    )
+        "#
+    ))
     .bind(github_org)
     .fetch_optional(pool)
     .await?;
@@ -166,41 +1143,69 @@ pub async fn get_authorized_org(pool: &PgPool, github_org: &str) -> Result<Optio
 
 /// List all authorized orgs
 pub async fn list_authorized_orgs(pool: &PgPool) -> Result<Vec<AuthorizedOrg>> {
-    let orgs = sqlx::query_as::<_, AuthorizedOrg>(
+    let orgs = sqlx::query_as::<_, AuthorizedOrg>(&format!(
         r#"
-        SELECT id, github_org, zones, domain_patterns, enabled, created_at, updated_at
+        SELECT {ORG_COLUMNS}
         FROM authorized_orgs
         ORDER BY github_org
-        "#,
-    )
+        "#
+    ))
     .fetch_all(pool)
     .await?;
 
     Ok(orgs)
 }
 
+/// Deployment quotas for an org (None is unlimited)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrgQuota {
+    pub max_active_deployments: Option<i32>,
+    pub max_total_mb: Option<i64>,
+}
+
+/// Which of an org's repos may deploy, as repo name globs
+#[derive(Debug, Clone, Default)]
+pub struct RepoFilter {
+    /// Repos allowed to deploy (empty allows every repo)
+    pub allowlist: Vec<String>,
+    /// Repos never allowed to deploy, even if allowlisted
+    pub denylist: Vec<String>,
+}
+
 /// Create or update an authorized org
 pub async fn upsert_authorized_org(
     pool: &PgPool,
     github_org: &str,
     zones: &[String],
     domain_patterns: &[String],
+    quota: OrgQuota,
+    repos: &RepoFilter,
 ) -> Result<AuthorizedOrg> {
-    let org = sqlx::query_as::<_, AuthorizedOrg>(
+    let org = sqlx::query_as::<_, AuthorizedOrg>(&format!(
         r#"
-        INSERT INTO authorized_orgs (github_org, zones, domain_patterns, enabled)
-        VALUES ($1, $2, $3, true)
+        INSERT INTO authorized_orgs
+            (github_org, zones, domain_patterns, enabled, max_active_deployments, max_total_mb,
+             repo_allowlist, repo_denylist)
+        VALUES ($1, $2, $3, true, $4, $5, $6, $7)
         ON CONFLICT (github_org) DO UPDATE SET
             zones = EXCLUDED.zones,
             domain_patterns = EXCLUDED.domain_patterns,
             enabled = true,
+            max_active_deployments = EXCLUDED.max_active_deployments,
+            max_total_mb = EXCLUDED.max_total_mb,
+            repo_allowlist = EXCLUDED.repo_allowlist,
+            repo_denylist = EXCLUDED.repo_denylist,
             updated_at = NOW()
-        RETURNING id, github_org, zones, domain_patterns, enabled, created_at, updated_at
-        "#,
-    )
+        RETURNING {ORG_COLUMNS}
+        "#
+    ))
     .bind(github_org)
     .bind(zones)
     .bind(domain_patterns)
+    .bind(quota.max_active_deployments)
+    .bind(quota.max_total_mb)
+    .bind(&repos.allowlist)
+    .bind(&repos.denylist)
     .fetch_one(pool)
     .await?;
 
@@ -300,3 +1305,128 @@ pub async fn delete_pr_comment(
 
     Ok(result.rows_affected() > 0)
 }
+
+/// Pause or resume deployments for a repository
+pub async fn set_repo_paused(
+    pool: &PgPool,
+    org: &str,
+    repo: &str,
+    paused: bool,
+) -> Result<RepoSettings> {
+    let settings = sqlx::query_as::<_, RepoSettings>(
+        r#"
+        INSERT INTO repo_settings (github_org, github_repo, paused)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (LOWER(github_org), LOWER(github_repo)) DO UPDATE SET
+            paused = EXCLUDED.paused,
+            updated_at = NOW()
+        RETURNING github_org, github_repo, paused, updated_at
+        "#,
+    )
+    .bind(org)
+    .bind(repo)
+    .bind(paused)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(settings)
+}
+
+/// Whether deployments for a repository are paused
+pub async fn is_repo_paused(pool: &PgPool, org: &str, repo: &str) -> Result<bool> {
+    let paused = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT paused
+        FROM repo_settings
+        WHERE LOWER(github_org) = LOWER($1)
+          AND LOWER(github_repo) = LOWER($2)
+        "#,
+    )
+    .bind(org)
+    .bind(repo)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(paused.unwrap_or(false))
+}
+
+// ==================== Admin Audit ====================
+
+/// Record an admin API action in the audit log
+pub async fn record_admin_action(
+    pool: &PgPool,
+    actor: &str,
+    action: AdminAction,
+    target: &str,
+    payload: Option<&serde_json::Value>,
+) -> Result<()> {
+    let payload = payload
+        .map(serde_json::to_string)
+        .transpose()
+        .context("Failed to serialize audit payload")?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO admin_audit (actor, action, target, payload)
+        VALUES ($1, $2, $3, $4::jsonb)
+        "#,
+    )
+    .bind(actor)
+    .bind(action.to_string())
+    .bind(target)
+    .bind(payload)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Audit log row, with the payload as JSON text
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    id: i64,
+    actor: String,
+    action: String,
+    target: String,
+    payload: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+/// List audit log entries newest first, starting below the `before` ID
+pub async fn list_admin_audit(
+    pool: &PgPool,
+    before: Option<i64>,
+    limit: i64,
+) -> Result<Vec<AdminAuditEntry>> {
+    let rows = sqlx::query_as::<_, AuditRow>(
+        r#"
+        SELECT id, actor, action, target, payload::text AS payload, created_at
+        FROM admin_audit
+        WHERE $1::BIGINT IS NULL OR id < $1
+        ORDER BY id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let payload = row
+                .payload
+                .map(|payload| serde_json::from_str(&payload))
+                .transpose()
+                .context("Failed to parse audit payload")?;
+            Ok(AdminAuditEntry {
+                id: row.id,
+                actor: row.actor,
+                action: row.action,
+                target: row.target,
+                payload,
+                created_at: row.created_at,
+            })
+        })
+        .collect()
+}
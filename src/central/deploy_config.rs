@@ -3,69 +3,255 @@
 //! Fetches and merges `.deploy.json` files from:
 //! 1. Organization defaults: `{org}/.github/.deploy.json`
 //! 2. Repository overrides: `{org}/{repo}/.deploy.json`
+//!
+//! Both are layered over optional operator-configured defaults (global and
+//! per-org), so repos following the conventions need no file at all.
+//!
+//! Fetched files are cached with their ETag so unchanged files are
+//! revalidated with a conditional request (304) instead of re-downloaded.
+//! Concurrent fetches of the same file share a single request.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use anyhow::{Context, Result};
 use base64::Engine;
+use futures::FutureExt;
+use futures::future::{BoxFuture, Shared};
 
+use crate::central::github::GitHubApi;
+use crate::central::github::api::send_with_retry;
 use crate::shared::DeployConfig;
 
+/// Operator-configured deploy defaults, loaded from a JSON file
+///
+/// ```json
+/// {
+///   "default": {"zone": "nullislabs", "domain_pattern": "{repo}.nullislabs.io"},
+///   "orgs": {"acme": {"zone": "acme", "domain_pattern": "{repo}.acme.dev"}}
+/// }
+/// ```
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct DefaultDeployConfigs {
+    /// Defaults for every organization
+    #[serde(default)]
+    pub default: Option<DeployConfig>,
+
+    /// Per-organization defaults, applied over `default`
+    #[serde(default)]
+    pub orgs: HashMap<String, DeployConfig>,
+}
+
+impl DefaultDeployConfigs {
+    /// Load defaults from a JSON file
+    pub fn load(path: &std::path::Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read default deploy config from {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse default deploy config in {:?}", path))
+    }
+
+    /// Defaults for an organization (org names match case-insensitively)
+    pub fn for_org(&self, org: &str) -> Option<DeployConfig> {
+        let org_default = self
+            .orgs
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(org))
+            .map(|(_, config)| config.clone());
+
+        merge_layers([self.default.clone(), org_default])
+    }
+}
+
+/// Merge config layers in order, later layers taking precedence
+///
+/// Returns None if no layer is present.
+fn merge_layers(layers: impl IntoIterator<Item = Option<DeployConfig>>) -> Option<DeployConfig> {
+    layers.into_iter().flatten().reduce(|mut merged, layer| {
+        merged.merge(&layer);
+        merged
+    })
+}
+
+/// Parsed config files keyed by `{org}/{repo}/{path}`, with their ETags
+#[derive(Clone, Default)]
+pub struct ConfigCache {
+    entries: Arc<Mutex<HashMap<String, CachedConfig>>>,
+    /// Requests in flight, joined by concurrent fetches of the same file
+    inflight: Arc<Mutex<HashMap<String, InflightFetch>>>,
+}
+
+type InflightFetch = Shared<BoxFuture<'static, Result<Option<DeployConfig>, Arc<anyhow::Error>>>>;
+
+impl std::fmt::Debug for ConfigCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigCache")
+            .field("entries", &self.entries)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedConfig {
+    etag: String,
+    config: DeployConfig,
+}
+
+impl ConfigCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(org: &str, repo: &str, path: &str) -> String {
+        format!("{}/{}/{}", org, repo, path).to_lowercase()
+    }
+
+    fn get(&self, key: &str) -> Option<CachedConfig> {
+        self.entries.lock().unwrap().get(key).cloned()
+    }
+
+    fn insert(&self, key: String, etag: String, config: DeployConfig) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key, CachedConfig { etag, config });
+    }
+
+    fn remove(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
 /// Fetch and merge deploy configuration for a repository
 ///
-/// Tries to fetch configuration from:
-/// 1. `{org}/.github/.deploy.json` - Organization defaults
-/// 2. `{org}/{repo}/.deploy.json` - Repository-specific overrides
+/// Layers, later ones taking precedence:
+/// 1. Configured defaults (global, then per-org)
+/// 2. `{org}/.github/.deploy.json` - Organization defaults
+/// 3. `{org}/{repo}/.deploy.json` - Repository-specific overrides
 ///
-/// Returns merged config, or None if neither file exists.
+/// Returns merged config, or None if there are no defaults and neither file exists.
 pub async fn fetch_deploy_config(
     http_client: &reqwest::Client,
+    api: &GitHubApi,
+    cache: &ConfigCache,
+    defaults: &DefaultDeployConfigs,
     token: &str,
     org: &str,
     repo: &str,
 ) -> Result<Option<DeployConfig>> {
     // Fetch org-level defaults from .github repo
-    let org_config = fetch_config_file(http_client, token, org, ".github", ".deploy.json").await?;
+    let org_config = fetch_config_file(
+        http_client,
+        api,
+        cache,
+        token,
+        org,
+        ".github",
+        ".deploy.json",
+    )
+    .await?;
 
     // Fetch repo-level overrides
-    let repo_config = fetch_config_file(http_client, token, org, repo, ".deploy.json").await?;
+    let repo_config =
+        fetch_config_file(http_client, api, cache, token, org, repo, ".deploy.json").await?;
 
     // Merge configs
-    match (org_config, repo_config) {
-        (None, None) => Ok(None),
-        (Some(org), None) => Ok(Some(org)),
-        (None, Some(repo)) => Ok(Some(repo)),
-        (Some(mut org), Some(repo)) => {
-            org.merge(&repo);
-            Ok(Some(org))
-        }
-    }
+    Ok(merge_layers([
+        defaults.for_org(org),
+        org_config,
+        repo_config,
+    ]))
 }
 
 /// Fetch a single config file from a GitHub repository
+///
+/// A burst of events for one org would otherwise send identical requests
+/// side by side; callers arriving while a fetch of the same file is in
+/// flight wait for its result instead.
 async fn fetch_config_file(
     http_client: &reqwest::Client,
+    api: &GitHubApi,
+    cache: &ConfigCache,
+    token: &str,
+    org: &str,
+    repo: &str,
+    path: &str,
+) -> Result<Option<DeployConfig>> {
+    let cache_key = ConfigCache::key(org, repo, path);
+
+    let fetch = cache
+        .inflight
+        .lock()
+        .unwrap()
+        .entry(cache_key.clone())
+        .or_insert_with(|| {
+            let (http_client, api, cache) = (http_client.clone(), api.clone(), cache.clone());
+            let (token, org, repo, path) = (
+                token.to_string(),
+                org.to_string(),
+                repo.to_string(),
+                path.to_string(),
+            );
+            async move {
+                let result =
+                    request_config_file(&http_client, &api, &cache, &token, &org, &repo, &path)
+                        .await
+                        .map_err(Arc::new);
+                // Later callers start a fresh request (and see later pushes)
+                cache.inflight.lock().unwrap().remove(&cache_key);
+                result
+            }
+            .boxed()
+            .shared()
+        })
+        .clone();
+
+    fetch.await.map_err(|e| anyhow::anyhow!("{:#}", e))
+}
+
+/// Request a config file from GitHub, updating the cache
+///
+/// Sends `If-None-Match` when the file is cached; a 304 returns the cached
+/// config without downloading or parsing it again.
+async fn request_config_file(
+    http_client: &reqwest::Client,
+    api: &GitHubApi,
+    cache: &ConfigCache,
     token: &str,
     org: &str,
     repo: &str,
     path: &str,
 ) -> Result<Option<DeployConfig>> {
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/contents/{}",
-        org, repo, path
-    );
+    let url = api.url(&format!("/repos/{}/{}/contents/{}", org, repo, path));
+    let cache_key = ConfigCache::key(org, repo, path);
+    let cached = cache.get(&cache_key);
 
-    let response = http_client
+    let mut request = http_client
         .get(&url)
         .header("Authorization", format!("Bearer {}", token))
         .header("Accept", "application/vnd.github+json")
-        .header("User-Agent", "catapult")
-        .header("X-GitHub-Api-Version", "2022-11-28")
-        .send()
+        .header("User-Agent", api.user_agent())
+        .header("X-GitHub-Api-Version", "2022-11-28");
+    if let Some(cached) = &cached {
+        request = request.header("If-None-Match", &cached.etag);
+    }
+
+    let response = send_with_retry(request)
         .await
         .context("Failed to fetch config file from GitHub")?;
 
+    // 304 means our cached copy is still current
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED
+        && let Some(cached) = cached
+    {
+        tracing::debug!(org, repo, path, "Using cached .deploy.json (not modified)");
+        return Ok(Some(cached.config));
+    }
+
     // 404 means file doesn't exist - that's OK
     if response.status() == reqwest::StatusCode::NOT_FOUND {
         tracing::debug!(org, repo, path, "No .deploy.json found");
+        cache.remove(&cache_key);
         return Ok(None);
     }
 
@@ -75,6 +261,12 @@ async fn fetch_config_file(
         anyhow::bail!("GitHub API error {}: {}", status, body);
     }
 
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+
     // Parse the contents response
     let content_response: GitHubContentResponse = response
         .json()
@@ -94,6 +286,11 @@ async fn fetch_config_file(
 
     tracing::debug!(org, repo, path, "Loaded .deploy.json");
 
+    match etag {
+        Some(etag) => cache.insert(cache_key, etag, config.clone()),
+        None => cache.remove(&cache_key),
+    }
+
     Ok(Some(config))
 }
 
@@ -107,6 +304,320 @@ struct GitHubContentResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// GitHub contents API response for a file
+    fn contents_response(json: &str) -> serde_json::Value {
+        serde_json::json!({
+            "content": base64::engine::general_purpose::STANDARD.encode(json),
+            "encoding": "base64",
+        })
+    }
+
+    async fn fetch(server: &MockServer, cache: &ConfigCache) -> Result<Option<DeployConfig>> {
+        fetch_config_file(
+            &reqwest::Client::new(),
+            &GitHubApi::new(&server.uri(), "catapult-test"),
+            cache,
+            "token",
+            "nullisLabs",
+            "website",
+            ".deploy.json",
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_fetch_config_caches_by_etag() {
+        let server = MockServer::start().await;
+        let cache = ConfigCache::new();
+
+        // Conditional request with the cached ETag: not modified, no body
+        Mock::given(method("GET"))
+            .and(path("/repos/nullisLabs/website/contents/.deploy.json"))
+            .and(header("If-None-Match", "\"abc123\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/nullisLabs/website/contents/.deploy.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"abc123\"")
+                    .set_body_json(contents_response(r#"{"zone": "nullislabs"}"#)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let first = fetch(&server, &cache).await.unwrap().unwrap();
+        assert_eq!(first.zone.as_deref(), Some("nullislabs"));
+
+        // The 304 has no body to parse; the cached config is returned
+        let second = fetch(&server, &cache).await.unwrap().unwrap();
+        assert_eq!(second.zone.as_deref(), Some("nullislabs"));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_fetches_share_one_request() {
+        let server = MockServer::start().await;
+        let cache = ConfigCache::new();
+
+        // Slow enough that every fetch starts while the first is in flight
+        Mock::given(method("GET"))
+            .and(path("/repos/nullisLabs/website/contents/.deploy.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(contents_response(r#"{"zone": "nullislabs"}"#))
+                    .set_delay(std::time::Duration::from_millis(200)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let fetches = futures::future::join_all((0..10).map(|_| fetch(&server, &cache))).await;
+        for config in fetches {
+            assert_eq!(config.unwrap().unwrap().zone.as_deref(), Some("nullislabs"));
+        }
+        server.verify().await;
+        assert!(cache.inflight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_config_retried_after_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/nullisLabs/website/contents/.deploy.json"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/nullisLabs/website/contents/.deploy.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(contents_response(r#"{"zone": "nullislabs"}"#)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = fetch(&server, &ConfigCache::new()).await.unwrap().unwrap();
+        assert_eq!(config.zone.as_deref(), Some("nullislabs"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_config_uses_api_base() {
+        let server = MockServer::start().await;
+
+        // GitHub Enterprise Server serves the REST API under /api/v3
+        Mock::given(method("GET"))
+            .and(path(
+                "/api/v3/repos/nullisLabs/website/contents/.deploy.json",
+            ))
+            .and(header("User-Agent", "catapult (ops@nullislabs.io)"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(contents_response(r#"{"zone": "nullislabs"}"#)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = fetch_config_file(
+            &reqwest::Client::new(),
+            &GitHubApi::new(
+                &format!("{}/api/v3/", server.uri()),
+                "catapult (ops@nullislabs.io)",
+            ),
+            &ConfigCache::new(),
+            "token",
+            "nullisLabs",
+            "website",
+            ".deploy.json",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(config.zone.as_deref(), Some("nullislabs"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_config_not_found_clears_cache() {
+        let server = MockServer::start().await;
+        let cache = ConfigCache::new();
+        cache.insert(
+            ConfigCache::key("nullisLabs", "website", ".deploy.json"),
+            "\"stale\"".to_string(),
+            DeployConfig::default(),
+        );
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        assert!(fetch(&server, &cache).await.unwrap().is_none());
+        assert!(
+            cache
+                .get(&ConfigCache::key("nullislabs", "website", ".deploy.json"))
+                .is_none()
+        );
+    }
+
+    fn defaults() -> DefaultDeployConfigs {
+        serde_json::from_str(
+            r#"{
+                "default": {
+                    "zone": "nullislabs",
+                    "domain_pattern": "{repo}.nullislabs.io",
+                    "build_type": "vite"
+                },
+                "orgs": {
+                    "Acme": {"zone": "acme", "domain_pattern": "{repo}.acme.dev"}
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_default_config_only() {
+        let config = merge_layers([defaults().for_org("nullisLabs"), None, None]).unwrap();
+
+        assert!(config.is_deployable());
+        assert_eq!(
+            config.resolve_domain("website"),
+            Some("website.nullislabs.io".to_string())
+        );
+        assert_eq!(config.build_type, Some(crate::shared::SiteType::Vite));
+
+        // No defaults and no files: nothing to deploy
+        assert!(
+            merge_layers([
+                DefaultDeployConfigs::default().for_org("nullisLabs"),
+                None,
+                None
+            ])
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_default_config_per_org() {
+        let config = defaults().for_org("acme").unwrap();
+
+        // Org defaults override the global default; unset fields fall through
+        assert_eq!(config.zone.as_deref(), Some("acme"));
+        assert_eq!(
+            config.resolve_domain("docs"),
+            Some("docs.acme.dev".to_string())
+        );
+        assert_eq!(config.build_type, Some(crate::shared::SiteType::Vite));
+    }
+
+    #[test]
+    fn test_default_config_with_repo_config() {
+        let repo_config: DeployConfig =
+            serde_json::from_str(r#"{"output_dir": "public"}"#).unwrap();
+
+        let config =
+            merge_layers([defaults().for_org("nullisLabs"), None, Some(repo_config)]).unwrap();
+
+        // Defaults fill in what the repo doesn't set
+        assert_eq!(config.zone.as_deref(), Some("nullislabs"));
+        assert_eq!(config.output_dir.as_deref(), Some("public"));
+    }
+
+    #[test]
+    fn test_default_config_explicit_override() {
+        let org_config: DeployConfig = serde_json::from_str(r#"{"build_type": "zola"}"#).unwrap();
+        let repo_config: DeployConfig =
+            serde_json::from_str(r#"{"domain": "nullislabs.io", "enabled": false}"#).unwrap();
+
+        let config = merge_layers([
+            defaults().for_org("nullisLabs"),
+            Some(org_config),
+            Some(repo_config),
+        ])
+        .unwrap();
+
+        assert_eq!(config.build_type, Some(crate::shared::SiteType::Zola));
+        assert_eq!(
+            config.resolve_domain("website"),
+            Some("nullislabs.io".to_string())
+        );
+        assert!(!config.is_deployable());
+    }
+
+    #[test]
+    fn test_full_schema_parses() {
+        // Every documented `.deploy.json` field
+        let config: DeployConfig = serde_json::from_str(
+            r#"{
+                "zone": "nxm",
+                "domain_pattern": "{repo}.nxm.rs",
+                "pr_pattern": "pr-{pr}-{repo}.nxm.rs",
+                "domain": "nxm.rs",
+                "subdomain": "www",
+                "aliases": ["nxm.rs"],
+                "build_type": "sveltekit",
+                "build_command": "npm run build",
+                "output_dir": "build",
+                "node_version": "20",
+                "root_dir": "apps/web",
+                "build_args": {"THEME": "dark"},
+                "memory_mb": 6144,
+                "cpu_cores": 1.5,
+                "pids_limit": 2000,
+                "precompress": true,
+                "git_lfs": true,
+                "minimize_stale_comments": true,
+                "require_label": "preview",
+                "skip_drafts": true,
+                "pr_previews_enabled": false,
+                "basic_auth": {"username": "preview", "bcrypt_hash": "$2b$12$hash"},
+                "canary_percent": 10,
+                "immutable_paths": ["/_app/immutable/*"],
+                "purge_cache": "hostnames",
+                "caddy_extra": [{"handler": "rewrite", "uri": "/index.html"}],
+                "enabled": true
+            }"#,
+        )
+        .unwrap();
+
+        assert!(config.is_deployable());
+        assert_eq!(
+            config.resolve_domain("website"),
+            Some("www.nxm.rs".to_string())
+        );
+        assert_eq!(
+            config.resolve_pr_domain("website", 42),
+            Some("pr-42-website.nxm.rs".to_string())
+        );
+        assert_eq!(config.resolve_aliases("www.nxm.rs"), vec!["nxm.rs"]);
+        assert_eq!(config.build_type, Some(crate::shared::SiteType::SvelteKit));
+        assert_eq!(config.root_dir.as_deref(), Some("apps/web"));
+        assert_eq!(config.cpu_cores, Some(1.5));
+        assert_eq!(config.canary_percent, Some(10));
+        assert_eq!(config.skip_drafts, Some(true));
+        assert_eq!(config.git_lfs, Some(true));
+        assert!(!config.previews_enabled());
+        assert_eq!(
+            config.purge_cache,
+            Some(crate::shared::CachePurge::Hostnames)
+        );
+        assert_eq!(
+            config.caddy_extra,
+            Some(serde_json::json!([{"handler": "rewrite", "uri": "/index.html"}]))
+        );
+        assert_eq!(
+            config.basic_auth.map(|auth| auth.username).as_deref(),
+            Some("preview")
+        );
+    }
 
     #[test]
     fn test_deploy_config_merge() {
@@ -139,6 +650,101 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_immutable_paths() {
+        use crate::shared::SiteType;
+
+        let config: DeployConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(
+            config.resolve_immutable_paths(SiteType::SvelteKit),
+            ["/_app/immutable/*"]
+        );
+        assert_eq!(
+            config.resolve_immutable_paths(SiteType::Vite),
+            ["/assets/*"]
+        );
+        assert!(config.resolve_immutable_paths(SiteType::Zola).is_empty());
+
+        // An explicit list replaces the defaults; an empty one opts out
+        let config: DeployConfig =
+            serde_json::from_str(r#"{"immutable_paths": ["/static/hashed/*"]}"#).unwrap();
+        assert_eq!(
+            config.resolve_immutable_paths(SiteType::SvelteKit),
+            ["/static/hashed/*"]
+        );
+        let config: DeployConfig = serde_json::from_str(r#"{"immutable_paths": []}"#).unwrap();
+        assert!(
+            config
+                .resolve_immutable_paths(SiteType::SvelteKit)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_deploy_config_merge_build_args() {
+        let mut org_config = DeployConfig {
+            build_args: Some(
+                [
+                    ("THEME".to_string(), "dark".to_string()),
+                    ("DOCS".to_string(), "0".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+
+        let repo_config = DeployConfig {
+            build_args: Some(
+                [
+                    ("DOCS".to_string(), "1".to_string()),
+                    ("LOCALE".to_string(), "en".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+
+        org_config.merge(&repo_config);
+
+        let args = org_config.build_args.unwrap();
+        assert_eq!(args.len(), 3);
+        assert_eq!(args["THEME"], "dark"); // Org value preserved
+        assert_eq!(args["DOCS"], "1"); // Repo overrides org
+        assert_eq!(args["LOCALE"], "en"); // Repo addition
+    }
+
+    #[test]
+    fn test_deploy_config_merge_keeps_org_build_args() {
+        let mut org_config = DeployConfig {
+            build_args: Some([("THEME".to_string(), "dark".to_string())].into()),
+            ..Default::default()
+        };
+
+        org_config.merge(&DeployConfig::default());
+
+        assert_eq!(org_config.build_args.unwrap()["THEME"], "dark");
+    }
+
+    #[test]
+    fn test_deploy_config_basic_auth() {
+        let org_json = r#"{
+            "zone": "nullislabs",
+            "basic_auth": {"username": "preview", "bcrypt_hash": "$2b$12$org"}
+        }"#;
+        let repo_json = r#"{"build_type": "vite"}"#;
+
+        let mut config: DeployConfig = serde_json::from_str(org_json).unwrap();
+        let repo_config: DeployConfig = serde_json::from_str(repo_json).unwrap();
+        config.merge(&repo_config);
+
+        // Org-wide preview protection survives a repo config without basic_auth
+        let auth = config.basic_auth.unwrap();
+        assert_eq!(auth.username, "preview");
+        assert_eq!(auth.bcrypt_hash, "$2b$12$org");
+    }
+
     #[test]
     fn test_resolve_domain() {
         let config = DeployConfig {
@@ -168,6 +774,30 @@ mod tests {
         assert_eq!(config.resolve_domain("website"), Some("nxm.rs".to_string()));
     }
 
+    #[test]
+    fn test_resolve_aliases() {
+        let config = DeployConfig {
+            domain: Some("example.com".to_string()),
+            subdomain: Some("www".to_string()),
+            aliases: Some(vec![
+                "Example.com".to_string(),
+                "www.example.com".to_string(),
+                "example.com".to_string(),
+                " ".to_string(),
+            ]),
+            ..Default::default()
+        };
+
+        // The main domain, duplicates and blanks are dropped
+        let main_domain = config.resolve_domain("website").unwrap();
+        assert_eq!(config.resolve_aliases(&main_domain), vec!["example.com"]);
+        assert!(
+            DeployConfig::default()
+                .resolve_aliases("example.com")
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_resolve_pr_domain() {
         let config = DeployConfig {
@@ -195,6 +825,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_commit_domain() {
+        let config = DeployConfig {
+            domain: Some("nxm.rs".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.resolve_commit_domain("Website", "0123456789abcdef"),
+            Some("commit-0123456-website.nxm.rs".to_string())
+        );
+        assert_eq!(
+            DeployConfig::default().resolve_commit_domain("website", "0123456"),
+            None
+        );
+    }
+
     #[test]
     fn test_is_deployable() {
         let mut config = DeployConfig::default();
@@ -1,18 +1,21 @@
 use anyhow::{Context, Result};
 
-use crate::shared::{BuildJob, CleanupJob, auth::sign_request};
+use crate::shared::{
+    BuildJob, CancelJob, CleanupJob, JobStatusQuery, PromoteJob, WorkerJobState,
+    auth::RequestSigner,
+};
 
 /// Dispatch a build job to a worker
 pub async fn dispatch_build_job(
     http_client: &reqwest::Client,
     worker_endpoint: &str,
-    shared_secret: &str,
+    signer: RequestSigner<'_>,
     job: &BuildJob,
 ) -> Result<()> {
     let url = format!("{}/build", worker_endpoint);
     let body = serde_json::to_vec(job).context("Failed to serialize build job")?;
 
-    let (signature, timestamp) = sign_request(shared_secret.as_bytes(), &body);
+    let (signature, timestamp) = signer.sign(&body);
 
     let response = http_client
         .post(&url)
@@ -37,13 +40,13 @@ pub async fn dispatch_build_job(
 pub async fn dispatch_cleanup_job(
     http_client: &reqwest::Client,
     worker_endpoint: &str,
-    shared_secret: &str,
+    signer: RequestSigner<'_>,
     job: &CleanupJob,
 ) -> Result<()> {
     let url = format!("{}/cleanup", worker_endpoint);
     let body = serde_json::to_vec(job).context("Failed to serialize cleanup job")?;
 
-    let (signature, timestamp) = sign_request(shared_secret.as_bytes(), &body);
+    let (signature, timestamp) = signer.sign(&body);
 
     let response = http_client
         .post(&url)
@@ -63,3 +66,111 @@ pub async fn dispatch_cleanup_job(
 
     Ok(())
 }
+
+/// Ask a worker to promote a canary release to all traffic
+///
+/// Returns false if the worker has no canary from the job awaiting promotion.
+pub async fn dispatch_promote_job(
+    http_client: &reqwest::Client,
+    worker_endpoint: &str,
+    signer: RequestSigner<'_>,
+    job: &PromoteJob,
+) -> Result<bool> {
+    let url = format!("{}/promote", worker_endpoint);
+    let body = serde_json::to_vec(job).context("Failed to serialize promote job")?;
+
+    let (signature, timestamp) = signer.sign(&body);
+
+    let response = http_client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .header("X-Central-Signature", signature)
+        .header("X-Request-Timestamp", timestamp.to_string())
+        .body(body)
+        .send()
+        .await
+        .context("Failed to dispatch promote job to worker")?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(false);
+    }
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Worker returned error {}: {}", status, body);
+    }
+
+    Ok(true)
+}
+
+/// Ask a worker to cancel an in-flight build
+///
+/// Returns false if the worker has no cancellable build for the job.
+pub async fn dispatch_cancel_job(
+    http_client: &reqwest::Client,
+    worker_endpoint: &str,
+    signer: RequestSigner<'_>,
+    job: &CancelJob,
+) -> Result<bool> {
+    let url = format!("{}/cancel", worker_endpoint);
+    let body = serde_json::to_vec(job).context("Failed to serialize cancel job")?;
+
+    let (signature, timestamp) = signer.sign(&body);
+
+    let response = http_client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .header("X-Central-Signature", signature)
+        .header("X-Request-Timestamp", timestamp.to_string())
+        .body(body)
+        .send()
+        .await
+        .context("Failed to dispatch cancel job to worker")?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(false);
+    }
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Worker returned error {}: {}", status, body);
+    }
+
+    Ok(true)
+}
+
+/// Ask a worker what became of a build job
+pub async fn query_job_status(
+    http_client: &reqwest::Client,
+    worker_endpoint: &str,
+    signer: RequestSigner<'_>,
+    query: &JobStatusQuery,
+) -> Result<WorkerJobState> {
+    let url = format!("{}/status", worker_endpoint);
+    let body = serde_json::to_vec(query).context("Failed to serialize job status query")?;
+
+    let (signature, timestamp) = signer.sign(&body);
+
+    let response = http_client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .header("X-Central-Signature", signature)
+        .header("X-Request-Timestamp", timestamp.to_string())
+        .body(body)
+        .send()
+        .await
+        .context("Failed to query job status from worker")?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Worker returned error {}: {}", status, body);
+    }
+
+    response
+        .json()
+        .await
+        .context("Invalid job status response from worker")
+}
@@ -1,9 +1,142 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use anyhow::{Context, Result};
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
 
+/// Public GitHub's REST API base URL
+pub const DEFAULT_API_BASE: &str = "https://api.github.com";
+
+/// Default User-Agent for GitHub API requests
+pub const DEFAULT_USER_AGENT: &str = concat!("catapult/", env!("CARGO_PKG_VERSION"));
+
+/// Where GitHub API requests go and how they identify themselves
+///
+/// Public GitHub by default; GitHub Enterprise Server serves the REST API
+/// under `/api/v3` on its own host.
+#[derive(Debug, Clone)]
+pub struct GitHubApi {
+    base_url: String,
+    user_agent: String,
+}
+
+impl Default for GitHubApi {
+    fn default() -> Self {
+        Self::new(DEFAULT_API_BASE, DEFAULT_USER_AGENT)
+    }
+}
+
+impl GitHubApi {
+    pub fn new(base_url: &str, user_agent: &str) -> Self {
+        Self {
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            user_agent: user_agent.to_string(),
+        }
+    }
+
+    /// REST API URL for `path` (starting with `/`)
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// GraphQL API URL (`/api/graphql` next to Enterprise's `/api/v3`)
+    pub fn graphql_url(&self) -> String {
+        match self.base_url.strip_suffix("/v3") {
+            Some(api_root) => format!("{}/graphql", api_root),
+            None => self.url("/graphql"),
+        }
+    }
+
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+}
+
+/// Steps listed in the in-progress PR comment, in order
+pub const PROGRESS_STEPS: [&str; 4] = ["Clone", "Build", "Deploy", "Route traffic"];
+
+/// Retries of a rate-limited GitHub request before its response is returned as is
+const RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Longest rate limit worth waiting out; longer ones fail right away
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Wait GitHub asks for after a secondary rate limit without `Retry-After`
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Send a GitHub API request, waiting out and retrying rate limits
+///
+/// A rate-limited response is retried up to `RATE_LIMIT_RETRIES` times when
+/// the wait is at most `MAX_RATE_LIMIT_WAIT`; otherwise it's returned for the
+/// caller's usual error handling.
+pub async fn send_with_retry(
+    request: reqwest::RequestBuilder,
+) -> reqwest::Result<reqwest::Response> {
+    let mut attempt = 0;
+    loop {
+        // Bodies are always buffered JSON, so only streaming bodies can't be retried
+        let Some(retry) = request.try_clone() else {
+            return request.send().await;
+        };
+        let response = retry.send().await?;
+
+        let wait = match rate_limit_wait(response.status(), response.headers(), unix_now()) {
+            Some(wait) if attempt < RATE_LIMIT_RETRIES && wait <= MAX_RATE_LIMIT_WAIT => wait,
+            _ => return Ok(response),
+        };
+
+        attempt += 1;
+        tracing::warn!(
+            url = %response.url(),
+            status = %response.status(),
+            wait_secs = wait.as_secs(),
+            attempt,
+            "GitHub API rate limited, retrying"
+        );
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// How long to wait before retrying a rate-limited response, if it is one
+///
+/// GitHub answers rate limits with 429, or 403 with `Retry-After` or an
+/// exhausted `x-ratelimit-remaining`. Other 403s are permission errors.
+fn rate_limit_wait(status: StatusCode, headers: &HeaderMap, now: u64) -> Option<Duration> {
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::FORBIDDEN {
+        return None;
+    }
+
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    };
+
+    if let Some(secs) = header("retry-after") {
+        return Some(Duration::from_secs(secs));
+    }
+    if header("x-ratelimit-remaining") == Some(0)
+        && let Some(reset) = header("x-ratelimit-reset")
+    {
+        return Some(Duration::from_secs(reset.saturating_sub(now)));
+    }
+
+    (status == StatusCode::TOO_MANY_REQUESTS).then_some(DEFAULT_RATE_LIMIT_WAIT)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
 /// GitHub API client for interacting with repositories
 pub struct GitHubClient {
     http_client: reqwest::Client,
+    api: GitHubApi,
     token: String,
 }
 
@@ -12,16 +145,79 @@ struct CreateCommentRequest {
     body: String,
 }
 
+/// Commit status context used unless configured otherwise
+pub const DEFAULT_STATUS_CONTEXT: &str = "catapult";
+
+/// State of a commit status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitStatusState {
+    Pending,
+    Success,
+    Failure,
+    Error,
+}
+
+/// A commit status, as sent to GitHub
+#[derive(Debug, Serialize)]
+pub struct CommitStatus<'a> {
+    pub state: CommitStatusState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_url: Option<&'a str>,
+    pub description: &'a str,
+    pub context: &'a str,
+}
+
+/// Commit status context for an environment ("preview" or "production")
+///
+/// Lets teams tell Catapult's statuses apart from other deploy tools',
+/// e.g. `catapult/{environment}` gives `catapult/preview`.
+pub fn status_context(template: &str, environment: &str) -> String {
+    template.replace("{environment}", environment)
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CommentResponse {
     pub id: i64,
+    /// GraphQL node ID, needed for GraphQL-only operations like minimizing
+    #[serde(default)]
+    pub node_id: String,
+}
+
+/// GraphQL mutation hiding a comment behind a "minimized" notice
+const MINIMIZE_COMMENT_MUTATION: &str = "mutation($id: ID!, $classifier: ReportedContentClassifiers!) { \
+     minimizeComment(input: {subjectId: $id, classifier: $classifier}) { \
+     minimizedComment { isMinimized } } }";
+
+/// GraphQL response envelope (only errors are inspected)
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse {
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+/// Build the GraphQL request minimizing a comment as outdated
+fn minimize_comment_request(node_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "query": MINIMIZE_COMMENT_MUTATION,
+        "variables": {
+            "id": node_id,
+            "classifier": "OUTDATED",
+        },
+    })
 }
 
 impl GitHubClient {
     /// Create a new GitHub client with an installation access token
-    pub fn new(token: String) -> Self {
+    pub fn new(http_client: reqwest::Client, api: GitHubApi, token: String) -> Self {
         Self {
-            http_client: reqwest::Client::new(),
+            http_client,
+            api,
             token,
         }
     }
@@ -34,24 +230,24 @@ impl GitHubClient {
         pr_number: u32,
         body: &str,
     ) -> Result<CommentResponse> {
-        let url = format!(
-            "https://api.github.com/repos/{}/{}/issues/{}/comments",
+        let url = self.api.url(&format!(
+            "/repos/{}/{}/issues/{}/comments",
             owner, repo, pr_number
-        );
+        ));
 
-        let response = self
-            .http_client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .header("Accept", "application/vnd.github+json")
-            .header("User-Agent", "catapult")
-            .header("X-GitHub-Api-Version", "2022-11-28")
-            .json(&CreateCommentRequest {
-                body: body.to_string(),
-            })
-            .send()
-            .await
-            .context("Failed to create PR comment")?;
+        let response = send_with_retry(
+            self.http_client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", self.token))
+                .header("Accept", "application/vnd.github+json")
+                .header("User-Agent", self.api.user_agent())
+                .header("X-GitHub-Api-Version", "2022-11-28")
+                .json(&CreateCommentRequest {
+                    body: body.to_string(),
+                }),
+        )
+        .await
+        .context("Failed to create PR comment")?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -65,6 +261,40 @@ impl GitHubClient {
             .context("Failed to parse comment response")
     }
 
+    /// Set a commit status
+    pub async fn create_commit_status(
+        &self,
+        owner: &str,
+        repo: &str,
+        commit_sha: &str,
+        status: &CommitStatus<'_>,
+    ) -> Result<()> {
+        let url = self.api.url(&format!(
+            "/repos/{}/{}/statuses/{}",
+            owner, repo, commit_sha
+        ));
+
+        let response = send_with_retry(
+            self.http_client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", self.token))
+                .header("Accept", "application/vnd.github+json")
+                .header("User-Agent", self.api.user_agent())
+                .header("X-GitHub-Api-Version", "2022-11-28")
+                .json(status),
+        )
+        .await
+        .context("Failed to create commit status")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("GitHub API error {}: {}", status, body);
+        }
+
+        Ok(())
+    }
+
     /// Update an existing comment
     pub async fn update_comment(
         &self,
@@ -73,24 +303,88 @@ impl GitHubClient {
         comment_id: i64,
         body: &str,
     ) -> Result<()> {
-        let url = format!(
-            "https://api.github.com/repos/{}/{}/issues/comments/{}",
+        let url = self.api.url(&format!(
+            "/repos/{}/{}/issues/comments/{}",
             owner, repo, comment_id
-        );
+        ));
 
-        let response = self
-            .http_client
-            .patch(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .header("Accept", "application/vnd.github+json")
-            .header("User-Agent", "catapult")
-            .header("X-GitHub-Api-Version", "2022-11-28")
-            .json(&CreateCommentRequest {
-                body: body.to_string(),
-            })
-            .send()
+        let response = send_with_retry(
+            self.http_client
+                .patch(&url)
+                .header("Authorization", format!("Bearer {}", self.token))
+                .header("Accept", "application/vnd.github+json")
+                .header("User-Agent", self.api.user_agent())
+                .header("X-GitHub-Api-Version", "2022-11-28")
+                .json(&CreateCommentRequest {
+                    body: body.to_string(),
+                }),
+        )
+        .await
+        .context("Failed to update comment")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("GitHub API error {}: {}", status, body);
+        }
+
+        Ok(())
+    }
+
+    /// Get an issue comment
+    pub async fn get_comment(
+        &self,
+        owner: &str,
+        repo: &str,
+        comment_id: i64,
+    ) -> Result<CommentResponse> {
+        let url = self.api.url(&format!(
+            "/repos/{}/{}/issues/comments/{}",
+            owner, repo, comment_id
+        ));
+
+        let response = send_with_retry(
+            self.http_client
+                .get(&url)
+                .header("Authorization", format!("Bearer {}", self.token))
+                .header("Accept", "application/vnd.github+json")
+                .header("User-Agent", self.api.user_agent())
+                .header("X-GitHub-Api-Version", "2022-11-28"),
+        )
+        .await
+        .context("Failed to get comment")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("GitHub API error {}: {}", status, body);
+        }
+
+        response
+            .json()
             .await
-            .context("Failed to update comment")?;
+            .context("Failed to parse comment response")
+    }
+
+    /// Minimize (hide) a comment as outdated
+    ///
+    /// Minimizing is only available through the GraphQL API, which needs the
+    /// comment's node ID rather than its REST ID.
+    pub async fn minimize_comment(&self, owner: &str, repo: &str, comment_id: i64) -> Result<()> {
+        let comment = self.get_comment(owner, repo, comment_id).await?;
+        if comment.node_id.is_empty() {
+            anyhow::bail!("Comment {} has no node ID", comment_id);
+        }
+
+        let response = send_with_retry(
+            self.http_client
+                .post(self.api.graphql_url())
+                .header("Authorization", format!("Bearer {}", self.token))
+                .header("User-Agent", self.api.user_agent())
+                .json(&minimize_comment_request(&comment.node_id)),
+        )
+        .await
+        .context("Failed to minimize comment")?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -98,40 +392,538 @@ impl GitHubClient {
             anyhow::bail!("GitHub API error {}: {}", status, body);
         }
 
+        // GraphQL reports failures in the body with a 200 status
+        let body: GraphQlResponse = response
+            .json()
+            .await
+            .context("Failed to parse GraphQL response")?;
+        if let Some(error) = body.errors.first() {
+            anyhow::bail!("GitHub GraphQL error: {}", error.message);
+        }
+
         Ok(())
     }
 
     /// Generate a "Building..." comment body
     pub fn building_comment(commit_sha: &str) -> String {
+        Self::progress_comment(commit_sha, 0)
+    }
+
+    /// Generate an in-progress comment body with the first `completed` steps done
+    pub fn progress_comment(commit_sha: &str, completed: usize) -> String {
+        let steps: String = PROGRESS_STEPS
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let icon = match i.cmp(&completed) {
+                    std::cmp::Ordering::Less => "✅",
+                    std::cmp::Ordering::Equal => "⏳",
+                    std::cmp::Ordering::Greater => "⬜",
+                };
+                format!("- {} {}\n", icon, step)
+            })
+            .collect();
+
         format!(
             "🚀 **Deployment in progress**\n\n\
              Building commit `{}`...\n\n\
+             {}\n\
              _This comment will be updated when the deployment completes._",
-            &commit_sha[..7.min(commit_sha.len())]
+            short_sha(commit_sha),
+            steps
+        )
+    }
+
+    /// Generate a comment body for a deployment rejected by an org quota
+    pub fn quota_exceeded_comment(commit_sha: &str, reason: &str) -> String {
+        format!(
+            "⛔ **Deployment skipped**\n\n\
+             Commit `{}` was not deployed: {}.\n\n\
+             _Close other PRs with previews or ask an administrator to raise the quota, \
+             then push again._",
+            short_sha(commit_sha),
+            reason
         )
     }
 
     /// Generate a success comment body
-    pub fn success_comment(commit_sha: &str, deployed_url: &str) -> String {
+    pub fn success_comment(build: &CommentContext, deployed_url: &str) -> String {
         format!(
             "✅ **Deployment successful**\n\n\
              Commit `{}` has been deployed.\n\n\
              🔗 **Preview URL:** {}\n\n\
-             _This deployment will be automatically cleaned up when the PR is closed._",
-            &commit_sha[..7.min(commit_sha.len())],
-            deployed_url
+             _This deployment will be automatically cleaned up when the PR is closed._{}",
+            short_sha(build.commit_sha),
+            deployed_url,
+            comment_footer(build)
+        )
+    }
+
+    /// Generate a comment body for a dry run whose build succeeded
+    pub fn dry_run_comment(build: &CommentContext) -> String {
+        format!(
+            "🧪 **Build verified (dry run)**\n\n\
+             Commit `{}` built successfully. Nothing was deployed.{}",
+            short_sha(build.commit_sha),
+            comment_footer(build)
         )
     }
 
     /// Generate a failure comment body
-    pub fn failure_comment(commit_sha: &str, error: &str) -> String {
+    pub fn failure_comment(build: &CommentContext, error: &str) -> String {
         format!(
             "❌ **Deployment failed**\n\n\
              Failed to deploy commit `{}`.\n\n\
              **Error:**\n```\n{}\n```\n\n\
-             _Please check the build logs for more details._",
-            &commit_sha[..7.min(commit_sha.len())],
-            error
+             _Please check the build logs for more details._{}",
+            short_sha(build.commit_sha),
+            error,
+            comment_footer(build)
+        )
+    }
+
+    /// Generate a comment body for a preview removed after reaching its maximum age
+    pub fn preview_expired_comment(commit_sha: &str, max_age: Duration) -> String {
+        format!(
+            "⌛ **Preview expired**\n\n\
+             The preview of commit `{}` was removed after {}.\n\n\
+             _Push a new commit to redeploy it._",
+            short_sha(commit_sha),
+            format_duration(max_age)
+        )
+    }
+
+    /// Generate a cancellation comment body
+    pub fn cancelled_comment(build: &CommentContext) -> String {
+        format!(
+            "⏹️ **Deployment cancelled**\n\n\
+             The deployment of commit `{}` was cancelled before it went live.{}",
+            short_sha(build.commit_sha),
+            comment_footer(build)
         )
     }
 }
+
+/// The build a deployment result comment describes
+#[derive(Debug, Clone)]
+pub struct CommentContext<'a> {
+    pub org: &'a str,
+    pub repo: &'a str,
+    pub commit_sha: &'a str,
+    /// Time from the worker starting the build to its final status
+    pub duration: Option<Duration>,
+    /// Append a footer with the duration and a link to the commit
+    pub footer: bool,
+}
+
+fn short_sha(commit_sha: &str) -> &str {
+    &commit_sha[..7.min(commit_sha.len())]
+}
+
+/// Footer with the build duration (when known) and a commit link
+fn comment_footer(build: &CommentContext) -> String {
+    if !build.footer {
+        return String::new();
+    }
+
+    let commit = format!(
+        "[`{}`]({})",
+        short_sha(build.commit_sha),
+        commit_url(build.org, build.repo, build.commit_sha)
+    );
+    match build.duration {
+        Some(duration) => format!(
+            "\n\n---\n⏱️ Built in {} · {}",
+            format_duration(duration),
+            commit
+        ),
+        None => format!("\n\n---\n{}", commit),
+    }
+}
+
+/// GitHub web URL of a commit
+pub fn commit_url(org: &str, repo: &str, commit_sha: &str) -> String {
+    format!("https://github.com/{}/{}/commit/{}", org, repo, commit_sha)
+}
+
+/// Human-readable duration, e.g. "45s", "3m 07s", "1h 02m"
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_api_urls() {
+        let public = GitHubApi::default();
+        assert_eq!(
+            public.url("/repos/nullisLabs/website/issues/42/comments"),
+            "https://api.github.com/repos/nullisLabs/website/issues/42/comments"
+        );
+        assert_eq!(public.graphql_url(), "https://api.github.com/graphql");
+        assert!(public.user_agent().starts_with("catapult/"));
+
+        let enterprise = GitHubApi::new("https://ghe.example.com/api/v3/", "catapult");
+        assert_eq!(
+            enterprise.url("/app/installations/1/access_tokens"),
+            "https://ghe.example.com/api/v3/app/installations/1/access_tokens"
+        );
+        assert_eq!(
+            enterprise.graphql_url(),
+            "https://ghe.example.com/api/graphql"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_uses_api_base() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/repos/nullisLabs/website/issues/42/comments"))
+            .and(header("User-Agent", "catapult (ops@nullislabs.io)"))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .set_body_json(serde_json::json!({"id": 7, "node_id": "IC_7"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GitHubClient::new(
+            reqwest::Client::new(),
+            GitHubApi::new(
+                &format!("{}/api/v3", server.uri()),
+                "catapult (ops@nullislabs.io)",
+            ),
+            "token".to_string(),
+        );
+        let comment = client
+            .create_pr_comment("nullisLabs", "website", 42, "Building...")
+            .await
+            .unwrap();
+        assert_eq!(comment.id, 7);
+    }
+
+    #[test]
+    fn test_rate_limit_wait() {
+        let headers = |pairs: &[(&'static str, &str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.insert(*name, value.parse().unwrap());
+            }
+            headers
+        };
+
+        // Secondary rate limits say how long to back off
+        assert_eq!(
+            rate_limit_wait(
+                StatusCode::FORBIDDEN,
+                &headers(&[("retry-after", "30")]),
+                1000
+            ),
+            Some(Duration::from_secs(30))
+        );
+        // Primary rate limits say when the quota resets
+        assert_eq!(
+            rate_limit_wait(
+                StatusCode::FORBIDDEN,
+                &headers(&[
+                    ("x-ratelimit-remaining", "0"),
+                    ("x-ratelimit-reset", "1045")
+                ]),
+                1000
+            ),
+            Some(Duration::from_secs(45))
+        );
+        assert_eq!(
+            rate_limit_wait(
+                StatusCode::TOO_MANY_REQUESTS,
+                &headers(&[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "900")]),
+                1000
+            ),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            rate_limit_wait(StatusCode::TOO_MANY_REQUESTS, &headers(&[]), 1000),
+            Some(DEFAULT_RATE_LIMIT_WAIT)
+        );
+
+        // A 403 with quota left is a permission error
+        assert_eq!(
+            rate_limit_wait(
+                StatusCode::FORBIDDEN,
+                &headers(&[("x-ratelimit-remaining", "4999")]),
+                1000
+            ),
+            None
+        );
+        assert_eq!(
+            rate_limit_wait(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &headers(&[("retry-after", "1")]),
+                1000
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_comment_retried_after_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/repos/nullisLabs/website/issues/42/comments"))
+            .respond_with(ResponseTemplate::new(403).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/repos/nullisLabs/website/issues/42/comments"))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .set_body_json(serde_json::json!({"id": 7, "node_id": "IC_7"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GitHubClient::new(
+            reqwest::Client::new(),
+            GitHubApi::new(&server.uri(), "catapult-test"),
+            "token".to_string(),
+        );
+        let comment = client
+            .create_pr_comment("nullisLabs", "website", 42, "Building...")
+            .await
+            .unwrap();
+        assert_eq!(comment.id, 7);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_status_context() {
+        assert_eq!(status_context("catapult", "preview"), "catapult");
+        assert_eq!(
+            status_context("catapult/{environment}", "production"),
+            "catapult/production"
+        );
+        assert_eq!(
+            status_context("deploy ({environment})", "preview"),
+            "deploy (preview)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_commit_status() {
+        use wiremock::matchers::body_json;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/repos/nullisLabs/website/statuses/abc1234"))
+            .and(body_json(serde_json::json!({
+                "state": "success",
+                "target_url": "https://pr-42.nullislabs.io",
+                "description": "Deployed",
+                "context": "catapult/preview",
+            })))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GitHubClient::new(
+            reqwest::Client::new(),
+            GitHubApi::new(&server.uri(), "catapult-test"),
+            "token".to_string(),
+        );
+        client
+            .create_commit_status(
+                "nullisLabs",
+                "website",
+                "abc1234",
+                &CommitStatus {
+                    state: CommitStatusState::Success,
+                    target_url: Some("https://pr-42.nullislabs.io"),
+                    description: "Deployed",
+                    context: &status_context("catapult/{environment}", "preview"),
+                },
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_retries_bounded() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .mount(&server)
+            .await;
+
+        let client = GitHubClient::new(
+            reqwest::Client::new(),
+            GitHubApi::new(&server.uri(), "catapult-test"),
+            "token".to_string(),
+        );
+        let error = client
+            .update_comment("nullisLabs", "website", 7, "Done")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("429"));
+        assert_eq!(
+            server.received_requests().await.unwrap().len(),
+            1 + RATE_LIMIT_RETRIES as usize
+        );
+
+        // Waits beyond the limit aren't worth holding the request for
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .respond_with(ResponseTemplate::new(403).insert_header("retry-after", "3600"))
+            .mount(&server)
+            .await;
+        let client = GitHubClient::new(
+            reqwest::Client::new(),
+            GitHubApi::new(&server.uri(), "catapult-test"),
+            "token".to_string(),
+        );
+        assert!(
+            client
+                .update_comment("nullisLabs", "website", 7, "Done")
+                .await
+                .is_err()
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_minimize_comment_request() {
+        let request = minimize_comment_request("IC_kwDOABCD1234");
+
+        assert_eq!(request["variables"]["id"], "IC_kwDOABCD1234");
+        assert_eq!(request["variables"]["classifier"], "OUTDATED");
+
+        let query = request["query"].as_str().unwrap();
+        assert!(query.starts_with("mutation("));
+        assert!(
+            query.contains("minimizeComment(input: {subjectId: $id, classifier: $classifier})")
+        );
+    }
+
+    #[test]
+    fn test_graphql_errors_parsed() {
+        let body: GraphQlResponse = serde_json::from_str(
+            r#"{"data": null, "errors": [{"type": "FORBIDDEN", "message": "Resource not accessible by integration"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            body.errors[0].message,
+            "Resource not accessible by integration"
+        );
+
+        let body: GraphQlResponse = serde_json::from_str(
+            r#"{"data": {"minimizeComment": {"minimizedComment": {"isMinimized": true}}}}"#,
+        )
+        .unwrap();
+        assert!(body.errors.is_empty());
+    }
+
+    fn build(duration: Option<Duration>, footer: bool) -> CommentContext<'static> {
+        CommentContext {
+            org: "nullisLabs",
+            repo: "website",
+            commit_sha: "abc1234def5678",
+            duration,
+            footer,
+        }
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "0s");
+        assert_eq!(format_duration(Duration::from_millis(45_900)), "45s");
+        assert_eq!(format_duration(Duration::from_secs(187)), "3m 07s");
+        assert_eq!(format_duration(Duration::from_secs(3600)), "1h 00m");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1h 02m");
+    }
+
+    #[test]
+    fn test_commit_url() {
+        assert_eq!(
+            commit_url("nullisLabs", "website", "abc1234def5678"),
+            "https://github.com/nullisLabs/website/commit/abc1234def5678"
+        );
+    }
+
+    #[test]
+    fn test_comment_footer() {
+        let body = GitHubClient::success_comment(
+            &build(Some(Duration::from_secs(83)), true),
+            "https://pr-42.nullislabs.io",
+        );
+        assert!(body.ends_with(
+            "⏱️ Built in 1m 23s · [`abc1234`](https://github.com/nullisLabs/website/commit/abc1234def5678)"
+        ));
+
+        // Without a duration the commit link remains
+        let body = GitHubClient::failure_comment(&build(None, true), "build failed");
+        assert!(body.ends_with(
+            "\n---\n[`abc1234`](https://github.com/nullisLabs/website/commit/abc1234def5678)"
+        ));
+        assert!(!body.contains("Built in"));
+
+        let body = GitHubClient::failure_comment(&build(Some(Duration::from_secs(5)), false), "x");
+        assert!(body.ends_with("_Please check the build logs for more details._"));
+    }
+
+    #[test]
+    fn test_progress_comment() {
+        let body = GitHubClient::building_comment("abc1234def5678");
+        assert!(body.starts_with("🚀 **Deployment in progress**"));
+        assert!(body.contains("- ⏳ Clone\n- ⬜ Build\n- ⬜ Deploy\n- ⬜ Route traffic\n"));
+
+        let body = GitHubClient::progress_comment("abc1234def5678", 3);
+        assert!(body.contains("- ✅ Clone\n- ✅ Build\n- ✅ Deploy\n- ⏳ Route traffic\n"));
+    }
+
+    #[test]
+    fn test_cancelled_comment() {
+        let body = GitHubClient::cancelled_comment(&build(None, false));
+        assert!(body.starts_with("⏹️ **Deployment cancelled**"));
+        assert!(body.contains("commit `abc1234` was cancelled"));
+    }
+
+    #[test]
+    fn test_dry_run_comment() {
+        let body = GitHubClient::dry_run_comment(&build(None, false));
+        assert!(body.starts_with("🧪 **Build verified (dry run)**"));
+        assert!(body.ends_with("Commit `abc1234` built successfully. Nothing was deployed."));
+    }
+
+    #[test]
+    fn test_preview_expired_comment() {
+        let body =
+            GitHubClient::preview_expired_comment("abc1234def5678", Duration::from_secs(7 * 86400));
+        assert!(body.starts_with("⌛ **Preview expired**"));
+        assert!(body.contains("commit `abc1234` was removed after"));
+        assert!(body.ends_with("_Push a new commit to redeploy it._"));
+    }
+
+    #[test]
+    fn test_quota_exceeded_comment() {
+        let body = GitHubClient::quota_exceeded_comment(
+            "abc1234def5678",
+            "the organization already has 5 of 5 active deployments",
+        );
+        assert!(body.starts_with("⛔ **Deployment skipped**"));
+        assert!(body.contains(
+            "Commit `abc1234` was not deployed: the organization already has 5 of 5 active deployments."
+        ));
+    }
+}
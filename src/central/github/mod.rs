@@ -2,6 +2,12 @@ pub mod api;
 pub mod app;
 pub mod webhook;
 
-pub use api::GitHubClient;
-pub use app::GitHubApp;
-pub use webhook::{PullRequestAction, WebhookEvent, parse_webhook_event, verify_webhook_signature};
+pub use api::{
+    CommentContext, CommitStatus, CommitStatusState, DEFAULT_API_BASE, DEFAULT_STATUS_CONTEXT,
+    DEFAULT_USER_AGENT, GitHubApi, GitHubClient, status_context,
+};
+pub use app::{GitHubApp, GitHubApps};
+pub use webhook::{
+    EventSupport, InstallationAction, PullRequestAction, PullRequestEvent, WebhookEvent,
+    event_support, parse_webhook_event, verify_webhook_signature,
+};
@@ -11,10 +11,25 @@ pub fn verify_webhook_signature(secret: &str, payload: &[u8], signature: &str) -
 pub enum WebhookEvent {
     PullRequest(PullRequestEvent),
     Push(PushEvent),
+    Installation(InstallationEvent),
+    Repository(RepositoryEvent),
     Ping,
     Unknown(String),
 }
 
+impl WebhookEvent {
+    /// Installation the event was delivered for
+    pub fn installation_id(&self) -> Option<u64> {
+        match self {
+            WebhookEvent::PullRequest(event) => event.installation.as_ref().map(|i| i.id),
+            WebhookEvent::Push(event) => event.installation.as_ref().map(|i| i.id),
+            WebhookEvent::Installation(event) => Some(event.installation.id),
+            WebhookEvent::Repository(event) => event.installation.as_ref().map(|i| i.id),
+            WebhookEvent::Ping | WebhookEvent::Unknown(_) => None,
+        }
+    }
+}
+
 /// Pull request event payload
 #[derive(Debug, Clone, Deserialize)]
 pub struct PullRequestEvent {
@@ -23,6 +38,9 @@ pub struct PullRequestEvent {
     pub pull_request: PullRequest,
     pub repository: Repository,
     pub installation: Option<Installation>,
+    /// Label added or removed (`labeled`/`unlabeled` actions only)
+    #[serde(default)]
+    pub label: Option<Label>,
 }
 
 /// Pull request action type
@@ -33,6 +51,9 @@ pub enum PullRequestAction {
     Synchronize,
     Closed,
     Reopened,
+    Labeled,
+    Unlabeled,
+    ReadyForReview,
     #[serde(other)]
     Other,
 }
@@ -43,6 +64,32 @@ pub struct PullRequest {
     pub head: PullRequestHead,
     #[allow(dead_code)]
     pub merged: Option<bool>,
+    /// Whether the pull request is still a draft
+    #[serde(default)]
+    pub draft: bool,
+    /// Labels currently on the pull request
+    #[serde(default)]
+    pub labels: Vec<Label>,
+}
+
+impl PullRequest {
+    /// Check if the pull request carries a label (case-insensitive, like GitHub)
+    pub fn has_label(&self, name: &str) -> bool {
+        self.labels.iter().any(|label| label.is(name))
+    }
+}
+
+/// Issue/pull request label
+#[derive(Debug, Clone, Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+impl Label {
+    /// Check if this is the named label (case-insensitive, like GitHub)
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
 }
 
 /// Pull request head (source branch)
@@ -75,6 +122,58 @@ impl PushEvent {
     }
 }
 
+/// Installation event payload (app installed, uninstalled, suspended)
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstallationEvent {
+    pub action: InstallationAction,
+    pub installation: InstallationAccount,
+}
+
+/// Installation action type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallationAction {
+    Created,
+    Deleted,
+    Suspend,
+    Unsuspend,
+    #[serde(other)]
+    Other,
+}
+
+/// Installation details including the account it belongs to
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstallationAccount {
+    pub id: u64,
+    pub account: RepositoryOwner,
+}
+
+/// Repository event payload (created, deleted, archived, ...)
+#[derive(Debug, Clone, Deserialize)]
+pub struct RepositoryEvent {
+    pub action: RepositoryAction,
+    pub repository: Repository,
+    pub installation: Option<Installation>,
+}
+
+/// Repository action type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositoryAction {
+    Deleted,
+    Archived,
+    Unarchived,
+    #[serde(other)]
+    Other,
+}
+
+impl RepositoryAction {
+    /// Whether this action means the repository's deployments should be torn down
+    pub fn is_removal(&self) -> bool {
+        matches!(self, RepositoryAction::Deleted | RepositoryAction::Archived)
+    }
+}
+
 /// Repository information
 #[derive(Debug, Clone, Deserialize)]
 pub struct Repository {
@@ -90,6 +189,87 @@ impl Repository {
     pub fn org_name(&self) -> &str {
         &self.owner.login
     }
+
+    /// The clone URL in canonical HTTPS form, checked against the GitHub host
+    pub fn https_clone_url(&self, github_host: &str) -> Result<String, CloneUrlError> {
+        normalize_clone_url(&self.clone_url, github_host)
+    }
+}
+
+/// A repository clone URL that can't be used to build from
+#[derive(Debug, thiserror::Error)]
+pub enum CloneUrlError {
+    #[error("unsupported clone URL '{0}'")]
+    Unsupported(String),
+    #[error("clone URL host '{host}' does not match GitHub host '{expected}'")]
+    HostMismatch { host: String, expected: String },
+    #[error("clone URL '{0}' is not an owner/repository path")]
+    InvalidPath(String),
+}
+
+/// Convert a clone URL to `https://{github_host}/{owner}/{repo}.git`
+///
+/// Accepts HTTPS, `git://`, `ssh://` and scp-like (`git@host:owner/repo`)
+/// forms. The host must match `github_host` (including any port, for HTTPS).
+pub fn normalize_clone_url(clone_url: &str, github_host: &str) -> Result<String, CloneUrlError> {
+    let unsupported = || CloneUrlError::Unsupported(clone_url.to_string());
+
+    let (host, path) = match url::Url::parse(clone_url) {
+        Ok(url) => {
+            let host = url.host_str().ok_or_else(unsupported)?;
+            let host = match (url.scheme(), url.port()) {
+                ("https", Some(port)) => format!("{}:{}", host, port),
+                ("https" | "git" | "ssh", _) => host.to_string(),
+                _ => return Err(unsupported()),
+            };
+            // Credentials never belong in a clone URL from GitHub
+            if url.scheme() != "ssh" && (!url.username().is_empty() || url.password().is_some()) {
+                return Err(unsupported());
+            }
+            if url.query().is_some() || url.fragment().is_some() {
+                return Err(unsupported());
+            }
+            (host, url.path().to_string())
+        }
+        // scp-like SSH syntax: git@github.com:owner/repo.git
+        Err(_) => {
+            let (user_host, path) = clone_url.split_once(':').ok_or_else(unsupported)?;
+            let host = user_host
+                .rsplit_once('@')
+                .map_or(user_host, |(_, host)| host);
+            if host.is_empty() || host.contains('/') {
+                return Err(unsupported());
+            }
+            (host.to_string(), path.to_string())
+        }
+    };
+
+    if !host.eq_ignore_ascii_case(github_host) {
+        return Err(CloneUrlError::HostMismatch {
+            host,
+            expected: github_host.to_string(),
+        });
+    }
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let valid_segment = |segment: &str| {
+        !segment.is_empty()
+            && segment != "."
+            && segment != ".."
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    match path.split_once('/') {
+        Some((owner, repo)) if valid_segment(owner) && valid_segment(repo) => Ok(format!(
+            "https://{}/{}/{}.git",
+            github_host.to_ascii_lowercase(),
+            owner,
+            repo
+        )),
+        _ => Err(CloneUrlError::InvalidPath(clone_url.to_string())),
+    }
 }
 
 /// Repository owner
@@ -104,6 +284,38 @@ pub struct Installation {
     pub id: u64,
 }
 
+/// How the webhook endpoint treats an `X-GitHub-Event` type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventSupport {
+    /// Parsed and processed
+    Handled,
+    /// Acknowledged without being parsed (events an app may be subscribed to
+    /// that never affect deployments)
+    Ignored,
+    /// Rejected
+    Unsupported,
+}
+
+/// Classify a webhook event type
+pub fn event_support(event_type: &str) -> EventSupport {
+    match event_type {
+        "pull_request" | "push" | "installation" | "repository" | "ping" => EventSupport::Handled,
+        "installation_repositories"
+        | "installation_target"
+        | "github_app_authorization"
+        | "meta"
+        | "create"
+        | "delete"
+        | "check_run"
+        | "check_suite"
+        | "status"
+        | "pull_request_review"
+        | "pull_request_review_comment"
+        | "issue_comment" => EventSupport::Ignored,
+        _ => EventSupport::Unsupported,
+    }
+}
+
 /// Parse a webhook event from the event type and payload
 pub fn parse_webhook_event(
     event_type: &str,
@@ -118,6 +330,14 @@ pub fn parse_webhook_event(
             let event: PushEvent = serde_json::from_slice(payload)?;
             Ok(WebhookEvent::Push(event))
         }
+        "installation" => {
+            let event: InstallationEvent = serde_json::from_slice(payload)?;
+            Ok(WebhookEvent::Installation(event))
+        }
+        "repository" => {
+            let event: RepositoryEvent = serde_json::from_slice(payload)?;
+            Ok(WebhookEvent::Repository(event))
+        }
         "ping" => Ok(WebhookEvent::Ping),
         other => Ok(WebhookEvent::Unknown(other.to_string())),
     }
@@ -127,6 +347,15 @@ pub fn parse_webhook_event(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_event_support() {
+        assert_eq!(event_support("pull_request"), EventSupport::Handled);
+        assert_eq!(event_support("ping"), EventSupport::Handled);
+        assert_eq!(event_support("check_suite"), EventSupport::Ignored);
+        assert_eq!(event_support("fork"), EventSupport::Unsupported);
+        assert_eq!(event_support(""), EventSupport::Unsupported);
+    }
+
     #[test]
     fn test_parse_pull_request_event() {
         let payload = r#"{
@@ -157,6 +386,7 @@ mod tests {
             WebhookEvent::PullRequest(pr) => {
                 assert_eq!(pr.action, PullRequestAction::Opened);
                 assert_eq!(pr.number, 42);
+                assert!(!pr.pull_request.draft);
                 assert_eq!(pr.pull_request.head.branch, "feature-branch");
                 assert_eq!(pr.repository.org_name(), "nullisLabs");
             }
@@ -164,6 +394,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_labeled_event() {
+        let payload = r#"{
+            "action": "labeled",
+            "number": 42,
+            "label": { "name": "preview" },
+            "pull_request": {
+                "head": {
+                    "ref": "feature-branch",
+                    "sha": "abc123"
+                },
+                "labels": [{ "name": "bug" }, { "name": "Preview" }]
+            },
+            "repository": {
+                "name": "website",
+                "full_name": "nullisLabs/website",
+                "clone_url": "https://github.com/nullisLabs/website.git",
+                "owner": {
+                    "login": "nullisLabs"
+                }
+            }
+        }"#;
+
+        let event = parse_webhook_event("pull_request", payload.as_bytes()).unwrap();
+        match event {
+            WebhookEvent::PullRequest(pr) => {
+                assert_eq!(pr.action, PullRequestAction::Labeled);
+                assert!(pr.label.unwrap().is("preview"));
+                assert!(pr.pull_request.has_label("preview"));
+                assert!(!pr.pull_request.has_label("wip"));
+            }
+            _ => panic!("Expected PullRequest event"),
+        }
+    }
+
     #[test]
     fn test_parse_push_event() {
         let payload = r#"{
@@ -192,4 +457,165 @@ mod tests {
             _ => panic!("Expected Push event"),
         }
     }
+
+    #[test]
+    fn test_parse_installation_deleted_event() {
+        let payload = r#"{
+            "action": "deleted",
+            "installation": {
+                "id": 12345,
+                "account": {
+                    "login": "nullisLabs"
+                }
+            }
+        }"#;
+
+        let event = parse_webhook_event("installation", payload.as_bytes()).unwrap();
+        match event {
+            WebhookEvent::Installation(installation) => {
+                assert_eq!(installation.action, InstallationAction::Deleted);
+                assert_eq!(installation.installation.id, 12345);
+                assert_eq!(installation.installation.account.login, "nullisLabs");
+            }
+            _ => panic!("Expected Installation event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_repository_archived_event() {
+        let payload = r#"{
+            "action": "archived",
+            "repository": {
+                "name": "website",
+                "full_name": "nullisLabs/website",
+                "clone_url": "https://github.com/nullisLabs/website.git",
+                "owner": {
+                    "login": "nullisLabs"
+                }
+            },
+            "installation": {
+                "id": 12345
+            }
+        }"#;
+
+        let event = parse_webhook_event("repository", payload.as_bytes()).unwrap();
+        match event {
+            WebhookEvent::Repository(repo) => {
+                assert_eq!(repo.action, RepositoryAction::Archived);
+                assert!(repo.action.is_removal());
+                assert_eq!(repo.repository.name, "website");
+            }
+            _ => panic!("Expected Repository event"),
+        }
+    }
+
+    #[test]
+    fn test_repository_action_is_removal() {
+        assert!(RepositoryAction::Deleted.is_removal());
+        assert!(RepositoryAction::Archived.is_removal());
+        assert!(!RepositoryAction::Unarchived.is_removal());
+        assert!(!RepositoryAction::Other.is_removal());
+    }
+
+    #[test]
+    fn test_normalize_clone_url_github() {
+        let canonical = "https://github.com/nullisLabs/website.git";
+        for url in [
+            "https://github.com/nullisLabs/website.git",
+            "https://github.com/nullisLabs/website",
+            "https://GitHub.com/nullisLabs/website/",
+            "git://github.com/nullisLabs/website.git",
+            "ssh://git@github.com/nullisLabs/website.git",
+            "ssh://git@github.com:22/nullisLabs/website.git",
+            "git@github.com:nullisLabs/website.git",
+            "git@github.com:nullisLabs/website",
+        ] {
+            assert_eq!(
+                normalize_clone_url(url, "github.com").unwrap(),
+                canonical,
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_clone_url_enterprise() {
+        assert_eq!(
+            normalize_clone_url(
+                "https://ghe.example.com/platform/docs.git",
+                "ghe.example.com"
+            )
+            .unwrap(),
+            "https://ghe.example.com/platform/docs.git"
+        );
+        assert_eq!(
+            normalize_clone_url("git@ghe.example.com:platform/docs.git", "ghe.example.com")
+                .unwrap(),
+            "https://ghe.example.com/platform/docs.git"
+        );
+        assert_eq!(
+            normalize_clone_url(
+                "https://ghe.example.com:8443/platform/docs.git",
+                "ghe.example.com:8443"
+            )
+            .unwrap(),
+            "https://ghe.example.com:8443/platform/docs.git"
+        );
+
+        // An enterprise URL is rejected when Central expects github.com, and vice versa
+        assert!(matches!(
+            normalize_clone_url("https://ghe.example.com/platform/docs.git", "github.com"),
+            Err(CloneUrlError::HostMismatch { .. })
+        ));
+        assert!(matches!(
+            normalize_clone_url("https://github.com/platform/docs.git", "ghe.example.com"),
+            Err(CloneUrlError::HostMismatch { .. })
+        ));
+        assert!(matches!(
+            normalize_clone_url(
+                "https://ghe.example.com:9000/platform/docs.git",
+                "ghe.example.com:8443"
+            ),
+            Err(CloneUrlError::HostMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_normalize_clone_url_rejects_bad_shapes() {
+        for url in [
+            "http://github.com/nullisLabs/website.git",
+            "file:///srv/git/website.git",
+            "https://token@github.com/nullisLabs/website.git",
+            "https://github.com/nullisLabs/website.git?ref=main",
+            "website.git",
+            "",
+        ] {
+            assert!(
+                matches!(
+                    normalize_clone_url(url, "github.com"),
+                    Err(CloneUrlError::Unsupported(_))
+                ),
+                "{}",
+                url
+            );
+        }
+
+        for url in [
+            "https://github.com/nullisLabs",
+            "https://github.com/nullisLabs/website/extra.git",
+            "https://github.com/nullisLabs/../website.git",
+            "git@github.com:nullisLabs/web site.git",
+            "git@github.com:/website.git",
+        ] {
+            assert!(
+                matches!(
+                    normalize_clone_url(url, "github.com"),
+                    Err(CloneUrlError::InvalidPath(_))
+                ),
+                "{}",
+                url
+            );
+        }
+    }
 }
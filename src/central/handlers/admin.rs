@@ -1,13 +1,27 @@
 use axum::{
     Json,
-    extract::State,
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::central::db;
+use crate::central::admin_keys::{AdminIdentity, AdminKeys, AdminScope};
+use crate::central::db::{
+    self, AdminAction, AuthorizedOrg, DeploymentFilter, DeploymentHistory, DeploymentType,
+    NewDeployment,
+};
+use crate::central::deploy_config::fetch_deploy_config;
+use crate::central::dispatch::{dispatch_build_job, dispatch_cancel_job, dispatch_promote_job};
+use crate::central::handlers::webhook::cleanup_deployment;
+use crate::central::mirror::{collect_results, deployment_workers, dispatch_to_workers};
 use crate::central::server::AppState;
+use crate::shared::{
+    ArtifactSource, BuildJob, BuildTarget, CancelJob, DeployConfig, JobStatus, PromoteJob,
+    SiteType, generate_commit_site_id, generate_preview_url, generate_site_id,
+};
 
 /// Request to create/update an authorized org
 #[derive(Debug, Deserialize)]
@@ -15,6 +29,18 @@ pub struct UpsertAuthRequest {
     pub github_org: String,
     pub zones: Vec<String>,
     pub domain_patterns: Vec<String>,
+    /// Maximum active deployments (omitted is unlimited)
+    #[serde(default)]
+    pub max_active_deployments: Option<i32>,
+    /// Maximum total size of active deployments in MB (omitted is unlimited)
+    #[serde(default)]
+    pub max_total_mb: Option<i64>,
+    /// Repo name globs allowed to deploy (omitted allows every repo)
+    #[serde(default)]
+    pub repo_allowlist: Vec<String>,
+    /// Repo name globs never allowed to deploy, even if allowlisted
+    #[serde(default)]
+    pub repo_denylist: Vec<String>,
 }
 
 /// Request to delete an authorized org
@@ -23,6 +49,125 @@ pub struct DeleteAuthRequest {
     pub github_org: String,
 }
 
+/// Request to deploy a prebuilt artifact archive, skipping the build
+#[derive(Debug, Deserialize)]
+pub struct DeployArtifactRequest {
+    pub github_org: String,
+    pub github_repo: String,
+    pub zone: String,
+    pub domain: String,
+    pub commit_sha: String,
+    #[serde(default)]
+    pub pr_number: Option<u32>,
+    #[serde(default)]
+    pub precompress: bool,
+    #[serde(default)]
+    pub immutable_paths: Vec<String>,
+    pub artifact: ArtifactSource,
+}
+
+impl DeployArtifactRequest {
+    /// Check required fields, returning the first problem found
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.github_org.is_empty() || self.github_repo.is_empty() {
+            return Err("github_org and github_repo are required");
+        }
+        if self.zone.is_empty() || self.domain.is_empty() {
+            return Err("zone and domain are required");
+        }
+        if self.commit_sha.is_empty() {
+            return Err("commit_sha is required");
+        }
+        match url::Url::parse(&self.artifact.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            _ => return Err("artifact.url must be an http(s) URL"),
+        }
+        if let Some(sha256) = &self.artifact.sha256
+            && (sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()))
+        {
+            return Err("artifact.sha256 must be a hex SHA-256 digest");
+        }
+        Ok(())
+    }
+}
+
+/// Request to deploy a single commit at a throwaway hostname
+#[derive(Debug, Deserialize)]
+pub struct PreviewCommitRequest {
+    pub org: String,
+    pub repo: String,
+    /// Full 40 character commit SHA
+    pub commit_sha: String,
+    /// Hostname to serve the preview at (defaults to `commit-{sha}-{repo}.{domain}`)
+    #[serde(default)]
+    pub hostname: Option<String>,
+}
+
+impl PreviewCommitRequest {
+    /// Check required fields, returning the first problem found
+    fn validate(&self) -> Result<(), &'static str> {
+        // Names end up in the site ID, and so in a directory name on the worker
+        let valid_name = |name: &str| {
+            !name.is_empty()
+                && !name.starts_with('.')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        };
+        if !valid_name(&self.org) || !valid_name(&self.repo) {
+            return Err("org and repo must be valid GitHub names");
+        }
+        // Workers fetch the commit by SHA, which GitHub only allows unabbreviated
+        if self.commit_sha.len() != 40 || !self.commit_sha.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("commit_sha must be a full 40 character hex SHA");
+        }
+        if let Some(hostname) = &self.hostname
+            && (hostname.is_empty()
+                || !hostname
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.')))
+        {
+            return Err("hostname must be a valid DNS name");
+        }
+        Ok(())
+    }
+
+    /// Build job deploying the commit to `domain` with the repository's config
+    fn build_job(
+        &self,
+        job_id: Uuid,
+        git_token: String,
+        deploy_config: &DeployConfig,
+        domain: &str,
+        github_host: &str,
+        callback_base_url: &str,
+    ) -> BuildJob {
+        BuildJob::from_deploy_config(
+            BuildTarget {
+                job_id,
+                repo_url: format!("https://{}/{}/{}.git", github_host, self.org, self.repo),
+                git_token,
+                branch: String::new(), // Checked out by commit SHA
+                commit_sha: self.commit_sha.to_lowercase(),
+                pr_number: None,
+                site_id: Some(generate_commit_site_id(
+                    &self.org,
+                    &self.repo,
+                    &self.commit_sha,
+                )),
+                domain: domain.to_string(),
+                aliases: Vec::new(), // Aliases are for main deploys only
+                callback_url: format!("{}/api/status", callback_base_url),
+                repo_name: self.repo.clone(),
+                org_name: self.org.clone(),
+                environment: deploy_config.zone.clone(),
+                preview: true, // Protected like PR previews
+            },
+            deploy_config,
+        )
+    }
+}
+
 /// Response for authorized org
 #[derive(Debug, Serialize)]
 pub struct AuthorizedOrgResponse {
@@ -31,6 +176,10 @@ pub struct AuthorizedOrgResponse {
     pub zones: Vec<String>,
     pub domain_patterns: Vec<String>,
     pub enabled: bool,
+    pub max_active_deployments: Option<i32>,
+    pub max_total_mb: Option<i64>,
+    pub repo_allowlist: Vec<String>,
+    pub repo_denylist: Vec<String>,
 }
 
 impl From<db::AuthorizedOrg> for AuthorizedOrgResponse {
@@ -41,21 +190,170 @@ impl From<db::AuthorizedOrg> for AuthorizedOrgResponse {
             zones: org.zones,
             domain_patterns: org.domain_patterns,
             enabled: org.enabled,
+            max_active_deployments: org.max_active_deployments,
+            max_total_mb: org.max_total_mb,
+            repo_allowlist: org.repo_allowlist,
+            repo_denylist: org.repo_denylist,
+        }
+    }
+}
+
+/// Response for a worker
+#[derive(Debug, Serialize)]
+pub struct WorkerResponse {
+    pub environment: String,
+    pub endpoint: String,
+    pub enabled: bool,
+    pub mirror: bool,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub health: db::WorkerHealth,
+}
+
+impl WorkerResponse {
+    fn from_worker(worker: db::Worker, now: DateTime<Utc>) -> Self {
+        Self {
+            health: worker.health(now),
+            environment: worker.environment,
+            endpoint: worker.endpoint,
+            enabled: worker.enabled,
+            mirror: worker.mirror,
+            last_seen: worker.last_seen,
+        }
+    }
+}
+
+/// Response for a deployment with its lifecycle timeline
+#[derive(Debug, Serialize)]
+pub struct DeploymentResponse {
+    pub job_id: Uuid,
+    pub github_org: String,
+    pub github_repo: String,
+    pub pr_number: Option<i32>,
+    pub site_id: String,
+    pub zone: String,
+    pub domain: String,
+    pub commit_sha: String,
+    pub deployment_type: String,
+    pub dry_run: bool,
+    pub status: String,
+    pub size_bytes: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub events: Vec<DeploymentEventResponse>,
+}
+
+/// A single lifecycle event in a deployment timeline
+#[derive(Debug, Serialize)]
+pub struct DeploymentEventResponse {
+    pub phase: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DeploymentResponse {
+    fn new(deployment: db::DeploymentHistory, events: Vec<db::DeploymentEvent>) -> Self {
+        Self {
+            job_id: deployment.job_id,
+            github_org: deployment.github_org,
+            github_repo: deployment.github_repo,
+            pr_number: deployment.pr_number,
+            site_id: deployment.site_id,
+            zone: deployment.zone,
+            domain: deployment.domain,
+            commit_sha: deployment.commit_sha,
+            deployment_type: deployment.deployment_type,
+            dry_run: deployment.dry_run,
+            status: deployment.status,
+            size_bytes: deployment.size_bytes,
+            created_at: deployment.created_at,
+            updated_at: deployment.updated_at,
+            events: events
+                .into_iter()
+                .map(|event| DeploymentEventResponse {
+                    phase: event.phase,
+                    created_at: event.created_at,
+                })
+                .collect(),
         }
     }
 }
 
-/// Verify admin API key from Authorization header
-fn verify_admin_key(headers: &HeaderMap, expected_key: &str) -> bool {
+/// Record a successful admin action in the audit log
+///
+/// The action has already taken effect, so failing to record it is logged
+/// rather than returned.
+async fn audit(
+    state: &AppState,
+    admin: &AdminIdentity,
+    action: AdminAction,
+    target: &str,
+    payload: Option<serde_json::Value>,
+) {
+    if let Err(e) =
+        db::record_admin_action(&state.db, &admin.name, action, target, payload.as_ref()).await
+    {
+        tracing::error!(
+            error = %e,
+            actor = %admin.name,
+            action = %action,
+            target,
+            "Failed to record admin action"
+        );
+    }
+}
+
+/// Identify the admin key in the Authorization header
+fn verify_admin_key(headers: &HeaderMap, keys: &AdminKeys) -> Option<AdminIdentity> {
     headers
         .get("authorization")
         .and_then(|v| v.to_str().ok())
-        .map(|v| {
+        .and_then(|v| {
             // Support both "Bearer <key>" and raw "<key>"
             let key = v.strip_prefix("Bearer ").unwrap_or(v);
-            key == expected_key
+            keys.identify(key)
         })
-        .unwrap_or(false)
+}
+
+/// Why an admin request was refused
+#[derive(Debug, PartialEq, Eq)]
+enum AdminAuthError {
+    /// No key, or not one of ours
+    InvalidKey,
+    /// A read-only key on a mutating endpoint
+    ReadOnly,
+}
+
+impl IntoResponse for AdminAuthError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::InvalidKey => (
+                StatusCode::UNAUTHORIZED,
+                Json(serde_json::json!({"error": "Invalid or missing API key"})),
+            )
+                .into_response(),
+            Self::ReadOnly => (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({"error": "API key is read-only"})),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Authorize an admin request needing `scope`
+fn authorize_admin(
+    headers: &HeaderMap,
+    state: &AppState,
+    scope: AdminScope,
+) -> Result<AdminIdentity, AdminAuthError> {
+    let admin =
+        verify_admin_key(headers, &state.config.admin_keys).ok_or(AdminAuthError::InvalidKey)?;
+
+    if !admin.scope.allows(scope) {
+        tracing::warn!(actor = %admin.name, "Read-only admin key used for a mutating endpoint");
+        return Err(AdminAuthError::ReadOnly);
+    }
+
+    Ok(admin)
 }
 
 /// List all authorized organizations
@@ -63,12 +361,8 @@ pub async fn list_authorized_orgs(
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    if !verify_admin_key(&headers, &state.config.admin_api_key) {
-        return (
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({"error": "Invalid or missing API key"})),
-        )
-            .into_response();
+    if let Err(e) = authorize_admin(&headers, &state, AdminScope::Read) {
+        return e.into_response();
     }
 
     match db::list_authorized_orgs(&state.db).await {
@@ -93,13 +387,10 @@ pub async fn upsert_authorized_org(
     headers: HeaderMap,
     Json(request): Json<UpsertAuthRequest>,
 ) -> impl IntoResponse {
-    if !verify_admin_key(&headers, &state.config.admin_api_key) {
-        return (
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({"error": "Invalid or missing API key"})),
-        )
-            .into_response();
-    }
+    let admin = match authorize_admin(&headers, &state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
 
     // Validate request
     if request.github_org.is_empty() {
@@ -123,12 +414,42 @@ pub async fn upsert_authorized_org(
         )
             .into_response();
     }
+    if request.max_active_deployments.is_some_and(|max| max < 0)
+        || request.max_total_mb.is_some_and(|max| max < 0)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Quotas must not be negative"})),
+        )
+            .into_response();
+    }
+
+    if request
+        .repo_allowlist
+        .iter()
+        .chain(&request.repo_denylist)
+        .any(|pattern| pattern.trim().is_empty())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Repo patterns must not be empty"})),
+        )
+            .into_response();
+    }
 
     match db::upsert_authorized_org(
         &state.db,
         &request.github_org,
         &request.zones,
         &request.domain_patterns,
+        db::OrgQuota {
+            max_active_deployments: request.max_active_deployments,
+            max_total_mb: request.max_total_mb,
+        },
+        &db::RepoFilter {
+            allowlist: request.repo_allowlist.clone(),
+            denylist: request.repo_denylist.clone(),
+        },
     )
     .await
     {
@@ -137,8 +458,27 @@ pub async fn upsert_authorized_org(
                 github_org = %org.github_org,
                 zones = ?org.zones,
                 domain_patterns = ?org.domain_patterns,
+                max_active_deployments = ?org.max_active_deployments,
+                max_total_mb = ?org.max_total_mb,
+                repo_allowlist = ?org.repo_allowlist,
+                repo_denylist = ?org.repo_denylist,
                 "Authorized org created/updated"
             );
+            audit(
+                &state,
+                &admin,
+                AdminAction::AuthUpsert,
+                &org.github_org,
+                Some(serde_json::json!({
+                    "zones": request.zones,
+                    "domain_patterns": request.domain_patterns,
+                    "max_active_deployments": request.max_active_deployments,
+                    "max_total_mb": request.max_total_mb,
+                    "repo_allowlist": request.repo_allowlist,
+                    "repo_denylist": request.repo_denylist,
+                })),
+            )
+            .await;
             let response: AuthorizedOrgResponse = org.into();
             (StatusCode::OK, Json(response)).into_response()
         }
@@ -159,18 +499,23 @@ pub async fn delete_authorized_org(
     headers: HeaderMap,
     Json(request): Json<DeleteAuthRequest>,
 ) -> impl IntoResponse {
-    if !verify_admin_key(&headers, &state.config.admin_api_key) {
-        return (
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({"error": "Invalid or missing API key"})),
-        )
-            .into_response();
-    }
+    let admin = match authorize_admin(&headers, &state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
 
     match db::delete_authorized_org(&state.db, &request.github_org).await {
         Ok(deleted) => {
             if deleted {
                 tracing::info!(github_org = %request.github_org, "Authorized org deleted");
+                audit(
+                    &state,
+                    &admin,
+                    AdminAction::AuthDelete,
+                    &request.github_org,
+                    None,
+                )
+                .await;
                 (StatusCode::OK, Json(serde_json::json!({"deleted": true}))).into_response()
             } else {
                 (
@@ -190,3 +535,2003 @@ pub async fn delete_authorized_org(
         }
     }
 }
+
+/// List all workers with their computed health
+pub async fn list_workers(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    if let Err(e) = authorize_admin(&headers, &state, AdminScope::Read) {
+        return e.into_response();
+    }
+
+    match db::list_workers(&state.db).await {
+        Ok(workers) => {
+            let now = Utc::now();
+            let response: Vec<WorkerResponse> = workers
+                .into_iter()
+                .map(|w| WorkerResponse::from_worker(w, now))
+                .collect();
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to list workers");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Database error"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// List the sites a worker last reported serving
+pub async fn list_worker_sites(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(environment): Path<String>,
+) -> impl IntoResponse {
+    if let Err(e) = authorize_admin(&headers, &state, AdminScope::Read) {
+        return e.into_response();
+    }
+
+    match db::list_worker_sites(&state.db, &environment).await {
+        Ok(sites) => (StatusCode::OK, Json(sites)).into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, environment = %environment, "Failed to list worker sites");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Database error"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Enable a worker
+pub async fn enable_worker(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(environment): Path<String>,
+) -> impl IntoResponse {
+    set_worker_enabled(&state, &headers, &environment, true).await
+}
+
+/// Disable a worker
+///
+/// Disabled workers receive no new jobs. Workers listed in the CLI config are
+/// re-enabled when Central restarts.
+pub async fn disable_worker(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(environment): Path<String>,
+) -> impl IntoResponse {
+    set_worker_enabled(&state, &headers, &environment, false).await
+}
+
+async fn set_worker_enabled(
+    state: &AppState,
+    headers: &HeaderMap,
+    environment: &str,
+    enabled: bool,
+) -> Response {
+    let admin = match authorize_admin(headers, state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
+
+    match db::set_worker_enabled(&state.db, environment, enabled).await {
+        Ok(Some(worker)) => {
+            tracing::info!(environment = %environment, enabled, "Worker enabled state changed");
+            let action = if enabled {
+                AdminAction::WorkerEnable
+            } else {
+                AdminAction::WorkerDisable
+            };
+            audit(state, &admin, action, environment, None).await;
+            let response = WorkerResponse::from_worker(worker, Utc::now());
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Worker not found"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to update worker");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Database error"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Pause deployments for a repository
+///
+/// Webhooks are still acknowledged, but no builds are dispatched until the
+/// repository is resumed. PR previews are still cleaned up when PRs close.
+pub async fn pause_repo(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((org, repo)): Path<(String, String)>,
+) -> impl IntoResponse {
+    set_repo_paused(&state, &headers, &org, &repo, true).await
+}
+
+/// Resume deployments for a paused repository
+pub async fn resume_repo(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((org, repo)): Path<(String, String)>,
+) -> impl IntoResponse {
+    set_repo_paused(&state, &headers, &org, &repo, false).await
+}
+
+async fn set_repo_paused(
+    state: &AppState,
+    headers: &HeaderMap,
+    org: &str,
+    repo: &str,
+    paused: bool,
+) -> Response {
+    let admin = match authorize_admin(headers, state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
+
+    match db::set_repo_paused(&state.db, org, repo, paused).await {
+        Ok(settings) => {
+            tracing::info!(org, repo, paused, "Repository paused state changed");
+            let action = if paused {
+                AdminAction::RepoPause
+            } else {
+                AdminAction::RepoResume
+            };
+            audit(state, &admin, action, &format!("{}/{}", org, repo), None).await;
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "github_org": settings.github_org,
+                    "github_repo": settings.github_repo,
+                    "paused": settings.paused,
+                    "updated_at": settings.updated_at,
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, org, repo, "Failed to update repository settings");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Database error"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Default and maximum number of audit log entries per page
+const AUDIT_PAGE_SIZE: i64 = 50;
+const AUDIT_MAX_PAGE_SIZE: i64 = 500;
+
+/// Audit log pagination
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    /// Entries per page (default 50, at most 500)
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// Only entries older than this ID (the previous page's `next_before`)
+    #[serde(default)]
+    pub before: Option<i64>,
+}
+
+impl AuditQuery {
+    fn page_size(&self) -> i64 {
+        self.limit
+            .unwrap_or(AUDIT_PAGE_SIZE)
+            .clamp(1, AUDIT_MAX_PAGE_SIZE)
+    }
+}
+
+/// List admin actions, newest first
+pub async fn list_audit_log(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    if let Err(e) = authorize_admin(&headers, &state, AdminScope::Read) {
+        return e.into_response();
+    }
+
+    let page_size = query.page_size();
+    match db::list_admin_audit(&state.db, query.before, page_size).await {
+        Ok(entries) => {
+            // A full page may have more behind it
+            let next_before = entries
+                .last()
+                .filter(|_| entries.len() as i64 == page_size)
+                .map(|entry| entry.id);
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({"entries": entries, "next_before": next_before})),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to list audit log");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Database error"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Get a deployment and its lifecycle event timeline
+pub async fn get_deployment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(job_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(e) = authorize_admin(&headers, &state, AdminScope::Read) {
+        return e.into_response();
+    }
+
+    let result = async {
+        let Some(deployment) = db::get_deployment(&state.db, job_id).await? else {
+            return Ok(None);
+        };
+        let events = db::list_deployment_events(&state.db, job_id).await?;
+        anyhow::Ok(Some(DeploymentResponse::new(deployment, events)))
+    }
+    .await;
+
+    match result {
+        Ok(Some(response)) => (StatusCode::OK, Json(response)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Deployment not found"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %job_id, "Failed to get deployment");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Database error"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Outcome of approving a held deployment
+enum ApprovalOutcome {
+    Dispatched,
+    NotFound,
+    Expired,
+    NotPending(String),
+}
+
+/// Approve a deployment held for approval and dispatch it to its worker
+pub async fn approve_deployment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(job_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let admin = match authorize_admin(&headers, &state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
+
+    match approve_and_dispatch(&state, job_id).await {
+        Ok(ApprovalOutcome::Dispatched) => {
+            tracing::info!(job_id = %job_id, "Deployment approved and dispatched");
+            audit(
+                &state,
+                &admin,
+                AdminAction::DeploymentApprove,
+                &job_id.to_string(),
+                None,
+            )
+            .await;
+            (
+                StatusCode::ACCEPTED,
+                Json(serde_json::json!({"job_id": job_id, "status": JobStatus::Pending})),
+            )
+                .into_response()
+        }
+        Ok(ApprovalOutcome::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Deployment not found"})),
+        )
+            .into_response(),
+        Ok(ApprovalOutcome::Expired) => (
+            StatusCode::GONE,
+            Json(serde_json::json!({"error": "Approval window has expired"})),
+        )
+            .into_response(),
+        Ok(ApprovalOutcome::NotPending(status)) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": "Deployment is not awaiting approval",
+                "status": status,
+            })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %job_id, "Failed to dispatch approved deployment");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({"error": "Failed to dispatch deployment"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn approve_and_dispatch(state: &AppState, job_id: Uuid) -> anyhow::Result<ApprovalOutcome> {
+    // Expire first so a stale approval is reported as expired, not dispatched
+    db::expire_pending_approvals(&state.db).await?;
+
+    let Some(approval) = db::take_pending_approval(&state.db, job_id).await? else {
+        return Ok(match db::get_deployment(&state.db, job_id).await? {
+            None => ApprovalOutcome::NotFound,
+            Some(d) if d.status == JobStatus::Expired.to_string() => ApprovalOutcome::Expired,
+            Some(d) => ApprovalOutcome::NotPending(d.status),
+        });
+    };
+
+    let mut job = approval.job;
+    let result = async {
+        let zone = job
+            .environment
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Held build job has no zone"))?;
+        let workers = deployment_workers(state, &zone, job.pr_number).await?;
+        if workers.is_empty() {
+            anyhow::bail!("No worker configured for zone: {}", zone);
+        }
+
+        // The held job was stored without a token; the original has expired by now
+        let token = state
+            .github_apps
+            .get_installation_token(&state.http_client, approval.installation_id as u64)
+            .await?;
+        job.git_token = token.token;
+
+        dispatch_to_workers(state, &workers, &job).await
+    }
+    .await;
+
+    if let Err(e) = result {
+        // The approval is consumed; mark the deployment failed rather than
+        // leaving it pending forever
+        if let Err(update_err) =
+            db::update_deployment_status(&state.db, job_id, JobStatus::Failed).await
+        {
+            tracing::warn!(error = %update_err, job_id = %job_id, "Failed to mark deployment failed");
+        }
+        return Err(e);
+    }
+
+    Ok(ApprovalOutcome::Dispatched)
+}
+
+/// Outcome of promoting a canary release
+enum PromoteOutcome {
+    Promoted(String),
+    NotFound,
+    NotMain,
+    NoCanary,
+    NoWorker,
+}
+
+/// Promote a main deployment rolled out as a canary to 100% of traffic
+pub async fn promote_deployment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(job_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let admin = match authorize_admin(&headers, &state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
+
+    match dispatch_promotion(&state, job_id).await {
+        Ok(PromoteOutcome::Promoted(site_id)) => {
+            tracing::info!(job_id = %job_id, site_id = %site_id, "Promoted canary release");
+            audit(
+                &state,
+                &admin,
+                AdminAction::DeploymentPromote,
+                &job_id.to_string(),
+                Some(serde_json::json!({"site_id": site_id})),
+            )
+            .await;
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({"job_id": job_id, "site_id": site_id, "promoted": true})),
+            )
+                .into_response()
+        }
+        Ok(PromoteOutcome::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Deployment not found"})),
+        )
+            .into_response(),
+        Ok(PromoteOutcome::NotMain) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": "Only main branch deployments can be promoted"})),
+        )
+            .into_response(),
+        Ok(PromoteOutcome::NoCanary) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": "Deployment is not a canary awaiting promotion"})),
+        )
+            .into_response(),
+        Ok(PromoteOutcome::NoWorker) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "No worker configured for zone"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %job_id, "Failed to promote canary release");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({"error": "Failed to promote deployment"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn dispatch_promotion(state: &AppState, job_id: Uuid) -> anyhow::Result<PromoteOutcome> {
+    let Some(deployment) = db::get_deployment(&state.db, job_id).await? else {
+        return Ok(PromoteOutcome::NotFound);
+    };
+    if deployment.pr_number.is_some() {
+        return Ok(PromoteOutcome::NotMain);
+    }
+    // The canary is held on each of the zone's mirrors as well
+    let workers = deployment_workers(state, &deployment.zone, None).await?;
+    if workers.is_empty() {
+        return Ok(PromoteOutcome::NoWorker);
+    }
+
+    let job = PromoteJob {
+        job_id,
+        site_id: deployment.site_id.clone(),
+        environment: Some(deployment.zone.clone()),
+    };
+    let signer = state.config.worker_secrets.signer(&deployment.zone)?;
+    let results =
+        futures::future::join_all(workers.iter().map(|worker| {
+            dispatch_promote_job(&state.http_client, &worker.endpoint, signer, &job)
+        }))
+        .await;
+    let promoted = collect_results(&workers, results, "promote canary")?;
+
+    Ok(if promoted.contains(&true) {
+        PromoteOutcome::Promoted(deployment.site_id)
+    } else {
+        PromoteOutcome::NoCanary
+    })
+}
+
+/// Outcome of cancelling a deployment
+enum CancelOutcome {
+    Cancelled,
+    NotFound,
+    NotInProgress(String),
+    NoWorker,
+}
+
+/// Cancel a deployment that is held, pending or still building
+///
+/// Builds are stopped on their worker; once a build has started deploying
+/// its files it can no longer be cancelled.
+pub async fn cancel_deployment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(job_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let admin = match authorize_admin(&headers, &state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
+
+    match dispatch_cancellation(&state, job_id).await {
+        Ok(CancelOutcome::Cancelled) => {
+            tracing::info!(job_id = %job_id, "Cancelled deployment");
+            audit(
+                &state,
+                &admin,
+                AdminAction::DeploymentCancel,
+                &job_id.to_string(),
+                None,
+            )
+            .await;
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({"job_id": job_id, "status": JobStatus::Cancelled})),
+            )
+                .into_response()
+        }
+        Ok(CancelOutcome::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Deployment not found"})),
+        )
+            .into_response(),
+        Ok(CancelOutcome::NotInProgress(status)) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": "Deployment is not in progress",
+                "status": status,
+            })),
+        )
+            .into_response(),
+        Ok(CancelOutcome::NoWorker) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "No worker configured for zone"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %job_id, "Failed to cancel deployment");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({"error": "Failed to cancel deployment"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn dispatch_cancellation(state: &AppState, job_id: Uuid) -> anyhow::Result<CancelOutcome> {
+    let Some(deployment) = db::get_deployment(&state.db, job_id).await? else {
+        return Ok(CancelOutcome::NotFound);
+    };
+
+    let status = deployment.status;
+    if status == JobStatus::PendingApproval.to_string() {
+        // Never dispatched; dropping the hold is enough
+        if db::take_pending_approval(&state.db, job_id)
+            .await?
+            .is_none()
+        {
+            return Ok(CancelOutcome::NotInProgress(status));
+        }
+    } else if status == JobStatus::Pending.to_string() || status == JobStatus::Building.to_string()
+    {
+        let pr_number = deployment.pr_number.map(|pr| pr as u32);
+        let workers = deployment_workers(state, &deployment.zone, pr_number).await?;
+        if workers.is_empty() {
+            return Ok(CancelOutcome::NoWorker);
+        }
+
+        let job = CancelJob { job_id };
+        let signer = state.config.worker_secrets.signer(&deployment.zone)?;
+        let results =
+            futures::future::join_all(workers.iter().map(|worker| {
+                dispatch_cancel_job(&state.http_client, &worker.endpoint, signer, &job)
+            }))
+            .await;
+        let cancelled = collect_results(&workers, results, "cancel build")?;
+        if !cancelled.contains(&true) {
+            return Ok(CancelOutcome::NotInProgress(status));
+        }
+    } else {
+        return Ok(CancelOutcome::NotInProgress(status));
+    }
+
+    // The worker also reports `cancelled`; record it now so the response and
+    // the deployment record agree
+    db::update_deployment_status(&state.db, job_id, JobStatus::Cancelled).await?;
+
+    Ok(CancelOutcome::Cancelled)
+}
+
+/// Outcome of an artifact deployment request
+enum ArtifactOutcome {
+    Dispatched(Uuid),
+    Forbidden(String),
+    NoWorker,
+}
+
+/// Deploy a prebuilt artifact archive for a repository
+///
+/// For teams building in their own CI: the worker downloads and unpacks the
+/// archive instead of cloning and building. Authorization is checked exactly
+/// as for webhook deployments.
+pub async fn deploy_artifact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<DeployArtifactRequest>,
+) -> impl IntoResponse {
+    let admin = match authorize_admin(&headers, &state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(error) = request.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": error})),
+        )
+            .into_response();
+    }
+
+    match dispatch_artifact(&state, &request).await {
+        Ok(ArtifactOutcome::Dispatched(job_id)) => {
+            tracing::info!(
+                job_id = %job_id,
+                org = %request.github_org,
+                repo = %request.github_repo,
+                domain = %request.domain,
+                zone = %request.zone,
+                "Dispatched artifact deployment"
+            );
+            // The artifact URL may carry a signature, so it isn't recorded
+            audit(
+                &state,
+                &admin,
+                AdminAction::DeploymentArtifact,
+                &job_id.to_string(),
+                Some(serde_json::json!({
+                    "github_org": request.github_org,
+                    "github_repo": request.github_repo,
+                    "zone": request.zone,
+                    "domain": request.domain,
+                    "commit_sha": request.commit_sha,
+                    "pr_number": request.pr_number,
+                })),
+            )
+            .await;
+            (
+                StatusCode::ACCEPTED,
+                Json(serde_json::json!({"job_id": job_id, "status": JobStatus::Pending})),
+            )
+                .into_response()
+        }
+        Ok(ArtifactOutcome::Forbidden(error)) => (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": error})),
+        )
+            .into_response(),
+        Ok(ArtifactOutcome::NoWorker) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "No worker configured for zone"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to dispatch artifact deployment");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({"error": "Failed to dispatch deployment"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn dispatch_artifact(
+    state: &AppState,
+    request: &DeployArtifactRequest,
+) -> anyhow::Result<ArtifactOutcome> {
+    let org = request.github_org.as_str();
+    let repo = request.github_repo.as_str();
+
+    let Some(auth) = db::get_authorized_org(&state.db, org).await? else {
+        return Ok(ArtifactOutcome::Forbidden(format!(
+            "Organization '{}' is not authorized",
+            org
+        )));
+    };
+    if !auth.can_deploy_repo(repo) {
+        return Ok(ArtifactOutcome::Forbidden(format!(
+            "Repository '{}/{}' is not allowed by the org's repo filters",
+            org, repo
+        )));
+    }
+    if !auth.can_use_zone(&request.zone) {
+        return Ok(ArtifactOutcome::Forbidden(format!(
+            "Organization '{}' is not authorized to use zone '{}'",
+            org, request.zone
+        )));
+    }
+    if !auth.can_use_domain(&request.domain) {
+        return Ok(ArtifactOutcome::Forbidden(format!(
+            "Organization '{}' is not authorized to use domain '{}'",
+            org, request.domain
+        )));
+    }
+
+    let workers = deployment_workers(state, &request.zone, request.pr_number).await?;
+    if workers.is_empty() {
+        return Ok(ArtifactOutcome::NoWorker);
+    }
+
+    let job_id = Uuid::new_v4();
+    let job = BuildJob {
+        job_id,
+        repo_url: format!("https://{}/{}/{}.git", state.config.github_host, org, repo),
+        git_token: String::new(), // Nothing to clone
+        branch: String::new(),
+        commit_sha: request.commit_sha.clone(),
+        pr_number: request.pr_number,
+        site_id: None,
+        domain: request.domain.clone(),
+        aliases: Vec::new(),
+        site_type: SiteType::default(),
+        callback_url: format!("{}/api/status", state.config.callback_base_url),
+        repo_name: repo.to_string(),
+        org_name: org.to_string(),
+        subdomain: None,
+        build_args: Default::default(),
+        basic_auth: None,
+        precompress: request.precompress,
+        git_lfs: false, // Nothing to clone
+        index_names: Vec::new(),
+        browse: false,
+        caddy_extra: None,
+        isolation: None,
+        dry_run: false,
+        worker_id: None,
+        memory_mb: None,
+        cpu_cores: None,
+        pids_limit: None,
+        node_version: None,
+        environment: Some(request.zone.clone()),
+        artifact: Some(request.artifact.clone()),
+        canary_percent: None,
+        immutable_paths: Some(request.immutable_paths.clone()),
+        purge_cache: None,
+        maintenance_page: None,
+    };
+
+    dispatch_to_workers(state, &workers, &job).await?;
+
+    let site_id = generate_site_id(org, repo, request.pr_number);
+    db::record_deployment(
+        &state.db,
+        &NewDeployment {
+            job_id,
+            org,
+            repo,
+            pr_number: request.pr_number,
+            site_id: &site_id,
+            zone: &request.zone,
+            domain: &request.domain,
+            commit_sha: &request.commit_sha,
+            deployment_type: DeploymentType::Artifact,
+            dry_run: job.dry_run,
+        },
+    )
+    .await?;
+
+    Ok(ArtifactOutcome::Dispatched(job_id))
+}
+
+/// Outcome of a manual commit preview request
+enum PreviewOutcome {
+    Dispatched { job_id: Uuid, url: String },
+    NotDeployable(String),
+    Forbidden(String),
+    NoWorker,
+}
+
+/// Deploy a single commit at a throwaway hostname
+///
+/// For debugging commits that aren't tied to an open PR. The preview is built
+/// with the repository's deploy config like a PR preview, and is cleaned up
+/// with the rest of the repository's deployments.
+pub async fn preview_commit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<PreviewCommitRequest>,
+) -> impl IntoResponse {
+    let admin = match authorize_admin(&headers, &state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(error) = request.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": error})),
+        )
+            .into_response();
+    }
+
+    match dispatch_preview(&state, &request).await {
+        Ok(PreviewOutcome::Dispatched { job_id, url }) => {
+            tracing::info!(
+                job_id = %job_id,
+                org = %request.org,
+                repo = %request.repo,
+                commit = %request.commit_sha,
+                url = %url,
+                "Dispatched manual commit preview"
+            );
+            audit(
+                &state,
+                &admin,
+                AdminAction::DeploymentPreview,
+                &job_id.to_string(),
+                Some(serde_json::json!({
+                    "org": request.org,
+                    "repo": request.repo,
+                    "commit_sha": request.commit_sha,
+                    "url": url,
+                })),
+            )
+            .await;
+            (
+                StatusCode::ACCEPTED,
+                Json(serde_json::json!({"job_id": job_id, "url": url})),
+            )
+                .into_response()
+        }
+        Ok(PreviewOutcome::NotDeployable(error)) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({"error": error})),
+        )
+            .into_response(),
+        Ok(PreviewOutcome::Forbidden(error)) => (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": error})),
+        )
+            .into_response(),
+        Ok(PreviewOutcome::NoWorker) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "No worker configured for zone"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to dispatch manual commit preview");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({"error": "Failed to dispatch preview"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn dispatch_preview(
+    state: &AppState,
+    request: &PreviewCommitRequest,
+) -> anyhow::Result<PreviewOutcome> {
+    let org = request.org.as_str();
+    let repo = request.repo.as_str();
+
+    let Some(auth) = db::get_authorized_org(&state.db, org).await? else {
+        return Ok(PreviewOutcome::Forbidden(format!(
+            "Organization '{}' is not authorized",
+            org
+        )));
+    };
+    if !auth.can_deploy_repo(repo) {
+        return Ok(PreviewOutcome::Forbidden(format!(
+            "Repository '{}/{}' is not allowed by the org's repo filters",
+            org, repo
+        )));
+    }
+
+    let installation_id = state
+        .github_apps
+        .get_repo_installation_id(&state.http_client, org, repo)
+        .await?;
+    let token = state
+        .github_apps
+        .get_installation_token(&state.http_client, installation_id)
+        .await?;
+
+    let deploy_config = match fetch_deploy_config(
+        &state.http_client,
+        &state.config.github_api,
+        &state.config_cache,
+        &state.config.default_deploy_configs,
+        &token.token,
+        org,
+        repo,
+    )
+    .await?
+    {
+        Some(config) if config.is_deployable() => config,
+        _ => {
+            return Ok(PreviewOutcome::NotDeployable(format!(
+                "Repository '{}/{}' has no deployable configuration",
+                org, repo
+            )));
+        }
+    };
+    let zone = deploy_config.zone.clone().unwrap_or_default();
+
+    let Some(domain) = request
+        .hostname
+        .as_ref()
+        .map(|hostname| hostname.to_lowercase())
+        .or_else(|| deploy_config.resolve_commit_domain(repo, &request.commit_sha))
+    else {
+        return Ok(PreviewOutcome::NotDeployable(
+            "Cannot resolve domain - no hostname given and no domain configured".to_string(),
+        ));
+    };
+
+    if !auth.can_use_zone(&zone) {
+        return Ok(PreviewOutcome::Forbidden(format!(
+            "Organization '{}' is not authorized to use zone '{}'",
+            org, zone
+        )));
+    }
+    if !auth.can_use_domain(&domain) {
+        return Ok(PreviewOutcome::Forbidden(format!(
+            "Organization '{}' is not authorized to use domain '{}'",
+            org, domain
+        )));
+    }
+
+    let Some(worker) = db::get_worker(&state.db, &zone).await? else {
+        return Ok(PreviewOutcome::NoWorker);
+    };
+
+    let job_id = Uuid::new_v4();
+    let job = request.build_job(
+        job_id,
+        token.token,
+        &deploy_config,
+        &domain,
+        &state.config.github_host,
+        &state.config.callback_base_url,
+    );
+
+    dispatch_build_job(
+        &state.http_client,
+        &worker.endpoint,
+        state.config.worker_secrets.signer(&worker.environment)?,
+        &job,
+    )
+    .await?;
+
+    let site_id = job.site_id.clone().unwrap_or_default();
+    db::record_deployment(
+        &state.db,
+        &NewDeployment {
+            job_id,
+            org,
+            repo,
+            pr_number: None,
+            site_id: &site_id,
+            zone: &zone,
+            domain: &domain,
+            commit_sha: &job.commit_sha,
+            deployment_type: DeploymentType::Manual,
+            dry_run: job.dry_run,
+        },
+    )
+    .await?;
+
+    // No PR to comment on
+    db::store_job_context(
+        &state.db,
+        job_id,
+        installation_id,
+        org,
+        repo,
+        None,
+        &job.commit_sha,
+    )
+    .await?;
+
+    Ok(PreviewOutcome::Dispatched {
+        job_id,
+        url: generate_preview_url(&domain),
+    })
+}
+
+/// A site rebuilt by a repository rebuild
+#[derive(Debug, Serialize)]
+pub struct RebuiltSite {
+    pub site_id: String,
+    pub job_id: Uuid,
+}
+
+/// An active site a repository rebuild left alone
+#[derive(Debug, Serialize)]
+pub struct SkippedSite {
+    pub site_id: String,
+    pub reason: String,
+}
+
+enum RebuildOutcome {
+    Dispatched {
+        rebuilt: Vec<RebuiltSite>,
+        skipped: Vec<SkippedSite>,
+    },
+    Paused,
+    NotDeployable(String),
+    Forbidden(String),
+}
+
+/// Rebuild every active main and PR deployment of a repository
+///
+/// For picking up a changed build environment without waiting for pushes:
+/// each site is rebuilt at its deployed commit with the repository's current
+/// deploy config. Sites with a build already in flight are skipped, so
+/// repeating the request doesn't queue duplicate builds.
+pub async fn rebuild_repo(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((org, repo)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let admin = match authorize_admin(&headers, &state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
+
+    match dispatch_rebuild(&state, &org, &repo).await {
+        Ok(RebuildOutcome::Dispatched { rebuilt, skipped }) => {
+            tracing::info!(
+                org = %org,
+                repo = %repo,
+                rebuilt = rebuilt.len(),
+                skipped = skipped.len(),
+                "Dispatched repository rebuild"
+            );
+            if !rebuilt.is_empty() {
+                audit(
+                    &state,
+                    &admin,
+                    AdminAction::RepoRebuild,
+                    &format!("{}/{}", org, repo),
+                    Some(serde_json::json!({
+                        "job_ids": rebuilt.iter().map(|site| site.job_id).collect::<Vec<_>>(),
+                    })),
+                )
+                .await;
+            }
+            (
+                StatusCode::ACCEPTED,
+                Json(serde_json::json!({"rebuilt": rebuilt, "skipped": skipped})),
+            )
+                .into_response()
+        }
+        Ok(RebuildOutcome::Paused) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": "Deployments are paused for this repository"})),
+        )
+            .into_response(),
+        Ok(RebuildOutcome::NotDeployable(error)) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({"error": error})),
+        )
+            .into_response(),
+        Ok(RebuildOutcome::Forbidden(error)) => (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": error})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(org = %org, repo = %repo, error = %e, "Failed to rebuild repository");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({"error": "Failed to rebuild repository"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn dispatch_rebuild(
+    state: &AppState,
+    org: &str,
+    repo: &str,
+) -> anyhow::Result<RebuildOutcome> {
+    if db::is_repo_paused(&state.db, org, repo).await? {
+        return Ok(RebuildOutcome::Paused);
+    }
+
+    let Some(auth) = db::get_authorized_org(&state.db, org).await? else {
+        return Ok(RebuildOutcome::Forbidden(format!(
+            "Organization '{}' is not authorized",
+            org
+        )));
+    };
+    if !auth.can_deploy_repo(repo) {
+        return Ok(RebuildOutcome::Forbidden(format!(
+            "Repository '{}/{}' is not allowed by the org's repo filters",
+            org, repo
+        )));
+    }
+
+    let deployments = db::list_active_deployments(&state.db, org, repo).await?;
+    if deployments.is_empty() {
+        return Ok(RebuildOutcome::Dispatched {
+            rebuilt: Vec::new(),
+            skipped: Vec::new(),
+        });
+    }
+
+    let installation_id = state
+        .github_apps
+        .get_repo_installation_id(&state.http_client, org, repo)
+        .await?;
+    let token = state
+        .github_apps
+        .get_installation_token(&state.http_client, installation_id)
+        .await?;
+
+    let deploy_config = match fetch_deploy_config(
+        &state.http_client,
+        &state.config.github_api,
+        &state.config_cache,
+        &state.config.default_deploy_configs,
+        &token.token,
+        org,
+        repo,
+    )
+    .await?
+    {
+        Some(config) if config.is_deployable() => config,
+        _ => {
+            return Ok(RebuildOutcome::NotDeployable(format!(
+                "Repository '{}/{}' has no deployable configuration",
+                org, repo
+            )));
+        }
+    };
+
+    let (planned, mut skipped) = plan_rebuild(&deployments, &auth, &deploy_config);
+    let mut rebuilt = Vec::new();
+
+    for (deployment, aliases) in planned {
+        let skip = |reason: &str| SkippedSite {
+            site_id: deployment.site_id.clone(),
+            reason: reason.to_string(),
+        };
+
+        let pr_number = deployment.pr_number.map(|pr| pr as u32);
+        let workers = deployment_workers(state, &deployment.zone, pr_number).await?;
+        if workers.is_empty() {
+            skipped.push(skip("No worker configured for zone"));
+            continue;
+        }
+
+        let job = rebuild_job(
+            deployment,
+            Uuid::new_v4(),
+            token.token.clone(),
+            &deploy_config,
+            aliases,
+            &state.config.github_host,
+            &state.config.callback_base_url,
+        );
+
+        if let Err(e) = dispatch_to_workers(state, &workers, &job).await {
+            tracing::warn!(
+                site_id = %deployment.site_id,
+                zone = %deployment.zone,
+                error = %e,
+                "Failed to dispatch rebuild"
+            );
+            skipped.push(skip("Failed to dispatch to worker"));
+            continue;
+        }
+
+        db::record_deployment(
+            &state.db,
+            &NewDeployment {
+                job_id: job.job_id,
+                org: &deployment.github_org,
+                repo: &deployment.github_repo,
+                pr_number: job.pr_number,
+                site_id: &deployment.site_id,
+                zone: &deployment.zone,
+                domain: &deployment.domain,
+                commit_sha: &deployment.commit_sha,
+                deployment_type: DeploymentType::Webhook,
+                dry_run: job.dry_run,
+            },
+        )
+        .await?;
+
+        // The result updates the PR's deployment comment, if it has one
+        let comment_id = match job.pr_number {
+            Some(pr_number) => db::get_pr_comment(&state.db, org, repo, pr_number).await?,
+            None => None,
+        };
+        db::store_job_context(
+            &state.db,
+            job.job_id,
+            installation_id,
+            &deployment.github_org,
+            &deployment.github_repo,
+            comment_id,
+            &deployment.commit_sha,
+        )
+        .await?;
+
+        rebuilt.push(RebuiltSite {
+            site_id: deployment.site_id.clone(),
+            job_id: job.job_id,
+        });
+    }
+
+    Ok(RebuildOutcome::Dispatched { rebuilt, skipped })
+}
+
+/// Split active deployments into those to rebuild (with their aliases) and skipped ones
+fn plan_rebuild<'a>(
+    deployments: &'a [DeploymentHistory],
+    auth: &AuthorizedOrg,
+    deploy_config: &DeployConfig,
+) -> (Vec<(&'a DeploymentHistory, Vec<String>)>, Vec<SkippedSite>) {
+    let mut planned = Vec::new();
+    let mut skipped = Vec::new();
+
+    for deployment in deployments {
+        let aliases = match deployment.pr_number {
+            None => deploy_config.resolve_aliases(&deployment.domain),
+            Some(_) => Vec::new(),
+        };
+
+        match rebuild_skip_reason(deployment, &aliases, auth) {
+            Some(reason) => skipped.push(SkippedSite {
+                site_id: deployment.site_id.clone(),
+                reason,
+            }),
+            None => planned.push((deployment, aliases)),
+        }
+    }
+
+    (planned, skipped)
+}
+
+/// Why an active deployment can't be rebuilt, if it can't
+fn rebuild_skip_reason(
+    deployment: &DeploymentHistory,
+    aliases: &[String],
+    auth: &AuthorizedOrg,
+) -> Option<String> {
+    let status = deployment.status.as_str();
+
+    if deployment.deployment_type != DeploymentType::Webhook.to_string() {
+        return Some("Not a main branch or PR deployment".to_string());
+    }
+    if status == JobStatus::Pending.to_string() || status == JobStatus::Building.to_string() {
+        return Some("A build is already in flight".to_string());
+    }
+    if status == JobStatus::PendingApproval.to_string() {
+        return Some("Awaiting approval".to_string());
+    }
+    if status != JobStatus::Success.to_string() && status != JobStatus::Failed.to_string() {
+        return Some(format!("Latest deployment is {}", status));
+    }
+    if !auth.can_use_zone(&deployment.zone) {
+        return Some(format!("Not authorized to use zone '{}'", deployment.zone));
+    }
+    std::iter::once(&deployment.domain)
+        .chain(aliases)
+        .find(|domain| !auth.can_use_domain(domain))
+        .map(|domain| format!("Not authorized to use domain '{}'", domain))
+}
+
+/// Build job rebuilding a deployment's commit with the current deploy config
+fn rebuild_job(
+    deployment: &DeploymentHistory,
+    job_id: Uuid,
+    git_token: String,
+    deploy_config: &DeployConfig,
+    aliases: Vec<String>,
+    github_host: &str,
+    callback_base_url: &str,
+) -> BuildJob {
+    let job = BuildJob::from_deploy_config(
+        BuildTarget {
+            job_id,
+            repo_url: format!(
+                "https://{}/{}/{}.git",
+                github_host, deployment.github_org, deployment.github_repo
+            ),
+            git_token,
+            branch: String::new(), // Checked out by commit SHA
+            commit_sha: deployment.commit_sha.clone(),
+            pr_number: deployment.pr_number.map(|n| n as u32),
+            site_id: None,
+            domain: deployment.domain.clone(),
+            aliases,
+            callback_url: format!("{}/api/status", callback_base_url),
+            repo_name: deployment.github_repo.clone(),
+            org_name: deployment.github_org.clone(),
+            environment: Some(deployment.zone.clone()),
+            preview: deployment.pr_number.is_some(),
+        },
+        deploy_config,
+    );
+    BuildJob {
+        canary_percent: None, // The commit is already live
+        ..job
+    }
+}
+
+/// Filters for a bulk cleanup; at least one must be set
+#[derive(Debug, Default, Deserialize)]
+pub struct BulkCleanupRequest {
+    #[serde(default)]
+    pub org: Option<String>,
+    #[serde(default)]
+    pub repo: Option<String>,
+    /// Only sites whose latest deployment is at least this many seconds old
+    #[serde(default)]
+    pub older_than_secs: Option<u64>,
+    /// Only sites whose latest deployment has this status
+    #[serde(default)]
+    pub status: Option<JobStatus>,
+}
+
+impl BulkCleanupRequest {
+    /// The deployment filter, or why the request can't be used as one
+    ///
+    /// A zero age would match every deployment, so it doesn't count as a filter.
+    fn filter(&self, now: DateTime<Utc>) -> Result<DeploymentFilter, &'static str> {
+        if self.older_than_secs == Some(0) {
+            return Err("older_than_secs must be greater than zero");
+        }
+        let created_before = self.older_than_secs.map(|secs| {
+            i64::try_from(secs)
+                .ok()
+                .and_then(chrono::Duration::try_seconds)
+                .and_then(|age| now.checked_sub_signed(age))
+                .unwrap_or(DateTime::<Utc>::MIN_UTC)
+        });
+        let filter = DeploymentFilter {
+            org: self.org.clone().filter(|org| !org.is_empty()),
+            repo: self.repo.clone().filter(|repo| !repo.is_empty()),
+            created_before,
+            status: self.status,
+        };
+
+        (filter.org.is_some()
+            || filter.repo.is_some()
+            || filter.created_before.is_some()
+            || filter.status.is_some())
+        .then_some(filter)
+        .ok_or("Set at least one of org, repo, older_than_secs or status")
+    }
+}
+
+/// A site cleaned up by a bulk cleanup
+#[derive(Debug, Serialize)]
+pub struct CleanedSite {
+    pub site_id: String,
+    pub job_id: Uuid,
+}
+
+/// Clean up every active deployment matching the request's filters
+///
+/// For tearing down stale sites in bulk, e.g. after a refactor. At least one
+/// filter is required so a bare request can't remove everything, and sites
+/// with a build in flight or awaiting approval are skipped.
+pub async fn bulk_cleanup(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<BulkCleanupRequest>,
+) -> impl IntoResponse {
+    let admin = match authorize_admin(&headers, &state, AdminScope::Write) {
+        Ok(admin) => admin,
+        Err(e) => return e.into_response(),
+    };
+
+    let filter = match request.filter(Utc::now()) {
+        Ok(filter) => filter,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e})),
+            )
+                .into_response();
+        }
+    };
+
+    let deployments = match db::list_active_deployments_matching(&state.db, &filter).await {
+        Ok(deployments) => deployments,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to list deployments for cleanup");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Database error"})),
+            )
+                .into_response();
+        }
+    };
+
+    let (planned, mut skipped) = plan_cleanup(&deployments);
+    let callback_url = format!("{}/api/status", state.config.callback_base_url);
+    let mut cleaned = Vec::new();
+    for deployment in planned {
+        match cleanup_deployment(&state, deployment, &callback_url).await {
+            Ok(job_id) => cleaned.push(CleanedSite {
+                site_id: deployment.site_id.clone(),
+                job_id,
+            }),
+            Err(e) => {
+                tracing::warn!(
+                    site_id = %deployment.site_id,
+                    zone = %deployment.zone,
+                    error = %e,
+                    "Failed to dispatch cleanup"
+                );
+                skipped.push(SkippedSite {
+                    site_id: deployment.site_id.clone(),
+                    reason: "Failed to dispatch to worker".to_string(),
+                });
+            }
+        }
+    }
+
+    tracing::info!(
+        actor = %admin.name,
+        cleaned = cleaned.len(),
+        skipped = skipped.len(),
+        "Dispatched bulk cleanup"
+    );
+    if !cleaned.is_empty() {
+        audit(
+            &state,
+            &admin,
+            AdminAction::DeploymentCleanup,
+            &describe_filter(&filter),
+            Some(serde_json::json!({
+                "job_ids": cleaned.iter().map(|site| site.job_id).collect::<Vec<_>>(),
+            })),
+        )
+        .await;
+    }
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "count": cleaned.len(),
+            "cleaned": cleaned,
+            "skipped": skipped,
+        })),
+    )
+        .into_response()
+}
+
+/// Split matching deployments into those to clean up and skipped ones
+fn plan_cleanup(deployments: &[DeploymentHistory]) -> (Vec<&DeploymentHistory>, Vec<SkippedSite>) {
+    let mut planned = Vec::new();
+    let mut skipped = Vec::new();
+
+    for deployment in deployments {
+        let status = deployment.status.as_str();
+        let reason = if status == JobStatus::Pending.to_string()
+            || status == JobStatus::Building.to_string()
+        {
+            Some("A build is already in flight")
+        } else if status == JobStatus::PendingApproval.to_string() {
+            Some("Awaiting approval")
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => skipped.push(SkippedSite {
+                site_id: deployment.site_id.clone(),
+                reason: reason.to_string(),
+            }),
+            None => planned.push(deployment),
+        }
+    }
+
+    (planned, skipped)
+}
+
+/// Audit log target describing a bulk cleanup's filters
+fn describe_filter(filter: &DeploymentFilter) -> String {
+    let mut parts = Vec::new();
+    if let Some(org) = &filter.org {
+        parts.push(format!("org={}", org));
+    }
+    if let Some(repo) = &filter.repo {
+        parts.push(format!("repo={}", repo));
+    }
+    if let Some(created_before) = filter.created_before {
+        parts.push(format!("created_before={}", created_before.to_rfc3339()));
+    }
+    if let Some(status) = filter.status {
+        parts.push(format!("status={}", status));
+    }
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_page_size() {
+        let query = |limit| AuditQuery {
+            limit,
+            before: None,
+        };
+        assert_eq!(query(None).page_size(), 50);
+        assert_eq!(query(Some(10)).page_size(), 10);
+        assert_eq!(query(Some(0)).page_size(), 1);
+        assert_eq!(query(Some(10_000)).page_size(), 500);
+    }
+
+    #[test]
+    fn test_admin_action_names() {
+        assert_eq!(AdminAction::AuthUpsert.to_string(), "auth.upsert");
+        assert_eq!(AdminAction::WorkerDisable.to_string(), "worker.disable");
+        assert_eq!(
+            AdminAction::DeploymentPreview.to_string(),
+            "deployment.preview"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_key_scopes() {
+        use sha2::{Digest, Sha256};
+        use std::sync::Arc;
+
+        let mut config = crate::config::CentralConfig::for_tests();
+        config.admin_keys = AdminKeys::new(
+            Some("test-admin-key"),
+            &format!(
+                "dashboard:read:{}",
+                hex::encode(Sha256::digest(b"dashboard-key"))
+            ),
+        )
+        .unwrap();
+        let state = AppState {
+            config: Arc::new(config),
+            ..AppState::for_tests()
+        };
+
+        let authorize = |key: Option<&str>, scope| {
+            let mut headers = HeaderMap::new();
+            if let Some(key) = key {
+                headers.insert("authorization", format!("Bearer {}", key).parse().unwrap());
+            }
+            authorize_admin(&headers, &state, scope).map(|admin| admin.name)
+        };
+
+        assert_eq!(
+            authorize(Some("test-admin-key"), AdminScope::Write),
+            Ok("admin".to_string())
+        );
+        assert_eq!(
+            authorize(Some("dashboard-key"), AdminScope::Read),
+            Ok("dashboard".to_string())
+        );
+        assert_eq!(
+            authorize(Some("dashboard-key"), AdminScope::Write),
+            Err(AdminAuthError::ReadOnly)
+        );
+        assert_eq!(
+            authorize(Some("wrong-key"), AdminScope::Read),
+            Err(AdminAuthError::InvalidKey)
+        );
+        assert_eq!(
+            authorize(None, AdminScope::Read),
+            Err(AdminAuthError::InvalidKey)
+        );
+        assert_eq!(
+            AdminAuthError::ReadOnly.into_response().status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    /// Router with the worker admin endpoints
+    fn worker_routes(state: AppState) -> axum::Router {
+        use axum::routing::{get, post};
+
+        axum::Router::new()
+            .route("/api/admin/workers", get(list_workers))
+            .route("/api/admin/workers/:env/enable", post(enable_worker))
+            .route("/api/admin/workers/:env/disable", post(disable_worker))
+            .with_state(state)
+    }
+
+    /// Send a request with the given admin key, returning status and JSON body
+    async fn send(
+        app: &axum::Router,
+        method: &str,
+        path: &str,
+        key: Option<&str>,
+    ) -> (StatusCode, serde_json::Value) {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let mut request = Request::builder().method(method).uri(path);
+        if let Some(key) = key {
+            request = request.header("authorization", format!("Bearer {}", key));
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_worker_endpoints_require_admin_key() {
+        // Rejected before the (never connected) database is touched
+        let app = worker_routes(AppState::for_tests());
+        for (method, path) in [
+            ("GET", "/api/admin/workers"),
+            ("POST", "/api/admin/workers/production/enable"),
+            ("POST", "/api/admin/workers/production/disable"),
+        ] {
+            for key in [None, Some("wrong-key")] {
+                let (status, _) = send(&app, method, path, key).await;
+                assert_eq!(status, StatusCode::UNAUTHORIZED, "{} {}", method, path);
+            }
+        }
+    }
+
+    fn request(url: &str, sha256: Option<&str>) -> DeployArtifactRequest {
+        DeployArtifactRequest {
+            github_org: "nullislabs".to_string(),
+            github_repo: "website".to_string(),
+            zone: "nullislabs".to_string(),
+            domain: "nullislabs.io".to_string(),
+            commit_sha: "abc123".to_string(),
+            pr_number: None,
+            precompress: false,
+            immutable_paths: Vec::new(),
+            artifact: ArtifactSource {
+                url: url.to_string(),
+                sha256: sha256.map(str::to_string),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_artifact_request() {
+        let sha256 = "a".repeat(64);
+        assert!(
+            request("https://ci.example.com/site.tar.gz", Some(&sha256))
+                .validate()
+                .is_ok()
+        );
+        assert!(request("file:///etc/passwd", None).validate().is_err());
+        assert!(request("not a url", None).validate().is_err());
+        assert!(
+            request("https://ci.example.com/site.tar.gz", Some("abc"))
+                .validate()
+                .is_err()
+        );
+
+        let mut missing = request("https://ci.example.com/site.tar.gz", None);
+        missing.commit_sha.clear();
+        assert!(missing.validate().is_err());
+    }
+
+    fn preview_request(commit_sha: &str, hostname: Option<&str>) -> PreviewCommitRequest {
+        PreviewCommitRequest {
+            org: "NullisLabs".to_string(),
+            repo: "Website".to_string(),
+            commit_sha: commit_sha.to_string(),
+            hostname: hostname.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_validate_preview_request() {
+        let sha = "0123456789abcdef0123456789abcdef01234567";
+        assert!(preview_request(sha, None).validate().is_ok());
+        assert!(
+            preview_request(sha, Some("debug.nullislabs.io"))
+                .validate()
+                .is_ok()
+        );
+
+        // Abbreviated or non-hex SHAs can't be fetched
+        assert!(preview_request("0123456", None).validate().is_err());
+        assert!(preview_request(&"g".repeat(40), None).validate().is_err());
+        assert!(
+            preview_request(sha, Some("evil.io/path"))
+                .validate()
+                .is_err()
+        );
+
+        let mut traversal = preview_request(sha, None);
+        traversal.repo = "../website".to_string();
+        assert!(traversal.validate().is_err());
+    }
+
+    #[test]
+    fn test_preview_build_job() {
+        let deploy_config = DeployConfig {
+            zone: Some("nullislabs".to_string()),
+            domain: Some("nullislabs.io".to_string()),
+            canary_percent: Some(10),
+            ..Default::default()
+        };
+        let request = preview_request("0123456789ABCDEF0123456789ABCDEF01234567", None);
+        let domain = deploy_config
+            .resolve_commit_domain(&request.repo, &request.commit_sha)
+            .unwrap();
+        let job_id = Uuid::new_v4();
+
+        let job = request.build_job(
+            job_id,
+            "ghs_token".to_string(),
+            &deploy_config,
+            &domain,
+            "github.com",
+            "https://catapult.nullislabs.io",
+        );
+
+        assert_eq!(job.job_id, job_id);
+        assert_eq!(job.repo_url, "https://github.com/NullisLabs/Website.git");
+        assert_eq!(job.commit_sha, "0123456789abcdef0123456789abcdef01234567");
+        assert_eq!(job.domain, "commit-0123456-website.nullislabs.io");
+        assert_eq!(
+            job.site_id.as_deref(),
+            Some("nullislabs-website-commit-0123456")
+        );
+        assert_eq!(job.pr_number, None);
+        assert_eq!(job.environment.as_deref(), Some("nullislabs"));
+        assert_eq!(
+            job.callback_url,
+            "https://catapult.nullislabs.io/api/status"
+        );
+        // A preview never splits traffic
+        assert_eq!(job.canary_percent, None);
+    }
+
+    fn active(site_id: &str, pr_number: Option<i32>, status: &str) -> DeploymentHistory {
+        DeploymentHistory {
+            id: 1,
+            job_id: Uuid::new_v4(),
+            github_org: "nullisLabs".to_string(),
+            github_repo: "website".to_string(),
+            pr_number,
+            site_id: site_id.to_string(),
+            zone: "nullislabs".to_string(),
+            domain: match pr_number {
+                Some(pr) => format!("pr-{}.nullislabs.io", pr),
+                None => "www.nullislabs.io".to_string(),
+            },
+            commit_sha: "abc1234def5678".to_string(),
+            status: status.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            size_bytes: None,
+            deployment_type: DeploymentType::Webhook.to_string(),
+            dry_run: false,
+        }
+    }
+
+    fn rebuild_config() -> DeployConfig {
+        DeployConfig {
+            zone: Some("nullislabs".to_string()),
+            domain: Some("www.nullislabs.io".to_string()),
+            aliases: Some(vec!["nullislabs.dev".to_string()]),
+            subdomain: Some("www".to_string()),
+            basic_auth: Some(crate::shared::BasicAuth {
+                username: "preview".to_string(),
+                bcrypt_hash: "$2b$12$hash".to_string(),
+            }),
+            canary_percent: Some(10),
+            ..Default::default()
+        }
+    }
+
+    fn rebuild_auth(domain_patterns: &[&str]) -> AuthorizedOrg {
+        AuthorizedOrg {
+            id: 1,
+            github_org: "nullisLabs".to_string(),
+            zones: vec!["nullislabs".to_string()],
+            domain_patterns: domain_patterns.iter().map(|p| p.to_string()).collect(),
+            enabled: true,
+            max_active_deployments: None,
+            max_total_mb: None,
+            repo_allowlist: Vec::new(),
+            repo_denylist: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_rebuild_plans_one_job_per_active_deployment() {
+        let mut artifact = active("nullislabs-website-pr-4", Some(4), "success");
+        artifact.deployment_type = DeploymentType::Artifact.to_string();
+        let deployments = [
+            active("nullislabs-website", None, "success"),
+            active("nullislabs-website-pr-1", Some(1), "failed"),
+            active("nullislabs-website-pr-2", Some(2), "building"),
+            active("nullislabs-website-pr-3", Some(3), "pending_approval"),
+            artifact,
+        ];
+        let auth = rebuild_auth(&["*.nullislabs.io", "nullislabs.dev"]);
+
+        let (planned, skipped) = plan_rebuild(&deployments, &auth, &rebuild_config());
+
+        let planned_sites: Vec<&str> = planned.iter().map(|(d, _)| d.site_id.as_str()).collect();
+        assert_eq!(
+            planned_sites,
+            ["nullislabs-website", "nullislabs-website-pr-1"]
+        );
+        // Aliases are for main deploys only
+        assert_eq!(planned[0].1, ["nullislabs.dev"]);
+        assert!(planned[1].1.is_empty());
+
+        let skipped: Vec<(&str, &str)> = skipped
+            .iter()
+            .map(|s| (s.site_id.as_str(), s.reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                ("nullislabs-website-pr-2", "A build is already in flight"),
+                ("nullislabs-website-pr-3", "Awaiting approval"),
+                (
+                    "nullislabs-website-pr-4",
+                    "Not a main branch or PR deployment"
+                ),
+            ]
+        );
+
+        // A dispatched rebuild is pending, so asking again skips the site
+        let mut rebuilding = deployments[0].clone();
+        rebuilding.status = JobStatus::Pending.to_string();
+        let rebuilding = [rebuilding];
+        let (planned, _) = plan_rebuild(&rebuilding, &auth, &rebuild_config());
+        assert!(planned.is_empty());
+    }
+
+    #[test]
+    fn test_bulk_cleanup_requires_a_filter() {
+        let now = Utc::now();
+        assert!(BulkCleanupRequest::default().filter(now).is_err());
+        let blank = BulkCleanupRequest {
+            org: Some(String::new()),
+            ..Default::default()
+        };
+        assert!(blank.filter(now).is_err());
+
+        // A zero age would match everything, even alongside other filters
+        let zero = BulkCleanupRequest {
+            org: Some("nullisLabs".to_string()),
+            older_than_secs: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            zero.filter(now).unwrap_err(),
+            "older_than_secs must be greater than zero"
+        );
+
+        let request: BulkCleanupRequest = serde_json::from_value(serde_json::json!({
+            "org": "nullisLabs",
+            "older_than_secs": 86400,
+            "status": "failed",
+        }))
+        .unwrap();
+        let filter = request.filter(now).unwrap();
+        assert_eq!(filter.org.as_deref(), Some("nullisLabs"));
+        assert_eq!(filter.repo, None);
+        assert_eq!(filter.created_before, Some(now - chrono::Duration::days(1)));
+        assert_eq!(filter.status, Some(JobStatus::Failed));
+        assert_eq!(
+            describe_filter(&filter),
+            format!(
+                "org=nullisLabs created_before={} status=failed",
+                (now - chrono::Duration::days(1)).to_rfc3339()
+            )
+        );
+
+        // Any single filter will do, and absurd ages match everything
+        let request = BulkCleanupRequest {
+            older_than_secs: Some(u64::MAX),
+            ..Default::default()
+        };
+        assert_eq!(
+            request.filter(now).unwrap().created_before,
+            Some(DateTime::<Utc>::MIN_UTC)
+        );
+    }
+
+    #[test]
+    fn test_bulk_cleanup_skips_in_flight_sites() {
+        let deployments = [
+            active("nullislabs-website", None, "success"),
+            active("nullislabs-website-pr-1", Some(1), "failed"),
+            active("nullislabs-website-pr-2", Some(2), "building"),
+            active("nullislabs-website-pr-3", Some(3), "pending"),
+            active("nullislabs-website-pr-4", Some(4), "pending_approval"),
+        ];
+
+        let (planned, skipped) = plan_cleanup(&deployments);
+
+        let planned: Vec<&str> = planned.iter().map(|d| d.site_id.as_str()).collect();
+        assert_eq!(planned, ["nullislabs-website", "nullislabs-website-pr-1"]);
+        let skipped: Vec<(&str, &str)> = skipped
+            .iter()
+            .map(|s| (s.site_id.as_str(), s.reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                ("nullislabs-website-pr-2", "A build is already in flight"),
+                ("nullislabs-website-pr-3", "A build is already in flight"),
+                ("nullislabs-website-pr-4", "Awaiting approval"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rebuild_rechecks_authorization() {
+        let deployments = [
+            active("nullislabs-website", None, "success"),
+            active("nullislabs-website-pr-1", Some(1), "success"),
+        ];
+
+        // The alias domain is no longer allowed
+        let auth = rebuild_auth(&["*.nullislabs.io"]);
+        let (planned, skipped) = plan_rebuild(&deployments, &auth, &rebuild_config());
+        assert_eq!(planned.len(), 1);
+        assert_eq!(skipped[0].site_id, "nullislabs-website");
+        assert_eq!(
+            skipped[0].reason,
+            "Not authorized to use domain 'nullislabs.dev'"
+        );
+    }
+
+    #[test]
+    fn test_rebuild_job() {
+        let config = rebuild_config();
+        let main = active("nullislabs-website", None, "success");
+        let job_id = Uuid::new_v4();
+
+        let job = rebuild_job(
+            &main,
+            job_id,
+            "ghs_token".to_string(),
+            &config,
+            vec!["nullislabs.dev".to_string()],
+            "github.com",
+            "https://catapult.nullislabs.io",
+        );
+        assert_eq!(job.job_id, job_id);
+        assert_eq!(job.repo_url, "https://github.com/nullisLabs/website.git");
+        assert_eq!(job.commit_sha, main.commit_sha);
+        assert_eq!(job.domain, "www.nullislabs.io");
+        assert_eq!(job.aliases, ["nullislabs.dev"]);
+        assert_eq!(job.subdomain.as_deref(), Some("www"));
+        assert_eq!(job.pr_number, None);
+        assert!(job.basic_auth.is_none());
+        assert_eq!(job.environment.as_deref(), Some("nullislabs"));
+        // The commit is already live, so there's nothing to canary
+        assert_eq!(job.canary_percent, None);
+
+        let pr = active("nullislabs-website-pr-1", Some(1), "success");
+        let job = rebuild_job(
+            &pr,
+            Uuid::new_v4(),
+            "ghs_token".to_string(),
+            &config,
+            Vec::new(),
+            "github.com",
+            "https://catapult.nullislabs.io",
+        );
+        assert_eq!(job.pr_number, Some(1));
+        assert_eq!(job.domain, "pr-1.nullislabs.io");
+        assert!(job.subdomain.is_none());
+        assert!(job.basic_auth.is_some());
+    }
+}
@@ -8,14 +8,18 @@ use axum::{
 use serde::{Deserialize, Serialize};
 
 use crate::central::db;
+use crate::central::handlers::{WorkerSignature, worker_signature};
 use crate::central::server::AppState;
-use crate::shared::auth::verify_signature;
+use crate::shared::version::VersionInfo;
 
 /// Heartbeat request from worker
 #[derive(Debug, Deserialize)]
 pub struct HeartbeatRequest {
     /// The zone/environment this worker serves
     pub zone: String,
+    /// Build the worker is running, for spotting version skew
+    #[serde(default)]
+    pub version: Option<VersionInfo>,
 }
 
 /// Heartbeat response to worker
@@ -36,43 +40,45 @@ pub async fn handle_heartbeat(
     headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
-    // Extract signature and timestamp headers
-    let signature = match headers.get("x-worker-signature") {
-        Some(sig) => sig.to_str().unwrap_or_default(),
-        None => {
-            tracing::warn!("Missing X-Worker-Signature header");
+    let WorkerSignature {
+        signature,
+        timestamp,
+    } = match worker_signature(&headers) {
+        Ok(signed) => signed,
+        Err(missing) => {
             return (
                 StatusCode::UNAUTHORIZED,
                 Json(HeartbeatResponse {
                     ok: false,
-                    message: "Missing signature".to_string(),
+                    message: format!("Missing {}", missing),
                 }),
             );
         }
     };
 
-    let timestamp: u64 = match headers.get("x-request-timestamp") {
-        Some(ts) => ts.to_str().unwrap_or("0").parse().unwrap_or(0),
-        None => {
-            tracing::warn!("Missing X-Request-Timestamp header");
+    // Parse heartbeat request (the claimed zone selects the secret to verify with)
+    let request: HeartbeatRequest = match serde_json::from_slice(&body) {
+        Ok(req) => req,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to parse heartbeat request");
             return (
-                StatusCode::UNAUTHORIZED,
+                StatusCode::BAD_REQUEST,
                 Json(HeartbeatResponse {
                     ok: false,
-                    message: "Missing timestamp".to_string(),
+                    message: format!("Invalid request: {}", e),
                 }),
             );
         }
     };
 
-    // Verify signature
-    if !verify_signature(
-        state.config.worker_shared_secret.as_bytes(),
-        &body,
-        signature,
-        timestamp,
-    ) {
-        tracing::warn!("Invalid worker signature for heartbeat");
+    // Verify signature with the zone's own secret
+    let check =
+        state
+            .config
+            .worker_secrets
+            .verify(Some(&request.zone), &body, signature, timestamp);
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, zone = %request.zone, "Invalid worker signature for heartbeat");
         return (
             StatusCode::UNAUTHORIZED,
             Json(HeartbeatResponse {
@@ -82,20 +88,9 @@ pub async fn handle_heartbeat(
         );
     }
 
-    // Parse heartbeat request
-    let request: HeartbeatRequest = match serde_json::from_slice(&body) {
-        Ok(req) => req,
-        Err(e) => {
-            tracing::error!(error = %e, "Failed to parse heartbeat request");
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(HeartbeatResponse {
-                    ok: false,
-                    message: format!("Invalid request: {}", e),
-                }),
-            );
-        }
-    };
+    if let Some(version) = &request.version {
+        log_version_skew(&request.zone, version);
+    }
 
     // Update worker last_seen
     match db::update_worker_heartbeat(&state.db, &request.zone).await {
@@ -132,3 +127,18 @@ pub async fn handle_heartbeat(
         }
     }
 }
+
+/// Warn when a worker runs a different build than Central
+pub fn log_version_skew(zone: &str, worker: &VersionInfo) {
+    let central = VersionInfo::current();
+    if central.is_skewed_from(worker) {
+        tracing::warn!(
+            zone = %zone,
+            worker_version = %worker.version,
+            worker_commit = %worker.git_commit,
+            central_version = %central.version,
+            central_commit = %central.git_commit,
+            "Worker version differs from Central"
+        );
+    }
+}
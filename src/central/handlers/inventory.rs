@@ -0,0 +1,85 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::central::db;
+use crate::central::handlers::{WorkerSignature, worker_signature};
+use crate::central::server::AppState;
+use crate::shared::SiteInventory;
+
+/// Handle a worker's site inventory report
+///
+/// Replaces the stored inventory for the worker's zone, so sites the worker
+/// no longer serves are dropped.
+pub async fn handle_inventory(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Ok(WorkerSignature {
+        signature,
+        timestamp,
+    }) = worker_signature(&headers)
+    else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    // Parse the report (the claimed zone selects the secret to verify with)
+    let inventory: SiteInventory = match serde_json::from_slice(&body) {
+        Ok(inventory) => inventory,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to parse site inventory");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    let check =
+        state
+            .config
+            .worker_secrets
+            .verify(Some(&inventory.zone), &body, signature, timestamp);
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, zone = %inventory.zone, "Invalid worker signature for site inventory");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    match db::get_worker(&state.db, &inventory.zone).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            tracing::warn!(zone = %inventory.zone, "Site inventory from unknown or disabled worker");
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": format!("Unknown zone: {}", inventory.zone)})),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!(error = %e, zone = %inventory.zone, "Failed to look up worker");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    match db::replace_worker_sites(&state.db, &inventory.zone, &inventory.sites).await {
+        Ok(removed) => {
+            tracing::debug!(
+                zone = %inventory.zone,
+                sites = inventory.sites.len(),
+                removed,
+                "Stored site inventory"
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({"sites": inventory.sites.len(), "removed": removed})),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, zone = %inventory.zone, "Failed to store site inventory");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
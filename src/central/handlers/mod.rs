@@ -1,9 +1,90 @@
+use axum::http::HeaderMap;
+use derive_more::Display;
+
 pub mod admin;
 pub mod heartbeat;
+pub mod inventory;
 pub mod status;
+pub mod token;
 pub mod webhook;
+pub mod worker_config;
 
-pub use admin::{delete_authorized_org, list_authorized_orgs, upsert_authorized_org};
+pub use admin::{
+    approve_deployment, bulk_cleanup, cancel_deployment, delete_authorized_org, deploy_artifact,
+    disable_worker, enable_worker, get_deployment, list_audit_log, list_authorized_orgs,
+    list_worker_sites, list_workers, pause_repo, preview_commit, promote_deployment, rebuild_repo,
+    resume_repo, upsert_authorized_org,
+};
 pub use heartbeat::handle_heartbeat;
+pub use inventory::handle_inventory;
 pub use status::handle_status;
+pub use token::handle_token_refresh;
 pub use webhook::handle_webhook;
+pub use worker_config::get_worker_config;
+
+/// Signature headers sent with every request from a worker
+#[derive(Debug, Clone, Copy)]
+pub struct WorkerSignature<'a> {
+    pub signature: &'a str,
+    pub timestamp: u64,
+}
+
+/// Signature header missing from a worker request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum MissingSignatureHeader {
+    #[display("signature")]
+    Signature,
+    #[display("timestamp")]
+    Timestamp,
+}
+
+/// Read the signature headers of a worker request
+///
+/// A missing header is logged; the caller only decides how to reject the
+/// request. Unparseable values are passed on and fail verification.
+pub fn worker_signature(
+    headers: &HeaderMap,
+) -> Result<WorkerSignature<'_>, MissingSignatureHeader> {
+    let Some(signature) = headers.get("x-worker-signature") else {
+        tracing::warn!("Missing X-Worker-Signature header");
+        return Err(MissingSignatureHeader::Signature);
+    };
+    let Some(timestamp) = headers.get("x-request-timestamp") else {
+        tracing::warn!("Missing X-Request-Timestamp header");
+        return Err(MissingSignatureHeader::Timestamp);
+    };
+
+    Ok(WorkerSignature {
+        signature: signature.to_str().unwrap_or_default(),
+        timestamp: timestamp.to_str().unwrap_or("0").parse().unwrap_or(0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_signature_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            worker_signature(&headers).unwrap_err(),
+            MissingSignatureHeader::Signature
+        );
+
+        headers.insert("x-worker-signature", "v1=abc".parse().unwrap());
+        assert_eq!(
+            worker_signature(&headers).unwrap_err(),
+            MissingSignatureHeader::Timestamp
+        );
+
+        headers.insert("x-request-timestamp", "1700000000".parse().unwrap());
+        let signed = worker_signature(&headers).unwrap();
+        assert_eq!(signed.signature, "v1=abc");
+        assert_eq!(signed.timestamp, 1_700_000_000);
+
+        // A garbled timestamp is left for verification to reject
+        headers.insert("x-request-timestamp", "soon".parse().unwrap());
+        assert_eq!(worker_signature(&headers).unwrap().timestamp, 0);
+    }
+}
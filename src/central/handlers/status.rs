@@ -5,10 +5,14 @@ use axum::{
     response::IntoResponse,
 };
 
-use crate::central::db;
-use crate::central::github::GitHubClient;
+use crate::central::db::{self, JobContext};
+use crate::central::github::{
+    CommentContext, CommitStatus, CommitStatusState, GitHubClient, status_context,
+};
+use crate::central::handlers::{WorkerSignature, worker_signature};
+use crate::central::mirror::{MissingWorkerId, quorum_update};
 use crate::central::server::AppState;
-use crate::shared::{JobStatus, StatusUpdate, auth::verify_signature};
+use crate::shared::{DeploymentPhase, JobStatus, StatusUpdate};
 
 /// Handle status updates from workers
 pub async fn handle_status(
@@ -16,41 +20,60 @@ pub async fn handle_status(
     headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
-    // Extract signature and timestamp headers
-    let signature = match headers.get("x-worker-signature") {
-        Some(sig) => sig.to_str().unwrap_or_default(),
-        None => {
-            tracing::warn!("Missing X-Worker-Signature header");
-            return StatusCode::UNAUTHORIZED;
+    let Ok(WorkerSignature {
+        signature,
+        timestamp,
+    }) = worker_signature(&headers)
+    else {
+        return StatusCode::UNAUTHORIZED;
+    };
+
+    // Parse status update
+    let status_update: StatusUpdate = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to parse status update");
+            return StatusCode::BAD_REQUEST;
         }
     };
 
-    let timestamp: u64 = match headers.get("x-request-timestamp") {
-        Some(ts) => ts.to_str().unwrap_or("0").parse().unwrap_or(0),
-        None => {
-            tracing::warn!("Missing X-Request-Timestamp header");
-            return StatusCode::UNAUTHORIZED;
+    // The recorded zone of the job decides which worker's secret must have
+    // signed the update. Cleanup jobs have no history row, so their claimed
+    // zone is used instead; a wrong claim only fails verification.
+    let zone = match db::get_deployment(&state.db, status_update.job_id).await {
+        Ok(Some(deployment)) => Some(deployment.zone),
+        Ok(None) => status_update.zone.clone(),
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %status_update.job_id, "Failed to look up deployment zone");
+            return StatusCode::INTERNAL_SERVER_ERROR;
         }
     };
 
     // Verify signature
-    if !verify_signature(
-        state.config.worker_shared_secret.as_bytes(),
-        &body,
-        signature,
-        timestamp,
-    ) {
-        tracing::warn!("Invalid worker signature");
+    let check = state
+        .config
+        .worker_secrets
+        .verify(zone.as_deref(), &body, signature, timestamp);
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, job_id = %status_update.job_id, zone = ?zone, "Invalid worker signature");
         return StatusCode::UNAUTHORIZED;
     }
 
-    // Parse status update
-    let status_update: StatusUpdate = match serde_json::from_slice(&body) {
-        Ok(update) => update,
-        Err(e) => {
-            tracing::error!(error = %e, "Failed to parse status update");
+    // Mirrored deployments take progress from the primary and their final
+    // status from the quorum
+    let job_id = status_update.job_id;
+    let worker_id = status_update.worker_id;
+    let status_update = match quorum_update(&state, zone.as_deref(), status_update).await {
+        Ok(Some(update)) => update,
+        Ok(None) => return StatusCode::OK,
+        Err(e) if e.is::<MissingWorkerId>() => {
+            tracing::warn!(job_id = %job_id, "Rejected mirrored status update without a worker ID");
             return StatusCode::BAD_REQUEST;
         }
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %job_id, worker_id, "Failed to record mirrored status");
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
     };
 
     tracing::info!(
@@ -60,6 +83,23 @@ pub async fn handle_status(
         "Received status update from worker"
     );
 
+    // Record lifecycle events before acknowledging, so the timeline keeps the
+    // order in which the worker reported them
+    if let Some(phase) = status_update.event
+        && let Err(e) = db::record_deployment_event(&state.db, status_update.job_id, phase).await
+    {
+        tracing::error!(error = %e, job_id = %status_update.job_id, "Failed to record deployment event");
+    }
+
+    // Progress goes out before acknowledging too: the worker sends its final
+    // status only after this, so progress can't overwrite the final comment
+    if status_update.status == JobStatus::Building
+        && let Some(completed) = status_update.event.and_then(progress_steps)
+        && let Err(e) = update_progress_comment(&state, &status_update, completed).await
+    {
+        tracing::warn!(error = %e, job_id = %status_update.job_id, "Failed to update progress comment");
+    }
+
     // Process status update asynchronously
     let state_clone = state.clone();
     tokio::spawn(async move {
@@ -71,7 +111,14 @@ pub async fn handle_status(
     StatusCode::OK
 }
 
-async fn process_status_update(state: &AppState, update: StatusUpdate) -> anyhow::Result<()> {
+/// Apply a job's status update: record it and update the PR comment
+pub async fn process_status_update(state: &AppState, update: StatusUpdate) -> anyhow::Result<()> {
+    // Track the deployment's latest status (cleanup jobs have no history row)
+    let changed = db::update_deployment_status(&state.db, update.job_id, update.status).await?;
+    if let Some(size_bytes) = update.size_bytes {
+        db::record_deployment_size(&state.db, update.job_id, size_bytes).await?;
+    }
+
     // Look up job context
     let context = match db::get_job_context(&state.db, update.job_id).await? {
         Some(ctx) => ctx,
@@ -89,6 +136,17 @@ async fn process_status_update(state: &AppState, update: StatusUpdate) -> anyhow
         "Received status update"
     );
 
+    // Commit statuses go on every deployed commit, with or without a PR
+    // comment, once per status change rather than per progress report. Final
+    // statuses always go out: a cancellation is recorded by Central before
+    // the worker reports it.
+    if let Some(template) = &state.config.commit_status_context
+        && (changed || update.status.is_final())
+        && let Err(e) = post_commit_status(state, &context, &update, template).await
+    {
+        tracing::warn!(job_id = %update.job_id, error = %e, "Failed to post commit status");
+    }
+
     // Update GitHub PR comment if we have a comment_id
     if let Some(comment_id) = context.github_comment_id {
         // Skip building status (we already posted "Building..." initially)
@@ -103,25 +161,45 @@ async fn process_status_update(state: &AppState, update: StatusUpdate) -> anyhow
 
         // Get a fresh installation token
         let token = state
-            .github_app
+            .github_apps
             .get_installation_token(&state.http_client, context.installation_id as u64)
             .await?;
 
-        let github_client = GitHubClient::new(token.token);
+        let github_client = GitHubClient::new(
+            state.http_client.clone(),
+            state.config.github_api.clone(),
+            token.token,
+        );
+
+        // Build duration from the timestamps recorded by the status update above
+        let deployment = db::get_deployment(&state.db, update.job_id).await?;
+        let duration = deployment
+            .as_ref()
+            .and_then(|deployment| deployment.build_duration());
+        let dry_run = deployment.is_some_and(|deployment| deployment.dry_run);
+        let build = CommentContext {
+            org: &context.github_org,
+            repo: &context.github_repo,
+            commit_sha: &context.commit_sha,
+            duration,
+            footer: state.config.comment_footer,
+        };
 
         // Build the comment body based on status
         let comment_body = match update.status {
+            JobStatus::Success if dry_run => GitHubClient::dry_run_comment(&build),
             JobStatus::Success => {
                 let url = update
                     .deployed_url
                     .as_deref()
                     .unwrap_or("(URL not available)");
-                GitHubClient::success_comment(&context.commit_sha, url)
+                GitHubClient::success_comment(&build, url)
             }
             JobStatus::Failed => {
                 let error = update.error_message.as_deref().unwrap_or("Unknown error");
-                GitHubClient::failure_comment(&context.commit_sha, error)
+                GitHubClient::failure_comment(&build, error)
             }
+            JobStatus::Cancelled => GitHubClient::cancelled_comment(&build),
             _ => return Ok(()),
         };
 
@@ -145,3 +223,172 @@ async fn process_status_update(state: &AppState, update: StatusUpdate) -> anyhow
 
     Ok(())
 }
+
+/// Steps of the PR comment's progress list done once `phase` is reached
+///
+/// Phases that don't change what the comment shows are skipped, saving
+/// GitHub API calls.
+fn progress_steps(phase: DeploymentPhase) -> Option<usize> {
+    match phase {
+        // Cloned is immediately followed by Building
+        DeploymentPhase::Building => Some(1),
+        DeploymentPhase::Deployed => Some(3),
+        // Routing finishes right before the final status
+        DeploymentPhase::Cloned
+        | DeploymentPhase::CaddyConfigured
+        | DeploymentPhase::CloudflareConfigured
+        | DeploymentPhase::Reachable => None,
+    }
+}
+
+/// Show a building job's progress in its PR comment
+async fn update_progress_comment(
+    state: &AppState,
+    update: &StatusUpdate,
+    completed: usize,
+) -> anyhow::Result<()> {
+    let Some(context) = db::get_job_context(&state.db, update.job_id).await? else {
+        return Ok(());
+    };
+    let Some(comment_id) = context.github_comment_id else {
+        return Ok(());
+    };
+
+    let token = state
+        .github_apps
+        .get_installation_token(&state.http_client, context.installation_id as u64)
+        .await?;
+    let github_client = GitHubClient::new(
+        state.http_client.clone(),
+        state.config.github_api.clone(),
+        token.token,
+    );
+
+    github_client
+        .update_comment(
+            &context.github_org,
+            &context.github_repo,
+            comment_id,
+            &GitHubClient::progress_comment(&context.commit_sha, completed),
+        )
+        .await?;
+
+    tracing::debug!(job_id = %update.job_id, completed, "Updated PR comment progress");
+    Ok(())
+}
+
+/// Mirror a deployment status update as a status on its commit
+async fn post_commit_status(
+    state: &AppState,
+    context: &JobContext,
+    update: &StatusUpdate,
+    template: &str,
+) -> anyhow::Result<()> {
+    // Cleanup jobs have no deployment and nothing to report on the commit
+    let Some(deployment) = db::get_deployment(&state.db, update.job_id).await? else {
+        return Ok(());
+    };
+    let Some((status_state, description)) = commit_status_for(update.status, deployment.dry_run)
+    else {
+        return Ok(());
+    };
+    let environment = match deployment.pr_number {
+        Some(_) => "preview",
+        None => "production",
+    };
+
+    let token = state
+        .github_apps
+        .get_installation_token(&state.http_client, context.installation_id as u64)
+        .await?;
+    let github_client = GitHubClient::new(
+        state.http_client.clone(),
+        state.config.github_api.clone(),
+        token.token,
+    );
+
+    github_client
+        .create_commit_status(
+            &context.github_org,
+            &context.github_repo,
+            &context.commit_sha,
+            &CommitStatus {
+                state: status_state,
+                target_url: update.deployed_url.as_deref(),
+                description,
+                context: &status_context(template, environment),
+            },
+        )
+        .await
+}
+
+/// Commit status state and description for a job status
+///
+/// `None` for statuses not reported on the commit.
+fn commit_status_for(
+    status: JobStatus,
+    dry_run: bool,
+) -> Option<(CommitStatusState, &'static str)> {
+    match status {
+        JobStatus::Building => Some((CommitStatusState::Pending, "Deploying")),
+        JobStatus::Success if dry_run => {
+            Some((CommitStatusState::Success, "Build verified (dry run)"))
+        }
+        JobStatus::Success => Some((CommitStatusState::Success, "Deployed")),
+        JobStatus::Failed => Some((CommitStatusState::Failure, "Deployment failed")),
+        JobStatus::Cancelled => Some((CommitStatusState::Error, "Deployment cancelled")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_status_for() {
+        assert_eq!(
+            commit_status_for(JobStatus::Building, false),
+            Some((CommitStatusState::Pending, "Deploying"))
+        );
+        assert_eq!(
+            commit_status_for(JobStatus::Success, false),
+            Some((CommitStatusState::Success, "Deployed"))
+        );
+        assert_eq!(
+            commit_status_for(JobStatus::Failed, false).map(|(state, _)| state),
+            Some(CommitStatusState::Failure)
+        );
+        assert_eq!(commit_status_for(JobStatus::Cleaned, false), None);
+        assert_eq!(commit_status_for(JobStatus::Pending, false), None);
+        assert_eq!(
+            commit_status_for(JobStatus::Success, true),
+            Some((CommitStatusState::Success, "Build verified (dry run)"))
+        );
+    }
+
+    #[test]
+    fn test_progress_follows_worker_phases() {
+        use crate::central::github::api::PROGRESS_STEPS;
+
+        // Phases in the order a worker reports a source build
+        let reported = [
+            DeploymentPhase::Cloned,
+            DeploymentPhase::Building,
+            DeploymentPhase::Deployed,
+            DeploymentPhase::CaddyConfigured,
+            DeploymentPhase::CloudflareConfigured,
+            DeploymentPhase::Reachable,
+        ];
+        let progress: Vec<usize> = reported.into_iter().filter_map(progress_steps).collect();
+
+        assert_eq!(progress, [1, 3]);
+        assert!(progress.is_sorted());
+        // The last step is only ever finished by the final status
+        assert!(
+            progress
+                .iter()
+                .all(|&completed| completed < PROGRESS_STEPS.len())
+        );
+    }
+}
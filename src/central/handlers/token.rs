@@ -0,0 +1,154 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::central::db;
+use crate::central::handlers::{WorkerSignature, worker_signature};
+use crate::central::server::AppState;
+use crate::shared::{GitTokenRequest, GitTokenResponse, JobStatus};
+
+/// Mint a fresh git token for a job whose token GitHub rejected
+///
+/// Installation tokens expire after an hour, so a job that sat in a queue can
+/// outlive the token it was dispatched with. Only the worker the job was
+/// dispatched to can ask, and only while the job is still in flight.
+pub async fn handle_token_refresh(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Ok(WorkerSignature {
+        signature,
+        timestamp,
+    }) = worker_signature(&headers)
+    else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    let request: GitTokenRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to parse git token request");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    // Only the recorded zone's secret is accepted, so a worker can't get
+    // tokens for other zones' jobs. The zone is needed to check the signature,
+    // so an unknown job gets the same 401 as a bad signature, and unsigned
+    // callers can't probe which job IDs exist.
+    let deployment = match db::get_deployment(&state.db, request.job_id).await {
+        Ok(Some(deployment)) => deployment,
+        Ok(None) => {
+            tracing::warn!(job_id = %request.job_id, "Git token requested for unknown job");
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %request.job_id, "Failed to look up deployment");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let check =
+        state
+            .config
+            .worker_secrets
+            .verify(Some(&deployment.zone), &body, signature, timestamp);
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, job_id = %request.job_id, zone = %deployment.zone, "Invalid worker signature for git token request");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    if !in_flight(&deployment.status) {
+        tracing::warn!(
+            job_id = %request.job_id,
+            status = %deployment.status,
+            "Git token requested for a job no longer in flight"
+        );
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": format!("Job is {}", deployment.status)
+            })),
+        )
+            .into_response();
+    }
+
+    let context = match db::get_job_context(&state.db, request.job_id).await {
+        Ok(Some(context)) => context,
+        Ok(None) => {
+            tracing::warn!(job_id = %request.job_id, "No job context for git token request");
+            return StatusCode::NOT_FOUND.into_response();
+        }
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %request.job_id, "Failed to look up job context");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    match state
+        .github_apps
+        .get_installation_token(&state.http_client, context.installation_id as u64)
+        .await
+    {
+        Ok(token) => {
+            tracing::info!(
+                job_id = %request.job_id,
+                expires_at = %token.expires_at,
+                "Minted fresh git token for worker"
+            );
+            Json(GitTokenResponse {
+                token: token.token,
+                expires_at: token.expires_at,
+            })
+            .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %request.job_id, "Failed to mint git token");
+            StatusCode::BAD_GATEWAY.into_response()
+        }
+    }
+}
+
+/// Whether a deployment with this status may still be cloning
+fn in_flight(status: &str) -> bool {
+    [JobStatus::Pending, JobStatus::Building]
+        .iter()
+        .any(|in_flight| in_flight.to_string() == status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_in_flight_jobs_get_tokens() {
+        assert!(in_flight("pending"));
+        assert!(in_flight("building"));
+        for status in [
+            "success",
+            "failed",
+            "cancelled",
+            "cleaned",
+            "pending_approval",
+        ] {
+            assert!(!in_flight(status), "{} should not get a token", status);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_token_request_rejected() {
+        let body = serde_json::to_vec(&GitTokenRequest {
+            job_id: uuid::Uuid::new_v4(),
+        })
+        .unwrap();
+
+        let response =
+            handle_token_refresh(State(AppState::for_tests()), HeaderMap::new(), body.into()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
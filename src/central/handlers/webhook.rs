@@ -7,15 +7,25 @@ use axum::{
 use uuid::Uuid;
 
 use crate::central::db;
+use crate::central::db::{
+    AuthorizedOrg, DeploymentHistory, DeploymentType, NewDeployment, QuotaExceeded, Worker,
+};
 use crate::central::deploy_config::fetch_deploy_config;
 use crate::central::dispatch::dispatch_build_job;
+use crate::central::dispatch::dispatch_cleanup_job;
 use crate::central::github::{
-    GitHubClient, PullRequestAction, WebhookEvent, parse_webhook_event, verify_webhook_signature,
+    EventSupport, GitHubClient, InstallationAction, PullRequestAction, PullRequestEvent,
+    WebhookEvent, event_support, parse_webhook_event, verify_webhook_signature,
 };
+use crate::central::mirror::{collect_results, deployment_workers, dispatch_to_workers};
 use crate::central::server::AppState;
-use crate::shared::{BuildJob, CleanupJob, generate_site_id};
+use crate::shared::{BuildJob, BuildTarget, CleanupJob, DeployConfig, JobStatus, generate_site_id};
 
 /// Handle incoming GitHub webhooks
+///
+/// Only JSON payloads (the app's content type setting) and known event types
+/// are accepted; events that never affect deployments are acknowledged
+/// without being parsed.
 pub async fn handle_webhook(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -38,12 +48,36 @@ pub async fn handle_webhook(
         }
     };
 
-    // Verify signature
-    if !verify_webhook_signature(&state.config.github_webhook_secret, &body, signature) {
+    if !is_json(&headers) {
+        tracing::warn!(
+            content_type = ?headers.get("content-type"),
+            "Webhook payload is not JSON"
+        );
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE;
+    }
+
+    let support = event_support(event_type);
+    if support == EventSupport::Unsupported {
+        tracing::warn!(event_type, "Unsupported webhook event type");
+        return StatusCode::BAD_REQUEST;
+    }
+
+    // Verify signature with the secret of the app the webhook was sent for
+    let app_id = webhook_app_id(&headers);
+    let Some(secret) = state.github_apps.webhook_secret(app_id) else {
+        tracing::warn!(app_id, "Webhook for unknown GitHub App");
+        return StatusCode::UNAUTHORIZED;
+    };
+    if !verify_webhook_signature(secret, &body, signature) {
         tracing::warn!("Invalid webhook signature");
         return StatusCode::UNAUTHORIZED;
     }
 
+    if support == EventSupport::Ignored {
+        tracing::debug!(event_type, "Ignoring webhook event");
+        return StatusCode::OK;
+    }
+
     // Parse event
     let event = match parse_webhook_event(event_type, &body) {
         Ok(event) => event,
@@ -53,6 +87,13 @@ pub async fn handle_webhook(
         }
     };
 
+    // Mint this installation's tokens with the app that received the webhook
+    if let (Some(app_id), Some(installation_id)) = (app_id, event.installation_id()) {
+        state
+            .github_apps
+            .remember_installation(installation_id, app_id);
+    }
+
     // Process event asynchronously
     tokio::spawn(async move {
         if let Err(e) = process_webhook_event(&state, event).await {
@@ -63,6 +104,32 @@ pub async fn handle_webhook(
     StatusCode::OK
 }
 
+/// GitHub App a webhook was sent for
+///
+/// `None` for webhooks configured on a repository or organization, which
+/// don't target an app.
+fn webhook_app_id(headers: &HeaderMap) -> Option<u64> {
+    let target_type = headers.get("x-github-hook-installation-target-type")?;
+    if target_type.to_str().ok()? != "integration" {
+        return None;
+    }
+    headers
+        .get("x-github-hook-installation-target-id")?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Whether the request body is declared as JSON
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"))
+}
+
 async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow::Result<()> {
     match event {
         WebhookEvent::PullRequest(pr_event) => {
@@ -86,13 +153,21 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
 
             // Get installation token (needed to fetch .deploy.json)
             let token = state
-                .github_app
+                .github_apps
                 .get_installation_token(&state.http_client, installation_id)
                 .await?;
 
             // Fetch deploy config from org/.github and repo
-            let deploy_config =
-                fetch_deploy_config(&state.http_client, &token.token, org, repo).await?;
+            let deploy_config = fetch_deploy_config(
+                &state.http_client,
+                &state.config.github_api,
+                &state.config_cache,
+                &state.config.default_deploy_configs,
+                &token.token,
+                org,
+                repo,
+            )
+            .await?;
 
             let deploy_config = match deploy_config {
                 Some(config) if config.is_deployable() => config,
@@ -130,10 +205,67 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
                 .await?
                 .ok_or_else(|| anyhow::anyhow!("No worker configured for zone: {}", zone))?;
 
-            match pr_event.action {
-                PullRequestAction::Opened
-                | PullRequestAction::Synchronize
-                | PullRequestAction::Reopened => {
+            match repo_preview_action(&pr_event, &deploy_config) {
+                PreviewAction::Deploy => {
+                    // Repo filters stop new previews; those already deployed
+                    // are still cleaned up
+                    if !auth.can_deploy_repo(repo) {
+                        anyhow::bail!(
+                            "Repository '{}/{}' is not allowed by the org's repo filters",
+                            org,
+                            repo
+                        );
+                    }
+
+                    // A PR reopened within the cleanup grace period keeps its
+                    // preview, and needs no rebuild if nothing was pushed
+                    if state.pending_cleanups.cancel(org, repo, pr_event.number) {
+                        tracing::info!(
+                            org,
+                            repo,
+                            pr = pr_event.number,
+                            "Cancelled scheduled PR preview cleanup"
+                        );
+                        let site_id = generate_site_id(org, repo, Some(pr_event.number));
+                        let latest = db::get_latest_site_deployment(&state.db, &site_id).await?;
+                        if pr_event.action == PullRequestAction::Reopened
+                            && latest.is_some_and(|deployment| {
+                                is_current_preview(&deployment, &pr_event.pull_request.head.sha)
+                            })
+                        {
+                            return Ok(());
+                        }
+                    }
+
+                    // Only clone from the configured GitHub host, in canonical form
+                    let repo_url = match pr_event
+                        .repository
+                        .https_clone_url(&state.config.github_host)
+                    {
+                        Ok(url) => url,
+                        Err(e) => {
+                            tracing::warn!(
+                                org,
+                                repo,
+                                pr = pr_event.number,
+                                error = %e,
+                                "Rejected repository clone URL"
+                            );
+                            return Ok(());
+                        }
+                    };
+
+                    // Paused repos keep their config; previews are still cleaned up
+                    if db::is_repo_paused(&state.db, org, repo).await? {
+                        tracing::info!(
+                            org,
+                            repo,
+                            pr = pr_event.number,
+                            "Deployments paused for repository, skipping"
+                        );
+                        return Ok(());
+                    }
+
                     // Resolve PR domain
                     let pr_domain = deploy_config
                         .resolve_pr_domain(repo, pr_event.number)
@@ -152,46 +284,36 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
                         );
                     }
 
-                    // Generate job_id
-                    let job_id = Uuid::new_v4();
+                    let github_client = GitHubClient::new(
+                        state.http_client.clone(),
+                        state.config.github_api.clone(),
+                        token.token.clone(),
+                    );
 
-                    // Create or update the PR comment
-                    let github_client = GitHubClient::new(token.token.clone());
-                    let comment_id =
+                    // Enforce the org's deployment quotas
+                    let site_id = generate_site_id(org, repo, Some(pr_event.number));
+                    if let Err(exceeded) = check_org_quota(state, &auth, &site_id).await? {
+                        tracing::warn!(
+                            org,
+                            repo,
+                            pr = pr_event.number,
+                            reason = %exceeded,
+                            "Deployment rejected by org quota"
+                        );
+                        let body = GitHubClient::quota_exceeded_comment(
+                            &pr_event.pull_request.head.sha,
+                            &exceeded.to_string(),
+                        );
                         match db::get_pr_comment(&state.db, org, repo, pr_event.number).await? {
-                            Some(existing_comment_id) => {
-                                // Update existing comment
-                                tracing::debug!(
-                                    pr = pr_event.number,
-                                    comment_id = existing_comment_id,
-                                    "Updating existing PR comment"
-                                );
+                            Some(comment_id) => {
                                 github_client
-                                    .update_comment(
-                                        org,
-                                        repo,
-                                        existing_comment_id,
-                                        &GitHubClient::building_comment(
-                                            &pr_event.pull_request.head.sha,
-                                        ),
-                                    )
+                                    .update_comment(org, repo, comment_id, &body)
                                     .await?;
-                                existing_comment_id
                             }
                             None => {
-                                // Create new comment
-                                tracing::debug!(pr = pr_event.number, "Creating new PR comment");
                                 let comment = github_client
-                                    .create_pr_comment(
-                                        org,
-                                        repo,
-                                        pr_event.number,
-                                        &GitHubClient::building_comment(
-                                            &pr_event.pull_request.head.sha,
-                                        ),
-                                    )
+                                    .create_pr_comment(org, repo, pr_event.number, &body)
                                     .await?;
-                                // Store the comment ID for future updates
                                 db::upsert_pr_comment(
                                     &state.db,
                                     org,
@@ -200,30 +322,91 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
                                     comment.id,
                                 )
                                 .await?;
-                                comment.id
                             }
-                        };
+                        }
+                        return Ok(());
+                    }
 
-                    // Dispatch build job
-                    let job = BuildJob {
-                        job_id,
-                        repo_url: pr_event.repository.clone_url.clone(),
-                        git_token: token.token,
-                        branch: pr_event.pull_request.head.branch.clone(),
-                        commit_sha: pr_event.pull_request.head.sha.clone(),
-                        pr_number: Some(pr_event.number),
-                        domain: pr_domain.clone(),
-                        site_type: deploy_config.build_type.unwrap_or_default(),
-                        callback_url: format!("{}/api/status", state.config.callback_base_url),
-                        repo_name: repo.to_string(),
-                        org_name: org.to_string(),
-                        subdomain: None, // PRs don't use subdomain
+                    // Generate job_id
+                    let job_id = Uuid::new_v4();
+
+                    // Create or update the PR comment
+                    let building_comment =
+                        GitHubClient::building_comment(&pr_event.pull_request.head.sha);
+                    let minimize_stale = deploy_config.minimize_stale_comments.unwrap_or(false);
+                    let comment_id = match db::get_pr_comment(&state.db, org, repo, pr_event.number)
+                        .await?
+                    {
+                        Some(existing_comment_id) if !minimize_stale => {
+                            // Update existing comment
+                            tracing::debug!(
+                                pr = pr_event.number,
+                                comment_id = existing_comment_id,
+                                "Updating existing PR comment"
+                            );
+                            github_client
+                                .update_comment(org, repo, existing_comment_id, &building_comment)
+                                .await?;
+                            existing_comment_id
+                        }
+                        stale_comment_id => {
+                            // Create new comment
+                            tracing::debug!(pr = pr_event.number, "Creating new PR comment");
+                            let comment = github_client
+                                .create_pr_comment(org, repo, pr_event.number, &building_comment)
+                                .await?;
+                            // Store the comment ID for future updates
+                            db::upsert_pr_comment(
+                                &state.db,
+                                org,
+                                repo,
+                                pr_event.number,
+                                comment.id,
+                            )
+                            .await?;
+
+                            // Hide the superseded deployment comment (best effort)
+                            if let Some(stale_id) = stale_comment_id
+                                && let Err(e) =
+                                    github_client.minimize_comment(org, repo, stale_id).await
+                            {
+                                tracing::warn!(
+                                    error = %e,
+                                    pr = pr_event.number,
+                                    comment_id = stale_id,
+                                    "Failed to minimize stale PR comment"
+                                );
+                            }
+
+                            comment.id
+                        }
                     };
 
+                    // Dispatch build job
+                    let job = BuildJob::from_deploy_config(
+                        BuildTarget {
+                            job_id,
+                            repo_url,
+                            git_token: token.token,
+                            branch: pr_event.pull_request.head.branch.clone(),
+                            commit_sha: pr_event.pull_request.head.sha.clone(),
+                            pr_number: Some(pr_event.number),
+                            site_id: None,
+                            domain: pr_domain.clone(),
+                            aliases: Vec::new(), // Aliases are for main deploys only
+                            callback_url: format!("{}/api/status", state.config.callback_base_url),
+                            repo_name: repo.to_string(),
+                            org_name: org.to_string(),
+                            environment: Some(zone.clone()),
+                            preview: true,
+                        },
+                        &deploy_config,
+                    );
+
                     dispatch_build_job(
                         &state.http_client,
                         &worker.endpoint,
-                        &state.config.worker_shared_secret,
+                        state.config.worker_secrets.signer(&worker.environment)?,
                         &job,
                     )
                     .await?;
@@ -236,6 +419,23 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
                         "Dispatched PR build job"
                     );
 
+                    db::record_deployment(
+                        &state.db,
+                        &NewDeployment {
+                            job_id,
+                            org,
+                            repo,
+                            pr_number: Some(pr_event.number),
+                            site_id: &site_id,
+                            zone,
+                            domain: &pr_domain,
+                            commit_sha: &pr_event.pull_request.head.sha,
+                            deployment_type: DeploymentType::Webhook,
+                            dry_run: job.dry_run,
+                        },
+                    )
+                    .await?;
+
                     // Store deployment info for status updates
                     // We store the comment_id with the job_id for later correlation
                     store_deployment_context(
@@ -249,45 +449,86 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
                     )
                     .await?;
                 }
-                PullRequestAction::Closed => {
-                    // Resolve PR domain for cleanup
-                    let pr_domain = deploy_config.resolve_pr_domain(repo, pr_event.number);
-
-                    // Dispatch cleanup job
-                    let job = CleanupJob {
-                        job_id: Uuid::new_v4(),
-                        site_id: generate_site_id(org, repo, Some(pr_event.number)),
-                        callback_url: format!("{}/api/status", state.config.callback_base_url),
-                        domain: pr_domain,
-                    };
-
-                    crate::central::dispatch::dispatch_cleanup_job(
-                        &state.http_client,
-                        &worker.endpoint,
-                        &state.config.worker_shared_secret,
-                        &job,
-                    )
-                    .await?;
+                PreviewAction::Cleanup => {
+                    let site_id = generate_site_id(org, repo, Some(pr_event.number));
+                    let latest = db::get_latest_site_deployment(&state.db, &site_id).await?;
 
-                    // Clean up the PR comment tracking
-                    if let Err(e) =
-                        db::delete_pr_comment(&state.db, org, repo, pr_event.number).await
+                    // A skipped draft has nothing to clean up
+                    if latest.is_none()
+                        && pr_event.pull_request.draft
+                        && deploy_config.skip_drafts.unwrap_or(false)
                     {
-                        tracing::warn!(
-                            error = %e,
+                        tracing::debug!(pr = pr_event.number, "Draft PR was never deployed");
+                        return Ok(());
+                    }
+
+                    // Nor does a PR opened while previews were disabled
+                    if latest.is_none() && !deploy_config.previews_enabled() {
+                        tracing::debug!(
                             pr = pr_event.number,
-                            "Failed to delete PR comment tracking"
+                            "PR previews disabled for repository"
                         );
+                        return Ok(());
                     }
 
-                    tracing::info!(
-                        job_id = %job.job_id,
-                        pr = pr_event.number,
-                        zone = %zone,
-                        "Dispatched cleanup job"
-                    );
+                    let configured_domain = deploy_config.resolve_pr_domain(repo, pr_event.number);
+                    let grace_period = state
+                        .config
+                        .pr_cleanup_grace_period
+                        .filter(|_| pr_event.action == PullRequestAction::Closed);
+                    match grace_period {
+                        Some(delay) => {
+                            let pr_number = pr_event.number;
+                            let (task_state, org_name, repo_name, zone_name) =
+                                (state.clone(), org.to_string(), repo.clone(), zone.clone());
+                            state.pending_cleanups.schedule(
+                                org,
+                                repo,
+                                pr_number,
+                                delay,
+                                async move {
+                                    if let Err(e) = cleanup_pr_preview(
+                                        &task_state,
+                                        &worker,
+                                        &org_name,
+                                        &repo_name,
+                                        pr_number,
+                                        &zone_name,
+                                        configured_domain,
+                                    )
+                                    .await
+                                    {
+                                        tracing::error!(
+                                            error = %e,
+                                            org = org_name,
+                                            repo = repo_name,
+                                            pr = pr_number,
+                                            "Scheduled PR preview cleanup failed"
+                                        );
+                                    }
+                                },
+                            );
+                            tracing::info!(
+                                pr = pr_event.number,
+                                grace_secs = delay.as_secs(),
+                                "Scheduled PR preview cleanup"
+                            );
+                        }
+                        None => {
+                            cleanup_pr_preview(
+                                state,
+                                &worker,
+                                org,
+                                repo,
+                                pr_event.number,
+                                zone,
+                                configured_domain,
+                            )
+                            .await?;
+                        }
+                    }
                 }
-                _ => {
+                PreviewAction::Ignore => {
                     tracing::debug!(action = ?pr_event.action, "Ignoring PR action");
                 }
             }
@@ -312,6 +553,24 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
                 "Processing main branch push"
             );
 
+            // Only clone from the configured GitHub host, in canonical form
+            let repo_url = match push_event
+                .repository
+                .https_clone_url(&state.config.github_host)
+            {
+                Ok(url) => url,
+                Err(e) => {
+                    tracing::warn!(org, repo, error = %e, "Rejected repository clone URL");
+                    return Ok(());
+                }
+            };
+
+            // Paused repos keep their config and history but don't deploy
+            if db::is_repo_paused(&state.db, org, repo).await? {
+                tracing::info!(org, repo, "Deployments paused for repository, skipping");
+                return Ok(());
+            }
+
             // Get installation ID
             let installation_id = push_event
                 .installation
@@ -321,13 +580,21 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
 
             // Get installation token
             let token = state
-                .github_app
+                .github_apps
                 .get_installation_token(&state.http_client, installation_id)
                 .await?;
 
             // Fetch deploy config
-            let deploy_config =
-                fetch_deploy_config(&state.http_client, &token.token, org, repo).await?;
+            let deploy_config = fetch_deploy_config(
+                &state.http_client,
+                &state.config.github_api,
+                &state.config_cache,
+                &state.config.default_deploy_configs,
+                &token.token,
+                org,
+                repo,
+            )
+            .await?;
 
             let deploy_config = match deploy_config {
                 Some(config) if config.is_deployable() => config,
@@ -360,6 +627,14 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
                 );
             }
 
+            if !auth.can_deploy_repo(repo) {
+                anyhow::bail!(
+                    "Repository '{}/{}' is not allowed by the org's repo filters",
+                    org,
+                    repo
+                );
+            }
+
             // Resolve main branch domain
             let main_domain = deploy_config.resolve_domain(repo).ok_or_else(|| {
                 anyhow::anyhow!("Cannot resolve domain - no domain or pattern configured")
@@ -374,45 +649,102 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
                 );
             }
 
-            // Get worker for this zone
-            let worker = db::get_worker(&state.db, zone)
-                .await?
-                .ok_or_else(|| anyhow::anyhow!("No worker configured for zone: {}", zone))?;
+            let aliases = deploy_config.resolve_aliases(&main_domain);
+            if let Some(alias) = aliases.iter().find(|alias| !auth.can_use_domain(alias)) {
+                anyhow::bail!(
+                    "Organization '{}' is not authorized to use domain '{}'",
+                    org,
+                    alias
+                );
+            }
+
+            // Enforce the org's deployment quotas (no PR to comment on)
+            let site_id = generate_site_id(org, repo, None);
+            if let Err(exceeded) = check_org_quota(state, &auth, &site_id).await? {
+                tracing::warn!(
+                    org,
+                    repo,
+                    commit = &push_event.after,
+                    reason = %exceeded,
+                    "Deployment rejected by org quota"
+                );
+                return Ok(());
+            }
+
+            // Get the zone's workers, mirrors included
+            let workers = deployment_workers(state, zone, None).await?;
+            if workers.is_empty() {
+                anyhow::bail!("No worker configured for zone: {}", zone);
+            }
 
             // Generate job_id
             let job_id = Uuid::new_v4();
 
             // Dispatch build job
-            let job = BuildJob {
+            let job = BuildJob::from_deploy_config(
+                BuildTarget {
+                    job_id,
+                    repo_url,
+                    git_token: token.token,
+                    branch: push_event.branch_name().unwrap_or("main").to_string(),
+                    commit_sha: push_event.after.clone(),
+                    pr_number: None,
+                    site_id: None,
+                    domain: main_domain.clone(),
+                    aliases,
+                    callback_url: format!("{}/api/status", state.config.callback_base_url),
+                    repo_name: repo.to_string(),
+                    org_name: org.to_string(),
+                    environment: Some(zone.clone()),
+                    preview: false,
+                },
+                &deploy_config,
+            );
+
+            let deployment = NewDeployment {
                 job_id,
-                repo_url: push_event.repository.clone_url.clone(),
-                git_token: token.token,
-                branch: push_event.branch_name().unwrap_or("main").to_string(),
-                commit_sha: push_event.after.clone(),
+                org,
+                repo,
                 pr_number: None,
-                domain: main_domain.clone(),
-                site_type: deploy_config.build_type.unwrap_or_default(),
-                callback_url: format!("{}/api/status", state.config.callback_base_url),
-                repo_name: repo.to_string(),
-                org_name: org.to_string(),
-                subdomain: deploy_config.subdomain.clone(),
+                site_id: &site_id,
+                zone,
+                domain: &main_domain,
+                commit_sha: &push_event.after,
+                deployment_type: DeploymentType::Webhook,
+                dry_run: job.dry_run,
             };
 
-            dispatch_build_job(
-                &state.http_client,
-                &worker.endpoint,
-                &state.config.worker_shared_secret,
-                &job,
-            )
-            .await?;
+            if state.config.requires_approval(zone) {
+                // Held until an operator approves it via the admin API
+                db::record_pending_approval(
+                    &state.db,
+                    &deployment,
+                    installation_id,
+                    &job,
+                    state.config.approval_timeout,
+                )
+                .await?;
 
-            tracing::info!(
-                job_id = %job_id,
-                commit = &push_event.after,
-                domain = %main_domain,
-                zone = %zone,
-                "Dispatched main branch build job"
-            );
+                tracing::info!(
+                    job_id = %job_id,
+                    commit = &push_event.after,
+                    domain = %main_domain,
+                    zone = %zone,
+                    "Main branch deployment awaiting approval"
+                );
+            } else {
+                dispatch_to_workers(state, &workers, &job).await?;
+
+                tracing::info!(
+                    job_id = %job_id,
+                    commit = &push_event.after,
+                    domain = %main_domain,
+                    zone = %zone,
+                    "Dispatched main branch build job"
+                );
+
+                db::record_deployment(&state.db, &deployment).await?;
+            }
 
             // Store deployment info for status updates
             // Push events don't have PR comments, so comment_id is None
@@ -427,6 +759,47 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
             )
             .await?;
         }
+        WebhookEvent::Installation(installation_event) => {
+            let org = &installation_event.installation.account.login;
+
+            if installation_event.action != InstallationAction::Deleted {
+                tracing::debug!(
+                    org,
+                    action = ?installation_event.action,
+                    "Ignoring installation action"
+                );
+                return Ok(());
+            }
+
+            tracing::info!(org, "App uninstalled, cleaning up all deployments");
+
+            let deployments = db::list_active_deployments_for_org(&state.db, org).await?;
+            cleanup_deployments(state, &deployments).await;
+        }
+        WebhookEvent::Repository(repo_event) => {
+            let org = repo_event.repository.org_name();
+            let repo = &repo_event.repository.name;
+
+            if !repo_event.action.is_removal() {
+                tracing::debug!(
+                    org,
+                    repo,
+                    action = ?repo_event.action,
+                    "Ignoring repository action"
+                );
+                return Ok(());
+            }
+
+            tracing::info!(
+                org,
+                repo,
+                action = ?repo_event.action,
+                "Repository removed, cleaning up deployments"
+            );
+
+            let deployments = db::list_active_deployments(&state.db, org, repo).await?;
+            cleanup_deployments(state, &deployments).await;
+        }
         WebhookEvent::Ping => {
             tracing::info!("Received ping event");
         }
@@ -438,6 +811,89 @@ async fn process_webhook_event(state: &AppState, event: WebhookEvent) -> anyhow:
     Ok(())
 }
 
+/// What a pull request event means for its preview deployment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PreviewAction {
+    Deploy,
+    Cleanup,
+    Ignore,
+}
+
+/// Apply a repository's deploy config to a pull request event
+///
+/// With PR previews disabled nothing is deployed, but closing a PR still
+/// cleans up a preview left from before they were turned off.
+fn repo_preview_action(event: &PullRequestEvent, config: &DeployConfig) -> PreviewAction {
+    match preview_action(
+        event,
+        config.require_label.as_deref(),
+        config.skip_drafts.unwrap_or(false),
+    ) {
+        PreviewAction::Deploy if !config.previews_enabled() => PreviewAction::Ignore,
+        action => action,
+    }
+}
+
+/// Decide whether a pull request event deploys, cleans up or is ignored
+///
+/// With `require_label` set, a preview only exists while the PR carries the
+/// label: adding it deploys, removing it cleans up. With `skip_drafts`, draft
+/// PRs aren't deployed until they are marked ready for review.
+fn preview_action(
+    event: &PullRequestEvent,
+    require_label: Option<&str>,
+    skip_drafts: bool,
+) -> PreviewAction {
+    let is_required = |label: &str| event.label.as_ref().is_some_and(|l| l.is(label));
+    let labelled = require_label.is_none_or(|label| event.pull_request.has_label(label));
+
+    match (event.action, require_label) {
+        (PullRequestAction::Closed, _) => PreviewAction::Cleanup,
+        (PullRequestAction::ReadyForReview, _) if skip_drafts && labelled => PreviewAction::Deploy,
+        _ if skip_drafts && event.pull_request.draft => PreviewAction::Ignore,
+        (
+            PullRequestAction::Opened
+            | PullRequestAction::Synchronize
+            | PullRequestAction::Reopened,
+            None,
+        ) => PreviewAction::Deploy,
+        (
+            PullRequestAction::Opened
+            | PullRequestAction::Synchronize
+            | PullRequestAction::Reopened,
+            Some(label),
+        ) if event.pull_request.has_label(label) => PreviewAction::Deploy,
+        (PullRequestAction::Labeled, Some(label)) if is_required(label) => PreviewAction::Deploy,
+        (PullRequestAction::Unlabeled, Some(label)) if is_required(label) => PreviewAction::Cleanup,
+        _ => PreviewAction::Ignore,
+    }
+}
+
+/// Whether a PR's preview is live at `head_sha`, so reopening the PR needs no rebuild
+fn is_current_preview(deployment: &DeploymentHistory, head_sha: &str) -> bool {
+    deployment.status == JobStatus::Success.to_string() && deployment.commit_sha == head_sha
+}
+
+/// Check whether deploying `site_id` fits within the org's quotas
+///
+/// Orgs without quotas skip the usage query entirely.
+async fn check_org_quota(
+    state: &AppState,
+    auth: &AuthorizedOrg,
+    site_id: &str,
+) -> anyhow::Result<Result<(), QuotaExceeded>> {
+    if auth.max_active_deployments.is_none() && auth.max_total_mb.is_none() {
+        return Ok(Ok(()));
+    }
+
+    let usage = db::get_org_usage(&state.db, &auth.github_org).await?;
+    let already_active = db::get_latest_site_deployment(&state.db, site_id)
+        .await?
+        .is_some_and(|deployment| deployment.status != "cleaned");
+
+    Ok(auth.check_quota(&usage, already_active))
+}
+
 /// Store deployment context for status update correlation
 ///
 /// This stores the minimum info needed to update GitHub comments when
@@ -465,3 +921,544 @@ async fn store_deployment_context(
 
     Ok(())
 }
+
+/// Clean up a PR's preview: dispatch the cleanup job, mark the site cleaned
+/// and forget the PR comment
+///
+/// `configured_domain` is used when the PR was never deployed; otherwise the
+/// domain that was actually deployed is cleaned up, as the domain pattern may
+/// have changed since.
+async fn cleanup_pr_preview(
+    state: &AppState,
+    worker: &Worker,
+    org: &str,
+    repo: &str,
+    pr_number: u32,
+    zone: &str,
+    configured_domain: Option<String>,
+) -> anyhow::Result<()> {
+    let site_id = generate_site_id(org, repo, Some(pr_number));
+    let pr_domain = match db::get_latest_site_deployment(&state.db, &site_id).await? {
+        Some(deployment) => Some(deployment.domain),
+        None => configured_domain,
+    };
+
+    let job = CleanupJob {
+        job_id: Uuid::new_v4(),
+        site_id,
+        callback_url: format!("{}/api/status", state.config.callback_base_url),
+        domain: pr_domain,
+        environment: Some(zone.to_string()),
+    };
+
+    dispatch_cleanup_job(
+        &state.http_client,
+        &worker.endpoint,
+        state.config.worker_secrets.signer(&worker.environment)?,
+        &job,
+    )
+    .await?;
+
+    db::mark_site_cleaned(&state.db, &job.site_id).await?;
+
+    // Clean up the PR comment tracking
+    if let Err(e) = db::delete_pr_comment(&state.db, org, repo, pr_number).await {
+        tracing::warn!(
+            error = %e,
+            pr = pr_number,
+            "Failed to delete PR comment tracking"
+        );
+    }
+
+    tracing::info!(
+        job_id = %job.job_id,
+        pr = pr_number,
+        zone = %zone,
+        "Dispatched cleanup job"
+    );
+    Ok(())
+}
+
+/// Dispatch cleanup jobs for a set of active deployments
+///
+/// Failures are logged per site so one unreachable worker doesn't block
+/// cleanup of sites in other zones.
+async fn cleanup_deployments(state: &AppState, deployments: &[DeploymentHistory]) {
+    let callback_url = format!("{}/api/status", state.config.callback_base_url);
+
+    for deployment in deployments {
+        if let Err(e) = cleanup_deployment(state, deployment, &callback_url).await {
+            tracing::error!(
+                error = %e,
+                site_id = %deployment.site_id,
+                zone = %deployment.zone,
+                "Failed to clean up deployment"
+            );
+        }
+    }
+}
+
+/// Dispatch a deployment's cleanup to its workers and mark the site cleaned,
+/// returning the cleanup job's ID
+pub(crate) async fn cleanup_deployment(
+    state: &AppState,
+    deployment: &DeploymentHistory,
+    callback_url: &str,
+) -> anyhow::Result<Uuid> {
+    // Main branch sites live on the zone's mirrors too
+    let pr_number = deployment.pr_number.map(|pr| pr as u32);
+    let workers = deployment_workers(state, &deployment.zone, pr_number).await?;
+    if workers.is_empty() {
+        anyhow::bail!("No worker configured for zone: {}", deployment.zone);
+    }
+
+    let job = cleanup_job_for(deployment, callback_url);
+
+    // Every worker gets the cleanup, but the site is only marked cleaned once
+    // all of them took it; otherwise it stays active for a later cleanup to
+    // reach the rest
+    let signer = state.config.worker_secrets.signer(&deployment.zone)?;
+    let results =
+        futures::future::join_all(workers.iter().map(|worker| {
+            dispatch_cleanup_job(&state.http_client, &worker.endpoint, signer, &job)
+        }))
+        .await;
+    collect_results(&workers, results, "dispatch cleanup")?;
+
+    db::mark_site_cleaned(&state.db, &deployment.site_id).await?;
+
+    tracing::info!(
+        job_id = %job.job_id,
+        site_id = %job.site_id,
+        zone = %deployment.zone,
+        "Dispatched cleanup job"
+    );
+
+    Ok(job.job_id)
+}
+
+/// Build a cleanup job for a recorded deployment
+fn cleanup_job_for(deployment: &DeploymentHistory, callback_url: &str) -> CleanupJob {
+    CleanupJob {
+        job_id: Uuid::new_v4(),
+        site_id: deployment.site_id.clone(),
+        callback_url: callback_url.to_string(),
+        domain: Some(deployment.domain.clone()),
+        environment: Some(deployment.zone.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn main_deployment() -> DeploymentHistory {
+        DeploymentHistory {
+            id: 1,
+            job_id: Uuid::new_v4(),
+            github_org: "nullisLabs".to_string(),
+            github_repo: "website".to_string(),
+            pr_number: None,
+            site_id: "nullislabs-website".to_string(),
+            zone: "nullislabs".to_string(),
+            domain: "nullislabs.io".to_string(),
+            commit_sha: "abc123".to_string(),
+            status: "success".to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            started_at: None,
+            completed_at: None,
+            size_bytes: None,
+            deployment_type: "webhook".to_string(),
+            dry_run: false,
+        }
+    }
+
+    #[test]
+    fn test_cleanup_job_for_main_deployment() {
+        let deployment = main_deployment();
+        let job = cleanup_job_for(&deployment, "https://central.example.com/api/status");
+
+        assert_eq!(job.site_id, "nullislabs-website");
+        assert_eq!(job.domain.as_deref(), Some("nullislabs.io"));
+        assert_eq!(job.callback_url, "https://central.example.com/api/status");
+        assert_eq!(job.environment.as_deref(), Some("nullislabs"));
+        assert_ne!(job.job_id, deployment.job_id);
+    }
+
+    #[test]
+    fn test_is_current_preview() {
+        let deployment = main_deployment();
+        assert!(is_current_preview(&deployment, "abc123"));
+        assert!(!is_current_preview(&deployment, "def456"));
+
+        let failed = DeploymentHistory {
+            status: "failed".to_string(),
+            ..main_deployment()
+        };
+        assert!(!is_current_preview(&failed, "abc123"));
+    }
+
+    /// Parse a pull request event with the given action and labels
+    fn pr_event(action: &str, labels: &[&str], label: Option<&str>) -> PullRequestEvent {
+        let payload = serde_json::json!({
+            "action": action,
+            "number": 42,
+            "label": label.map(|name| serde_json::json!({ "name": name })),
+            "pull_request": {
+                "head": { "ref": "feature-branch", "sha": "abc123" },
+                "labels": labels.iter().map(|name| serde_json::json!({ "name": name })).collect::<Vec<_>>(),
+            },
+            "repository": {
+                "name": "website",
+                "full_name": "nullisLabs/website",
+                "clone_url": "https://github.com/nullisLabs/website.git",
+                "owner": { "login": "nullisLabs" }
+            }
+        });
+
+        match parse_webhook_event("pull_request", payload.to_string().as_bytes()).unwrap() {
+            WebhookEvent::PullRequest(event) => event,
+            other => panic!("Expected PullRequest event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_preview_action_without_required_label() {
+        for action in ["opened", "synchronize", "reopened"] {
+            assert_eq!(
+                preview_action(&pr_event(action, &[], None), None, false),
+                PreviewAction::Deploy
+            );
+        }
+        assert_eq!(
+            preview_action(&pr_event("closed", &[], None), None, false),
+            PreviewAction::Cleanup
+        );
+
+        // Label changes don't matter without a required label
+        assert_eq!(
+            preview_action(
+                &pr_event("labeled", &["preview"], Some("preview")),
+                None,
+                false
+            ),
+            PreviewAction::Ignore
+        );
+        assert_eq!(
+            preview_action(&pr_event("unlabeled", &[], Some("preview")), None, false),
+            PreviewAction::Ignore
+        );
+        assert_eq!(
+            preview_action(&pr_event("edited", &[], None), None, false),
+            PreviewAction::Ignore
+        );
+    }
+
+    #[test]
+    fn test_preview_action_label_gated_dispatch() {
+        let required = Some("preview");
+
+        // Pushes only deploy while the label is present
+        for action in ["opened", "synchronize", "reopened"] {
+            assert_eq!(
+                preview_action(&pr_event(action, &["bug"], None), required, false),
+                PreviewAction::Ignore
+            );
+            assert_eq!(
+                preview_action(
+                    &pr_event(action, &["bug", "Preview"], None),
+                    required,
+                    false
+                ),
+                PreviewAction::Deploy
+            );
+        }
+
+        // Adding the label deploys; adding another label doesn't
+        assert_eq!(
+            preview_action(
+                &pr_event("labeled", &["preview"], Some("preview")),
+                required,
+                false
+            ),
+            PreviewAction::Deploy
+        );
+        assert_eq!(
+            preview_action(
+                &pr_event("labeled", &["preview", "bug"], Some("bug")),
+                required,
+                false
+            ),
+            PreviewAction::Ignore
+        );
+    }
+
+    #[test]
+    fn test_preview_action_label_removal_cleans_up() {
+        let required = Some("preview");
+
+        assert_eq!(
+            preview_action(
+                &pr_event("unlabeled", &[], Some("preview")),
+                required,
+                false
+            ),
+            PreviewAction::Cleanup
+        );
+        assert_eq!(
+            preview_action(
+                &pr_event("unlabeled", &["preview"], Some("bug")),
+                required,
+                false
+            ),
+            PreviewAction::Ignore
+        );
+
+        // Closing always cleans up, labelled or not
+        assert_eq!(
+            preview_action(&pr_event("closed", &[], None), required, false),
+            PreviewAction::Cleanup
+        );
+    }
+
+    fn draft_event(action: &str, labels: &[&str], draft: bool) -> PullRequestEvent {
+        let mut event = pr_event(action, labels, None);
+        event.pull_request.draft = draft;
+        event
+    }
+
+    #[test]
+    fn test_preview_action_skips_drafts() {
+        // Drafts are ignored until they are ready
+        for action in ["opened", "synchronize", "reopened"] {
+            assert_eq!(
+                preview_action(&draft_event(action, &[], true), None, true),
+                PreviewAction::Ignore
+            );
+            assert_eq!(
+                preview_action(&draft_event(action, &[], false), None, true),
+                PreviewAction::Deploy
+            );
+            // Without skip_drafts, drafts deploy like any PR
+            assert_eq!(
+                preview_action(&draft_event(action, &[], true), None, false),
+                PreviewAction::Deploy
+            );
+        }
+
+        // Closing is still a cleanup; a never-deployed draft is skipped there
+        assert_eq!(
+            preview_action(&draft_event("closed", &[], true), None, true),
+            PreviewAction::Cleanup
+        );
+    }
+
+    #[test]
+    fn test_preview_action_ready_for_review_deploys() {
+        assert_eq!(
+            preview_action(&draft_event("ready_for_review", &[], false), None, true),
+            PreviewAction::Deploy
+        );
+
+        // A required label still applies
+        let required = Some("preview");
+        assert_eq!(
+            preview_action(&draft_event("ready_for_review", &[], false), required, true),
+            PreviewAction::Ignore
+        );
+        assert_eq!(
+            preview_action(
+                &draft_event("ready_for_review", &["preview"], false),
+                required,
+                true
+            ),
+            PreviewAction::Deploy
+        );
+        // Adding the label to a draft waits for it to be ready
+        assert_eq!(
+            preview_action(
+                &{
+                    let mut event = pr_event("labeled", &["preview"], Some("preview"));
+                    event.pull_request.draft = true;
+                    event
+                },
+                required,
+                true
+            ),
+            PreviewAction::Ignore
+        );
+
+        // Drafts were already deployed without skip_drafts
+        assert_eq!(
+            preview_action(&draft_event("ready_for_review", &[], false), None, false),
+            PreviewAction::Ignore
+        );
+    }
+
+    #[test]
+    fn test_previews_disabled_keeps_main_deploys() {
+        let config = DeployConfig {
+            zone: Some("nullislabs".to_string()),
+            pr_previews_enabled: Some(false),
+            ..DeployConfig::default()
+        };
+
+        for action in ["opened", "synchronize", "reopened"] {
+            assert_eq!(
+                repo_preview_action(&pr_event(action, &[], None), &config),
+                PreviewAction::Ignore
+            );
+        }
+        // Previews deployed before the switch are still cleaned up
+        assert_eq!(
+            repo_preview_action(&pr_event("closed", &[], None), &config),
+            PreviewAction::Cleanup
+        );
+        // Pushes to main deploy with the same config
+        assert!(config.is_deployable());
+
+        let enabled = DeployConfig {
+            pr_previews_enabled: None,
+            ..config
+        };
+        assert_eq!(
+            repo_preview_action(&pr_event("opened", &[], None), &enabled),
+            PreviewAction::Deploy
+        );
+    }
+
+    fn webhook_headers(event_type: &str, content_type: &str, body: &[u8]) -> HeaderMap {
+        signed_headers("test-webhook-secret", event_type, content_type, body)
+    }
+
+    fn signed_headers(
+        secret: &str,
+        event_type: &str,
+        content_type: &str,
+        body: &[u8],
+    ) -> HeaderMap {
+        use hmac::{Hmac, Mac};
+
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-hub-signature-256", signature.parse().unwrap());
+        headers.insert("x-github-event", event_type.parse().unwrap());
+        headers.insert("content-type", content_type.parse().unwrap());
+        headers
+    }
+
+    async fn webhook_status(headers: HeaderMap, body: &'static [u8]) -> StatusCode {
+        handle_webhook(
+            State(AppState::for_tests()),
+            headers,
+            Bytes::from_static(body),
+        )
+        .await
+        .into_response()
+        .status()
+    }
+
+    #[tokio::test]
+    async fn test_webhook_rejects_non_json_payload() {
+        let body = b"payload=%7B%22zen%22%3A%22hi%22%7D";
+        let headers = webhook_headers("ping", "application/x-www-form-urlencoded", body);
+
+        assert_eq!(
+            webhook_status(headers, body).await,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_rejects_unsupported_event() {
+        let body = b"{}";
+        let headers = webhook_headers("fork", "application/json", body);
+
+        assert_eq!(webhook_status(headers, body).await, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_acks_ignored_event() {
+        // Not a payload we could parse, and never parsed
+        let body = b"{\"action\": \"requested\"}";
+        let headers = webhook_headers("check_suite", "application/json; charset=utf-8", body);
+        assert_eq!(webhook_status(headers, body).await, StatusCode::OK);
+
+        // Still only for signed requests
+        let mut headers = webhook_headers("check_suite", "application/json", body);
+        headers.insert("x-hub-signature-256", "sha256=wrong".parse().unwrap());
+        assert_eq!(
+            webhook_status(headers, body).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_verified_with_app_secret() {
+        use crate::central::github::{GitHubApi, GitHubApp, GitHubApps};
+        use crate::test_util::TEST_PRIVATE_KEY;
+
+        let app = |app_id| GitHubApp::new(app_id, TEST_PRIVATE_KEY, GitHubApi::default()).unwrap();
+        let state = AppState {
+            github_apps: std::sync::Arc::new(
+                GitHubApps::new(vec![
+                    (app(111), "primary-secret".to_string()),
+                    (app(222), "second-secret".to_string()),
+                ])
+                .unwrap(),
+            ),
+            ..AppState::for_tests()
+        };
+        let status = |headers| async {
+            handle_webhook(State(state.clone()), headers, Bytes::from_static(b"{}"))
+                .await
+                .into_response()
+                .status()
+        };
+        let for_app = |secret: &str, app_id: &str| {
+            let mut headers = signed_headers(secret, "check_suite", "application/json", b"{}");
+            headers.insert(
+                "x-github-hook-installation-target-type",
+                "integration".parse().unwrap(),
+            );
+            headers.insert(
+                "x-github-hook-installation-target-id",
+                app_id.parse().unwrap(),
+            );
+            headers
+        };
+
+        assert_eq!(
+            status(for_app("second-secret", "222")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(for_app("primary-secret", "111")).await,
+            StatusCode::OK
+        );
+        // Another app's secret doesn't verify
+        assert_eq!(
+            status(for_app("primary-secret", "222")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(for_app("second-secret", "333")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        // Webhooks naming no app use the primary app's secret
+        assert_eq!(
+            status(signed_headers(
+                "primary-secret",
+                "check_suite",
+                "application/json",
+                b"{}"
+            ))
+            .await,
+            StatusCode::OK
+        );
+    }
+}
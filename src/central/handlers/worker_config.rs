@@ -0,0 +1,133 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::central::handlers::{WorkerSignature, worker_signature};
+use crate::central::server::AppState;
+
+/// Serve the settings assigned to a zone's workers
+///
+/// A GET has no body, so the worker signs the zone name instead; the zone's
+/// secret must have signed it.
+pub async fn get_worker_config(
+    State(state): State<AppState>,
+    Path(zone): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let Ok(WorkerSignature {
+        signature,
+        timestamp,
+    }) = worker_signature(&headers)
+    else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    let check =
+        state
+            .config
+            .worker_secrets
+            .verify(Some(&zone), zone.as_bytes(), signature, timestamp);
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, zone = %zone, "Invalid worker signature for config request");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    match state.config.worker_settings.get(&zone) {
+        Some(settings) => Json(settings.clone()).into_response(),
+        // Known zones without settings keep their local config
+        None if state.config.workers.contains_key(&zone) => {
+            Json(crate::shared::WorkerSettings::default()).into_response()
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("Unknown zone: {}", zone)})),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::config::CentralConfig;
+    use crate::shared::{WorkerSettings, auth::sign_request};
+
+    fn state() -> AppState {
+        let mut config = CentralConfig::for_tests();
+        config.worker_settings.insert(
+            "nullislabs".to_string(),
+            WorkerSettings {
+                cloudflare_tunnel_id: Some("tunnel-1".to_string()),
+                keep_releases: Some(5),
+                ..Default::default()
+            },
+        );
+        config
+            .workers
+            .insert("staging".to_string(), "http://staging:8080".to_string());
+        AppState {
+            config: Arc::new(config),
+            ..AppState::for_tests()
+        }
+    }
+
+    fn signed_headers(secret: &str, signed: &str) -> HeaderMap {
+        let (signature, timestamp) = sign_request(secret.as_bytes(), signed.as_bytes());
+        let mut headers = HeaderMap::new();
+        headers.insert("x-worker-signature", signature.parse().unwrap());
+        headers.insert(
+            "x-request-timestamp",
+            timestamp.to_string().parse().unwrap(),
+        );
+        headers
+    }
+
+    async fn config_response(zone: &str, headers: HeaderMap) -> (StatusCode, serde_json::Value) {
+        let response = get_worker_config(State(state()), Path(zone.to_string()), headers).await;
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_worker_config_served_to_signed_request() {
+        let (status, body) =
+            config_response("nullislabs", signed_headers("test-secret", "nullislabs")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({"cloudflare_tunnel_id": "tunnel-1", "keep_releases": 5})
+        );
+
+        // A configured worker without settings gets an empty set
+        let (status, body) =
+            config_response("staging", signed_headers("test-secret", "staging")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({}));
+
+        let (status, _) = config_response("acme", signed_headers("test-secret", "acme")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_worker_config_rejects_bad_signature() {
+        let (status, _) = config_response("nullislabs", HeaderMap::new()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) =
+            config_response("nullislabs", signed_headers("wrong", "nullislabs")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // A signature for another zone doesn't carry over
+        let (status, _) =
+            config_response("nullislabs", signed_headers("test-secret", "staging")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
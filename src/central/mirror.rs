@@ -0,0 +1,455 @@
+//! Main branch deployments mirrored across a zone's workers
+//!
+//! A zone configured with `--mirror` workers gets its main branch deployments
+//! built on the primary and every mirror, so losing one worker doesn't take
+//! the site down. Each worker gets the job with its worker ID, which it
+//! includes in its signed status updates, and the deployment's status follows
+//! the quorum: it
+//! succeeds once enough workers succeeded, and fails once too many failed
+//! for the quorum to be reached. Progress is taken from the primary only.
+
+use anyhow::Result;
+
+use crate::central::db::{self, Worker};
+use crate::central::dispatch::dispatch_build_job;
+use crate::central::server::AppState;
+use crate::shared::{BuildJob, JobStatus, StatusUpdate, auth::RequestSigner};
+
+/// Workers a deployment goes to: all of the zone's for main branch
+/// deployments, only the primary for PR previews
+pub async fn deployment_workers(
+    state: &AppState,
+    zone: &str,
+    pr_number: Option<u32>,
+) -> Result<Vec<Worker>> {
+    Ok(match pr_number {
+        None => db::get_workers(&state.db, zone).await?,
+        Some(_) => db::get_worker(&state.db, zone).await?.into_iter().collect(),
+    })
+}
+
+/// Dispatch a build job to a zone's workers, fanning out to its mirrors
+///
+/// A single worker gets the job as before. With mirrors, workers that can't
+/// be reached count as failed, and dispatch only fails if too few workers
+/// took the job for the quorum to be reached.
+pub async fn dispatch_to_workers(
+    state: &AppState,
+    workers: &[Worker],
+    job: &BuildJob,
+) -> Result<()> {
+    let (primary, mirrors) = match workers {
+        [] => anyhow::bail!("No worker to dispatch job {} to", job.job_id),
+        [primary, mirrors @ ..] => (primary, mirrors),
+    };
+    let signer = state.config.worker_secrets.signer(&primary.environment)?;
+
+    if mirrors.is_empty() {
+        return dispatch_build_job(&state.http_client, &primary.endpoint, signer, job).await;
+    }
+
+    let worker_ids: Vec<i32> = workers.iter().map(|worker| worker.id).collect();
+    db::record_mirror_dispatch(&state.db, job.job_id, &worker_ids).await?;
+
+    let results = fan_out(&state.http_client, workers, signer, job).await;
+
+    let mut dispatched = 0;
+    for (worker, result) in workers.iter().zip(results) {
+        match result {
+            Ok(()) => dispatched += 1,
+            Err(e) => {
+                tracing::warn!(
+                    job_id = %job.job_id,
+                    zone = %worker.environment,
+                    endpoint = %worker.endpoint,
+                    error = %e,
+                    "Failed to dispatch mirrored build job"
+                );
+                let error = format!("Dispatch failed: {:#}", e);
+                db::record_mirror_result(&state.db, job.job_id, worker.id, false, Some(&error))
+                    .await?;
+            }
+        }
+    }
+
+    let quorum = state.config.mirror_quorum(workers.len());
+    if dispatched < quorum {
+        anyhow::bail!(
+            "Dispatched to {} of {} workers, {} needed",
+            dispatched,
+            workers.len(),
+            quorum
+        );
+    }
+
+    tracing::info!(
+        job_id = %job.job_id,
+        zone = %primary.environment,
+        dispatched,
+        workers = workers.len(),
+        quorum,
+        "Dispatched mirrored build job"
+    );
+
+    Ok(())
+}
+
+/// Send a job to every worker at once, each tagged with its worker ID
+///
+/// Results are in the order of `workers`.
+async fn fan_out(
+    http_client: &reqwest::Client,
+    workers: &[Worker],
+    signer: RequestSigner<'_>,
+    job: &BuildJob,
+) -> Vec<Result<()>> {
+    let dispatches = workers.iter().map(|worker| {
+        let job = BuildJob {
+            worker_id: Some(worker.id),
+            ..job.clone()
+        };
+        async move { dispatch_build_job(http_client, &worker.endpoint, signer, &job).await }
+    });
+
+    futures::future::join_all(dispatches).await
+}
+
+/// Collect the results of sending a job to each of a deployment's workers
+///
+/// Every worker has had its chance by now, so failures are only logged here;
+/// any of them fails the whole, leaving the operation to be retried once the
+/// worker is back. Results are in the order of `workers`.
+pub fn collect_results<T>(
+    workers: &[Worker],
+    results: Vec<Result<T>>,
+    action: &str,
+) -> Result<Vec<T>> {
+    let mut failed = 0;
+    let mut collected = Vec::with_capacity(results.len());
+    for (worker, result) in workers.iter().zip(results) {
+        match result {
+            Ok(value) => collected.push(value),
+            Err(e) => {
+                tracing::warn!(
+                    zone = %worker.environment,
+                    endpoint = %worker.endpoint,
+                    error = %e,
+                    "Failed to {} on worker",
+                    action
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!(
+            "Failed to {} on {} of {} workers",
+            action,
+            failed,
+            workers.len()
+        );
+    }
+    Ok(collected)
+}
+
+/// A status update for a mirrored deployment that doesn't name its worker
+#[derive(Debug, thiserror::Error)]
+#[error("Status update for a mirrored deployment without a worker ID")]
+pub struct MissingWorkerId;
+
+/// Turn a worker's status update into the deployment's update, if it
+/// changes the deployment
+///
+/// Updates naming their worker go through the mirror quorum. Mirrored
+/// deployments reject updates that don't with [`MissingWorkerId`]; those of
+/// other deployments pass through.
+pub async fn quorum_update(
+    state: &AppState,
+    zone: Option<&str>,
+    update: StatusUpdate,
+) -> Result<Option<StatusUpdate>> {
+    match update.worker_id {
+        Some(worker_id) => mirror_update(state, zone, worker_id, update).await,
+        None => {
+            if db::list_mirror_results(&state.db, update.job_id)
+                .await?
+                .is_empty()
+            {
+                Ok(Some(update))
+            } else {
+                Err(MissingWorkerId.into())
+            }
+        }
+    }
+}
+
+/// Turn a status update from one of a mirrored job's workers into the
+/// deployment's update, if it changes the deployment
+///
+/// Progress passes through from the zone's primary until the quorum is
+/// decided, so it can't overwrite the final status. A final status is
+/// recorded for its worker and passes through only when it decides the
+/// quorum; everything else returns None.
+async fn mirror_update(
+    state: &AppState,
+    zone: Option<&str>,
+    worker_id: i32,
+    update: StatusUpdate,
+) -> Result<Option<StatusUpdate>> {
+    let Some(succeeded) = final_result(update.status) else {
+        let results: Vec<Option<bool>> = db::list_mirror_results(&state.db, update.job_id)
+            .await?
+            .iter()
+            .map(|result| result.succeeded)
+            .collect();
+        if !results.is_empty()
+            && mirror_outcome(&results, state.config.mirror_quorum(results.len())).is_some()
+        {
+            return Ok(None);
+        }
+
+        let primary = match zone {
+            Some(zone) => db::get_worker(&state.db, zone).await?,
+            None => None,
+        };
+        return Ok(primary
+            .is_some_and(|primary| primary.id == worker_id)
+            .then_some(update));
+    };
+
+    let Some(previous) = db::record_mirror_result(
+        &state.db,
+        update.job_id,
+        worker_id,
+        succeeded,
+        update.error_message.as_deref(),
+    )
+    .await?
+    else {
+        tracing::warn!(job_id = %update.job_id, worker_id, "Status update from a worker the job wasn't mirrored to");
+        return Ok(None);
+    };
+
+    let quorum = state.config.mirror_quorum(previous.len());
+    let before: Vec<Option<bool>> = previous.iter().map(|result| result.succeeded).collect();
+    let after: Vec<Option<bool>> = previous
+        .iter()
+        .map(|result| {
+            if result.worker_id == worker_id {
+                Some(succeeded)
+            } else {
+                result.succeeded
+            }
+        })
+        .collect();
+
+    match (
+        mirror_outcome(&before, quorum),
+        mirror_outcome(&after, quorum),
+    ) {
+        (None, Some(true)) => Ok(Some(update)),
+        (None, Some(false)) => {
+            let failed = after
+                .iter()
+                .filter(|result| **result == Some(false))
+                .count();
+            let error_message = format!(
+                "{} of {} workers failed: {}",
+                failed,
+                after.len(),
+                update.error_message.as_deref().unwrap_or("Unknown error")
+            );
+            Ok(Some(StatusUpdate {
+                error_message: Some(error_message),
+                ..update
+            }))
+        }
+        (Some(true), _) if !succeeded => {
+            tracing::warn!(
+                job_id = %update.job_id,
+                worker_id,
+                error = update.error_message.as_deref(),
+                "Mirror failed after the deployment reached its quorum"
+            );
+            Ok(None)
+        }
+        (Some(_), _) => Ok(None),
+        (None, None) => {
+            tracing::info!(
+                job_id = %update.job_id,
+                worker_id,
+                status = %update.status,
+                reported = after.iter().flatten().count(),
+                workers = after.len(),
+                quorum,
+                "Mirrored deployment waiting for quorum"
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Whether a status is a worker's final success (true) or failure (false)
+fn final_result(status: JobStatus) -> Option<bool> {
+    match status {
+        JobStatus::Success => Some(true),
+        JobStatus::Failed | JobStatus::Cancelled => Some(false),
+        _ => None,
+    }
+}
+
+/// Decide a mirrored deployment from its workers' results, None if undecided
+///
+/// It succeeds once `quorum` workers succeeded and fails once so many failed
+/// that the rest can't make up the quorum.
+fn mirror_outcome(results: &[Option<bool>], quorum: usize) -> Option<bool> {
+    let succeeded = results.iter().filter(|r| **r == Some(true)).count();
+    let failed = results.iter().filter(|r| **r == Some(false)).count();
+
+    if succeeded >= quorum {
+        Some(true)
+    } else if results.len() - failed < quorum {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn worker(id: i32, endpoint: &str) -> Worker {
+        Worker {
+            id,
+            environment: "nullislabs".to_string(),
+            endpoint: endpoint.to_string(),
+            enabled: true,
+            mirror: id != 1,
+            last_seen: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn job() -> BuildJob {
+        serde_json::from_value(serde_json::json!({
+            "job_id": uuid::Uuid::new_v4(),
+            "repo_url": "https://github.com/nullisLabs/website.git",
+            "git_token": "token",
+            "branch": "main",
+            "commit_sha": "abc1234",
+            "pr_number": null,
+            "domain": "nullislabs.io",
+            "site_type": "zola",
+            "callback_url": "https://central.example.com/api/status",
+            "repo_name": "website",
+            "org_name": "nullisLabs",
+            "subdomain": null,
+        }))
+        .unwrap()
+    }
+
+    async fn worker_server(status: u16, worker_id: i32) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/build"))
+            .and(body_partial_json(serde_json::json!({
+                "worker_id": worker_id
+            })))
+            .respond_with(ResponseTemplate::new(status))
+            .expect(1)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_dispatches_to_every_worker() {
+        let (primary, mirror) = (worker_server(202, 1).await, worker_server(202, 2).await);
+        let workers = [worker(1, &primary.uri()), worker(2, &mirror.uri())];
+
+        let results = fan_out(
+            &reqwest::Client::new(),
+            &workers,
+            RequestSigner::Secret("secret"),
+            &job(),
+        )
+        .await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_reports_partial_failure() {
+        let (primary, mirror) = (worker_server(202, 1).await, worker_server(503, 2).await);
+        let workers = [worker(1, &primary.uri()), worker(2, &mirror.uri())];
+
+        let results = fan_out(
+            &reqwest::Client::new(),
+            &workers,
+            RequestSigner::Secret("secret"),
+            &job(),
+        )
+        .await;
+
+        assert!(results[0].is_ok());
+        let error = results[1].as_ref().unwrap_err().to_string();
+        assert!(error.contains("503"), "{}", error);
+    }
+
+    #[test]
+    fn test_collect_results_fails_on_any_worker() {
+        let workers = [worker(1, "http://primary"), worker(2, "http://mirror")];
+
+        let collected = collect_results(&workers, vec![Ok(true), Ok(false)], "promote").unwrap();
+        assert_eq!(collected, vec![true, false]);
+
+        let error = collect_results(
+            &workers,
+            vec![Ok(true), Err(anyhow::anyhow!("unreachable"))],
+            "promote",
+        )
+        .unwrap_err()
+        .to_string();
+        assert_eq!(error, "Failed to promote on 1 of 2 workers");
+    }
+
+    #[test]
+    fn test_mirror_outcome_follows_quorum() {
+        // Undecided while the quorum can still go either way
+        assert_eq!(mirror_outcome(&[None, None], 1), None);
+        assert_eq!(mirror_outcome(&[Some(false), None], 1), None);
+        assert_eq!(mirror_outcome(&[Some(true), None, None], 2), None);
+
+        // One success is enough for a quorum of one, despite a failure
+        assert_eq!(mirror_outcome(&[Some(false), Some(true)], 1), Some(true));
+        assert_eq!(mirror_outcome(&[Some(true), None], 1), Some(true));
+        assert_eq!(
+            mirror_outcome(&[Some(true), Some(true), None], 2),
+            Some(true)
+        );
+
+        // Fails once the remaining workers can't make up the quorum
+        assert_eq!(mirror_outcome(&[Some(false), Some(false)], 1), Some(false));
+        assert_eq!(mirror_outcome(&[Some(false), None], 2), Some(false));
+        assert_eq!(
+            mirror_outcome(&[Some(true), Some(false), Some(false)], 2),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_only_final_statuses_count() {
+        assert_eq!(final_result(JobStatus::Success), Some(true));
+        assert_eq!(final_result(JobStatus::Failed), Some(false));
+        assert_eq!(final_result(JobStatus::Cancelled), Some(false));
+        assert_eq!(final_result(JobStatus::Building), None);
+        assert_eq!(final_result(JobStatus::Pending), None);
+    }
+}
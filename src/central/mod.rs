@@ -1,13 +1,20 @@
 use crate::config::CentralConfig;
 use anyhow::Result;
 
+pub mod admin_keys;
 pub mod db;
-mod deploy_config;
+pub mod deploy_config;
 mod dispatch;
-mod github;
-mod handlers;
-mod server;
-mod worker_monitor;
+pub mod github;
+pub mod handlers;
+mod mirror;
+pub mod outbox;
+pub mod pending_cleanup;
+mod preview_expiry;
+mod reconciler;
+pub mod secrets;
+pub mod server;
+pub mod worker_monitor;
 
 /// Run the Central orchestrator
 pub async fn run(config: CentralConfig) -> Result<()> {
@@ -0,0 +1,353 @@
+//! Delivery of deployment events to external systems
+//!
+//! Every deployment status change queues an event in the database outbox, in
+//! the same transaction as the change: recording a deployment, worker status
+//! updates (see `db::update_deployment_status`), approval and its expiry, and
+//! cleanup. A recorded deployment's first event has no previous status.
+//! This background task delivers queued events to the configured sinks and
+//! retries failed deliveries with backoff, so no event is lost to a crash or
+//! an unreachable sink. Delivery is at least once: if any sink fails, the
+//! event goes to every sink again, so receivers should deduplicate on `id`.
+
+use std::fmt;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::central::db::{self, OutboxEvent};
+
+/// Interval between checks for events due for delivery
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Events claimed per check
+const OUTBOX_BATCH_SIZE: i64 = 50;
+
+/// How long a claimed event is left alone before it's retried, in case
+/// Central stops mid-delivery
+const OUTBOX_LEASE: Duration = Duration::from_secs(300);
+
+/// Delay before the first retry, doubling per failed delivery
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(10);
+
+/// Longest delay between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(3600);
+
+/// Where deployment events are delivered
+#[derive(Clone, PartialEq, Eq)]
+pub enum EventSink {
+    /// POST each event as JSON
+    Webhook(Url),
+    /// Post a one-line summary to a Slack incoming webhook
+    Slack(Url),
+}
+
+// The URLs commonly embed a token, so they stay out of logs
+impl fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EventSink::{}(..)", self.name())
+    }
+}
+
+impl EventSink {
+    /// Parse a sink URL, which must be http or https
+    pub fn parse_url(url: &str) -> Result<Url> {
+        let url = Url::parse(url.trim()).context("Invalid event sink URL")?;
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("Event sink URL must be http or https");
+        }
+        Ok(url)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Webhook(_) => "Webhook",
+            Self::Slack(_) => "Slack",
+        }
+    }
+
+    /// Deliver one event, failing unless the sink accepted it
+    async fn deliver(&self, http_client: &reqwest::Client, event: &OutboxEvent) -> Result<()> {
+        let request = match self {
+            Self::Webhook(url) => http_client
+                .post(url.clone())
+                .header("X-Catapult-Event-Id", event.id)
+                .json(&WebhookEvent {
+                    id: event.id,
+                    kind: DEPLOYMENT_STATUS_EVENT,
+                    created_at: event.created_at,
+                    data: &event.payload,
+                }),
+            Self::Slack(url) => http_client
+                .post(url.clone())
+                .json(&serde_json::json!({ "text": slack_message(&event.payload) })),
+        };
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("{} sink unreachable", self.name()))?;
+        if !response.status().is_success() {
+            anyhow::bail!("{} sink returned status {}", self.name(), response.status());
+        }
+
+        Ok(())
+    }
+}
+
+/// Type of the events queued by deployment status changes
+const DEPLOYMENT_STATUS_EVENT: &str = "deployment.status";
+
+/// Body POSTed to webhook sinks
+#[derive(Debug, Serialize)]
+struct WebhookEvent<'a> {
+    /// Outbox ID, the same on every delivery of the event
+    id: i64,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    created_at: DateTime<Utc>,
+    data: &'a Value,
+}
+
+/// One-line summary of a deployment status event for chat
+fn slack_message(payload: &Value) -> String {
+    let field = |name: &str| payload.get(name).and_then(Value::as_str).unwrap_or("?");
+
+    let target = match payload.get("pr_number").and_then(Value::as_i64) {
+        Some(pr_number) => format!("PR #{}", pr_number),
+        None => field("zone").to_string(),
+    };
+
+    let change = match payload.get("previous_status").and_then(Value::as_str) {
+        Some(previous) => format!("{} → {}", previous, field("status")),
+        None => field("status").to_string(),
+    };
+
+    format!(
+        "Deployment of {}/{} ({}) to {}: {}",
+        field("org"),
+        field("repo"),
+        target,
+        field("domain"),
+        change,
+    )
+}
+
+/// Delay before retrying an event that failed `attempts` times
+fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(0, 16) as u32;
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(exponent))
+        .min(RETRY_MAX_DELAY)
+}
+
+/// Periodically deliver queued deployment events to `sinks`
+///
+/// Runs without sinks too, so the outbox doesn't grow while none are set.
+pub fn spawn_outbox_publisher(
+    db: PgPool,
+    http_client: reqwest::Client,
+    sinks: Vec<EventSink>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut poll_interval = tokio::time::interval(OUTBOX_POLL_INTERVAL);
+
+        loop {
+            poll_interval.tick().await;
+
+            if let Err(e) = publish_due_events(&db, &http_client, &sinks).await {
+                tracing::warn!(error = %e, "Failed to publish deployment events");
+            }
+        }
+    })
+}
+
+/// Deliver the events currently due, returning how many every sink accepted
+pub async fn publish_due_events(
+    db: &PgPool,
+    http_client: &reqwest::Client,
+    sinks: &[EventSink],
+) -> Result<usize> {
+    let events = db::claim_outbox_events(db, OUTBOX_BATCH_SIZE, OUTBOX_LEASE).await?;
+    let mut delivered = 0;
+
+    for event in events {
+        match deliver_to_all(http_client, sinks, &event).await {
+            Ok(()) => {
+                db::delete_outbox_event(db, event.id).await?;
+                delivered += 1;
+            }
+            Err(e) => {
+                let retry_in = retry_delay(event.attempts);
+                tracing::warn!(
+                    event_id = event.id,
+                    job_id = %event.job_id,
+                    attempts = event.attempts + 1,
+                    retry_in_secs = retry_in.as_secs(),
+                    error = %e,
+                    "Failed to deliver deployment event"
+                );
+                db::retry_outbox_event(db, event.id, retry_in, &format!("{:#}", e)).await?;
+            }
+        }
+    }
+
+    Ok(delivered)
+}
+
+/// Deliver an event to every sink, stopping at the first failure
+async fn deliver_to_all(
+    http_client: &reqwest::Client,
+    sinks: &[EventSink],
+    event: &OutboxEvent,
+) -> Result<()> {
+    for sink in sinks {
+        sink.deliver(http_client, event).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn event() -> OutboxEvent {
+        OutboxEvent {
+            id: 7,
+            job_id: Uuid::nil(),
+            payload: serde_json::json!({
+                "org": "nullislabs",
+                "repo": "website",
+                "pr_number": 42,
+                "zone": "nullislabs",
+                "domain": "pr-42.nullislabs.io",
+                "previous_status": "building",
+                "status": "success",
+            }),
+            attempts: 0,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn sink_url(server: &MockServer, sink_path: &str) -> Url {
+        EventSink::parse_url(&format!("{}{}", server.uri(), sink_path)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert!(EventSink::parse_url("https://hooks.example.com/catapult").is_ok());
+        assert!(EventSink::parse_url("ftp://hooks.example.com").is_err());
+        assert!(EventSink::parse_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_debug_hides_url() {
+        let sink = EventSink::Slack(
+            EventSink::parse_url("https://hooks.slack.com/services/T0/B0/s3cret").unwrap(),
+        );
+        assert!(!format!("{:?}", sink).contains("s3cret"));
+    }
+
+    #[test]
+    fn test_slack_message() {
+        assert_eq!(
+            slack_message(&event().payload),
+            "Deployment of nullislabs/website (PR #42) to pr-42.nullislabs.io: building → success"
+        );
+
+        let main = serde_json::json!({
+            "org": "nullislabs",
+            "repo": "website",
+            "pr_number": null,
+            "zone": "nullislabs",
+            "domain": "nullislabs.io",
+            "previous_status": "pending",
+            "status": "building",
+        });
+        assert_eq!(
+            slack_message(&main),
+            "Deployment of nullislabs/website (nullislabs) to nullislabs.io: pending → building"
+        );
+
+        // A newly recorded deployment has no previous status
+        let recorded = serde_json::json!({
+            "org": "nullislabs",
+            "repo": "website",
+            "pr_number": null,
+            "zone": "nullislabs",
+            "domain": "nullislabs.io",
+            "previous_status": null,
+            "status": "pending_approval",
+        });
+        assert_eq!(
+            slack_message(&recorded),
+            "Deployment of nullislabs/website (nullislabs) to nullislabs.io: pending_approval"
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(0), RETRY_BASE_DELAY);
+        assert_eq!(retry_delay(1), RETRY_BASE_DELAY * 2);
+        assert_eq!(retry_delay(3), RETRY_BASE_DELAY * 8);
+        assert_eq!(retry_delay(20), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(i32::MAX), RETRY_MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_deliver_to_webhook_and_slack() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/events"))
+            .and(header("x-catapult-event-id", "7"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/slack"))
+            .and(body_json(serde_json::json!({
+                "text": "Deployment of nullislabs/website (PR #42) to pr-42.nullislabs.io: building → success"
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sinks = [
+            EventSink::Webhook(sink_url(&server, "/events")),
+            EventSink::Slack(sink_url(&server, "/slack")),
+        ];
+        deliver_to_all(&reqwest::Client::new(), &sinks, &event())
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["id"], 7);
+        assert_eq!(body["type"], "deployment.status");
+        assert_eq!(body["data"]["status"], "success");
+    }
+
+    #[tokio::test]
+    async fn test_deliver_fails_on_rejected_event() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let sinks = [EventSink::Webhook(sink_url(&server, "/events"))];
+        let error = deliver_to_all(&reqwest::Client::new(), &sinks, &event())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("503"), "{}", error);
+    }
+}
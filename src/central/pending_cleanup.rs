@@ -0,0 +1,192 @@
+//! Delayed cleanup of closed PR previews
+//!
+//! A PR closed by accident is often reopened right away, and tearing its
+//! preview down only to rebuild it is wasted work. With a grace period set,
+//! closing a PR schedules the cleanup instead of running it, and reopening the
+//! PR within the window cancels it. Schedules live in memory only: a Central
+//! restart drops them, leaving the preview to the preview expiry sweep, which
+//! is why a grace period requires preview expiry to be enabled.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::task::AbortHandle;
+
+/// Cleanups waiting out their grace period, keyed by `{org}/{repo}#{pr}`
+#[derive(Clone, Default)]
+pub struct PendingCleanups {
+    tasks: Arc<Mutex<HashMap<String, PendingCleanup>>>,
+}
+
+struct PendingCleanup {
+    /// Tells a rescheduled cleanup apart from the one it replaced
+    generation: u64,
+    task: AbortHandle,
+}
+
+impl std::fmt::Debug for PendingCleanups {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PendingCleanups")
+            .field("pending", &self.tasks.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl PendingCleanups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(org: &str, repo: &str, pr_number: u32) -> String {
+        format!("{}/{}#{}", org, repo, pr_number).to_lowercase()
+    }
+
+    /// Run `cleanup` for a PR after `delay`, unless cancelled first
+    ///
+    /// Replaces a cleanup already scheduled for the PR.
+    pub fn schedule<F>(&self, org: &str, repo: &str, pr_number: u32, delay: Duration, cleanup: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let key = Self::key(org, repo, pr_number);
+        let mut tasks = self.tasks.lock().unwrap();
+        let generation = tasks.get(&key).map_or(0, |pending| pending.generation + 1);
+
+        // The wait counts from now, not from when the task first runs
+        let deadline = tokio::time::Instant::now() + delay;
+        let pending = self.clone();
+        let task_key = key.clone();
+        let task = tokio::spawn(async move {
+            tokio::time::sleep_until(deadline).await;
+
+            // Past this point the cleanup can no longer be cancelled
+            {
+                let mut tasks = pending.tasks.lock().unwrap();
+                if tasks
+                    .get(&task_key)
+                    .is_some_and(|pending| pending.generation == generation)
+                {
+                    tasks.remove(&task_key);
+                }
+            }
+            cleanup.await;
+        });
+
+        if let Some(replaced) = tasks.insert(
+            key,
+            PendingCleanup {
+                generation,
+                task: task.abort_handle(),
+            },
+        ) {
+            replaced.task.abort();
+        }
+    }
+
+    /// Cancel a PR's scheduled cleanup, returning whether one was pending
+    pub fn cancel(&self, org: &str, repo: &str, pr_number: u32) -> bool {
+        match self
+            .tasks
+            .lock()
+            .unwrap()
+            .remove(&Self::key(org, repo, pr_number))
+        {
+            Some(pending) => {
+                pending.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a cleanup is scheduled for the PR
+    #[cfg(test)]
+    pub fn is_pending(&self, org: &str, repo: &str, pr_number: u32) -> bool {
+        self.tasks
+            .lock()
+            .unwrap()
+            .contains_key(&Self::key(org, repo, pr_number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const GRACE: Duration = Duration::from_secs(300);
+
+    fn counting_cleanup(count: &Arc<AtomicUsize>) -> impl Future<Output = ()> + Send + 'static {
+        let count = count.clone();
+        async move {
+            count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Move the paused clock forward and let woken tasks run
+    async fn elapse(duration: Duration) {
+        tokio::time::advance(duration).await;
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cleanup_runs_after_grace_period() {
+        let pending = PendingCleanups::new();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        pending.schedule("nullisLabs", "website", 42, GRACE, counting_cleanup(&count));
+        assert!(pending.is_pending("nullislabs", "website", 42));
+
+        elapse(GRACE - Duration::from_secs(1)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert!(pending.is_pending("nullislabs", "website", 42));
+
+        elapse(Duration::from_secs(1)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(!pending.is_pending("nullisLabs", "website", 42));
+        assert!(!pending.cancel("nullisLabs", "website", 42));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reopen_cancels_cleanup() {
+        let pending = PendingCleanups::new();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        pending.schedule("nullisLabs", "website", 42, GRACE, counting_cleanup(&count));
+        pending.schedule("nullisLabs", "website", 43, GRACE, counting_cleanup(&count));
+        assert!(pending.cancel("NullisLabs", "website", 42));
+
+        // Only the PR that stayed closed is cleaned up
+        elapse(GRACE).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(!pending.cancel("nullisLabs", "website", 42));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_closing_again_restarts_grace_period() {
+        let pending = PendingCleanups::new();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        pending.schedule("nullisLabs", "website", 42, GRACE, counting_cleanup(&count));
+        pending.schedule(
+            "nullisLabs",
+            "website",
+            42,
+            GRACE * 2,
+            counting_cleanup(&count),
+        );
+
+        // The replaced schedule's deadline passes without a cleanup
+        elapse(GRACE).await;
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert!(pending.is_pending("nullisLabs", "website", 42));
+
+        elapse(GRACE).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(!pending.is_pending("nullisLabs", "website", 42));
+    }
+}
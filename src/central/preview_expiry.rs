@@ -0,0 +1,213 @@
+//! Cleanup of PR previews that outlived their maximum age
+//!
+//! Previews normally live until their PR closes. With `MAX_PREVIEW_AGE_SECS`
+//! set, this background task removes previews whose latest deployment is
+//! older than that, even while the PR is open, and tells the PR why.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::central::db::{self, DeploymentHistory};
+use crate::central::github::GitHubClient;
+use crate::central::handlers::webhook::cleanup_deployment;
+use crate::central::server::AppState;
+
+/// Interval between sweeps for expired previews
+const PREVIEW_EXPIRY_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Periodically clean up previews older than `max_age`
+pub fn spawn_preview_expiry(state: AppState, max_age: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut sweep_interval = tokio::time::interval(PREVIEW_EXPIRY_INTERVAL);
+
+        loop {
+            sweep_interval.tick().await;
+
+            if let Err(e) = expire_previews(&state, max_age).await {
+                tracing::warn!(error = %e, "Failed to clean up expired previews");
+            }
+        }
+    })
+}
+
+async fn expire_previews(state: &AppState, max_age: Duration) -> Result<()> {
+    let previews = db::list_active_previews(&state.db).await?;
+    let callback_url = format!("{}/api/status", state.config.callback_base_url);
+
+    for deployment in expired_previews(&previews, max_age, Utc::now()) {
+        // Left for the next sweep if the worker can't be reached
+        if let Err(e) = cleanup_deployment(state, deployment, &callback_url).await {
+            tracing::warn!(
+                site_id = %deployment.site_id,
+                zone = %deployment.zone,
+                error = %e,
+                "Failed to clean up expired preview"
+            );
+            continue;
+        }
+
+        tracing::info!(
+            site_id = %deployment.site_id,
+            created_at = %deployment.created_at,
+            "Cleaned up expired preview"
+        );
+
+        // The preview is gone either way; a missing comment isn't worth a retry
+        if let Err(e) = comment_preview_expired(state, deployment, max_age).await {
+            tracing::warn!(
+                site_id = %deployment.site_id,
+                error = %e,
+                "Failed to comment on expired preview"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Previews whose latest deployment is older than `max_age`
+///
+/// Deployments still building are left alone so a fresh push isn't
+/// cleaned up from under its own build.
+fn expired_previews(
+    deployments: &[DeploymentHistory],
+    max_age: Duration,
+    now: DateTime<Utc>,
+) -> Vec<&DeploymentHistory> {
+    deployments
+        .iter()
+        .filter(|deployment| deployment.pr_number.is_some())
+        .filter(|deployment| {
+            !matches!(
+                deployment.status.as_str(),
+                "pending" | "building" | "cleaned"
+            )
+        })
+        .filter(|deployment| {
+            (now - deployment.created_at)
+                .to_std()
+                .is_ok_and(|age| age > max_age)
+        })
+        .collect()
+}
+
+/// Tell the PR its preview expired, reusing its deployment comment
+async fn comment_preview_expired(
+    state: &AppState,
+    deployment: &DeploymentHistory,
+    max_age: Duration,
+) -> Result<()> {
+    let Some(pr_number) = deployment.pr_number else {
+        return Ok(());
+    };
+    let pr_number = pr_number as u32;
+    let (org, repo) = (&deployment.github_org, &deployment.github_repo);
+
+    let installation_id = state
+        .github_apps
+        .get_repo_installation_id(&state.http_client, org, repo)
+        .await?;
+    let token = state
+        .github_apps
+        .get_installation_token(&state.http_client, installation_id)
+        .await?;
+    let github_client = GitHubClient::new(
+        state.http_client.clone(),
+        state.config.github_api.clone(),
+        token.token,
+    );
+
+    let body = GitHubClient::preview_expired_comment(&deployment.commit_sha, max_age);
+    match db::get_pr_comment(&state.db, org, repo, pr_number).await? {
+        Some(comment_id) => {
+            github_client
+                .update_comment(org, repo, comment_id, &body)
+                .await?;
+        }
+        None => {
+            let comment = github_client
+                .create_pr_comment(org, repo, pr_number, &body)
+                .await?;
+            db::upsert_pr_comment(&state.db, org, repo, pr_number, comment.id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::central::db::DeploymentType;
+    use uuid::Uuid;
+
+    fn preview(pr_number: i32, status: &str, created_at: DateTime<Utc>) -> DeploymentHistory {
+        DeploymentHistory {
+            id: 1,
+            job_id: Uuid::new_v4(),
+            github_org: "nullisLabs".to_string(),
+            github_repo: "website".to_string(),
+            pr_number: Some(pr_number),
+            site_id: format!("nullislabs-website-pr-{}", pr_number),
+            zone: "nullislabs".to_string(),
+            domain: format!("pr-{}.nullislabs.io", pr_number),
+            commit_sha: "abc1234def5678".to_string(),
+            status: status.to_string(),
+            created_at,
+            updated_at: created_at,
+            started_at: None,
+            completed_at: None,
+            size_bytes: None,
+            deployment_type: DeploymentType::Webhook.to_string(),
+            dry_run: false,
+        }
+    }
+
+    fn site_ids(deployments: Vec<&DeploymentHistory>) -> Vec<&str> {
+        deployments.iter().map(|d| d.site_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_expired_previews_by_age() {
+        let now = Utc::now();
+        let max_age = Duration::from_secs(7 * 86400);
+        let previews = [
+            preview(1, "success", now - chrono::Duration::days(8)),
+            preview(2, "success", now - chrono::Duration::days(6)),
+            preview(3, "failed", now - chrono::Duration::days(30)),
+        ];
+
+        assert_eq!(
+            site_ids(expired_previews(&previews, max_age, now)),
+            vec!["nullislabs-website-pr-1", "nullislabs-website-pr-3"]
+        );
+    }
+
+    #[test]
+    fn test_expired_previews_skip_in_flight_builds() {
+        let now = Utc::now();
+        let old = now - chrono::Duration::days(30);
+        let previews = [
+            preview(1, "pending", old),
+            preview(2, "building", old),
+            preview(3, "cleaned", old),
+            preview(4, "cancelled", old),
+        ];
+
+        assert_eq!(
+            site_ids(expired_previews(&previews, Duration::from_secs(3600), now)),
+            vec!["nullislabs-website-pr-4"]
+        );
+    }
+
+    #[test]
+    fn test_expired_previews_skip_production() {
+        let now = Utc::now();
+        let mut production = preview(1, "success", now - chrono::Duration::days(30));
+        production.pr_number = None;
+
+        assert!(expired_previews(&[production], Duration::from_secs(3600), now).is_empty());
+    }
+}
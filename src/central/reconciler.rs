@@ -0,0 +1,237 @@
+//! Reconciliation of deployments stuck without a final status
+//!
+//! A worker that crashes or loses connectivity after a build can leave its
+//! deployment `building` forever. This background task asks the owning worker
+//! about deployments that haven't changed status for a while and applies the
+//! outcome it reports. Mirrored deployments ask each worker that hasn't
+//! reported yet, and their outcomes go through the quorum like callbacks.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::central::db::{self, DeploymentHistory, Worker};
+use crate::central::dispatch::query_job_status;
+use crate::central::handlers::status::process_status_update;
+use crate::central::mirror::quorum_update;
+use crate::central::server::AppState;
+use crate::shared::{JobStatus, JobStatusQuery, StatusUpdate, WorkerJobState};
+
+/// Interval between sweeps for stuck deployments
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Periodically reconcile stuck deployments with their workers
+pub fn spawn_reconciler(state: AppState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut sweep_interval = tokio::time::interval(RECONCILE_INTERVAL);
+
+        loop {
+            sweep_interval.tick().await;
+
+            if let Err(e) = reconcile_stuck_deployments(&state).await {
+                tracing::warn!(error = %e, "Failed to reconcile stuck deployments");
+            }
+        }
+    })
+}
+
+async fn reconcile_stuck_deployments(state: &AppState) -> Result<()> {
+    let stuck =
+        db::list_stuck_deployments(&state.db, state.config.stuck_deployment_timeout).await?;
+
+    for deployment in stuck {
+        if let Err(e) = reconcile_deployment(state, &deployment).await {
+            // The worker may just be down; try again next sweep
+            tracing::warn!(
+                job_id = %deployment.job_id,
+                zone = %deployment.zone,
+                error = %e,
+                "Failed to reconcile stuck deployment"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn reconcile_deployment(state: &AppState, deployment: &DeploymentHistory) -> Result<()> {
+    let mirrors = db::list_mirror_results(&state.db, deployment.job_id).await?;
+    if mirrors.is_empty() {
+        let worker = db::get_worker(&state.db, &deployment.zone).await?;
+        return reconcile_with_worker(state, deployment, worker.as_ref(), None).await;
+    }
+
+    let workers = db::get_workers(&state.db, &deployment.zone).await?;
+    for mirror in mirrors.iter().filter(|mirror| mirror.succeeded.is_none()) {
+        let worker = workers.iter().find(|worker| worker.id == mirror.worker_id);
+        if let Err(e) =
+            reconcile_with_worker(state, deployment, worker, Some(mirror.worker_id)).await
+        {
+            // The others may still decide the quorum
+            tracing::warn!(
+                job_id = %deployment.job_id,
+                worker_id = mirror.worker_id,
+                error = %e,
+                "Failed to reconcile mirrored deployment with worker"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply what one worker reports about a stuck job
+///
+/// `worker_id` is set for mirrored deployments, whose outcome is decided by
+/// the quorum.
+async fn reconcile_with_worker(
+    state: &AppState,
+    deployment: &DeploymentHistory,
+    worker: Option<&Worker>,
+    worker_id: Option<i32>,
+) -> Result<()> {
+    let job_state = match worker {
+        Some(worker) => {
+            query_job_status(
+                &state.http_client,
+                &worker.endpoint,
+                state.config.worker_secrets.signer(&worker.environment)?,
+                &JobStatusQuery {
+                    job_id: deployment.job_id,
+                    site_id: deployment.site_id.clone(),
+                    environment: Some(deployment.zone.clone()),
+                },
+            )
+            .await?
+        }
+        // Nothing can still be running the job
+        None => WorkerJobState::Unknown,
+    };
+
+    let Some(update) = resolve_stuck_job(deployment.job_id, &deployment.zone, worker_id, job_state)
+    else {
+        tracing::debug!(job_id = %deployment.job_id, worker_id, "Stuck deployment is still running");
+        return Ok(());
+    };
+    let Some(update) = quorum_update(state, Some(&deployment.zone), update).await? else {
+        return Ok(());
+    };
+
+    tracing::info!(
+        job_id = %deployment.job_id,
+        status = %update.status,
+        previous = %deployment.status,
+        "Reconciled stuck deployment"
+    );
+    process_status_update(state, update).await
+}
+
+/// Status update to apply to a stuck job, given what its worker reports
+///
+/// None while the worker is still working on it.
+fn resolve_stuck_job(
+    job_id: Uuid,
+    zone: &str,
+    worker_id: Option<i32>,
+    job_state: WorkerJobState,
+) -> Option<StatusUpdate> {
+    match job_state {
+        WorkerJobState::Running => None,
+        WorkerJobState::Finished { update } => Some(StatusUpdate {
+            job_id,
+            worker_id,
+            ..update
+        }),
+        WorkerJobState::Unknown => Some(StatusUpdate {
+            job_id,
+            status: JobStatus::Failed,
+            deployed_url: None,
+            error_message: Some(
+                "Worker has no record of this job; it was likely interrupted".to_string(),
+            ),
+            event: None,
+            zone: Some(zone.to_string()),
+            size_bytes: None,
+            worker_id,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_job_left_alone() {
+        assert!(resolve_stuck_job(Uuid::new_v4(), "nxm", None, WorkerJobState::Running).is_none());
+    }
+
+    #[test]
+    fn test_finished_job_takes_worker_outcome() {
+        let job_id = Uuid::new_v4();
+        let update = resolve_stuck_job(
+            job_id,
+            "nxm",
+            None,
+            WorkerJobState::Finished {
+                update: StatusUpdate {
+                    job_id,
+                    status: JobStatus::Success,
+                    deployed_url: Some("https://website.nxm.rs".to_string()),
+                    error_message: None,
+                    event: None,
+                    zone: Some("nxm".to_string()),
+                    size_bytes: None,
+                    worker_id: None,
+                },
+            },
+        )
+        .unwrap();
+
+        assert_eq!(update.job_id, job_id);
+        assert_eq!(update.status, JobStatus::Success);
+        assert_eq!(
+            update.deployed_url.as_deref(),
+            Some("https://website.nxm.rs")
+        );
+    }
+
+    #[test]
+    fn test_mirrored_job_attributed_to_its_worker() {
+        let job_id = Uuid::new_v4();
+        let update = resolve_stuck_job(job_id, "nxm", Some(2), WorkerJobState::Unknown).unwrap();
+        assert_eq!(update.worker_id, Some(2));
+
+        // The worker that was asked is the one the outcome counts for
+        let update = resolve_stuck_job(
+            job_id,
+            "nxm",
+            Some(2),
+            WorkerJobState::Finished {
+                update: StatusUpdate {
+                    job_id,
+                    status: JobStatus::Success,
+                    deployed_url: None,
+                    error_message: None,
+                    event: None,
+                    zone: Some("nxm".to_string()),
+                    size_bytes: None,
+                    worker_id: Some(1),
+                },
+            },
+        )
+        .unwrap();
+        assert_eq!(update.worker_id, Some(2));
+    }
+
+    #[test]
+    fn test_unknown_job_fails() {
+        let job_id = Uuid::new_v4();
+        let update = resolve_stuck_job(job_id, "nxm", None, WorkerJobState::Unknown).unwrap();
+
+        assert_eq!(update.job_id, job_id);
+        assert_eq!(update.status, JobStatus::Failed);
+        assert!(update.error_message.unwrap().contains("no record"));
+    }
+}
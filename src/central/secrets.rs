@@ -0,0 +1,294 @@
+//! Per-zone worker secrets
+//!
+//! Each zone's worker can hold its own shared secret, so a compromised worker
+//! can neither sign nor verify traffic for other zones. Zones without their own
+//! secret fall back to the global `WORKER_SHARED_SECRET`, if one is configured.
+//!
+//! Zones with an Ed25519 public key for their worker don't share a secret at
+//! all: Central signs dispatches with its own private key and verifies the
+//! worker's requests with the zone's public key.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+
+use crate::shared::auth::{
+    RequestSigner, RequestVerifier, SignatureCheck, SignatureTolerance, load_verifying_key,
+};
+
+/// Secrets shared between Central and its workers
+#[derive(Debug, Clone, Default)]
+pub struct WorkerSecrets {
+    default: Option<String>,
+    zones: HashMap<String, String>,
+    /// Central's private key, for zones with a worker public key
+    signing_key: Option<SigningKey>,
+    /// Worker public keys by zone
+    public_keys: HashMap<String, VerifyingKey>,
+    /// How far worker request timestamps may be from Central's clock
+    tolerance: SignatureTolerance,
+}
+
+impl WorkerSecrets {
+    pub fn new(default: Option<String>, zones: HashMap<String, String>) -> Self {
+        Self {
+            default,
+            zones,
+            ..Default::default()
+        }
+    }
+
+    /// Use Ed25519 keys instead of secrets for the zones in `public_keys`
+    pub fn with_keys(
+        mut self,
+        signing_key: SigningKey,
+        public_keys: HashMap<String, VerifyingKey>,
+    ) -> Self {
+        self.signing_key = Some(signing_key);
+        self.public_keys = public_keys;
+        self
+    }
+
+    /// Accept worker request timestamps within `tolerance`
+    pub fn with_tolerance(mut self, tolerance: SignatureTolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Whether no secret or key is configured at all
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.zones.is_empty() && self.public_keys.is_empty()
+    }
+
+    /// Secret shared with the worker serving `zone`
+    pub fn for_zone(&self, zone: &str) -> Option<&str> {
+        self.zones
+            .get(zone)
+            .or(self.default.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Key to sign dispatches to the worker serving `zone` with
+    pub fn signer(&self, zone: &str) -> Result<RequestSigner<'_>> {
+        if let (Some(key), true) = (&self.signing_key, self.public_keys.contains_key(zone)) {
+            return Ok(RequestSigner::Ed25519(key));
+        }
+
+        self.for_zone(zone)
+            .map(RequestSigner::Secret)
+            .with_context(|| format!("No worker secret configured for zone '{}'", zone))
+    }
+
+    /// Verify a request from the worker serving `zone`
+    ///
+    /// Without a zone only the global secret applies. A zone without any
+    /// secret can't produce a valid signature, so it's a `Mismatch`.
+    pub fn verify(
+        &self,
+        zone: Option<&str>,
+        body: &[u8],
+        signature: &str,
+        timestamp: u64,
+    ) -> SignatureCheck {
+        if let Some(key) = zone.and_then(|zone| self.public_keys.get(zone)) {
+            return RequestVerifier::Ed25519(key).check(body, signature, timestamp, self.tolerance);
+        }
+
+        let secret = match zone {
+            Some(zone) => self.for_zone(zone),
+            None => self.default.as_deref(),
+        };
+
+        match secret {
+            Some(secret) => {
+                RequestVerifier::Secret(secret).check(body, signature, timestamp, self.tolerance)
+            }
+            None => SignatureCheck::Mismatch,
+        }
+    }
+}
+
+/// Parse per-zone secrets: `zone=secret` entries separated by commas or newlines
+pub fn parse_worker_secrets(value: &str) -> Result<HashMap<String, String>> {
+    let mut secrets = HashMap::new();
+
+    for entry in value.split([',', '\n']).map(str::trim) {
+        if entry.is_empty() {
+            continue;
+        }
+
+        // Split on the first '=' only; base64 secrets may end in padding
+        let (zone, secret) = entry
+            .split_once('=')
+            .map(|(zone, secret)| (zone.trim(), secret.trim()))
+            .filter(|(zone, secret)| !zone.is_empty() && !secret.is_empty())
+            .with_context(|| format!("Invalid worker secret entry for '{}'", entry_zone(entry)))?;
+
+        if secrets
+            .insert(zone.to_string(), secret.to_string())
+            .is_some()
+        {
+            anyhow::bail!("Duplicate worker secret for zone '{}'", zone);
+        }
+    }
+
+    Ok(secrets)
+}
+
+/// Load worker public keys from `zone=path` entries separated by commas or newlines
+pub fn load_worker_public_keys(value: &str) -> Result<HashMap<String, VerifyingKey>> {
+    parse_worker_secrets(value)?
+        .into_iter()
+        .map(|(zone, path)| Ok((zone, load_verifying_key(Path::new(&path))?)))
+        .collect()
+}
+
+/// The zone part of an entry, so errors never echo a secret
+fn entry_zone(entry: &str) -> &str {
+    entry.split_once('=').map_or("", |(zone, _)| zone.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::auth::sign_request;
+
+    fn secrets(default: Option<&str>) -> WorkerSecrets {
+        WorkerSecrets::new(
+            default.map(str::to_string),
+            parse_worker_secrets("nullislabs=secret-a,acme=secret-b").unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_parse_worker_secrets() {
+        let parsed = parse_worker_secrets("nullislabs=abc==\n acme = def ,\n").unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed["nullislabs"], "abc==");
+        assert_eq!(parsed["acme"], "def");
+        assert!(parse_worker_secrets("").unwrap().is_empty());
+
+        assert!(parse_worker_secrets("nullislabs").is_err());
+        assert!(parse_worker_secrets("=secret").is_err());
+        assert!(parse_worker_secrets("nullislabs=").is_err());
+        assert!(parse_worker_secrets("a=x,a=y").is_err());
+
+        let err = parse_worker_secrets("nullislabs=").unwrap_err();
+        assert!(err.to_string().contains("nullislabs"));
+    }
+
+    #[test]
+    fn test_zone_secret_cannot_verify_other_zone() {
+        let secrets = secrets(None);
+        let body = br#"{"zone":"acme"}"#;
+        let (signature, timestamp) = sign_request(b"secret-a", body);
+
+        assert!(
+            secrets
+                .verify(Some("nullislabs"), body, &signature, timestamp)
+                .is_valid()
+        );
+        assert!(
+            !secrets
+                .verify(Some("acme"), body, &signature, timestamp)
+                .is_valid()
+        );
+
+        // No global fallback: unknown zones and zoneless requests are rejected
+        assert!(
+            !secrets
+                .verify(Some("other"), body, &signature, timestamp)
+                .is_valid()
+        );
+        assert!(!secrets.verify(None, body, &signature, timestamp).is_valid());
+    }
+
+    #[test]
+    fn test_global_secret_fallback() {
+        let secrets = secrets(Some("global"));
+        let body = b"{}";
+        let (signature, timestamp) = sign_request(b"global", body);
+
+        assert_eq!(secrets.for_zone("other"), Some("global"));
+        assert!(
+            secrets
+                .verify(Some("other"), body, &signature, timestamp)
+                .is_valid()
+        );
+        assert!(secrets.verify(None, body, &signature, timestamp).is_valid());
+
+        // A zone with its own secret no longer accepts the global one
+        assert!(
+            !secrets
+                .verify(Some("nullislabs"), body, &signature, timestamp)
+                .is_valid()
+        );
+    }
+
+    #[test]
+    fn test_signer_per_zone() {
+        let secrets = secrets(None);
+        assert!(matches!(
+            secrets.signer("acme").unwrap(),
+            RequestSigner::Secret("secret-b")
+        ));
+        assert!(secrets.signer("other").is_err());
+        assert!(!secrets.is_empty());
+        assert!(WorkerSecrets::default().is_empty());
+    }
+
+    #[test]
+    fn test_zones_with_public_keys_use_ed25519() {
+        let central = SigningKey::from_bytes(&[1; 32]);
+        let worker = SigningKey::from_bytes(&[2; 32]);
+        let secrets = secrets(Some("global")).with_keys(
+            central.clone(),
+            HashMap::from([("acme".to_string(), worker.verifying_key())]),
+        );
+
+        assert!(matches!(
+            secrets.signer("acme").unwrap(),
+            RequestSigner::Ed25519(key) if *key == central
+        ));
+        assert!(matches!(
+            secrets.signer("nullislabs").unwrap(),
+            RequestSigner::Secret("secret-a")
+        ));
+
+        let body = br#"{"zone":"acme"}"#;
+        let (signature, timestamp) = RequestSigner::Ed25519(&worker).sign(body);
+        assert!(
+            secrets
+                .verify(Some("acme"), body, &signature, timestamp)
+                .is_valid()
+        );
+        assert!(
+            !secrets
+                .verify(Some("nullislabs"), body, &signature, timestamp)
+                .is_valid()
+        );
+
+        // The zone's old secret is no longer accepted
+        let (signature, timestamp) = sign_request(b"secret-b", body);
+        assert!(
+            !secrets
+                .verify(Some("acme"), body, &signature, timestamp)
+                .is_valid()
+        );
+    }
+
+    #[test]
+    fn test_public_keys_alone_are_enough() {
+        let keyed = WorkerSecrets::default().with_keys(
+            SigningKey::from_bytes(&[1; 32]),
+            HashMap::from([(
+                "acme".to_string(),
+                SigningKey::from_bytes(&[2; 32]).verifying_key(),
+            )]),
+        );
+        assert!(!keyed.is_empty());
+        assert!(keyed.signer("other").is_err());
+    }
+}
@@ -1,30 +1,53 @@
+use std::net::SocketAddr;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use axum::{
-    Router,
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
     routing::{get, post},
 };
+use hyper_util::rt::TokioIo;
+use hyper_util::service::TowerToHyperService;
+use serde::Serialize;
 use sqlx::PgPool;
 use sqlx::postgres::PgPoolOptions;
+use tokio::net::UnixListener;
 use tower_http::trace::TraceLayer;
 
 use crate::central::db;
-use crate::central::github::GitHubApp;
+use crate::central::deploy_config::ConfigCache;
+use crate::central::github::{GitHubApp, GitHubApps};
 use crate::central::handlers::{
-    delete_authorized_org, handle_heartbeat, handle_status, handle_webhook, list_authorized_orgs,
-    upsert_authorized_org,
+    approve_deployment, bulk_cleanup, cancel_deployment, delete_authorized_org, deploy_artifact,
+    disable_worker, enable_worker, get_deployment, get_worker_config, handle_heartbeat,
+    handle_inventory, handle_status, handle_token_refresh, handle_webhook, list_audit_log,
+    list_authorized_orgs, list_worker_sites, list_workers, pause_repo, preview_commit,
+    promote_deployment, rebuild_repo, resume_repo, upsert_authorized_org,
 };
+use crate::central::outbox::spawn_outbox_publisher;
+use crate::central::pending_cleanup::PendingCleanups;
+use crate::central::preview_expiry::spawn_preview_expiry;
+use crate::central::reconciler::spawn_reconciler;
 use crate::central::worker_monitor::{MonitorConfig, WorkerMonitor};
 use crate::config::CentralConfig;
+use crate::shared::http::build_http_client;
+use crate::shared::version::version_handler;
 
 /// Shared application state
 #[derive(Clone)]
 pub struct AppState {
     pub config: Arc<CentralConfig>,
     pub db: PgPool,
-    pub github_app: Arc<GitHubApp>,
+    pub github_apps: Arc<GitHubApps>,
     pub http_client: reqwest::Client,
+    pub config_cache: ConfigCache,
+    pub pending_cleanups: PendingCleanups,
 }
 
 /// Run the Central HTTP server
@@ -33,27 +56,46 @@ pub async fn run(config: CentralConfig) -> Result<()> {
     let private_key = config.load_private_key()?;
 
     // Initialize GitHub App
-    let github_app = GitHubApp::new(config.github_app_id, &private_key)
-        .context("Failed to initialize GitHub App")?;
+    let github_app = GitHubApp::new(
+        config.github_app_id,
+        &private_key,
+        config.github_api.clone(),
+    )
+    .context("Failed to initialize GitHub App")?;
 
-    // Connect to database
-    let db = PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&config.database_url)
-        .await
-        .context("Failed to connect to database")?;
+    // Further apps share the API but have their own key and webhook secret
+    let mut apps = vec![(github_app, config.github_webhook_secret.clone())];
+    for extra in &config.github_extra_apps {
+        let app = GitHubApp::new(
+            extra.app_id,
+            &extra.load_private_key()?,
+            config.github_api.clone(),
+        )
+        .with_context(|| format!("Failed to initialize GitHub App {}", extra.app_id))?;
+        apps.push((app, extra.webhook_secret.clone()));
+    }
+    let github_apps = GitHubApps::new(apps)?;
 
-    // Run migrations
-    sqlx::migrate!("./migrations")
-        .run(&db)
-        .await
-        .context("Failed to run database migrations")?;
+    // The database may still be starting alongside Central
+    let db = retry_with_backoff(
+        "Database not ready",
+        config.database_startup_attempts,
+        DB_STARTUP_BASE_DELAY,
+        DB_STARTUP_MAX_DELAY,
+        || connect_database(&config),
+    )
+    .await?;
 
-    tracing::info!("Database connected and migrations applied");
+    tracing::info!(
+        max_connections = config.database_max_connections,
+        min_connections = config.database_min_connections,
+        acquire_timeout_secs = config.database_acquire_timeout.as_secs(),
+        "Database connected and migrations applied"
+    );
 
     // Sync workers from config to database
     if !config.workers.is_empty() {
-        let worker_count = db::sync_workers(&db, &config.workers)
+        let worker_count = db::sync_workers(&db, &config.workers, &config.worker_mirrors)
             .await
             .context("Failed to sync workers to database")?;
         tracing::info!(count = worker_count, "Workers synced to database");
@@ -61,28 +103,100 @@ pub async fn run(config: CentralConfig) -> Result<()> {
         for (zone, endpoint) in &config.workers {
             tracing::info!(zone = %zone, endpoint = %endpoint, "Worker registered");
         }
+        for (zone, endpoints) in &config.worker_mirrors {
+            for endpoint in endpoints {
+                tracing::info!(zone = %zone, endpoint = %endpoint, "Mirror worker registered");
+            }
+        }
 
-        // Start worker health monitor
-        let monitor =
-            WorkerMonitor::new(db.clone(), config.workers.clone(), MonitorConfig::default());
+        // Start worker health monitor, mirrors included
+        let endpoints = config
+            .workers
+            .iter()
+            .chain(config.worker_mirrors.iter().flat_map(|(zone, endpoints)| {
+                endpoints.iter().map(move |endpoint| (zone, endpoint))
+            }))
+            .map(|(zone, endpoint)| (zone.clone(), endpoint.clone()))
+            .collect();
+        let monitor_config = MonitorConfig {
+            probe: config.worker_health_probe.clone(),
+            ..Default::default()
+        };
+        let monitor = WorkerMonitor::new(db.clone(), endpoints, monitor_config);
         monitor.start();
     } else {
         tracing::warn!("No workers configured - deployments will fail until workers are added");
     }
 
+    // Expire deployments nobody approved in time
+    spawn_approval_expiry(db.clone());
+
     // Build application state
     let state = AppState {
         config: Arc::new(config.clone()),
         db,
-        github_app: Arc::new(github_app),
-        http_client: reqwest::Client::new(),
+        github_apps: Arc::new(github_apps),
+        http_client: build_http_client(config.http_timeouts)?,
+        config_cache: ConfigCache::new(),
+        pending_cleanups: PendingCleanups::new(),
     };
 
-    // Build router
-    let app = Router::new()
+    // Settle deployments whose final status update was lost
+    spawn_reconciler(state.clone());
+
+    // Deliver deployment events queued with each status change
+    spawn_outbox_publisher(
+        state.db.clone(),
+        state.http_client.clone(),
+        state.config.event_sinks.clone(),
+    );
+
+    // Remove previews of long-lived PRs once they reach the maximum age
+    if let Some(max_age) = state.config.max_preview_age {
+        spawn_preview_expiry(state.clone(), max_age);
+    }
+
+    let public = public_routes().with_state(state.clone());
+    let admin = admin_routes().with_state(state);
+
+    match (config.admin_listen_addr, &config.admin_socket_path) {
+        // Admin API on its own (typically internal-only) listener
+        (Some(admin_addr), _) => {
+            tokio::try_join!(
+                serve(config.listen_addr, public, "Server"),
+                serve(admin_addr, admin, "Admin server"),
+            )?;
+        }
+        // Admin API for colocated tooling only, with no network exposure
+        (None, Some(socket_path)) => {
+            tokio::try_join!(
+                serve(config.listen_addr, public, "Server"),
+                serve_unix(socket_path, admin, "Admin server"),
+            )?;
+        }
+        (None, None) => serve(config.listen_addr, public.merge(admin), "Server").await?,
+    }
+
+    Ok(())
+}
+
+/// Routes for GitHub, workers and health probes
+pub fn public_routes() -> Router<AppState> {
+    Router::new()
         .route("/webhook/github", post(handle_webhook))
         .route("/api/status", post(handle_status))
         .route("/api/workers/heartbeat", post(handle_heartbeat))
+        .route("/api/workers/inventory", post(handle_inventory))
+        .route("/api/workers/token", post(handle_token_refresh))
+        .route("/api/workers/:zone/config", get(get_worker_config))
+        .route("/health", get(health_check))
+        .route("/version", get(version_handler))
+        .route("/ready", get(ready_check))
+}
+
+/// Routes requiring the admin API key
+pub fn admin_routes() -> Router<AppState> {
+    Router::new()
         // Admin API for managing authorizations
         .route("/api/admin/auth", get(list_authorized_orgs))
         .route("/api/admin/auth", post(upsert_authorized_org))
@@ -90,22 +204,473 @@ pub async fn run(config: CentralConfig) -> Result<()> {
             "/api/admin/auth",
             axum::routing::delete(delete_authorized_org),
         )
-        .route("/health", get(health_check))
-        .layer(TraceLayer::new_for_http())
-        .with_state(state);
+        // Admin API for inspecting and toggling workers
+        .route("/api/admin/workers", get(list_workers))
+        .route("/api/admin/workers/:env/enable", post(enable_worker))
+        .route("/api/admin/workers/:env/disable", post(disable_worker))
+        .route("/api/admin/workers/:env/sites", get(list_worker_sites))
+        // Pause deployments for a repository without touching its config
+        .route("/api/admin/repos/:org/:repo/pause", post(pause_repo))
+        .route("/api/admin/repos/:org/:repo/resume", post(resume_repo))
+        .route("/api/admin/repos/:org/:repo/rebuild", post(rebuild_repo))
+        // Approve deployments held for protected zones
+        .route(
+            "/api/admin/deployments/:job_id/approve",
+            post(approve_deployment),
+        )
+        // Send all traffic to a canary release
+        .route(
+            "/api/admin/deployments/:job_id/promote",
+            post(promote_deployment),
+        )
+        // Stop a deployment that hasn't been deployed yet
+        .route(
+            "/api/admin/deployments/:job_id/cancel",
+            post(cancel_deployment),
+        )
+        // Deploy a prebuilt artifact archive instead of building
+        .route("/api/admin/deployments/artifact", post(deploy_artifact))
+        // Tear down active deployments matching filters
+        .route("/api/admin/cleanup", post(bulk_cleanup))
+        // Deploy an arbitrary commit at a throwaway hostname
+        .route("/api/admin/preview", post(preview_commit))
+        // Who changed what through the admin API
+        .route("/api/admin/audit", get(list_audit_log))
+        // Deployment timeline for debugging (admin API key)
+        .route("/api/deployments/:job_id", get(get_deployment))
+}
+
+/// Bind `addr` and serve `app` until the server fails
+async fn serve(addr: SocketAddr, app: Router, name: &str) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind to address {}", addr))?;
+
+    tracing::info!(addr = %addr, "{} listening", name);
 
-    // Start server
-    let listener = tokio::net::TcpListener::bind(&config.listen_addr)
+    axum::serve(listener, app.layer(TraceLayer::new_for_http()))
         .await
-        .context("Failed to bind to address")?;
+        .with_context(|| format!("{} error", name))
+}
 
-    tracing::info!(addr = %config.listen_addr, "Server listening");
+/// Bind a UNIX socket at `path` and serve `app` until the server fails
+///
+/// A socket left behind by a previous run is replaced, but any other file at
+/// `path` is an error. Only the service's user and group may connect.
+async fn serve_unix(path: &Path, app: Router, name: &str) -> Result<()> {
+    remove_stale_socket(path).await?;
+    let listener = bind_private_socket(path).await?;
 
-    axum::serve(listener, app).await.context("Server error")?;
+    tracing::info!(path = %path.display(), "{} listening", name);
 
-    Ok(())
+    let app = app.layer(TraceLayer::new_for_http());
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                // Out of file descriptors and the like; keep serving
+                tracing::warn!(error = %e, "{} failed to accept connection", name);
+                continue;
+            }
+        };
+
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!(error = %e, "Socket connection closed with error");
+            }
+        });
+    }
 }
 
+/// Remove a socket left at `path` by a previous run
+async fn remove_stale_socket(path: &Path) -> Result<()> {
+    match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) if metadata.file_type().is_socket() => tokio::fs::remove_file(path)
+            .await
+            .with_context(|| format!("Failed to remove stale socket {:?}", path)),
+        Ok(_) => anyhow::bail!("{:?} exists and is not a socket", path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("Failed to inspect {:?}", path)),
+    }
+}
+
+/// Bind a socket with mode 0660 at `path`
+///
+/// The socket is bound in a directory only we can enter and moved into place
+/// once its mode is set, so nobody can connect while it is still open to all.
+async fn bind_private_socket(path: &Path) -> Result<UnixListener> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let private_dir = parent.join(format!(".catapult-socket-{}", uuid::Uuid::new_v4()));
+    tokio::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private_dir)
+        .await
+        .with_context(|| format!("Failed to create {:?}", private_dir))?;
+
+    let temp_path = private_dir.join("socket");
+    let result = async {
+        let listener = UnixListener::bind(&temp_path)
+            .with_context(|| format!("Failed to bind to socket {:?}", path))?;
+        tokio::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o660))
+            .await
+            .with_context(|| format!("Failed to set permissions on socket {:?}", path))?;
+        tokio::fs::rename(&temp_path, path)
+            .await
+            .with_context(|| format!("Failed to move socket to {:?}", path))?;
+        Ok(listener)
+    }
+    .await;
+
+    // Leftovers of a failed bind go with the directory
+    let _ = tokio::fs::remove_dir_all(&private_dir).await;
+    result
+}
+
+/// First delay between database startup attempts, doubling per attempt
+const DB_STARTUP_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between database startup attempts
+const DB_STARTUP_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Connect the pool and apply migrations
+async fn connect_database(config: &CentralConfig) -> Result<PgPool> {
+    let db = tokio::time::timeout(
+        config.database_connect_timeout,
+        PgPoolOptions::new()
+            .max_connections(config.database_max_connections)
+            .min_connections(config.database_min_connections)
+            .acquire_timeout(config.database_acquire_timeout)
+            .connect(&config.database_url),
+    )
+    .await
+    .context("Timed out connecting to database")?
+    .context("Failed to connect to database")?;
+
+    sqlx::migrate!("./migrations")
+        .run(&db)
+        .await
+        .context("Failed to run database migrations")?;
+
+    Ok(db)
+}
+
+/// Run `operation` until it succeeds, at most `attempts` times
+///
+/// Waits `base_delay` after the first failure, doubling up to `max_delay`,
+/// and returns the last error once the attempts are used up.
+async fn retry_with_backoff<T, F, Fut>(
+    what: &str,
+    attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    mut operation: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= attempts => return Err(e),
+            Err(e) => {
+                let delay = backoff_delay(attempt, base_delay, max_delay);
+                tracing::warn!(
+                    error = format!("{:#}", e),
+                    attempt,
+                    attempts,
+                    retry_in_ms = delay.as_millis() as u64,
+                    "{}, retrying",
+                    what
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Delay after the given (1-based) failed attempt
+fn backoff_delay(attempt: u32, base_delay: Duration, max_delay: Duration) -> Duration {
+    base_delay
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(max_delay)
+}
+
+/// Interval between sweeps for deployments whose approval window has passed
+const APPROVAL_EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Periodically mark unapproved deployments as expired
+fn spawn_approval_expiry(db: PgPool) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut sweep_interval = tokio::time::interval(APPROVAL_EXPIRY_INTERVAL);
+
+        loop {
+            sweep_interval.tick().await;
+
+            match db::expire_pending_approvals(&db).await {
+                Ok(expired) => {
+                    for job_id in expired {
+                        tracing::info!(job_id = %job_id, "Deployment approval expired");
+                    }
+                }
+                Err(e) => tracing::warn!(error = %e, "Failed to expire pending approvals"),
+            }
+        }
+    })
+}
+
+/// Timeout for the readiness database check
+const READY_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
 async fn health_check() -> &'static str {
     "OK"
 }
+
+/// Readiness response body
+#[derive(Debug, Serialize)]
+struct ReadyResponse {
+    ready: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    pool: PoolStats,
+}
+
+/// Database connection pool statistics
+#[derive(Debug, Serialize)]
+struct PoolStats {
+    size: u32,
+    idle: usize,
+    used: usize,
+}
+
+/// Readiness check: verifies database connectivity
+///
+/// Unlike `/health` (cheap liveness), this returns 503 when the database
+/// is unreachable or the pool can't hand out a connection in time.
+async fn ready_check(State(state): State<AppState>) -> impl IntoResponse {
+    let result = db::ping(&state.db, READY_CHECK_TIMEOUT).await;
+
+    let size = state.db.size();
+    let idle = state.db.num_idle();
+    let pool = PoolStats {
+        size,
+        idle,
+        used: (size as usize).saturating_sub(idle),
+    };
+
+    match result {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(ReadyResponse {
+                ready: true,
+                error: None,
+                pool,
+            }),
+        ),
+        Err(e) => {
+            tracing::warn!(error = %e, "Readiness check failed");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ReadyResponse {
+                    ready: false,
+                    error: Some(e.to_string()),
+                    pool,
+                }),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::os::unix::fs::MetadataExt;
+    use tower::ServiceExt;
+
+    async fn status(app: Router, method: &str, path: &str) -> StatusCode {
+        app.oneshot(
+            Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_not_served_publicly() {
+        let public = public_routes().with_state(AppState::for_tests());
+        assert_eq!(
+            status(public.clone(), "GET", "/api/admin/workers").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(public.clone(), "POST", "/api/admin/preview").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(
+                public.clone(),
+                "GET",
+                &format!("/api/deployments/{}", uuid::Uuid::new_v4())
+            )
+            .await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(status(public, "GET", "/health").await, StatusCode::OK);
+
+        // The admin listener serves only the admin API
+        let admin = admin_routes().with_state(AppState::for_tests());
+        assert_eq!(
+            status(admin.clone(), "GET", "/api/admin/workers").await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(admin.clone(), "GET", "/api/admin/audit?limit=10").await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(admin, "POST", "/webhook/github").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    /// Send a bare HTTP/1.1 request over a UNIX socket, returning the status line
+    async fn socket_request(path: &Path, request: &str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = tokio::net::UnixStream::connect(path).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_admin_api_served_on_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("admin.sock");
+        // A stale socket from a previous run doesn't block startup
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let stale = path.metadata().unwrap().ino();
+
+        let state = AppState {
+            // Nothing listens here, so authorized requests fail fast in the handler
+            db: PgPoolOptions::new()
+                .acquire_timeout(Duration::from_millis(200))
+                .connect_lazy("postgres://localhost:1/catapult")
+                .unwrap(),
+            ..AppState::for_tests()
+        };
+        let server = tokio::spawn({
+            let path = path.clone();
+            async move { serve_unix(&path, admin_routes().with_state(state), "Admin server").await }
+        });
+        while !path
+            .metadata()
+            .is_ok_and(|m| m.file_type().is_socket() && m.ino() != stale)
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(path.metadata().unwrap().permissions().mode() & 0o777, 0o660);
+
+        assert_eq!(
+            socket_request(
+                &path,
+                "GET /api/admin/auth HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
+            )
+            .await,
+            "HTTP/1.1 401 Unauthorized"
+        );
+        // list_authorized_orgs itself ran and hit the unreachable database
+        assert_eq!(
+            socket_request(
+                &path,
+                "GET /api/admin/auth HTTP/1.1\r\nHost: localhost\r\n\
+                 Authorization: Bearer test-admin-key\r\nConnection: close\r\n\r\n"
+            )
+            .await,
+            "HTTP/1.1 500 Internal Server Error"
+        );
+
+        server.abort();
+
+        // Only the socket is left behind
+        let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_never_replaces_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("admin.sock");
+        std::fs::write(&path, b"not a socket").unwrap();
+
+        let result = serve_unix(
+            &path,
+            admin_routes().with_state(AppState::for_tests()),
+            "Admin server",
+        )
+        .await;
+        assert!(result.unwrap_err().to_string().contains("is not a socket"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"not a socket");
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let base = Duration::from_secs(1);
+        let max = Duration::from_secs(30);
+
+        assert_eq!(backoff_delay(1, base, max), Duration::from_secs(1));
+        assert_eq!(backoff_delay(2, base, max), Duration::from_secs(2));
+        assert_eq!(backoff_delay(5, base, max), Duration::from_secs(16));
+        assert_eq!(backoff_delay(6, base, max), max);
+        assert_eq!(backoff_delay(100, base, max), max);
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_backoff() {
+        let delay = Duration::from_millis(1);
+
+        // Succeeds once the database comes up
+        let mut calls = 0;
+        let result = retry_with_backoff("Not ready", 5, delay, delay, || {
+            calls += 1;
+            let ready = calls >= 3;
+            async move {
+                if ready {
+                    Ok(calls)
+                } else {
+                    anyhow::bail!("connection refused")
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        // Gives up with the last error after the configured attempts
+        let mut calls = 0;
+        let result: Result<()> = retry_with_backoff("Not ready", 4, delay, delay, || {
+            calls += 1;
+            let attempt = calls;
+            async move { anyhow::bail!("attempt {} failed", attempt) }
+        })
+        .await;
+        assert_eq!(result.unwrap_err().to_string(), "attempt 4 failed");
+        assert_eq!(calls, 4);
+    }
+}
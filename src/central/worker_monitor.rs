@@ -4,14 +4,19 @@
 //! and updates the `last_seen` timestamp in the database.
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Method, StatusCode};
 use sqlx::PgPool;
-use tokio::time::{interval, sleep};
+use tokio::time::{Instant, interval_at, sleep, timeout};
 
 use crate::central::db;
+use crate::central::handlers::heartbeat::log_version_skew;
+use crate::shared::http::{HttpTimeouts, build_http_client};
+use crate::shared::version::VersionInfo;
 
 /// Configuration for the worker monitor
 #[derive(Debug, Clone)]
@@ -22,10 +27,14 @@ pub struct MonitorConfig {
     pub request_timeout: Duration,
     /// Maximum retries before marking worker as unhealthy
     pub max_retries: u32,
-    /// Initial retry delay (doubles each retry, up to max_delay)
+    /// Initial retry delay cap (doubles each retry, up to max_delay)
     pub initial_retry_delay: Duration,
-    /// Maximum retry delay
+    /// Maximum retry delay cap
     pub max_retry_delay: Duration,
+    /// Upper bound on the whole initial check across all workers
+    pub initial_check_timeout: Duration,
+    /// Request sent to each worker and the responses that count as healthy
+    pub probe: HealthProbe,
 }
 
 impl Default for MonitorConfig {
@@ -36,33 +45,154 @@ impl Default for MonitorConfig {
             max_retries: 3,
             initial_retry_delay: Duration::from_secs(1),
             max_retry_delay: Duration::from_secs(30),
+            initial_check_timeout: Duration::from_secs(120),
+            probe: HealthProbe::default(),
         }
     }
 }
 
+/// Health check request sent to workers
+///
+/// Defaults to `GET /health` expecting any 2xx. Workers behind a proxy may
+/// need a different path, an auth header, or answer with a specific status.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthProbe {
+    /// HTTP method of the probe
+    pub method: Method,
+    /// Path appended to the worker endpoint, starting with `/`
+    pub path: String,
+    /// Extra header sent with the probe (e.g. a proxy's auth token)
+    pub header: Option<(HeaderName, HeaderValue)>,
+    /// Statuses that count as healthy (empty accepts any 2xx)
+    pub healthy_statuses: Vec<StatusCode>,
+}
+
+impl Default for HealthProbe {
+    fn default() -> Self {
+        Self {
+            method: Method::GET,
+            path: "/health".to_string(),
+            header: None,
+            healthy_statuses: Vec::new(),
+        }
+    }
+}
+
+impl HealthProbe {
+    /// Build a probe from its textual settings, defaulting any that are unset
+    ///
+    /// `header` is `Name: value` and `statuses` a comma-separated list of
+    /// status codes.
+    pub fn parse(
+        method: Option<&str>,
+        path: Option<&str>,
+        header: Option<&str>,
+        statuses: Option<&str>,
+    ) -> Result<Self> {
+        let mut probe = Self::default();
+
+        if let Some(method) = method.map(str::trim).filter(|m| !m.is_empty()) {
+            probe.method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                .with_context(|| format!("Invalid health check method '{}'", method))?;
+        }
+
+        if let Some(path) = path.map(str::trim).filter(|p| !p.is_empty()) {
+            if !path.starts_with('/') {
+                anyhow::bail!("Health check path must start with '/': '{}'", path);
+            }
+            probe.path = path.to_string();
+        }
+
+        if let Some(header) = header.map(str::trim).filter(|h| !h.is_empty()) {
+            let (name, value) = header
+                .split_once(':')
+                .context("Health check header must be 'Name: value'")?;
+            let name = HeaderName::from_bytes(name.trim().as_bytes())
+                .with_context(|| format!("Invalid health check header name '{}'", name.trim()))?;
+            let mut value =
+                HeaderValue::from_str(value.trim()).context("Invalid health check header value")?;
+            // Often a credential, so keep it out of Debug output
+            value.set_sensitive(true);
+            probe.header = Some((name, value));
+        }
+
+        if let Some(statuses) = statuses {
+            probe.healthy_statuses = statuses
+                .split(',')
+                .map(str::trim)
+                .filter(|status| !status.is_empty())
+                .map(|status| {
+                    status
+                        .parse::<u16>()
+                        .ok()
+                        .and_then(|code| StatusCode::from_u16(code).ok())
+                        .with_context(|| format!("Invalid health check status '{}'", status))
+                })
+                .collect::<Result<_>>()?;
+        }
+
+        Ok(probe)
+    }
+
+    /// Whether a probe response status means the worker is healthy
+    fn is_healthy(&self, status: StatusCode) -> bool {
+        if self.healthy_statuses.is_empty() {
+            status.is_success()
+        } else {
+            self.healthy_statuses.contains(&status)
+        }
+    }
+}
+
+/// Send the health probe to a worker endpoint
+async fn probe_health(
+    http_client: &reqwest::Client,
+    endpoint: &str,
+    probe: &HealthProbe,
+) -> Result<()> {
+    let url = format!("{}{}", endpoint.trim_end_matches('/'), probe.path);
+
+    let mut request = http_client.request(probe.method.clone(), &url);
+    if let Some((name, value)) = &probe.header {
+        request = request.header(name, value);
+    }
+    let response = request.send().await?;
+
+    if !probe.is_healthy(response.status()) {
+        anyhow::bail!("Health check returned status {}", response.status());
+    }
+
+    Ok(())
+}
+
 /// Worker health monitor
 ///
 /// Runs as a background task and periodically checks worker health endpoints.
 pub struct WorkerMonitor {
     db: PgPool,
     http_client: reqwest::Client,
-    workers: Arc<HashMap<String, String>>,
+    /// Zone and endpoint of every worker, mirrors included
+    workers: Arc<Vec<(String, String)>>,
     config: MonitorConfig,
+    /// Last build reported by each endpoint, so skew is logged once per change
+    versions: Mutex<HashMap<String, VersionInfo>>,
 }
 
 impl WorkerMonitor {
     /// Create a new worker monitor
-    pub fn new(db: PgPool, workers: HashMap<String, String>, config: MonitorConfig) -> Self {
-        let http_client = reqwest::Client::builder()
-            .timeout(config.request_timeout)
-            .build()
-            .expect("Failed to create HTTP client");
+    pub fn new(db: PgPool, workers: Vec<(String, String)>, config: MonitorConfig) -> Self {
+        let http_client = build_http_client(HttpTimeouts {
+            connect: config.request_timeout,
+            request: config.request_timeout,
+        })
+        .expect("Failed to create HTTP client");
 
         Self {
             db,
             http_client,
             workers: Arc::new(workers),
             config,
+            versions: Mutex::new(HashMap::new()),
         }
     }
 
@@ -84,10 +214,20 @@ impl WorkerMonitor {
         );
 
         // Initial check with retries for workers that might not be ready yet
-        self.initial_check().await;
+        if timeout(self.config.initial_check_timeout, self.initial_check())
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                timeout_secs = self.config.initial_check_timeout.as_secs(),
+                "Initial worker health check timed out"
+            );
+        }
 
-        // Regular health check interval
-        let mut check_interval = interval(self.config.check_interval);
+        // Regular health check interval, starting at a random offset so
+        // Central replicas started together don't probe in lockstep
+        let start = Instant::now() + full_jitter(self.config.check_interval);
+        let mut check_interval = interval_at(start, self.config.check_interval);
 
         loop {
             check_interval.tick().await;
@@ -98,11 +238,13 @@ impl WorkerMonitor {
     /// Initial health check with exponential backoff
     ///
     /// Workers might not be ready when Central starts, so we retry with backoff.
+    /// Delays are fully jittered so workers restarting together don't retry
+    /// in lockstep.
     async fn initial_check(&self) {
         tracing::info!("Performing initial worker health check");
 
         for (zone, endpoint) in self.workers.iter() {
-            let mut delay = self.config.initial_retry_delay;
+            let mut delay_cap = self.config.initial_retry_delay;
             let mut attempt = 0;
 
             loop {
@@ -124,17 +266,18 @@ impl WorkerMonitor {
                             break;
                         }
 
+                        let delay = full_jitter(delay_cap);
                         tracing::debug!(
                             zone = %zone,
                             endpoint = %endpoint,
                             error = %e,
                             attempt = attempt,
-                            retry_in_secs = delay.as_secs(),
+                            retry_in_ms = delay.as_millis() as u64,
                             "Worker not ready, retrying"
                         );
 
                         sleep(delay).await;
-                        delay = std::cmp::min(delay * 2, self.config.max_retry_delay);
+                        delay_cap = std::cmp::min(delay_cap * 2, self.config.max_retry_delay);
                     }
                 }
             }
@@ -157,19 +300,168 @@ impl WorkerMonitor {
 
     /// Check a single worker's health
     async fn check_worker_health(&self, zone: &str, endpoint: &str) -> Result<()> {
-        let health_url = format!("{}/health", endpoint);
-
-        let response = self.http_client.get(&health_url).send().await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Health check returned status {}", response.status());
-        }
+        probe_health(&self.http_client, endpoint, &self.config.probe).await?;
 
         // Update last_seen in database
-        db::update_worker_heartbeat(&self.db, zone).await?;
+        db::update_endpoint_heartbeat(&self.db, zone, endpoint).await?;
 
         tracing::trace!(zone = %zone, "Worker health check passed");
 
+        self.check_worker_version(zone, endpoint).await;
+
         Ok(())
     }
+
+    /// Fetch a worker's build info and log it when it changes
+    ///
+    /// Best effort: older workers without `/version` are simply skipped.
+    async fn check_worker_version(&self, zone: &str, endpoint: &str) {
+        let version_url = format!("{}/version", endpoint);
+
+        let version = match self.http_client.get(&version_url).send().await {
+            Ok(response) if response.status().is_success() => {
+                match response.json::<VersionInfo>().await {
+                    Ok(version) => version,
+                    Err(e) => {
+                        tracing::debug!(zone = %zone, error = %e, "Invalid worker version response");
+                        return;
+                    }
+                }
+            }
+            _ => return,
+        };
+
+        let mut versions = self.versions.lock().unwrap();
+        if versions.get(endpoint) != Some(&version) {
+            tracing::info!(
+                zone = %zone,
+                version = %version.version,
+                commit = %version.git_commit,
+                "Worker build reported"
+            );
+            log_version_skew(zone, &version);
+            versions.insert(endpoint.to_string(), version);
+        }
+    }
+}
+
+/// Random delay between zero and `cap` ("full jitter" backoff)
+fn full_jitter(cap: Duration) -> Duration {
+    cap.mul_f64(fastrand::f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_jitter_within_bounds() {
+        let cap = Duration::from_secs(4);
+        let delays: Vec<_> = (0..1000).map(|_| full_jitter(cap)).collect();
+
+        assert!(delays.iter().all(|delay| *delay <= cap));
+        // Spread over the range rather than pinned to the cap
+        assert!(delays.iter().any(|delay| *delay < cap / 2));
+        assert!(delays.iter().any(|delay| *delay > cap / 2));
+
+        assert_eq!(full_jitter(Duration::ZERO), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_parse_health_probe() {
+        let probe = HealthProbe::parse(None, None, None, None).unwrap();
+        assert_eq!(probe, HealthProbe::default());
+        assert_eq!(probe.method, Method::GET);
+        assert_eq!(probe.path, "/health");
+
+        let probe = HealthProbe::parse(
+            Some("head"),
+            Some("/_catapult/health"),
+            Some("X-Probe-Token: s3cret"),
+            Some("200, 204"),
+        )
+        .unwrap();
+        assert_eq!(probe.method, Method::HEAD);
+        assert_eq!(probe.path, "/_catapult/health");
+        let (name, value) = probe.header.as_ref().unwrap();
+        assert_eq!(name, "x-probe-token");
+        assert_eq!(value, "s3cret");
+        assert!(!format!("{:?}", probe).contains("s3cret"));
+        assert_eq!(
+            probe.healthy_statuses,
+            vec![StatusCode::OK, StatusCode::NO_CONTENT]
+        );
+
+        assert!(HealthProbe::parse(None, Some("health"), None, None).is_err());
+        assert!(HealthProbe::parse(None, None, Some("X-Probe-Token"), None).is_err());
+        assert!(HealthProbe::parse(None, None, None, Some("ok")).is_err());
+        assert!(HealthProbe::parse(None, None, None, Some("99")).is_err());
+    }
+
+    #[test]
+    fn test_healthy_statuses() {
+        let default = HealthProbe::default();
+        assert!(default.is_healthy(StatusCode::OK));
+        assert!(default.is_healthy(StatusCode::NO_CONTENT));
+        assert!(!default.is_healthy(StatusCode::SERVICE_UNAVAILABLE));
+
+        let probe = HealthProbe::parse(None, None, None, Some("204,401")).unwrap();
+        assert!(probe.is_healthy(StatusCode::NO_CONTENT));
+        assert!(probe.is_healthy(StatusCode::UNAUTHORIZED));
+        assert!(!probe.is_healthy(StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn test_probe_uses_configured_request() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path("/_catapult/health"))
+            .and(header("x-probe-token", "s3cret"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let probe = HealthProbe::parse(
+            Some("HEAD"),
+            Some("/_catapult/health"),
+            Some("X-Probe-Token: s3cret"),
+            Some("204"),
+        )
+        .unwrap();
+        let client = reqwest::Client::new();
+        probe_health(&client, &server.uri(), &probe).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_probe_rejects_unlisted_status() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        probe_health(&client, &server.uri(), &HealthProbe::default())
+            .await
+            .unwrap();
+
+        // Only the configured statuses count once any are listed
+        let probe = HealthProbe::parse(None, None, None, Some("204")).unwrap();
+        let error = probe_health(&client, &server.uri(), &probe)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("200"), "{}", error);
+
+        // A missing path is unhealthy too
+        let probe = HealthProbe::parse(None, Some("/status"), None, None).unwrap();
+        assert!(probe_health(&client, &server.uri(), &probe).await.is_err());
+    }
 }
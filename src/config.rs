@@ -1,8 +1,27 @@
-use std::collections::HashMap;
-use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+
+use crate::central::admin_keys::AdminKeys;
+use crate::central::deploy_config::DefaultDeployConfigs;
+use crate::central::github::{
+    DEFAULT_API_BASE, DEFAULT_STATUS_CONTEXT, DEFAULT_USER_AGENT, GitHubApi,
+};
+use crate::central::outbox::EventSink;
+use crate::central::secrets::{WorkerSecrets, load_worker_public_keys, parse_worker_secrets};
+use crate::central::worker_monitor::HealthProbe;
+use crate::shared::WorkerSettings;
+use crate::shared::auth::{
+    RequestSigner, RequestVerifier, SignatureTolerance, load_signing_key, load_verifying_key,
+};
+use crate::shared::http::HttpTimeouts;
+use crate::worker::allowlist::{IpNet, parse_allowed_sources};
+use crate::worker::builder::nix::{NixSettings, parse_nix_list};
+use crate::worker::builder::policy::{BuildCommandPolicy, IsolationPolicy};
 
 /// Configuration for Central mode
 #[derive(Debug, Clone)]
@@ -10,6 +29,21 @@ pub struct CentralConfig {
     /// PostgreSQL connection URL
     pub database_url: String,
 
+    /// Maximum number of pooled database connections
+    pub database_max_connections: u32,
+
+    /// Minimum number of idle database connections kept open
+    pub database_min_connections: u32,
+
+    /// How long to wait for a pooled connection before failing
+    pub database_acquire_timeout: Duration,
+
+    /// How long to wait for the initial database connection at startup
+    pub database_connect_timeout: Duration,
+
+    /// Attempts at connecting and migrating at startup before giving up
+    pub database_startup_attempts: u32,
+
     /// GitHub App ID
     pub github_app_id: u64,
 
@@ -19,12 +53,27 @@ pub struct CentralConfig {
     /// GitHub webhook secret for signature verification
     pub github_webhook_secret: String,
 
-    /// Shared secret for worker authentication
-    pub worker_shared_secret: String,
+    /// GitHub Apps served besides the primary one, from GITHUB_EXTRA_APPS
+    pub github_extra_apps: Vec<GitHubAppConfig>,
+
+    /// Host repositories are cloned from (e.g. "github.com" or a GitHub Enterprise host)
+    pub github_host: String,
+
+    /// GitHub API base URL and User-Agent (public GitHub by default)
+    pub github_api: GitHubApi,
+
+    /// Secrets for worker authentication, per zone with a global fallback
+    pub worker_secrets: WorkerSecrets,
 
     /// Address to listen on
     pub listen_addr: SocketAddr,
 
+    /// Separate address for the admin API (None serves it on `listen_addr`)
+    pub admin_listen_addr: Option<SocketAddr>,
+
+    /// UNIX socket to serve the admin API on instead of a TCP address
+    pub admin_socket_path: Option<PathBuf>,
+
     /// Base URL for worker callbacks (how workers reach central)
     /// e.g., "http://catapult-central:8080" for internal network
     pub callback_base_url: String,
@@ -33,21 +82,136 @@ pub struct CentralConfig {
     /// e.g., {"production": "https://deployer.example.com", "staging": "https://deployer-staging.example.com"}
     pub workers: HashMap<String, String>,
 
-    /// Admin API key for managing authorizations
-    pub admin_api_key: String,
+    /// Mirror worker endpoints by environment, sent main branch deployments
+    /// alongside the primary in `workers`
+    pub worker_mirrors: HashMap<String, Vec<String>>,
+
+    /// Successful workers a mirrored deployment needs, from MIRROR_QUORUM
+    /// (None is a majority of the zone's workers)
+    pub mirror_quorum: Option<usize>,
+
+    /// Keys accepted by the admin API, from ADMIN_API_KEY and ADMIN_API_KEYS
+    pub admin_keys: AdminKeys,
+
+    /// Deploy config defaults for repos, loaded from DEFAULT_DEPLOY_CONFIG_PATH
+    pub default_deploy_configs: DefaultDeployConfigs,
+
+    /// Settings served to each zone's workers, loaded from WORKER_SETTINGS_PATH
+    pub worker_settings: HashMap<String, WorkerSettings>,
+
+    /// Zones whose main branch deployments wait for operator approval (lowercase)
+    pub approval_required_zones: HashSet<String>,
+
+    /// How long a deployment waits for approval before it expires
+    pub approval_timeout: Duration,
+
+    /// How long a deployment may sit in `pending`/`building` before its
+    /// worker is asked what became of it
+    pub stuck_deployment_timeout: Duration,
+
+    /// Age after which an open PR's preview is cleaned up (None keeps
+    /// previews until the PR closes)
+    pub max_preview_age: Option<Duration>,
+
+    /// How long after a PR closes its preview is cleaned up, cancelled if the
+    /// PR is reopened first (None cleans up right away)
+    pub pr_cleanup_grace_period: Option<Duration>,
+
+    /// Append build duration and a commit link to PR deployment comments
+    pub comment_footer: bool,
+
+    /// Commit status context, with `{environment}` substituted; None posts no
+    /// commit statuses
+    pub commit_status_context: Option<String>,
+
+    /// Timeouts for outbound requests (GitHub, workers)
+    pub http_timeouts: HttpTimeouts,
+
+    /// Health check sent to workers, from the WORKER_HEALTH_* variables
+    pub worker_health_probe: HealthProbe,
+
+    /// Where deployment events are delivered, from EVENT_WEBHOOK_URL and
+    /// SLACK_WEBHOOK_URL
+    pub event_sinks: Vec<EventSink>,
 }
 
 impl CentralConfig {
     /// Load configuration from environment variables and CLI arguments
     ///
-    /// Workers are specified via CLI: `--worker zone=https://endpoint`
-    pub fn from_env_and_args(worker_args: Vec<String>) -> Result<Self> {
+    /// Workers are specified via CLI: `--worker zone=https://endpoint`, and
+    /// their mirrors via `--mirror zone=https://endpoint`
+    pub fn from_env_and_args(worker_args: Vec<String>, mirror_args: Vec<String>) -> Result<Self> {
         let workers = Self::parse_worker_args(worker_args)?;
+        let worker_mirrors = Self::parse_mirror_args(mirror_args, &workers)?;
+
+        let database_max_connections = env_or("DATABASE_MAX_CONNECTIONS", 10)?;
+        let database_min_connections = env_or("DATABASE_MIN_CONNECTIONS", 0)?;
+        let database_acquire_timeout = env_or("DATABASE_ACQUIRE_TIMEOUT", 30)?;
+        let database_connect_timeout = env_or("DATABASE_CONNECT_TIMEOUT", 10)?;
+        validate_pool_settings(
+            database_max_connections,
+            database_min_connections,
+            database_acquire_timeout,
+            database_connect_timeout,
+        )?;
+
+        let max_preview_age = match env_or("MAX_PREVIEW_AGE_SECS", 0u64)? {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        let pr_cleanup_grace_period = match env_or("PR_CLEANUP_GRACE_SECS", 0u64)? {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        validate_cleanup_grace_period(pr_cleanup_grace_period, max_preview_age)?;
+
+        let worker_secrets = WorkerSecrets::new(
+            std::env::var("WORKER_SHARED_SECRET").ok(),
+            match std::env::var("WORKER_SECRETS") {
+                Ok(value) => parse_worker_secrets(&value)
+                    .context("WORKER_SECRETS must be zone=secret entries")?,
+                Err(_) => HashMap::new(),
+            },
+        );
+        let worker_secrets = match (
+            std::env::var("CENTRAL_SIGNING_KEY_FILE"),
+            std::env::var("WORKER_PUBLIC_KEYS"),
+        ) {
+            (Ok(key_file), Ok(public_keys)) => worker_secrets.with_keys(
+                load_signing_key(Path::new(&key_file))?,
+                load_worker_public_keys(&public_keys)
+                    .context("WORKER_PUBLIC_KEYS must be zone=path entries")?,
+            ),
+            (Err(_), Err(_)) => worker_secrets,
+            _ => anyhow::bail!(
+                "Set both or neither of CENTRAL_SIGNING_KEY_FILE and WORKER_PUBLIC_KEYS"
+            ),
+        };
+        let worker_secrets = worker_secrets.with_tolerance(signature_tolerance_from_env()?);
+        if worker_secrets.is_empty() {
+            anyhow::bail!(
+                "WORKER_SHARED_SECRET, WORKER_SECRETS or WORKER_PUBLIC_KEYS environment variable required"
+            );
+        }
+        if std::env::var_os("ADMIN_LISTEN_ADDR").is_some()
+            && std::env::var_os("ADMIN_SOCKET_PATH").is_some()
+        {
+            anyhow::bail!("Set only one of ADMIN_LISTEN_ADDR and ADMIN_SOCKET_PATH");
+        }
 
         Ok(Self {
             database_url: std::env::var("DATABASE_URL")
                 .context("DATABASE_URL environment variable required")?,
 
+            database_max_connections,
+            database_min_connections,
+            database_acquire_timeout: Duration::from_secs(database_acquire_timeout),
+            database_connect_timeout: Duration::from_secs(database_connect_timeout),
+            database_startup_attempts: match env_or("DATABASE_STARTUP_ATTEMPTS", 10)? {
+                0 => anyhow::bail!("DATABASE_STARTUP_ATTEMPTS must be at least 1"),
+                attempts => attempts,
+            },
+
             github_app_id: std::env::var("GITHUB_APP_ID")
                 .context("GITHUB_APP_ID environment variable required")?
                 .parse()
@@ -60,21 +224,91 @@ impl CentralConfig {
             github_webhook_secret: std::env::var("GITHUB_WEBHOOK_SECRET")
                 .context("GITHUB_WEBHOOK_SECRET environment variable required")?,
 
-            worker_shared_secret: std::env::var("WORKER_SHARED_SECRET")
-                .context("WORKER_SHARED_SECRET environment variable required")?,
+            github_extra_apps: parse_github_apps(
+                &std::env::var("GITHUB_EXTRA_APPS").unwrap_or_default(),
+            )?,
+
+            github_host: std::env::var("GITHUB_HOST")
+                .map(|host| host.trim().to_ascii_lowercase())
+                .unwrap_or_else(|_| "github.com".to_string()),
+
+            github_api: GitHubApi::new(
+                &std::env::var("GITHUB_API_BASE").unwrap_or_else(|_| DEFAULT_API_BASE.to_string()),
+                &std::env::var("GITHUB_USER_AGENT")
+                    .unwrap_or_else(|_| DEFAULT_USER_AGENT.to_string()),
+            ),
+
+            worker_secrets,
 
             listen_addr: std::env::var("LISTEN_ADDR")
                 .unwrap_or_else(|_| "0.0.0.0:8080".to_string())
                 .parse()
                 .context("LISTEN_ADDR must be a valid socket address")?,
 
+            admin_listen_addr: std::env::var("ADMIN_LISTEN_ADDR")
+                .ok()
+                .map(|addr| addr.parse())
+                .transpose()
+                .context("ADMIN_LISTEN_ADDR must be a valid socket address")?,
+
+            admin_socket_path: std::env::var("ADMIN_SOCKET_PATH").ok().map(PathBuf::from),
+
             callback_base_url: std::env::var("CALLBACK_BASE_URL")
                 .context("CALLBACK_BASE_URL environment variable required (e.g., http://catapult-central:8080)")?,
 
-            admin_api_key: std::env::var("ADMIN_API_KEY")
-                .context("ADMIN_API_KEY environment variable required")?,
+            admin_keys: AdminKeys::new(
+                std::env::var("ADMIN_API_KEY").ok().as_deref(),
+                &std::env::var("ADMIN_API_KEYS").unwrap_or_default(),
+            )?,
+
+            default_deploy_configs: match std::env::var("DEFAULT_DEPLOY_CONFIG_PATH") {
+                Ok(path) => DefaultDeployConfigs::load(path.as_ref())?,
+                Err(_) => DefaultDeployConfigs::default(),
+            },
+
+            worker_settings: match std::env::var("WORKER_SETTINGS_PATH") {
+                Ok(path) => load_worker_settings(path.as_ref())?,
+                Err(_) => HashMap::new(),
+            },
+
+            approval_required_zones: std::env::var("APPROVAL_REQUIRED_ZONES")
+                .map(|value| parse_zone_list(&value))
+                .unwrap_or_default(),
+
+            approval_timeout: Duration::from_secs(env_or("APPROVAL_TIMEOUT_SECS", 86400)?),
+            stuck_deployment_timeout: Duration::from_secs(env_or(
+                "STUCK_DEPLOYMENT_TIMEOUT_SECS",
+                3600,
+            )?),
+            max_preview_age,
+            pr_cleanup_grace_period,
+
+            comment_footer: env_or("COMMENT_FOOTER", true)?,
+
+            // Opt-in: statuses need the app's "Commit statuses" permission
+            commit_status_context: env_or("COMMIT_STATUSES", false)?.then(|| {
+                std::env::var("COMMIT_STATUS_CONTEXT")
+                    .unwrap_or_else(|_| DEFAULT_STATUS_CONTEXT.to_string())
+            }),
+
+            http_timeouts: http_timeouts_from_env()?,
+
+            worker_health_probe: HealthProbe::parse(
+                std::env::var("WORKER_HEALTH_METHOD").ok().as_deref(),
+                std::env::var("WORKER_HEALTH_PATH").ok().as_deref(),
+                std::env::var("WORKER_HEALTH_HEADER").ok().as_deref(),
+                std::env::var("WORKER_HEALTH_STATUSES").ok().as_deref(),
+            )
+            .context("Invalid WORKER_HEALTH_* settings")?,
+
+            event_sinks: event_sinks_from_env()?,
 
             workers,
+            worker_mirrors,
+            mirror_quorum: match env_or("MIRROR_QUORUM", 0usize)? {
+                0 => None,
+                quorum => Some(quorum),
+            },
         })
     }
 
@@ -85,25 +319,7 @@ impl CentralConfig {
         let mut workers = HashMap::new();
 
         for arg in args {
-            let (zone, endpoint) = arg.split_once('=').with_context(|| {
-                format!(
-                    "Invalid worker format '{}', expected 'zone=https://endpoint'",
-                    arg
-                )
-            })?;
-
-            let zone = zone.trim();
-            let endpoint = endpoint.trim();
-
-            if zone.is_empty() {
-                anyhow::bail!("Empty zone name in worker argument '{}'", arg);
-            }
-            if endpoint.is_empty() {
-                anyhow::bail!("Empty endpoint URL in worker argument '{}'", arg);
-            }
-            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
-                anyhow::bail!("Worker endpoint must be a URL: '{}'", endpoint);
-            }
+            let (zone, endpoint) = split_worker_arg(&arg)?;
 
             if workers
                 .insert(zone.to_string(), endpoint.to_string())
@@ -116,6 +332,45 @@ impl CentralConfig {
         Ok(workers)
     }
 
+    /// Parse mirror arguments from CLI, in the same `zone=https://endpoint`
+    /// format as workers
+    ///
+    /// Each mirror's zone needs a primary worker, and an endpoint may serve
+    /// a zone only once.
+    fn parse_mirror_args(
+        args: Vec<String>,
+        workers: &HashMap<String, String>,
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let mut mirrors: HashMap<String, Vec<String>> = HashMap::new();
+
+        for arg in args {
+            let (zone, endpoint) = split_worker_arg(&arg)?;
+
+            let Some(primary) = workers.get(zone) else {
+                anyhow::bail!("Mirror for zone '{}' without a --worker for it", zone);
+            };
+            let endpoints = mirrors.entry(zone.to_string()).or_default();
+            if primary == endpoint || endpoints.iter().any(|e| e == endpoint) {
+                anyhow::bail!("Duplicate endpoint '{}' for zone '{}'", endpoint, zone);
+            }
+            endpoints.push(endpoint.to_string());
+        }
+
+        Ok(mirrors)
+    }
+
+    /// Successful workers needed for a deployment mirrored to `workers` workers
+    pub fn mirror_quorum(&self, workers: usize) -> usize {
+        self.mirror_quorum
+            .unwrap_or(workers / 2 + 1)
+            .clamp(1, workers.max(1))
+    }
+
+    /// Whether main branch deployments to `zone` need operator approval
+    pub fn requires_approval(&self, zone: &str) -> bool {
+        self.approval_required_zones.contains(&zone.to_lowercase())
+    }
+
     /// Load the GitHub App private key from disk
     pub fn load_private_key(&self) -> Result<String> {
         std::fs::read_to_string(&self.github_private_key_path).with_context(|| {
@@ -127,34 +382,295 @@ impl CentralConfig {
     }
 }
 
+/// An additional GitHub App Central accepts webhooks from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubAppConfig {
+    pub app_id: u64,
+    pub private_key_path: PathBuf,
+    pub webhook_secret: String,
+}
+
+impl GitHubAppConfig {
+    /// Load the app's private key from disk
+    pub fn load_private_key(&self) -> Result<String> {
+        std::fs::read_to_string(&self.private_key_path).with_context(|| {
+            format!(
+                "Failed to read private key of GitHub App {} from {:?}",
+                self.app_id, self.private_key_path
+            )
+        })
+    }
+}
+
+/// Parse `app_id:private_key_path:webhook_secret` entries, comma or newline separated
+fn parse_github_apps(value: &str) -> Result<Vec<GitHubAppConfig>> {
+    value
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let mut parts = entry.splitn(3, ':');
+            let (Some(app_id), Some(path), Some(secret)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                anyhow::bail!(
+                    "Invalid GITHUB_EXTRA_APPS entry, expected app_id:private_key_path:webhook_secret"
+                );
+            };
+            if path.is_empty() || secret.is_empty() {
+                anyhow::bail!("GITHUB_EXTRA_APPS entry for app {} is incomplete", app_id);
+            }
+            Ok(GitHubAppConfig {
+                app_id: app_id
+                    .parse()
+                    .with_context(|| format!("Invalid GitHub App ID '{}'", app_id))?,
+                private_key_path: path.into(),
+                webhook_secret: secret.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Load per-zone worker settings from a JSON object keyed by zone
+fn load_worker_settings(path: &std::path::Path) -> Result<HashMap<String, WorkerSettings>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read worker settings from {:?}", path))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse worker settings in {:?}", path))
+}
+
+/// Split a `zone=https://endpoint` worker argument into its trimmed parts
+fn split_worker_arg(arg: &str) -> Result<(&str, &str)> {
+    let (zone, endpoint) = arg.split_once('=').with_context(|| {
+        format!(
+            "Invalid worker format '{}', expected 'zone=https://endpoint'",
+            arg
+        )
+    })?;
+
+    let zone = zone.trim();
+    let endpoint = endpoint.trim();
+
+    if zone.is_empty() {
+        anyhow::bail!("Empty zone name in worker argument '{}'", arg);
+    }
+    if endpoint.is_empty() {
+        anyhow::bail!("Empty endpoint URL in worker argument '{}'", arg);
+    }
+    if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+        anyhow::bail!("Worker endpoint must be a URL: '{}'", endpoint);
+    }
+
+    Ok((zone, endpoint))
+}
+
+/// Parse a comma-separated list of zone names (case-insensitive)
+fn parse_zone_list(value: &str) -> HashSet<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|zone| !zone.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Read outbound HTTP timeouts from HTTP_CONNECT_TIMEOUT_SECS and HTTP_REQUEST_TIMEOUT_SECS
+fn http_timeouts_from_env() -> Result<HttpTimeouts> {
+    let defaults = HttpTimeouts::default();
+    let connect = env_or("HTTP_CONNECT_TIMEOUT_SECS", defaults.connect.as_secs())?;
+    let request = env_or("HTTP_REQUEST_TIMEOUT_SECS", defaults.request.as_secs())?;
+
+    if connect == 0 || request == 0 {
+        anyhow::bail!("HTTP_CONNECT_TIMEOUT_SECS and HTTP_REQUEST_TIMEOUT_SECS must be positive");
+    }
+
+    Ok(HttpTimeouts {
+        connect: Duration::from_secs(connect),
+        request: Duration::from_secs(request),
+    })
+}
+
+/// Read the deployment event sinks from the environment
+fn event_sinks_from_env() -> Result<Vec<EventSink>> {
+    let mut sinks = Vec::new();
+
+    if let Ok(url) = std::env::var("EVENT_WEBHOOK_URL") {
+        let url = EventSink::parse_url(&url).context("EVENT_WEBHOOK_URL")?;
+        sinks.push(EventSink::Webhook(url));
+    }
+    if let Ok(url) = std::env::var("SLACK_WEBHOOK_URL") {
+        let url = EventSink::parse_url(&url).context("SLACK_WEBHOOK_URL")?;
+        sinks.push(EventSink::Slack(url));
+    }
+
+    Ok(sinks)
+}
+
+/// Parse a comma-separated list of IP addresses
+fn parse_ip_list(value: &str) -> Result<Vec<IpAddr>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .map(|ip| {
+            ip.parse()
+                .with_context(|| format!("Invalid IP address '{}'", ip))
+        })
+        .collect()
+}
+
+/// Upper bound for DATABASE_MAX_CONNECTIONS (Postgres defaults to 100 server-wide)
+const MAX_POOL_CONNECTIONS: u32 = 100;
+
+/// Upper bound for database timeouts, in seconds
+const MAX_DATABASE_TIMEOUT_SECS: u64 = 300;
+
+/// Request signature tolerances, shared by Central and worker modes
+fn signature_tolerance_from_env() -> Result<SignatureTolerance> {
+    let defaults = SignatureTolerance::default();
+    SignatureTolerance::new(
+        env_or("SIGNATURE_MAX_AGE_SECS", defaults.max_age_secs())?,
+        env_or("SIGNATURE_MAX_SKEW_SECS", defaults.max_skew_secs())?,
+    )
+}
+
+/// Read an optional numeric environment variable, falling back to a default
+///
+/// A variable that is set but fails to parse is an error rather than being ignored.
+fn env_or<T>(name: &str, default: T) -> Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .with_context(|| format!("{} must be a valid number, got '{}'", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Check that a PR cleanup grace period has preview expiry to fall back on
+///
+/// Scheduled cleanups are kept in memory; after a restart, only the preview
+/// expiry sweep removes the previews of PRs closed before it.
+fn validate_cleanup_grace_period(
+    grace_period: Option<Duration>,
+    max_preview_age: Option<Duration>,
+) -> Result<()> {
+    if grace_period.is_some() && max_preview_age.is_none() {
+        anyhow::bail!(
+            "PR_CLEANUP_GRACE_SECS requires MAX_PREVIEW_AGE_SECS, so previews whose scheduled \
+             cleanup a restart dropped are still removed"
+        );
+    }
+    Ok(())
+}
+
+/// Validate database pool settings are within sane bounds
+fn validate_pool_settings(
+    max_connections: u32,
+    min_connections: u32,
+    acquire_timeout_secs: u64,
+    connect_timeout_secs: u64,
+) -> Result<()> {
+    if max_connections == 0 || max_connections > MAX_POOL_CONNECTIONS {
+        anyhow::bail!(
+            "DATABASE_MAX_CONNECTIONS must be between 1 and {}, got {}",
+            MAX_POOL_CONNECTIONS,
+            max_connections
+        );
+    }
+    if min_connections > max_connections {
+        anyhow::bail!(
+            "DATABASE_MIN_CONNECTIONS ({}) must not exceed DATABASE_MAX_CONNECTIONS ({})",
+            min_connections,
+            max_connections
+        );
+    }
+    for (name, secs) in [
+        ("DATABASE_ACQUIRE_TIMEOUT", acquire_timeout_secs),
+        ("DATABASE_CONNECT_TIMEOUT", connect_timeout_secs),
+    ] {
+        if secs == 0 || secs > MAX_DATABASE_TIMEOUT_SECS {
+            anyhow::bail!(
+                "{} must be between 1 and {} seconds, got {}",
+                name,
+                MAX_DATABASE_TIMEOUT_SECS,
+                secs
+            );
+        }
+    }
+
+    Ok(())
+}
+
 /// Configuration for Worker mode
 #[derive(Debug, Clone)]
 pub struct WorkerConfig {
-    /// URL of the Central server (reserved for future use)
-    #[allow(dead_code)]
+    /// URL of the Central server, for site inventory reports
     pub central_url: String,
 
+    /// Zone this worker serves (None disables site inventory reports)
+    pub zone: Option<String>,
+
+    /// Interval between site inventory reports to Central
+    pub inventory_interval: Duration,
+
     /// Shared secret for authentication with Central
     pub worker_shared_secret: String,
 
+    /// Ed25519 keys used instead of the shared secret
+    pub request_keys: Option<RequestKeys>,
+
+    /// How far Central's request timestamps may be from the local clock
+    pub signature_tolerance: SignatureTolerance,
+
     /// Path to Podman socket
     pub podman_socket: PathBuf,
 
-    /// Caddy admin API URL
-    pub caddy_admin_api: String,
+    /// Caddy admin API URL, optionally per zone
+    pub caddy_admin_api: CaddyAdminApis,
 
     /// Directory where sites are deployed
     pub sites_dir: PathBuf,
 
+    /// Deploy into `sites_dir/{zone}/{site_id}` instead of `sites_dir/{site_id}`
+    pub sites_dir_per_zone: bool,
+
+    /// Previous releases kept per site after a deploy (0 keeps none)
+    pub keep_releases: usize,
+
+    /// Sites whose Caddy routes are restored at once on startup
+    pub route_restore_concurrency: usize,
+
+    /// How long to wait for a deployed URL to answer before reporting failure
+    /// (None skips the post-deploy health check)
+    pub deploy_health_check_timeout: Option<Duration>,
+
     /// Address to listen on
     pub listen_addr: SocketAddr,
 
     /// Whether to use container isolation for builds
     pub use_containers: bool,
 
+    /// Only log the build network's iptables commands, for the system
+    /// config to apply (the rules are still verified)
+    pub build_network_dry_run: bool,
+
     /// Container image for builds (must have nix installed)
     pub build_image: String,
 
+    /// Extra options for `nix develop` (substituters, flake registry, mirror)
+    pub nix: NixSettings,
+
+    /// Which repository-provided build commands may run
+    pub build_command_policy: BuildCommandPolicy,
+
+    /// Which isolation levels builds may request (host builds for trusted orgs)
+    pub isolation_policy: IsolationPolicy,
+
     /// Memory limit for build containers (in bytes)
     pub container_memory_limit: u64,
 
@@ -164,6 +680,36 @@ pub struct WorkerConfig {
     /// PID limit for build containers
     pub container_pids_limit: i64,
 
+    /// Upper bound for per-deployment memory overrides (in bytes)
+    pub container_max_memory_limit: u64,
+
+    /// Upper bound for per-deployment CPU overrides (number of CPUs * 100000)
+    pub container_max_cpu_quota: i64,
+
+    /// Upper bound for per-deployment PID limit overrides
+    pub container_max_pids_limit: i64,
+
+    /// Timeout for each git clone/fetch/checkout invocation and artifact download
+    pub clone_timeout: Duration,
+
+    /// Upper bound for a prebuilt artifact archive and its unpacked contents (in bytes)
+    pub max_artifact_bytes: u64,
+
+    /// Upper bound for a cloned repository checkout (in bytes, None is unlimited)
+    pub max_repo_bytes: Option<u64>,
+
+    /// Longest a `/build?wait=true` caller is kept waiting for the result
+    pub build_wait_timeout: Duration,
+
+    /// Source networks allowed to call /build and /cleanup (empty allows all)
+    pub allowed_sources: Vec<IpNet>,
+
+    /// Take the client address from X-Forwarded-For (set by a trusted proxy)
+    pub trust_forwarded_for: bool,
+
+    /// Timeouts for outbound requests (Caddy, Cloudflare, Central callbacks)
+    pub http_timeouts: HttpTimeouts,
+
     // === Cloudflare Tunnel Configuration ===
     //
     // For automatic DNS record and tunnel ingress management:
@@ -181,29 +727,202 @@ pub struct WorkerConfig {
 
     /// Local service URL for tunnel routing (defaults to http://localhost:8080)
     pub cloudflare_service_url: String,
+
+    /// Per-environment tunnels, overriding the default tunnel for that zone
+    pub cloudflare_tunnels: HashMap<String, CloudflareTunnel>,
+
+    /// Whether the default route goes through a tunnel or straight to the origin
+    pub cloudflare_dns_mode: CloudflareDnsMode,
+
+    /// Worker addresses for A/AAAA records in direct mode
+    pub cloudflare_origin_ips: Vec<IpAddr>,
+}
+
+/// How Cloudflare DNS records route to this worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CloudflareDnsMode {
+    /// CNAME to the tunnel plus tunnel ingress rules
+    #[default]
+    Tunnel,
+    /// A/AAAA records to `cloudflare_origin_ips`, no tunnel
+    Direct,
+}
+
+impl std::str::FromStr for CloudflareDnsMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "tunnel" => Ok(Self::Tunnel),
+            "direct" => Ok(Self::Direct),
+            other => anyhow::bail!(
+                "Unknown Cloudflare DNS mode '{}', expected tunnel or direct",
+                other
+            ),
+        }
+    }
+}
+
+/// Ed25519 keys a worker authenticates to Central with
+#[derive(Debug, Clone)]
+pub struct RequestKeys {
+    /// The worker's private key, for requests to Central
+    pub signing_key: SigningKey,
+    /// Central's public key, for requests from Central
+    pub central_key: VerifyingKey,
+}
+
+/// A Cloudflare tunnel used for one environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudflareTunnel {
+    /// Tunnel ID
+    pub tunnel_id: String,
+
+    /// Local service URL (None uses CLOUDFLARE_SERVICE_URL)
+    pub service_url: Option<String>,
+}
+
+/// Caddy admin API endpoints, optionally one per zone
+///
+/// Workers fronting a separate Caddy per zone route each zone's sites through
+/// that zone's admin API; everything else uses the default endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaddyAdminApis {
+    default: String,
+    zones: HashMap<String, String>,
+}
+
+impl CaddyAdminApis {
+    /// A single admin API for every zone
+    pub fn single(url: impl Into<String>) -> Self {
+        Self {
+            default: url.into(),
+            zones: HashMap::new(),
+        }
+    }
+
+    /// Parse comma-separated `zone=url` overrides on top of a default endpoint
+    pub fn parse(default: &str, zones: &str) -> Result<Self> {
+        let mut apis = Self::single(default);
+
+        for entry in zones.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (zone, url) = entry.split_once('=').with_context(|| {
+                format!(
+                    "Invalid Caddy admin API entry '{}', expected 'zone=url'",
+                    entry
+                )
+            })?;
+            let (zone, url) = (zone.trim(), url.trim());
+
+            if zone.is_empty() {
+                anyhow::bail!("Empty zone in Caddy admin API entry '{}'", entry);
+            }
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                anyhow::bail!("Caddy admin API must be a URL: '{}'", url);
+            }
+            if apis
+                .zones
+                .insert(zone.to_string(), url.to_string())
+                .is_some()
+            {
+                anyhow::bail!("Duplicate Caddy admin API zone: '{}'", zone);
+            }
+        }
+
+        Ok(apis)
+    }
+
+    /// Admin API for a zone's sites, falling back to the default endpoint
+    pub fn for_zone(&self, zone: Option<&str>) -> &str {
+        zone.and_then(|zone| self.zones.get(zone))
+            .unwrap_or(&self.default)
+    }
+
+    /// Every distinct endpoint, default first
+    pub fn endpoints(&self) -> Vec<&str> {
+        let mut endpoints = vec![self.default.as_str()];
+        for url in self.zones.values() {
+            if !endpoints.contains(&url.as_str()) {
+                endpoints.push(url);
+            }
+        }
+        endpoints
+    }
 }
 
 impl WorkerConfig {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self> {
+        let cloudflare_dns_mode: CloudflareDnsMode = match std::env::var("CLOUDFLARE_DNS_MODE") {
+            Ok(value) => value.parse()?,
+            Err(_) => CloudflareDnsMode::default(),
+        };
+        let cloudflare_origin_ips = match std::env::var("CLOUDFLARE_ORIGIN_IPS") {
+            Ok(value) => parse_ip_list(&value)
+                .context("CLOUDFLARE_ORIGIN_IPS must be comma-separated IP addresses")?,
+            Err(_) => Vec::new(),
+        };
+        if cloudflare_dns_mode == CloudflareDnsMode::Direct && cloudflare_origin_ips.is_empty() {
+            anyhow::bail!("CLOUDFLARE_ORIGIN_IPS is required when CLOUDFLARE_DNS_MODE=direct");
+        }
+        let request_keys = match (
+            std::env::var("WORKER_SIGNING_KEY_FILE"),
+            std::env::var("CENTRAL_PUBLIC_KEY_FILE"),
+        ) {
+            (Ok(key_file), Ok(central_key_file)) => Some(RequestKeys {
+                signing_key: load_signing_key(Path::new(&key_file))?,
+                central_key: load_verifying_key(Path::new(&central_key_file))?,
+            }),
+            (Err(_), Err(_)) => None,
+            _ => anyhow::bail!(
+                "Set both or neither of WORKER_SIGNING_KEY_FILE and CENTRAL_PUBLIC_KEY_FILE"
+            ),
+        };
+
         Ok(Self {
             central_url: std::env::var("CENTRAL_URL")
                 .context("CENTRAL_URL environment variable required")?,
 
-            worker_shared_secret: std::env::var("WORKER_SHARED_SECRET")
-                .context("WORKER_SHARED_SECRET environment variable required")?,
+            zone: std::env::var("WORKER_ZONE")
+                .ok()
+                .filter(|zone| !zone.is_empty()),
+            inventory_interval: Duration::from_secs(env_or("INVENTORY_INTERVAL_SECS", 300)?),
+
+            // Only needed to talk to Central without Ed25519 keys
+            worker_shared_secret: match std::env::var("WORKER_SHARED_SECRET") {
+                Ok(secret) => secret,
+                Err(_) if request_keys.is_some() => String::new(),
+                Err(_) => anyhow::bail!("WORKER_SHARED_SECRET environment variable required"),
+            },
+            request_keys,
+            signature_tolerance: signature_tolerance_from_env()?,
 
             podman_socket: std::env::var("PODMAN_SOCKET")
                 .unwrap_or_else(|_| Self::detect_podman_socket())
                 .into(),
 
-            caddy_admin_api: std::env::var("CADDY_ADMIN_API")
-                .unwrap_or_else(|_| "http://localhost:2019".to_string()),
+            caddy_admin_api: CaddyAdminApis::parse(
+                &std::env::var("CADDY_ADMIN_API")
+                    .unwrap_or_else(|_| "http://localhost:2019".to_string()),
+                &std::env::var("CADDY_ADMIN_APIS").unwrap_or_default(),
+            )?,
 
             sites_dir: std::env::var("SITES_DIR")
                 .unwrap_or_else(|_| "/var/www/sites".to_string())
                 .into(),
 
+            sites_dir_per_zone: env_or("SITES_DIR_PER_ZONE", false)?,
+            keep_releases: env_or("KEEP_RELEASES", 3)?,
+            route_restore_concurrency: match env_or("ROUTE_RESTORE_CONCURRENCY", 8)? {
+                0 => anyhow::bail!("ROUTE_RESTORE_CONCURRENCY must be at least 1"),
+                n => n,
+            },
+
+            deploy_health_check_timeout: match env_or("DEPLOY_HEALTH_CHECK_SECS", 0)? {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+
             listen_addr: std::env::var("LISTEN_ADDR")
                 .unwrap_or_else(|_| "0.0.0.0:8080".to_string())
                 .parse()
@@ -213,9 +932,38 @@ impl WorkerConfig {
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(true), // Default to using containers
 
+            build_network_dry_run: std::env::var("BUILD_NETWORK_DRY_RUN")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+
             build_image: std::env::var("BUILD_IMAGE")
                 .unwrap_or_else(|_| "nixos/nix:latest".to_string()),
 
+            nix: NixSettings {
+                extra_substituters: std::env::var("NIX_EXTRA_SUBSTITUTERS")
+                    .map(|v| parse_nix_list(&v))
+                    .unwrap_or_default(),
+                extra_trusted_public_keys: std::env::var("NIX_EXTRA_TRUSTED_PUBLIC_KEYS")
+                    .map(|v| parse_nix_list(&v))
+                    .unwrap_or_default(),
+                flake_registry: std::env::var("NIX_FLAKE_REGISTRY").ok(),
+                catapult_flake: std::env::var("CATAPULT_FLAKE_MIRROR").ok(),
+            },
+
+            build_command_policy: match std::env::var("BUILD_COMMAND_ALLOWLIST_PATH") {
+                Ok(path) => BuildCommandPolicy::parse(
+                    &std::fs::read_to_string(&path).with_context(|| {
+                        format!("Failed to read build command allowlist {}", path)
+                    })?,
+                    &std::env::var("BUILD_COMMAND_RESTRICTED_ORGS").unwrap_or_default(),
+                )?,
+                Err(_) => BuildCommandPolicy::default(),
+            },
+
+            isolation_policy: IsolationPolicy::parse(
+                &std::env::var("HOST_BUILD_ORGS").unwrap_or_default(),
+            ),
+
             container_memory_limit: std::env::var("CONTAINER_MEMORY_LIMIT")
                 .ok()
                 .and_then(|v| v.parse().ok())
@@ -231,6 +979,39 @@ impl WorkerConfig {
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(1000),
 
+            container_max_memory_limit: env_or(
+                "CONTAINER_MAX_MEMORY_LIMIT",
+                8 * 1024 * 1024 * 1024, // 8GB default
+            )?,
+
+            container_max_cpu_quota: env_or("CONTAINER_MAX_CPU_QUOTA", 400000)?, // 4 CPUs default
+
+            container_max_pids_limit: env_or("CONTAINER_MAX_PIDS_LIMIT", 4096)?,
+
+            clone_timeout: Duration::from_secs(env_or("CLONE_TIMEOUT_SECS", 300)?),
+
+            max_artifact_bytes: env_or(
+                "MAX_ARTIFACT_BYTES",
+                1024 * 1024 * 1024, // 1GB default
+            )?,
+
+            max_repo_bytes: match env_or("MAX_REPO_SIZE_MB", 2048u64)? {
+                0 => None,
+                mb => Some(mb * 1024 * 1024),
+            },
+
+            build_wait_timeout: Duration::from_secs(env_or("BUILD_WAIT_TIMEOUT_SECS", 900)?),
+
+            allowed_sources: match std::env::var("ALLOWED_SOURCES") {
+                Ok(value) => parse_allowed_sources(&value)
+                    .context("ALLOWED_SOURCES must be comma-separated IPs or CIDRs")?,
+                Err(_) => Vec::new(),
+            },
+
+            trust_forwarded_for: env_or("TRUST_FORWARDED_FOR", false)?,
+
+            http_timeouts: http_timeouts_from_env()?,
+
             cloudflare_api_token: std::env::var("CLOUDFLARE_API_TOKEN").ok(),
 
             cloudflare_account_id: std::env::var("CLOUDFLARE_ACCOUNT_ID").ok(),
@@ -239,9 +1020,111 @@ impl WorkerConfig {
 
             cloudflare_service_url: std::env::var("CLOUDFLARE_SERVICE_URL")
                 .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+
+            cloudflare_tunnels: match std::env::var("CLOUDFLARE_TUNNELS") {
+                Ok(value) => Self::parse_cloudflare_tunnels(&value)?,
+                Err(_) => HashMap::new(),
+            },
+
+            cloudflare_dns_mode,
+
+            cloudflare_origin_ips,
         })
     }
 
+    /// Key to sign requests to Central with
+    pub fn signer(&self) -> RequestSigner<'_> {
+        match &self.request_keys {
+            Some(keys) => RequestSigner::Ed25519(&keys.signing_key),
+            None => RequestSigner::Secret(&self.worker_shared_secret),
+        }
+    }
+
+    /// Key to verify requests from Central with
+    pub fn verifier(&self) -> RequestVerifier<'_> {
+        match &self.request_keys {
+            Some(keys) => RequestVerifier::Ed25519(&keys.central_key),
+            None => RequestVerifier::Secret(&self.worker_shared_secret),
+        }
+    }
+
+    /// Apply settings Central assigns to this worker's zone
+    pub fn apply_settings(&mut self, settings: &WorkerSettings) -> Result<()> {
+        if let Some(url) = &settings.cloudflare_service_url
+            && !url.starts_with("http://")
+            && !url.starts_with("https://")
+        {
+            anyhow::bail!("Tunnel service URL must be a URL: '{}'", url);
+        }
+
+        if let Some(tunnel_id) = &settings.cloudflare_tunnel_id {
+            self.cloudflare_tunnel_id = Some(tunnel_id.clone());
+        }
+        if let Some(url) = &settings.cloudflare_service_url {
+            self.cloudflare_service_url = url.clone();
+        }
+        if let Some(keep_releases) = settings.keep_releases {
+            self.keep_releases = keep_releases;
+        }
+        if let Some(secs) = settings.deploy_health_check_secs {
+            self.deploy_health_check_timeout = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        if let Some(secs) = settings.clone_timeout_secs {
+            self.clone_timeout = Duration::from_secs(secs);
+        }
+        if let Some(mb) = settings.max_repo_size_mb {
+            self.max_repo_bytes = (mb > 0).then(|| mb * 1024 * 1024);
+        }
+
+        Ok(())
+    }
+
+    /// Parse per-environment tunnels
+    ///
+    /// Format: comma-separated `environment=tunnel_id` entries, each optionally
+    /// followed by `|service_url` (e.g. `staging=abc,production=def|http://localhost:8081`).
+    fn parse_cloudflare_tunnels(value: &str) -> Result<HashMap<String, CloudflareTunnel>> {
+        let mut tunnels = HashMap::new();
+
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (environment, tunnel) = entry.split_once('=').with_context(|| {
+                format!(
+                    "Invalid tunnel format '{}', expected 'environment=tunnel_id[|service_url]'",
+                    entry
+                )
+            })?;
+
+            let (tunnel_id, service_url) = match tunnel.split_once('|') {
+                Some((id, url)) => (id.trim(), Some(url.trim())),
+                None => (tunnel.trim(), None),
+            };
+
+            let environment = environment.trim();
+            if environment.is_empty() {
+                anyhow::bail!("Empty environment in tunnel entry '{}'", entry);
+            }
+            if tunnel_id.is_empty() {
+                anyhow::bail!("Empty tunnel ID in tunnel entry '{}'", entry);
+            }
+            if let Some(url) = service_url
+                && !url.starts_with("http://")
+                && !url.starts_with("https://")
+            {
+                anyhow::bail!("Tunnel service URL must be a URL: '{}'", url);
+            }
+
+            let tunnel = CloudflareTunnel {
+                tunnel_id: tunnel_id.to_string(),
+                service_url: service_url.map(String::from),
+            };
+            if tunnels.insert(environment.to_string(), tunnel).is_some() {
+                anyhow::bail!("Duplicate tunnel environment: '{}'", environment);
+            }
+        }
+
+        Ok(tunnels)
+    }
+
     /// Detect the best available Podman socket
     ///
     /// Prefers the system socket (for production with iptables support),
@@ -266,3 +1149,318 @@ impl WorkerConfig {
         system_socket.to_string()
     }
 }
+
+#[cfg(test)]
+impl WorkerConfig {
+    /// Worker config with defaults, deploying into `sites_dir` via `caddy_admin_api`
+    pub fn for_tests(sites_dir: PathBuf, caddy_admin_api: &str) -> Self {
+        Self {
+            central_url: "http://localhost:8080".to_string(),
+            zone: None,
+            inventory_interval: Duration::from_secs(300),
+            worker_shared_secret: "test-secret".to_string(),
+            request_keys: None,
+            signature_tolerance: SignatureTolerance::default(),
+            podman_socket: PathBuf::from("/run/podman/podman.sock"),
+            caddy_admin_api: CaddyAdminApis::single(caddy_admin_api),
+            sites_dir,
+            sites_dir_per_zone: false,
+            keep_releases: 3,
+            route_restore_concurrency: 8,
+            deploy_health_check_timeout: None,
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            use_containers: false,
+            build_network_dry_run: false,
+            build_image: "nixos/nix:latest".to_string(),
+            nix: NixSettings::default(),
+            build_command_policy: BuildCommandPolicy::default(),
+            isolation_policy: IsolationPolicy::default(),
+            container_memory_limit: 4 * 1024 * 1024 * 1024,
+            container_cpu_quota: 200000,
+            container_pids_limit: 1000,
+            container_max_memory_limit: 8 * 1024 * 1024 * 1024,
+            container_max_cpu_quota: 400000,
+            container_max_pids_limit: 4096,
+            clone_timeout: Duration::from_secs(300),
+            max_artifact_bytes: 1024 * 1024 * 1024,
+            max_repo_bytes: None,
+            build_wait_timeout: Duration::from_secs(900),
+            allowed_sources: Vec::new(),
+            trust_forwarded_for: false,
+            http_timeouts: HttpTimeouts::default(),
+            cloudflare_api_token: None,
+            cloudflare_account_id: None,
+            cloudflare_tunnel_id: None,
+            cloudflare_service_url: "http://localhost:8080".to_string(),
+            cloudflare_tunnels: HashMap::new(),
+            cloudflare_dns_mode: CloudflareDnsMode::default(),
+            cloudflare_origin_ips: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cleanup_grace_period_requires_preview_expiry() {
+        let day = Some(Duration::from_secs(86400));
+        let grace = Some(Duration::from_secs(300));
+
+        assert!(validate_cleanup_grace_period(None, None).is_ok());
+        assert!(validate_cleanup_grace_period(None, day).is_ok());
+        assert!(validate_cleanup_grace_period(grace, day).is_ok());
+        let error = validate_cleanup_grace_period(grace, None).unwrap_err();
+        assert!(error.to_string().contains("MAX_PREVIEW_AGE_SECS"));
+    }
+
+    #[test]
+    fn test_validate_pool_settings_defaults() {
+        assert!(validate_pool_settings(10, 0, 30, 10).is_ok());
+    }
+
+    #[test]
+    fn test_validate_pool_settings_bounds() {
+        // Max connections must be non-zero and bounded
+        assert!(validate_pool_settings(0, 0, 30, 10).is_err());
+        assert!(validate_pool_settings(1000, 0, 30, 10).is_err());
+
+        // Min can't exceed max
+        assert!(validate_pool_settings(5, 5, 30, 10).is_ok());
+        assert!(validate_pool_settings(5, 6, 30, 10).is_err());
+
+        // Timeouts must be non-zero and bounded
+        assert!(validate_pool_settings(10, 0, 0, 10).is_err());
+        assert!(validate_pool_settings(10, 0, 30, 0).is_err());
+        assert!(validate_pool_settings(10, 0, 3600, 10).is_err());
+    }
+
+    #[test]
+    fn test_parse_github_apps() {
+        let apps = parse_github_apps(
+            "222:/run/keys/second.pem:s3cret:with:colons,\n 333:/run/keys/third.pem:other\n",
+        )
+        .unwrap();
+        assert_eq!(
+            apps,
+            vec![
+                GitHubAppConfig {
+                    app_id: 222,
+                    private_key_path: "/run/keys/second.pem".into(),
+                    webhook_secret: "s3cret:with:colons".to_string(),
+                },
+                GitHubAppConfig {
+                    app_id: 333,
+                    private_key_path: "/run/keys/third.pem".into(),
+                    webhook_secret: "other".to_string(),
+                },
+            ]
+        );
+
+        assert!(parse_github_apps("").unwrap().is_empty());
+        assert!(parse_github_apps("222:/run/keys/second.pem").is_err());
+        assert!(parse_github_apps("abc:/run/keys/second.pem:secret").is_err());
+        assert!(parse_github_apps("222::secret").is_err());
+    }
+
+    #[test]
+    fn test_parse_zone_list() {
+        let zones = parse_zone_list(" Production, staging,,");
+        assert_eq!(zones.len(), 2);
+        assert!(zones.contains("production"));
+        assert!(zones.contains("staging"));
+        assert!(parse_zone_list("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_mirror_args() {
+        let workers = CentralConfig::parse_worker_args(vec![
+            "nullislabs=https://deployer.nullislabs.io".to_string(),
+        ])
+        .unwrap();
+        let mirror = |arg: &str| CentralConfig::parse_mirror_args(vec![arg.to_string()], &workers);
+
+        let mirrors = CentralConfig::parse_mirror_args(
+            vec![
+                "nullislabs=https://deployer2.nullislabs.io".to_string(),
+                " nullislabs = https://deployer3.nullislabs.io ".to_string(),
+            ],
+            &workers,
+        )
+        .unwrap();
+        assert_eq!(
+            mirrors["nullislabs"],
+            vec![
+                "https://deployer2.nullislabs.io",
+                "https://deployer3.nullislabs.io"
+            ]
+        );
+
+        // Mirrors need a primary and a distinct endpoint
+        assert!(mirror("staging=https://deployer2.nullislabs.io").is_err());
+        assert!(mirror("nullislabs=https://deployer.nullislabs.io").is_err());
+        assert!(mirror("nullislabs").is_err());
+    }
+
+    #[test]
+    fn test_mirror_quorum() {
+        let mut config = CentralConfig::for_tests();
+        // A majority by default
+        assert_eq!(config.mirror_quorum(1), 1);
+        assert_eq!(config.mirror_quorum(2), 2);
+        assert_eq!(config.mirror_quorum(3), 2);
+
+        // An explicit quorum can't exceed the workers there are
+        config.mirror_quorum = Some(1);
+        assert_eq!(config.mirror_quorum(2), 1);
+        config.mirror_quorum = Some(3);
+        assert_eq!(config.mirror_quorum(2), 2);
+    }
+
+    #[test]
+    fn test_parse_cloudflare_dns_mode() {
+        assert_eq!(
+            "tunnel".parse::<CloudflareDnsMode>().unwrap(),
+            CloudflareDnsMode::Tunnel
+        );
+        assert_eq!(
+            " Direct ".parse::<CloudflareDnsMode>().unwrap(),
+            CloudflareDnsMode::Direct
+        );
+        assert!("cname".parse::<CloudflareDnsMode>().is_err());
+    }
+
+    #[test]
+    fn test_parse_ip_list() {
+        let ips = parse_ip_list("203.0.113.10, 2001:db8::10,").unwrap();
+        assert_eq!(ips.len(), 2);
+        assert!(ips[1].is_ipv6());
+        assert!(parse_ip_list("").unwrap().is_empty());
+        assert!(parse_ip_list("203.0.113.0/24").is_err());
+    }
+
+    #[test]
+    fn test_caddy_admin_api_per_zone() {
+        let apis = CaddyAdminApis::parse(
+            "http://localhost:2019",
+            "staging=http://localhost:2020, nxm=http://localhost:2021",
+        )
+        .unwrap();
+
+        assert_eq!(apis.for_zone(Some("staging")), "http://localhost:2020");
+        assert_eq!(apis.for_zone(Some("nxm")), "http://localhost:2021");
+        // Unknown or missing zones use the default endpoint
+        assert_eq!(apis.for_zone(Some("production")), "http://localhost:2019");
+        assert_eq!(apis.for_zone(None), "http://localhost:2019");
+        assert_eq!(apis.endpoints().len(), 3);
+        assert_eq!(apis.endpoints()[0], "http://localhost:2019");
+
+        let single = CaddyAdminApis::parse("http://localhost:2019", "").unwrap();
+        assert_eq!(single, CaddyAdminApis::single("http://localhost:2019"));
+        assert_eq!(single.for_zone(Some("staging")), "http://localhost:2019");
+    }
+
+    #[test]
+    fn test_caddy_admin_api_invalid() {
+        let parse = |zones| CaddyAdminApis::parse("http://localhost:2019", zones);
+        assert!(parse("staging").is_err());
+        assert!(parse("=http://localhost:2020").is_err());
+        assert!(parse("staging=localhost:2020").is_err());
+        assert!(parse("staging=http://a,staging=http://b").is_err());
+    }
+
+    #[test]
+    fn test_parse_cloudflare_tunnels() {
+        let tunnels = WorkerConfig::parse_cloudflare_tunnels(
+            "staging=tunnel-staging, production=tunnel-prod|http://localhost:8081",
+        )
+        .unwrap();
+
+        assert_eq!(tunnels.len(), 2);
+        assert_eq!(
+            tunnels["staging"],
+            CloudflareTunnel {
+                tunnel_id: "tunnel-staging".to_string(),
+                service_url: None,
+            }
+        );
+        assert_eq!(
+            tunnels["production"].service_url.as_deref(),
+            Some("http://localhost:8081")
+        );
+
+        assert!(
+            WorkerConfig::parse_cloudflare_tunnels("")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_cloudflare_tunnels_invalid() {
+        assert!(WorkerConfig::parse_cloudflare_tunnels("staging").is_err());
+        assert!(WorkerConfig::parse_cloudflare_tunnels("=tunnel").is_err());
+        assert!(WorkerConfig::parse_cloudflare_tunnels("staging=").is_err());
+        assert!(WorkerConfig::parse_cloudflare_tunnels("staging=a|localhost").is_err());
+        assert!(WorkerConfig::parse_cloudflare_tunnels("staging=a,staging=b").is_err());
+    }
+
+    #[test]
+    fn test_worker_request_keys_replace_secret() {
+        let mut config = WorkerConfig::for_tests(PathBuf::from("/var/www/sites"), "http://caddy");
+        assert!(matches!(
+            config.signer(),
+            RequestSigner::Secret("test-secret")
+        ));
+        assert!(matches!(
+            config.verifier(),
+            RequestVerifier::Secret("test-secret")
+        ));
+
+        let signing_key = SigningKey::from_bytes(&[1; 32]);
+        let central_key = SigningKey::from_bytes(&[2; 32]).verifying_key();
+        config.request_keys = Some(RequestKeys {
+            signing_key: signing_key.clone(),
+            central_key,
+        });
+        assert!(matches!(config.signer(), RequestSigner::Ed25519(key) if *key == signing_key));
+        assert!(matches!(
+            config.verifier(),
+            RequestVerifier::Ed25519(key) if *key == central_key
+        ));
+    }
+
+    #[test]
+    fn test_apply_worker_settings() {
+        let mut config = WorkerConfig::for_tests(PathBuf::from("/var/www/sites"), "http://caddy");
+        config.deploy_health_check_timeout = Some(Duration::from_secs(30));
+        config.max_repo_bytes = Some(1024);
+
+        let settings: WorkerSettings = serde_json::from_str(
+            r#"{"cloudflare_tunnel_id": "tunnel-1", "keep_releases": 5,
+                "deploy_health_check_secs": 0, "max_repo_size_mb": 10}"#,
+        )
+        .unwrap();
+        config.apply_settings(&settings).unwrap();
+
+        assert_eq!(config.cloudflare_tunnel_id.as_deref(), Some("tunnel-1"));
+        assert_eq!(config.keep_releases, 5);
+        assert_eq!(config.deploy_health_check_timeout, None);
+        assert_eq!(config.max_repo_bytes, Some(10 * 1024 * 1024));
+        // Unset fields keep the local value
+        assert_eq!(config.cloudflare_service_url, "http://localhost:8080");
+        assert_eq!(config.clone_timeout, Duration::from_secs(300));
+
+        // Invalid settings are rejected without applying any of them
+        let invalid = WorkerSettings {
+            keep_releases: Some(1),
+            cloudflare_service_url: Some("localhost:8081".to_string()),
+            ..Default::default()
+        };
+        assert!(config.apply_settings(&invalid).is_err());
+        assert_eq!(config.keep_releases, 5);
+
+        assert!(serde_json::from_str::<WorkerSettings>(r#"{"keep_release": 5}"#).is_err());
+    }
+}
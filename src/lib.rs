@@ -8,3 +8,6 @@ pub mod central;
 pub mod config;
 pub mod shared;
 pub mod worker;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
@@ -6,6 +6,9 @@ mod config;
 mod shared;
 mod worker;
 
+#[cfg(test)]
+mod test_util;
+
 #[derive(Parser)]
 #[command(name = "catapult")]
 #[command(about = "Automated deployment runner for GitHub webhooks")]
@@ -26,9 +29,35 @@ enum Command {
         /// Example: --worker nullislabs=https://deployer.nullislabs.io
         #[arg(long = "worker", value_name = "ZONE=URL")]
         workers: Vec<String>,
+
+        /// Mirror workers in format zone=https://endpoint
+        ///
+        /// Main branch deployments go to the zone's worker and all its mirrors.
+        /// Can be specified multiple times.
+        ///
+        /// Example: --mirror nullislabs=https://deployer2.nullislabs.io
+        #[arg(long = "mirror", value_name = "ZONE=URL")]
+        mirrors: Vec<String>,
     },
     /// Run as Worker (executes builds, deploys to Caddy)
-    Worker,
+    Worker {
+        #[command(subcommand)]
+        command: Option<WorkerCommand>,
+    },
+    /// Hash a preview password (read from stdin) for `basic_auth` in .deploy.json
+    HashPassword {
+        /// Username to pair with the password
+        #[arg(long, default_value = "preview")]
+        username: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkerCommand {
+    /// Check Podman, the build network, a build and Caddy on this host, then exit
+    ///
+    /// Uses the worker's usual environment. Exits nonzero if any check fails.
+    Selftest,
 }
 
 #[tokio::main]
@@ -45,13 +74,27 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Command::Central { workers } => {
-            let config = config::CentralConfig::from_env_and_args(workers)?;
+        Command::Central { workers, mirrors } => {
+            let config = config::CentralConfig::from_env_and_args(workers, mirrors)?;
             central::run(config).await?;
         }
-        Command::Worker => {
+        Command::Worker { command } => {
             let config = config::WorkerConfig::from_env()?;
-            worker::run(config).await?;
+            match command {
+                None => worker::run(config).await?,
+                Some(WorkerCommand::Selftest) => worker::selftest::run(config).await?,
+            }
+        }
+        Command::HashPassword { username } => {
+            let mut password = String::new();
+            std::io::stdin().read_line(&mut password)?;
+            let password = password.trim_end_matches(['\r', '\n']);
+            if password.is_empty() {
+                anyhow::bail!("Password must not be empty");
+            }
+
+            let auth = shared::BasicAuth::with_password(&username, password)?;
+            println!("{}", serde_json::to_string_pretty(&auth)?);
         }
     }
 
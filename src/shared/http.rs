@@ -0,0 +1,77 @@
+//! Outbound HTTP client construction
+//!
+//! Every client talking to GitHub, Cloudflare, Caddy, workers or Central is
+//! built here so no request can hang a task indefinitely.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// Timeouts applied to every outbound HTTP request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpTimeouts {
+    /// Time allowed to establish a connection
+    pub connect: Duration,
+    /// Time allowed for the whole request, including reading the response
+    pub request: Duration,
+}
+
+impl Default for HttpTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(10),
+            request: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Build an HTTP client with connect and request timeouts
+pub fn build_http_client(timeouts: HttpTimeouts) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .connect_timeout(timeouts.connect)
+        .timeout(timeouts.request)
+        .build()
+        .context("Failed to create HTTP client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_slow_response_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+            .mount(&server)
+            .await;
+
+        let client = build_http_client(HttpTimeouts {
+            connect: Duration::from_secs(1),
+            request: Duration::from_millis(200),
+        })
+        .unwrap();
+
+        let started = std::time::Instant::now();
+        let err = client.get(server.uri()).send().await.unwrap_err();
+
+        assert!(err.is_timeout());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_fast_response_succeeds() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = build_http_client(HttpTimeouts::default()).unwrap();
+        let response = client.get(server.uri()).send().await.unwrap();
+
+        assert!(response.status().is_success());
+    }
+}
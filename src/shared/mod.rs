@@ -1,4 +1,7 @@
 pub mod auth;
+pub mod http;
+pub mod redact;
 pub mod types;
+pub mod version;
 
 pub use types::*;
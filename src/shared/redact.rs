@@ -0,0 +1,101 @@
+//! Scrubbing secrets from build output before it leaves the worker
+
+/// Replacement text for redacted secrets
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Maximum build log bytes kept for status updates
+pub const MAX_BUILD_LOG_BYTES: usize = 64 * 1024;
+
+/// Replace every occurrence of each secret in `text` with [`REDACTED`]
+///
+/// Longer secrets are replaced first so a secret containing another
+/// secret is fully hidden. Empty secrets are ignored.
+pub fn redact(text: &str, secrets: &[&str]) -> String {
+    let mut secrets: Vec<&str> = secrets.iter().copied().filter(|s| !s.is_empty()).collect();
+    secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    secrets.dedup();
+
+    let mut output = text.to_string();
+    for secret in secrets {
+        if output.contains(secret) {
+            output = output.replace(secret, REDACTED);
+        }
+    }
+
+    output
+}
+
+/// Keep at most `max_bytes` of a log, preferring the end where errors appear
+///
+/// Truncation happens on a character boundary and is marked at the start.
+pub fn truncate_log(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+
+    let mut start = text.len() - max_bytes;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+
+    format!("[... {} bytes truncated ...]\n{}", start, &text[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_token_within_line() {
+        let token = "ghs_abc123XYZ";
+        let text = format!(
+            "fatal: could not read from https://x-access-token:{}@github.com/org/repo.git",
+            token
+        );
+
+        let redacted = redact(&text, &[token]);
+        assert!(!redacted.contains(token));
+        assert!(redacted.contains("x-access-token:[REDACTED]@github.com"));
+    }
+
+    #[test]
+    fn test_redact_multiple_secrets() {
+        let text = "TOKEN=aaa111\nAPI_KEY=bbb222\nTOKEN again: aaa111\n";
+
+        let redacted = redact(text, &["aaa111", "bbb222"]);
+        assert_eq!(
+            redacted,
+            "TOKEN=[REDACTED]\nAPI_KEY=[REDACTED]\nTOKEN again: [REDACTED]\n"
+        );
+    }
+
+    #[test]
+    fn test_redact_overlapping_secrets() {
+        // The longer secret must not leave a suffix behind
+        let redacted = redact("value: secret-extended", &["secret", "secret-extended"]);
+        assert_eq!(redacted, "value: [REDACTED]");
+    }
+
+    #[test]
+    fn test_redact_ignores_empty_secrets() {
+        assert_eq!(redact("build ok", &["", "missing"]), "build ok");
+    }
+
+    #[test]
+    fn test_truncate_log_keeps_tail() {
+        let text = format!("{}error: build failed", "x".repeat(100));
+
+        let truncated = truncate_log(&text, 19);
+        assert!(truncated.starts_with("[... 100 bytes truncated ...]"));
+        assert!(truncated.ends_with("error: build failed"));
+
+        assert_eq!(truncate_log("short", 100), "short");
+    }
+
+    #[test]
+    fn test_truncate_log_char_boundary() {
+        // Multi-byte characters must not be split
+        let truncated = truncate_log("ééééé", 3);
+        assert!(truncated.ends_with("é"));
+    }
+}
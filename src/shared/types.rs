@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -23,9 +26,17 @@ pub struct BuildJob {
     /// PR number (None for main branch deployments)
     pub pr_number: Option<u32>,
 
+    /// Site ID override for sites outside the main/PR scheme (manual previews)
+    #[serde(default)]
+    pub site_id: Option<String>,
+
     /// Domain for the deployment (e.g., "example.com")
     pub domain: String,
 
+    /// Additional hostnames serving the same deployment (e.g., "www.example.com")
+    #[serde(default)]
+    pub aliases: Vec<String>,
+
     /// Site type for build configuration
     pub site_type: SiteType,
 
@@ -40,6 +51,199 @@ pub struct BuildJob {
 
     /// Subdomain for main branch deployment (None for PR deployments)
     pub subdomain: Option<String>,
+
+    /// Build arguments from the merged org/repo deploy config
+    #[serde(default)]
+    pub build_args: HashMap<String, String>,
+
+    /// HTTP basic auth credentials protecting the deployed site (PR previews only)
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuth>,
+
+    /// Pre-compress text assets and serve them via Caddy's precompressed support
+    #[serde(default)]
+    pub precompress: bool,
+
+    /// Fetch Git LFS objects after checkout
+    #[serde(default)]
+    pub git_lfs: bool,
+
+    /// Build container memory override in MB (clamped by the worker)
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+
+    /// Build container CPU override in cores (clamped by the worker)
+    #[serde(default)]
+    pub cpu_cores: Option<f64>,
+
+    /// Build container PID limit override (clamped by the worker)
+    #[serde(default)]
+    pub pids_limit: Option<i64>,
+
+    /// Node.js version for Node-based builds (None uses the flake default)
+    #[serde(default)]
+    pub node_version: Option<String>,
+
+    /// Zone/environment being deployed to (selects the Cloudflare tunnel)
+    #[serde(default)]
+    pub environment: Option<String>,
+
+    /// Deploy this prebuilt archive instead of cloning and building
+    #[serde(default)]
+    pub artifact: Option<ArtifactSource>,
+
+    /// Serve this release to a percentage of traffic alongside the live one
+    /// until it is promoted (main branch deployments only)
+    #[serde(default)]
+    pub canary_percent: Option<u8>,
+
+    /// Path globs of content-hashed assets, served as immutable
+    ///
+    /// Unset means the site type's defaults, resolved by the worker once an
+    /// auto-detected type is known.
+    #[serde(default)]
+    pub immutable_paths: Option<Vec<String>>,
+
+    /// Index files tried after `index.html` for directory requests
+    #[serde(default)]
+    pub index_names: Vec<String>,
+
+    /// List the contents of directories without an index file
+    #[serde(default)]
+    pub browse: bool,
+
+    /// Purge Cloudflare's cache after deploying (main branch deployments only)
+    #[serde(default)]
+    pub purge_cache: Option<CachePurge>,
+
+    /// Page in the build output served while the site's files are swapped
+    /// (main branch deployments only)
+    #[serde(default)]
+    pub maintenance_page: Option<String>,
+
+    /// Extra Caddy handlers or route fields merged into the site's route
+    #[serde(default)]
+    pub caddy_extra: Option<serde_json::Value>,
+
+    /// Build isolation level (None uses the worker's default)
+    #[serde(default)]
+    pub isolation: Option<BuildIsolation>,
+
+    /// Clone and build only, publishing nothing (verifies the build works)
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Worker a mirrored job was dispatched to, echoed in its status updates
+    #[serde(default)]
+    pub worker_id: Option<i32>,
+}
+
+/// What a build job deploys, independent of the repository's deploy config
+#[derive(Debug, Clone)]
+pub struct BuildTarget {
+    pub job_id: Uuid,
+    pub repo_url: String,
+    pub git_token: String,
+    pub branch: String,
+    pub commit_sha: String,
+    pub pr_number: Option<u32>,
+    pub site_id: Option<String>,
+    pub domain: String,
+    pub aliases: Vec<String>,
+    pub callback_url: String,
+    pub repo_name: String,
+    pub org_name: String,
+    pub environment: Option<String>,
+    /// PR or commit preview: protected by basic auth, and without the main
+    /// deploy's subdomain, canary, cache purge or maintenance page
+    pub preview: bool,
+}
+
+impl BuildJob {
+    /// Build job for `target` with the settings of its merged deploy config
+    pub fn from_deploy_config(target: BuildTarget, deploy_config: &DeployConfig) -> Self {
+        let site_type = deploy_config.build_type.unwrap_or_default();
+        let is_main = !target.preview;
+        Self {
+            job_id: target.job_id,
+            repo_url: target.repo_url,
+            git_token: target.git_token,
+            branch: target.branch,
+            commit_sha: target.commit_sha,
+            pr_number: target.pr_number,
+            site_id: target.site_id,
+            domain: target.domain,
+            aliases: target.aliases,
+            site_type,
+            callback_url: target.callback_url,
+            repo_name: target.repo_name,
+            org_name: target.org_name,
+            subdomain: is_main.then(|| deploy_config.subdomain.clone()).flatten(),
+            build_args: deploy_config.build_args.clone().unwrap_or_default(),
+            basic_auth: target
+                .preview
+                .then(|| deploy_config.basic_auth.clone())
+                .flatten(),
+            precompress: deploy_config.precompress.unwrap_or(false),
+            git_lfs: deploy_config.git_lfs.unwrap_or(false),
+            memory_mb: deploy_config.memory_mb,
+            cpu_cores: deploy_config.cpu_cores,
+            pids_limit: deploy_config.pids_limit,
+            node_version: deploy_config.node_version.clone(),
+            environment: target.environment,
+            artifact: None,
+            canary_percent: is_main.then_some(deploy_config.canary_percent).flatten(),
+            immutable_paths: deploy_config.immutable_paths.clone(),
+            index_names: deploy_config.index_names.clone().unwrap_or_default(),
+            browse: deploy_config.browse.unwrap_or(false),
+            // Previews aren't served from a warm cache and have no visitors to
+            // keep informed
+            purge_cache: is_main.then_some(deploy_config.purge_cache).flatten(),
+            maintenance_page: is_main
+                .then(|| deploy_config.maintenance_page.clone())
+                .flatten(),
+            caddy_extra: deploy_config.caddy_extra.clone(),
+            isolation: deploy_config.isolation,
+            dry_run: deploy_config.dry_run.unwrap_or(false),
+            worker_id: None,
+        }
+    }
+}
+
+/// What to purge from Cloudflare's cache after a deploy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CachePurge {
+    /// Only the deployed hostnames (domain and aliases)
+    Hostnames,
+    /// Everything cached for the hostnames' zones
+    Zone,
+}
+
+/// How a build is isolated from the worker host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display)]
+#[serde(rename_all = "kebab-case")]
+pub enum BuildIsolation {
+    /// Podman container on the isolated build network
+    #[display("container")]
+    Container,
+    /// Directly on the worker host (trusted orgs only)
+    #[display("host")]
+    Host,
+    /// Podman container whose network is removed once dependencies are fetched
+    #[display("container-no-network")]
+    ContainerNoNetwork,
+}
+
+/// Prebuilt `.tar.gz` of site files, fetched by the worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactSource {
+    /// Download URL (typically a short-lived signed URL)
+    pub url: String,
+
+    /// Expected hex SHA-256 of the archive
+    #[serde(default)]
+    pub sha256: Option<String>,
 }
 
 /// Cleanup job dispatched from Central to Worker
@@ -57,6 +261,85 @@ pub struct CleanupJob {
     /// Domain to remove from DNS/routing (e.g., "pr-42-website.nxm.rs")
     #[serde(default)]
     pub domain: Option<String>,
+
+    /// Zone/environment the site was deployed to (selects the Cloudflare tunnel)
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+/// Promote job dispatched from Central to Worker, sending all traffic to a canary release
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromoteJob {
+    /// Build job that produced the canary release to promote
+    pub job_id: Uuid,
+
+    /// Site the canary belongs to (e.g., "nullislabs-website-main")
+    pub site_id: String,
+
+    /// Zone/environment the site was deployed to
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+/// Cancel job dispatched from Central to Worker, stopping an in-flight build
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelJob {
+    /// Build job to cancel
+    pub job_id: Uuid,
+}
+
+/// Query from Central for a worker's view of a build job, used to
+/// reconcile deployments whose final status update never arrived
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatusQuery {
+    /// Build job to look up
+    pub job_id: Uuid,
+
+    /// Site the job deploys (e.g., "nullislabs-website-main")
+    pub site_id: String,
+
+    /// Zone/environment the job was dispatched to
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+/// A worker's answer to a [`JobStatusQuery`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum WorkerJobState {
+    /// Still building or deploying
+    Running,
+    /// Finished with this final status
+    Finished { update: StatusUpdate },
+    /// The worker has no record of the job (e.g. it restarted mid-build)
+    Unknown,
+}
+
+/// Sites a worker is serving, reported periodically to Central
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteInventory {
+    /// Zone/environment the worker serves
+    pub zone: String,
+
+    /// Every deployed site; sites missing from the report are gone
+    pub sites: Vec<InventorySite>,
+}
+
+/// A deployed site as found in the worker's sites directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InventorySite {
+    /// Site identifier (e.g., "nullislabs-website-pr-42")
+    pub site_id: String,
+
+    /// Domain the site is served on
+    pub domain: String,
+
+    /// Size of the deployed site files in bytes
+    pub size_bytes: u64,
+
+    /// When the current release was deployed
+    #[serde(default)]
+    pub deployed_at: Option<DateTime<Utc>>,
 }
 
 /// Status update sent from Worker to Central
@@ -73,6 +356,71 @@ pub struct StatusUpdate {
 
     /// Error message (if failed)
     pub error_message: Option<String>,
+
+    /// Lifecycle phase reached, recorded in the deployment's event log
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<DeploymentPhase>,
+
+    /// Zone of the job, selecting the secret Central verifies jobs it has no record of with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+
+    /// Size of the deployed site files in bytes (successful builds only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+
+    /// Worker of a mirrored job, signed with the update so it can't be swapped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_id: Option<i32>,
+}
+
+/// Worker request for a fresh git token after GitHub rejected the job's token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitTokenRequest {
+    /// Job the token is for; only jobs still in flight get one
+    pub job_id: Uuid,
+}
+
+/// Fresh installation token minted by Central for a running job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitTokenResponse {
+    pub token: String,
+
+    /// Expiry as reported by GitHub (RFC 3339)
+    pub expires_at: String,
+}
+
+/// Settings Central assigns to a zone's workers, applied over their local
+/// config at startup
+///
+/// Field names and units match the worker's environment variables; unset
+/// fields keep the local value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkerSettings {
+    /// Cloudflare tunnel the zone's hostnames are routed through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloudflare_tunnel_id: Option<String>,
+
+    /// Local service URL the tunnel forwards to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloudflare_service_url: Option<String>,
+
+    /// Previous releases kept per site
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_releases: Option<usize>,
+
+    /// Post-deploy health check timeout (0 skips the check)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy_health_check_secs: Option<u64>,
+
+    /// Timeout for each git invocation and artifact download
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clone_timeout_secs: Option<u64>,
+
+    /// Upper bound for a repository checkout (0 is unlimited)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_repo_size_mb: Option<u64>,
 }
 
 /// Job status values
@@ -94,6 +442,55 @@ pub enum JobStatus {
     /// PR deployment cleaned up
     #[display("cleaned")]
     Cleaned,
+    /// Waiting for an operator to approve dispatch (protected zones)
+    #[display("pending_approval")]
+    PendingApproval,
+    /// Not approved within the approval window, never dispatched
+    #[display("expired")]
+    Expired,
+    /// Stopped by an operator before it was deployed
+    #[display("cancelled")]
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Statuses a deployment ends in
+    pub const FINAL: [JobStatus; 5] = [
+        JobStatus::Success,
+        JobStatus::Failed,
+        JobStatus::Cleaned,
+        JobStatus::Expired,
+        JobStatus::Cancelled,
+    ];
+
+    /// Whether a deployment with this status is finished
+    pub fn is_final(self) -> bool {
+        Self::FINAL.contains(&self)
+    }
+}
+
+/// Lifecycle phases of a deployment, reported by the worker as they complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display)]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentPhase {
+    /// Repository cloned and checked out
+    #[display("cloned")]
+    Cloned,
+    /// Build command started
+    #[display("building")]
+    Building,
+    /// Build artifacts copied to the sites directory
+    #[display("deployed")]
+    Deployed,
+    /// Caddy route configured
+    #[display("caddy_configured")]
+    CaddyConfigured,
+    /// Cloudflare DNS and tunnel ingress configured
+    #[display("cloudflare_configured")]
+    CloudflareConfigured,
+    /// Deployed URL answered the post-deploy health check
+    #[display("reachable")]
+    Reachable,
 }
 
 /// Build/site type configuration
@@ -102,6 +499,7 @@ pub enum JobStatus {
 pub enum SiteType {
     /// SvelteKit application
     #[display("sveltekit")]
+    #[serde(alias = "sveltekit")] // The documented spelling
     SvelteKit,
     /// Vite-based application
     #[display("vite")]
@@ -109,6 +507,13 @@ pub enum SiteType {
     /// Zola static site generator
     #[display("zola")]
     Zola,
+    /// Docusaurus documentation site
+    #[display("docusaurus")]
+    Docusaurus,
+    /// VitePress documentation site
+    #[display("vitepress")]
+    #[serde(rename = "vitepress")]
+    VitePress,
     /// Custom build (uses repo's flake.nix)
     #[display("custom")]
     Custom,
@@ -119,23 +524,60 @@ pub enum SiteType {
 }
 
 impl SiteType {
+    /// Every site type, in declaration order
+    pub const ALL: [SiteType; 7] = [
+        SiteType::SvelteKit,
+        SiteType::Vite,
+        SiteType::Zola,
+        SiteType::Docusaurus,
+        SiteType::VitePress,
+        SiteType::Custom,
+        SiteType::Auto,
+    ];
+
     /// Get the default build command for this site type
     pub fn default_build_command(&self) -> Option<&'static str> {
         match self {
             SiteType::SvelteKit => Some("npm ci && npm run build"),
             SiteType::Vite => Some("npm ci && npm run build"),
             SiteType::Zola => Some("zola build"),
+            SiteType::Docusaurus => Some("npm ci && npm run build"),
+            SiteType::VitePress => Some("npm ci && npx vitepress build"),
             SiteType::Custom => None,
             SiteType::Auto => None,
         }
     }
 
+    /// Path globs where this site type's build emits content-hashed assets
+    pub fn default_immutable_paths(&self) -> &'static [&'static str] {
+        match self {
+            SiteType::SvelteKit => &["/_app/immutable/*"],
+            SiteType::Vite | SiteType::Docusaurus | SiteType::VitePress => &["/assets/*"],
+            SiteType::Zola | SiteType::Custom | SiteType::Auto => &[],
+        }
+    }
+
     /// Get the default output directory for this site type
     pub fn default_output_dir(&self) -> Option<&'static str> {
         match self {
             SiteType::SvelteKit => Some("build"),
             SiteType::Vite => Some("dist"),
             SiteType::Zola => Some("public"),
+            SiteType::Docusaurus => Some("build"),
+            SiteType::VitePress => Some(".vitepress/dist"),
+            SiteType::Custom => None,
+            SiteType::Auto => None,
+        }
+    }
+
+    /// File this site type's build output must contain
+    pub fn default_entrypoint(&self) -> Option<&'static str> {
+        match self {
+            SiteType::SvelteKit
+            | SiteType::Vite
+            | SiteType::Zola
+            | SiteType::Docusaurus
+            | SiteType::VitePress => Some("index.html"),
             SiteType::Custom => None,
             SiteType::Auto => None,
         }
@@ -147,12 +589,41 @@ impl SiteType {
             SiteType::SvelteKit => Some("github:nullisLabs/catapult#sveltekit"),
             SiteType::Vite => Some("github:nullisLabs/catapult#vite"),
             SiteType::Zola => Some("github:nullisLabs/catapult#zola"),
+            SiteType::Docusaurus => Some("github:nullisLabs/catapult#docusaurus"),
+            SiteType::VitePress => Some("github:nullisLabs/catapult#vitepress"),
             SiteType::Custom => None,
             SiteType::Auto => None,
         }
     }
+
+    /// Get the Nix flake reference for this site type with a specific Node.js version
+    ///
+    /// Returns None for site types that don't build with Node.js, and an error
+    /// for versions without a build shell.
+    pub fn node_flake_ref(&self, node_version: &str) -> Result<Option<String>, String> {
+        let base = match self {
+            SiteType::SvelteKit | SiteType::Vite | SiteType::Docusaurus | SiteType::VitePress => {
+                self.flake_ref()
+            }
+            _ => return Ok(None),
+        };
+
+        let version = node_version.trim().trim_start_matches(['v', 'V']);
+        if !SUPPORTED_NODE_VERSIONS.contains(&version) {
+            return Err(format!(
+                "Unsupported node_version '{}' (supported: {})",
+                node_version,
+                SUPPORTED_NODE_VERSIONS.join(", ")
+            ));
+        }
+
+        Ok(base.map(|base| format!("{}-node{}", base, version)))
+    }
 }
 
+/// Node.js versions with a build shell in the catapult flake (`#<type>-node<version>`)
+pub const SUPPORTED_NODE_VERSIONS: &[&str] = &["20", "22"];
+
 impl std::str::FromStr for SiteType {
     type Err = String;
 
@@ -161,6 +632,8 @@ impl std::str::FromStr for SiteType {
             "sveltekit" => Ok(SiteType::SvelteKit),
             "vite" => Ok(SiteType::Vite),
             "zola" => Ok(SiteType::Zola),
+            "docusaurus" => Ok(SiteType::Docusaurus),
+            "vitepress" => Ok(SiteType::VitePress),
             "custom" => Ok(SiteType::Custom),
             "auto" => Ok(SiteType::Auto),
             _ => Err(format!("Unknown site type: {}", s)),
@@ -199,6 +672,10 @@ pub struct DeployConfig {
     #[serde(default)]
     pub subdomain: Option<String>,
 
+    /// Extra hostnames for the main branch deployment (e.g., the apex next to "www")
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
+
     // === Build configuration ===
     /// Build type override
     #[serde(default, alias = "siteType")]
@@ -212,11 +689,152 @@ pub struct DeployConfig {
     #[serde(default)]
     pub output_dir: Option<String>,
 
+    /// File the build output must contain, relative to `output_dir`
+    ///
+    /// Defaults to the site type's entrypoint (`index.html`, none for custom
+    /// builds); an empty string skips the check.
+    #[serde(default)]
+    pub entrypoint: Option<String>,
+
+    /// App directory within the repository for monorepos (e.g. "apps/web")
+    ///
+    /// Site type detection, the build command and `output_dir` are all
+    /// relative to this directory. Must stay within the repository.
+    #[serde(default)]
+    pub root_dir: Option<String>,
+
+    /// Build arguments exported into the build shell environment
+    ///
+    /// Used for build-time switches such as enabling a docs build or selecting
+    /// a theme. Merged per key, so repo args override org args of the same name.
+    #[serde(default)]
+    pub build_args: Option<HashMap<String, String>>,
+
+    /// HTTP basic auth for PR preview deployments
+    ///
+    /// Only the bcrypt hash is stored; generate it with `catapult hash-password`.
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuth>,
+
+    /// Pre-compress text assets (gzip + brotli) after the build
+    #[serde(default)]
+    pub precompress: Option<bool>,
+
+    /// Fetch Git LFS objects after checkout (requires git-lfs on the worker)
+    #[serde(default)]
+    pub git_lfs: Option<bool>,
+
+    /// Post a fresh PR comment per deploy and minimize the previous one
+    /// as outdated, instead of editing a single comment in place
+    #[serde(default)]
+    pub minimize_stale_comments: Option<bool>,
+
+    /// Only deploy PR previews carrying this label; removing it cleans up
+    #[serde(default)]
+    pub require_label: Option<String>,
+
+    /// Don't deploy draft PRs until they are marked ready for review
+    #[serde(default)]
+    pub skip_drafts: Option<bool>,
+
+    /// Deploy PR previews (default true); false keeps only main-branch deploys
+    #[serde(default)]
+    pub pr_previews_enabled: Option<bool>,
+
+    // === Build container resources (clamped to worker maximums) ===
+    /// Memory limit in MB
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+
+    /// CPU limit in cores (fractions allowed, e.g. 1.5)
+    #[serde(default)]
+    pub cpu_cores: Option<f64>,
+
+    /// Maximum number of processes
+    #[serde(default)]
+    pub pids_limit: Option<i64>,
+
+    /// Node.js version for SvelteKit/Vite builds (one of `SUPPORTED_NODE_VERSIONS`)
+    #[serde(default)]
+    pub node_version: Option<String>,
+
+    /// Roll main branch deploys out as a canary taking this percentage of
+    /// traffic (1-99); the previous release serves the rest until promoted
+    #[serde(default)]
+    pub canary_percent: Option<u8>,
+
+    /// Path globs of content-hashed assets to cache forever (e.g.
+    /// `/_app/immutable/*`); HTML is then served with `no-cache`. Defaults to
+    /// the site type's hashed asset directory
+    #[serde(default)]
+    pub immutable_paths: Option<Vec<String>>,
+
+    /// Extra index files tried after `index.html` (e.g. `index.htm`)
+    #[serde(default)]
+    pub index_names: Option<Vec<String>>,
+
+    /// List directories without an index file (default: disabled)
+    #[serde(default)]
+    pub browse: Option<bool>,
+
+    /// Purge Cloudflare's cache after main branch deploys, either for the
+    /// deployed hostnames or the whole zone (default: no purge)
+    #[serde(default)]
+    pub purge_cache: Option<CachePurge>,
+
+    /// Page in the build output (e.g. `maintenance.html`) served with a 503
+    /// while a main branch redeploy swaps the site's files (default: none)
+    #[serde(default)]
+    pub maintenance_page: Option<String>,
+
+    /// Caddy handlers added to the site's route, as a list of handler
+    /// objects or a route object with a `handle` list and further route
+    /// fields; the route's `@id`, matchers and `terminal` can't be changed
+    #[serde(default)]
+    pub caddy_extra: Option<serde_json::Value>,
+
+    /// How the build is isolated: `container`, `container-no-network`
+    /// (network removed after dependencies are fetched) or `host` (only for
+    /// orgs the worker trusts); default: the worker's mode
+    #[serde(default)]
+    pub isolation: Option<BuildIsolation>,
+
+    /// Build without publishing: the site's files, routes and DNS are left
+    /// as they are and the PR comment reports the build as verified
+    /// (default: false)
+    #[serde(default)]
+    pub dry_run: Option<bool>,
+
     /// Whether deployments are enabled (default: true)
     #[serde(default = "default_enabled")]
     pub enabled: bool,
 }
 
+/// HTTP basic auth credentials for a protected site
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BasicAuth {
+    /// Username to authenticate with
+    pub username: String,
+
+    /// Bcrypt hash of the password
+    pub bcrypt_hash: String,
+}
+
+impl BasicAuth {
+    /// Create credentials by bcrypt hashing a plaintext password
+    pub fn with_password(username: &str, password: &str) -> Result<Self, bcrypt::BcryptError> {
+        Ok(Self {
+            username: username.to_string(),
+            bcrypt_hash: hash_password(password)?,
+        })
+    }
+}
+
+/// Hash a password with bcrypt for use in [`BasicAuth`]
+pub fn hash_password(password: &str) -> Result<String, bcrypt::BcryptError> {
+    bcrypt::hash(password, bcrypt::DEFAULT_COST)
+}
+
 fn default_enabled() -> bool {
     true
 }
@@ -229,9 +847,33 @@ impl Default for DeployConfig {
             pr_pattern: None,
             domain: None,
             subdomain: None,
+            aliases: None,
             build_type: None,
             build_command: None,
             output_dir: None,
+            entrypoint: None,
+            root_dir: None,
+            build_args: None,
+            basic_auth: None,
+            precompress: None,
+            git_lfs: None,
+            minimize_stale_comments: None,
+            require_label: None,
+            skip_drafts: None,
+            pr_previews_enabled: None,
+            memory_mb: None,
+            cpu_cores: None,
+            pids_limit: None,
+            node_version: None,
+            canary_percent: None,
+            immutable_paths: None,
+            index_names: None,
+            browse: None,
+            purge_cache: None,
+            maintenance_page: None,
+            caddy_extra: None,
+            isolation: None,
+            dry_run: None,
             enabled: true, // Enabled by default
         }
     }
@@ -255,6 +897,9 @@ impl DeployConfig {
         if other.subdomain.is_some() {
             self.subdomain = other.subdomain.clone();
         }
+        if other.aliases.is_some() {
+            self.aliases = other.aliases.clone();
+        }
         if other.build_type.is_some() {
             self.build_type = other.build_type;
         }
@@ -264,10 +909,93 @@ impl DeployConfig {
         if other.output_dir.is_some() {
             self.output_dir = other.output_dir.clone();
         }
+        if other.entrypoint.is_some() {
+            self.entrypoint = other.entrypoint.clone();
+        }
+        if other.root_dir.is_some() {
+            self.root_dir = other.root_dir.clone();
+        }
+        if let Some(other_args) = &other.build_args {
+            self.build_args
+                .get_or_insert_with(HashMap::new)
+                .extend(other_args.clone());
+        }
+        if other.basic_auth.is_some() {
+            self.basic_auth = other.basic_auth.clone();
+        }
+        if other.precompress.is_some() {
+            self.precompress = other.precompress;
+        }
+        if other.git_lfs.is_some() {
+            self.git_lfs = other.git_lfs;
+        }
+        if other.minimize_stale_comments.is_some() {
+            self.minimize_stale_comments = other.minimize_stale_comments;
+        }
+        if other.require_label.is_some() {
+            self.require_label = other.require_label.clone();
+        }
+        if other.skip_drafts.is_some() {
+            self.skip_drafts = other.skip_drafts;
+        }
+        if other.pr_previews_enabled.is_some() {
+            self.pr_previews_enabled = other.pr_previews_enabled;
+        }
+        if other.memory_mb.is_some() {
+            self.memory_mb = other.memory_mb;
+        }
+        if other.cpu_cores.is_some() {
+            self.cpu_cores = other.cpu_cores;
+        }
+        if other.pids_limit.is_some() {
+            self.pids_limit = other.pids_limit;
+        }
+        if other.node_version.is_some() {
+            self.node_version = other.node_version.clone();
+        }
+        if other.canary_percent.is_some() {
+            self.canary_percent = other.canary_percent;
+        }
+        if other.purge_cache.is_some() {
+            self.purge_cache = other.purge_cache;
+        }
+        if other.maintenance_page.is_some() {
+            self.maintenance_page = other.maintenance_page.clone();
+        }
+        if other.immutable_paths.is_some() {
+            self.immutable_paths = other.immutable_paths.clone();
+        }
+        if other.index_names.is_some() {
+            self.index_names = other.index_names.clone();
+        }
+        if other.browse.is_some() {
+            self.browse = other.browse;
+        }
+        if other.caddy_extra.is_some() {
+            self.caddy_extra = other.caddy_extra.clone();
+        }
+        if other.isolation.is_some() {
+            self.isolation = other.isolation;
+        }
+        if other.dry_run.is_some() {
+            self.dry_run = other.dry_run;
+        }
         // enabled is always explicitly set, so always take other's value
         self.enabled = other.enabled;
     }
 
+    /// Immutable asset globs for a build, falling back to the site type's defaults
+    pub fn resolve_immutable_paths(&self, site_type: SiteType) -> Vec<String> {
+        match &self.immutable_paths {
+            Some(paths) => paths.clone(),
+            None => site_type
+                .default_immutable_paths()
+                .iter()
+                .map(|path| path.to_string())
+                .collect(),
+        }
+    }
+
     /// Resolve the main branch domain for a given repo
     ///
     /// Resolution order:
@@ -315,10 +1043,38 @@ impl DeployConfig {
         None
     }
 
+    /// Resolve the default domain for a manual preview of a single commit
+    pub fn resolve_commit_domain(&self, repo: &str, commit_sha: &str) -> Option<String> {
+        let domain = self.resolve_domain(repo)?;
+        Some(format!(
+            "commit-{}-{}.{}",
+            short_sha(commit_sha),
+            repo.to_lowercase(),
+            domain
+        ))
+    }
+
+    /// Aliases for the main branch deployment, excluding the main domain itself
+    pub fn resolve_aliases(&self, main_domain: &str) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for alias in self.aliases.iter().flatten() {
+            let alias = alias.trim().to_lowercase();
+            if !alias.is_empty() && alias != main_domain && !aliases.contains(&alias) {
+                aliases.push(alias);
+            }
+        }
+        aliases
+    }
+
     /// Check if this config is valid for deployment
     pub fn is_deployable(&self) -> bool {
         self.enabled && self.zone.is_some()
     }
+
+    /// Whether pull requests get preview deployments
+    pub fn previews_enabled(&self) -> bool {
+        self.pr_previews_enabled.unwrap_or(true)
+    }
 }
 
 /// Generate a site ID for a deployment
@@ -329,6 +1085,21 @@ pub fn generate_site_id(org: &str, repo: &str, pr_number: Option<u32>) -> String
     }
 }
 
+/// Generate a site ID for a manual preview of a single commit
+pub fn generate_commit_site_id(org: &str, repo: &str, commit_sha: &str) -> String {
+    format!(
+        "{}-{}-commit-{}",
+        org.to_lowercase(),
+        repo.to_lowercase(),
+        short_sha(commit_sha)
+    )
+}
+
+/// First seven characters of a commit SHA, lowercased
+fn short_sha(commit_sha: &str) -> String {
+    commit_sha[..7.min(commit_sha.len())].to_lowercase()
+}
+
 /// Generate the preview URL for a deployment
 ///
 /// The domain is already fully resolved by central server (includes PR subdomain if applicable),
@@ -341,6 +1112,67 @@ pub fn generate_preview_url(domain: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_job_status_is_final() {
+        assert!(JobStatus::Success.is_final());
+        assert!(JobStatus::Failed.is_final());
+        assert!(JobStatus::Cleaned.is_final());
+        assert!(JobStatus::Expired.is_final());
+        assert!(JobStatus::Cancelled.is_final());
+        assert!(!JobStatus::Pending.is_final());
+        assert!(!JobStatus::Building.is_final());
+        assert!(!JobStatus::PendingApproval.is_final());
+    }
+
+    #[test]
+    fn test_build_job_from_deploy_config() {
+        let deploy_config = DeployConfig {
+            subdomain: Some("docs".to_string()),
+            basic_auth: Some(BasicAuth {
+                username: "preview".to_string(),
+                bcrypt_hash: "$2a$14$hash".to_string(),
+            }),
+            canary_percent: Some(10),
+            purge_cache: Some(CachePurge::Zone),
+            maintenance_page: Some("<h1>Back soon</h1>".to_string()),
+            precompress: Some(true),
+            ..Default::default()
+        };
+        let target = |preview| BuildTarget {
+            job_id: Uuid::new_v4(),
+            repo_url: "https://github.com/nullisLabs/website.git".to_string(),
+            git_token: "token".to_string(),
+            branch: "main".to_string(),
+            commit_sha: "abc123".to_string(),
+            pr_number: None,
+            site_id: None,
+            domain: "nullislabs.io".to_string(),
+            aliases: Vec::new(),
+            callback_url: "https://central.example.com/api/status".to_string(),
+            repo_name: "website".to_string(),
+            org_name: "nullisLabs".to_string(),
+            environment: Some("nullislabs".to_string()),
+            preview,
+        };
+
+        let main = BuildJob::from_deploy_config(target(false), &deploy_config);
+        assert_eq!(main.subdomain.as_deref(), Some("docs"));
+        assert!(main.basic_auth.is_none());
+        assert_eq!(main.canary_percent, Some(10));
+        assert_eq!(main.purge_cache, Some(CachePurge::Zone));
+        assert!(main.maintenance_page.is_some());
+        assert!(main.precompress);
+
+        // Previews are protected, and skip everything aimed at live visitors
+        let preview = BuildJob::from_deploy_config(target(true), &deploy_config);
+        assert!(preview.subdomain.is_none());
+        assert!(preview.basic_auth.is_some());
+        assert_eq!(preview.canary_percent, None);
+        assert_eq!(preview.purge_cache, None);
+        assert!(preview.maintenance_page.is_none());
+        assert!(preview.precompress);
+    }
+
     #[test]
     fn test_generate_site_id() {
         assert_eq!(
@@ -353,6 +1185,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_generate_commit_site_id() {
+        assert_eq!(
+            generate_commit_site_id("NullisLabs", "Website", "ABC1234def5678"),
+            "nullislabs-website-commit-abc1234"
+        );
+        // Same commit, same site: a repeated preview replaces the previous one
+        assert_eq!(
+            generate_commit_site_id("nullislabs", "website", "abc1234"),
+            generate_commit_site_id("nullislabs", "website", "abc1234ffff")
+        );
+    }
+
     #[test]
     fn test_generate_preview_url() {
         // Domain is already fully resolved by central server
@@ -370,6 +1215,102 @@ mod tests {
             SiteType::SvelteKit
         );
         assert_eq!("VITE".parse::<SiteType>().unwrap(), SiteType::Vite);
+        assert_eq!(
+            "VitePress".parse::<SiteType>().unwrap(),
+            SiteType::VitePress
+        );
         assert!("unknown".parse::<SiteType>().is_err());
     }
+
+    #[test]
+    fn test_site_type_serde_accepts_documented_names() {
+        for (json, expected) in [
+            (r#""sveltekit""#, SiteType::SvelteKit),
+            (r#""svelte_kit""#, SiteType::SvelteKit),
+            (r#""vite""#, SiteType::Vite),
+            (r#""docusaurus""#, SiteType::Docusaurus),
+            (r#""vitepress""#, SiteType::VitePress),
+        ] {
+            assert_eq!(serde_json::from_str::<SiteType>(json).unwrap(), expected);
+        }
+
+        // Serialized form is unchanged, so workers on older builds still parse jobs
+        assert_eq!(
+            serde_json::to_string(&SiteType::SvelteKit).unwrap(),
+            r#""svelte_kit""#
+        );
+    }
+
+    #[test]
+    fn test_basic_auth_with_password() {
+        let auth = BasicAuth::with_password("preview", "hunter2").unwrap();
+
+        assert_eq!(auth.username, "preview");
+        assert!(auth.bcrypt_hash.starts_with("$2"));
+        assert!(bcrypt::verify("hunter2", &auth.bcrypt_hash).unwrap());
+        assert!(!bcrypt::verify("wrong", &auth.bcrypt_hash).unwrap());
+    }
+
+    #[test]
+    fn test_status_update_event_serde() {
+        let update = StatusUpdate {
+            job_id: Uuid::nil(),
+            status: JobStatus::Building,
+            deployed_url: None,
+            error_message: None,
+            event: Some(DeploymentPhase::CaddyConfigured),
+            zone: None,
+            size_bytes: None,
+            worker_id: None,
+        };
+        let json = serde_json::to_value(&update).unwrap();
+        assert_eq!(json["event"], "caddy_configured");
+        assert_eq!(
+            DeploymentPhase::CaddyConfigured.to_string(),
+            "caddy_configured"
+        );
+
+        // Updates from older workers have no event
+        let legacy: StatusUpdate = serde_json::from_str(&format!(
+            r#"{{"job_id": "{}", "status": "success", "deployed_url": null, "error_message": null}}"#,
+            Uuid::nil()
+        ))
+        .unwrap();
+        assert_eq!(legacy.event, None);
+
+        // Progress updates carry the phase alongside the building status
+        let progress: StatusUpdate = serde_json::from_str(&format!(
+            r#"{{"job_id": "{}", "status": "building", "deployed_url": null,
+                "error_message": null, "event": "deployed"}}"#,
+            Uuid::nil()
+        ))
+        .unwrap();
+        assert_eq!(progress.status, JobStatus::Building);
+        assert_eq!(progress.event, Some(DeploymentPhase::Deployed));
+    }
+
+    #[test]
+    fn test_cleanup_job_domain_serde() {
+        let job = CleanupJob {
+            job_id: Uuid::nil(),
+            site_id: "nullislabs-website-pr-42".to_string(),
+            callback_url: "https://central.example.com/api/status".to_string(),
+            domain: Some("pr-42.nullislabs.io".to_string()),
+            environment: Some("nullislabs".to_string()),
+        };
+
+        let json = serde_json::to_string(&job).unwrap();
+        let round_trip: CleanupJob = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip.domain.as_deref(), Some("pr-42.nullislabs.io"));
+        assert_eq!(round_trip.environment.as_deref(), Some("nullislabs"));
+
+        // Jobs from older Centrals have no domain; the worker falls back to site metadata
+        let legacy: CleanupJob = serde_json::from_str(&format!(
+            r#"{{"job_id": "{}", "site_id": "nullislabs-website-pr-42", "callback_url": "https://central.example.com/api/status"}}"#,
+            Uuid::nil()
+        ))
+        .unwrap();
+        assert_eq!(legacy.domain, None);
+        assert_eq!(legacy.environment, None);
+    }
 }
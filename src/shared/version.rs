@@ -0,0 +1,94 @@
+//! Build information reported by `/version` on both services
+//!
+//! Used to spot version skew between Central and its workers.
+
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::SiteType;
+
+/// Version and build details of a running service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionInfo {
+    /// Crate version
+    pub version: String,
+    /// Git commit the binary was built from ("unknown" outside a checkout)
+    pub git_commit: String,
+    /// When the binary was built
+    pub build_timestamp: Option<DateTime<Utc>>,
+    /// Site types this build can deploy
+    pub site_types: Vec<String>,
+}
+
+impl VersionInfo {
+    /// Build information of this binary
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: env!("CATAPULT_GIT_COMMIT").to_string(),
+            build_timestamp: env!("CATAPULT_BUILD_TIMESTAMP")
+                .parse()
+                .ok()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            site_types: SiteType::ALL.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    /// Whether `other` runs a different build than this one
+    pub fn is_skewed_from(&self, other: &VersionInfo) -> bool {
+        self.version != other.version || self.git_commit != other.git_commit
+    }
+}
+
+/// `GET /version`
+pub async fn version_handler() -> Json<VersionInfo> {
+    Json(VersionInfo::current())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_version_endpoint_shape() {
+        let Json(info) = version_handler().await;
+        let json = serde_json::to_value(&info).unwrap();
+
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(!json["git_commit"].as_str().unwrap().is_empty());
+        assert!(json["build_timestamp"].is_string());
+        assert_eq!(
+            json["site_types"],
+            serde_json::json!([
+                "sveltekit",
+                "vite",
+                "zola",
+                "docusaurus",
+                "vitepress",
+                "custom",
+                "auto"
+            ])
+        );
+        assert_eq!(json.as_object().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_version_skew() {
+        let current = VersionInfo::current();
+        assert!(!current.is_skewed_from(&current.clone()));
+
+        let other = VersionInfo {
+            git_commit: "0000000".to_string(),
+            ..current.clone()
+        };
+        assert!(current.is_skewed_from(&other));
+
+        // Build time alone isn't skew (e.g. the same commit rebuilt)
+        let rebuilt = VersionInfo {
+            build_timestamp: None,
+            ..current.clone()
+        };
+        assert!(!current.is_skewed_from(&rebuilt));
+    }
+}
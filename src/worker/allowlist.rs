@@ -0,0 +1,290 @@
+//! Source address allowlist for the worker API
+//!
+//! Defense in depth on top of request signatures: when configured, only
+//! Central's addresses may reach `/build`, `/cleanup`, `/promote`, `/cancel`
+//! and `/status`.
+
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// An IP network in CIDR notation (a bare address is a single host)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNet {
+    /// Whether `ip` is inside this network
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, canonical(ip)) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => prefix_matches(
+                u32::from(net).into(),
+                u32::from(ip).into(),
+                32,
+                self.prefix_len,
+            ),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_matches(u128::from(net), u128::from(ip), 128, self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (s, None),
+        };
+
+        let addr: IpAddr = addr
+            .parse()
+            .with_context(|| format!("Invalid IP address in '{}'", s))?;
+        let addr = canonical(addr);
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        let prefix_len = match prefix_len {
+            Some(len) => len
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .with_context(|| format!("Invalid prefix length in '{}'", s))?,
+            None => max_len,
+        };
+
+        Ok(Self { addr, prefix_len })
+    }
+}
+
+/// Compare the top `prefix_len` bits of two `bits`-wide addresses
+fn prefix_matches(net: u128, ip: u128, bits: u8, prefix_len: u8) -> bool {
+    if prefix_len == 0 {
+        return true;
+    }
+    let shift = bits - prefix_len;
+    (net >> shift) == (ip >> shift)
+}
+
+/// Treat IPv4-mapped IPv6 addresses (dual-stack sockets) as IPv4
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    }
+}
+
+/// Parse a comma-separated list of allowed source networks
+pub fn parse_allowed_sources(value: &str) -> Result<Vec<IpNet>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(IpNet::from_str)
+        .collect()
+}
+
+/// Where the client address of a request is taken from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceAddr {
+    /// The TCP peer address
+    Peer,
+    /// The last `X-Forwarded-For` entry, as set by a trusted reverse proxy
+    ForwardedFor,
+}
+
+impl SourceAddr {
+    /// Extract the client address from a request
+    fn extract(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+        match self {
+            SourceAddr::Peer => peer.map(|addr| addr.ip()),
+            SourceAddr::ForwardedFor => headers
+                .get_all("x-forwarded-for")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .next_back()
+                .and_then(|ip| ip.trim().parse().ok()),
+        }
+    }
+}
+
+/// Allowed source networks for the protected worker routes
+#[derive(Debug, Clone)]
+pub struct SourceAllowlist {
+    networks: Arc<Vec<IpNet>>,
+    source: SourceAddr,
+}
+
+impl SourceAllowlist {
+    pub fn new(networks: Vec<IpNet>, source: SourceAddr) -> Self {
+        Self {
+            networks: Arc::new(networks),
+            source,
+        }
+    }
+
+    /// Whether `ip` may access the protected routes
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|net| net.contains(ip))
+    }
+}
+
+/// Middleware rejecting requests from outside the allowlist with 403
+///
+/// Runs before the handlers, so disallowed sources never reach signature
+/// verification.
+pub async fn enforce_allowlist(
+    State(allowlist): State<SourceAllowlist>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let peer = peer.map(|ConnectInfo(addr)| addr);
+
+    match allowlist.source.extract(request.headers(), peer) {
+        Some(ip) if allowlist.allows(ip) => next.run(request).await,
+        source => {
+            tracing::warn!(
+                source = ?source,
+                path = %request.uri().path(),
+                "Rejected request from source outside the allowlist"
+            );
+            StatusCode::FORBIDDEN.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        Router,
+        body::Body,
+        extract::connect_info::MockConnectInfo,
+        http::Request as HttpRequest,
+        middleware,
+        routing::{get, post},
+    };
+    use tower::ServiceExt;
+
+    fn net(s: &str) -> IpNet {
+        s.parse().unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    /// Worker-shaped router: protected routes plus an open health check
+    fn test_router(allowlist: SourceAllowlist, peer: &str) -> Router {
+        let protected = Router::new()
+            .route("/build", post(|| async { "built" }))
+            .route_layer(middleware::from_fn_with_state(allowlist, enforce_allowlist));
+
+        Router::new()
+            .route("/health", get(|| async { "OK" }))
+            .merge(protected)
+            .layer(MockConnectInfo(SocketAddr::new(ip(peer), 40000)))
+    }
+
+    async fn status(app: Router, method: &str, path: &str, forwarded: Option<&str>) -> StatusCode {
+        let mut request = HttpRequest::builder().method(method).uri(path);
+        if let Some(forwarded) = forwarded {
+            request = request.header("x-forwarded-for", forwarded);
+        }
+        app.oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[test]
+    fn test_ipnet_contains() {
+        assert!(net("10.0.0.0/8").contains(ip("10.1.2.3")));
+        assert!(!net("10.0.0.0/8").contains(ip("11.0.0.1")));
+        assert!(net("203.0.113.7").contains(ip("203.0.113.7")));
+        assert!(!net("203.0.113.7").contains(ip("203.0.113.8")));
+        assert!(net("0.0.0.0/0").contains(ip("198.51.100.1")));
+        assert!(net("2001:db8::/32").contains(ip("2001:db8:1::1")));
+        assert!(!net("2001:db8::/32").contains(ip("2001:db9::1")));
+        assert!(!net("10.0.0.0/8").contains(ip("::1")));
+
+        // IPv4-mapped addresses from dual-stack sockets match IPv4 networks
+        assert!(net("10.0.0.0/8").contains(ip("::ffff:10.0.0.1")));
+    }
+
+    #[test]
+    fn test_parse_allowed_sources() {
+        let nets = parse_allowed_sources("10.0.0.0/8, 203.0.113.7,2001:db8::/32").unwrap();
+        assert_eq!(nets.len(), 3);
+        assert!(parse_allowed_sources("").unwrap().is_empty());
+
+        assert!(parse_allowed_sources("10.0.0.0/33").is_err());
+        assert!(parse_allowed_sources("not-an-ip").is_err());
+        assert!(parse_allowed_sources("10.0.0.0/x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_allowed_peer_reaches_handler() {
+        let allowlist = SourceAllowlist::new(vec![net("203.0.113.0/24")], SourceAddr::Peer);
+        let app = test_router(allowlist, "203.0.113.10");
+
+        assert_eq!(status(app, "POST", "/build", None).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_peer_rejected() {
+        let allowlist = SourceAllowlist::new(vec![net("203.0.113.0/24")], SourceAddr::Peer);
+
+        let app = test_router(allowlist.clone(), "198.51.100.1");
+        assert_eq!(
+            status(app, "POST", "/build", None).await,
+            StatusCode::FORBIDDEN
+        );
+
+        // Health checks stay open
+        let app = test_router(allowlist, "198.51.100.1");
+        assert_eq!(status(app, "GET", "/health", None).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_for_source() {
+        let allowlist = SourceAllowlist::new(vec![net("203.0.113.0/24")], SourceAddr::ForwardedFor);
+
+        // Behind a local proxy the peer is loopback; the proxy-appended entry counts
+        let app = test_router(allowlist.clone(), "127.0.0.1");
+        assert_eq!(
+            status(app, "POST", "/build", Some("198.51.100.1, 203.0.113.10")).await,
+            StatusCode::OK
+        );
+
+        // A client-supplied first entry can't spoof an allowed address
+        let app = test_router(allowlist.clone(), "127.0.0.1");
+        assert_eq!(
+            status(app, "POST", "/build", Some("203.0.113.10, 198.51.100.1")).await,
+            StatusCode::FORBIDDEN
+        );
+
+        let app = test_router(allowlist, "203.0.113.10");
+        assert_eq!(
+            status(app, "POST", "/build", None).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+}
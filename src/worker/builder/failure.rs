@@ -0,0 +1,269 @@
+//! Build failure classification
+//!
+//! Separates failures setting up the nix environment (flake evaluation,
+//! fetching inputs, substituters) from failures of the build command itself.
+//! The former are often transient and worth retrying; the latter are user
+//! errors and fail fast.
+
+use std::time::Duration;
+
+/// Printed right before the build command runs (inside `nix develop` for
+/// flake builds). If it appears in the output, the nix environment was set up
+/// successfully.
+pub const BUILD_STARTED_MARKER: &str = "==> Build environment ready";
+
+/// Maximum attempts for builds failing with a transient error
+pub const MAX_BUILD_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubles for each further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+
+/// Output patterns of nix fetch failures
+///
+/// Only messages nix itself prints; generic ones like "timed out" also show
+/// up in failing user builds, which must not be retried.
+const NIX_ENVIRONMENT_PATTERNS: &[&str] = &[
+    "unable to download",
+    "unable to fetch",
+    "cannot fetch",
+    "while fetching",
+    "error: getting status of",
+    "error: cannot connect",
+    "could not resolve host",
+    "temporary failure in name resolution",
+    "http error 5",
+];
+
+/// What part of the build failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildFailureKind {
+    /// Nix flake evaluation or fetching failed before the build command ran
+    NixEnvironment,
+    /// The build command itself failed
+    BuildCommand,
+}
+
+impl BuildFailureKind {
+    /// Whether this failure is worth retrying
+    pub fn is_transient(&self) -> bool {
+        matches!(self, BuildFailureKind::NixEnvironment)
+    }
+}
+
+/// A failed build with its classified cause and (redacted) output
+#[derive(Debug, thiserror::Error)]
+#[error("{}: {output}", self.summary())]
+pub struct BuildFailure {
+    pub kind: BuildFailureKind,
+    pub exit_code: Option<i64>,
+    pub output: String,
+}
+
+impl BuildFailure {
+    /// Classify a failure from the build output
+    ///
+    /// `build_started` tells whether the output had the
+    /// [`BUILD_STARTED_MARKER`] line, which is checked while the output comes
+    /// in: `output` may be cut to its tail, past the marker.
+    pub fn from_output(exit_code: Option<i64>, output: String, build_started: bool) -> Self {
+        Self {
+            kind: classify_build_failure(&output, build_started),
+            exit_code,
+            output,
+        }
+    }
+
+    fn summary(&self) -> String {
+        let what = match self.kind {
+            BuildFailureKind::NixEnvironment => {
+                "Build environment setup failed (nix flake evaluation/fetch)"
+            }
+            BuildFailureKind::BuildCommand => "Build command failed",
+        };
+
+        match self.exit_code {
+            Some(code) => format!("{} with exit code {}", what, code),
+            None => what.to_string(),
+        }
+    }
+}
+
+/// Whether `output` has the [`BUILD_STARTED_MARKER`] line
+pub fn has_build_started(output: &str) -> bool {
+    output
+        .lines()
+        .any(|line| line.trim() == BUILD_STARTED_MARKER)
+}
+
+/// Classify build output as a nix environment or build command failure
+///
+/// Once the build command started, it's a build command failure. Anything
+/// else that isn't clearly a nix evaluation/fetch problem is treated as one
+/// too, so user errors are never retried.
+pub fn classify_build_failure(output: &str, build_started: bool) -> BuildFailureKind {
+    if build_started {
+        return BuildFailureKind::BuildCommand;
+    }
+
+    let output = output.to_lowercase();
+    if NIX_ENVIRONMENT_PATTERNS
+        .iter()
+        .any(|pattern| output.contains(pattern))
+    {
+        BuildFailureKind::NixEnvironment
+    } else {
+        BuildFailureKind::BuildCommand
+    }
+}
+
+/// Whether a build error should be retried
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<BuildFailure>()
+        .is_some_and(|failure| failure.kind.is_transient())
+}
+
+/// Backoff delay before retrying after the given (1-based) attempt
+pub fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY * 2u32.saturating_pow(attempt.saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_download_failure() {
+        let stderr = "==> Running build with nix develop (github:nullisLabs/catapult#vite)\n\
+            error: unable to download 'https://api.github.com/repos/nullisLabs/catapult/commits/HEAD': \
+            HTTP error 503\n";
+        assert_eq!(
+            classify_build_failure(stderr, false),
+            BuildFailureKind::NixEnvironment
+        );
+    }
+
+    #[test]
+    fn test_classify_dns_failure() {
+        let stderr = "warning: error: unable to download 'https://cache.nixos.org/nix-cache-info': \
+            Couldn't resolve host name (6); retrying\n\
+            error: Could not resolve host: github.com\n";
+        assert_eq!(
+            classify_build_failure(stderr, false),
+            BuildFailureKind::NixEnvironment
+        );
+    }
+
+    #[test]
+    fn test_flake_eval_failure_not_retried() {
+        // A missing attribute fails the same way on every attempt
+        let stderr = "error: flake 'github:nullisLabs/catapult' does not provide attribute \
+            'devShells.x86_64-linux.vite'\n";
+        assert_eq!(
+            classify_build_failure(stderr, false),
+            BuildFailureKind::BuildCommand
+        );
+    }
+
+    #[test]
+    fn test_generic_network_errors_not_retried() {
+        for output in [
+            "Error: connect ECONNREFUSED 127.0.0.1:5432 (connection refused)\n",
+            "test suite timed out after 30s\n",
+            "read: connection reset by peer\n",
+        ] {
+            assert_eq!(
+                classify_build_failure(output, false),
+                BuildFailureKind::BuildCommand,
+                "{}",
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_build_command_failure() {
+        let output = format!(
+            "{}\n> vite build\nerror during build:\n[vite]: Rollup failed to resolve import \"svelte\"\n",
+            BUILD_STARTED_MARKER
+        );
+        assert!(has_build_started(&output));
+        assert_eq!(
+            classify_build_failure(&output, true),
+            BuildFailureKind::BuildCommand
+        );
+    }
+
+    #[test]
+    fn test_build_output_mentioning_network_is_not_retried() {
+        // Once the build command started, network errors are the build's own problem
+        let output = format!(
+            "{}\nnpm ERR! network request to https://registry.npmjs.org failed, reason: connect ETIMEDOUT\n\
+            npm ERR! network This is a problem related to network connectivity. timed out\n",
+            BUILD_STARTED_MARKER
+        );
+        assert!(has_build_started(&output));
+        assert_eq!(
+            classify_build_failure(&output, true),
+            BuildFailureKind::BuildCommand
+        );
+    }
+
+    #[test]
+    fn test_classify_unknown_failure_fails_fast() {
+        assert_eq!(
+            classify_build_failure("sh: npm: not found\n", false),
+            BuildFailureKind::BuildCommand
+        );
+    }
+
+    #[test]
+    fn test_failure_message_includes_category() {
+        let failure =
+            BuildFailure::from_output(Some(1), "error: unable to download".to_string(), false);
+        assert!(failure.to_string().starts_with(
+            "Build environment setup failed (nix flake evaluation/fetch) with exit code 1"
+        ));
+        assert!(is_transient(&anyhow::Error::new(failure)));
+
+        let failure =
+            BuildFailure::from_output(Some(2), format!("{}\nboom", BUILD_STARTED_MARKER), true);
+        assert!(
+            failure
+                .to_string()
+                .starts_with("Build command failed with exit code 2")
+        );
+        assert!(!is_transient(&anyhow::Error::new(failure)));
+    }
+
+    #[test]
+    fn test_marker_cut_from_log_still_counts() {
+        // The log kept only its tail, but the build command had started
+        let output = "npm ERR! network request to https://registry.npmjs.org failed\n\
+            error: unable to download 'https://registry.npmjs.org/vite'\n";
+        assert!(!has_build_started(output));
+        assert_eq!(
+            classify_build_failure(output, true),
+            BuildFailureKind::BuildCommand
+        );
+    }
+
+    #[test]
+    fn test_marker_must_be_a_whole_line() {
+        assert!(has_build_started(&format!(
+            "  {}  \n",
+            BUILD_STARTED_MARKER
+        )));
+        assert!(!has_build_started(&format!(
+            "sh -c 'echo \"{}\"; npm run build'\n",
+            BUILD_STARTED_MARKER
+        )));
+    }
+
+    #[test]
+    fn test_retry_delay_backoff() {
+        assert_eq!(retry_delay(1), Duration::from_secs(5));
+        assert_eq!(retry_delay(2), Duration::from_secs(10));
+        assert_eq!(retry_delay(3), Duration::from_secs(20));
+    }
+}
@@ -1,7 +1,11 @@
 pub mod clone;
+pub mod failure;
 pub mod network;
+pub mod nix;
+pub mod output;
 pub mod podman;
+pub mod policy;
 pub mod types;
 
-pub use clone::clone_repository;
-pub use podman::run_build;
+pub use clone::{CloneOptions, clone_repository};
+pub use podman::{BuiltSite, run_build};
@@ -2,7 +2,9 @@ use anyhow::{Context, Result};
 use bollard::Docker;
 use bollard::models::IpamConfig;
 use bollard::network::{CreateNetworkOptions, InspectNetworkOptions, ListNetworksOptions};
+use serde::Serialize;
 use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 use tokio::process::Command;
 
 /// Name of the isolated build network
@@ -12,7 +14,10 @@ pub const BUILD_NETWORK_NAME: &str = "catapult-build-isolated";
 const RFC1918_RANGES: &[&str] = &["10.0.0.0/8", "172.16.0.0/12", "192.168.0.0/16"];
 
 /// Ensure the isolated build network exists with proper RFC1918 blocking
-pub async fn ensure_build_network(docker: &Docker) -> Result<()> {
+///
+/// Returns whether the blocking rules were verified; see
+/// [`ensure_iptables_rules`].
+pub async fn ensure_build_network(docker: &Docker, dry_run: bool) -> Result<IsolationStatus> {
     // Check if network already exists
     match docker
         .inspect_network(
@@ -27,16 +32,33 @@ pub async fn ensure_build_network(docker: &Docker) -> Result<()> {
         Ok(network) => {
             tracing::debug!(network = BUILD_NETWORK_NAME, "Build network already exists");
             // Network exists, ensure iptables rules are in place
-            if let Some(ipam) = network.ipam
-                && let Some(configs) = ipam.config
-            {
-                for config in configs {
-                    if let Some(subnet) = config.subnet {
-                        ensure_iptables_rules(&subnet).await?;
-                    }
-                }
+            let subnets: Vec<String> = network
+                .ipam
+                .and_then(|ipam| ipam.config)
+                .into_iter()
+                .flatten()
+                .filter_map(|config| config.subnet)
+                .collect();
+            if subnets.is_empty() {
+                tracing::warn!(
+                    network = BUILD_NETWORK_NAME,
+                    "Build network has no subnet to isolate"
+                );
             }
-            return Ok(());
+
+            let mut status = IsolationStatus {
+                active: !subnets.is_empty(),
+                missing_rules: Vec::new(),
+                unknown_rules: Vec::new(),
+                dry_run,
+            };
+            for subnet in subnets {
+                let subnet_status = ensure_iptables_rules(&subnet, dry_run).await;
+                status.active &= subnet_status.active;
+                status.missing_rules.extend(subnet_status.missing_rules);
+                status.unknown_rules.extend(subnet_status.unknown_rules);
+            }
+            return Ok(status);
         }
         Err(bollard::errors::Error::DockerResponseServerError {
             status_code: 404, ..
@@ -83,16 +105,14 @@ pub async fn ensure_build_network(docker: &Docker) -> Result<()> {
         .await
         .context("Failed to create build network")?;
 
-    // Set up iptables rules to block RFC1918
-    ensure_iptables_rules(&subnet).await?;
-
     tracing::info!(
         network = BUILD_NETWORK_NAME,
         subnet = %subnet,
-        "Created isolated build network with RFC1918 blocking"
+        "Created isolated build network"
     );
 
-    Ok(())
+    // Set up iptables rules to block RFC1918
+    Ok(ensure_iptables_rules(&subnet, dry_run).await)
 }
 
 /// Find an available subnet in the 10.89.x.0/24 range
@@ -173,115 +193,295 @@ fn subnets_overlap(a: &str, b: &str) -> bool {
     (net_a & common_mask) == (net_b & common_mask)
 }
 
-/// Ensure iptables rules block RFC1918 destinations from the build network
-async fn ensure_iptables_rules(source_subnet: &str) -> Result<()> {
-    // Create a custom chain for catapult rules if it doesn't exist
-    let chain_name = "CATAPULT_BUILD_ISOLATION";
-
-    // Check if chain exists
-    let check_chain = Command::new("iptables")
-        .args(["-n", "-L", chain_name])
-        .output()
-        .await;
-
-    if check_chain.is_err() || !check_chain.unwrap().status.success() {
-        // Create the chain
-        let output = Command::new("iptables")
-            .args(["-N", chain_name])
-            .output()
-            .await
-            .context("Failed to create iptables chain")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            // Chain might already exist (race condition) - that's fine
-            if !stderr.contains("Chain already exists") {
-                tracing::warn!(
-                    chain = chain_name,
-                    stderr = %stderr,
-                    "Failed to create iptables chain (may require root)"
-                );
-                // Don't fail - we'll log a warning but continue
-                // In production, these rules should be set up by system configuration
-                return Ok(());
-            }
+/// Chain holding the build network's isolation rules
+const ISOLATION_CHAIN: &str = "CATAPULT_BUILD_ISOLATION";
+
+/// Whether the build network's RFC1918 blocking was found in place
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IsolationStatus {
+    /// Every isolation rule is present
+    pub active: bool,
+    /// Commands adding the rules that are missing
+    pub missing_rules: Vec<String>,
+    /// Commands adding the rules that couldn't be checked, for example
+    /// without iptables or the permission to run it
+    pub unknown_rules: Vec<String>,
+    /// Commands were only logged, for the system config to apply
+    pub dry_run: bool,
+}
+
+impl IsolationStatus {
+    /// One-line summary for logs and the self-test
+    pub fn summary(&self) -> String {
+        let mode = if self.dry_run { " (dry run)" } else { "" };
+        if self.active {
+            return format!("RFC1918 rules in place{}", mode);
         }
 
-        // Add rules to block RFC1918 destinations
-        for range in RFC1918_RANGES {
-            // Skip the build network's own subnet (allow self-communication)
-            if range == &"10.0.0.0/8" {
-                // More specific rule to allow the build network itself but block rest of 10.x
-                add_iptables_rule(chain_name, source_subnet, source_subnet, "ACCEPT").await?;
-            }
+        let mut problems = Vec::new();
+        if !self.missing_rules.is_empty() {
+            problems.push(format!(
+                "{} RFC1918 rules missing{}: {}",
+                self.missing_rules.len(),
+                mode,
+                self.missing_rules.join("; ")
+            ));
+        }
+        if !self.unknown_rules.is_empty() {
+            problems.push(format!(
+                "{} RFC1918 rules could not be checked{}: {}",
+                self.unknown_rules.len(),
+                mode,
+                self.unknown_rules.join("; ")
+            ));
+        }
+        problems.join("; ")
+    }
+}
+
+/// Latest isolation status of the build network, for the readiness check
+#[derive(Debug, Clone, Default)]
+pub struct IsolationTracker(Arc<Mutex<Option<IsolationStatus>>>);
+
+impl IsolationTracker {
+    pub fn record(&self, status: IsolationStatus) {
+        *self.0.lock().unwrap() = Some(status);
+    }
+
+    /// None until the build network has been checked
+    pub fn status(&self) -> Option<IsolationStatus> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/// An iptables rule isolating the build network
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IsolationRule {
+    chain: &'static str,
+    /// Match and target, e.g. `-s 10.89.0.0/24 -d 10.0.0.0/8 -j DROP`
+    spec: Vec<String>,
+    /// Inserted at the top of the chain instead of appended
+    first: bool,
+}
 
-            add_iptables_rule(chain_name, source_subnet, range, "DROP").await?;
+impl IsolationRule {
+    fn new(chain: &'static str, spec: &[&str], first: bool) -> Self {
+        Self {
+            chain,
+            spec: spec.iter().map(|arg| arg.to_string()).collect(),
+            first,
         }
+    }
 
-        // Add jump rule from FORWARD chain if not present
-        let check_jump = Command::new("iptables")
-            .args(["-C", "FORWARD", "-s", source_subnet, "-j", chain_name])
-            .output()
-            .await;
-
-        if check_jump.is_err() || !check_jump.unwrap().status.success() {
-            let output = Command::new("iptables")
-                .args(["-I", "FORWARD", "1", "-s", source_subnet, "-j", chain_name])
-                .output()
-                .await
-                .context("Failed to add FORWARD jump rule")?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                tracing::warn!(
-                    stderr = %stderr,
-                    "Failed to add iptables FORWARD jump rule (may require root)"
-                );
-            }
+    /// Arguments adding the rule
+    fn add_args(&self) -> Vec<String> {
+        let mut args = if self.first {
+            vec!["-I".to_string(), self.chain.to_string(), "1".to_string()]
+        } else {
+            vec!["-A".to_string(), self.chain.to_string()]
+        };
+        args.extend(self.spec.iter().cloned());
+        args
+    }
+
+    /// Arguments checking the rule is present
+    fn check_args(&self) -> Vec<String> {
+        let mut args = vec!["-C".to_string(), self.chain.to_string()];
+        args.extend(self.spec.iter().cloned());
+        args
+    }
+}
+
+/// Rules blocking RFC1918 destinations from `source_subnet`, in order
+///
+/// The subnet itself stays reachable; the FORWARD jump comes last so the
+/// chain is complete before traffic is sent through it.
+fn isolation_rules(source_subnet: &str) -> Vec<IsolationRule> {
+    let mut rules = Vec::new();
+    for range in RFC1918_RANGES {
+        // Allow the build network itself but block the rest of 10.x
+        if *range == "10.0.0.0/8" {
+            rules.push(IsolationRule::new(
+                ISOLATION_CHAIN,
+                &["-s", source_subnet, "-d", source_subnet, "-j", "ACCEPT"],
+                false,
+            ));
+        }
+        rules.push(IsolationRule::new(
+            ISOLATION_CHAIN,
+            &["-s", source_subnet, "-d", range, "-j", "DROP"],
+            false,
+        ));
+    }
+    rules.push(IsolationRule::new(
+        "FORWARD",
+        &["-s", source_subnet, "-j", ISOLATION_CHAIN],
+        true,
+    ));
+    rules
+}
+
+/// Every iptables command setting up isolation for `source_subnet`
+fn setup_commands(source_subnet: &str) -> Vec<Vec<String>> {
+    let mut commands = vec![vec!["-N".to_string(), ISOLATION_CHAIN.to_string()]];
+    commands.extend(
+        isolation_rules(source_subnet)
+            .iter()
+            .map(IsolationRule::add_args),
+    );
+    commands
+}
+
+/// Shell form of an iptables command, for logs
+fn command_line(args: &[String]) -> String {
+    format!("iptables {}", args.join(" "))
+}
+
+/// Run iptables, returning its stderr on failure
+async fn run_iptables(args: &[String]) -> std::result::Result<(), String> {
+    match Command::new("iptables").args(args).output().await {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Ensure iptables rules block RFC1918 destinations from the build network
+///
+/// Setup failures (typically missing root) are logged rather than returned;
+/// the rules are checked afterwards and the result says whether isolation
+/// is actually active. With `dry_run` the setup commands are only logged,
+/// for operators applying them through their system config.
+pub async fn ensure_iptables_rules(source_subnet: &str, dry_run: bool) -> IsolationStatus {
+    if dry_run {
+        for args in setup_commands(source_subnet) {
+            tracing::info!(
+                command = %command_line(&args),
+                "Dry run: iptables command not executed"
+            );
         }
+    } else {
+        apply_iptables_rules(source_subnet).await;
+    }
 
+    let status = verify_iptables_rules(source_subnet, dry_run).await;
+    if status.active {
         tracing::info!(
-            chain = chain_name,
+            chain = ISOLATION_CHAIN,
+            source = source_subnet,
+            dry_run,
+            "Verified iptables rules for RFC1918 blocking"
+        );
+    } else {
+        tracing::warn!(
+            chain = ISOLATION_CHAIN,
             source = source_subnet,
-            "Configured iptables rules for RFC1918 blocking"
+            dry_run,
+            missing = ?status.missing_rules,
+            unknown = ?status.unknown_rules,
+            "Build network isolation is NOT active"
         );
     }
+    status
+}
+
+/// Create the isolation chain and add each rule that isn't present yet
+async fn apply_iptables_rules(source_subnet: &str) {
+    let create = vec!["-N".to_string(), ISOLATION_CHAIN.to_string()];
+    match run_iptables(&create).await {
+        Ok(()) => tracing::info!(command = %command_line(&create), "Created iptables chain"),
+        Err(stderr) if stderr.contains("Chain already exists") => {
+            tracing::debug!(chain = ISOLATION_CHAIN, "iptables chain already exists");
+        }
+        Err(stderr) => {
+            tracing::warn!(
+                command = %command_line(&create),
+                stderr = %stderr,
+                "Failed to create iptables chain (may require root)"
+            );
+        }
+    }
+
+    for rule in isolation_rules(source_subnet) {
+        if run_iptables(&rule.check_args()).await.is_ok() {
+            tracing::debug!(command = %command_line(&rule.add_args()), "iptables rule already present");
+            continue;
+        }
+
+        let args = rule.add_args();
+        match run_iptables(&args).await {
+            Ok(()) => tracing::info!(command = %command_line(&args), "Added iptables rule"),
+            Err(stderr) => tracing::warn!(
+                command = %command_line(&args),
+                stderr = %stderr,
+                "Failed to add iptables rule (may require root)"
+            ),
+        }
+    }
+}
 
-    Ok(())
+/// Outcome of checking a rule with `iptables -C`
+#[derive(Debug, PartialEq, Eq)]
+enum RuleCheck {
+    Present,
+    Missing,
+    /// The check itself failed, with its error
+    Unknown(String),
 }
 
-/// Add an iptables rule to the chain
-async fn add_iptables_rule(chain: &str, source: &str, dest: &str, target: &str) -> Result<()> {
-    // Check if rule exists first
-    let check = Command::new("iptables")
-        .args(["-C", chain, "-s", source, "-d", dest, "-j", target])
-        .output()
-        .await;
-
-    if check.is_ok() && check.unwrap().status.success() {
-        // Rule already exists
-        return Ok(());
+impl RuleCheck {
+    /// Interpret the exit code of `iptables -C`
+    ///
+    /// It exits with 1 when the rule or its chain doesn't exist; anything
+    /// else, such as a permission error, says nothing about the rule.
+    fn from_exit_code(code: Option<i32>, stderr: &str) -> Self {
+        match code {
+            Some(0) => RuleCheck::Present,
+            Some(1) => RuleCheck::Missing,
+            _ => RuleCheck::Unknown(stderr.trim().to_string()),
+        }
     }
+}
 
-    let output = Command::new("iptables")
-        .args(["-A", chain, "-s", source, "-d", dest, "-j", target])
-        .output()
-        .await
-        .context("Failed to add iptables rule")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        tracing::debug!(
-            chain = chain,
-            source = source,
-            dest = dest,
-            target = target,
-            stderr = %stderr,
-            "Failed to add iptables rule"
-        );
+/// Check a rule with `iptables -C`
+async fn check_iptables_rule(args: &[String]) -> RuleCheck {
+    match Command::new("iptables").args(args).output().await {
+        Ok(output) => RuleCheck::from_exit_code(
+            output.status.code(),
+            &String::from_utf8_lossy(&output.stderr),
+        ),
+        Err(e) => RuleCheck::Unknown(e.to_string()),
+    }
+}
+
+/// Check each isolation rule with `iptables -C`
+async fn verify_iptables_rules(source_subnet: &str, dry_run: bool) -> IsolationStatus {
+    let mut missing_rules = Vec::new();
+    let mut unknown_rules = Vec::new();
+    for rule in isolation_rules(source_subnet) {
+        match check_iptables_rule(&rule.check_args()).await {
+            RuleCheck::Present => {}
+            RuleCheck::Missing => {
+                tracing::debug!(command = %command_line(&rule.check_args()), "iptables rule missing");
+                missing_rules.push(command_line(&rule.add_args()));
+            }
+            RuleCheck::Unknown(error) => {
+                tracing::warn!(
+                    command = %command_line(&rule.check_args()),
+                    error = %error,
+                    "Failed to check iptables rule (requires iptables and CAP_NET_ADMIN)"
+                );
+                unknown_rules.push(command_line(&rule.add_args()));
+            }
+        }
     }
 
-    Ok(())
+    IsolationStatus {
+        active: missing_rules.is_empty() && unknown_rules.is_empty(),
+        missing_rules,
+        unknown_rules,
+        dry_run,
+    }
 }
 
 #[cfg(test)]
@@ -297,6 +497,108 @@ mod tests {
         assert!(RFC1918_RANGES.contains(&"192.168.0.0/16"));
     }
 
+    #[test]
+    fn test_dry_run_setup_commands() {
+        let commands: Vec<String> = setup_commands("10.89.3.0/24")
+            .iter()
+            .map(|args| command_line(args))
+            .collect();
+
+        assert_eq!(
+            commands,
+            vec![
+                "iptables -N CATAPULT_BUILD_ISOLATION",
+                "iptables -A CATAPULT_BUILD_ISOLATION -s 10.89.3.0/24 -d 10.89.3.0/24 -j ACCEPT",
+                "iptables -A CATAPULT_BUILD_ISOLATION -s 10.89.3.0/24 -d 10.0.0.0/8 -j DROP",
+                "iptables -A CATAPULT_BUILD_ISOLATION -s 10.89.3.0/24 -d 172.16.0.0/12 -j DROP",
+                "iptables -A CATAPULT_BUILD_ISOLATION -s 10.89.3.0/24 -d 192.168.0.0/16 -j DROP",
+                "iptables -I FORWARD 1 -s 10.89.3.0/24 -j CATAPULT_BUILD_ISOLATION",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rules_checked_with_same_spec() {
+        let rules = isolation_rules("10.89.3.0/24");
+
+        // The accept rule for the subnet itself precedes the 10/8 drop
+        assert_eq!(rules[0].spec[5], "ACCEPT");
+        assert_eq!(rules[1].spec[3], "10.0.0.0/8");
+
+        for rule in &rules {
+            let check = rule.check_args();
+            assert_eq!(check[0], "-C");
+            assert_eq!(check[1], rule.chain);
+            assert!(rule.add_args().ends_with(&check[2..]));
+        }
+        assert_eq!(
+            command_line(&rules.last().unwrap().check_args()),
+            "iptables -C FORWARD -s 10.89.3.0/24 -j CATAPULT_BUILD_ISOLATION"
+        );
+    }
+
+    #[test]
+    fn test_isolation_summary() {
+        let active = IsolationStatus {
+            active: true,
+            missing_rules: Vec::new(),
+            unknown_rules: Vec::new(),
+            dry_run: true,
+        };
+        assert_eq!(active.summary(), "RFC1918 rules in place (dry run)");
+
+        let missing = IsolationStatus {
+            active: false,
+            missing_rules: vec![
+                "iptables -I FORWARD 1 -s 10.89.3.0/24 -j CATAPULT_BUILD_ISOLATION".to_string(),
+            ],
+            unknown_rules: Vec::new(),
+            dry_run: false,
+        };
+        assert_eq!(
+            missing.summary(),
+            "1 RFC1918 rules missing: iptables -I FORWARD 1 -s 10.89.3.0/24 -j CATAPULT_BUILD_ISOLATION"
+        );
+
+        let unknown = IsolationStatus {
+            active: false,
+            missing_rules: Vec::new(),
+            unknown_rules: vec!["iptables -N CATAPULT_BUILD_ISOLATION".to_string()],
+            dry_run: true,
+        };
+        assert_eq!(
+            unknown.summary(),
+            "1 RFC1918 rules could not be checked (dry run): iptables -N CATAPULT_BUILD_ISOLATION"
+        );
+    }
+
+    #[test]
+    fn test_rule_check_from_exit_code() {
+        assert_eq!(RuleCheck::from_exit_code(Some(0), ""), RuleCheck::Present);
+        assert_eq!(
+            RuleCheck::from_exit_code(
+                Some(1),
+                "iptables: Bad rule (does a matching rule exist in that chain?)."
+            ),
+            RuleCheck::Missing
+        );
+        // Not permitted to check says nothing about the rule
+        assert_eq!(
+            RuleCheck::from_exit_code(
+                Some(4),
+                "iptables v1.8.10 (nf_tables): Could not fetch rule set generation id: Permission denied (you must be root)\n"
+            ),
+            RuleCheck::Unknown(
+                "iptables v1.8.10 (nf_tables): Could not fetch rule set generation id: Permission denied (you must be root)"
+                    .to_string()
+            )
+        );
+        assert!(matches!(
+            RuleCheck::from_exit_code(None, "killed"),
+            RuleCheck::Unknown(_)
+        ));
+    }
+
     #[test]
     fn test_build_network_name() {
         assert_eq!(BUILD_NETWORK_NAME, "catapult-build-isolated");
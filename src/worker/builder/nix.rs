@@ -0,0 +1,150 @@
+//! Nix settings for build environments
+//!
+//! Workers behind a binary cache or without GitHub access can point `nix develop`
+//! at extra substituters and resolve Catapult's own flake from a mirror.
+
+/// Flake providing the build environments referenced by `SiteType::flake_ref`
+pub const CATAPULT_FLAKE: &str = "github:nullisLabs/catapult";
+
+/// Extra options passed to every `nix develop` invocation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NixSettings {
+    /// Binary caches queried in addition to the configured ones
+    pub extra_substituters: Vec<String>,
+    /// Public keys trusted to sign paths from the extra substituters
+    pub extra_trusted_public_keys: Vec<String>,
+    /// Flake registry path or URL replacing the global registry
+    pub flake_registry: Option<String>,
+    /// Flake reference used in place of `github:nullisLabs/catapult`
+    pub catapult_flake: Option<String>,
+}
+
+impl NixSettings {
+    /// Rewrite references to Catapult's flake to the configured mirror
+    pub fn resolve_flake_ref(&self, flake_ref: &str) -> String {
+        match (&self.catapult_flake, flake_ref.strip_prefix(CATAPULT_FLAKE)) {
+            (Some(mirror), Some(rest)) if rest.is_empty() || rest.starts_with('#') => {
+                format!("{}{}", mirror, rest)
+            }
+            _ => flake_ref.to_string(),
+        }
+    }
+
+    /// Arguments for `nix develop <flake_ref>`, up to (not including) `--command`
+    pub fn develop_args(&self, flake_ref: &str) -> Vec<String> {
+        let mut args = vec!["develop".to_string(), self.resolve_flake_ref(flake_ref)];
+
+        let mut option = |name: &str, value: String| {
+            args.extend(["--option".to_string(), name.to_string(), value]);
+        };
+        if !self.extra_substituters.is_empty() {
+            option("extra-substituters", self.extra_substituters.join(" "));
+        }
+        if !self.extra_trusted_public_keys.is_empty() {
+            option(
+                "extra-trusted-public-keys",
+                self.extra_trusted_public_keys.join(" "),
+            );
+        }
+        if let Some(registry) = &self.flake_registry {
+            option("flake-registry", registry.clone());
+        }
+
+        args
+    }
+}
+
+/// Parse a list of nix setting values separated by whitespace or commas
+pub fn parse_nix_list(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Quote a value for a POSIX shell
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> NixSettings {
+        NixSettings {
+            extra_substituters: vec![
+                "https://cache.internal".to_string(),
+                "s3://nix-cache?region=eu-west-1".to_string(),
+            ],
+            extra_trusted_public_keys: vec!["cache.internal-1:abc=".to_string()],
+            flake_registry: Some("/etc/nix/registry.json".to_string()),
+            catapult_flake: Some("git+https://git.internal/mirror/catapult".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_develop_args_default() {
+        assert_eq!(
+            NixSettings::default().develop_args("github:nullisLabs/catapult#vite"),
+            vec!["develop", "github:nullisLabs/catapult#vite"]
+        );
+    }
+
+    #[test]
+    fn test_develop_args_with_settings() {
+        assert_eq!(
+            settings().develop_args("github:nullisLabs/catapult#sveltekit"),
+            vec![
+                "develop",
+                "git+https://git.internal/mirror/catapult#sveltekit",
+                "--option",
+                "extra-substituters",
+                "https://cache.internal s3://nix-cache?region=eu-west-1",
+                "--option",
+                "extra-trusted-public-keys",
+                "cache.internal-1:abc=",
+                "--option",
+                "flake-registry",
+                "/etc/nix/registry.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_flake_ref_only_rewrites_catapult() {
+        let settings = settings();
+        assert_eq!(
+            settings.resolve_flake_ref("github:nullisLabs/catapult#vite-node20"),
+            "git+https://git.internal/mirror/catapult#vite-node20"
+        );
+        assert_eq!(
+            settings.resolve_flake_ref("github:nullisLabs/catapult-extras#zola"),
+            "github:nullisLabs/catapult-extras#zola"
+        );
+        assert_eq!(
+            settings.resolve_flake_ref("github:acme/envs#vite"),
+            "github:acme/envs#vite"
+        );
+    }
+
+    #[test]
+    fn test_parse_nix_list() {
+        assert_eq!(
+            parse_nix_list("https://a.example, https://b.example\nhttps://c.example "),
+            vec![
+                "https://a.example",
+                "https://b.example",
+                "https://c.example"
+            ]
+        );
+        assert!(parse_nix_list("").is_empty());
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+}
@@ -0,0 +1,170 @@
+//! Build output validation
+//!
+//! A build command can exit successfully without producing anything worth
+//! deploying, e.g. when `output_dir` points at the wrong directory or the
+//! generator silently skipped every page. These checks fail such builds
+//! before they replace a working deployment.
+
+use std::path::{Component, Path};
+
+use anyhow::{Context, Result};
+
+use crate::worker::builder::types::BuildContext;
+
+/// Check that a build produced deployable output in `output_path`
+///
+/// The directory must not be empty and must contain the context's
+/// entrypoint, if it requires one.
+pub async fn validate_output(output_path: &Path, context: &BuildContext) -> Result<()> {
+    let mut entries = tokio::fs::read_dir(output_path).await.with_context(|| {
+        format!(
+            "Build output directory does not exist: {}",
+            context.output_dir
+        )
+    })?;
+    if entries.next_entry().await?.is_none() {
+        anyhow::bail!(
+            "Build output directory is empty: {} (check output_dir in .deploy.json)",
+            context.output_dir
+        );
+    }
+
+    let Some(entrypoint) = &context.entrypoint else {
+        return Ok(());
+    };
+
+    let relative = Path::new(entrypoint);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        anyhow::bail!(
+            "entrypoint must be a relative path within the output directory: {}",
+            entrypoint
+        );
+    }
+
+    let is_file = tokio::fs::metadata(output_path.join(relative))
+        .await
+        .is_ok_and(|metadata| metadata.is_file());
+    if !is_file {
+        anyhow::bail!(
+            "Build output is missing its entrypoint {} in {} (set entrypoint in \
+             .deploy.json, or \"\" to skip the check)",
+            entrypoint,
+            context.output_dir
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::{DeployConfig, SiteType};
+    use crate::worker::builder::types::PackageManager;
+
+    fn context(site_type: SiteType, entrypoint: Option<&str>) -> BuildContext {
+        let config = DeployConfig {
+            entrypoint: entrypoint.map(String::from),
+            ..Default::default()
+        };
+        BuildContext::with_package_manager(site_type, PackageManager::Npm, Some(config)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_output_with_entrypoint_is_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::write(dir.path().join("index.html"), "<html></html>")
+            .await
+            .unwrap();
+
+        validate_output(dir.path(), &context(SiteType::Zola, None))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_empty_output_fails() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // Even custom builds, which need no entrypoint, must output something
+        let error = validate_output(dir.path(), &context(SiteType::Custom, None))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("is empty"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_missing_output_dir_fails() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let error = validate_output(&dir.path().join("public"), &context(SiteType::Zola, None))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("does not exist: public"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_missing_entrypoint_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::write(dir.path().join("404.html"), "Not found")
+            .await
+            .unwrap();
+
+        let error = validate_output(dir.path(), &context(SiteType::Vite, None))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("missing its entrypoint index.html"),
+            "{}",
+            error
+        );
+
+        // A configured entrypoint replaces the default
+        validate_output(dir.path(), &context(SiteType::Vite, Some("404.html")))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_entrypoint_check_can_be_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::create_dir(dir.path().join("assets"))
+            .await
+            .unwrap();
+
+        // Custom builds have no default entrypoint, and "" disables it
+        validate_output(dir.path(), &context(SiteType::Custom, None))
+            .await
+            .unwrap();
+        validate_output(dir.path(), &context(SiteType::Zola, Some("")))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_entrypoint_must_stay_within_output() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::create_dir(dir.path().join("public"))
+            .await
+            .unwrap();
+        tokio::fs::write(dir.path().join("public/app.html"), "")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.path().join("index.html"), "")
+            .await
+            .unwrap();
+
+        let output = dir.path().join("public");
+        let error = validate_output(&output, &context(SiteType::Zola, Some("../index.html")))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("relative path"), "{}", error);
+    }
+}
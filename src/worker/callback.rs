@@ -1,27 +1,128 @@
 use anyhow::{Context, Result};
 
-use crate::shared::{StatusUpdate, auth::sign_request};
+use uuid::Uuid;
+
+use crate::shared::{
+    GitTokenRequest, GitTokenResponse, SiteInventory, StatusUpdate, WorkerSettings,
+    auth::RequestSigner,
+};
 
 /// Send a status update to Central
 pub async fn send_status_update(
     http_client: &reqwest::Client,
     callback_url: &str,
-    shared_secret: &str,
+    signer: RequestSigner<'_>,
     status: StatusUpdate,
 ) -> Result<()> {
     let body = serde_json::to_vec(&status).context("Failed to serialize status update")?;
 
-    let (signature, timestamp) = sign_request(shared_secret.as_bytes(), &body);
+    post_signed(http_client, callback_url, signer, body, "status update").await
+}
+
+/// Send the worker's site inventory to Central
+pub async fn send_inventory(
+    http_client: &reqwest::Client,
+    central_url: &str,
+    signer: RequestSigner<'_>,
+    inventory: &SiteInventory,
+) -> Result<()> {
+    let body = serde_json::to_vec(inventory).context("Failed to serialize site inventory")?;
+    let url = format!(
+        "{}/api/workers/inventory",
+        central_url.trim_end_matches('/')
+    );
+
+    post_signed(http_client, &url, signer, body, "site inventory").await
+}
+
+/// Ask Central for a fresh git token for a running job
+pub async fn request_git_token(
+    http_client: &reqwest::Client,
+    central_url: &str,
+    signer: RequestSigner<'_>,
+    job_id: Uuid,
+) -> Result<String> {
+    let body = serde_json::to_vec(&GitTokenRequest { job_id })
+        .context("Failed to serialize git token request")?;
+    let url = format!("{}/api/workers/token", central_url.trim_end_matches('/'));
+
+    let response = send_signed(http_client, &url, signer, body, "git token request").await?;
+    let token: GitTokenResponse = response
+        .json()
+        .await
+        .context("Failed to parse git token from Central")?;
+
+    tracing::info!(job_id = %job_id, expires_at = %token.expires_at, "Received fresh git token");
+    Ok(token.token)
+}
+
+/// Fetch the settings Central assigns to this worker's zone
+///
+/// The request has no body, so the zone name is what gets signed.
+pub async fn fetch_worker_settings(
+    http_client: &reqwest::Client,
+    central_url: &str,
+    signer: RequestSigner<'_>,
+    zone: &str,
+) -> Result<WorkerSettings> {
+    let url = format!(
+        "{}/api/workers/{}/config",
+        central_url.trim_end_matches('/'),
+        zone
+    );
+    let (signature, timestamp) = signer.sign(zone.as_bytes());
 
     let response = http_client
-        .post(callback_url)
+        .get(&url)
+        .header("X-Worker-Signature", signature)
+        .header("X-Request-Timestamp", timestamp.to_string())
+        .send()
+        .await
+        .context("Failed to request worker settings from Central")?;
+
+    if !response.status().is_success() {
+        let status_code = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Central returned error {}: {}", status_code, body);
+    }
+
+    response
+        .json()
+        .await
+        .context("Failed to parse worker settings from Central")
+}
+
+/// POST a JSON body to Central, signed with the worker's secret
+async fn post_signed(
+    http_client: &reqwest::Client,
+    url: &str,
+    signer: RequestSigner<'_>,
+    body: Vec<u8>,
+    what: &str,
+) -> Result<()> {
+    send_signed(http_client, url, signer, body, what).await?;
+    Ok(())
+}
+
+/// Signed POST returning Central's successful response
+async fn send_signed(
+    http_client: &reqwest::Client,
+    url: &str,
+    signer: RequestSigner<'_>,
+    body: Vec<u8>,
+    what: &str,
+) -> Result<reqwest::Response> {
+    let (signature, timestamp) = signer.sign(&body);
+
+    let response = http_client
+        .post(url)
         .header("Content-Type", "application/json")
         .header("X-Worker-Signature", signature)
         .header("X-Request-Timestamp", timestamp.to_string())
         .body(body)
         .send()
         .await
-        .context("Failed to send status update to Central")?;
+        .with_context(|| format!("Failed to send {} to Central", what))?;
 
     if !response.status().is_success() {
         let status_code = response.status();
@@ -29,5 +130,95 @@ pub async fn send_status_update(
         anyhow::bail!("Central returned error {}: {}", status_code, body);
     }
 
-    Ok(())
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_request_git_token() {
+        let central = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/workers/token"))
+            .and(header_exists("X-Worker-Signature"))
+            .and(header_exists("X-Request-Timestamp"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "token": "ghs_fresh",
+                "expires_at": "2026-10-16T13:00:00Z"
+            })))
+            .expect(1)
+            .mount(&central)
+            .await;
+
+        let token = request_git_token(
+            &reqwest::Client::new(),
+            &format!("{}/", central.uri()),
+            RequestSigner::Secret("test-secret"),
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(token, "ghs_fresh");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_worker_settings_signed() {
+        let central = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/workers/nullislabs/config"))
+            .and(header_exists("X-Worker-Signature"))
+            .and(header_exists("X-Request-Timestamp"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"keep_releases": 5})),
+            )
+            .expect(1)
+            .mount(&central)
+            .await;
+
+        let settings = fetch_worker_settings(
+            &reqwest::Client::new(),
+            &central.uri(),
+            RequestSigner::Secret("test-secret"),
+            "nullislabs",
+        )
+        .await
+        .unwrap();
+        assert_eq!(settings.keep_releases, Some(5));
+
+        // The signature covers the zone name
+        let request = &central.received_requests().await.unwrap()[0];
+        let signature = request.headers["x-worker-signature"].to_str().unwrap();
+        let timestamp = request.headers["x-request-timestamp"].to_str().unwrap();
+        assert!(crate::test_util::verify_signature(
+            b"test-secret",
+            b"nullislabs",
+            signature,
+            timestamp.parse().unwrap(),
+            Default::default()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_git_token_refused() {
+        let central = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/workers/token"))
+            .respond_with(ResponseTemplate::new(409).set_body_string("Job is success"))
+            .mount(&central)
+            .await;
+
+        let err = request_git_token(
+            &reqwest::Client::new(),
+            &central.uri(),
+            RequestSigner::Secret("test-secret"),
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("409"));
+    }
 }
@@ -0,0 +1,354 @@
+//! Prebuilt artifact deployments
+//!
+//! Teams that build in their own CI hand the worker a `.tar.gz` of site files
+//! instead of a repository. The archive is downloaded with a size cap and every
+//! entry is checked before it is written, so a hostile archive can't place
+//! files outside the output directory.
+
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+use tar::EntryType;
+use tokio::io::AsyncWriteExt;
+
+use crate::shared::ArtifactSource;
+
+/// Download and unpack an artifact into `work_dir/output`
+///
+/// Returns the output directory, ready to be deployed like a build's output.
+/// `max_bytes` bounds both the archive and its unpacked contents.
+pub async fn fetch_artifact(
+    http_client: &reqwest::Client,
+    source: &ArtifactSource,
+    work_dir: &Path,
+    max_bytes: u64,
+    timeout: Duration,
+) -> Result<PathBuf> {
+    let archive = work_dir.join("artifact.tar.gz");
+    let output_dir = work_dir.join("output");
+
+    download_artifact(http_client, source, &archive, max_bytes, timeout).await?;
+
+    let (archive_path, dest) = (archive.clone(), output_dir.clone());
+    let result =
+        tokio::task::spawn_blocking(move || extract_artifact(&archive_path, &dest, max_bytes))
+            .await
+            .context("Artifact extraction task panicked")?;
+
+    let _ = tokio::fs::remove_file(&archive).await;
+
+    if let Err(e) = result {
+        // Never leave a partially unpacked tree behind
+        let _ = tokio::fs::remove_dir_all(&output_dir).await;
+        return Err(e);
+    }
+
+    Ok(output_dir)
+}
+
+/// Stream an artifact to `dest`, enforcing the size limit and checksum
+async fn download_artifact(
+    http_client: &reqwest::Client,
+    source: &ArtifactSource,
+    dest: &Path,
+    max_bytes: u64,
+    timeout: Duration,
+) -> Result<()> {
+    // Signed URLs carry credentials, so keep them out of error messages
+    let mut response = http_client
+        .get(&source.url)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|e| e.without_url())
+        .context("Failed to download artifact")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("Artifact download failed with status {}", response.status());
+    }
+
+    if let Some(len) = response.content_length()
+        && len > max_bytes
+    {
+        anyhow::bail!("Artifact is {} bytes, limit is {}", len, max_bytes);
+    }
+
+    let mut file = tokio::fs::File::create(dest)
+        .await
+        .context("Failed to create artifact file")?;
+    let mut hasher = Sha256::new();
+    let mut total: u64 = 0;
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| e.without_url())
+        .context("Failed to download artifact")?
+    {
+        total += chunk.len() as u64;
+        if total > max_bytes {
+            anyhow::bail!("Artifact exceeds the {} byte limit", max_bytes);
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+
+    if let Some(expected) = &source.sha256 {
+        let actual = hex::encode(hasher.finalize());
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            anyhow::bail!(
+                "Artifact checksum mismatch: expected {}, got {}",
+                expected,
+                actual
+            );
+        }
+    }
+
+    tracing::info!(bytes = total, "Downloaded artifact");
+
+    Ok(())
+}
+
+/// Unpack a `.tar.gz` into `dest`
+///
+/// Only regular files and directories are accepted; links and device nodes
+/// are rejected outright, as are entries with absolute or `..` paths.
+fn extract_artifact(archive: &Path, dest: &Path, max_bytes: u64) -> Result<()> {
+    let file = std::fs::File::open(archive).context("Failed to open artifact")?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+
+    std::fs::create_dir_all(dest)?;
+    let mut total: u64 = 0;
+    let mut files = 0;
+
+    for entry in archive
+        .entries()
+        .context("Failed to read artifact archive")?
+    {
+        let mut entry = entry.context("Failed to read artifact archive")?;
+        let path = entry
+            .path()
+            .context("Invalid path in artifact archive")?
+            .into_owned();
+        let relative = safe_entry_path(&path)?;
+
+        match entry.header().entry_type() {
+            EntryType::Directory => {
+                std::fs::create_dir_all(dest.join(&relative))?;
+            }
+            EntryType::Regular | EntryType::Continuous => {
+                if relative.as_os_str().is_empty() {
+                    anyhow::bail!("Artifact entry '{}' has no file name", path.display());
+                }
+
+                total += entry.size();
+                if total > max_bytes {
+                    anyhow::bail!("Unpacked artifact exceeds the {} byte limit", max_bytes);
+                }
+
+                let target = dest.join(&relative);
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                entry
+                    .unpack(&target)
+                    .with_context(|| format!("Failed to unpack '{}'", path.display()))?;
+                files += 1;
+            }
+            // pax global headers carry metadata only
+            EntryType::XGlobalHeader => {}
+            other => anyhow::bail!(
+                "Artifact entry '{}' has unsupported type {:?}",
+                path.display(),
+                other
+            ),
+        }
+    }
+
+    tracing::info!(files, bytes = total, "Unpacked artifact");
+
+    Ok(())
+}
+
+/// Normalize an entry path, rejecting anything that could leave the output directory
+fn safe_entry_path(path: &Path) -> Result<PathBuf> {
+    let mut safe = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => safe.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                anyhow::bail!(
+                    "Artifact entry '{}' escapes the output directory",
+                    path.display()
+                )
+            }
+        }
+    }
+    Ok(safe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compression, write::GzEncoder};
+    use tempfile::tempdir;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Build a `.tar.gz`, writing entry names raw so unsafe paths survive
+    fn archive(entries: &[(&str, EntryType, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        for (name, entry_type, data) in entries {
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_entry_type(*entry_type);
+            header.set_mode(0o644);
+            header.set_size(data.len() as u64);
+            if *entry_type == EntryType::Symlink {
+                header.set_link_name("/etc/passwd").unwrap();
+            }
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn extract(
+        entries: &[(&str, EntryType, &[u8])],
+        max_bytes: u64,
+    ) -> (tempfile::TempDir, Result<()>) {
+        let dir = tempdir().unwrap();
+        let archive_path = dir.path().join("artifact.tar.gz");
+        std::fs::write(&archive_path, archive(entries)).unwrap();
+        let result = extract_artifact(&archive_path, &dir.path().join("work/output"), max_bytes);
+        (dir, result)
+    }
+
+    #[test]
+    fn test_extract_site_files() {
+        let (dir, result) = extract(
+            &[
+                ("./index.html", EntryType::Regular, b"<h1>hi</h1>"),
+                ("assets/", EntryType::Directory, b""),
+                ("assets/app.js", EntryType::Regular, b"// js"),
+            ],
+            1024,
+        );
+        result.unwrap();
+
+        let output = dir.path().join("work/output");
+        assert_eq!(
+            std::fs::read_to_string(output.join("index.html")).unwrap(),
+            "<h1>hi</h1>"
+        );
+        assert!(output.join("assets/app.js").exists());
+    }
+
+    #[test]
+    fn test_extract_rejects_parent_dir_entries() {
+        let (dir, result) = extract(
+            &[
+                ("index.html", EntryType::Regular, b"ok"),
+                ("../evil.html", EntryType::Regular, b"pwned"),
+            ],
+            1024,
+        );
+
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("escapes the output directory"));
+        assert!(!dir.path().join("work/evil.html").exists());
+
+        let (_dir, result) = extract(
+            &[("assets/../../evil.html", EntryType::Regular, b"pwned")],
+            1024,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_extract_rejects_absolute_entries() {
+        let (_dir, result) = extract(&[("/tmp/evil.html", EntryType::Regular, b"pwned")], 1024);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_extract_rejects_links() {
+        let (dir, result) = extract(&[("passwd", EntryType::Symlink, b"")], 1024);
+        assert!(result.is_err());
+        assert!(!dir.path().join("work/output/passwd").exists());
+    }
+
+    #[test]
+    fn test_extract_enforces_size_limit() {
+        let (_dir, result) = extract(
+            &[
+                ("a.bin", EntryType::Regular, &[0u8; 600]),
+                ("b.bin", EntryType::Regular, &[0u8; 600]),
+            ],
+            1000,
+        );
+        assert!(result.unwrap_err().to_string().contains("byte limit"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_artifact_verifies_checksum() {
+        let body = archive(&[("index.html", EntryType::Regular, b"ok")]);
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body.clone()))
+            .mount(&server)
+            .await;
+
+        let work = tempdir().unwrap();
+        let source = |sha256: &str| ArtifactSource {
+            url: format!("{}/site.tar.gz?signature=secret", server.uri()),
+            sha256: Some(sha256.to_string()),
+        };
+        let client = reqwest::Client::new();
+        let timeout = Duration::from_secs(10);
+
+        let err = fetch_artifact(&client, &source("00"), work.path(), 1024, timeout)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+
+        let sha256 = hex::encode(Sha256::digest(&body));
+        let output = fetch_artifact(&client, &source(&sha256), work.path(), 1024, timeout)
+            .await
+            .unwrap();
+        assert!(output.join("index.html").exists());
+        assert!(!work.path().join("artifact.tar.gz").exists());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_artifact_rejects_oversized_download() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0u8; 4096]))
+            .mount(&server)
+            .await;
+
+        let work = tempdir().unwrap();
+        let source = ArtifactSource {
+            url: format!("{}/site.tar.gz?signature=secret", server.uri()),
+            sha256: None,
+        };
+
+        let err = fetch_artifact(
+            &reqwest::Client::new(),
+            &source,
+            work.path(),
+            1024,
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("limit"));
+        assert!(!format!("{:#}", err).contains("secret"));
+    }
+}
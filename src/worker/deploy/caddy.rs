@@ -1,11 +1,19 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use super::sites::METADATA_FILE;
+use crate::shared::BasicAuth;
+
 const CADDY_READY_TIMEOUT: Duration = Duration::from_secs(60);
 const CADDY_READY_INTERVAL: Duration = Duration::from_millis(500);
 
+/// Backoff between post-deploy health checks, doubling up to the maximum
+const SITE_READY_INITIAL_INTERVAL: Duration = Duration::from_secs(1);
+const SITE_READY_MAX_INTERVAL: Duration = Duration::from_secs(10);
+
 /// Wait for Caddy admin API to be ready
 ///
 /// Polls the Caddy admin API until it responds or timeout is reached.
@@ -14,7 +22,6 @@ pub async fn wait_for_caddy_ready(
     http_client: &reqwest::Client,
     caddy_admin_api: &str,
 ) -> Result<()> {
-    let start = std::time::Instant::now();
     let url = format!("{}/config/", caddy_admin_api);
 
     tracing::info!(
@@ -23,31 +30,85 @@ pub async fn wait_for_caddy_ready(
         "Waiting for Caddy to be ready"
     );
 
+    poll_until_ready(
+        http_client,
+        &url,
+        |status| status.is_success(),
+        CADDY_READY_TIMEOUT,
+        CADDY_READY_INTERVAL,
+        CADDY_READY_INTERVAL,
+    )
+    .await
+    .context("Caddy admin API not ready")?;
+
+    tracing::info!("Caddy admin API is ready");
+    Ok(())
+}
+
+/// Wait for a deployed site to be served at `url`
+///
+/// Checks with backoff until it answers 2xx, or 401 for sites behind basic
+/// auth, catching routes that are configured but not reachable yet (DNS
+/// propagation, tunnel or Caddy reload lag).
+pub async fn wait_for_site_ready(
+    http_client: &reqwest::Client,
+    url: &str,
+    protected: bool,
+    timeout: Duration,
+) -> Result<()> {
+    poll_until_ready(
+        http_client,
+        url,
+        |status| status.is_success() || (protected && status == reqwest::StatusCode::UNAUTHORIZED),
+        timeout,
+        SITE_READY_INITIAL_INTERVAL,
+        SITE_READY_MAX_INTERVAL,
+    )
+    .await
+}
+
+/// GET `url` until `ready` accepts the response status or `timeout` passes
+///
+/// The delay between attempts starts at `interval` and doubles up to `max_interval`.
+async fn poll_until_ready(
+    http_client: &reqwest::Client,
+    url: &str,
+    ready: impl Fn(reqwest::StatusCode) -> bool,
+    timeout: Duration,
+    mut interval: Duration,
+    max_interval: Duration,
+) -> Result<()> {
+    let start = std::time::Instant::now();
+    let mut attempts = 0;
+
     loop {
-        match http_client.get(&url).send().await {
-            Ok(response) if response.status().is_success() => {
-                tracing::info!(
+        attempts += 1;
+        let last = match http_client.get(url).send().await {
+            Ok(response) if ready(response.status()) => {
+                tracing::debug!(
+                    url,
+                    attempts,
                     elapsed_ms = start.elapsed().as_millis() as u64,
-                    "Caddy admin API is ready"
+                    "Ready"
                 );
                 return Ok(());
             }
-            Ok(response) => {
-                tracing::debug!(
-                    status = %response.status(),
-                    "Caddy not ready yet (unexpected status)"
-                );
-            }
-            Err(e) => {
-                tracing::debug!(error = %e, "Caddy not ready yet");
-            }
-        }
+            Ok(response) => format!("status {}", response.status()),
+            Err(e) => e.without_url().to_string(),
+        };
+        tracing::debug!(url, attempts, last = %last, "Not ready yet");
 
-        if start.elapsed() >= CADDY_READY_TIMEOUT {
-            anyhow::bail!("Caddy admin API not ready after {:?}", CADDY_READY_TIMEOUT);
+        if start.elapsed() + interval > timeout {
+            anyhow::bail!(
+                "not ready after {} attempts in {:?} (last: {})",
+                attempts,
+                start.elapsed(),
+                last
+            );
         }
 
-        tokio::time::sleep(CADDY_READY_INTERVAL).await;
+        tokio::time::sleep(interval).await;
+        interval = (interval * 2).min(max_interval);
     }
 }
 
@@ -61,25 +122,31 @@ pub async fn configure_caddy_route(
     site_id: &str,
     site_dir: &Path,
     domain: &str,
+    options: &RouteOptions,
 ) -> Result<()> {
     // Domain is already the full hostname (resolved by central server)
     let hostname = domain;
 
     // Build the route configuration
-    let route = CaddyRoute {
-        id: site_id.to_string(),
-        match_rules: vec![CaddyMatch {
-            host: vec![hostname.to_string()],
-        }],
-        handle: vec![CaddyHandler::FileServer {
-            root: site_dir.to_string_lossy().to_string(),
-            index_names: vec!["index.html".to_string()],
-        }],
-        terminal: true,
-    };
+    let mut route = build_route(site_id, site_dir, hostname, options);
+    if let Some(extra) = &options.caddy_extra {
+        apply_caddy_extra(&mut route, extra)?;
+    }
 
-    // First, try to delete any existing route with this ID
-    let _ = remove_caddy_route(http_client, caddy_admin_api, site_id).await;
+    // Replace an existing route in place so the site is never unrouted
+    if replace_caddy_route(http_client, caddy_admin_api, &route).await? {
+        tracing::info!(
+            site_id = site_id,
+            hostname = hostname,
+            aliases = ?options.aliases,
+            site_dir = %site_dir.display(),
+            basic_auth = options.basic_auth.is_some(),
+            precompressed = options.precompressed,
+            canary_percent = ?options.canary.as_ref().map(|canary| canary.percent),
+            "Updated existing Caddy route"
+        );
+        return Ok(());
+    }
 
     // Find the position to insert (before any catch-all route)
     let insert_index = find_catch_all_index(http_client, caddy_admin_api).await?;
@@ -90,14 +157,464 @@ pub async fn configure_caddy_route(
     tracing::info!(
         site_id = site_id,
         hostname = hostname,
+        aliases = ?options.aliases,
         site_dir = %site_dir.display(),
         insert_index = ?insert_index,
+        basic_auth = options.basic_auth.is_some(),
+        precompressed = options.precompressed,
+        canary_percent = ?options.canary.as_ref().map(|canary| canary.percent),
         "Configured Caddy route"
     );
 
     Ok(())
 }
 
+/// Point a site's existing route at a maintenance page
+///
+/// Every request is answered with `page` and a 503, so visitors and crawlers
+/// come back later instead of hitting a half-replaced site. Authentication
+/// and aliases stay as configured. Returns false, changing nothing, if the
+/// site has no route yet; configuring its route again restores the site.
+pub async fn show_maintenance_page(
+    http_client: &reqwest::Client,
+    caddy_admin_api: &str,
+    site_id: &str,
+    domain: &str,
+    page: &str,
+    options: &RouteOptions,
+) -> Result<bool> {
+    let route = route_with(site_id, domain, options, maintenance_response(page));
+
+    let replaced = replace_caddy_route(http_client, caddy_admin_api, &route).await?;
+    if replaced {
+        tracing::info!(
+            site_id = site_id,
+            hostname = domain,
+            "Serving maintenance page"
+        );
+    }
+
+    Ok(replaced)
+}
+
+/// Per-site options for the generated Caddy route
+#[derive(Debug, Clone, Default)]
+pub struct RouteOptions {
+    /// Additional hostnames matched alongside the site's domain
+    pub aliases: Vec<String>,
+    /// Require HTTP basic auth before serving files
+    pub basic_auth: Option<BasicAuth>,
+    /// Serve pre-compressed `.br`/`.gz` siblings when the client accepts them
+    pub precompressed: bool,
+    /// Path globs of content-hashed assets; when set, these are cached
+    /// forever and HTML is revalidated on every request
+    pub immutable_paths: Vec<String>,
+    /// Index files tried after `index.html`
+    pub index_names: Vec<String>,
+    /// List directories without an index file
+    pub browse: bool,
+    /// Split traffic between the site directory and a canary release
+    pub canary: Option<CanarySplit>,
+    /// Site-provided handlers and route fields (see [`validate_caddy_extra`])
+    pub caddy_extra: Option<serde_json::Value>,
+}
+
+/// A canary release served to a share of visitors alongside the stable one
+#[derive(Debug, Clone)]
+pub struct CanarySplit {
+    /// Directory the canary release is served from
+    pub dir: PathBuf,
+    /// Percentage of new visitors sent to the canary (1-99)
+    pub percent: u8,
+    /// Whether the canary release has pre-compressed siblings
+    pub precompressed: bool,
+}
+
+/// Cookie pinning a visitor to one side of a canary split, so a page and
+/// its hashed assets always come from the same release
+const CANARY_COOKIE: &str = "catapult_release";
+
+/// Index file every site is served with
+const DEFAULT_INDEX: &str = "index.html";
+
+/// Cache-Control for content-hashed assets
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Seconds clients are asked to wait while the maintenance page is up
+const MAINTENANCE_RETRY_AFTER: &str = "30";
+
+/// Build the route for a site, placing authentication ahead of the file server
+fn build_route(
+    site_id: &str,
+    site_dir: &Path,
+    hostname: &str,
+    options: &RouteOptions,
+) -> CaddyRoute {
+    let content = match &options.canary {
+        Some(canary) => canary_subroute(site_dir, options, canary),
+        None => file_server(site_dir, options.precompressed, options),
+    };
+
+    route_with(site_id, hostname, options, content)
+}
+
+/// Route fields Catapult manages, which `caddy_extra` may not set
+const MANAGED_ROUTE_FIELDS: [&str; 3] = ["@id", "match", "terminal"];
+
+/// Handlers `caddy_extra` may add; anything that could serve or proxy other
+/// content, such as `file_server`, `reverse_proxy` or a `subroute`, is left out
+const ALLOWED_EXTRA_HANDLERS: [&str; 6] = [
+    "encode",
+    "headers",
+    "rate_limit",
+    "request_body",
+    "rewrite",
+    "static_response",
+];
+
+/// Check a site's `caddy_extra` before it's merged into its route
+///
+/// It is either a list of handlers, or a route object whose `handle` list
+/// holds the handlers and whose other fields are added to the route. The
+/// route's `@id`, host matchers and `terminal` stay managed, and no `@id`
+/// may appear anywhere in it, since Caddy IDs address other sites' routes.
+/// Only [`ALLOWED_EXTRA_HANDLERS`] are taken, and no `root` may be set, so a
+/// site can't serve files outside its own directory.
+pub fn validate_caddy_extra(extra: &serde_json::Value) -> Result<()> {
+    split_caddy_extra(extra).map(|_| ())
+}
+
+/// Split `caddy_extra` into its handlers and extra route fields
+fn split_caddy_extra(
+    extra: &serde_json::Value,
+) -> Result<(
+    Vec<CaddyHandler>,
+    serde_json::Map<String, serde_json::Value>,
+)> {
+    use serde_json::Value;
+
+    if contains_key(extra, "@id") {
+        anyhow::bail!("caddy_extra must not set an @id");
+    }
+    if contains_key(extra, "root") {
+        anyhow::bail!("caddy_extra must not set a root");
+    }
+
+    let (handlers, fields) = match extra {
+        Value::Array(handlers) => (handlers.clone(), serde_json::Map::new()),
+        Value::Object(route) => {
+            let mut fields = route.clone();
+            if let Some(managed) = MANAGED_ROUTE_FIELDS
+                .iter()
+                .find(|field| fields.contains_key(**field))
+            {
+                anyhow::bail!("caddy_extra must not set the route's managed '{}'", managed);
+            }
+            let handlers = match fields.remove("handle") {
+                Some(Value::Array(handlers)) => handlers,
+                Some(_) => anyhow::bail!("caddy_extra 'handle' must be a list of handlers"),
+                None => Vec::new(),
+            };
+            (handlers, fields)
+        }
+        _ => anyhow::bail!("caddy_extra must be a list of handlers or a route object"),
+    };
+
+    let handlers = handlers
+        .into_iter()
+        .map(|handler| {
+            let Some(name) = handler.get("handler").and_then(Value::as_str) else {
+                anyhow::bail!("Every caddy_extra handler needs a 'handler' name");
+            };
+            if !ALLOWED_EXTRA_HANDLERS.contains(&name) {
+                anyhow::bail!(
+                    "caddy_extra handler '{}' is not allowed (allowed: {})",
+                    name,
+                    ALLOWED_EXTRA_HANDLERS.join(", ")
+                );
+            }
+            Ok(CaddyHandler::Custom(handler))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok((handlers, fields))
+}
+
+/// Whether `key` appears anywhere in `value`
+fn contains_key(value: &serde_json::Value, key: &str) -> bool {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.contains_key(key) || map.values().any(|value| contains_key(value, key))
+        }
+        serde_json::Value::Array(values) => values.iter().any(|value| contains_key(value, key)),
+        _ => false,
+    }
+}
+
+/// Merge a site's `caddy_extra` into its route
+///
+/// Extra handlers run after authentication and cache headers, right before
+/// the handler serving the site's content.
+fn apply_caddy_extra(route: &mut CaddyRoute, extra: &serde_json::Value) -> Result<()> {
+    let (handlers, fields) = split_caddy_extra(extra).context("Invalid caddy_extra")?;
+
+    let content = route.handle.len().saturating_sub(1);
+    route.handle.splice(content..content, handlers);
+    route.extra.extend(fields);
+
+    Ok(())
+}
+
+/// Build a site's route around the handler producing its content
+fn route_with(
+    site_id: &str,
+    hostname: &str,
+    options: &RouteOptions,
+    content: CaddyHandler,
+) -> CaddyRoute {
+    let mut handle = Vec::new();
+
+    if let Some(auth) = &options.basic_auth {
+        handle.push(CaddyHandler::Authentication {
+            providers: CaddyAuthProviders {
+                http_basic: CaddyHttpBasic {
+                    accounts: vec![CaddyAccount {
+                        username: auth.username.clone(),
+                        password: auth.bcrypt_hash.clone(),
+                    }],
+                    hash: CaddyHash {
+                        algorithm: "bcrypt".to_string(),
+                    },
+                },
+            },
+        });
+    }
+
+    if !options.immutable_paths.is_empty() {
+        handle.extend(cache_headers(&options.immutable_paths));
+    }
+
+    handle.push(content);
+
+    let mut hosts = vec![hostname.to_string()];
+    hosts.extend(
+        options
+            .aliases
+            .iter()
+            .filter(|alias| alias.as_str() != hostname)
+            .cloned(),
+    );
+
+    CaddyRoute {
+        id: site_id.to_string(),
+        match_rules: vec![CaddyMatch {
+            host: hosts,
+            ..Default::default()
+        }],
+        handle,
+        terminal: true,
+        extra: serde_json::Map::new(),
+    }
+}
+
+/// File server for one release directory
+///
+/// Directory listing is only enabled when the site asks for it. The deploy
+/// metadata stored in the site directory is hidden from requests and listings.
+fn file_server(root: &Path, precompressed: bool, options: &RouteOptions) -> CaddyHandler {
+    let (precompressed, precompressed_order) = if precompressed {
+        (
+            Some(CaddyPrecompressed::default()),
+            Some(vec!["br".to_string(), "gzip".to_string()]),
+        )
+    } else {
+        (None, None)
+    };
+
+    CaddyHandler::FileServer {
+        root: root.to_string_lossy().to_string(),
+        index_names: index_names(&options.index_names),
+        precompressed,
+        precompressed_order,
+        browse: options.browse.then(CaddyBrowse::default),
+        hide: vec![METADATA_FILE.to_string()],
+    }
+}
+
+/// Answer every request with the maintenance page and a 503
+fn maintenance_response(page: &str) -> CaddyHandler {
+    CaddyHandler::StaticResponse {
+        status_code: 503,
+        headers: HashMap::from([
+            (
+                "Content-Type".to_string(),
+                vec!["text/html; charset=utf-8".to_string()],
+            ),
+            (
+                "Retry-After".to_string(),
+                vec![MAINTENANCE_RETRY_AFTER.to_string()],
+            ),
+        ]),
+        body: page.to_string(),
+    }
+}
+
+/// `index.html` followed by the site's extra index files
+///
+/// Names must be plain file names; anything that could point outside the
+/// requested directory is dropped.
+fn index_names(extra: &[String]) -> Vec<String> {
+    let mut names = vec![DEFAULT_INDEX.to_string()];
+    for name in extra {
+        let plain =
+            !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0']);
+        if !plain {
+            tracing::warn!(index_name = %name, "Ignoring invalid index file name");
+        } else if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+/// Cache-Control handlers for hashed assets and HTML
+///
+/// Both are applied to the response, so error pages never get cached as
+/// immutable: hashed assets only on success, HTML by its content type.
+fn cache_headers(immutable_paths: &[String]) -> [CaddyHandler; 2] {
+    let cache_control =
+        |value: &str| HashMap::from([("Cache-Control".to_string(), vec![value.to_string()])]);
+
+    let immutable = CaddyHandler::Headers {
+        response: CaddyHeaderOps {
+            set: cache_control(IMMUTABLE_CACHE_CONTROL),
+            require: Some(CaddyResponseMatcher {
+                status_code: vec![2],
+                ..Default::default()
+            }),
+        },
+    };
+
+    [
+        CaddyHandler::Subroute {
+            routes: vec![CaddySubroute {
+                match_rules: vec![CaddyMatch {
+                    path: immutable_paths.to_vec(),
+                    ..Default::default()
+                }],
+                handle: vec![immutable],
+                terminal: false,
+            }],
+        },
+        CaddyHandler::Headers {
+            response: CaddyHeaderOps {
+                set: cache_control("no-cache"),
+                require: Some(CaddyResponseMatcher {
+                    headers: HashMap::from([(
+                        "Content-Type".to_string(),
+                        vec!["text/html*".to_string()],
+                    )]),
+                    ..Default::default()
+                }),
+            },
+        },
+    ]
+}
+
+/// Split traffic between the stable and canary releases
+///
+/// Returning visitors follow their release cookie. New visitors are assigned
+/// by the first byte of Caddy's random per-request UUID and get the cookie set.
+fn canary_subroute(
+    stable_dir: &Path,
+    options: &RouteOptions,
+    canary: &CanarySplit,
+) -> CaddyHandler {
+    let releases = [
+        (
+            "canary",
+            file_server(&canary.dir, canary.precompressed, options),
+        ),
+        (
+            "stable",
+            file_server(stable_dir, options.precompressed, options),
+        ),
+    ];
+
+    let mut routes = Vec::new();
+    for (release, server) in &releases {
+        let pattern = format!(r"(^|;\s*){}={}(;|$)", CANARY_COOKIE, release);
+        routes.push(CaddySubroute {
+            match_rules: vec![CaddyMatch {
+                header_regexp: Some(HashMap::from([(
+                    "Cookie".to_string(),
+                    CaddyRegexp { pattern },
+                )])),
+                ..Default::default()
+            }],
+            handle: vec![server.clone()],
+            terminal: true,
+        });
+    }
+
+    for (release, server) in releases {
+        let match_rules = if release == "canary" {
+            vec![CaddyMatch {
+                vars_regexp: Some(HashMap::from([(
+                    "{http.request.uuid}".to_string(),
+                    CaddyRegexp {
+                        pattern: canary_uuid_pattern(canary.percent),
+                    },
+                )])),
+                ..Default::default()
+            }]
+        } else {
+            Vec::new()
+        };
+        let cookie = format!(
+            "{}={}; Path=/; Max-Age=86400; SameSite=Lax",
+            CANARY_COOKIE, release
+        );
+
+        routes.push(CaddySubroute {
+            match_rules,
+            handle: vec![
+                CaddyHandler::Headers {
+                    response: CaddyHeaderOps {
+                        set: HashMap::from([("Set-Cookie".to_string(), vec![cookie])]),
+                        require: None,
+                    },
+                },
+                server,
+            ],
+            terminal: true,
+        });
+    }
+
+    CaddyHandler::Subroute { routes }
+}
+
+/// Regex matching a UUID's leading hex byte for `percent`% of UUIDs
+///
+/// The byte is uniform over 0-255, so the split is accurate to 1/256.
+fn canary_uuid_pattern(percent: u8) -> String {
+    const HEX: &str = "0123456789abcdef";
+
+    let threshold = (u32::from(percent.min(100)) * 256 + 50) / 100;
+    let threshold = threshold.clamp(1, 255) as usize;
+    let (high, low) = (threshold / 16, threshold % 16);
+
+    let mut alternatives = Vec::new();
+    if high > 0 {
+        alternatives.push(format!("[{}][0-9a-f]", &HEX[..high]));
+    }
+    if low > 0 {
+        alternatives.push(format!("{}[{}]", &HEX[high..=high], &HEX[..low]));
+    }
+
+    format!("^(?:{})", alternatives.join("|"))
+}
+
 /// Find the index of a catch-all route (one without match rules)
 /// Returns None if no catch-all is found (append to end)
 async fn find_catch_all_index(
@@ -132,6 +649,33 @@ async fn find_catch_all_index(
     Ok(None)
 }
 
+/// Replace the route with the same `@id`, if one exists
+///
+/// PATCH on Caddy's /id/ endpoint swaps the object atomically and answers 404
+/// for an unknown ID. Returns whether a route was replaced.
+async fn replace_caddy_route(
+    http_client: &reqwest::Client,
+    caddy_admin_api: &str,
+    route: &CaddyRoute,
+) -> Result<bool> {
+    let url = format!("{}/id/{}", caddy_admin_api, route.id);
+
+    let response = http_client
+        .patch(&url)
+        .json(route)
+        .send()
+        .await
+        .context("Failed to update Caddy route")?;
+
+    if response.status().is_success() {
+        Ok(true)
+    } else if response.status() == reqwest::StatusCode::NOT_FOUND {
+        Ok(false)
+    } else {
+        Err(CaddyApiError::from_response(response).await.into())
+    }
+}
+
 /// Add a route to Caddy, inserting before catch-all if one exists
 ///
 /// Uses PUT to insert at a specific index (Caddy API: PUT to /routes/N inserts at N)
@@ -164,9 +708,7 @@ async fn add_caddy_route(
         .context("Failed to add Caddy route")?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        anyhow::bail!("Caddy API error {}: {}", status, body);
+        return Err(CaddyApiError::from_response(response).await.into());
     }
 
     tracing::info!(
@@ -201,9 +743,75 @@ pub async fn remove_caddy_route(
         tracing::info!(site_id = site_id, "Removed Caddy route");
         Ok(())
     } else {
+        Err(CaddyApiError::from_response(response).await.into())
+    }
+}
+
+/// How a Caddy admin API error should be handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaddyErrorKind {
+    /// Caddy is overloaded, restarting or raced another config change
+    Transient,
+    /// The config path doesn't exist, e.g. the `main` server isn't configured
+    MissingPath,
+    /// Caddy rejected the request or config; retrying won't help
+    Rejected,
+}
+
+/// Error response from Caddy's admin API
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Caddy API error {status}: {message}")]
+pub struct CaddyApiError {
+    pub status: reqwest::StatusCode,
+    /// The `error` field of Caddy's JSON body, or the raw body
+    pub message: String,
+}
+
+/// Caddy's JSON error body: `{"error": "..."}`
+#[derive(Deserialize)]
+struct CaddyErrorBody {
+    error: String,
+}
+
+impl CaddyApiError {
+    async fn from_response(response: reqwest::Response) -> Self {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
-        anyhow::bail!("Caddy API error {}: {}", status, body)
+        Self::parse(status, &body)
+    }
+
+    fn parse(status: reqwest::StatusCode, body: &str) -> Self {
+        let message = match serde_json::from_str::<CaddyErrorBody>(body) {
+            Ok(parsed) => parsed.error,
+            Err(_) => body.trim().to_string(),
+        };
+        Self { status, message }
+    }
+
+    pub fn kind(&self) -> CaddyErrorKind {
+        use reqwest::StatusCode;
+
+        // Caddy answers 400 for paths into config that doesn't exist
+        let missing = ["invalid traversal path", "unknown object id", "not found"];
+        let message = self.message.to_lowercase();
+
+        match self.status {
+            StatusCode::REQUEST_TIMEOUT
+            | StatusCode::CONFLICT
+            | StatusCode::PRECONDITION_FAILED
+            | StatusCode::TOO_MANY_REQUESTS => CaddyErrorKind::Transient,
+            status if status.is_server_error() => CaddyErrorKind::Transient,
+            StatusCode::NOT_FOUND => CaddyErrorKind::MissingPath,
+            _ if missing.iter().any(|pattern| message.contains(pattern)) => {
+                CaddyErrorKind::MissingPath
+            }
+            _ => CaddyErrorKind::Rejected,
+        }
+    }
+
+    /// Whether retrying the same request may succeed
+    pub fn is_transient(&self) -> bool {
+        self.kind() == CaddyErrorKind::Transient
     }
 }
 
@@ -216,27 +824,148 @@ struct CaddyRoute {
     match_rules: Vec<CaddyMatch>,
     handle: Vec<CaddyHandler>,
     terminal: bool,
+    /// Further route fields from the site's `caddy_extra`
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Caddy match rules
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct CaddyMatch {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     host: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    path: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    header_regexp: Option<HashMap<String, CaddyRegexp>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vars_regexp: Option<HashMap<String, CaddyRegexp>>,
+}
+
+/// Caddy regular expression matcher
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CaddyRegexp {
+    pattern: String,
+}
+
+/// Route nested in a subroute handler (no `@id`, optional match)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CaddySubroute {
+    #[serde(rename = "match", default, skip_serializing_if = "Vec::is_empty")]
+    match_rules: Vec<CaddyMatch>,
+    handle: Vec<CaddyHandler>,
+    terminal: bool,
 }
 
 /// Caddy handlers
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "handler", rename_all = "snake_case")]
 enum CaddyHandler {
     FileServer {
         root: String,
         index_names: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        precompressed: Option<CaddyPrecompressed>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        precompressed_order: Option<Vec<String>>,
+        /// Present only when listing is enabled; absent means disabled
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        browse: Option<CaddyBrowse>,
+        /// File names that are never served or listed
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        hide: Vec<String>,
+    },
+    Authentication {
+        providers: CaddyAuthProviders,
+    },
+    Subroute {
+        routes: Vec<CaddySubroute>,
+    },
+    Headers {
+        response: CaddyHeaderOps,
     },
+    StaticResponse {
+        status_code: u16,
+        headers: HashMap<String, Vec<String>>,
+        body: String,
+    },
+    /// Handler from a site's `caddy_extra`, passed through as is
+    #[serde(untagged)]
+    Custom(serde_json::Value),
+}
+
+/// Caddy header operations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CaddyHeaderOps {
+    set: HashMap<String, Vec<String>>,
+    /// Only apply to responses matching this (defers the operations)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    require: Option<CaddyResponseMatcher>,
+}
+
+/// Caddy response matcher; status codes of one digit match a whole class
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CaddyResponseMatcher {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    status_code: Vec<u16>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    headers: HashMap<String, Vec<String>>,
+}
+
+/// Caddy directory listing with its default template
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CaddyBrowse {}
+
+/// Caddy precompressed encodings for the file server (modules take no options)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CaddyPrecompressed {
+    br: serde_json::Map<String, serde_json::Value>,
+    gzip: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Caddy authentication providers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CaddyAuthProviders {
+    http_basic: CaddyHttpBasic,
+}
+
+/// Caddy HTTP basic auth provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CaddyHttpBasic {
+    accounts: Vec<CaddyAccount>,
+    hash: CaddyHash,
+}
+
+/// Caddy basic auth account (password is the bcrypt hash)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CaddyAccount {
+    username: String,
+    password: String,
+}
+
+/// Caddy password hash algorithm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CaddyHash {
+    algorithm: String,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn configure_test_route(caddy: &MockServer) -> Result<()> {
+        configure_caddy_route(
+            &reqwest::Client::new(),
+            &caddy.uri(),
+            "nullislabs-website",
+            Path::new("/var/www/sites/nullislabs-website"),
+            "nullislabs.io",
+            &RouteOptions::default(),
+        )
+        .await
+    }
 
     #[test]
     fn test_caddy_route_serialization() {
@@ -244,12 +973,18 @@ mod tests {
             id: "test-site".to_string(),
             match_rules: vec![CaddyMatch {
                 host: vec!["pr-42-website.example.com".to_string()],
+                ..Default::default()
             }],
             handle: vec![CaddyHandler::FileServer {
                 root: "/var/www/sites/test-site".to_string(),
                 index_names: vec!["index.html".to_string()],
+                precompressed: None,
+                precompressed_order: None,
+                browse: None,
+                hide: vec![],
             }],
             terminal: true,
+            extra: serde_json::Map::new(),
         };
 
         let json = serde_json::to_string_pretty(&route).unwrap();
@@ -257,4 +992,810 @@ mod tests {
         assert!(json.contains("pr-42-website.example.com"));
         assert!(json.contains("file_server"));
     }
+
+    #[test]
+    fn test_basic_auth_route_serialization() {
+        let auth = BasicAuth {
+            username: "preview".to_string(),
+            bcrypt_hash: "$2b$12$abcdefghijklmnopqrstuv".to_string(),
+        };
+
+        let route = build_route(
+            "test-site-pr-42",
+            Path::new("/var/www/sites/test-site-pr-42"),
+            "pr-42-website.example.com",
+            &RouteOptions {
+                basic_auth: Some(auth.clone()),
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_value(&route).unwrap();
+
+        // Authentication must run before the file server in the same terminal route
+        assert_eq!(json["terminal"], true);
+        assert_eq!(json["handle"][0]["handler"], "authentication");
+        assert_eq!(json["handle"][1]["handler"], "file_server");
+
+        let basic = &json["handle"][0]["providers"]["http_basic"];
+        assert_eq!(basic["hash"]["algorithm"], "bcrypt");
+        assert_eq!(basic["accounts"][0]["username"], "preview");
+        assert_eq!(basic["accounts"][0]["password"], auth.bcrypt_hash);
+    }
+
+    #[test]
+    fn test_route_without_basic_auth() {
+        let route = build_route(
+            "test-site",
+            Path::new("/var/www/sites/test-site"),
+            "website.example.com",
+            &RouteOptions::default(),
+        );
+        let json = serde_json::to_value(&route).unwrap();
+
+        assert_eq!(json["handle"].as_array().unwrap().len(), 1);
+        assert_eq!(json["handle"][0]["handler"], "file_server");
+        assert!(json["handle"][0].get("precompressed").is_none());
+        assert_eq!(
+            json["handle"][0]["index_names"],
+            serde_json::json!(["index.html"])
+        );
+        // Caddy lists directories only when `browse` is present
+        assert!(json["handle"][0].get("browse").is_none());
+    }
+
+    #[test]
+    fn test_file_server_hides_site_metadata() {
+        let route = build_route(
+            "nullislabs-docs-main",
+            Path::new("/var/www/sites/nullislabs-docs-main"),
+            "docs.nullislabs.io",
+            &RouteOptions {
+                browse: true,
+                canary: Some(CanarySplit {
+                    dir: PathBuf::from("/var/www/sites/nullislabs-docs-main.canary"),
+                    percent: 10,
+                    precompressed: false,
+                }),
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_value(&route).unwrap();
+
+        let routes = json["handle"][0]["routes"].as_array().unwrap();
+        for route in routes {
+            let server = route["handle"].as_array().unwrap().last().unwrap();
+            assert_eq!(server["handler"], "file_server");
+            assert_eq!(server["hide"], serde_json::json!([".catapult.json"]));
+        }
+    }
+
+    #[test]
+    fn test_file_server_index_names_and_browse() {
+        let route = build_route(
+            "nullislabs-docs-main",
+            Path::new("/var/www/sites/nullislabs-docs-main"),
+            "docs.nullislabs.io",
+            &RouteOptions {
+                index_names: vec![
+                    "index.htm".to_string(),
+                    "index.html".to_string(),
+                    "../secret".to_string(),
+                    "default.html".to_string(),
+                ],
+                browse: true,
+                canary: Some(CanarySplit {
+                    dir: PathBuf::from("/var/www/sites/nullislabs-docs-main.canary"),
+                    percent: 10,
+                    precompressed: false,
+                }),
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_value(&route).unwrap();
+
+        // Both sides of the canary split share the site's index and browse policy
+        let routes = json["handle"][0]["routes"].as_array().unwrap();
+        let servers: Vec<_> = routes
+            .iter()
+            .map(|route| route["handle"].as_array().unwrap().last().unwrap())
+            .filter(|handler| handler["handler"] == "file_server")
+            .collect();
+        assert!(!servers.is_empty());
+        for server in servers {
+            assert_eq!(
+                server["index_names"],
+                serde_json::json!(["index.html", "index.htm", "default.html"])
+            );
+            assert_eq!(server["browse"], serde_json::json!({}));
+        }
+    }
+
+    #[test]
+    fn test_route_matches_aliases() {
+        let route = build_route(
+            "nullislabs-website-main",
+            Path::new("/var/www/sites/nullislabs-website-main"),
+            "www.nullislabs.io",
+            &RouteOptions {
+                aliases: vec![
+                    "nullislabs.io".to_string(),
+                    "www.nullislabs.io".to_string(),
+                    "nullislabs.com".to_string(),
+                ],
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_value(&route).unwrap();
+
+        // One route for every host, with the domain first and no duplicates
+        assert_eq!(
+            json["match"],
+            serde_json::json!([{"host": ["www.nullislabs.io", "nullislabs.io", "nullislabs.com"]}])
+        );
+        assert_eq!(json["handle"][0]["handler"], "file_server");
+    }
+
+    #[test]
+    fn test_precompressed_route_serialization() {
+        let route = build_route(
+            "test-site",
+            Path::new("/var/www/sites/test-site"),
+            "website.example.com",
+            &RouteOptions {
+                precompressed: true,
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_value(&route).unwrap();
+
+        let file_server = &json["handle"][0];
+        assert_eq!(file_server["handler"], "file_server");
+        assert!(file_server["precompressed"]["br"].is_object());
+        assert!(file_server["precompressed"]["gzip"].is_object());
+        assert_eq!(
+            file_server["precompressed_order"],
+            serde_json::json!(["br", "gzip"])
+        );
+    }
+
+    #[test]
+    fn test_cache_headers_route_serialization() {
+        let route = build_route(
+            "test-site",
+            Path::new("/var/www/sites/test-site"),
+            "website.example.com",
+            &RouteOptions {
+                basic_auth: Some(BasicAuth {
+                    username: "preview".to_string(),
+                    bcrypt_hash: "$2b$12$abcdefghijklmnopqrstuv".to_string(),
+                }),
+                immutable_paths: vec!["/_app/immutable/*".to_string()],
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_value(&route).unwrap();
+
+        // Authentication first, then the header handlers, then the files
+        let handlers: Vec<_> = json["handle"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|handler| handler["handler"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            handlers,
+            ["authentication", "subroute", "headers", "file_server"]
+        );
+
+        assert_eq!(
+            json["handle"][1],
+            serde_json::json!({
+                "handler": "subroute",
+                "routes": [{
+                    "match": [{"path": ["/_app/immutable/*"]}],
+                    "handle": [{
+                        "handler": "headers",
+                        "response": {
+                            "set": {"Cache-Control": ["public, max-age=31536000, immutable"]},
+                            "require": {"status_code": [2]}
+                        }
+                    }],
+                    "terminal": false
+                }]
+            })
+        );
+        assert_eq!(
+            json["handle"][2],
+            serde_json::json!({
+                "handler": "headers",
+                "response": {
+                    "set": {"Cache-Control": ["no-cache"]},
+                    "require": {"headers": {"Content-Type": ["text/html*"]}}
+                }
+            })
+        );
+
+        // Without immutable paths caching is left to Caddy's defaults
+        let route = build_route(
+            "test-site",
+            Path::new("/var/www/sites/test-site"),
+            "website.example.com",
+            &RouteOptions::default(),
+        );
+        let json = serde_json::to_value(&route).unwrap();
+        assert!(!json.to_string().contains("Cache-Control"));
+    }
+
+    #[test]
+    fn test_canary_route_serialization() {
+        let route = build_route(
+            "nullislabs-website-main",
+            Path::new("/var/www/sites/nullislabs-website-main"),
+            "nullislabs.io",
+            &RouteOptions {
+                canary: Some(CanarySplit {
+                    dir: PathBuf::from("/var/www/sites/nullislabs-website-main.canary"),
+                    percent: 10,
+                    precompressed: true,
+                }),
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_value(&route).unwrap();
+
+        assert_eq!(json["@id"], "nullislabs-website-main");
+        assert_eq!(json["match"][0]["host"][0], "nullislabs.io");
+        assert_eq!(json["handle"][0]["handler"], "subroute");
+
+        let routes = json["handle"][0]["routes"].as_array().unwrap();
+        assert_eq!(routes.len(), 4);
+
+        // Returning visitors stay on the release their cookie names
+        let cookie = |i: usize| routes[i]["match"][0]["header_regexp"]["Cookie"]["pattern"].clone();
+        assert_eq!(cookie(0), r"(^|;\s*)catapult_release=canary(;|$)");
+        assert_eq!(cookie(1), r"(^|;\s*)catapult_release=stable(;|$)");
+        assert_eq!(
+            routes[0]["handle"][0]["root"],
+            "/var/www/sites/nullislabs-website-main.canary"
+        );
+        assert!(routes[0]["handle"][0]["precompressed"].is_object());
+        assert_eq!(
+            routes[1]["handle"][0]["root"],
+            "/var/www/sites/nullislabs-website-main"
+        );
+        assert!(routes[1]["handle"][0].get("precompressed").is_none());
+
+        // New visitors are split by the request UUID and pinned with a cookie
+        assert_eq!(
+            routes[2]["match"][0]["vars_regexp"]["{http.request.uuid}"]["pattern"],
+            canary_uuid_pattern(10)
+        );
+        assert_eq!(routes[2]["handle"][0]["handler"], "headers");
+        assert!(
+            routes[2]["handle"][0]["response"]["set"]["Set-Cookie"][0]
+                .as_str()
+                .unwrap()
+                .starts_with("catapult_release=canary;")
+        );
+        assert_eq!(
+            routes[2]["handle"][1]["root"],
+            "/var/www/sites/nullislabs-website-main.canary"
+        );
+
+        // Everyone else gets the stable release
+        assert!(routes[3].get("match").is_none());
+        assert!(
+            routes[3]["handle"][0]["response"]["set"]["Set-Cookie"][0]
+                .as_str()
+                .unwrap()
+                .starts_with("catapult_release=stable;")
+        );
+        assert_eq!(
+            routes[3]["handle"][1]["root"],
+            "/var/www/sites/nullislabs-website-main"
+        );
+        assert!(routes.iter().all(|route| route["terminal"] == true));
+    }
+
+    #[test]
+    fn test_canary_uuid_pattern() {
+        // Share of the 256 possible leading UUID bytes routed to the canary
+        let share = |percent: u8| {
+            let pattern = regex::Regex::new(&canary_uuid_pattern(percent)).unwrap();
+            (0..=255u8)
+                .filter(|byte| {
+                    pattern.is_match(&format!("{:02x}3e4567-e89b-42d3-a456-426614174000", byte))
+                })
+                .count()
+        };
+
+        assert_eq!(canary_uuid_pattern(10), "^(?:[0][0-9a-f]|1[0123456789])");
+        assert_eq!(canary_uuid_pattern(50), "^(?:[01234567][0-9a-f])");
+        assert_eq!(share(10), 26);
+        assert_eq!(share(25), 64);
+        assert_eq!(share(50), 128);
+        assert_eq!(share(1), 3);
+        assert_eq!(share(99), 253);
+    }
+
+    #[test]
+    fn test_caddy_extra_handlers_before_content() {
+        let mut route = build_route(
+            "nullislabs-website-pr-42",
+            Path::new("/var/www/sites/nullislabs-website-pr-42"),
+            "pr-42.nullislabs.io",
+            &RouteOptions {
+                basic_auth: Some(BasicAuth {
+                    username: "preview".to_string(),
+                    bcrypt_hash: "$2b$12$hash".to_string(),
+                }),
+                ..Default::default()
+            },
+        );
+        let extra = serde_json::json!([
+            {"handler": "rewrite", "uri": "/index.html"},
+        ]);
+        apply_caddy_extra(&mut route, &extra).unwrap();
+        let json = serde_json::to_value(&route).unwrap();
+
+        // Managed fields are untouched and auth still runs first
+        assert_eq!(json["@id"], "nullislabs-website-pr-42");
+        assert_eq!(
+            json["match"],
+            serde_json::json!([{"host": ["pr-42.nullislabs.io"]}])
+        );
+        assert_eq!(json["terminal"], true);
+        assert_eq!(json["handle"][0]["handler"], "authentication");
+        assert_eq!(
+            json["handle"][1],
+            serde_json::json!({"handler": "rewrite", "uri": "/index.html"})
+        );
+        assert_eq!(json["handle"][2]["handler"], "file_server");
+    }
+
+    #[test]
+    fn test_caddy_extra_route_fields() {
+        let mut route = build_route(
+            "nullislabs-website",
+            Path::new("/var/www/sites/nullislabs-website"),
+            "nullislabs.io",
+            &RouteOptions::default(),
+        );
+        let extra = serde_json::json!({
+            "group": "rate-limited",
+            "handle": [{"handler": "headers", "response": {"set": {"X-Frame-Options": ["DENY"]}}}],
+        });
+        apply_caddy_extra(&mut route, &extra).unwrap();
+        let json = serde_json::to_value(&route).unwrap();
+
+        assert_eq!(json["@id"], "nullislabs-website");
+        assert_eq!(json["group"], "rate-limited");
+        assert_eq!(
+            json["handle"][0]["response"]["set"]["X-Frame-Options"][0],
+            "DENY"
+        );
+        assert_eq!(json["handle"][1]["handler"], "file_server");
+    }
+
+    #[test]
+    fn test_caddy_extra_cannot_override_managed_fields() {
+        for extra in [
+            serde_json::json!({"@id": "other-site"}),
+            serde_json::json!({"match": [{"host": ["evil.example.com"]}]}),
+            serde_json::json!({"terminal": false}),
+            serde_json::json!([{"handler": "subroute", "routes": [{"@id": "other-site"}]}]),
+            serde_json::json!([{"uri": "/index.html"}]),
+            serde_json::json!({"handle": {"handler": "rewrite"}}),
+            serde_json::json!("rewrite"),
+        ] {
+            assert!(validate_caddy_extra(&extra).is_err(), "{}", extra);
+        }
+
+        assert!(validate_caddy_extra(&serde_json::json!([])).is_ok());
+    }
+
+    #[test]
+    fn test_caddy_extra_rejects_serving_other_content() {
+        for extra in [
+            serde_json::json!([{"handler": "file_server", "root": "/etc"}]),
+            serde_json::json!([{"handler": "file_server"}]),
+            serde_json::json!([{"handler": "reverse_proxy", "upstreams": [{"dial": "localhost:2019"}]}]),
+            serde_json::json!({"handle": [{"handler": "reverse_proxy"}]}),
+            serde_json::json!([{"handler": "vars", "root": "/etc"}]),
+            serde_json::json!([{"handler": "rewrite", "uri": "/", "root": "/etc"}]),
+        ] {
+            assert!(validate_caddy_extra(&extra).is_err(), "{}", extra);
+        }
+    }
+
+    #[test]
+    fn test_caddy_extra_allows_listed_handlers() {
+        for handler in ALLOWED_EXTRA_HANDLERS {
+            let extra = serde_json::json!([{"handler": handler}]);
+            assert!(validate_caddy_extra(&extra).is_ok(), "{}", extra);
+        }
+        let extra = serde_json::json!({
+            "group": "rate-limited",
+            "handle": [
+                {"handler": "encode", "encodings": {"gzip": {}}},
+                {"handler": "static_response", "status_code": 404},
+            ],
+        });
+        assert!(validate_caddy_extra(&extra).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_configure_route_with_caddy_extra() {
+        let caddy = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/id/nullislabs-website"))
+            .and(body_partial_json(serde_json::json!({
+                "@id": "nullislabs-website",
+                "handle": [{"handler": "rewrite"}, {"handler": "file_server"}],
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+
+        configure_caddy_route(
+            &reqwest::Client::new(),
+            &caddy.uri(),
+            "nullislabs-website",
+            Path::new("/var/www/sites/nullislabs-website"),
+            "nullislabs.io",
+            &RouteOptions {
+                caddy_extra: Some(serde_json::json!([{"handler": "rewrite", "uri": "/"}])),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        // An invalid snippet is refused without touching Caddy
+        let err = configure_caddy_route(
+            &reqwest::Client::new(),
+            &caddy.uri(),
+            "nullislabs-website",
+            Path::new("/var/www/sites/nullislabs-website"),
+            "nullislabs.io",
+            &RouteOptions {
+                caddy_extra: Some(serde_json::json!({"@id": "other-site"})),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("@id"), "{:#}", err);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_replaces_existing_route() {
+        let caddy = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/id/nullislabs-website"))
+            .and(body_partial_json(serde_json::json!({
+                "@id": "nullislabs-website",
+                "match": [{"host": ["nullislabs.io"]}],
+                "handle": [{"root": "/var/www/sites/nullislabs-website"}],
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+
+        configure_test_route(&caddy).await.unwrap();
+
+        // The route was never removed or appended a second time
+        let requests = caddy.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_inserts_missing_route_before_catch_all() {
+        let caddy = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/id/nullislabs-website"))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_string(r#"{"error":"unknown object ID 'nullislabs-website'"}"#),
+            )
+            .expect(1)
+            .mount(&caddy)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {"@id": "other", "match": [{"host": ["other.io"]}]},
+                {"handle": [{"handler": "static_response"}]},
+            ])))
+            .mount(&caddy)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/config/apps/http/servers/main/routes/1"))
+            .and(body_partial_json(
+                serde_json::json!({"@id": "nullislabs-website"}),
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+
+        configure_test_route(&caddy).await.unwrap();
+
+        let requests = caddy.received_requests().await.unwrap();
+        assert!(requests.iter().all(|r| r.method.as_str() != "DELETE"));
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_page_then_new_release() {
+        let caddy = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/id/nullislabs-website"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&caddy)
+            .await;
+
+        let options = RouteOptions {
+            aliases: vec!["www.nullislabs.io".to_string()],
+            ..Default::default()
+        };
+        let shown = show_maintenance_page(
+            &reqwest::Client::new(),
+            &caddy.uri(),
+            "nullislabs-website",
+            "nullislabs.io",
+            "<h1>Back shortly</h1>",
+            &options,
+        )
+        .await
+        .unwrap();
+        assert!(shown);
+        configure_test_route(&caddy).await.unwrap();
+
+        // The route answers with the page first, then serves the new release
+        let requests = caddy.received_requests().await.unwrap();
+        let bodies: Vec<serde_json::Value> = requests
+            .iter()
+            .map(|request| request.body_json().unwrap())
+            .collect();
+        assert_eq!(bodies.len(), 2);
+
+        let maintenance = &bodies[0]["handle"][0];
+        assert_eq!(maintenance["handler"], "static_response");
+        assert_eq!(maintenance["status_code"], 503);
+        assert_eq!(maintenance["headers"]["Retry-After"][0], "30");
+        assert_eq!(maintenance["body"], "<h1>Back shortly</h1>");
+        assert_eq!(
+            bodies[0]["match"][0]["host"],
+            serde_json::json!(["nullislabs.io", "www.nullislabs.io"])
+        );
+
+        let release = &bodies[1]["handle"][0];
+        assert_eq!(release["handler"], "file_server");
+        assert_eq!(release["root"], "/var/www/sites/nullislabs-website");
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_page_keeps_basic_auth() {
+        let caddy = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/id/nullislabs-website"))
+            .and(body_partial_json(serde_json::json!({
+                "handle": [
+                    {"handler": "authentication"},
+                    {"handler": "static_response"},
+                ],
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+
+        let options = RouteOptions {
+            basic_auth: Some(BasicAuth {
+                username: "preview".to_string(),
+                bcrypt_hash: "$2b$12$hash".to_string(),
+            }),
+            ..Default::default()
+        };
+        show_maintenance_page(
+            &reqwest::Client::new(),
+            &caddy.uri(),
+            "nullislabs-website",
+            "nullislabs.io",
+            "Back shortly",
+            &options,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_page_skipped_without_route() {
+        let caddy = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/id/nullislabs-website"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+
+        let shown = show_maintenance_page(
+            &reqwest::Client::new(),
+            &caddy.uri(),
+            "nullislabs-website",
+            "nullislabs.io",
+            "Back shortly",
+            &RouteOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        // A first deploy has no visitors to show the page to, and no route is added
+        assert!(!shown);
+    }
+
+    #[test]
+    fn test_caddy_error_parsing() {
+        use reqwest::StatusCode;
+
+        let cases = [
+            (
+                StatusCode::BAD_REQUEST,
+                r#"{"error":"invalid traversal path at: config/apps/http/servers/main"}"#,
+                "invalid traversal path at: config/apps/http/servers/main",
+                CaddyErrorKind::MissingPath,
+            ),
+            (
+                StatusCode::NOT_FOUND,
+                r#"{"error":"unknown object ID 'nullislabs-website'"}"#,
+                "unknown object ID 'nullislabs-website'",
+                CaddyErrorKind::MissingPath,
+            ),
+            (
+                StatusCode::BAD_REQUEST,
+                r#"{"error":"loading config: loading new config: http app module: start: listening on :443: address already in use"}"#,
+                "loading config: loading new config: http app module: start: listening on :443: address already in use",
+                CaddyErrorKind::Rejected,
+            ),
+            (
+                StatusCode::PRECONDITION_FAILED,
+                r#"{"error":"If-Match header did not match current config hash"}"#,
+                "If-Match header did not match current config hash",
+                CaddyErrorKind::Transient,
+            ),
+            (
+                StatusCode::BAD_GATEWAY,
+                "upstream unavailable\n",
+                "upstream unavailable",
+                CaddyErrorKind::Transient,
+            ),
+        ];
+
+        for (status, body, message, kind) in cases {
+            let error = CaddyApiError::parse(status, body);
+            assert_eq!(error.message, message);
+            assert_eq!(error.kind(), kind, "{}", body);
+            assert_eq!(error.is_transient(), kind == CaddyErrorKind::Transient);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upsert_surfaces_caddy_errors() {
+        let caddy = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("config load failed"))
+            .mount(&caddy)
+            .await;
+
+        // A failed update must not fall through to appending a duplicate
+        let err = configure_test_route(&caddy).await.unwrap_err();
+        assert!(err.to_string().contains("config load failed"));
+        let caddy_error = err.downcast_ref::<CaddyApiError>().unwrap();
+        assert!(caddy_error.is_transient());
+        assert_eq!(caddy.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_caddy_ready_once_admin_api_starts() {
+        // Reserve a port, then leave it closed so early checks are refused
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let admin_api = format!("http://127.0.0.1:{}", port);
+
+        let caddy = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(1200)).await;
+            let listener = std::net::TcpListener::bind(("127.0.0.1", port)).unwrap();
+            let server = MockServer::builder().listener(listener).start().await;
+            Mock::given(method("GET"))
+                .and(path("/config/"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+                .mount(&server)
+                .await;
+            server
+        });
+
+        let start = std::time::Instant::now();
+        wait_for_caddy_ready(&reqwest::Client::new(), &admin_api)
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(1200));
+
+        let caddy = caddy.await.unwrap();
+        assert!(!caddy.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_site_ready_after_retries() {
+        let site = MockServer::start().await;
+        // Bad gateway for the first three checks, then served
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(502))
+            .up_to_n_times(3)
+            .with_priority(1)
+            .mount(&site)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&site)
+            .await;
+
+        poll_until_ready(
+            &reqwest::Client::new(),
+            &site.uri(),
+            |status| status.is_success(),
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+            Duration::from_millis(40),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(site.received_requests().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_site_never_ready() {
+        let site = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&site)
+            .await;
+
+        let err = poll_until_ready(
+            &reqwest::Client::new(),
+            &site.uri(),
+            |status| status.is_success(),
+            Duration::from_millis(200),
+            Duration::from_millis(10),
+            Duration::from_millis(40),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("502"));
+    }
+
+    #[tokio::test]
+    async fn test_protected_site_ready_on_unauthorized() {
+        let site = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&site)
+            .await;
+
+        let client = reqwest::Client::new();
+        wait_for_site_ready(&client, &site.uri(), true, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(
+            wait_for_site_ready(&client, &site.uri(), false, Duration::from_millis(100))
+                .await
+                .is_err()
+        );
+    }
 }
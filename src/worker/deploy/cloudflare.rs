@@ -1,92 +1,262 @@
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::shared::CachePurge;
+
 /// Cloudflare integration configuration
 #[derive(Debug, Clone)]
 pub struct CloudflareConfig {
     /// Cloudflare API token with DNS and Tunnel edit permissions
     pub api_token: String,
-    /// Cloudflare Account ID (for tunnel management)
+    /// Cloudflare Account ID (for zone lookup and tunnel management)
     pub account_id: String,
-    /// Tunnel ID
-    pub tunnel_id: String,
-    /// Local service URL that the tunnel routes to (e.g., "http://localhost:8080")
-    pub service_url: String,
+    /// How deployed hostnames reach this worker
+    pub mode: DnsMode,
+}
+
+/// How DNS records route deployed hostnames to the worker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsMode {
+    /// CNAME to a Cloudflare Tunnel plus an ingress rule for the hostname
+    Tunnel {
+        /// Tunnel ID
+        tunnel_id: String,
+        /// Local service URL that the tunnel routes to (e.g., "http://localhost:8080")
+        service_url: String,
+    },
+    /// A/AAAA records pointing straight at the worker; no tunnel involved
+    Direct {
+        /// Public addresses of the worker
+        origin_ips: Vec<IpAddr>,
+    },
 }
 
+/// Cloudflare API v4 base URL
+const CLOUDFLARE_API: &str = "https://api.cloudflare.com/client/v4";
+
+/// DNS record types managed for deployed hostnames
+const MANAGED_RECORD_TYPES: &[&str] = &["CNAME", "A", "AAAA"];
+
+/// Retries of a rate-limited cache purge before giving up
+const PURGE_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Longest `Retry-After` worth waiting out for a purge
+const MAX_PURGE_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Wait before retrying a rate-limited purge without `Retry-After`
+const DEFAULT_PURGE_RATE_LIMIT_WAIT: Duration = Duration::from_secs(5);
+
 /// Cloudflare client for managing deployment DNS records and tunnel routes
 ///
 /// This manages both:
-/// 1. DNS records (CNAME pointing to tunnel)
-/// 2. Tunnel ingress rules (hostname → local service)
+/// 1. DNS records (CNAME to the tunnel, or A/AAAA to the origin in direct mode)
+/// 2. Tunnel ingress rules (hostname → local service), in tunnel mode only
 ///
 /// Zone IDs are dynamically looked up based on the domain being deployed.
 #[derive(Clone)]
 pub struct CloudflareClient {
     http_client: reqwest::Client,
+    /// API base URL (overridden in tests)
+    api_base: String,
+    /// Default tunnel, used when no environment-specific tunnel matches
     config: Option<CloudflareConfig>,
+    /// Tunnels for specific environments (e.g. staging vs production)
+    tunnels: HashMap<String, CloudflareConfig>,
     /// Cache of domain -> zone_id mappings
     zone_cache: Arc<RwLock<HashMap<String, String>>>,
 }
 
 impl CloudflareClient {
     /// Create a new Cloudflare client (enabled)
-    pub fn new(config: CloudflareConfig) -> Self {
+    #[cfg(test)]
+    pub fn new(http_client: reqwest::Client, config: CloudflareConfig) -> Self {
+        Self::with_tunnels(http_client, Some(config), HashMap::new())
+    }
+
+    /// Create a client with a default tunnel and per-environment tunnels
+    ///
+    /// The client is enabled if either is configured.
+    pub fn with_tunnels(
+        http_client: reqwest::Client,
+        default: Option<CloudflareConfig>,
+        tunnels: HashMap<String, CloudflareConfig>,
+    ) -> Self {
         Self {
-            http_client: reqwest::Client::new(),
-            config: Some(config),
+            http_client,
+            api_base: CLOUDFLARE_API.to_string(),
+            config: default,
+            tunnels,
             zone_cache: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Send API requests to `api_base` instead of Cloudflare
+    #[cfg(test)]
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.to_string();
+        self
+    }
+
     /// Create a disabled Cloudflare client
     pub fn disabled() -> Self {
-        Self {
-            http_client: reqwest::Client::new(),
-            config: None,
-            zone_cache: Arc::new(RwLock::new(HashMap::new())),
-        }
+        // Never sends requests, so the client's timeouts don't matter
+        Self::with_tunnels(reqwest::Client::new(), None, HashMap::new())
     }
 
     /// Check if Cloudflare integration is enabled
     pub fn is_enabled(&self) -> bool {
-        self.config.is_some()
+        self.config.is_some() || !self.tunnels.is_empty()
+    }
+
+    /// Select the tunnel for an environment, falling back to the default tunnel
+    fn config_for(&self, environment: Option<&str>) -> Option<&CloudflareConfig> {
+        environment
+            .and_then(|env| self.tunnels.get(env))
+            .or(self.config.as_ref())
     }
 
     /// Ensure DNS record and tunnel ingress rule exist for a hostname
-    pub async fn ensure_route(&self, hostname: &str) -> Result<()> {
-        let config = match &self.config {
+    pub async fn ensure_route(&self, hostname: &str, environment: Option<&str>) -> Result<()> {
+        let config = match self.config_for(environment) {
             Some(c) => c,
-            None => return Ok(()),
+            None => {
+                tracing::debug!(
+                    hostname,
+                    environment,
+                    "No Cloudflare tunnel for environment"
+                );
+                return Ok(());
+            }
         };
 
         // Add tunnel ingress rule first (this routes traffic to local service)
-        self.ensure_tunnel_ingress(hostname, config).await?;
+        if let DnsMode::Tunnel {
+            tunnel_id,
+            service_url,
+        } = &config.mode
+        {
+            self.ensure_tunnel_ingress(hostname, config, tunnel_id, service_url)
+                .await?;
+        }
 
-        // Then create DNS record (this makes the hostname resolve to tunnel)
-        self.ensure_dns_record(hostname, config).await?;
+        // Then create DNS records (this makes the hostname resolve to the worker)
+        self.ensure_dns_records(hostname, config).await?;
 
         Ok(())
     }
 
     /// Remove DNS record and tunnel ingress rule for a hostname
-    pub async fn remove_route(&self, hostname: &str) -> Result<()> {
-        let config = match &self.config {
+    pub async fn remove_route(&self, hostname: &str, environment: Option<&str>) -> Result<()> {
+        let config = match self.config_for(environment) {
             Some(c) => c,
             None => return Ok(()),
         };
 
         // Remove DNS first, then tunnel ingress
-        self.remove_dns_record(hostname, config).await?;
-        self.remove_tunnel_ingress(hostname, config).await?;
+        self.remove_dns_records(hostname, config).await?;
+        if let DnsMode::Tunnel { tunnel_id, .. } = &config.mode {
+            self.remove_tunnel_ingress(hostname, config, tunnel_id)
+                .await?;
+        }
 
         Ok(())
     }
 
+    /// Purge Cloudflare's cache for freshly deployed hostnames
+    ///
+    /// Purges either just the hostnames or everything in their zones. Rate
+    /// limited purges are retried after the wait Cloudflare asks for.
+    pub async fn purge_cache(
+        &self,
+        hostnames: &[String],
+        scope: CachePurge,
+        environment: Option<&str>,
+    ) -> Result<()> {
+        let Some(config) = self.config_for(environment) else {
+            return Ok(());
+        };
+
+        // One request per zone, as purges are scoped to a zone
+        let mut zones: Vec<(String, Vec<String>)> = Vec::new();
+        for hostname in hostnames {
+            let zone_id = self.get_zone_id(hostname, config).await?;
+            match zones.iter_mut().find(|(id, _)| *id == zone_id) {
+                Some((_, hosts)) => hosts.push(hostname.clone()),
+                None => zones.push((zone_id, vec![hostname.clone()])),
+            }
+        }
+
+        for (zone_id, hosts) in zones {
+            let request = match scope {
+                CachePurge::Hostnames => PurgeCacheRequest::Hosts { hosts },
+                CachePurge::Zone => PurgeCacheRequest::Everything {
+                    purge_everything: true,
+                },
+            };
+            self.purge_zone_cache(&zone_id, &request, config).await?;
+            tracing::info!(zone_id = %zone_id, ?scope, "Purged Cloudflare cache");
+        }
+
+        Ok(())
+    }
+
+    async fn purge_zone_cache(
+        &self,
+        zone_id: &str,
+        request: &PurgeCacheRequest,
+        config: &CloudflareConfig,
+    ) -> Result<()> {
+        let url = format!("{}/zones/{}/purge_cache", self.api_base, zone_id);
+
+        let mut attempt = 0;
+        loop {
+            let response = self
+                .http_client
+                .post(&url)
+                .bearer_auth(&config.api_token)
+                .json(request)
+                .send()
+                .await
+                .context("Failed to purge cache")?;
+
+            if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                && attempt < PURGE_RATE_LIMIT_RETRIES
+            {
+                let wait = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse().ok())
+                    .map_or(DEFAULT_PURGE_RATE_LIMIT_WAIT, Duration::from_secs);
+                if wait <= MAX_PURGE_RATE_LIMIT_WAIT {
+                    attempt += 1;
+                    tracing::warn!(
+                        zone_id,
+                        wait_secs = wait.as_secs(),
+                        attempt,
+                        "Cloudflare cache purge rate limited, retrying"
+                    );
+                    tokio::time::sleep(wait).await;
+                    continue;
+                }
+            }
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                anyhow::bail!("Cloudflare Cache API error {}: {}", status, body);
+            }
+
+            return Ok(());
+        }
+    }
+
     // ==================== Zone ID Lookup ====================
 
     /// Extract the base domain from a hostname
@@ -137,8 +307,8 @@ impl CloudflareClient {
     /// Look up zone ID from Cloudflare API by domain name
     async fn lookup_zone_id(&self, domain: &str, config: &CloudflareConfig) -> Result<String> {
         let url = format!(
-            "https://api.cloudflare.com/client/v4/zones?name={}&account.id={}",
-            domain, config.account_id
+            "{}/zones?name={}&account.id={}",
+            self.api_base, domain, config.account_id
         );
 
         let response = self
@@ -170,30 +340,43 @@ impl CloudflareClient {
 
     // ==================== DNS Management ====================
 
-    async fn ensure_dns_record(&self, hostname: &str, config: &CloudflareConfig) -> Result<()> {
+    async fn ensure_dns_records(&self, hostname: &str, config: &CloudflareConfig) -> Result<()> {
         let zone_id = self.get_zone_id(hostname, config).await?;
-        let tunnel_target = format!("{}.cfargotunnel.com", config.tunnel_id);
 
-        let existing = self.get_dns_record(hostname, &zone_id, config).await?;
+        let existing = self.get_dns_records(hostname, &zone_id, config).await?;
+        let plan = plan_dns_changes(existing, desired_dns_records(hostname, &config.mode));
 
-        if let Some(record) = existing {
-            if record.content != tunnel_target {
-                self.update_dns_record(&record.id, hostname, &tunnel_target, &zone_id, config)
-                    .await?;
-                tracing::info!(hostname = hostname, zone_id = %zone_id, "Updated DNS record");
-            } else {
-                tracing::debug!(hostname = hostname, "DNS record already up to date");
-            }
-        } else {
-            self.create_dns_record(hostname, &tunnel_target, &zone_id, config)
+        if plan.is_empty() {
+            tracing::debug!(hostname = hostname, "DNS records already up to date");
+            return Ok(());
+        }
+
+        for (record_id, record) in &plan.update {
+            self.update_dns_record(record_id, record, &zone_id, config)
                 .await?;
-            tracing::info!(hostname = hostname, zone_id = %zone_id, "Created DNS record");
         }
+        // Stale records go before creating new ones: a CNAME can't coexist
+        // with A/AAAA records of the same name
+        for record_id in &plan.delete {
+            self.delete_dns_record(record_id, &zone_id, config).await?;
+        }
+        for record in &plan.create {
+            self.create_dns_record(record, &zone_id, config).await?;
+        }
+
+        tracing::info!(
+            hostname = hostname,
+            zone_id = %zone_id,
+            updated = plan.update.len(),
+            deleted = plan.delete.len(),
+            created = plan.create.len(),
+            "Updated DNS records"
+        );
 
         Ok(())
     }
 
-    async fn remove_dns_record(&self, hostname: &str, config: &CloudflareConfig) -> Result<()> {
+    async fn remove_dns_records(&self, hostname: &str, config: &CloudflareConfig) -> Result<()> {
         let zone_id = match self.get_zone_id(hostname, config).await {
             Ok(id) => id,
             Err(e) => {
@@ -202,43 +385,58 @@ impl CloudflareClient {
             }
         };
 
-        let existing = self.get_dns_record(hostname, &zone_id, config).await?;
+        let existing = self.get_dns_records(hostname, &zone_id, config).await?;
 
-        if let Some(record) = existing {
-            let url = format!(
-                "https://api.cloudflare.com/client/v4/zones/{}/dns_records/{}",
-                zone_id, record.id
+        for record in existing.iter().filter(|r| r.is_managed()) {
+            self.delete_dns_record(&record.id, &zone_id, config).await?;
+            tracing::info!(
+                hostname = hostname,
+                zone_id = %zone_id,
+                record_type = %record.record_type,
+                "Removed DNS record"
             );
+        }
 
-            let response = self
-                .http_client
-                .delete(&url)
-                .bearer_auth(&config.api_token)
-                .send()
-                .await
-                .context("Failed to delete DNS record")?;
+        Ok(())
+    }
 
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
-                anyhow::bail!("Cloudflare DNS API error {}: {}", status, body);
-            }
+    async fn delete_dns_record(
+        &self,
+        record_id: &str,
+        zone_id: &str,
+        config: &CloudflareConfig,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/zones/{}/dns_records/{}",
+            self.api_base, zone_id, record_id
+        );
 
-            tracing::info!(hostname = hostname, zone_id = %zone_id, "Removed DNS record");
+        let response = self
+            .http_client
+            .delete(&url)
+            .bearer_auth(&config.api_token)
+            .send()
+            .await
+            .context("Failed to delete DNS record")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Cloudflare DNS API error {}: {}", status, body);
         }
 
         Ok(())
     }
 
-    async fn get_dns_record(
+    async fn get_dns_records(
         &self,
         hostname: &str,
         zone_id: &str,
         config: &CloudflareConfig,
-    ) -> Result<Option<DnsRecord>> {
+    ) -> Result<Vec<DnsRecord>> {
         let url = format!(
-            "https://api.cloudflare.com/client/v4/zones/{}/dns_records?name={}",
-            zone_id, hostname
+            "{}/zones/{}/dns_records?name={}",
+            self.api_base, zone_id, hostname
         );
 
         let response = self
@@ -260,34 +458,22 @@ impl CloudflareClient {
             .await
             .context("Failed to parse Cloudflare response")?;
 
-        Ok(result.result.into_iter().next())
+        Ok(result.result)
     }
 
     async fn create_dns_record(
         &self,
-        hostname: &str,
-        target: &str,
+        record: &CreateDnsRecord,
         zone_id: &str,
         config: &CloudflareConfig,
     ) -> Result<()> {
-        let url = format!(
-            "https://api.cloudflare.com/client/v4/zones/{}/dns_records",
-            zone_id
-        );
-
-        let request = CreateDnsRecord {
-            record_type: "CNAME".to_string(),
-            name: hostname.to_string(),
-            content: target.to_string(),
-            proxied: true,
-            ttl: 1,
-        };
+        let url = format!("{}/zones/{}/dns_records", self.api_base, zone_id);
 
         let response = self
             .http_client
             .post(&url)
             .bearer_auth(&config.api_token)
-            .json(&request)
+            .json(record)
             .send()
             .await
             .context("Failed to create DNS record")?;
@@ -304,29 +490,20 @@ impl CloudflareClient {
     async fn update_dns_record(
         &self,
         record_id: &str,
-        hostname: &str,
-        target: &str,
+        record: &CreateDnsRecord,
         zone_id: &str,
         config: &CloudflareConfig,
     ) -> Result<()> {
         let url = format!(
-            "https://api.cloudflare.com/client/v4/zones/{}/dns_records/{}",
-            zone_id, record_id
+            "{}/zones/{}/dns_records/{}",
+            self.api_base, zone_id, record_id
         );
 
-        let request = CreateDnsRecord {
-            record_type: "CNAME".to_string(),
-            name: hostname.to_string(),
-            content: target.to_string(),
-            proxied: true,
-            ttl: 1,
-        };
-
         let response = self
             .http_client
             .put(&url)
             .bearer_auth(&config.api_token)
-            .json(&request)
+            .json(record)
             .send()
             .await
             .context("Failed to update DNS record")?;
@@ -342,80 +519,73 @@ impl CloudflareClient {
 
     // ==================== Tunnel Ingress Management ====================
 
-    async fn ensure_tunnel_ingress(&self, hostname: &str, config: &CloudflareConfig) -> Result<()> {
-        let mut tunnel_config = self.get_tunnel_config(config).await?;
+    async fn ensure_tunnel_ingress(
+        &self,
+        hostname: &str,
+        config: &CloudflareConfig,
+        tunnel_id: &str,
+        service_url: &str,
+    ) -> Result<()> {
+        let mut tunnel_config = self.get_tunnel_config(config, tunnel_id).await?;
+        let repaired = repair_catch_all(&mut tunnel_config.ingress, tunnel_id);
 
         // Check if hostname already exists in ingress rules
         let exists = tunnel_config
-            .config
             .ingress
             .iter()
             .any(|rule| rule.hostname.as_deref() == Some(hostname));
 
-        if exists {
+        if exists && !repaired {
             tracing::debug!(hostname = hostname, "Tunnel ingress rule already exists");
             return Ok(());
         }
 
-        // Create new ingress rule
-        let new_rule = TunnelIngressRule {
-            hostname: Some(hostname.to_string()),
-            service: config.service_url.clone(),
-            origin_request: None,
-        };
-
-        // Insert before the catch-all rule (which should be last)
-        // The catch-all has hostname: None
-        let insert_pos = tunnel_config
-            .config
-            .ingress
-            .iter()
-            .position(|r| r.hostname.is_none())
-            .unwrap_or(tunnel_config.config.ingress.len());
-
-        tunnel_config.config.ingress.insert(insert_pos, new_rule);
-
-        // Ensure there's a catch-all at the end
-        if !tunnel_config
-            .config
-            .ingress
-            .iter()
-            .any(|r| r.hostname.is_none())
-        {
-            tunnel_config.config.ingress.push(TunnelIngressRule {
-                hostname: None,
-                service: "http_status:404".to_string(),
-                origin_request: None,
-            });
+        if !exists {
+            // Insert before the catch-all rule, which is last
+            let insert_pos = tunnel_config.ingress.len() - 1;
+            tunnel_config.ingress.insert(
+                insert_pos,
+                TunnelIngressRule {
+                    hostname: Some(hostname.to_string()),
+                    service: service_url.to_string(),
+                    origin_request: None,
+                    other: serde_json::Map::new(),
+                },
+            );
         }
 
-        self.update_tunnel_config(config, &tunnel_config.config)
+        self.update_tunnel_config(config, tunnel_id, &tunnel_config)
             .await?;
 
         tracing::info!(
             hostname = hostname,
-            service = %config.service_url,
+            service = %service_url,
             "Added tunnel ingress rule"
         );
 
         Ok(())
     }
 
-    async fn remove_tunnel_ingress(&self, hostname: &str, config: &CloudflareConfig) -> Result<()> {
-        let mut tunnel_config = self.get_tunnel_config(config).await?;
+    async fn remove_tunnel_ingress(
+        &self,
+        hostname: &str,
+        config: &CloudflareConfig,
+        tunnel_id: &str,
+    ) -> Result<()> {
+        let mut tunnel_config = self.get_tunnel_config(config, tunnel_id).await?;
 
-        let original_len = tunnel_config.config.ingress.len();
+        let original_len = tunnel_config.ingress.len();
         tunnel_config
-            .config
             .ingress
             .retain(|rule| rule.hostname.as_deref() != Some(hostname));
 
-        if tunnel_config.config.ingress.len() == original_len {
+        if tunnel_config.ingress.len() == original_len {
             tracing::debug!(hostname = hostname, "Tunnel ingress rule not found");
             return Ok(());
         }
+        repair_catch_all(&mut tunnel_config.ingress, tunnel_id);
 
-        self.update_tunnel_config(config, &tunnel_config.config)
+        self.update_tunnel_config(config, tunnel_id, &tunnel_config)
             .await?;
 
         tracing::info!(hostname = hostname, "Removed tunnel ingress rule");
@@ -423,10 +593,14 @@ impl CloudflareClient {
         Ok(())
     }
 
-    async fn get_tunnel_config(&self, config: &CloudflareConfig) -> Result<TunnelConfigResponse> {
+    async fn get_tunnel_config(
+        &self,
+        config: &CloudflareConfig,
+        tunnel_id: &str,
+    ) -> Result<TunnelConfig> {
         let url = format!(
-            "https://api.cloudflare.com/client/v4/accounts/{}/cfd_tunnel/{}/configurations",
-            config.account_id, config.tunnel_id
+            "{}/accounts/{}/cfd_tunnel/{}/configurations",
+            self.api_base, config.account_id, tunnel_id
         );
 
         let response = self
@@ -443,24 +617,42 @@ impl CloudflareClient {
             anyhow::bail!("Cloudflare Tunnel API error {}: {}", status, body);
         }
 
-        let result: CloudflareResponse<TunnelConfigResponse> = response
-            .json()
+        let body = response
+            .bytes()
             .await
-            .context("Failed to parse tunnel config")?;
+            .context("Failed to read tunnel config")?;
+        let tunnel_config = parse_tunnel_config(&body)?;
 
-        Ok(result.result)
+        if tunnel_config.ingress.is_empty() {
+            tracing::warn!(
+                tunnel_id,
+                "Tunnel config has no ingress rules, starting a new list"
+            );
+        }
+
+        Ok(tunnel_config)
     }
 
     async fn update_tunnel_config(
         &self,
         config: &CloudflareConfig,
+        tunnel_id: &str,
         tunnel_config: &TunnelConfig,
     ) -> Result<()> {
         let url = format!(
-            "https://api.cloudflare.com/client/v4/accounts/{}/cfd_tunnel/{}/configurations",
-            config.account_id, config.tunnel_id
+            "{}/accounts/{}/cfd_tunnel/{}/configurations",
+            self.api_base, config.account_id, tunnel_id
         );
 
+        // Cloudflare routes unmatched requests with the last rule
+        if !tunnel_config
+            .ingress
+            .last()
+            .is_some_and(TunnelIngressRule::is_catch_all)
+        {
+            anyhow::bail!("Refusing to write tunnel config without a trailing catch-all rule");
+        }
+
         let request = TunnelConfigRequest {
             config: tunnel_config.clone(),
         };
@@ -505,10 +697,19 @@ struct Zone {
 #[derive(Debug, Deserialize)]
 struct DnsRecord {
     id: String,
+    #[serde(rename = "type")]
+    record_type: String,
     content: String,
 }
 
-#[derive(Debug, Serialize)]
+impl DnsRecord {
+    /// Whether this record type is managed for deployed hostnames
+    fn is_managed(&self) -> bool {
+        MANAGED_RECORD_TYPES.contains(&self.record_type.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 struct CreateDnsRecord {
     #[serde(rename = "type")]
     record_type: String,
@@ -518,18 +719,108 @@ struct CreateDnsRecord {
     ttl: u32,
 }
 
+/// DNS records a hostname should have in the given mode
+fn desired_dns_records(hostname: &str, mode: &DnsMode) -> Vec<CreateDnsRecord> {
+    let record = |record_type: &str, content: String| CreateDnsRecord {
+        record_type: record_type.to_string(),
+        name: hostname.to_string(),
+        content,
+        proxied: true,
+        ttl: 1,
+    };
+
+    match mode {
+        DnsMode::Tunnel { tunnel_id, .. } => {
+            vec![record("CNAME", format!("{}.cfargotunnel.com", tunnel_id))]
+        }
+        DnsMode::Direct { origin_ips } => origin_ips
+            .iter()
+            .map(|ip| match ip {
+                IpAddr::V4(_) => record("A", ip.to_string()),
+                IpAddr::V6(_) => record("AAAA", ip.to_string()),
+            })
+            .collect(),
+    }
+}
+
+/// Changes bringing a hostname's managed DNS records to the desired set
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DnsPlan {
+    /// Existing records rewritten in place (record ID, new contents)
+    update: Vec<(String, CreateDnsRecord)>,
+    /// Existing records no longer wanted
+    delete: Vec<String>,
+    /// Records to add
+    create: Vec<CreateDnsRecord>,
+}
+
+impl DnsPlan {
+    fn is_empty(&self) -> bool {
+        self.update.is_empty() && self.delete.is_empty() && self.create.is_empty()
+    }
+}
+
+/// Diff existing records against the desired ones
+///
+/// Matching records are kept, stale records of the same type are updated in
+/// place, and the rest are deleted or created. Unmanaged record types (e.g.
+/// TXT) are left alone.
+fn plan_dns_changes(existing: Vec<DnsRecord>, desired: Vec<CreateDnsRecord>) -> DnsPlan {
+    let mut stale: Vec<DnsRecord> = Vec::new();
+    let mut missing = desired;
+
+    for record in existing.into_iter().filter(DnsRecord::is_managed) {
+        match missing
+            .iter()
+            .position(|d| d.record_type == record.record_type && d.content == record.content)
+        {
+            Some(pos) => {
+                missing.remove(pos);
+            }
+            None => stale.push(record),
+        }
+    }
+
+    let mut plan = DnsPlan::default();
+    for record in missing {
+        match stale
+            .iter()
+            .position(|s| s.record_type == record.record_type)
+        {
+            Some(pos) => plan.update.push((stale.remove(pos).id, record)),
+            None => plan.create.push(record),
+        }
+    }
+    plan.delete = stale.into_iter().map(|record| record.id).collect();
+
+    plan
+}
+
+// --- Cache Types ---
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum PurgeCacheRequest {
+    Hosts { hosts: Vec<String> },
+    Everything { purge_everything: bool },
+}
+
 // --- Tunnel Types ---
 
 #[derive(Debug, Deserialize)]
 struct TunnelConfigResponse {
-    config: TunnelConfig,
+    /// Null for tunnels that were never configured remotely
+    #[serde(default)]
+    config: Option<TunnelConfig>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct TunnelConfig {
+    #[serde(default)]
     ingress: Vec<TunnelIngressRule>,
+    /// Settings we don't manage (e.g. `warp-routing`), written back as is
     #[serde(flatten)]
-    other: serde_json::Value,
+    other: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -539,6 +830,59 @@ struct TunnelIngressRule {
     service: String,
     #[serde(rename = "originRequest", skip_serializing_if = "Option::is_none")]
     origin_request: Option<serde_json::Value>,
+    /// Fields we don't manage (e.g. `path`), written back as is
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TunnelIngressRule {
+    /// Matches every request: no hostname and no path
+    fn is_catch_all(&self) -> bool {
+        self.hostname.is_none() && !self.other.contains_key("path")
+    }
+}
+
+/// Parse a tunnel configuration API response
+///
+/// A missing config or ingress list is an empty list; anything else that
+/// doesn't parse is an error, so it is never overwritten.
+fn parse_tunnel_config(body: &[u8]) -> Result<TunnelConfig> {
+    let response: CloudflareResponse<TunnelConfigResponse> =
+        serde_json::from_slice(body).context("Failed to parse tunnel config")?;
+    Ok(response.result.config.unwrap_or_default())
+}
+
+/// Make the last ingress rule a catch-all, moving or adding one as needed
+///
+/// Returns whether the rules were changed.
+fn repair_catch_all(ingress: &mut Vec<TunnelIngressRule>, tunnel_id: &str) -> bool {
+    match ingress.iter().position(TunnelIngressRule::is_catch_all) {
+        Some(pos) if pos + 1 == ingress.len() => false,
+        Some(pos) => {
+            tracing::warn!(
+                tunnel_id,
+                "Tunnel catch-all ingress rule is not last, moving it"
+            );
+            let catch_all = ingress.remove(pos);
+            ingress.push(catch_all);
+            true
+        }
+        None => {
+            if !ingress.is_empty() {
+                tracing::warn!(
+                    tunnel_id,
+                    "Tunnel has no catch-all ingress rule, adding one"
+                );
+            }
+            ingress.push(TunnelIngressRule {
+                hostname: None,
+                service: "http_status:404".to_string(),
+                origin_request: None,
+                other: serde_json::Map::new(),
+            });
+            true
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -558,13 +902,139 @@ mod tests {
 
     #[test]
     fn test_cloudflare_enabled() {
-        let client = CloudflareClient::new(CloudflareConfig {
+        let client = CloudflareClient::new(reqwest::Client::new(), tunnel("tunnel"));
+        assert!(client.is_enabled());
+    }
+
+    fn tunnel(tunnel_id: &str) -> CloudflareConfig {
+        CloudflareConfig {
             api_token: "token".into(),
             account_id: "account".into(),
-            tunnel_id: "tunnel".into(),
-            service_url: "http://localhost:8080".into(),
-        });
+            mode: DnsMode::Tunnel {
+                tunnel_id: tunnel_id.into(),
+                service_url: "http://localhost:8080".into(),
+            },
+        }
+    }
+
+    fn existing(id: &str, record_type: &str, content: &str) -> DnsRecord {
+        DnsRecord {
+            id: id.into(),
+            record_type: record_type.into(),
+            content: content.into(),
+        }
+    }
+
+    #[test]
+    fn test_select_tunnel_per_environment() {
+        let client = CloudflareClient::with_tunnels(
+            reqwest::Client::new(),
+            Some(tunnel("default")),
+            [
+                ("staging".to_string(), tunnel("staging-tunnel")),
+                ("production".to_string(), tunnel("prod-tunnel")),
+            ]
+            .into(),
+        );
+
+        let tunnel_id = |env| {
+            client.config_for(env).map(|c| match &c.mode {
+                DnsMode::Tunnel { tunnel_id, .. } => tunnel_id.as_str(),
+                DnsMode::Direct { .. } => panic!("Expected tunnel mode"),
+            })
+        };
+        assert_eq!(tunnel_id(Some("staging")), Some("staging-tunnel"));
+        assert_eq!(tunnel_id(Some("production")), Some("prod-tunnel"));
+        // Unknown or missing environments use the default tunnel
+        assert_eq!(tunnel_id(Some("other")), Some("default"));
+        assert_eq!(tunnel_id(None), Some("default"));
+    }
+
+    #[test]
+    fn test_environment_tunnels_without_default() {
+        let client = CloudflareClient::with_tunnels(
+            reqwest::Client::new(),
+            None,
+            [("staging".to_string(), tunnel("staging-tunnel"))].into(),
+        );
+
         assert!(client.is_enabled());
+        assert!(client.config_for(Some("staging")).is_some());
+        assert!(client.config_for(Some("production")).is_none());
+    }
+
+    /// Client against a mock API where every hostname is in zone `zone-id`
+    async fn purge_client(server: &wiremock::MockServer) -> CloudflareClient {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "result": [{"id": "zone-id"}],
+            })))
+            .mount(server)
+            .await;
+
+        CloudflareClient::new(reqwest::Client::new(), tunnel("tunnel")).with_api_base(&server.uri())
+    }
+
+    #[tokio::test]
+    async fn test_purge_cache_by_hostname() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone-id/purge_cache"))
+            .and(body_json(serde_json::json!({
+                "hosts": ["www.nullislabs.io", "nullislabs.io"],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "result": {"id": "zone-id"},
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        purge_client(&server)
+            .await
+            .purge_cache(
+                &["www.nullislabs.io".to_string(), "nullislabs.io".to_string()],
+                CachePurge::Hostnames,
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_purge_cache_zone_retried_after_rate_limit() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone-id/purge_cache"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone-id/purge_cache"))
+            .and(body_json(serde_json::json!({"purge_everything": true})))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        purge_client(&server)
+            .await
+            .purge_cache(&["nullislabs.io".to_string()], CachePurge::Zone, None)
+            .await
+            .unwrap();
     }
 
     #[test]
@@ -591,4 +1061,201 @@ mod tests {
             "example.com"
         );
     }
+
+    #[test]
+    fn test_tunnel_mode_record_payload() {
+        let records = desired_dns_records("pr-1.nxm.rs", &tunnel("tunnel-123").mode);
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&records[0]).unwrap(),
+            serde_json::json!({
+                "type": "CNAME",
+                "name": "pr-1.nxm.rs",
+                "content": "tunnel-123.cfargotunnel.com",
+                "proxied": true,
+                "ttl": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_direct_mode_record_payloads() {
+        let mode = DnsMode::Direct {
+            origin_ips: vec![
+                "203.0.113.10".parse().unwrap(),
+                "2001:db8::10".parse().unwrap(),
+            ],
+        };
+        let records = desired_dns_records("app.nullislabs.io", &mode);
+
+        let payloads: Vec<serde_json::Value> = records
+            .iter()
+            .map(|r| serde_json::to_value(r).unwrap())
+            .collect();
+        assert_eq!(
+            payloads,
+            [
+                serde_json::json!({
+                    "type": "A",
+                    "name": "app.nullislabs.io",
+                    "content": "203.0.113.10",
+                    "proxied": true,
+                    "ttl": 1,
+                }),
+                serde_json::json!({
+                    "type": "AAAA",
+                    "name": "app.nullislabs.io",
+                    "content": "2001:db8::10",
+                    "proxied": true,
+                    "ttl": 1,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_dns_changes_up_to_date() {
+        let desired = desired_dns_records("pr-1.nxm.rs", &tunnel("t1").mode);
+        let plan = plan_dns_changes(
+            vec![
+                existing("r1", "CNAME", "t1.cfargotunnel.com"),
+                existing("r2", "TXT", "verification"),
+            ],
+            desired,
+        );
+        assert!(plan.is_empty());
+    }
+
+    #[test]
+    fn test_plan_dns_changes_updates_stale_record() {
+        let desired = desired_dns_records("pr-1.nxm.rs", &tunnel("t2").mode);
+        let plan = plan_dns_changes(
+            vec![existing("r1", "CNAME", "t1.cfargotunnel.com")],
+            desired.clone(),
+        );
+
+        assert_eq!(plan.update, vec![("r1".to_string(), desired[0].clone())]);
+        assert!(plan.delete.is_empty());
+        assert!(plan.create.is_empty());
+    }
+
+    #[test]
+    fn test_plan_dns_changes_tunnel_to_direct() {
+        let mode = DnsMode::Direct {
+            origin_ips: vec![
+                "203.0.113.10".parse().unwrap(),
+                "2001:db8::10".parse().unwrap(),
+            ],
+        };
+        let desired = desired_dns_records("pr-1.nxm.rs", &mode);
+        let plan = plan_dns_changes(
+            vec![
+                existing("r1", "CNAME", "t1.cfargotunnel.com"),
+                existing("r2", "A", "198.51.100.1"),
+                existing("r3", "TXT", "verification"),
+            ],
+            desired.clone(),
+        );
+
+        // The old A record is repointed, the CNAME removed, the AAAA added
+        assert_eq!(plan.update, vec![("r2".to_string(), desired[0].clone())]);
+        assert_eq!(plan.delete, vec!["r1".to_string()]);
+        assert_eq!(plan.create, vec![desired[1].clone()]);
+    }
+
+    fn hostnames(ingress: &[TunnelIngressRule]) -> Vec<Option<&str>> {
+        ingress
+            .iter()
+            .map(|rule| rule.hostname.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn test_tunnel_config_missing_ingress() {
+        let config = parse_tunnel_config(
+            br#"{"success": true, "result": {"config": {"warp-routing": {"enabled": false}}}}"#,
+        )
+        .unwrap();
+        assert!(config.ingress.is_empty());
+        assert!(config.other.contains_key("warp-routing"));
+
+        // Never configured remotely
+        let config =
+            parse_tunnel_config(br#"{"success": true, "result": {"config": null}}"#).unwrap();
+        assert!(config.ingress.is_empty());
+        let config = parse_tunnel_config(br#"{"success": true, "result": {}}"#).unwrap();
+        assert!(config.ingress.is_empty());
+
+        // An empty list gets the catch-all new rules are inserted before
+        let mut ingress = config.ingress;
+        assert!(repair_catch_all(&mut ingress, "tunnel-1"));
+        assert_eq!(hostnames(&ingress), vec![None]);
+        assert_eq!(ingress[0].service, "http_status:404");
+    }
+
+    #[test]
+    fn test_tunnel_config_malformed() {
+        // Not safe to rewrite, so reported instead of overwritten
+        assert!(
+            parse_tunnel_config(br#"{"success": true, "result": {"config": {"ingress": {}}}}"#)
+                .is_err()
+        );
+        assert!(
+            parse_tunnel_config(
+                br#"{"success": true, "result": {"config": {"ingress": [{"hostname": "a.nxm.rs"}]}}}"#
+            )
+            .is_err()
+        );
+        assert!(parse_tunnel_config(b"<html>").is_err());
+    }
+
+    #[test]
+    fn test_repair_catch_all() {
+        let config = parse_tunnel_config(
+            br#"{"success": true, "result": {"config": {"ingress": [
+                {"service": "http_status:404"},
+                {"hostname": "a.nxm.rs", "service": "http://localhost:8080"},
+                {"path": "/status", "service": "http://localhost:9000"}
+            ]}}}"#,
+        )
+        .unwrap();
+
+        // The catch-all moves last; a path-only rule isn't one
+        let mut ingress = config.ingress.clone();
+        assert!(repair_catch_all(&mut ingress, "tunnel-1"));
+        assert_eq!(hostnames(&ingress), vec![Some("a.nxm.rs"), None, None]);
+        assert!(ingress[1].other.contains_key("path"));
+        assert!(ingress[2].is_catch_all());
+        assert!(!repair_catch_all(&mut ingress, "tunnel-1"));
+
+        // Missing entirely, one is added
+        let mut ingress = vec![config.ingress[1].clone()];
+        assert!(repair_catch_all(&mut ingress, "tunnel-1"));
+        assert_eq!(hostnames(&ingress), vec![Some("a.nxm.rs"), None]);
+    }
+
+    #[test]
+    fn test_tunnel_config_round_trip_keeps_unknown_fields() {
+        let config = parse_tunnel_config(
+            br#"{"success": true, "result": {"config": {
+                "ingress": [
+                    {"hostname": "a.nxm.rs", "path": "/api", "service": "http://localhost:8080",
+                     "originRequest": {"noTLSVerify": true}},
+                    {"service": "http_status:404"}
+                ],
+                "warp-routing": {"enabled": true}
+            }}}"#,
+        )
+        .unwrap();
+
+        let written = serde_json::to_value(&config).unwrap();
+        assert_eq!(written["warp-routing"]["enabled"], true);
+        assert_eq!(written["ingress"][0]["path"], "/api");
+        assert_eq!(written["ingress"][0]["originRequest"]["noTLSVerify"], true);
+        assert_eq!(
+            written["ingress"][1],
+            serde_json::json!({"service": "http_status:404"})
+        );
+    }
 }
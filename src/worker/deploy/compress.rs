@@ -0,0 +1,170 @@
+//! Pre-compression of static assets
+//!
+//! Writes `.gz` and `.br` siblings for text assets so Caddy can serve them
+//! directly instead of compressing on every request.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// File extensions worth compressing (binary formats are already compressed)
+const COMPRESSIBLE_EXTENSIONS: &[&str] = &[
+    "html",
+    "htm",
+    "css",
+    "js",
+    "mjs",
+    "json",
+    "xml",
+    "svg",
+    "txt",
+    "map",
+    "wasm",
+    "webmanifest",
+];
+
+/// Files smaller than this don't benefit from compression
+const MIN_COMPRESS_SIZE: u64 = 256;
+
+const BROTLI_QUALITY: u32 = 11;
+const BROTLI_WINDOW: u32 = 22;
+
+/// Compress all text assets in a directory tree
+///
+/// Runs on a blocking thread since compression is CPU-bound.
+/// Returns the number of files compressed.
+pub async fn precompress_dir(dir: &Path) -> Result<usize> {
+    let dir = dir.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut files = Vec::new();
+        collect_compressible(&dir, &mut files)?;
+
+        for file in &files {
+            compress_file(file)
+                .with_context(|| format!("Failed to compress {}", file.display()))?;
+        }
+
+        Ok(files.len())
+    })
+    .await
+    .context("Compression task panicked")?
+}
+
+/// Whether a file should get pre-compressed siblings
+fn is_compressible(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            COMPRESSIBLE_EXTENSIONS
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(ext))
+        })
+}
+
+fn collect_compressible(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+
+        if file_type.is_dir() {
+            collect_compressible(&path, files)?;
+        } else if file_type.is_file()
+            && is_compressible(&path)
+            && entry.metadata()?.len() >= MIN_COMPRESS_SIZE
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn compress_file(path: &Path) -> Result<()> {
+    let gz_path = sibling(path, "gz");
+    let mut input = BufReader::new(File::open(path)?);
+    let mut encoder = flate2::write::GzEncoder::new(
+        BufWriter::new(File::create(&gz_path)?),
+        flate2::Compression::best(),
+    );
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.flush()?;
+
+    let br_path = sibling(path, "br");
+    let mut input = BufReader::new(File::open(path)?);
+    let mut encoder = brotli::CompressorWriter::new(
+        BufWriter::new(File::create(&br_path)?),
+        4096,
+        BROTLI_QUALITY,
+        BROTLI_WINDOW,
+    );
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.into_inner().flush()?;
+
+    Ok(())
+}
+
+/// Path with an extra extension appended (e.g. `app.js` → `app.js.gz`)
+fn sibling(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_precompress_creates_siblings() {
+        let dir = tempdir().unwrap();
+        let html = "<html><body>hello</body></html>\n".repeat(50);
+        std::fs::write(dir.path().join("index.html"), &html).unwrap();
+        std::fs::create_dir(dir.path().join("assets")).unwrap();
+        std::fs::write(
+            dir.path().join("assets/app.js"),
+            "console.log(1);\n".repeat(50),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("assets/logo.png"), vec![0u8; 1024]).unwrap();
+        std::fs::write(dir.path().join("small.css"), "a{}").unwrap();
+
+        let count = precompress_dir(dir.path()).await.unwrap();
+        assert_eq!(count, 2);
+
+        assert!(dir.path().join("index.html.gz").exists());
+        assert!(dir.path().join("index.html.br").exists());
+        assert!(dir.path().join("assets/app.js.gz").exists());
+        assert!(dir.path().join("assets/app.js.br").exists());
+
+        // Binary and tiny files are left alone
+        assert!(!dir.path().join("assets/logo.png.gz").exists());
+        assert!(!dir.path().join("small.css.br").exists());
+
+        // Compressed output round-trips
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(File::open(dir.path().join("index.html.gz")).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, html);
+
+        let mut decoded = String::new();
+        brotli::Decompressor::new(File::open(dir.path().join("index.html.br")).unwrap(), 4096)
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, html);
+    }
+
+    #[test]
+    fn test_is_compressible() {
+        assert!(is_compressible(Path::new("index.html")));
+        assert!(is_compressible(Path::new("assets/APP.JS")));
+        assert!(!is_compressible(Path::new("image.png")));
+        assert!(!is_compressible(Path::new("index.html.gz")));
+        assert!(!is_compressible(Path::new("README")));
+    }
+}
@@ -1,7 +1,18 @@
+pub mod artifact;
 pub mod caddy;
 pub mod cloudflare;
+pub mod compress;
 pub mod sites;
 
-pub use caddy::{configure_caddy_route, remove_caddy_route, wait_for_caddy_ready};
-pub use cloudflare::{CloudflareClient, CloudflareConfig};
-pub use sites::{SiteMetadata, restore_all_routes, write_site_metadata};
+pub use artifact::fetch_artifact;
+pub use caddy::{
+    configure_caddy_route, remove_caddy_route, show_maintenance_page, validate_caddy_extra,
+    wait_for_site_ready,
+};
+pub use cloudflare::{CloudflareClient, CloudflareConfig, DnsMode};
+pub use compress::precompress_dir;
+pub use sites::{
+    SiteMetadata, canary_dir, deploy_canary_files, deploy_site_files, dir_size, find_site_dirs,
+    promote_canary, prune_releases, read_maintenance_page, read_site_metadata, releases_dir,
+    remove_site_metadata, restore_all_routes, site_dir,
+};
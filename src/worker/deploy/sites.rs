@@ -3,21 +3,185 @@
 //! Manages persistent metadata for deployed sites and restores Caddy routes on startup.
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use uuid::Uuid;
 
-use super::caddy::configure_caddy_route;
+use super::caddy::{
+    CaddyApiError, CanarySplit, RouteOptions, configure_caddy_route, wait_for_caddy_ready,
+};
+use crate::config::CaddyAdminApis;
+use crate::shared::{BasicAuth, BuildJob, SiteType};
 
 /// Metadata stored with each deployed site
+///
+/// Provenance fields are optional so metadata written by older workers
+/// (site_id and domain only) still loads.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SiteMetadata {
     /// Site identifier (e.g., "nullislabs-website-pr-42")
     pub site_id: String,
     /// Full domain for this site (e.g., "pr-42-website.nxm.rs")
     pub domain: String,
+    /// Additional hostnames serving this site
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Commit that was built
+    #[serde(default)]
+    pub commit_sha: Option<String>,
+    /// Branch that was built
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// PR number (None for main branch deployments)
+    #[serde(default)]
+    pub pr_number: Option<u32>,
+    /// When the site was deployed
+    #[serde(default)]
+    pub deployed_at: Option<DateTime<Utc>>,
+    /// Build job that produced this deployment
+    #[serde(default)]
+    pub job_id: Option<Uuid>,
+    /// Site type used for the build
+    #[serde(default)]
+    pub site_type: Option<SiteType>,
+    /// Basic auth protecting the site, reapplied when routes are restored
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuth>,
+    /// Whether `.br`/`.gz` siblings were generated for text assets
+    #[serde(default)]
+    pub precompressed: bool,
+    /// Path globs of content-hashed assets, served with immutable caching
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub immutable_paths: Vec<String>,
+    /// Index files tried after `index.html`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub index_names: Vec<String>,
+    /// Whether directories without an index are listed
+    #[serde(default)]
+    pub browse: bool,
+    /// Extra Caddy handlers or route fields from the deploy config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caddy_extra: Option<serde_json::Value>,
+    /// Canary release staged next to this one, awaiting promotion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary: Option<CanaryRelease>,
+    /// Zone the site was deployed for, selecting its Caddy on restore
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+}
+
+/// A canary release served to a share of visitors alongside the stable site
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryRelease {
+    /// Percentage of new visitors served the canary
+    pub percent: u8,
+    /// Metadata of the canary release, which becomes the site's once promoted
+    pub release: Box<SiteMetadata>,
+}
+
+impl SiteMetadata {
+    /// Build metadata for a site deployed by a build job
+    pub fn for_job(site_id: &str, job: &BuildJob) -> Self {
+        Self {
+            site_id: site_id.to_string(),
+            domain: job.domain.clone(),
+            aliases: job.aliases.clone(),
+            commit_sha: Some(job.commit_sha.clone()),
+            branch: Some(job.branch.clone()),
+            pr_number: job.pr_number,
+            deployed_at: Some(Utc::now()),
+            job_id: Some(job.job_id),
+            site_type: Some(job.site_type),
+            basic_auth: job.basic_auth.clone(),
+            precompressed: job.precompress,
+            immutable_paths: job.immutable_paths.clone().unwrap_or_default(),
+            index_names: job.index_names.clone(),
+            browse: job.browse,
+            caddy_extra: job.caddy_extra.clone(),
+            canary: None,
+            zone: job.environment.clone(),
+        }
+    }
+
+    /// Every hostname this site is served on, starting with its domain
+    ///
+    /// Includes a staged canary's aliases, so both releases are reachable on
+    /// every hostname either is configured for.
+    pub fn hostnames(&self) -> Vec<String> {
+        let canary_aliases = self
+            .canary
+            .iter()
+            .flat_map(|canary| &canary.release.aliases);
+
+        let mut hostnames = vec![self.domain.clone()];
+        for alias in self.aliases.iter().chain(canary_aliases) {
+            if !hostnames.contains(alias) {
+                hostnames.push(alias.clone());
+            }
+        }
+        hostnames
+    }
+
+    /// Caddy route options for this site, deployed to `site_dir`
+    pub fn route_options(&self, site_dir: &Path) -> RouteOptions {
+        RouteOptions {
+            aliases: self.hostnames().split_off(1),
+            basic_auth: self.basic_auth.clone(),
+            precompressed: self.precompressed,
+            immutable_paths: self.immutable_paths.clone(),
+            index_names: self.index_names.clone(),
+            browse: self.browse,
+            caddy_extra: self.caddy_extra.clone(),
+            canary: self.canary.as_ref().map(|canary| CanarySplit {
+                dir: canary_dir(site_dir),
+                percent: canary.percent,
+                precompressed: canary.release.precompressed,
+            }),
+        }
+    }
+}
+
+/// Deploy metadata kept next to the site files; never served (see caddy)
+pub const METADATA_FILE: &str = ".catapult.json";
+
+/// Directory a site is deployed to
+///
+/// With a zone the layout is `sites_dir/{zone}/{site_id}`, otherwise the flat
+/// `sites_dir/{site_id}`. The zone must be a single path segment.
+pub fn site_dir(sites_dir: &Path, zone: Option<&str>, site_id: &str) -> Result<PathBuf> {
+    match zone {
+        Some(zone) => {
+            let valid = !zone.is_empty()
+                && zone != "."
+                && zone != ".."
+                && !zone.contains(['/', '\\', '\0']);
+            if !valid {
+                anyhow::bail!("Invalid zone for site directory: '{}'", zone);
+            }
+            Ok(sites_dir.join(zone).join(site_id))
+        }
+        None => Ok(sites_dir.join(site_id)),
+    }
+}
+
+/// Directory a canary release is staged in, next to its site directory
+pub fn canary_dir(site_dir: &Path) -> PathBuf {
+    let mut name = site_dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".canary");
+    site_dir.with_file_name(name)
 }
 
-const METADATA_FILE: &str = ".catapult.json";
+/// Directory previous releases are kept in, next to its site directory
+///
+/// Each release is a `%Y%m%dT%H%M%S%.9fZ` subdirectory named after the time it
+/// was replaced, so names sort oldest first.
+pub fn releases_dir(site_dir: &Path) -> PathBuf {
+    let mut name = site_dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".releases");
+    site_dir.with_file_name(name)
+}
 
 /// Write site metadata to the site directory
 pub async fn write_site_metadata(site_dir: &Path, metadata: &SiteMetadata) -> Result<()> {
@@ -32,12 +196,282 @@ pub async fn write_site_metadata(site_dir: &Path, metadata: &SiteMetadata) -> Re
     tracing::debug!(
         site_id = %metadata.site_id,
         domain = %metadata.domain,
+        commit = ?metadata.commit_sha,
         "Wrote site metadata"
     );
 
     Ok(())
 }
 
+/// Remove the site metadata file so the site is no longer restored on restart
+pub async fn remove_site_metadata(site_dir: &Path) -> Result<()> {
+    let metadata_path = site_dir.join(METADATA_FILE);
+
+    match tokio::fs::remove_file(&metadata_path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).context("Failed to remove site metadata"),
+    }
+}
+
+/// Replace a site directory with freshly built artifacts and write its metadata
+///
+/// With `keep_releases` above zero the replaced release is moved into the
+/// site's releases directory instead of being deleted; see [`prune_releases`].
+pub async fn deploy_site_files(
+    output_dir: &Path,
+    site_dir: &Path,
+    metadata: &SiteMetadata,
+    keep_releases: usize,
+) -> Result<()> {
+    if keep_releases > 0 {
+        archive_release(site_dir).await?;
+    }
+    replace_dir(output_dir, site_dir).await?;
+    write_site_metadata(site_dir, metadata).await?;
+
+    // A full deploy supersedes any canary still awaiting promotion
+    let staged = canary_dir(site_dir);
+    if staged.exists() {
+        tokio::fs::remove_dir_all(&staged)
+            .await
+            .context("Failed to remove superseded canary release")?;
+    }
+
+    Ok(())
+}
+
+/// Stage freshly built artifacts as a canary next to the live site
+///
+/// The live release keeps serving from `site_dir`; its metadata records the
+/// canary so the split route survives restarts. A canary already awaiting
+/// promotion is replaced. Returns `None` when there's no live release to keep,
+/// in which case the caller deploys normally.
+pub async fn deploy_canary_files(
+    output_dir: &Path,
+    site_dir: &Path,
+    release: SiteMetadata,
+    percent: u8,
+) -> Result<Option<SiteMetadata>> {
+    let Some(mut stable) = read_site_metadata(site_dir).await? else {
+        return Ok(None);
+    };
+
+    replace_dir(output_dir, &canary_dir(site_dir)).await?;
+
+    stable.canary = Some(CanaryRelease {
+        percent,
+        release: Box::new(release),
+    });
+    write_site_metadata(site_dir, &stable).await?;
+
+    Ok(Some(stable))
+}
+
+/// Send all traffic to the canary release produced by `job_id`
+///
+/// The route is first pointed at the staged canary, so the site is never
+/// unrouted while the canary is moved into the site directory. Returns false
+/// if no canary from that job is awaiting promotion.
+pub async fn promote_canary(
+    http_client: &reqwest::Client,
+    caddy_admin_api: &str,
+    site_dir: &Path,
+    job_id: Uuid,
+) -> Result<bool> {
+    let Some(canary) = read_site_metadata(site_dir)
+        .await?
+        .and_then(|metadata| metadata.canary)
+        .filter(|canary| canary.release.job_id == Some(job_id))
+    else {
+        return Ok(false);
+    };
+    let release = *canary.release;
+    let staged = canary_dir(site_dir);
+
+    configure_caddy_route(
+        http_client,
+        caddy_admin_api,
+        &release.site_id,
+        &staged,
+        &release.domain,
+        &release.route_options(&staged),
+    )
+    .await?;
+
+    // Written first so an interrupted promotion still restores the canary
+    write_site_metadata(&staged, &release).await?;
+    tokio::fs::remove_dir_all(site_dir)
+        .await
+        .context("Failed to remove previous release")?;
+    tokio::fs::rename(&staged, site_dir)
+        .await
+        .context("Failed to move canary release into place")?;
+
+    configure_caddy_route(
+        http_client,
+        caddy_admin_api,
+        &release.site_id,
+        site_dir,
+        &release.domain,
+        &release.route_options(site_dir),
+    )
+    .await?;
+
+    tracing::info!(
+        site_id = %release.site_id,
+        job_id = %job_id,
+        commit = ?release.commit_sha,
+        "Promoted canary release"
+    );
+
+    Ok(true)
+}
+
+/// Move the live release of a site into its releases directory
+async fn archive_release(site_dir: &Path) -> Result<()> {
+    if !site_dir.exists() {
+        return Ok(());
+    }
+
+    let releases = releases_dir(site_dir);
+    tokio::fs::create_dir_all(&releases)
+        .await
+        .context("Failed to create releases directory")?;
+
+    let name = Utc::now().format("%Y%m%dT%H%M%S%.9fZ").to_string();
+    tokio::fs::rename(site_dir, releases.join(&name))
+        .await
+        .context("Failed to archive previous release")?;
+
+    tracing::debug!(site_dir = %site_dir.display(), release = %name, "Archived previous release");
+
+    Ok(())
+}
+
+/// Delete all but the `keep` most recent releases of a site
+///
+/// Only the releases directory is touched; the live release in `site_dir`
+/// is never a candidate. Returns the number of releases removed.
+pub async fn prune_releases(site_dir: &Path, keep: usize) -> Result<usize> {
+    let releases = releases_dir(site_dir);
+    if !releases.exists() {
+        return Ok(0);
+    }
+
+    let mut names = Vec::new();
+    let mut entries = tokio::fs::read_dir(&releases)
+        .await
+        .context("Failed to read releases directory")?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            names.push(entry.file_name());
+        }
+    }
+
+    // Timestamped names sort oldest first
+    names.sort();
+    let excess = names.len().saturating_sub(keep);
+
+    for name in &names[..excess] {
+        let release = releases.join(name);
+        tokio::fs::remove_dir_all(&release)
+            .await
+            .with_context(|| format!("Failed to remove release {}", release.display()))?;
+        tracing::info!(release = %release.display(), "Pruned old release");
+    }
+
+    if keep == 0 {
+        let _ = tokio::fs::remove_dir(&releases).await;
+    }
+
+    Ok(excess)
+}
+
+/// Replace a directory's contents with a copy of `src`
+async fn replace_dir(src: &Path, dst: &Path) -> Result<()> {
+    // Remove old deployment if exists
+    if dst.exists() {
+        tokio::fs::remove_dir_all(dst)
+            .await
+            .context("Failed to remove previous deployment")?;
+    }
+
+    copy_dir_recursive(src, dst)
+        .await
+        .context("Failed to copy build artifacts")
+}
+
+/// Largest maintenance page accepted, as Caddy keeps it in its config
+const MAX_MAINTENANCE_PAGE_BYTES: u64 = 256 * 1024;
+
+/// Read the maintenance page `page` from a build's output directory
+///
+/// The page must be a relative path within the output.
+pub async fn read_maintenance_page(output_dir: &Path, page: &str) -> Result<String> {
+    let relative = Path::new(page);
+    if page.is_empty()
+        || relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        anyhow::bail!(
+            "maintenance_page must be a relative path within the build output: {}",
+            page
+        );
+    }
+
+    let path = output_dir.join(relative);
+    let metadata = tokio::fs::metadata(&path)
+        .await
+        .with_context(|| format!("Maintenance page not found in build output: {}", page))?;
+    if metadata.len() > MAX_MAINTENANCE_PAGE_BYTES {
+        anyhow::bail!(
+            "Maintenance page {} is larger than {} bytes",
+            page,
+            MAX_MAINTENANCE_PAGE_BYTES
+        );
+    }
+
+    tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("Failed to read maintenance page: {}", page))
+}
+
+/// Total size of the files under `dir`
+pub async fn dir_size(dir: &Path) -> Result<u64> {
+    let mut total = 0;
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file_type = entry.file_type().await?;
+        if file_type.is_dir() {
+            total += Box::pin(dir_size(&entry.path())).await?;
+        } else if file_type.is_file() {
+            total += entry.metadata().await?.len();
+        }
+    }
+
+    Ok(total)
+}
+
+async fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+    tokio::fs::create_dir_all(dst).await?;
+
+    let mut entries = tokio::fs::read_dir(src).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+
+        if entry.file_type().await?.is_dir() {
+            Box::pin(copy_dir_recursive(&src_path, &dst_path)).await?;
+        } else {
+            tokio::fs::copy(&src_path, &dst_path).await?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Read site metadata from a site directory
 pub async fn read_site_metadata(site_dir: &Path) -> Result<Option<SiteMetadata>> {
     let metadata_path = site_dir.join(METADATA_FILE);
@@ -56,97 +490,288 @@ pub async fn read_site_metadata(site_dir: &Path) -> Result<Option<SiteMetadata>>
     Ok(Some(metadata))
 }
 
-/// Restore all Caddy routes from existing site deployments
+/// List candidate site directories in both the flat and per-zone layouts
 ///
-/// Scans the sites directory and configures Caddy routes for all sites
-/// that have metadata files. This should be called on worker startup.
-pub async fn restore_all_routes(
-    http_client: &reqwest::Client,
-    caddy_admin_api: &str,
-    sites_dir: &Path,
-) -> Result<usize> {
-    if !sites_dir.exists() {
-        tracing::debug!(sites_dir = %sites_dir.display(), "Sites directory doesn't exist, nothing to restore");
-        return Ok(0);
-    }
-
-    let mut restored = 0;
+/// A top-level directory with a metadata file is a site; one without is
+/// treated as a zone directory and its subdirectories are listed instead.
+pub async fn find_site_dirs(sites_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut site_dirs = Vec::new();
     let mut entries = tokio::fs::read_dir(sites_dir)
         .await
         .context("Failed to read sites directory")?;
 
     while let Some(entry) = entries.next_entry().await? {
-        let site_dir = entry.path();
+        let dir = entry.path();
 
-        // Skip non-directories
-        if !site_dir.is_dir() {
+        // Skip non-directories and archived releases
+        if !dir.is_dir() || dir.extension().is_some_and(|ext| ext == "releases") {
             continue;
         }
 
-        // Try to read metadata
-        match read_site_metadata(&site_dir).await {
-            Ok(Some(metadata)) => {
-                tracing::info!(
-                    site_id = %metadata.site_id,
-                    domain = %metadata.domain,
-                    "Restoring Caddy route"
-                );
-
-                match configure_caddy_route(
-                    http_client,
-                    caddy_admin_api,
-                    &metadata.site_id,
-                    &site_dir,
-                    &metadata.domain,
-                )
-                .await
-                {
-                    Ok(()) => {
-                        restored += 1;
-                        tracing::info!(
-                            site_id = %metadata.site_id,
-                            domain = %metadata.domain,
-                            "Restored Caddy route"
-                        );
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            site_id = %metadata.site_id,
-                            error = %e,
-                            "Failed to restore Caddy route"
-                        );
-                    }
-                }
-            }
-            Ok(None) => {
-                // No metadata file - this might be a manually created directory
-                // or from before metadata was implemented
-                let dir_name = site_dir.file_name().unwrap_or_default().to_string_lossy();
-                tracing::debug!(
-                    site_dir = %dir_name,
-                    "No metadata file found, skipping"
-                );
-            }
-            Err(e) => {
-                let dir_name = site_dir.file_name().unwrap_or_default().to_string_lossy();
-                tracing::warn!(
-                    site_dir = %dir_name,
-                    error = %e,
-                    "Failed to read site metadata"
-                );
+        if dir.join(METADATA_FILE).exists() {
+            site_dirs.push(dir);
+            continue;
+        }
+
+        let mut zone_entries = tokio::fs::read_dir(&dir)
+            .await
+            .with_context(|| format!("Failed to read {}", dir.display()))?;
+        let mut zone_sites = Vec::new();
+        while let Some(zone_entry) = zone_entries.next_entry().await? {
+            let site_dir = zone_entry.path();
+            if site_dir.is_dir() && site_dir.join(METADATA_FILE).exists() {
+                zone_sites.push(site_dir);
             }
         }
+
+        // Neither a site nor a zone: keep it so it's reported below
+        if zone_sites.is_empty() {
+            site_dirs.push(dir);
+        } else {
+            site_dirs.extend(zone_sites);
+        }
+    }
+
+    Ok(site_dirs)
+}
+
+/// Restore all Caddy routes from existing site deployments
+///
+/// Scans the sites directory (flat and per-zone layouts) and configures
+/// Caddy routes for all sites that have metadata files, once the Caddy admin
+/// APIs are up. Each site goes to its zone's Caddy: the zone recorded in its
+/// metadata, else the zone directory it's in. Up to `concurrency` sites are
+/// restored at once, and a site that fails doesn't stop the others. This
+/// should be called on worker startup.
+pub async fn restore_all_routes(
+    http_client: &reqwest::Client,
+    caddy_admin_api: &CaddyAdminApis,
+    sites_dir: &Path,
+    concurrency: usize,
+) -> Result<usize> {
+    if !sites_dir.exists() {
+        tracing::debug!(sites_dir = %sites_dir.display(), "Sites directory doesn't exist, nothing to restore");
+        return Ok(0);
+    }
+
+    // Caddy may still be starting alongside the worker
+    for endpoint in caddy_admin_api.endpoints() {
+        wait_for_caddy_ready(http_client, endpoint).await?;
     }
 
+    let restored = futures::stream::iter(find_site_dirs(sites_dir).await?)
+        .map(|site_dir| async move {
+            restore_site_route(http_client, caddy_admin_api, sites_dir, &site_dir).await
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter(|restored| std::future::ready(*restored))
+        .count()
+        .await;
+
     tracing::info!(count = restored, "Route restoration complete");
 
     Ok(restored)
 }
 
+/// Restore one site's Caddy route, returning whether it was restored
+async fn restore_site_route(
+    http_client: &reqwest::Client,
+    caddy_admin_api: &CaddyAdminApis,
+    sites_dir: &Path,
+    site_dir: &Path,
+) -> bool {
+    let metadata = match read_site_metadata(site_dir).await {
+        Ok(Some(metadata)) => metadata,
+        Ok(None) => {
+            // No metadata file - this might be a manually created directory
+            // or from before metadata was implemented
+            let dir_name = site_dir.file_name().unwrap_or_default().to_string_lossy();
+            tracing::debug!(
+                site_dir = %dir_name,
+                "No metadata file found, skipping"
+            );
+            return false;
+        }
+        Err(e) => {
+            let dir_name = site_dir.file_name().unwrap_or_default().to_string_lossy();
+            tracing::warn!(
+                site_dir = %dir_name,
+                error = %e,
+                "Failed to read site metadata"
+            );
+            return false;
+        }
+    };
+
+    tracing::info!(
+        site_id = %metadata.site_id,
+        domain = %metadata.domain,
+        "Restoring Caddy route"
+    );
+
+    // Sites from older workers only have their directory's zone
+    let zone_dir = site_dir
+        .parent()
+        .filter(|parent| *parent != sites_dir)
+        .and_then(|parent| parent.file_name())
+        .and_then(|name| name.to_str());
+    let zone = metadata.zone.as_deref().or(zone_dir);
+
+    match configure_caddy_route(
+        http_client,
+        caddy_admin_api.for_zone(zone),
+        &metadata.site_id,
+        site_dir,
+        &metadata.domain,
+        &metadata.route_options(site_dir),
+    )
+    .await
+    {
+        Ok(()) => {
+            tracing::info!(
+                site_id = %metadata.site_id,
+                domain = %metadata.domain,
+                "Restored Caddy route"
+            );
+            true
+        }
+        Err(e) => {
+            tracing::error!(
+                site_id = %metadata.site_id,
+                error = %e,
+                caddy_error = ?e.downcast_ref::<CaddyApiError>().map(CaddyApiError::kind),
+                transient = e
+                    .downcast_ref::<CaddyApiError>()
+                    .is_some_and(CaddyApiError::is_transient),
+                "Failed to restore Caddy route"
+            );
+            false
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::tempdir;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Mock Caddy admin API with no existing routes
+    async fn mock_caddy() -> MockServer {
+        let server = MockServer::start().await;
+        mock_caddy_ready(&server).await;
+
+        Mock::given(method("PATCH"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    /// Answer the admin API readiness check
+    async fn mock_caddy_ready(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/config/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(server)
+            .await;
+    }
+
+    fn test_job(pr_number: Option<u32>, domain: &str) -> BuildJob {
+        BuildJob {
+            job_id: Uuid::new_v4(),
+            repo_url: "https://github.com/nullisLabs/website.git".to_string(),
+            git_token: "token".to_string(),
+            branch: "main".to_string(),
+            commit_sha: "abc123".to_string(),
+            pr_number,
+            site_id: None,
+            domain: domain.to_string(),
+            aliases: Vec::new(),
+            site_type: SiteType::Auto,
+            callback_url: "https://central.example.com/api/status".to_string(),
+            repo_name: "website".to_string(),
+            org_name: "nullisLabs".to_string(),
+            subdomain: None,
+            build_args: Default::default(),
+            basic_auth: None,
+            precompress: false,
+            git_lfs: false,
+            index_names: Vec::new(),
+            browse: false,
+            caddy_extra: None,
+            isolation: None,
+            dry_run: false,
+            worker_id: None,
+            memory_mb: None,
+            cpu_cores: None,
+            pids_limit: None,
+            node_version: None,
+            environment: None,
+            artifact: None,
+            canary_percent: None,
+            immutable_paths: None,
+            purge_cache: None,
+            maintenance_page: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dir_size() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("index.html"), [0u8; 100]).unwrap();
+        std::fs::create_dir_all(dir.path().join("assets/img")).unwrap();
+        std::fs::write(dir.path().join("assets/app.js"), [0u8; 20]).unwrap();
+        std::fs::write(dir.path().join("assets/img/logo.png"), [0u8; 3]).unwrap();
+
+        assert_eq!(dir_size(dir.path()).await.unwrap(), 123);
+    }
+
+    #[tokio::test]
+    async fn test_read_maintenance_page() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("errors")).unwrap();
+        std::fs::write(dir.path().join("errors/503.html"), "<h1>Back shortly</h1>").unwrap();
+
+        let page = read_maintenance_page(dir.path(), "errors/503.html")
+            .await
+            .unwrap();
+        assert_eq!(page, "<h1>Back shortly</h1>");
+
+        assert!(
+            read_maintenance_page(dir.path(), "missing.html")
+                .await
+                .is_err()
+        );
+        assert!(
+            read_maintenance_page(dir.path(), "../503.html")
+                .await
+                .is_err()
+        );
+        assert!(
+            read_maintenance_page(dir.path(), "/etc/passwd")
+                .await
+                .is_err()
+        );
+
+        std::fs::write(
+            dir.path().join("huge.html"),
+            vec![b'a'; MAX_MAINTENANCE_PAGE_BYTES as usize + 1],
+        )
+        .unwrap();
+        assert!(
+            read_maintenance_page(dir.path(), "huge.html")
+                .await
+                .is_err()
+        );
+    }
 
     #[tokio::test]
     async fn test_write_and_read_metadata() {
@@ -156,6 +781,21 @@ mod tests {
         let metadata = SiteMetadata {
             site_id: "test-site-pr-42".to_string(),
             domain: "pr-42-test.example.com".to_string(),
+            aliases: Vec::new(),
+            commit_sha: Some("abc123".to_string()),
+            branch: Some("feature".to_string()),
+            pr_number: Some(42),
+            deployed_at: Some(Utc::now()),
+            job_id: Some(Uuid::new_v4()),
+            site_type: Some(SiteType::Vite),
+            basic_auth: None,
+            precompressed: true,
+            immutable_paths: Vec::new(),
+            index_names: vec!["index.htm".to_string()],
+            browse: false,
+            caddy_extra: None,
+            canary: None,
+            zone: None,
         };
 
         // Write metadata
@@ -165,6 +805,38 @@ mod tests {
         let read_back = read_site_metadata(site_dir).await.unwrap().unwrap();
         assert_eq!(read_back.site_id, metadata.site_id);
         assert_eq!(read_back.domain, metadata.domain);
+        assert_eq!(read_back.commit_sha, metadata.commit_sha);
+        assert_eq!(read_back.pr_number, Some(42));
+        assert_eq!(read_back.job_id, metadata.job_id);
+        assert_eq!(read_back.site_type, Some(SiteType::Vite));
+    }
+
+    #[test]
+    fn test_deserialize_legacy_metadata() {
+        let json = r#"{"site_id": "test-site", "domain": "test.example.com"}"#;
+
+        let metadata: SiteMetadata = serde_json::from_str(json).unwrap();
+        assert_eq!(metadata.site_id, "test-site");
+        assert_eq!(metadata.domain, "test.example.com");
+        assert!(metadata.commit_sha.is_none());
+        assert!(metadata.deployed_at.is_none());
+        assert!(metadata.site_type.is_none());
+    }
+
+    #[test]
+    fn test_deserialize_metadata_with_unknown_fields() {
+        // Metadata written by a newer worker must not break route restoration
+        let json = r#"{
+            "site_id": "test-site",
+            "domain": "test.example.com",
+            "commit_sha": "abc123",
+            "site_type": "zola",
+            "future_field": true
+        }"#;
+
+        let metadata: SiteMetadata = serde_json::from_str(json).unwrap();
+        assert_eq!(metadata.commit_sha.as_deref(), Some("abc123"));
+        assert_eq!(metadata.site_type, Some(SiteType::Zola));
     }
 
     #[tokio::test]
@@ -173,4 +845,568 @@ mod tests {
         let result = read_site_metadata(dir.path()).await.unwrap();
         assert!(result.is_none());
     }
+
+    #[tokio::test]
+    async fn test_deploy_then_restore_route() {
+        let output = tempdir().unwrap();
+        std::fs::write(output.path().join("index.html"), "<h1>hello</h1>").unwrap();
+        std::fs::create_dir(output.path().join("assets")).unwrap();
+        std::fs::write(output.path().join("assets/app.js"), "// js").unwrap();
+
+        let sites = tempdir().unwrap();
+        let site_dir = sites.path().join("nullislabs-website-pr-42");
+        let job = test_job(Some(42), "pr-42.nullislabs.io");
+
+        deploy_site_files(
+            output.path(),
+            &site_dir,
+            &SiteMetadata::for_job("nullislabs-website-pr-42", &job),
+            0,
+        )
+        .await
+        .unwrap();
+
+        assert!(site_dir.join("index.html").exists());
+        assert!(site_dir.join("assets/app.js").exists());
+
+        let caddy = mock_caddy().await;
+        Mock::given(method("POST"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .and(body_partial_json(serde_json::json!({
+                "@id": "nullislabs-website-pr-42",
+                "match": [{"host": ["pr-42.nullislabs.io"]}],
+                "handle": [{"root": site_dir.to_string_lossy()}],
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+
+        let restored = restore_all_routes(
+            &reqwest::Client::new(),
+            &CaddyAdminApis::single(caddy.uri()),
+            sites.path(),
+            8,
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored, 1);
+    }
+
+    #[tokio::test]
+    async fn test_restart_recovery_restores_every_site() {
+        let sites = tempdir().unwrap();
+        let output = tempdir().unwrap();
+        std::fs::write(output.path().join("index.html"), "ok").unwrap();
+
+        // Main and PR deployments written by the current worker
+        for (site_id, pr, domain) in [
+            ("nullislabs-website", None, "nullislabs.io"),
+            ("nullislabs-website-pr-7", Some(7), "pr-7.nullislabs.io"),
+        ] {
+            let job = test_job(pr, domain);
+            deploy_site_files(
+                output.path(),
+                &sites.path().join(site_id),
+                &SiteMetadata::for_job(site_id, &job),
+                0,
+            )
+            .await
+            .unwrap();
+        }
+
+        // Legacy metadata from an older worker
+        let legacy_dir = sites.path().join("nullislabs-docs");
+        std::fs::create_dir(&legacy_dir).unwrap();
+        std::fs::write(
+            legacy_dir.join(METADATA_FILE),
+            r#"{"site_id": "nullislabs-docs", "domain": "docs.nullislabs.io"}"#,
+        )
+        .unwrap();
+
+        // Cleaned-up site whose directory removal didn't finish
+        let cleaned_dir = sites.path().join("nullislabs-website-pr-3");
+        deploy_site_files(
+            output.path(),
+            &cleaned_dir,
+            &SiteMetadata::for_job(
+                "nullislabs-website-pr-3",
+                &test_job(Some(3), "pr-3.nullislabs.io"),
+            ),
+            0,
+        )
+        .await
+        .unwrap();
+        remove_site_metadata(&cleaned_dir).await.unwrap();
+
+        let caddy = mock_caddy().await;
+        Mock::given(method("POST"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(3)
+            .mount(&caddy)
+            .await;
+
+        let restored = restore_all_routes(
+            &reqwest::Client::new(),
+            &CaddyAdminApis::single(caddy.uri()),
+            sites.path(),
+            8,
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored, 3);
+    }
+
+    #[tokio::test]
+    async fn test_restore_many_sites_isolates_failures() {
+        let sites = tempdir().unwrap();
+        let output = release_output("ok");
+
+        for n in 0..40 {
+            let site_id = format!("nullislabs-website-pr-{}", n);
+            let job = test_job(Some(n), &format!("pr-{}.nullislabs.io", n));
+            deploy_site_files(
+                output.path(),
+                &sites.path().join(&site_id),
+                &SiteMetadata::for_job(&site_id, &job),
+                0,
+            )
+            .await
+            .unwrap();
+        }
+
+        // Unreadable metadata is logged and skipped
+        let corrupt_dir = sites.path().join("nullislabs-corrupt");
+        std::fs::create_dir(&corrupt_dir).unwrap();
+        std::fs::write(corrupt_dir.join(METADATA_FILE), "not json").unwrap();
+
+        // Caddy rejects every tenth site's route
+        let caddy = mock_caddy().await;
+        for n in [0, 10, 20, 30] {
+            Mock::given(method("POST"))
+                .and(path("/config/apps/http/servers/main/routes"))
+                .and(body_partial_json(serde_json::json!({
+                    "@id": format!("nullislabs-website-pr-{}", n),
+                })))
+                .respond_with(ResponseTemplate::new(400))
+                .with_priority(1)
+                .mount(&caddy)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(36)
+            .mount(&caddy)
+            .await;
+
+        let restored = restore_all_routes(
+            &reqwest::Client::new(),
+            &CaddyAdminApis::single(caddy.uri()),
+            sites.path(),
+            4,
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored, 36);
+    }
+
+    #[tokio::test]
+    async fn test_restore_routes_to_zone_caddy() {
+        let sites = tempdir().unwrap();
+        let output = release_output("ok");
+
+        // Recorded zone, zone directory only (older worker), and flat default
+        let mut staging_job = test_job(None, "staging.nullislabs.io");
+        staging_job.environment = Some("staging".to_string());
+        for (dir, site_id, job) in [
+            (
+                "staging/nullislabs-website",
+                "nullislabs-website",
+                staging_job,
+            ),
+            (
+                "nxm/nullislabs-docs",
+                "nullislabs-docs",
+                test_job(None, "docs.nxm.rs"),
+            ),
+            (
+                "nullislabs-blog",
+                "nullislabs-blog",
+                test_job(None, "blog.nullislabs.io"),
+            ),
+        ] {
+            deploy_site_files(
+                output.path(),
+                &sites.path().join(dir),
+                &SiteMetadata::for_job(site_id, &job),
+                0,
+            )
+            .await
+            .unwrap();
+        }
+
+        let mut servers = Vec::new();
+        for site_id in ["nullislabs-blog", "nullislabs-website", "nullislabs-docs"] {
+            let caddy = mock_caddy().await;
+            Mock::given(method("POST"))
+                .and(path("/config/apps/http/servers/main/routes"))
+                .and(body_partial_json(serde_json::json!({"@id": site_id})))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&caddy)
+                .await;
+            servers.push(caddy);
+        }
+
+        let apis = CaddyAdminApis::parse(
+            &servers[0].uri(),
+            &format!("staging={},nxm={}", servers[1].uri(), servers[2].uri()),
+        )
+        .unwrap();
+        let restored = restore_all_routes(&reqwest::Client::new(), &apis, sites.path(), 8)
+            .await
+            .unwrap();
+        assert_eq!(restored, 3);
+    }
+
+    /// Build output containing a single index page
+    fn release_output(content: &str) -> tempfile::TempDir {
+        let output = tempdir().unwrap();
+        std::fs::write(output.path().join("index.html"), content).unwrap();
+        output
+    }
+
+    #[tokio::test]
+    async fn test_canary_deploy_and_promote() {
+        let sites = tempdir().unwrap();
+        let site_dir = sites.path().join("nullislabs-website-main");
+        let staged = canary_dir(&site_dir);
+        let stable_job = test_job(None, "nullislabs.io");
+        let canary_job = test_job(None, "nullislabs.io");
+        let v1 = release_output("v1");
+        let v2 = release_output("v2");
+
+        // Nothing live yet: the first deploy can't be a canary
+        let first = deploy_canary_files(
+            v1.path(),
+            &site_dir,
+            SiteMetadata::for_job("nullislabs-website-main", &stable_job),
+            10,
+        )
+        .await
+        .unwrap();
+        assert!(first.is_none());
+
+        deploy_site_files(
+            v1.path(),
+            &site_dir,
+            &SiteMetadata::for_job("nullislabs-website-main", &stable_job),
+            0,
+        )
+        .await
+        .unwrap();
+
+        let metadata = deploy_canary_files(
+            v2.path(),
+            &site_dir,
+            SiteMetadata::for_job("nullislabs-website-main", &canary_job),
+            10,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        // The stable release keeps serving; the canary is staged beside it
+        assert_eq!(
+            std::fs::read_to_string(site_dir.join("index.html")).unwrap(),
+            "v1"
+        );
+        assert_eq!(
+            std::fs::read_to_string(staged.join("index.html")).unwrap(),
+            "v2"
+        );
+        assert!(!staged.join(METADATA_FILE).exists());
+        assert_eq!(metadata.job_id, Some(stable_job.job_id));
+        let canary = metadata.route_options(&site_dir).canary.unwrap();
+        assert_eq!(canary.dir, staged);
+        assert_eq!(canary.percent, 10);
+
+        // The split survives restarts
+        let caddy = MockServer::start().await;
+        mock_caddy_ready(&caddy).await;
+        Mock::given(method("PATCH"))
+            .and(path("/id/nullislabs-website-main"))
+            .and(body_partial_json(serde_json::json!({
+                "handle": [{"handler": "subroute"}],
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+        let restored = restore_all_routes(
+            &reqwest::Client::new(),
+            &CaddyAdminApis::single(caddy.uri()),
+            sites.path(),
+            8,
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored, 1);
+
+        // Promotion
+        let caddy = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/id/nullislabs-website-main"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&caddy)
+            .await;
+        let client = reqwest::Client::new();
+
+        // Only the canary's own job can promote it
+        let promoted = promote_canary(&client, &caddy.uri(), &site_dir, stable_job.job_id)
+            .await
+            .unwrap();
+        assert!(!promoted);
+
+        let promoted = promote_canary(&client, &caddy.uri(), &site_dir, canary_job.job_id)
+            .await
+            .unwrap();
+        assert!(promoted);
+
+        assert_eq!(
+            std::fs::read_to_string(site_dir.join("index.html")).unwrap(),
+            "v2"
+        );
+        assert!(!staged.exists());
+        let metadata = read_site_metadata(&site_dir).await.unwrap().unwrap();
+        assert_eq!(metadata.job_id, Some(canary_job.job_id));
+        assert!(metadata.canary.is_none());
+
+        // Traffic moves to the staged canary before it is moved into place
+        let roots: Vec<String> = caddy
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| {
+                let route: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                route["handle"][0]["root"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(
+            roots,
+            [
+                staged.to_string_lossy().to_string(),
+                site_dir.to_string_lossy().to_string()
+            ]
+        );
+
+        let promoted = promote_canary(&client, &caddy.uri(), &site_dir, canary_job.job_id)
+            .await
+            .unwrap();
+        assert!(!promoted);
+    }
+
+    #[tokio::test]
+    async fn test_full_deploy_discards_canary() {
+        let sites = tempdir().unwrap();
+        let site_dir = sites.path().join("nullislabs-website-main");
+        let job = test_job(None, "nullislabs.io");
+        let output = release_output("v1");
+        let metadata = SiteMetadata::for_job("nullislabs-website-main", &job);
+
+        deploy_site_files(output.path(), &site_dir, &metadata, 0)
+            .await
+            .unwrap();
+        deploy_canary_files(output.path(), &site_dir, metadata.clone(), 25)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(canary_dir(&site_dir).exists());
+
+        deploy_site_files(output.path(), &site_dir, &metadata, 0)
+            .await
+            .unwrap();
+        assert!(!canary_dir(&site_dir).exists());
+        let metadata = read_site_metadata(&site_dir).await.unwrap().unwrap();
+        assert!(metadata.canary.is_none());
+    }
+
+    /// Release directory names, oldest first
+    fn release_names(site_dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = std::fs::read_dir(releases_dir(site_dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_prune_releases_keeps_most_recent() {
+        let sites = tempdir().unwrap();
+        let site_dir = sites.path().join("nullislabs-website");
+        std::fs::create_dir(&site_dir).unwrap();
+        std::fs::write(site_dir.join("index.html"), "live").unwrap();
+
+        let releases = releases_dir(&site_dir);
+        for name in [
+            "20261014T090000.000Z",
+            "20261016T120000.000Z",
+            "20261015T080000.000Z",
+            "20261016T083000.500Z",
+            "20261013T235959.999Z",
+        ] {
+            std::fs::create_dir_all(releases.join(name)).unwrap();
+            std::fs::write(releases.join(name).join("index.html"), name).unwrap();
+        }
+
+        assert_eq!(prune_releases(&site_dir, 3).await.unwrap(), 2);
+        assert_eq!(
+            release_names(&site_dir),
+            [
+                "20261015T080000.000Z",
+                "20261016T083000.500Z",
+                "20261016T120000.000Z"
+            ]
+        );
+
+        // Already within the limit
+        assert_eq!(prune_releases(&site_dir, 3).await.unwrap(), 0);
+
+        // Keeping none removes every release but never the live one
+        assert_eq!(prune_releases(&site_dir, 0).await.unwrap(), 3);
+        assert!(!releases.exists());
+        assert!(site_dir.join("index.html").exists());
+
+        // A site that was never redeployed has nothing to prune
+        let fresh = sites.path().join("nullislabs-docs");
+        assert_eq!(prune_releases(&fresh, 3).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_archives_previous_release() {
+        let sites = tempdir().unwrap();
+        let site_dir = sites.path().join("nullislabs-website");
+        let metadata =
+            SiteMetadata::for_job("nullislabs-website", &test_job(None, "nullislabs.io"));
+
+        for content in ["v1", "v2", "v3"] {
+            let output = release_output(content);
+            deploy_site_files(output.path(), &site_dir, &metadata, 2)
+                .await
+                .unwrap();
+            prune_releases(&site_dir, 2).await.unwrap();
+        }
+
+        assert_eq!(
+            std::fs::read_to_string(site_dir.join("index.html")).unwrap(),
+            "v3"
+        );
+        let releases = releases_dir(&site_dir);
+        let contents: Vec<_> = release_names(&site_dir)
+            .iter()
+            .map(|name| std::fs::read_to_string(releases.join(name).join("index.html")).unwrap())
+            .collect();
+        assert_eq!(contents, ["v1", "v2"]);
+
+        // Archived releases keep their metadata but are never restored as sites
+        let caddy = mock_caddy().await;
+        Mock::given(method("POST"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+        let restored = restore_all_routes(
+            &reqwest::Client::new(),
+            &CaddyAdminApis::single(caddy.uri()),
+            sites.path(),
+            8,
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored, 1);
+    }
+
+    #[test]
+    fn test_site_dir_layouts() {
+        let sites = Path::new("/var/www/sites");
+
+        assert_eq!(
+            site_dir(sites, None, "nullislabs-website").unwrap(),
+            Path::new("/var/www/sites/nullislabs-website")
+        );
+        assert_eq!(
+            site_dir(sites, Some("nullislabs"), "nullislabs-website-pr-42").unwrap(),
+            Path::new("/var/www/sites/nullislabs/nullislabs-website-pr-42")
+        );
+
+        for zone in ["", ".", "..", "a/b", "../etc"] {
+            assert!(site_dir(sites, Some(zone), "site").is_err(), "{}", zone);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restore_routes_across_zones() {
+        let sites = tempdir().unwrap();
+        let output = tempdir().unwrap();
+        std::fs::write(output.path().join("index.html"), "ok").unwrap();
+
+        // Two zones plus a site still in the flat layout
+        for (zone, site_id, domain) in [
+            (Some("nullislabs"), "nullislabs-website", "nullislabs.io"),
+            (Some("acme"), "acme-docs", "docs.acme.dev"),
+            (None, "nullislabs-blog", "blog.nullislabs.io"),
+        ] {
+            let site_dir = site_dir(sites.path(), zone, site_id).unwrap();
+            deploy_site_files(
+                output.path(),
+                &site_dir,
+                &SiteMetadata::for_job(site_id, &test_job(None, domain)),
+                0,
+            )
+            .await
+            .unwrap();
+        }
+
+        // Empty zone directory with nothing deployed yet
+        std::fs::create_dir(sites.path().join("staging")).unwrap();
+
+        let caddy = mock_caddy().await;
+        Mock::given(method("POST"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .and(body_partial_json(serde_json::json!({
+                "@id": "acme-docs",
+                "handle": [{"root": sites.path().join("acme/acme-docs").to_string_lossy()}],
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&caddy)
+            .await;
+
+        let restored = restore_all_routes(
+            &reqwest::Client::new(),
+            &CaddyAdminApis::single(caddy.uri()),
+            sites.path(),
+            8,
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored, 3);
+    }
+
+    #[tokio::test]
+    async fn test_remove_missing_metadata_is_ok() {
+        let dir = tempdir().unwrap();
+        remove_site_metadata(dir.path()).await.unwrap();
+    }
 }
@@ -1,26 +1,46 @@
+use std::time::Duration;
+
 use axum::{
+    Json,
     body::Bytes,
-    extract::State,
+    extract::{Query, State},
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
+use serde::Deserialize;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
 
-use crate::shared::{BuildJob, JobStatus, StatusUpdate, auth::verify_signature};
-use crate::worker::callback::send_status_update;
+use crate::shared::redact::{MAX_BUILD_LOG_BYTES, redact, truncate_log};
+use crate::shared::{BuildJob, DeployConfig, DeploymentPhase, JobStatus, StatusUpdate};
+use crate::worker::builder::BuiltSite;
+use crate::worker::builder::podman::build_secrets;
+use crate::worker::callback::{request_git_token, send_status_update};
+use crate::worker::running::{BuildCancelled, CancelSignal};
 use crate::worker::server::AppState;
 
+/// Query parameters for `/build`
+#[derive(Debug, Default, Deserialize)]
+pub struct BuildParams {
+    /// Block until the build finishes (up to the worker's max wait) and
+    /// return its final status instead of 202
+    #[serde(default)]
+    pub wait: bool,
+}
+
 /// Handle incoming build job requests
 pub async fn handle_build(
     State(state): State<AppState>,
+    Query(params): Query<BuildParams>,
     headers: HeaderMap,
     body: Bytes,
-) -> impl IntoResponse {
+) -> Response {
     // Extract signature and timestamp headers
     let signature = match headers.get("x-central-signature") {
         Some(sig) => sig.to_str().unwrap_or_default(),
         None => {
             tracing::warn!("Missing X-Central-Signature header");
-            return StatusCode::UNAUTHORIZED;
+            return StatusCode::UNAUTHORIZED.into_response();
         }
     };
 
@@ -28,19 +48,20 @@ pub async fn handle_build(
         Some(ts) => ts.to_str().unwrap_or("0").parse().unwrap_or(0),
         None => {
             tracing::warn!("Missing X-Request-Timestamp header");
-            return StatusCode::UNAUTHORIZED;
+            return StatusCode::UNAUTHORIZED.into_response();
         }
     };
 
     // Verify signature
-    if !verify_signature(
-        state.config.worker_shared_secret.as_bytes(),
+    let check = state.config.verifier().check(
         &body,
         signature,
         timestamp,
-    ) {
-        tracing::warn!("Invalid central signature");
-        return StatusCode::UNAUTHORIZED;
+        state.config.signature_tolerance,
+    );
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, "Invalid central signature");
+        return StatusCode::UNAUTHORIZED.into_response();
     }
 
     // Parse build job
@@ -48,7 +69,7 @@ pub async fn handle_build(
         Ok(job) => job,
         Err(e) => {
             tracing::error!(error = %e, "Failed to parse build job");
-            return StatusCode::BAD_REQUEST;
+            return StatusCode::BAD_REQUEST.into_response();
         }
     };
 
@@ -57,20 +78,64 @@ pub async fn handle_build(
         repo = %job.repo_name,
         branch = %job.branch,
         pr = job.pr_number,
+        artifact = job.artifact.is_some(),
         "Received build job"
     );
 
-    // Spawn async build task
-    let state_clone = state.clone();
-    tokio::spawn(async move {
-        execute_build(state_clone, job).await;
-    });
+    // Spawn the build task; it runs to completion even if a waiting caller gives up.
+    // Registered before spawning so a /cancel right after 202 finds it.
+    let job_id = job.job_id;
+    let max_wait = state.config.build_wait_timeout;
+    let cancel = state.running.register(job_id);
+    let build = tokio::spawn(execute_build(state, job, cancel));
+
+    if params.wait {
+        wait_for_build(job_id, build, max_wait).await
+    } else {
+        // Return 202 Accepted immediately
+        StatusCode::ACCEPTED.into_response()
+    }
+}
 
-    // Return 202 Accepted immediately
-    StatusCode::ACCEPTED
+/// Wait for a spawned build and return its final status
+///
+/// On timeout the build keeps running and still reports through callbacks;
+/// the caller gets 202 with the job still `building`.
+async fn wait_for_build(
+    job_id: Uuid,
+    build: JoinHandle<StatusUpdate>,
+    max_wait: Duration,
+) -> Response {
+    match tokio::time::timeout(max_wait, build).await {
+        Ok(Ok(update)) => (StatusCode::OK, Json(update)).into_response(),
+        Ok(Err(e)) => {
+            tracing::error!(error = %e, job_id = %job_id, "Build task failed");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(_) => {
+            tracing::info!(job_id = %job_id, max_wait_secs = max_wait.as_secs(), "Stopped waiting for build");
+            (
+                StatusCode::ACCEPTED,
+                Json(StatusUpdate {
+                    job_id,
+                    status: JobStatus::Building,
+                    deployed_url: None,
+                    error_message: None,
+                    event: None,
+                    zone: None,
+                    size_bytes: None,
+                    worker_id: None,
+                }),
+            )
+                .into_response()
+        }
+    }
 }
 
-async fn execute_build(state: AppState, job: BuildJob) {
+/// Run a build job to completion, reporting progress to Central
+///
+/// Returns the final status that was sent to the callback URL.
+async fn execute_build(state: AppState, job: BuildJob, cancel: CancelSignal) -> StatusUpdate {
     let job_id = job.job_id;
     let callback_url = job.callback_url.clone();
 
@@ -78,12 +143,16 @@ async fn execute_build(state: AppState, job: BuildJob) {
     if let Err(e) = send_status_update(
         &state.http_client,
         &callback_url,
-        &state.config.worker_shared_secret,
+        state.config.signer(),
         StatusUpdate {
             job_id,
             status: JobStatus::Building,
             deployed_url: None,
             error_message: None,
+            event: None,
+            zone: job.environment.clone(),
+            size_bytes: None,
+            worker_id: job.worker_id,
         },
     )
     .await
@@ -92,130 +161,853 @@ async fn execute_build(state: AppState, job: BuildJob) {
     }
 
     // Execute the build pipeline
-    match run_build_pipeline(&state, &job).await {
-        Ok(deployed_url) => {
-            tracing::info!(job_id = %job_id, url = %deployed_url, "Build successful");
+    let result = run_build_pipeline(&state, &job, cancel).await;
+    state.running.complete(job_id);
 
-            if let Err(e) = send_status_update(
-                &state.http_client,
-                &callback_url,
-                &state.config.worker_shared_secret,
-                StatusUpdate {
-                    job_id,
-                    status: JobStatus::Success,
-                    deployed_url: Some(deployed_url),
-                    error_message: None,
-                },
-            )
-            .await
-            {
-                tracing::error!(error = %e, "Failed to send success status");
+    let update = match result {
+        Ok(deployed) => {
+            tracing::info!(job_id = %job_id, url = ?deployed.url, size_bytes = ?deployed.size_bytes, "Build successful");
+
+            StatusUpdate {
+                job_id,
+                status: JobStatus::Success,
+                deployed_url: deployed.url,
+                error_message: None,
+                event: None,
+                zone: job.environment.clone(),
+                size_bytes: deployed.size_bytes,
+                worker_id: job.worker_id,
+            }
+        }
+        Err(e) if e.is::<BuildCancelled>() => {
+            tracing::info!(job_id = %job_id, "Build cancelled");
+
+            StatusUpdate {
+                job_id,
+                status: JobStatus::Cancelled,
+                deployed_url: None,
+                error_message: None,
+                event: None,
+                zone: job.environment.clone(),
+                size_bytes: None,
+                worker_id: job.worker_id,
             }
         }
         Err(e) => {
-            tracing::error!(job_id = %job_id, error = %e, "Build failed");
+            // Errors can embed build output or the clone URL, so scrub secrets first
+            let secrets = build_secrets(&state.config, &job);
+            let error_message =
+                truncate_log(&redact(&e.to_string(), &secrets), MAX_BUILD_LOG_BYTES);
+            tracing::error!(job_id = %job_id, error = %error_message, "Build failed");
 
-            if let Err(e2) = send_status_update(
-                &state.http_client,
-                &callback_url,
-                &state.config.worker_shared_secret,
-                StatusUpdate {
-                    job_id,
-                    status: JobStatus::Failed,
-                    deployed_url: None,
-                    error_message: Some(e.to_string()),
-                },
-            )
-            .await
-            {
-                tracing::error!(error = %e2, "Failed to send failure status");
+            StatusUpdate {
+                job_id,
+                status: JobStatus::Failed,
+                deployed_url: None,
+                error_message: Some(error_message),
+                event: None,
+                zone: job.environment.clone(),
+                size_bytes: None,
+                worker_id: job.worker_id,
             }
         }
+    };
+
+    if let Err(e) = send_status_update(
+        &state.http_client,
+        &callback_url,
+        state.config.signer(),
+        update.clone(),
+    )
+    .await
+    {
+        tracing::error!(error = %e, status = %update.status, "Failed to send final status");
+        // Central can still learn the outcome by querying /status
+        state.running.record_undelivered(update.clone());
     }
+
+    update
 }
 
-async fn run_build_pipeline(state: &AppState, job: &BuildJob) -> anyhow::Result<String> {
+/// Outcome of a successful build pipeline
+struct Deployed {
+    /// Where the site is served (None for dry runs, which publish nothing)
+    url: Option<String>,
+    /// Size of the deployed files, if it could be measured
+    size_bytes: Option<u64>,
+}
+
+async fn run_build_pipeline(
+    state: &AppState,
+    job: &BuildJob,
+    mut cancel: CancelSignal,
+) -> anyhow::Result<Deployed> {
     use crate::shared::generate_site_id;
-    use crate::worker::builder::{clone_repository, run_build};
-    use crate::worker::deploy::{SiteMetadata, configure_caddy_route, write_site_metadata};
+    use crate::worker::deploy::{
+        SiteMetadata, configure_caddy_route, deploy_canary_files, deploy_site_files, dir_size,
+        precompress_dir, prune_releases, read_site_metadata, site_dir, validate_caddy_extra,
+        wait_for_site_ready,
+    };
 
-    let site_id = generate_site_id(&job.org_name, &job.repo_name, job.pr_number);
+    let site_id = job
+        .site_id
+        .clone()
+        .unwrap_or_else(|| generate_site_id(&job.org_name, &job.repo_name, job.pr_number));
+
+    // A snippet Caddy can't take must fail the job before the live site is touched
+    if let Some(extra) = &job.caddy_extra {
+        validate_caddy_extra(extra)?;
+    }
 
     // Create work directory
     let work_dir = std::env::temp_dir().join(format!("catapult-{}", job.job_id));
     tokio::fs::create_dir_all(&work_dir).await?;
 
-    // Clone repository
-    tracing::info!(job_id = %job.job_id, "Cloning repository");
-    let repo_dir =
-        clone_repository(&job.repo_url, &job.git_token, &job.commit_sha, &work_dir).await?;
+    // Cancellation drops the in-flight clone/build; its processes are killed on drop
+    let built = tokio::select! {
+        biased;
+        () = cancel.cancelled() => {
+            let _ = tokio::fs::remove_dir_all(&work_dir).await;
+            return Err(BuildCancelled.into());
+        }
+        built = build_output(state, job, &work_dir) => built?,
+    };
+    let output_dir = built.output_dir;
 
-    // Run build in container
-    tracing::info!(job_id = %job.job_id, "Running build");
-    let output_dir = run_build(state, job, &repo_dir).await?;
+    // Dry runs only check that the site builds; nothing is published
+    if job.dry_run {
+        tracing::info!(job_id = %job.job_id, "Dry run built, skipping deploy");
+        let _ = tokio::fs::remove_dir_all(&work_dir).await;
+        return Ok(Deployed {
+            url: None,
+            size_bytes: None,
+        });
+    }
 
-    // Deploy to sites directory
-    let site_dir = state.config.sites_dir.join(&site_id);
-    tracing::info!(job_id = %job.job_id, site_dir = %site_dir.display(), "Deploying artifacts");
+    // Deploying must run to completion, so the build can't be cancelled any more
+    state.running.finish(job.job_id);
 
-    // Remove old deployment if exists
-    if site_dir.exists() {
-        tokio::fs::remove_dir_all(&site_dir).await?;
+    // Optional compression pass so Caddy can serve .br/.gz directly
+    if job.precompress {
+        let count = precompress_dir(&output_dir).await?;
+        tracing::info!(job_id = %job.job_id, files = count, "Pre-compressed assets");
     }
 
-    // Copy build artifacts
-    copy_dir_recursive(&output_dir, &site_dir).await?;
+    // Deploy to sites directory
+    let zone = job
+        .environment
+        .as_deref()
+        .filter(|_| state.config.sites_dir_per_zone);
+    let site_dir = site_dir(&state.config.sites_dir, zone, &site_id)?;
+    tracing::info!(job_id = %job.job_id, site_dir = %site_dir.display(), "Deploying artifacts");
+
+    // Aliases dropped since the last full deploy lose their DNS once it's replaced
+    let previous = read_site_metadata(&site_dir).await.ok().flatten();
 
-    // Write site metadata for route restoration on restart
-    let metadata = SiteMetadata {
-        site_id: site_id.clone(),
-        domain: job.domain.clone(),
+    // Copy build artifacts and write site metadata for route restoration on restart
+    let mut release = SiteMetadata::for_job(&site_id, job);
+    release.site_type = Some(built.site_type);
+    release.immutable_paths = built.immutable_paths;
+    let canary = match canary_percent(job) {
+        Some(percent) => {
+            deploy_canary_files(&output_dir, &site_dir, release.clone(), percent).await?
+        }
+        None => None,
+    };
+    let mut maintenance = false;
+    let (metadata, replaced) = match canary {
+        Some(metadata) => {
+            tracing::info!(job_id = %job.job_id, percent = ?job.canary_percent, "Staged canary release");
+            (metadata, None)
+        }
+        None => {
+            // Visitors get the maintenance page rather than a half-replaced site
+            if let Some(live) = &previous {
+                maintenance =
+                    show_maintenance(state, job, &site_id, &site_dir, &output_dir, live).await;
+            }
+            let deployed =
+                deploy_site_files(&output_dir, &site_dir, &release, state.config.keep_releases)
+                    .await;
+            if let Err(e) = deployed {
+                // Best effort: what's left of the live release beats the page
+                if maintenance && let Some(live) = &previous {
+                    restore_route(state, job, &site_id, &site_dir, live).await;
+                }
+                return Err(e);
+            }
+            (release, previous)
+        }
     };
-    write_site_metadata(&site_dir, &metadata).await?;
+    report_phase(state, job, DeploymentPhase::Deployed).await;
 
     // Configure Caddy route
     // Domain is already fully resolved by central server (includes PR subdomain if applicable)
     let deployed_url = crate::shared::generate_preview_url(&job.domain);
 
-    configure_caddy_route(
+    let routed = configure_caddy_route(
         &state.http_client,
-        &state.config.caddy_admin_api,
+        state
+            .config
+            .caddy_admin_api
+            .for_zone(job.environment.as_deref()),
         &site_id,
         &site_dir,
         &job.domain,
+        &metadata.route_options(&site_dir),
     )
-    .await?;
+    .await;
+    if let Err(e) = routed {
+        // The new files are in place; serving them on the previous route
+        // beats leaving the maintenance page up
+        if maintenance && let Some(live) = &replaced {
+            restore_route(state, job, &site_id, &site_dir, live).await;
+        }
+        return Err(e);
+    }
+    report_phase(state, job, DeploymentPhase::CaddyConfigured).await;
+
+    // Old releases are only pruned once the new one is routed
+    if let Err(e) = prune_releases(&site_dir, state.config.keep_releases).await {
+        tracing::warn!(error = %e, site_dir = %site_dir.display(), "Failed to prune old releases");
+    }
 
-    // Configure Cloudflare DNS and tunnel ingress
+    // Configure Cloudflare DNS and tunnel ingress for the domain and each alias
     // The domain field contains the full hostname (e.g., "pr-42-website.nxm.rs")
     if state.cloudflare.is_enabled() {
-        tracing::info!(job_id = %job.job_id, hostname = %job.domain, "Configuring Cloudflare route");
-        if let Err(e) = state.cloudflare.ensure_route(&job.domain).await {
-            // Log but don't fail the build - Caddy is already configured
-            tracing::error!(error = %e, hostname = %job.domain, "Failed to configure Cloudflare route");
+        let hostnames = metadata.hostnames();
+        let mut configured = true;
+        for hostname in &hostnames {
+            tracing::info!(job_id = %job.job_id, hostname = %hostname, "Configuring Cloudflare route");
+            if let Err(e) = state
+                .cloudflare
+                .ensure_route(hostname, job.environment.as_deref())
+                .await
+            {
+                // Log but don't fail the build - Caddy is already configured
+                tracing::error!(error = %e, hostname = %hostname, "Failed to configure Cloudflare route");
+                configured = false;
+            }
+        }
+        if configured {
+            report_phase(state, job, DeploymentPhase::CloudflareConfigured).await;
+        }
+
+        // Don't let Cloudflare keep serving the previous release's assets
+        if let Some(scope) = job.purge_cache.filter(|_| job.pr_number.is_none())
+            && let Err(e) = state
+                .cloudflare
+                .purge_cache(&hostnames, scope, job.environment.as_deref())
+                .await
+        {
+            // The deploy itself succeeded; stale cache expires on its own
+            tracing::warn!(job_id = %job.job_id, error = %e, "Failed to purge Cloudflare cache");
+        }
+
+        let dropped = replaced
+            .iter()
+            .flat_map(|previous| previous.hostnames())
+            .filter(|hostname| !hostnames.contains(hostname));
+        for hostname in dropped {
+            tracing::info!(job_id = %job.job_id, hostname = %hostname, "Removing Cloudflare route for dropped alias");
+            if let Err(e) = state
+                .cloudflare
+                .remove_route(&hostname, job.environment.as_deref())
+                .await
+            {
+                tracing::warn!(error = %e, hostname = %hostname, "Failed to remove Cloudflare route");
+            }
         }
     }
 
+    // Measured before the work directory goes; Central uses it for disk quotas
+    let size_bytes = match dir_size(&output_dir).await {
+        Ok(size) => Some(size),
+        Err(e) => {
+            tracing::warn!(error = %e, job_id = %job.job_id, "Failed to measure deployment size");
+            None
+        }
+    };
+
     // Cleanup work directory
     let _ = tokio::fs::remove_dir_all(&work_dir).await;
 
-    Ok(deployed_url)
+    // Only report success once the site is actually served
+    if let Some(timeout) = state.config.deploy_health_check_timeout {
+        wait_for_site_ready(
+            &state.http_client,
+            &deployed_url,
+            job.basic_auth.is_some(),
+            timeout,
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("Deployed but unreachable at {}: {:#}", deployed_url, e))?;
+        report_phase(state, job, DeploymentPhase::Reachable).await;
+    }
+
+    Ok(Deployed {
+        url: Some(deployed_url),
+        size_bytes,
+    })
 }
 
-async fn copy_dir_recursive(src: &std::path::Path, dst: &std::path::Path) -> anyhow::Result<()> {
-    tokio::fs::create_dir_all(dst).await?;
+/// Serve the job's maintenance page on the live site's route while its
+/// files are replaced; the route is pointed at the new release afterwards
+///
+/// Best effort: a page that can't be read or routed doesn't hold up the
+/// deploy. Returns whether the page is being served.
+async fn show_maintenance(
+    state: &AppState,
+    job: &BuildJob,
+    site_id: &str,
+    site_dir: &std::path::Path,
+    output_dir: &std::path::Path,
+    live: &crate::worker::deploy::SiteMetadata,
+) -> bool {
+    use crate::worker::deploy::{read_maintenance_page, show_maintenance_page};
 
-    let mut entries = tokio::fs::read_dir(src).await?;
-    while let Some(entry) = entries.next_entry().await? {
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
+    // Only main branch deployments have visitors to keep informed
+    let Some(page) = job
+        .maintenance_page
+        .as_deref()
+        .filter(|_| job.pr_number.is_none())
+    else {
+        return false;
+    };
+
+    let shown = async {
+        let page = read_maintenance_page(output_dir, page).await?;
+        show_maintenance_page(
+            &state.http_client,
+            state
+                .config
+                .caddy_admin_api
+                .for_zone(job.environment.as_deref()),
+            site_id,
+            &live.domain,
+            &page,
+            &live.route_options(site_dir),
+        )
+        .await
+    }
+    .await;
 
-        if entry.file_type().await?.is_dir() {
-            Box::pin(copy_dir_recursive(&src_path, &dst_path)).await?;
-        } else {
-            tokio::fs::copy(&src_path, &dst_path).await?;
+    match shown {
+        Ok(shown) => shown,
+        Err(e) => {
+            tracing::warn!(job_id = %job.job_id, error = %e, "Failed to serve maintenance page");
+            false
         }
     }
+}
+
+/// Point the site's route back at `live`'s settings after a failed deploy
+/// left the maintenance page up
+///
+/// Best effort: the deploy has already failed, so a failure here is only logged.
+async fn restore_route(
+    state: &AppState,
+    job: &BuildJob,
+    site_id: &str,
+    site_dir: &std::path::Path,
+    live: &crate::worker::deploy::SiteMetadata,
+) {
+    use crate::worker::deploy::configure_caddy_route;
 
-    Ok(())
+    let restored = configure_caddy_route(
+        &state.http_client,
+        state
+            .config
+            .caddy_admin_api
+            .for_zone(job.environment.as_deref()),
+        site_id,
+        site_dir,
+        &live.domain,
+        &live.route_options(site_dir),
+    )
+    .await;
+    if let Err(e) = restored {
+        tracing::warn!(job_id = %job.job_id, error = %e, "Failed to take down maintenance page");
+    }
+}
+
+/// Fetch the artifact or clone and build the repository into `work_dir`
+async fn build_output(
+    state: &AppState,
+    job: &BuildJob,
+    work_dir: &std::path::Path,
+) -> anyhow::Result<BuiltSite> {
+    use crate::worker::builder::{CloneOptions, clone_repository, run_build};
+    use crate::worker::deploy::fetch_artifact;
+
+    let built = match &job.artifact {
+        // Built elsewhere (e.g. the team's own CI), nothing to clone or build
+        Some(artifact) => {
+            tracing::info!(job_id = %job.job_id, "Fetching prebuilt artifact");
+            let output_dir = fetch_artifact(
+                &state.http_client,
+                artifact,
+                work_dir,
+                state.config.max_artifact_bytes,
+                state.config.clone_timeout,
+            )
+            .await?;
+            let config = DeployConfig {
+                immutable_paths: job.immutable_paths.clone(),
+                ..Default::default()
+            };
+            BuiltSite {
+                output_dir,
+                site_type: job.site_type,
+                immutable_paths: config.resolve_immutable_paths(job.site_type),
+            }
+        }
+        None => {
+            // Clone repository; a job that waited long enough for its token
+            // to expire gets a fresh one from Central
+            tracing::info!(job_id = %job.job_id, "Cloning repository");
+            let repo_dir = clone_repository(
+                &job.repo_url,
+                &job.git_token,
+                &job.commit_sha,
+                work_dir,
+                CloneOptions {
+                    timeout: state.config.clone_timeout,
+                    max_bytes: state.config.max_repo_bytes,
+                    git_lfs: job.git_lfs,
+                },
+                || {
+                    request_git_token(
+                        &state.http_client,
+                        &state.config.central_url,
+                        state.config.signer(),
+                        job.job_id,
+                    )
+                },
+            )
+            .await?;
+            report_phase(state, job, DeploymentPhase::Cloned).await;
+
+            // Run build in container
+            tracing::info!(job_id = %job.job_id, "Running build");
+            report_phase(state, job, DeploymentPhase::Building).await;
+            run_build(state, job, &repo_dir).await?
+        }
+    };
+
+    Ok(built)
+}
+
+/// Canary percentage to roll this job out with, if any
+///
+/// Only main branch deploys are split; 100% is a regular deploy.
+fn canary_percent(job: &BuildJob) -> Option<u8> {
+    job.canary_percent
+        .filter(|percent| (1..100).contains(percent))
+        .filter(|_| job.pr_number.is_none())
+}
+
+/// Report a completed lifecycle phase to Central's deployment event log
+///
+/// Failures are logged only; the timeline is diagnostic and must not fail the build.
+async fn report_phase(state: &AppState, job: &BuildJob, phase: DeploymentPhase) {
+    if let Err(e) = send_status_update(
+        &state.http_client,
+        &job.callback_url,
+        state.config.signer(),
+        StatusUpdate {
+            job_id: job.job_id,
+            status: JobStatus::Building,
+            deployed_url: None,
+            error_message: None,
+            event: Some(phase),
+            zone: job.environment.clone(),
+            size_bytes: None,
+            worker_id: job.worker_id,
+        },
+    )
+    .await
+    {
+        tracing::warn!(error = %e, job_id = %job.job_id, phase = %phase, "Failed to report deployment phase");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use axum::extract::State;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::config::WorkerConfig;
+    use crate::shared::{ArtifactSource, CancelJob, SiteType, auth::sign_request};
+    use crate::worker::deploy::CloudflareClient;
+    use crate::worker::handlers::handle_cancel;
+
+    async fn response_update(response: Response) -> StatusUpdate {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn final_update(job_id: Uuid) -> StatusUpdate {
+        StatusUpdate {
+            job_id,
+            status: JobStatus::Success,
+            deployed_url: Some("https://nullislabs.io".to_string()),
+            error_message: None,
+            event: None,
+            zone: None,
+            size_bytes: None,
+            worker_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_returns_final_status() {
+        let job_id = Uuid::new_v4();
+        let build = tokio::spawn(async move { final_update(job_id) });
+
+        let response = wait_for_build(job_id, build, Duration::from_secs(5)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let update = response_update(response).await;
+        assert_eq!(update.job_id, job_id);
+        assert_eq!(update.status, JobStatus::Success);
+        assert_eq!(
+            update.deployed_url.as_deref(),
+            Some("https://nullislabs.io")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_timeout_leaves_build_running() {
+        let job_id = Uuid::new_v4();
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+        let build = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            let _ = done_tx.send(());
+            final_update(job_id)
+        });
+
+        let response = wait_for_build(job_id, build, Duration::from_millis(50)).await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let update = response_update(response).await;
+        assert_eq!(update.job_id, job_id);
+        assert_eq!(update.status, JobStatus::Building);
+
+        // The build wasn't cancelled by the caller giving up
+        tokio::time::timeout(Duration::from_secs(5), done_rx)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    fn artifact_job(server: &MockServer) -> BuildJob {
+        BuildJob {
+            job_id: Uuid::new_v4(),
+            repo_url: "https://github.com/nullisLabs/website.git".to_string(),
+            git_token: "token".to_string(),
+            branch: "main".to_string(),
+            commit_sha: "abc123".to_string(),
+            pr_number: Some(42),
+            site_id: None,
+            domain: "pr-42.nullislabs.io".to_string(),
+            aliases: Vec::new(),
+            site_type: SiteType::Auto,
+            callback_url: format!("{}/api/status", server.uri()),
+            repo_name: "website".to_string(),
+            org_name: "nullisLabs".to_string(),
+            subdomain: None,
+            build_args: Default::default(),
+            basic_auth: None,
+            precompress: false,
+            git_lfs: false,
+            index_names: Vec::new(),
+            browse: false,
+            caddy_extra: None,
+            isolation: None,
+            dry_run: false,
+            worker_id: None,
+            memory_mb: None,
+            cpu_cores: None,
+            pids_limit: None,
+            node_version: None,
+            environment: None,
+            artifact: Some(ArtifactSource {
+                url: format!("{}/site.tar.gz", server.uri()),
+                sha256: None,
+            }),
+            canary_percent: None,
+            immutable_paths: None,
+            purge_cache: None,
+            maintenance_page: None,
+        }
+    }
+
+    fn site_archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::fast(),
+        ));
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            archive
+                .append_data(&mut header, name, contents.as_bytes())
+                .unwrap();
+        }
+        archive.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn test_state(sites_dir: &std::path::Path, server: &MockServer) -> AppState {
+        AppState {
+            config: Arc::new(WorkerConfig::for_tests(
+                sites_dir.to_path_buf(),
+                &server.uri(),
+            )),
+            http_client: reqwest::Client::new(),
+            cloudflare: CloudflareClient::disabled(),
+            running: Default::default(),
+            isolation: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_builds_without_deploying() {
+        let archive = site_archive(&[("index.html", "ok")]);
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/site.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(archive))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/status"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        // The same server stands in for Caddy, which mustn't be touched
+        let sites = tempfile::tempdir().unwrap();
+        let state = test_state(sites.path(), &server);
+        let job = BuildJob {
+            dry_run: true,
+            ..artifact_job(&server)
+        };
+        let job_id = job.job_id;
+
+        let signal = state.running.register(job_id);
+        let update = execute_build(state.clone(), job, signal).await;
+        assert_eq!(update.status, JobStatus::Success);
+        assert_eq!(update.deployed_url, None);
+
+        // Nothing was published or left behind
+        assert_eq!(std::fs::read_dir(sites.path()).unwrap().count(), 0);
+        assert!(
+            !std::env::temp_dir()
+                .join(format!("catapult-{}", job_id))
+                .exists()
+        );
+        let requests = server.received_requests().await.unwrap();
+        assert!(
+            requests
+                .iter()
+                .all(|r| ["/site.tar.gz", "/api/status"].contains(&r.url.path()))
+        );
+
+        // Central still hears the build succeeded
+        let last: StatusUpdate = serde_json::from_slice(&requests.last().unwrap().body).unwrap();
+        assert_eq!(last.job_id, job_id);
+        assert_eq!(last.status, JobStatus::Success);
+    }
+
+    #[tokio::test]
+    async fn test_failed_route_takes_down_maintenance_page() {
+        let archive = site_archive(&[("index.html", "new"), ("maintenance.html", "Back soon")]);
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/site.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(archive))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/status"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        // Caddy shows the maintenance page, rejects the new route, then
+        // takes the restored one
+        for status in [200, 500, 200] {
+            Mock::given(method("PATCH"))
+                .and(path("/id/nullislabs-website"))
+                .respond_with(ResponseTemplate::new(status))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+
+        let sites = tempfile::tempdir().unwrap();
+        let state = test_state(sites.path(), &server);
+        let job = BuildJob {
+            pr_number: None,
+            site_id: Some("nullislabs-website".to_string()),
+            domain: "nullislabs.io".to_string(),
+            maintenance_page: Some("maintenance.html".to_string()),
+            ..artifact_job(&server)
+        };
+
+        // A live release for the maintenance page to stand in for
+        let live = tempfile::tempdir().unwrap();
+        std::fs::write(live.path().join("index.html"), "old").unwrap();
+        let site_dir = sites.path().join("nullislabs-website");
+        crate::worker::deploy::deploy_site_files(
+            live.path(),
+            &site_dir,
+            &crate::worker::deploy::SiteMetadata::for_job("nullislabs-website", &job),
+            0,
+        )
+        .await
+        .unwrap();
+
+        let signal = state.running.register(job.job_id);
+        let update = execute_build(state.clone(), job, signal).await;
+        assert_eq!(update.status, JobStatus::Failed);
+
+        // The last route Caddy was given serves files again, not the page
+        let requests = server.received_requests().await.unwrap();
+        let patches: Vec<_> = requests
+            .iter()
+            .filter(|r| r.method.as_str() == "PATCH")
+            .collect();
+        assert_eq!(patches.len(), 3);
+        let page = String::from_utf8_lossy(&patches[0].body);
+        assert!(page.contains("Back soon"));
+        let restored = String::from_utf8_lossy(&patches[2].body);
+        assert!(!restored.contains("Back soon"));
+        assert!(restored.contains("file_server"));
+    }
+
+    async fn cancel(state: &AppState, job_id: Uuid) -> StatusCode {
+        let body = serde_json::to_vec(&CancelJob { job_id }).unwrap();
+        let (signature, timestamp) = sign_request(b"test-secret", &body);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-central-signature", signature.parse().unwrap());
+        headers.insert(
+            "x-request-timestamp",
+            timestamp.to_string().parse().unwrap(),
+        );
+
+        handle_cancel(State(state.clone()), headers, Bytes::from(body))
+            .await
+            .into_response()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_cancel_in_flight_build() {
+        let server = MockServer::start().await;
+        // The artifact download hangs, keeping the build in flight
+        Mock::given(method("GET"))
+            .and(path("/site.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/status"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let sites = tempfile::tempdir().unwrap();
+        let state = AppState {
+            config: Arc::new(WorkerConfig::for_tests(
+                sites.path().to_path_buf(),
+                &server.uri(),
+            )),
+            http_client: reqwest::Client::new(),
+            cloudflare: CloudflareClient::disabled(),
+            running: Default::default(),
+            isolation: Default::default(),
+        };
+        let job = artifact_job(&server);
+        let job_id = job.job_id;
+
+        let signal = state.running.register(job_id);
+        let build = tokio::spawn(execute_build(state.clone(), job, signal));
+
+        // Wait for the download to start
+        for _ in 0..50 {
+            let requests = server.received_requests().await.unwrap();
+            if requests.iter().any(|r| r.url.path() == "/site.tar.gz") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(cancel(&state, job_id).await, StatusCode::OK);
+
+        let update = tokio::time::timeout(Duration::from_secs(5), build)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.status, JobStatus::Cancelled);
+        assert!(
+            !std::env::temp_dir()
+                .join(format!("catapult-{}", job_id))
+                .exists()
+        );
+
+        // Central is told the build was cancelled
+        let requests = server.received_requests().await.unwrap();
+        let last: StatusUpdate = serde_json::from_slice(&requests.last().unwrap().body).unwrap();
+        assert_eq!(last.job_id, job_id);
+        assert_eq!(last.status, JobStatus::Cancelled);
+
+        // Nothing left to cancel
+        assert_eq!(cancel(&state, job_id).await, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_rejects_bad_signature() {
+        let sites = tempfile::tempdir().unwrap();
+        let state = AppState {
+            config: Arc::new(WorkerConfig::for_tests(
+                sites.path().to_path_buf(),
+                "http://localhost:2019",
+            )),
+            http_client: reqwest::Client::new(),
+            cloudflare: CloudflareClient::disabled(),
+            running: Default::default(),
+            isolation: Default::default(),
+        };
+        let job_id = Uuid::new_v4();
+        let _signal = state.running.register(job_id);
+
+        let body = serde_json::to_vec(&CancelJob { job_id }).unwrap();
+        let (signature, timestamp) = sign_request(b"wrong-secret", &body);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-central-signature", signature.parse().unwrap());
+        headers.insert(
+            "x-request-timestamp",
+            timestamp.to_string().parse().unwrap(),
+        );
+
+        let status = handle_cancel(State(state.clone()), headers, Bytes::from(body))
+            .await
+            .into_response()
+            .status();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // Still cancellable with a valid request
+        assert_eq!(cancel(&state, job_id).await, StatusCode::OK);
+    }
 }
@@ -0,0 +1,75 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::shared::CancelJob;
+use crate::worker::builder::podman::remove_build_container;
+use crate::worker::server::AppState;
+
+/// Handle build cancellation requests
+///
+/// `200` once the build has been signalled to stop (its final `cancelled`
+/// status still arrives through the callback), `404` if the job isn't a
+/// build on this worker that can still be cancelled.
+pub async fn handle_cancel(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    // Extract signature and timestamp headers
+    let signature = match headers.get("x-central-signature") {
+        Some(sig) => sig.to_str().unwrap_or_default(),
+        None => {
+            tracing::warn!("Missing X-Central-Signature header");
+            return StatusCode::UNAUTHORIZED;
+        }
+    };
+
+    let timestamp: u64 = match headers.get("x-request-timestamp") {
+        Some(ts) => ts.to_str().unwrap_or("0").parse().unwrap_or(0),
+        None => {
+            tracing::warn!("Missing X-Request-Timestamp header");
+            return StatusCode::UNAUTHORIZED;
+        }
+    };
+
+    // Verify signature
+    let check = state.config.verifier().check(
+        &body,
+        signature,
+        timestamp,
+        state.config.signature_tolerance,
+    );
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, "Invalid central signature");
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    // Parse cancel job
+    let job: CancelJob = match serde_json::from_slice(&body) {
+        Ok(job) => job,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to parse cancel job");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    tracing::info!(job_id = %job.job_id, "Received cancel job");
+
+    let Some(container) = state.running.cancel(job.job_id) else {
+        tracing::warn!(job_id = %job.job_id, "No cancellable build for job");
+        return StatusCode::NOT_FOUND;
+    };
+
+    // Dropping the build future doesn't stop a container, so remove it
+    if let Some(container) = container
+        && let Err(e) = remove_build_container(&state.config.podman_socket, &container).await
+    {
+        tracing::error!(job_id = %job.job_id, container = %container, error = %e, "Failed to remove build container");
+    }
+
+    StatusCode::OK
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use axum::{
     body::Bytes,
     extract::State,
@@ -5,9 +7,12 @@ use axum::{
     response::IntoResponse,
 };
 
-use crate::shared::{CleanupJob, JobStatus, StatusUpdate, auth::verify_signature};
+use crate::shared::{CleanupJob, JobStatus, StatusUpdate};
 use crate::worker::callback::send_status_update;
-use crate::worker::deploy::remove_caddy_route;
+use crate::worker::deploy::{
+    SiteMetadata, canary_dir, read_site_metadata, releases_dir, remove_caddy_route,
+    remove_site_metadata, site_dir,
+};
 use crate::worker::server::AppState;
 
 /// Handle cleanup job requests
@@ -34,13 +39,14 @@ pub async fn handle_cleanup(
     };
 
     // Verify signature
-    if !verify_signature(
-        state.config.worker_shared_secret.as_bytes(),
+    let check = state.config.verifier().check(
         &body,
         signature,
         timestamp,
-    ) {
-        tracing::warn!("Invalid central signature");
+        state.config.signature_tolerance,
+    );
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, "Invalid central signature");
         return StatusCode::UNAUTHORIZED;
     }
 
@@ -79,12 +85,16 @@ async fn execute_cleanup(state: AppState, job: CleanupJob) {
             if let Err(e) = send_status_update(
                 &state.http_client,
                 &job.callback_url,
-                &state.config.worker_shared_secret,
+                state.config.signer(),
                 StatusUpdate {
                     job_id,
                     status: JobStatus::Cleaned,
                     deployed_url: None,
                     error_message: None,
+                    event: None,
+                    zone: job.environment.clone(),
+                    size_bytes: None,
+                    worker_id: None,
                 },
             )
             .await
@@ -98,12 +108,16 @@ async fn execute_cleanup(state: AppState, job: CleanupJob) {
             if let Err(e2) = send_status_update(
                 &state.http_client,
                 &job.callback_url,
-                &state.config.worker_shared_secret,
+                state.config.signer(),
                 StatusUpdate {
                     job_id,
                     status: JobStatus::Failed,
                     deployed_url: None,
                     error_message: Some(e.to_string()),
+                    event: None,
+                    zone: job.environment.clone(),
+                    size_bytes: None,
+                    worker_id: None,
                 },
             )
             .await
@@ -115,31 +129,342 @@ async fn execute_cleanup(state: AppState, job: CleanupJob) {
 }
 
 async fn run_cleanup(state: &AppState, job: &CleanupJob) -> anyhow::Result<()> {
+    // Site directories, checking the flat layout too for sites deployed
+    // before per-zone directories were enabled
+    let mut site_dirs = vec![site_dir(&state.config.sites_dir, None, &job.site_id)?];
+    if state.config.sites_dir_per_zone
+        && let Some(zone) = job.environment.as_deref()
+    {
+        site_dirs.push(site_dir(&state.config.sites_dir, Some(zone), &job.site_id)?);
+    }
+
+    // Aliases are only recorded in the site's metadata (as is the domain for
+    // older Centrals), which must be read before the directory is removed
+    let mut hostnames: Vec<String> = job.domain.iter().cloned().collect();
+    if let Some(metadata) = first_metadata(&site_dirs).await {
+        for hostname in metadata.hostnames() {
+            if !hostnames.contains(&hostname) {
+                hostnames.push(hostname);
+            }
+        }
+    }
+
     // Remove Caddy route
     remove_caddy_route(
         &state.http_client,
-        &state.config.caddy_admin_api,
+        state
+            .config
+            .caddy_admin_api
+            .for_zone(job.environment.as_deref()),
         &job.site_id,
     )
     .await?;
 
-    // Remove Cloudflare DNS and tunnel ingress (if domain is provided)
-    if let Some(domain) = &job.domain
-        && state.cloudflare.is_enabled()
-    {
-        tracing::info!(job_id = %job.job_id, hostname = %domain, "Removing Cloudflare route");
-        if let Err(e) = state.cloudflare.remove_route(domain).await {
-            // Log but don't fail cleanup - Caddy route is already removed
-            tracing::error!(error = %e, hostname = %domain, "Failed to remove Cloudflare route");
+    // Remove Cloudflare DNS and tunnel ingress for every hostname
+    if state.cloudflare.is_enabled() {
+        for hostname in &hostnames {
+            tracing::info!(job_id = %job.job_id, hostname = %hostname, "Removing Cloudflare route");
+            if let Err(e) = state
+                .cloudflare
+                .remove_route(hostname, job.environment.as_deref())
+                .await
+            {
+                // Log but don't fail cleanup - Caddy route is already removed
+                tracing::error!(error = %e, hostname = %hostname, "Failed to remove Cloudflare route");
+            }
         }
     }
 
-    // Remove site directory
-    let site_dir = state.config.sites_dir.join(&job.site_id);
-    if site_dir.exists() {
-        tokio::fs::remove_dir_all(&site_dir).await?;
-        tracing::info!(site_dir = %site_dir.display(), "Removed site directory");
+    // Remove site directories
+    for site_dir in site_dirs {
+        if site_dir.exists() {
+            // Drop the metadata first so a partially removed site isn't restored on restart
+            remove_site_metadata(&site_dir).await?;
+            tokio::fs::remove_dir_all(&site_dir).await?;
+            tracing::info!(site_dir = %site_dir.display(), "Removed site directory");
+        }
+
+        let staged = canary_dir(&site_dir);
+        if staged.exists() {
+            tokio::fs::remove_dir_all(&staged).await?;
+            tracing::info!(site_dir = %staged.display(), "Removed canary release");
+        }
+
+        let releases = releases_dir(&site_dir);
+        if releases.exists() {
+            tokio::fs::remove_dir_all(&releases).await?;
+            tracing::info!(site_dir = %releases.display(), "Removed previous releases");
+        }
     }
 
     Ok(())
 }
+
+/// Metadata of the first deployed site directory
+async fn first_metadata(site_dirs: &[PathBuf]) -> Option<SiteMetadata> {
+    for site_dir in site_dirs {
+        match read_site_metadata(site_dir).await {
+            Ok(Some(metadata)) => return Some(metadata),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(error = %e, site_dir = %site_dir.display(), "Failed to read site metadata")
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use tempfile::tempdir;
+    use uuid::Uuid;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::config::WorkerConfig;
+    use crate::worker::deploy::{CloudflareClient, CloudflareConfig, DnsMode};
+
+    /// Mock Cloudflare with one zone, a CNAME per name and tunnel ingress rules for `hostnames`
+    async fn mock_cloudflare(hostnames: &[&str]) -> MockServer {
+        let mut ingress: Vec<_> = hostnames
+            .iter()
+            .map(|hostname| serde_json::json!({"hostname": hostname, "service": "http://localhost:8080"}))
+            .collect();
+        ingress.push(serde_json::json!({"service": "http_status:404"}));
+
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "result": [{"id": "zone-1"}],
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone-1/dns_records"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "result": [{"id": "record-1", "type": "CNAME", "content": "tunnel-1.cfargotunnel.com"}],
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/accounts/account-1/cfd_tunnel/tunnel-1/configurations",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "result": {"config": {"ingress": ingress}},
+            })))
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    fn test_state(
+        sites_dir: &std::path::Path,
+        caddy: &MockServer,
+        cloudflare: &MockServer,
+    ) -> AppState {
+        let http_client = reqwest::Client::new();
+        let cloudflare = CloudflareClient::new(
+            http_client.clone(),
+            CloudflareConfig {
+                api_token: "cf-token".to_string(),
+                account_id: "account-1".to_string(),
+                mode: DnsMode::Tunnel {
+                    tunnel_id: "tunnel-1".to_string(),
+                    service_url: "http://localhost:8080".to_string(),
+                },
+            },
+        )
+        .with_api_base(&cloudflare.uri());
+
+        AppState {
+            config: Arc::new(WorkerConfig::for_tests(
+                sites_dir.to_path_buf(),
+                &caddy.uri(),
+            )),
+            http_client,
+            cloudflare,
+            running: Default::default(),
+            isolation: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_removes_cloudflare_route_from_metadata() {
+        let hostname = "pr-42.nullislabs.io";
+        let site_id = "nullislabs-website-pr-42";
+
+        let sites = tempdir().unwrap();
+        let site_dir = sites.path().join(site_id);
+        std::fs::create_dir(&site_dir).unwrap();
+        crate::worker::deploy::sites::write_site_metadata(
+            &site_dir,
+            &SiteMetadata {
+                site_id: site_id.to_string(),
+                domain: hostname.to_string(),
+                aliases: Vec::new(),
+                commit_sha: None,
+                branch: None,
+                pr_number: Some(42),
+                deployed_at: None,
+                job_id: None,
+                site_type: None,
+                basic_auth: None,
+                precompressed: false,
+                immutable_paths: Vec::new(),
+                index_names: Vec::new(),
+                browse: false,
+                caddy_extra: None,
+                canary: None,
+                zone: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let caddy = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path(format!("/id/{}", site_id)))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+
+        let cloudflare = mock_cloudflare(&[hostname]).await;
+        Mock::given(method("DELETE"))
+            .and(path("/zones/zone-1/dns_records/record-1"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&cloudflare)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(
+                "/accounts/account-1/cfd_tunnel/tunnel-1/configurations",
+            ))
+            .and(body_string_contains("http_status:404"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&cloudflare)
+            .await;
+
+        // No domain in the job, as sent by older Centrals
+        let job = CleanupJob {
+            job_id: Uuid::new_v4(),
+            site_id: site_id.to_string(),
+            callback_url: "http://localhost/api/status".to_string(),
+            domain: None,
+            environment: None,
+        };
+
+        run_cleanup(&test_state(sites.path(), &caddy, &cloudflare), &job)
+            .await
+            .unwrap();
+
+        assert!(!site_dir.exists());
+
+        // The ingress rule for the hostname is gone from the updated tunnel config
+        let requests = cloudflare.received_requests().await.unwrap();
+        let update = requests
+            .iter()
+            .find(|r| r.method.as_str() == "PUT")
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&update.body).contains(hostname));
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_removes_cloudflare_routes_for_aliases() {
+        let hostnames = ["nullislabs.io", "www.nullislabs.io"];
+        let site_id = "nullislabs-website-main";
+
+        let sites = tempdir().unwrap();
+        let site_dir = sites.path().join(site_id);
+        std::fs::create_dir(&site_dir).unwrap();
+        crate::worker::deploy::sites::write_site_metadata(
+            &site_dir,
+            &SiteMetadata {
+                site_id: site_id.to_string(),
+                domain: hostnames[0].to_string(),
+                aliases: vec![hostnames[1].to_string()],
+                commit_sha: None,
+                branch: None,
+                pr_number: None,
+                deployed_at: None,
+                job_id: None,
+                site_type: None,
+                basic_auth: None,
+                precompressed: false,
+                immutable_paths: Vec::new(),
+                index_names: Vec::new(),
+                browse: false,
+                caddy_extra: None,
+                canary: None,
+                zone: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let caddy = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path(format!("/id/{}", site_id)))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+
+        let cloudflare = mock_cloudflare(&hostnames).await;
+        Mock::given(method("DELETE"))
+            .and(path("/zones/zone-1/dns_records/record-1"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&cloudflare)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(
+                "/accounts/account-1/cfd_tunnel/tunnel-1/configurations",
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&cloudflare)
+            .await;
+
+        let job = CleanupJob {
+            job_id: Uuid::new_v4(),
+            site_id: site_id.to_string(),
+            callback_url: "http://localhost/api/status".to_string(),
+            domain: Some(hostnames[0].to_string()),
+            environment: None,
+        };
+
+        run_cleanup(&test_state(sites.path(), &caddy, &cloudflare), &job)
+            .await
+            .unwrap();
+
+        // Each hostname had its DNS records looked up and its ingress rule dropped
+        let requests = cloudflare.received_requests().await.unwrap();
+        for hostname in hostnames {
+            assert!(requests.iter().any(|r| {
+                r.url.path() == "/zones/zone-1/dns_records"
+                    && r.url.query() == Some(&format!("name={}", hostname))
+            }));
+            assert!(requests.iter().any(|r| {
+                r.method.as_str() == "PUT"
+                    && !serde_json::from_slice::<serde_json::Value>(&r.body).unwrap()["config"]
+                        ["ingress"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .any(|rule| rule["hostname"] == hostname)
+            }));
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::shared::{
+    JobStatus, JobStatusQuery, StatusUpdate, WorkerJobState, generate_preview_url,
+};
+use crate::worker::deploy::{read_site_metadata, site_dir};
+use crate::worker::server::AppState;
+
+/// Handle job status queries
+///
+/// Lets Central reconcile a deployment whose final status update was lost:
+/// a job is `running` while it builds or deploys, `finished` if its final
+/// callback failed or its release is the site's deployed one, and `unknown`
+/// otherwise.
+pub async fn handle_job_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    // Extract signature and timestamp headers
+    let signature = match headers.get("x-central-signature") {
+        Some(sig) => sig.to_str().unwrap_or_default(),
+        None => {
+            tracing::warn!("Missing X-Central-Signature header");
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    };
+
+    let timestamp: u64 = match headers.get("x-request-timestamp") {
+        Some(ts) => ts.to_str().unwrap_or("0").parse().unwrap_or(0),
+        None => {
+            tracing::warn!("Missing X-Request-Timestamp header");
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    };
+
+    // Verify signature
+    let check = state.config.verifier().check(
+        &body,
+        signature,
+        timestamp,
+        state.config.signature_tolerance,
+    );
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, "Invalid central signature");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    // Parse status query
+    let query: JobStatusQuery = match serde_json::from_slice(&body) {
+        Ok(query) => query,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to parse job status query");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    match job_state(&state, &query).await {
+        Ok(job_state) => {
+            tracing::info!(job_id = %query.job_id, state = ?job_state, "Answered job status query");
+            Json(job_state).into_response()
+        }
+        Err(e) => {
+            tracing::error!(job_id = %query.job_id, error = %e, "Failed to look up job status");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn job_state(state: &AppState, query: &JobStatusQuery) -> anyhow::Result<WorkerJobState> {
+    if state.running.is_running(query.job_id) {
+        return Ok(WorkerJobState::Running);
+    }
+
+    if let Some(update) = state.running.undelivered(query.job_id) {
+        return Ok(WorkerJobState::Finished { update });
+    }
+
+    // Deployed releases outlive restarts, so check the site's metadata too,
+    // including sites deployed before per-zone directories were enabled
+    let mut site_dirs = vec![site_dir(&state.config.sites_dir, None, &query.site_id)?];
+    if state.config.sites_dir_per_zone
+        && let Some(zone) = query.environment.as_deref()
+    {
+        site_dirs.push(site_dir(
+            &state.config.sites_dir,
+            Some(zone),
+            &query.site_id,
+        )?);
+    }
+
+    for dir in site_dirs {
+        let Some(metadata) = read_site_metadata(&dir).await? else {
+            continue;
+        };
+        let canary_job = metadata
+            .canary
+            .as_ref()
+            .and_then(|canary| canary.release.job_id);
+        if metadata.job_id == Some(query.job_id) || canary_job == Some(query.job_id) {
+            return Ok(WorkerJobState::Finished {
+                update: deployed(query, &metadata.domain),
+            });
+        }
+    }
+
+    Ok(WorkerJobState::Unknown)
+}
+
+/// Success update for a job whose release is deployed
+fn deployed(query: &JobStatusQuery, domain: &str) -> StatusUpdate {
+    StatusUpdate {
+        job_id: query.job_id,
+        status: JobStatus::Success,
+        deployed_url: Some(generate_preview_url(domain)),
+        error_message: None,
+        event: None,
+        zone: query.environment.clone(),
+        size_bytes: None,
+        worker_id: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    use crate::config::WorkerConfig;
+    use crate::shared::auth::sign_request;
+    use crate::worker::deploy::{CloudflareClient, SiteMetadata};
+
+    fn test_state(sites_dir: &std::path::Path) -> AppState {
+        AppState {
+            config: Arc::new(WorkerConfig::for_tests(
+                sites_dir.to_path_buf(),
+                "http://127.0.0.1:1",
+            )),
+            http_client: reqwest::Client::new(),
+            cloudflare: CloudflareClient::disabled(),
+            running: Default::default(),
+            isolation: Default::default(),
+        }
+    }
+
+    fn query(job_id: Uuid) -> JobStatusQuery {
+        JobStatusQuery {
+            job_id,
+            site_id: "nullislabs-website-main".to_string(),
+            environment: None,
+        }
+    }
+
+    async fn ask(state: &AppState, query: &JobStatusQuery) -> (StatusCode, Option<WorkerJobState>) {
+        let body = serde_json::to_vec(query).unwrap();
+        let (signature, timestamp) = sign_request(b"test-secret", &body);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-central-signature", signature.parse().unwrap());
+        headers.insert(
+            "x-request-timestamp",
+            timestamp.to_string().parse().unwrap(),
+        );
+
+        let response = handle_job_status(State(state.clone()), headers, Bytes::from(body)).await;
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).ok())
+    }
+
+    #[tokio::test]
+    async fn test_running_and_unknown_jobs() {
+        let sites = tempdir().unwrap();
+        let state = test_state(sites.path());
+        let job_id = Uuid::new_v4();
+
+        let _signal = state.running.register(job_id);
+        let (status, job_state) = ask(&state, &query(job_id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(matches!(job_state, Some(WorkerJobState::Running)));
+
+        // A job the worker lost track of, e.g. across a restart
+        state.running.complete(job_id);
+        let (_, job_state) = ask(&state, &query(job_id)).await;
+        assert!(matches!(job_state, Some(WorkerJobState::Unknown)));
+    }
+
+    #[tokio::test]
+    async fn test_deployed_job_finished() {
+        let sites = tempdir().unwrap();
+        let state = test_state(sites.path());
+        let job_id = Uuid::new_v4();
+
+        let site_dir = sites.path().join("nullislabs-website-main");
+        std::fs::create_dir(&site_dir).unwrap();
+        let mut metadata: SiteMetadata = serde_json::from_value(serde_json::json!({
+            "site_id": "nullislabs-website-main",
+            "domain": "nullislabs.io",
+        }))
+        .unwrap();
+        metadata.job_id = Some(job_id);
+        crate::worker::deploy::sites::write_site_metadata(&site_dir, &metadata)
+            .await
+            .unwrap();
+
+        let (_, job_state) = ask(&state, &query(job_id)).await;
+        let Some(WorkerJobState::Finished { update }) = job_state else {
+            panic!("expected a finished job, got {:?}", job_state);
+        };
+        assert_eq!(update.status, JobStatus::Success);
+        assert_eq!(
+            update.deployed_url.as_deref(),
+            Some("https://nullislabs.io")
+        );
+
+        // An older release of the site is no longer known
+        let (_, job_state) = ask(&state, &query(Uuid::new_v4())).await;
+        assert!(matches!(job_state, Some(WorkerJobState::Unknown)));
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_status_finished() {
+        let sites = tempdir().unwrap();
+        let state = test_state(sites.path());
+        let job_id = Uuid::new_v4();
+
+        state.running.record_undelivered(StatusUpdate {
+            job_id,
+            status: JobStatus::Failed,
+            deployed_url: None,
+            error_message: Some("npm ERR! missing script: build".to_string()),
+            event: None,
+            zone: None,
+            size_bytes: None,
+            worker_id: None,
+        });
+
+        let (_, job_state) = ask(&state, &query(job_id)).await;
+        let Some(WorkerJobState::Finished { update }) = job_state else {
+            panic!("expected a finished job, got {:?}", job_state);
+        };
+        assert_eq!(update.status, JobStatus::Failed);
+        assert_eq!(
+            update.error_message.as_deref(),
+            Some("npm ERR! missing script: build")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_query_rejected() {
+        let sites = tempdir().unwrap();
+        let state = test_state(sites.path());
+        let body = serde_json::to_vec(&query(Uuid::new_v4())).unwrap();
+
+        let response = handle_job_status(State(state), HeaderMap::new(), Bytes::from(body)).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,5 +1,11 @@
 pub mod build;
+pub mod cancel;
 pub mod cleanup;
+pub mod job_status;
+pub mod promote;
 
 pub use build::handle_build;
+pub use cancel::handle_cancel;
 pub use cleanup::handle_cleanup;
+pub use job_status::handle_job_status;
+pub use promote::handle_promote;
@@ -0,0 +1,101 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::shared::PromoteJob;
+use crate::worker::deploy::{promote_canary, site_dir};
+use crate::worker::server::AppState;
+
+/// Handle canary promotion requests
+///
+/// Promotion only swaps a directory and the Caddy route, so it runs inline:
+/// `200` once all traffic goes to the canary, `404` if no canary from the
+/// job is awaiting promotion.
+pub async fn handle_promote(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    // Extract signature and timestamp headers
+    let signature = match headers.get("x-central-signature") {
+        Some(sig) => sig.to_str().unwrap_or_default(),
+        None => {
+            tracing::warn!("Missing X-Central-Signature header");
+            return StatusCode::UNAUTHORIZED;
+        }
+    };
+
+    let timestamp: u64 = match headers.get("x-request-timestamp") {
+        Some(ts) => ts.to_str().unwrap_or("0").parse().unwrap_or(0),
+        None => {
+            tracing::warn!("Missing X-Request-Timestamp header");
+            return StatusCode::UNAUTHORIZED;
+        }
+    };
+
+    // Verify signature
+    let check = state.config.verifier().check(
+        &body,
+        signature,
+        timestamp,
+        state.config.signature_tolerance,
+    );
+    if !check.is_valid() {
+        tracing::warn!(reason = %check, "Invalid central signature");
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    // Parse promote job
+    let job: PromoteJob = match serde_json::from_slice(&body) {
+        Ok(job) => job,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to parse promote job");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    tracing::info!(
+        job_id = %job.job_id,
+        site_id = %job.site_id,
+        "Received promote job"
+    );
+
+    match run_promote(&state, &job).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => {
+            tracing::warn!(job_id = %job.job_id, site_id = %job.site_id, "No canary release to promote");
+            StatusCode::NOT_FOUND
+        }
+        Err(e) => {
+            tracing::error!(job_id = %job.job_id, error = %e, "Promotion failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn run_promote(state: &AppState, job: &PromoteJob) -> anyhow::Result<bool> {
+    let zone = job
+        .environment
+        .as_deref()
+        .filter(|_| state.config.sites_dir_per_zone);
+    let mut dir = site_dir(&state.config.sites_dir, zone, &job.site_id)?;
+
+    // Sites deployed before per-zone directories were enabled stay flat
+    if !dir.exists() {
+        dir = site_dir(&state.config.sites_dir, None, &job.site_id)?;
+    }
+
+    promote_canary(
+        &state.http_client,
+        state
+            .config
+            .caddy_admin_api
+            .for_zone(job.environment.as_deref()),
+        &dir,
+        job.job_id,
+    )
+    .await
+}
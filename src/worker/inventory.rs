@@ -0,0 +1,150 @@
+//! Periodic site inventory reports to Central
+//!
+//! Central only knows what it dispatched; the worker knows what it actually
+//! serves. When the worker's zone is configured, it scans the site metadata in
+//! its sites directory and sends the full list to Central at a fixed interval.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::shared::{InventorySite, SiteInventory};
+use crate::worker::callback::send_inventory;
+use crate::worker::deploy::{dir_size, find_site_dirs, read_site_metadata};
+use crate::worker::server::AppState;
+
+/// Report the site inventory to Central until the worker shuts down
+pub fn spawn_inventory_reporter(state: AppState, zone: String) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut report_interval = tokio::time::interval(state.config.inventory_interval);
+
+        loop {
+            report_interval.tick().await;
+
+            if let Err(e) = report_inventory(&state, &zone).await {
+                // Central may be down; the next report carries the full list anyway
+                tracing::warn!(zone = %zone, error = %e, "Failed to report site inventory");
+            }
+        }
+    })
+}
+
+async fn report_inventory(state: &AppState, zone: &str) -> Result<()> {
+    let inventory = SiteInventory {
+        zone: zone.to_string(),
+        sites: scan_inventory(&state.config.sites_dir, zone).await?,
+    };
+
+    send_inventory(
+        &state.http_client,
+        &state.config.central_url,
+        state.config.signer(),
+        &inventory,
+    )
+    .await?;
+
+    tracing::debug!(zone = %zone, sites = inventory.sites.len(), "Reported site inventory");
+    Ok(())
+}
+
+/// Sites deployed for `zone`, from the metadata in `sites_dir`
+///
+/// Includes flat-layout sites and those in the zone's own directory; other
+/// zones' directories belong to their own reports.
+pub async fn scan_inventory(sites_dir: &Path, zone: &str) -> Result<Vec<InventorySite>> {
+    if !sites_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut sites = Vec::new();
+    for site_dir in find_site_dirs(sites_dir).await? {
+        let in_zone = site_dir
+            .parent()
+            .is_some_and(|parent| parent == sites_dir || parent == sites_dir.join(zone));
+        if !in_zone {
+            continue;
+        }
+
+        let metadata = match read_site_metadata(&site_dir).await {
+            Ok(Some(metadata)) => metadata,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!(
+                    site_dir = %site_dir.display(),
+                    error = %e,
+                    "Skipping site with unreadable metadata"
+                );
+                continue;
+            }
+        };
+
+        sites.push(InventorySite {
+            size_bytes: dir_size(&site_dir).await?,
+            site_id: metadata.site_id,
+            domain: metadata.domain,
+            deployed_at: metadata.deployed_at,
+        });
+    }
+
+    sites.sort_by(|a, b| a.site_id.cmp(&b.site_id));
+    Ok(sites)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::deploy::SiteMetadata;
+    use crate::worker::deploy::sites::write_site_metadata;
+    use tempfile::tempdir;
+
+    async fn deploy(site_dir: &Path, site_id: &str, domain: &str) {
+        std::fs::create_dir_all(site_dir).unwrap();
+        std::fs::write(site_dir.join("index.html"), "<html></html>").unwrap();
+        let metadata: SiteMetadata = serde_json::from_value(serde_json::json!({
+            "site_id": site_id,
+            "domain": domain,
+        }))
+        .unwrap();
+        write_site_metadata(site_dir, &metadata).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scan_inventory() {
+        let sites = tempdir().unwrap();
+        deploy(
+            &sites.path().join("nullislabs-website-main"),
+            "nullislabs-website-main",
+            "nullislabs.io",
+        )
+        .await;
+        deploy(
+            &sites.path().join("nxm/nullislabs-docs-pr-7"),
+            "nullislabs-docs-pr-7",
+            "pr-7-docs.nxm.rs",
+        )
+        .await;
+        deploy(
+            &sites.path().join("staging/nullislabs-docs-main"),
+            "nullislabs-docs-main",
+            "docs.staging.nxm.rs",
+        )
+        .await;
+        // Not a deployed site
+        std::fs::create_dir(sites.path().join("lost+found")).unwrap();
+
+        let inventory = scan_inventory(sites.path(), "nxm").await.unwrap();
+        let ids: Vec<&str> = inventory.iter().map(|s| s.site_id.as_str()).collect();
+        assert_eq!(ids, ["nullislabs-docs-pr-7", "nullislabs-website-main"]);
+        assert_eq!(inventory[0].domain, "pr-7-docs.nxm.rs");
+        assert!(inventory[0].size_bytes > "<html></html>".len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_scan_missing_sites_dir() {
+        let sites = tempdir().unwrap();
+        let inventory = scan_inventory(&sites.path().join("missing"), "nxm")
+            .await
+            .unwrap();
+        assert!(inventory.is_empty());
+    }
+}
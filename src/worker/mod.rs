@@ -1,10 +1,14 @@
 use crate::config::WorkerConfig;
 use anyhow::Result;
 
+pub mod allowlist;
 pub mod builder;
 mod callback;
 mod deploy;
 mod handlers;
+mod inventory;
+mod running;
+pub mod selftest;
 mod server;
 
 /// Run the Worker build executor
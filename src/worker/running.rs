@@ -0,0 +1,184 @@
+//! Builds in progress on this worker
+//!
+//! Each build registers here until its job completes, along with the container
+//! it runs in, so `/cancel` can stop it and `/status` can report it as running.
+//! Once deployment starts the build is no longer cancellable; interrupting it
+//! could leave a site half-swapped.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use crate::shared::StatusUpdate;
+
+/// Returned by a build pipeline that was stopped by `/cancel`
+#[derive(Debug, thiserror::Error)]
+#[error("Build cancelled")]
+pub struct BuildCancelled;
+
+struct RunningBuild {
+    cancel: watch::Sender<bool>,
+    container: Option<String>,
+    /// Cleared once the build's output is being deployed
+    cancellable: bool,
+}
+
+/// Registry of running builds, keyed by job id
+#[derive(Clone, Default)]
+pub struct RunningBuilds {
+    builds: Arc<Mutex<HashMap<Uuid, RunningBuild>>>,
+    /// Final statuses Central never acknowledged, kept for `/status`
+    undelivered: Arc<Mutex<HashMap<Uuid, StatusUpdate>>>,
+}
+
+/// Resolves once the build it was registered for is cancelled
+pub struct CancelSignal(watch::Receiver<bool>);
+
+impl CancelSignal {
+    pub async fn cancelled(&mut self) {
+        // The sender only goes away once the build is unregistered
+        if self.0.wait_for(|cancelled| *cancelled).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+impl RunningBuilds {
+    /// Track a build until `complete` is called
+    pub fn register(&self, job_id: Uuid) -> CancelSignal {
+        let (cancel, signal) = watch::channel(false);
+        self.builds.lock().unwrap().insert(
+            job_id,
+            RunningBuild {
+                cancel,
+                container: None,
+                cancellable: true,
+            },
+        );
+        CancelSignal(signal)
+    }
+
+    /// Record the container a build runs in
+    pub fn set_container(&self, job_id: Uuid, container: &str) {
+        if let Some(build) = self.builds.lock().unwrap().get_mut(&job_id) {
+            build.container = Some(container.to_string());
+        }
+    }
+
+    /// Mark a build as finished building; it can no longer be cancelled
+    pub fn finish(&self, job_id: Uuid) {
+        if let Some(build) = self.builds.lock().unwrap().get_mut(&job_id) {
+            build.cancellable = false;
+            build.container = None;
+        }
+    }
+
+    /// Stop tracking a job once its final status has been decided
+    pub fn complete(&self, job_id: Uuid) {
+        self.builds.lock().unwrap().remove(&job_id);
+    }
+
+    /// Whether the job is still building or deploying on this worker
+    pub fn is_running(&self, job_id: Uuid) -> bool {
+        self.builds.lock().unwrap().contains_key(&job_id)
+    }
+
+    /// Signal a build to stop
+    ///
+    /// Returns None if the job isn't a cancellable build on this worker,
+    /// otherwise the container to remove, if any.
+    pub fn cancel(&self, job_id: Uuid) -> Option<Option<String>> {
+        let mut builds = self.builds.lock().unwrap();
+        if !builds.get(&job_id)?.cancellable {
+            return None;
+        }
+        let build = builds.remove(&job_id)?;
+        let _ = build.cancel.send(true);
+        Some(build.container)
+    }
+
+    /// Keep a final status whose callback to Central failed
+    pub fn record_undelivered(&self, update: StatusUpdate) {
+        self.undelivered
+            .lock()
+            .unwrap()
+            .insert(update.job_id, update);
+    }
+
+    /// Final status of a job whose callback to Central failed
+    pub fn undelivered(&self, job_id: Uuid) -> Option<StatusUpdate> {
+        self.undelivered.lock().unwrap().get(&job_id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_signals_build() {
+        let builds = RunningBuilds::default();
+        let job_id = Uuid::new_v4();
+        let mut signal = builds.register(job_id);
+        builds.set_container(job_id, "catapult-build-1");
+
+        assert_eq!(
+            builds.cancel(job_id),
+            Some(Some("catapult-build-1".to_string()))
+        );
+        tokio::time::timeout(Duration::from_secs(1), signal.cancelled())
+            .await
+            .unwrap();
+
+        // Already cancelled
+        assert_eq!(builds.cancel(job_id), None);
+    }
+
+    #[tokio::test]
+    async fn test_finished_build_not_cancellable() {
+        let builds = RunningBuilds::default();
+        let job_id = Uuid::new_v4();
+        let mut signal = builds.register(job_id);
+        builds.finish(job_id);
+
+        // Still deploying, but past the point of cancelling
+        assert!(builds.is_running(job_id));
+        assert_eq!(builds.cancel(job_id), None);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), signal.cancelled())
+                .await
+                .is_err()
+        );
+        assert_eq!(builds.cancel(Uuid::new_v4()), None);
+    }
+
+    #[test]
+    fn test_completed_job_not_running() {
+        let builds = RunningBuilds::default();
+        let job_id = Uuid::new_v4();
+        let _signal = builds.register(job_id);
+        assert!(builds.is_running(job_id));
+
+        builds.complete(job_id);
+        assert!(!builds.is_running(job_id));
+        assert!(builds.undelivered(job_id).is_none());
+
+        builds.record_undelivered(StatusUpdate {
+            job_id,
+            status: crate::shared::JobStatus::Failed,
+            deployed_url: None,
+            error_message: Some("Build failed".to_string()),
+            event: None,
+            zone: None,
+            size_bytes: None,
+            worker_id: None,
+        });
+        assert_eq!(
+            builds.undelivered(job_id).unwrap().error_message.as_deref(),
+            Some("Build failed")
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! `catapult worker selftest`: checks a worker host before it takes jobs
+//!
+//! Runs the same code paths as a real deployment against throwaway inputs:
+//! Podman, the isolated build network, a small Zola build (nix, and the build
+//! container when enabled), and adding and removing a Caddy route.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use uuid::Uuid;
+
+use crate::config::WorkerConfig;
+use crate::shared::http::build_http_client;
+use crate::shared::{BuildJob, SiteType};
+use crate::worker::builder::network::{IsolationTracker, ensure_build_network};
+use crate::worker::builder::podman::connect_podman;
+use crate::worker::builder::run_build;
+use crate::worker::deploy::caddy::RouteOptions;
+use crate::worker::deploy::{CloudflareClient, configure_caddy_route, remove_caddy_route};
+use crate::worker::running::RunningBuilds;
+use crate::worker::server::AppState;
+
+/// Site ID of the throwaway Caddy route
+const SELFTEST_SITE_ID: &str = "catapult-selftest";
+
+/// Hostname of the throwaway Caddy route; `.invalid` never resolves
+const SELFTEST_HOSTNAME: &str = "catapult-selftest.invalid";
+
+/// Outcome of a single check
+enum Outcome {
+    Passed(String),
+    Failed(anyhow::Error),
+    Skipped(&'static str),
+}
+
+struct Check {
+    name: &'static str,
+    outcome: Outcome,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<String>) -> Self {
+        let outcome = match result {
+            Ok(detail) => Outcome::Passed(detail),
+            Err(e) => Outcome::Failed(e),
+        };
+        Self { name, outcome }
+    }
+
+    fn skipped(name: &'static str, reason: &'static str) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Skipped(reason),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.outcome {
+            Outcome::Passed(detail) => write!(f, "[ ok ] {}: {}", self.name, detail),
+            Outcome::Failed(e) => write!(f, "[FAIL] {}: {:#}", self.name, e),
+            Outcome::Skipped(reason) => write!(f, "[skip] {}: {}", self.name, reason),
+        }
+    }
+}
+
+/// Run every check, print the results and fail if any check failed
+pub async fn run(config: WorkerConfig) -> Result<()> {
+    let checks = run_checks(config).await;
+
+    for check in &checks {
+        println!("{}", check);
+    }
+    summarize(&checks)
+}
+
+async fn run_checks(config: WorkerConfig) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    if config.use_containers {
+        checks.push(Check::new(
+            "Podman",
+            check_podman(&config.podman_socket).await,
+        ));
+        // Without Podman there's no network to check
+        let network = match checks.last().map(|check| &check.outcome) {
+            Some(Outcome::Passed(_)) => Check::new(
+                "Build network",
+                check_build_network(&config.podman_socket, config.build_network_dry_run).await,
+            ),
+            _ => Check::skipped("Build network", "Podman is unavailable"),
+        };
+        checks.push(network);
+    } else {
+        checks.push(Check::skipped("Podman", "USE_CONTAINERS is disabled"));
+        checks.push(Check::skipped(
+            "Build network",
+            "USE_CONTAINERS is disabled",
+        ));
+    }
+
+    let http_client = match build_http_client(config.http_timeouts) {
+        Ok(client) => client,
+        Err(e) => {
+            checks.push(Check::new("HTTP client", Err(e)));
+            return checks;
+        }
+    };
+    let state = AppState {
+        config: Arc::new(config),
+        http_client,
+        cloudflare: CloudflareClient::disabled(),
+        running: RunningBuilds::default(),
+        isolation: IsolationTracker::default(),
+    };
+
+    checks.push(Check::new("Build", check_build(&state).await));
+    for endpoint in state.config.caddy_admin_api.endpoints() {
+        checks.push(Check::new(
+            "Caddy route",
+            check_caddy_route(&state.http_client, endpoint).await,
+        ));
+    }
+
+    checks
+}
+
+async fn check_podman(socket: &Path) -> Result<String> {
+    let docker = connect_podman(socket)?;
+    let version = docker
+        .version()
+        .await
+        .with_context(|| format!("No response from {}", socket.display()))?;
+
+    Ok(format!(
+        "{} {} at {}",
+        version
+            .components
+            .and_then(|components| components.into_iter().next())
+            .map(|component| component.name)
+            .unwrap_or_else(|| "API".to_string()),
+        version.version.unwrap_or_default(),
+        socket.display()
+    ))
+}
+
+async fn check_build_network(socket: &Path, dry_run: bool) -> Result<String> {
+    let docker = connect_podman(socket)?;
+    let status = ensure_build_network(&docker, dry_run).await?;
+    if !status.active {
+        anyhow::bail!("isolated network created, but {}", status.summary());
+    }
+    Ok(format!("isolated network with {}", status.summary()))
+}
+
+/// Build a one-page Zola site through the regular build path
+async fn check_build(state: &AppState) -> Result<String> {
+    let repo = scratch_dir("site").await?;
+    let result = build_selftest_site(state, &repo).await;
+    let _ = tokio::fs::remove_dir_all(&repo).await;
+    result
+}
+
+async fn build_selftest_site(state: &AppState, repo: &Path) -> Result<String> {
+    write_selftest_site(repo).await?;
+
+    let output = run_build(state, &selftest_job(), repo).await?.output_dir;
+    let built = tokio::fs::try_exists(output.join("index.html")).await;
+    // Container builds leave their output outside the repo directory
+    let _ = tokio::fs::remove_dir_all(&output).await;
+
+    match built {
+        Ok(true) => Ok(format!(
+            "Zola site built {}",
+            if state.config.use_containers {
+                format!("in {}", state.config.build_image)
+            } else {
+                "without containers".to_string()
+            }
+        )),
+        Ok(false) => anyhow::bail!("Build succeeded but produced no index.html"),
+        Err(e) => Err(e).context("Failed to inspect build output"),
+    }
+}
+
+async fn write_selftest_site(dir: &Path) -> Result<()> {
+    tokio::fs::write(
+        dir.join("config.toml"),
+        format!("base_url = \"https://{}\"\n", SELFTEST_HOSTNAME),
+    )
+    .await?;
+    tokio::fs::create_dir_all(dir.join("templates")).await?;
+    tokio::fs::write(
+        dir.join("templates/index.html"),
+        "<p>catapult selftest</p>\n",
+    )
+    .await?;
+    Ok(())
+}
+
+fn selftest_job() -> BuildJob {
+    BuildJob {
+        job_id: Uuid::new_v4(),
+        repo_url: String::new(),
+        git_token: String::new(),
+        branch: String::new(),
+        commit_sha: String::new(),
+        pr_number: None,
+        site_id: Some(SELFTEST_SITE_ID.to_string()),
+        domain: SELFTEST_HOSTNAME.to_string(),
+        aliases: Vec::new(),
+        site_type: SiteType::Zola,
+        callback_url: String::new(),
+        repo_name: "selftest".to_string(),
+        org_name: "catapult".to_string(),
+        subdomain: None,
+        build_args: Default::default(),
+        basic_auth: None,
+        precompress: false,
+        git_lfs: false,
+        index_names: Vec::new(),
+        browse: false,
+        caddy_extra: None,
+        isolation: None,
+        dry_run: false,
+        worker_id: None,
+        memory_mb: None,
+        cpu_cores: None,
+        pids_limit: None,
+        node_version: None,
+        environment: None,
+        artifact: None,
+        canary_percent: None,
+        immutable_paths: None,
+        purge_cache: None,
+        maintenance_page: None,
+    }
+}
+
+/// Add a route for a scratch directory, then remove it again
+async fn check_caddy_route(http_client: &reqwest::Client, caddy_admin_api: &str) -> Result<String> {
+    let site_dir = scratch_dir("route").await?;
+
+    let configured = configure_caddy_route(
+        http_client,
+        caddy_admin_api,
+        SELFTEST_SITE_ID,
+        &site_dir,
+        SELFTEST_HOSTNAME,
+        &RouteOptions::default(),
+    )
+    .await;
+    // Removal runs even after a failed add, in case it got halfway
+    let removed = remove_caddy_route(http_client, caddy_admin_api, SELFTEST_SITE_ID).await;
+    let _ = tokio::fs::remove_dir_all(&site_dir).await;
+
+    configured.with_context(|| format!("Failed to add route via {}", caddy_admin_api))?;
+    removed.with_context(|| format!("Failed to remove route via {}", caddy_admin_api))?;
+    Ok(format!("added and removed a route via {}", caddy_admin_api))
+}
+
+/// Fresh directory under the system temp dir, removed by the caller
+async fn scratch_dir(purpose: &str) -> Result<PathBuf> {
+    let dir =
+        std::env::temp_dir().join(format!("catapult-selftest-{}-{}", purpose, Uuid::new_v4()));
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Error out with a summary if any check failed
+fn summarize(checks: &[Check]) -> Result<()> {
+    let failed: Vec<&str> = checks
+        .iter()
+        .filter(|check| matches!(check.outcome, Outcome::Failed(_)))
+        .map(|check| check.name)
+        .collect();
+
+    if failed.is_empty() {
+        println!("All {} checks passed", checks.len());
+        Ok(())
+    } else {
+        anyhow::bail!(
+            "{} of {} checks failed: {}",
+            failed.len(),
+            checks.len(),
+            failed.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_summary_fails_on_any_failed_check() {
+        let mut checks = vec![
+            Check::new("Podman", Ok("podman 5.2.0".to_string())),
+            Check::skipped("Build network", "Podman is unavailable"),
+        ];
+        assert!(summarize(&checks).is_ok());
+
+        checks.push(Check::new(
+            "Caddy route",
+            Err(anyhow::anyhow!("connection refused")),
+        ));
+        let error = summarize(&checks).unwrap_err().to_string();
+        assert_eq!(error, "1 of 3 checks failed: Caddy route");
+        assert_eq!(
+            checks[2].to_string(),
+            "[FAIL] Caddy route: connection refused"
+        );
+        assert_eq!(
+            checks[1].to_string(),
+            "[skip] Build network: Podman is unavailable"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_caddy_route_added_and_removed() {
+        let caddy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&caddy)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/config/apps/http/servers/main/routes"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path(format!("/id/{}", SELFTEST_SITE_ID)))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&caddy)
+            .await;
+
+        check_caddy_route(&reqwest::Client::new(), &caddy.uri())
+            .await
+            .unwrap();
+    }
+}
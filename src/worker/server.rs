@@ -1,17 +1,32 @@
 use std::sync::Arc;
 
+use std::net::SocketAddr;
+
 use anyhow::{Context, Result};
 use axum::{
-    Router,
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    middleware,
+    response::IntoResponse,
     routing::{get, post},
 };
+use serde::Serialize;
 use tower_http::trace::TraceLayer;
 
-use crate::config::WorkerConfig;
-use crate::worker::deploy::{
-    CloudflareClient, CloudflareConfig, restore_all_routes, wait_for_caddy_ready,
+use crate::config::{CloudflareDnsMode, WorkerConfig};
+use crate::shared::http::build_http_client;
+use crate::shared::version::version_handler;
+use crate::worker::allowlist::{SourceAddr, SourceAllowlist, enforce_allowlist};
+use crate::worker::builder::network::{IsolationStatus, IsolationTracker, ensure_build_network};
+use crate::worker::builder::podman::connect_podman;
+use crate::worker::callback::fetch_worker_settings;
+use crate::worker::deploy::{CloudflareClient, CloudflareConfig, DnsMode, restore_all_routes};
+use crate::worker::handlers::{
+    handle_build, handle_cancel, handle_cleanup, handle_job_status, handle_promote,
 };
-use crate::worker::handlers::{handle_build, handle_cleanup};
+use crate::worker::inventory::spawn_inventory_reporter;
+use crate::worker::running::RunningBuilds;
 
 /// Shared application state
 #[derive(Clone)]
@@ -19,10 +34,14 @@ pub struct AppState {
     pub config: Arc<WorkerConfig>,
     pub http_client: reqwest::Client,
     pub cloudflare: CloudflareClient,
+    /// Builds that can still be cancelled
+    pub running: RunningBuilds,
+    /// Whether the build network's RFC1918 blocking is in place
+    pub isolation: IsolationTracker,
 }
 
 /// Run the Worker HTTP server
-pub async fn run(config: WorkerConfig) -> Result<()> {
+pub async fn run(mut config: WorkerConfig) -> Result<()> {
     // Verify sites directory exists
     if !config.sites_dir.exists() {
         tokio::fs::create_dir_all(&config.sites_dir)
@@ -30,8 +49,13 @@ pub async fn run(config: WorkerConfig) -> Result<()> {
             .context("Failed to create sites directory")?;
     }
 
+    let http_client = build_http_client(config.http_timeouts)?;
+
+    // Central's settings for the zone take precedence over local ones
+    apply_central_settings(&mut config, &http_client).await;
+
     // Create Cloudflare client
-    let cloudflare = create_cloudflare_client(&config);
+    let cloudflare = create_cloudflare_client(&config, &http_client);
 
     if cloudflare.is_enabled() {
         tracing::info!("Cloudflare integration enabled");
@@ -40,35 +64,76 @@ pub async fn run(config: WorkerConfig) -> Result<()> {
     }
 
     // Build application state
-    let http_client = reqwest::Client::new();
     let state = AppState {
         config: Arc::new(config.clone()),
         http_client: http_client.clone(),
         cloudflare,
+        running: RunningBuilds::default(),
+        isolation: IsolationTracker::default(),
     };
 
-    // Wait for Caddy admin API to be ready before restoring routes
-    if let Err(e) = wait_for_caddy_ready(&http_client, &config.caddy_admin_api).await {
-        tracing::error!(error = %e, "Caddy admin API not available, skipping route restoration");
-    } else {
-        // Restore Caddy routes for existing site deployments
-        match restore_all_routes(&http_client, &config.caddy_admin_api, &config.sites_dir).await {
-            Ok(count) => {
-                if count > 0 {
-                    tracing::info!(count, "Restored Caddy routes for existing sites");
-                }
-            }
-            Err(e) => {
-                tracing::error!(error = %e, "Failed to restore Caddy routes");
+    // Check build isolation up front, so /ready reports it before the first build
+    if config.use_containers {
+        spawn_isolation_check(state.clone());
+    }
+
+    // Restore Caddy routes for existing site deployments (waits for the admin API)
+    match restore_all_routes(
+        &http_client,
+        &config.caddy_admin_api,
+        &config.sites_dir,
+        config.route_restore_concurrency,
+    )
+    .await
+    {
+        Ok(count) => {
+            if count > 0 {
+                tracing::info!(count, "Restored Caddy routes for existing sites");
             }
         }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to restore Caddy routes");
+        }
+    }
+
+    // Keep Central's view of the deployed sites current
+    match &config.zone {
+        Some(zone) => {
+            spawn_inventory_reporter(state.clone(), zone.clone());
+        }
+        None => tracing::info!("WORKER_ZONE not set, site inventory reports disabled"),
     }
 
     // Build router
-    let app = Router::new()
+    let mut protected = Router::new()
         .route("/build", post(handle_build))
         .route("/cleanup", post(handle_cleanup))
+        .route("/promote", post(handle_promote))
+        .route("/cancel", post(handle_cancel))
+        .route("/status", post(handle_job_status));
+
+    // Restrict job endpoints to Central's addresses; /health, /ready and /version stay open
+    if !config.allowed_sources.is_empty() {
+        let source = if config.trust_forwarded_for {
+            SourceAddr::ForwardedFor
+        } else {
+            SourceAddr::Peer
+        };
+        tracing::info!(
+            networks = config.allowed_sources.len(),
+            source = ?source,
+            "Source allowlist enabled for job endpoints"
+        );
+        let allowlist = SourceAllowlist::new(config.allowed_sources.clone(), source);
+        protected =
+            protected.route_layer(middleware::from_fn_with_state(allowlist, enforce_allowlist));
+    }
+
+    let app = Router::new()
         .route("/health", get(health_check))
+        .route("/ready", get(ready_check))
+        .route("/version", get(version_handler))
+        .merge(protected)
         .layer(TraceLayer::new_for_http())
         .with_state(state);
 
@@ -79,7 +144,12 @@ pub async fn run(config: WorkerConfig) -> Result<()> {
 
     tracing::info!(addr = %config.listen_addr, "Server listening");
 
-    axum::serve(listener, app).await.context("Server error")?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .context("Server error")?;
 
     Ok(())
 }
@@ -88,29 +158,260 @@ async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Readiness response, reporting build isolation
+#[derive(Debug, Serialize)]
+struct ReadyResponse {
+    ready: bool,
+    /// Whether builds run in containers on the isolated network
+    containers: bool,
+    /// Latest check of the build network's RFC1918 blocking, None until checked
+    build_isolation: Option<IsolationStatus>,
+}
+
+/// Readiness check: not ready while container builds would run without
+/// their network isolation
+async fn ready_check(State(state): State<AppState>) -> impl IntoResponse {
+    let containers = state.config.use_containers;
+    let build_isolation = containers.then(|| state.isolation.status()).flatten();
+    let ready = build_isolation.as_ref().is_none_or(|status| status.active);
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ReadyResponse {
+            ready,
+            containers,
+            build_isolation,
+        }),
+    )
+}
+
+/// Set up and verify the build network in the background
+///
+/// Builds check it again before each run; this only makes the status known
+/// at startup. Podman being unavailable leaves it unchecked.
+fn spawn_isolation_check(state: AppState) {
+    tokio::spawn(async move {
+        let result = async {
+            let docker = connect_podman(&state.config.podman_socket)?;
+            ensure_build_network(&docker, state.config.build_network_dry_run).await
+        }
+        .await;
+
+        match result {
+            Ok(status) => state.isolation.record(status),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to check build network isolation at startup");
+            }
+        }
+    });
+}
+
+/// Apply the settings Central assigns to this worker's zone
+///
+/// If Central can't be reached or sends invalid settings, the local config is
+/// kept, so a worker can start while Central is down.
+async fn apply_central_settings(config: &mut WorkerConfig, http_client: &reqwest::Client) {
+    let Some(zone) = config.zone.clone() else {
+        return;
+    };
+
+    let result =
+        match fetch_worker_settings(http_client, &config.central_url, config.signer(), &zone).await
+        {
+            Ok(settings) => config.apply_settings(&settings).map(|()| settings),
+            Err(e) => Err(e),
+        };
+
+    match result {
+        Ok(settings) => {
+            tracing::info!(zone = %zone, settings = ?settings, "Applied worker settings from Central");
+        }
+        Err(e) => {
+            tracing::warn!(zone = %zone, error = %e, "Failed to load worker settings from Central, using local config");
+        }
+    }
+}
+
 /// Create Cloudflare client from configuration
 ///
-/// Requires all of: CLOUDFLARE_API_TOKEN, CLOUDFLARE_ACCOUNT_ID, CLOUDFLARE_TUNNEL_ID
+/// Requires CLOUDFLARE_API_TOKEN and CLOUDFLARE_ACCOUNT_ID, plus a default
+/// CLOUDFLARE_TUNNEL_ID and/or per-environment CLOUDFLARE_TUNNELS.
 /// If any are missing, Cloudflare integration is disabled.
 /// Zone IDs are looked up dynamically based on the domain being deployed.
-fn create_cloudflare_client(config: &WorkerConfig) -> CloudflareClient {
+fn create_cloudflare_client(
+    config: &WorkerConfig,
+    http_client: &reqwest::Client,
+) -> CloudflareClient {
     // Check if all required config is present
-    let cf_config = match (
-        &config.cloudflare_api_token,
-        &config.cloudflare_account_id,
-        &config.cloudflare_tunnel_id,
-    ) {
-        (Some(api_token), Some(account_id), Some(tunnel_id)) => Some(CloudflareConfig {
-            api_token: api_token.clone(),
-            account_id: account_id.clone(),
-            tunnel_id: tunnel_id.clone(),
-            service_url: config.cloudflare_service_url.clone(),
-        }),
-        _ => None,
+    let (api_token, account_id) =
+        match (&config.cloudflare_api_token, &config.cloudflare_account_id) {
+            (Some(api_token), Some(account_id)) => (api_token, account_id),
+            _ => return CloudflareClient::disabled(),
+        };
+
+    let cloudflare_config = |mode: DnsMode| CloudflareConfig {
+        api_token: api_token.clone(),
+        account_id: account_id.clone(),
+        mode,
+    };
+    let tunnel_config = |tunnel_id: &str, service_url: Option<&str>| {
+        cloudflare_config(DnsMode::Tunnel {
+            tunnel_id: tunnel_id.to_string(),
+            service_url: service_url
+                .unwrap_or(&config.cloudflare_service_url)
+                .to_string(),
+        })
     };
 
-    match cf_config {
-        Some(cfg) => CloudflareClient::new(cfg),
-        None => CloudflareClient::disabled(),
+    let default = match config.cloudflare_dns_mode {
+        CloudflareDnsMode::Tunnel => config
+            .cloudflare_tunnel_id
+            .as_deref()
+            .map(|tunnel_id| tunnel_config(tunnel_id, None)),
+        CloudflareDnsMode::Direct => Some(cloudflare_config(DnsMode::Direct {
+            origin_ips: config.cloudflare_origin_ips.clone(),
+        })),
+    };
+
+    let tunnels = config
+        .cloudflare_tunnels
+        .iter()
+        .map(|(environment, tunnel)| {
+            (
+                environment.clone(),
+                tunnel_config(&tunnel.tunnel_id, tunnel.service_url.as_deref()),
+            )
+        })
+        .collect();
+
+    CloudflareClient::with_tunnels(http_client.clone(), default, tunnels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn worker_config(central: &MockServer) -> WorkerConfig {
+        WorkerConfig {
+            central_url: central.uri(),
+            zone: Some("nullislabs".to_string()),
+            ..WorkerConfig::for_tests(PathBuf::from("/var/www/sites"), "http://caddy")
+        }
+    }
+
+    fn test_state(use_containers: bool) -> AppState {
+        AppState {
+            config: Arc::new(WorkerConfig {
+                use_containers,
+                ..WorkerConfig::for_tests(PathBuf::from("/var/www/sites"), "http://caddy")
+            }),
+            http_client: reqwest::Client::new(),
+            cloudflare: CloudflareClient::disabled(),
+            running: RunningBuilds::default(),
+            isolation: IsolationTracker::default(),
+        }
+    }
+
+    async fn ready_json(state: AppState) -> (StatusCode, serde_json::Value) {
+        let response = ready_check(State(state)).await.into_response();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_ready_reports_build_isolation() {
+        // Unchecked isolation doesn't hold the worker back
+        let state = test_state(true);
+        let (status, body) = ready_json(state.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["build_isolation"], serde_json::Value::Null);
+
+        state.isolation.record(IsolationStatus {
+            active: true,
+            missing_rules: Vec::new(),
+            unknown_rules: Vec::new(),
+            dry_run: true,
+        });
+        let (status, body) = ready_json(state.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["build_isolation"]["active"], true);
+        assert_eq!(body["build_isolation"]["dry_run"], true);
+
+        let missing = "iptables -I FORWARD 1 -s 10.89.0.0/24 -j CATAPULT_BUILD_ISOLATION";
+        state.isolation.record(IsolationStatus {
+            active: false,
+            missing_rules: vec![missing.to_string()],
+            unknown_rules: Vec::new(),
+            dry_run: false,
+        });
+        let (status, body) = ready_json(state).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["build_isolation"]["missing_rules"][0], missing);
+    }
+
+    #[tokio::test]
+    async fn test_ready_without_containers() {
+        let (status, body) = ready_json(test_state(false)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["containers"], false);
+    }
+
+    #[tokio::test]
+    async fn test_central_settings_applied_at_startup() {
+        let central = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/workers/nullislabs/config"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "cloudflare_tunnel_id": "tunnel-1",
+                "clone_timeout_secs": 60
+            })))
+            .mount(&central)
+            .await;
+
+        let mut config = worker_config(&central);
+        apply_central_settings(&mut config, &reqwest::Client::new()).await;
+        assert_eq!(config.cloudflare_tunnel_id.as_deref(), Some("tunnel-1"));
+        assert_eq!(config.clone_timeout, Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_local_config_kept_when_central_fails() {
+        let central = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&central)
+            .await;
+
+        let mut config = worker_config(&central);
+        apply_central_settings(&mut config, &reqwest::Client::new()).await;
+        assert_eq!(config.cloudflare_tunnel_id, None);
+        assert_eq!(config.clone_timeout, Duration::from_secs(300));
+
+        // Settings the worker can't apply are ignored as a whole
+        let central = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "clone_timeout_secs": 60,
+                "cloudflare_service_url": "localhost:8081"
+            })))
+            .mount(&central)
+            .await;
+
+        let mut config = worker_config(&central);
+        apply_central_settings(&mut config, &reqwest::Client::new()).await;
+        assert_eq!(config.clone_timeout, Duration::from_secs(300));
     }
 }
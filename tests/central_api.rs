@@ -9,7 +9,7 @@ use axum::{
     routing::{get, post},
 };
 use catapult::central::db;
-use catapult::shared::{JobStatus, StatusUpdate, auth::sign_request};
+use catapult::shared::{DeploymentPhase, JobStatus, StatusUpdate, auth::sign_request};
 use common::TestDatabase;
 use tower::util::ServiceExt;
 use uuid::Uuid;
@@ -41,11 +41,12 @@ fn create_test_router(db: sqlx::PgPool, worker_secret: String) -> Router {
             None => return StatusCode::UNAUTHORIZED,
         };
 
-        if !catapult::shared::auth::verify_signature(
+        if !catapult::test_util::verify_signature(
             state.worker_secret.as_bytes(),
             &body,
             signature,
             timestamp,
+            Default::default(),
         ) {
             return StatusCode::UNAUTHORIZED;
         }
@@ -71,11 +72,19 @@ fn create_test_router(db: sqlx::PgPool, worker_secret: String) -> Router {
         "OK"
     }
 
+    async fn ready_check(State(state): State<TestState>) -> StatusCode {
+        match db::ping(&state.db, std::time::Duration::from_secs(2)).await {
+            Ok(()) => StatusCode::OK,
+            Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
     let state = TestState { db, worker_secret };
 
     Router::new()
         .route("/api/status", post(handle_status))
         .route("/health", get(health_check))
+        .route("/ready", get(ready_check))
         .with_state(state)
 }
 
@@ -97,6 +106,24 @@ async fn test_health_check() {
     assert_eq!(response.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn test_ready_check_database_reachable() {
+    let db = TestDatabase::new().await;
+    let app = create_test_router(db.pool, "test-secret".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/ready")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
 #[tokio::test]
 async fn test_status_update_missing_signature() {
     let db = TestDatabase::new().await;
@@ -127,6 +154,10 @@ async fn test_status_update_invalid_signature() {
         status: JobStatus::Success,
         deployed_url: None,
         error_message: None,
+        event: None,
+        zone: None,
+        size_bytes: None,
+        worker_id: None,
     })
     .unwrap();
 
@@ -173,6 +204,10 @@ async fn test_status_update_valid_signature() {
         status: JobStatus::Success,
         deployed_url: Some("https://pr-42.example.com".to_string()),
         error_message: None,
+        event: None,
+        zone: None,
+        size_bytes: None,
+        worker_id: None,
     };
     let body = serde_json::to_vec(&status_update).unwrap();
     let (signature, timestamp) = sign_request(secret.as_bytes(), &body);
@@ -206,6 +241,10 @@ async fn test_status_update_nonexistent_job() {
         status: JobStatus::Success,
         deployed_url: Some("https://example.com".to_string()),
         error_message: None,
+        event: None,
+        zone: None,
+        size_bytes: None,
+        worker_id: None,
     };
     let body = serde_json::to_vec(&status_update).unwrap();
     let (signature, timestamp) = sign_request(secret.as_bytes(), &body);
@@ -254,6 +293,10 @@ async fn test_status_update_failure() {
         status: JobStatus::Failed,
         deployed_url: None,
         error_message: Some("Build failed: npm install error".to_string()),
+        event: None,
+        zone: None,
+        size_bytes: None,
+        worker_id: None,
     };
     let body = serde_json::to_vec(&status_update).unwrap();
     let (signature, timestamp) = sign_request(secret.as_bytes(), &body);
@@ -274,3 +317,107 @@ async fn test_status_update_failure() {
 
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn test_token_refresh_unknown_job_indistinguishable_from_bad_signature() {
+    let db = TestDatabase::new().await;
+    let app = catapult::central::server::public_routes().with_state(db.app_state());
+
+    let body = serde_json::to_vec(&serde_json::json!({"job_id": Uuid::new_v4()})).unwrap();
+    let (signature, timestamp) = sign_request(b"test-secret", &body);
+    let request = |signature: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/workers/token")
+            .header("content-type", "application/json")
+            .header("x-worker-signature", signature)
+            .header("x-request-timestamp", timestamp.to_string())
+            .body(Body::from(body.clone()))
+            .unwrap()
+    };
+
+    // A validly signed request for an unknown job looks like a bad signature
+    let response = app.clone().oneshot(request(&signature)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.oneshot(request("sha256=invalid")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_mirrored_progress_dropped_once_quorum_decided() {
+    use std::collections::HashMap;
+
+    let db = TestDatabase::new().await;
+    let app = catapult::central::server::public_routes().with_state(db.app_state());
+
+    let workers = HashMap::from([(
+        "zone1".to_string(),
+        "https://worker1.example.com".to_string(),
+    )]);
+    let mirrors = HashMap::from([(
+        "zone1".to_string(),
+        vec!["https://mirror1.example.com".to_string()],
+    )]);
+    db::sync_workers(&db.pool, &workers, &mirrors)
+        .await
+        .expect("Failed to sync workers");
+    let primary = db::get_worker(&db.pool, "zone1")
+        .await
+        .expect("Failed to get worker")
+        .expect("Zone should have a primary");
+    let ids: Vec<i32> = db::get_workers(&db.pool, "zone1")
+        .await
+        .expect("Failed to get workers")
+        .iter()
+        .map(|worker| worker.id)
+        .collect();
+
+    let job_id = Uuid::new_v4();
+    db::record_mirror_dispatch(&db.pool, job_id, &ids)
+        .await
+        .expect("Failed to record dispatch");
+
+    let send = |status: JobStatus, event: Option<DeploymentPhase>| {
+        let body = serde_json::to_vec(&StatusUpdate {
+            job_id,
+            status,
+            deployed_url: None,
+            error_message: None,
+            event,
+            zone: Some("zone1".to_string()),
+            size_bytes: None,
+            worker_id: Some(primary.id),
+        })
+        .unwrap();
+        let (signature, timestamp) = sign_request(b"test-secret", &body);
+        Request::builder()
+            .method("POST")
+            .uri("/api/status")
+            .header("content-type", "application/json")
+            .header("x-worker-signature", signature)
+            .header("x-request-timestamp", timestamp.to_string())
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    // With a quorum of both workers, the primary failing decides the deployment
+    let response = app
+        .clone()
+        .oneshot(send(JobStatus::Failed, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Late progress from the primary is acknowledged but not recorded
+    let response = app
+        .oneshot(send(JobStatus::Building, Some(DeploymentPhase::Deployed)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        db::list_deployment_events(&db.pool, job_id)
+            .await
+            .expect("Failed to list events")
+            .is_empty()
+    );
+}
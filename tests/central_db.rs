@@ -2,10 +2,23 @@
 
 mod common;
 
+use axum::http::StatusCode;
 use catapult::central::db;
-use common::TestDatabase;
+use catapult::shared::{BuildJob, DeploymentPhase, InventorySite, JobStatus, SiteType};
+use common::{TestDatabase, admin_request};
+use std::collections::HashMap;
+use std::time::Duration;
 use uuid::Uuid;
 
+#[tokio::test]
+async fn test_ping() {
+    let db = TestDatabase::new().await;
+
+    db::ping(&db.pool, Duration::from_secs(2))
+        .await
+        .expect("Database should be reachable");
+}
+
 #[tokio::test]
 async fn test_worker_crud() {
     let db = TestDatabase::new().await;
@@ -183,7 +196,7 @@ async fn test_sync_workers() {
         "https://worker2.example.com".to_string(),
     );
 
-    let count = db::sync_workers(&db.pool, &workers)
+    let count = db::sync_workers(&db.pool, &workers, &HashMap::new())
         .await
         .expect("Failed to sync workers");
 
@@ -204,7 +217,7 @@ async fn test_sync_workers() {
 
     // Sync again with only zone1 - zone2 should be disabled
     workers.remove("zone2");
-    db::sync_workers(&db.pool, &workers)
+    db::sync_workers(&db.pool, &workers, &HashMap::new())
         .await
         .expect("Failed to sync workers");
 
@@ -221,6 +234,119 @@ async fn test_sync_workers() {
     assert!(worker1.is_some());
 }
 
+#[tokio::test]
+async fn test_sync_worker_mirrors() {
+    let db = TestDatabase::new().await;
+
+    use std::collections::HashMap;
+
+    let workers = HashMap::from([(
+        "zone1".to_string(),
+        "https://worker1.example.com".to_string(),
+    )]);
+    let mut mirrors = HashMap::from([(
+        "zone1".to_string(),
+        vec!["https://mirror1.example.com".to_string()],
+    )]);
+
+    let count = db::sync_workers(&db.pool, &workers, &mirrors)
+        .await
+        .expect("Failed to sync workers");
+    assert_eq!(count, 2);
+
+    // The primary comes first; get_worker never returns a mirror
+    let zone_workers = db::get_workers(&db.pool, "zone1")
+        .await
+        .expect("Failed to get workers");
+    let endpoints: Vec<&str> = zone_workers.iter().map(|w| w.endpoint.as_str()).collect();
+    assert_eq!(
+        endpoints,
+        vec!["https://worker1.example.com", "https://mirror1.example.com"]
+    );
+    assert!(!zone_workers[0].mirror);
+    assert!(zone_workers[1].mirror);
+    let primary = db::get_worker(&db.pool, "zone1")
+        .await
+        .expect("Failed to get worker")
+        .expect("Worker not found");
+    assert_eq!(primary.endpoint, "https://worker1.example.com");
+
+    // A mirror dropped from the config is disabled
+    mirrors.clear();
+    db::sync_workers(&db.pool, &workers, &mirrors)
+        .await
+        .expect("Failed to sync workers");
+    let zone_workers = db::get_workers(&db.pool, "zone1")
+        .await
+        .expect("Failed to get workers");
+    assert_eq!(zone_workers.len(), 1);
+}
+
+#[tokio::test]
+async fn test_mirror_results_recorded() {
+    let db = TestDatabase::new().await;
+
+    use std::collections::HashMap;
+
+    let workers = HashMap::from([(
+        "zone1".to_string(),
+        "https://worker1.example.com".to_string(),
+    )]);
+    let mirrors = HashMap::from([(
+        "zone1".to_string(),
+        vec!["https://mirror1.example.com".to_string()],
+    )]);
+    db::sync_workers(&db.pool, &workers, &mirrors)
+        .await
+        .expect("Failed to sync workers");
+    let ids: Vec<i32> = db::get_workers(&db.pool, "zone1")
+        .await
+        .expect("Failed to get workers")
+        .iter()
+        .map(|w| w.id)
+        .collect();
+
+    let job_id = Uuid::new_v4();
+    db::record_mirror_dispatch(&db.pool, job_id, &ids)
+        .await
+        .expect("Failed to record dispatch");
+    let results = db::list_mirror_results(&db.pool, job_id)
+        .await
+        .expect("Failed to list results");
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|result| result.succeeded.is_none()));
+
+    // Each result comes back with the results before it
+    let previous = db::record_mirror_result(&db.pool, job_id, ids[1], false, Some("disk full"))
+        .await
+        .expect("Failed to record result")
+        .expect("Job should be mirrored");
+    assert!(previous.iter().all(|result| result.succeeded.is_none()));
+
+    let previous = db::record_mirror_result(&db.pool, job_id, ids[0], true, None)
+        .await
+        .expect("Failed to record result")
+        .expect("Job should be mirrored");
+    let failed = previous
+        .iter()
+        .find(|result| result.worker_id == ids[1])
+        .unwrap();
+    assert_eq!(failed.succeeded, Some(false));
+
+    // Workers the job wasn't dispatched to have no say
+    let unmirrored = Uuid::new_v4();
+    let unknown = db::record_mirror_result(&db.pool, unmirrored, ids[0], true, None)
+        .await
+        .expect("Failed to record result");
+    assert!(unknown.is_none());
+    assert!(
+        db::list_mirror_results(&db.pool, unmirrored)
+            .await
+            .expect("Failed to list results")
+            .is_empty()
+    );
+}
+
 // ==================== Authorization Tests ====================
 
 #[tokio::test]
@@ -231,9 +357,16 @@ async fn test_authorized_org_crud() {
     let domain_patterns = vec!["*.example.com".to_string(), "example.com".to_string()];
 
     // Create authorized org
-    let org = db::upsert_authorized_org(&db.pool, "testorg", &zones, &domain_patterns)
-        .await
-        .expect("Failed to create authorized org");
+    let org = db::upsert_authorized_org(
+        &db.pool,
+        "testorg",
+        &zones,
+        &domain_patterns,
+        db::OrgQuota::default(),
+        &db::RepoFilter::default(),
+    )
+    .await
+    .expect("Failed to create authorized org");
 
     assert_eq!(org.github_org, "testorg");
     assert_eq!(org.zones, zones);
@@ -258,9 +391,16 @@ async fn test_authorized_org_case_insensitive() {
     let domain_patterns = vec!["*.example.com".to_string()];
 
     // Create with lowercase
-    db::upsert_authorized_org(&db.pool, "MyOrg", &zones, &domain_patterns)
-        .await
-        .expect("Failed to create authorized org");
+    db::upsert_authorized_org(
+        &db.pool,
+        "MyOrg",
+        &zones,
+        &domain_patterns,
+        db::OrgQuota::default(),
+        &db::RepoFilter::default(),
+    )
+    .await
+    .expect("Failed to create authorized org");
 
     // Fetch with different case
     let fetched = db::get_authorized_org(&db.pool, "myorg")
@@ -292,21 +432,82 @@ async fn test_authorized_org_update() {
     // Create initial
     let zones1 = vec!["production".to_string()];
     let domains1 = vec!["*.example.com".to_string()];
-    db::upsert_authorized_org(&db.pool, "testorg", &zones1, &domains1)
-        .await
-        .expect("Failed to create authorized org");
+    db::upsert_authorized_org(
+        &db.pool,
+        "testorg",
+        &zones1,
+        &domains1,
+        db::OrgQuota::default(),
+        &db::RepoFilter::default(),
+    )
+    .await
+    .expect("Failed to create authorized org");
 
     // Update with new values
     let zones2 = vec!["production".to_string(), "staging".to_string()];
     let domains2 = vec!["*.example.com".to_string(), "*.test.com".to_string()];
-    let updated = db::upsert_authorized_org(&db.pool, "testorg", &zones2, &domains2)
-        .await
-        .expect("Failed to update authorized org");
+    let updated = db::upsert_authorized_org(
+        &db.pool,
+        "testorg",
+        &zones2,
+        &domains2,
+        db::OrgQuota::default(),
+        &db::RepoFilter::default(),
+    )
+    .await
+    .expect("Failed to update authorized org");
 
     assert_eq!(updated.zones, zones2);
     assert_eq!(updated.domain_patterns, domains2);
 }
 
+#[tokio::test]
+async fn test_authorized_org_repo_filters() {
+    let db = TestDatabase::new().await;
+
+    let zones = vec!["production".to_string()];
+    let domain_patterns = vec!["*.example.com".to_string()];
+
+    // Orgs start without filters
+    let org = db::upsert_authorized_org(
+        &db.pool,
+        "testorg",
+        &zones,
+        &domain_patterns,
+        db::OrgQuota::default(),
+        &db::RepoFilter::default(),
+    )
+    .await
+    .expect("Failed to create authorized org");
+    assert!(org.repo_allowlist.is_empty());
+    assert!(org.repo_denylist.is_empty());
+    assert!(org.can_deploy_repo("anything"));
+
+    db::upsert_authorized_org(
+        &db.pool,
+        "testorg",
+        &zones,
+        &domain_patterns,
+        db::OrgQuota::default(),
+        &db::RepoFilter {
+            allowlist: vec!["docs-*".to_string()],
+            denylist: vec!["docs-internal".to_string()],
+        },
+    )
+    .await
+    .expect("Failed to update authorized org");
+
+    let fetched = db::get_authorized_org(&db.pool, "testorg")
+        .await
+        .expect("Failed to get authorized org")
+        .expect("Org not found");
+    assert_eq!(fetched.repo_allowlist, vec!["docs-*"]);
+    assert_eq!(fetched.repo_denylist, vec!["docs-internal"]);
+    assert!(fetched.can_deploy_repo("docs-api"));
+    assert!(!fetched.can_deploy_repo("docs-internal"));
+    assert!(!fetched.can_deploy_repo("website"));
+}
+
 #[tokio::test]
 async fn test_authorized_org_delete() {
     let db = TestDatabase::new().await;
@@ -315,9 +516,16 @@ async fn test_authorized_org_delete() {
     let domains = vec!["*.example.com".to_string()];
 
     // Create
-    db::upsert_authorized_org(&db.pool, "testorg", &zones, &domains)
-        .await
-        .expect("Failed to create authorized org");
+    db::upsert_authorized_org(
+        &db.pool,
+        "testorg",
+        &zones,
+        &domains,
+        db::OrgQuota::default(),
+        &db::RepoFilter::default(),
+    )
+    .await
+    .expect("Failed to create authorized org");
 
     // Delete
     let deleted = db::delete_authorized_org(&db.pool, "testorg")
@@ -340,8 +548,10 @@ async fn test_list_authorized_orgs() {
     db::upsert_authorized_org(
         &db.pool,
         "org1",
-        &vec!["zone1".to_string()],
-        &vec!["*.org1.com".to_string()],
+        &["zone1".to_string()],
+        &["*.org1.com".to_string()],
+        db::OrgQuota::default(),
+        &db::RepoFilter::default(),
     )
     .await
     .expect("Failed to create org1");
@@ -349,8 +559,10 @@ async fn test_list_authorized_orgs() {
     db::upsert_authorized_org(
         &db.pool,
         "org2",
-        &vec!["zone2".to_string()],
-        &vec!["*.org2.com".to_string()],
+        &["zone2".to_string()],
+        &["*.org2.com".to_string()],
+        db::OrgQuota::default(),
+        &db::RepoFilter::default(),
     )
     .await
     .expect("Failed to create org2");
@@ -362,3 +574,1414 @@ async fn test_list_authorized_orgs() {
 
     assert_eq!(orgs.len(), 2);
 }
+
+// ==================== Worker Admin Tests ====================
+
+#[tokio::test]
+async fn test_list_workers() {
+    let db = TestDatabase::new().await;
+    db.create_test_worker("zone-b").await;
+    db.create_test_worker("zone-a").await;
+
+    let workers = db::list_workers(&db.pool)
+        .await
+        .expect("Failed to list workers");
+
+    assert_eq!(workers.len(), 2);
+    // Ordered by environment
+    assert_eq!(workers[0].environment, "zone-a");
+    assert_eq!(workers[1].environment, "zone-b");
+}
+
+#[tokio::test]
+async fn test_set_worker_enabled() {
+    let db = TestDatabase::new().await;
+    db.create_test_worker("production").await;
+
+    // Disable
+    let worker = db::set_worker_enabled(&db.pool, "production", false)
+        .await
+        .expect("Failed to disable worker")
+        .expect("Worker not found");
+    assert!(!worker.enabled);
+    assert_eq!(
+        worker.health(chrono::Utc::now()),
+        db::WorkerHealth::Disabled
+    );
+
+    // Disabled workers are not used for dispatch
+    let fetched = db::get_worker(&db.pool, "production")
+        .await
+        .expect("Failed to query worker");
+    assert!(fetched.is_none());
+
+    // Disabled workers are still listed
+    let workers = db::list_workers(&db.pool)
+        .await
+        .expect("Failed to list workers");
+    assert_eq!(workers.len(), 1);
+
+    // Re-enable
+    let worker = db::set_worker_enabled(&db.pool, "production", true)
+        .await
+        .expect("Failed to enable worker")
+        .expect("Worker not found");
+    assert!(worker.enabled);
+
+    // Unknown worker
+    let missing = db::set_worker_enabled(&db.pool, "nonexistent", true)
+        .await
+        .expect("Failed to query worker");
+    assert!(missing.is_none());
+}
+
+#[tokio::test]
+async fn test_worker_toggle_via_admin_api() {
+    let db = TestDatabase::new().await;
+    db::sync_workers(
+        &db.pool,
+        &HashMap::from([(
+            "production".to_string(),
+            "https://worker.production.example.com".to_string(),
+        )]),
+        &HashMap::new(),
+    )
+    .await
+    .expect("Failed to sync workers");
+
+    let (status, _) = admin_request(
+        db.app_state(),
+        "POST",
+        "/api/admin/workers/staging/disable",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = admin_request(
+        db.app_state(),
+        "POST",
+        "/api/admin/workers/production/disable",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["enabled"], false);
+
+    // Disabled workers stay listed but receive no jobs
+    let (status, body) = admin_request(db.app_state(), "GET", "/api/admin/workers", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body[0]["environment"], "production");
+    assert_eq!(body[0]["enabled"], false);
+    assert_eq!(body[0]["health"], "disabled");
+    assert!(
+        db::get_worker(&db.pool, "production")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let (status, body) = admin_request(
+        db.app_state(),
+        "POST",
+        "/api/admin/workers/production/enable",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["enabled"], true);
+    assert!(
+        db::get_worker(&db.pool, "production")
+            .await
+            .unwrap()
+            .is_some()
+    );
+}
+
+// ==================== Deployment History Tests ====================
+
+fn new_deployment<'a>(
+    job_id: Uuid,
+    repo: &'a str,
+    pr_number: Option<u32>,
+    site_id: &'a str,
+    domain: &'a str,
+) -> db::NewDeployment<'a> {
+    db::NewDeployment {
+        job_id,
+        org: "nullisLabs",
+        repo,
+        pr_number,
+        site_id,
+        zone: "nullislabs",
+        domain,
+        commit_sha: "abc123",
+        deployment_type: db::DeploymentType::Webhook,
+        dry_run: false,
+    }
+}
+
+#[tokio::test]
+async fn test_deployment_status_tracking() {
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            None,
+            "nullislabs-website",
+            "nullislabs.io",
+        ),
+    )
+    .await
+    .expect("Failed to record deployment");
+
+    let updated = db::update_deployment_status(&db.pool, job_id, JobStatus::Success)
+        .await
+        .expect("Failed to update status");
+    assert!(updated);
+
+    let active = db::list_active_deployments(&db.pool, "nullislabs", "WEBSITE")
+        .await
+        .expect("Failed to list deployments");
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].status, "success");
+    assert_eq!(active[0].pr_number, None);
+
+    // Unknown jobs (e.g. cleanup jobs) are not tracked
+    let updated = db::update_deployment_status(&db.pool, Uuid::new_v4(), JobStatus::Cleaned)
+        .await
+        .expect("Failed to update status");
+    assert!(!updated);
+}
+
+#[tokio::test]
+async fn test_deployment_history_from_row() {
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            Some(42),
+            "nullislabs-website-pr-42",
+            "pr-42.nullislabs.io",
+        ),
+    )
+    .await
+    .expect("Failed to record deployment");
+    db::update_deployment_status(&db.pool, job_id, JobStatus::Success)
+        .await
+        .unwrap();
+    db::record_deployment_size(&db.pool, job_id, 4096)
+        .await
+        .unwrap();
+
+    // Every query returning deployments selects the same columns
+    let deployments = [
+        db::get_deployment(&db.pool, job_id).await.unwrap().unwrap(),
+        db::get_latest_site_deployment(&db.pool, "nullislabs-website-pr-42")
+            .await
+            .unwrap()
+            .unwrap(),
+        db::list_active_deployments(&db.pool, "nullislabs", "website")
+            .await
+            .unwrap()
+            .remove(0),
+        db::list_active_deployments_for_org(&db.pool, "nullislabs")
+            .await
+            .unwrap()
+            .remove(0),
+    ];
+
+    for deployment in deployments {
+        assert_eq!(deployment.job_id, job_id);
+        assert_eq!(deployment.github_org, "nullisLabs");
+        assert_eq!(deployment.github_repo, "website");
+        assert_eq!(deployment.pr_number, Some(42));
+        assert_eq!(deployment.site_id, "nullislabs-website-pr-42");
+        assert_eq!(deployment.zone, "nullislabs");
+        assert_eq!(deployment.domain, "pr-42.nullislabs.io");
+        assert_eq!(deployment.commit_sha, "abc123");
+        assert_eq!(deployment.status, "success");
+        assert!(deployment.completed_at.is_some());
+        assert_eq!(deployment.size_bytes, Some(4096));
+    }
+}
+
+#[tokio::test]
+async fn test_deployment_build_timing() {
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            Some(42),
+            "nullislabs-website-pr-42",
+            "pr-42.nullislabs.io",
+        ),
+    )
+    .await
+    .expect("Failed to record deployment");
+
+    let deployment = db::get_deployment(&db.pool, job_id).await.unwrap().unwrap();
+    assert!(deployment.started_at.is_none());
+    assert!(deployment.build_duration().is_none());
+
+    db::update_deployment_status(&db.pool, job_id, JobStatus::Building)
+        .await
+        .unwrap();
+    let started_at = db::get_deployment(&db.pool, job_id)
+        .await
+        .unwrap()
+        .unwrap()
+        .started_at
+        .expect("started_at set by the first building status");
+
+    // Phase reports are also `building` and must not move the start
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    db::update_deployment_status(&db.pool, job_id, JobStatus::Building)
+        .await
+        .unwrap();
+    db::update_deployment_status(&db.pool, job_id, JobStatus::Success)
+        .await
+        .unwrap();
+
+    let deployment = db::get_deployment(&db.pool, job_id).await.unwrap().unwrap();
+    assert_eq!(deployment.started_at, Some(started_at));
+    assert!(deployment.completed_at.is_some());
+    assert!(deployment.build_duration().unwrap() >= std::time::Duration::from_millis(20));
+}
+
+#[tokio::test]
+async fn test_cancelled_deployment_completes() {
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            Some(42),
+            "nullislabs-website-pr-42",
+            "pr-42.nullislabs.io",
+        ),
+    )
+    .await
+    .expect("Failed to record deployment");
+
+    db::update_deployment_status(&db.pool, job_id, JobStatus::Building)
+        .await
+        .unwrap();
+    db::update_deployment_status(&db.pool, job_id, JobStatus::Cancelled)
+        .await
+        .unwrap();
+
+    let deployment = db::get_deployment(&db.pool, job_id).await.unwrap().unwrap();
+    assert_eq!(deployment.status, "cancelled");
+    assert!(deployment.completed_at.is_some());
+}
+
+#[tokio::test]
+async fn test_progress_does_not_reopen_finished_deployment() {
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            Some(42),
+            "nullislabs-website-pr-42",
+            "pr-42.nullislabs.io",
+        ),
+    )
+    .await
+    .expect("Failed to record deployment");
+
+    for (status, changed) in [
+        (JobStatus::Building, true),
+        (JobStatus::Building, false),
+        (JobStatus::Cancelled, true),
+        (JobStatus::Building, false),
+        (JobStatus::Pending, false),
+    ] {
+        let updated = db::update_deployment_status(&db.pool, job_id, status)
+            .await
+            .unwrap();
+        assert_eq!(updated, changed, "{}", status);
+    }
+
+    let deployment = db::get_deployment(&db.pool, job_id).await.unwrap().unwrap();
+    assert_eq!(deployment.status, "cancelled");
+
+    // Only the real transitions were queued as events
+    let events = db::claim_outbox_events(&db.pool, 10, Duration::from_secs(300))
+        .await
+        .unwrap();
+    let statuses: Vec<_> = events
+        .iter()
+        .map(|event| event.payload["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, ["pending", "building", "cancelled"]);
+}
+
+#[tokio::test]
+async fn test_list_stuck_deployments() {
+    let db = TestDatabase::new().await;
+    let stuck = Uuid::new_v4();
+    let finished = Uuid::new_v4();
+    let recent = Uuid::new_v4();
+
+    async fn start_build(db: &TestDatabase, job_id: Uuid, pr: u32) {
+        let site_id = format!("nullislabs-website-pr-{}", pr);
+        let domain = format!("pr-{}.nullislabs.io", pr);
+        db::record_deployment(
+            &db.pool,
+            &new_deployment(job_id, "website", Some(pr), &site_id, &domain),
+        )
+        .await
+        .expect("Failed to record deployment");
+        db::update_deployment_status(&db.pool, job_id, JobStatus::Building)
+            .await
+            .unwrap();
+    }
+
+    start_build(&db, stuck, 1).await;
+    start_build(&db, finished, 2).await;
+    db::update_deployment_status(&db.pool, finished, JobStatus::Success)
+        .await
+        .unwrap();
+
+    // Updated after the timeout window started
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    start_build(&db, recent, 3).await;
+
+    let listed = db::list_stuck_deployments(&db.pool, Duration::from_secs(1))
+        .await
+        .unwrap();
+    assert_eq!(
+        listed.iter().map(|d| d.job_id).collect::<Vec<_>>(),
+        vec![stuck]
+    );
+
+    // Once reconciled it's no longer stuck
+    db::update_deployment_status(&db.pool, stuck, JobStatus::Failed)
+        .await
+        .unwrap();
+    assert!(
+        db::list_stuck_deployments(&db.pool, Duration::from_secs(1))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_list_active_previews() {
+    let db = TestDatabase::new().await;
+    let preview = Uuid::new_v4();
+    let cleaned = Uuid::new_v4();
+    let production = Uuid::new_v4();
+
+    for (job_id, pr) in [(preview, Some(1)), (cleaned, Some(2)), (production, None)] {
+        let site_id = match pr {
+            Some(pr) => format!("nullislabs-website-pr-{}", pr),
+            None => "nullislabs-website".to_string(),
+        };
+        db::record_deployment(
+            &db.pool,
+            &new_deployment(job_id, "website", pr, &site_id, "nullislabs.io"),
+        )
+        .await
+        .expect("Failed to record deployment");
+        db::update_deployment_status(&db.pool, job_id, JobStatus::Success)
+            .await
+            .unwrap();
+    }
+    db::mark_site_cleaned(&db.pool, "nullislabs-website-pr-2")
+        .await
+        .unwrap();
+
+    let listed = db::list_active_previews(&db.pool).await.unwrap();
+    assert_eq!(
+        listed.iter().map(|d| d.job_id).collect::<Vec<_>>(),
+        vec![preview]
+    );
+}
+
+#[tokio::test]
+async fn test_paused_repo_skips_dispatch() {
+    let db = TestDatabase::new().await;
+
+    // Repos without settings deploy normally
+    assert!(
+        !db::is_repo_paused(&db.pool, "nullisLabs", "website")
+            .await
+            .unwrap()
+    );
+
+    let settings = db::set_repo_paused(&db.pool, "nullisLabs", "website", true)
+        .await
+        .unwrap();
+    assert!(settings.paused);
+    assert_eq!(settings.github_org, "nullisLabs");
+
+    // GitHub names are case-insensitive; other repos are unaffected
+    assert!(
+        db::is_repo_paused(&db.pool, "nullislabs", "Website")
+            .await
+            .unwrap()
+    );
+    assert!(
+        !db::is_repo_paused(&db.pool, "nullisLabs", "docs")
+            .await
+            .unwrap()
+    );
+
+    // Resuming updates the same row
+    let resumed = db::set_repo_paused(&db.pool, "NULLISLABS", "website", false)
+        .await
+        .unwrap();
+    assert!(!resumed.paused);
+    assert_eq!(resumed.github_org, "nullisLabs");
+    assert!(
+        !db::is_repo_paused(&db.pool, "nullisLabs", "website")
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_org_quota_rejects_dispatch() {
+    let db = TestDatabase::new().await;
+    const MB: u64 = 1024 * 1024;
+
+    let auth = db::upsert_authorized_org(
+        &db.pool,
+        "nullisLabs",
+        &["nullislabs".to_string()],
+        &["*.nullislabs.io".to_string()],
+        db::OrgQuota {
+            max_active_deployments: Some(2),
+            max_total_mb: Some(100),
+        },
+        &db::RepoFilter::default(),
+    )
+    .await
+    .expect("Failed to create authorized org");
+    assert_eq!(auth.max_active_deployments, Some(2));
+    assert_eq!(auth.max_total_mb, Some(100));
+
+    // Two live previews, the first rebuilt since its size was reported
+    let sites = [
+        (Uuid::new_v4(), Some(1), "nullislabs-website-pr-1", 30 * MB),
+        (Uuid::new_v4(), Some(2), "nullislabs-website-pr-2", 20 * MB),
+    ];
+    for (job_id, pr, site_id, size) in sites {
+        db::record_deployment(
+            &db.pool,
+            &new_deployment(job_id, "website", pr, site_id, "pr.nullislabs.io"),
+        )
+        .await
+        .unwrap();
+        db::update_deployment_status(&db.pool, job_id, JobStatus::Success)
+            .await
+            .unwrap();
+        db::record_deployment_size(&db.pool, job_id, size)
+            .await
+            .unwrap();
+    }
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            Uuid::new_v4(),
+            "website",
+            Some(1),
+            "nullislabs-website-pr-1",
+            "pr.nullislabs.io",
+        ),
+    )
+    .await
+    .unwrap();
+
+    let usage = db::get_org_usage(&db.pool, "nullislabs").await.unwrap();
+    assert_eq!(usage.active_deployments, 2);
+    assert_eq!(usage.total_bytes, (50 * MB) as i64);
+
+    // A third preview is rejected; rebuilding an existing one is not
+    assert_eq!(
+        auth.check_quota(&usage, false),
+        Err(db::QuotaExceeded::ActiveDeployments { active: 2, max: 2 })
+    );
+    assert!(auth.check_quota(&usage, true).is_ok());
+
+    // Cleaned sites free their slot and disk
+    db::mark_site_cleaned(&db.pool, "nullislabs-website-pr-2")
+        .await
+        .unwrap();
+    let usage = db::get_org_usage(&db.pool, "nullislabs").await.unwrap();
+    assert_eq!(usage.active_deployments, 1);
+    assert_eq!(usage.total_bytes, (30 * MB) as i64);
+    assert!(auth.check_quota(&usage, false).is_ok());
+
+    // Over the disk quota even a redeploy is rejected
+    let (job_id, ..) = sites[0];
+    db::record_deployment_size(&db.pool, job_id, 100 * MB)
+        .await
+        .unwrap();
+    let usage = db::get_org_usage(&db.pool, "nullislabs").await.unwrap();
+    assert!(matches!(
+        auth.check_quota(&usage, true),
+        Err(db::QuotaExceeded::TotalSize { max_mb: 100, .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_list_active_deployments_latest_per_site() {
+    let db = TestDatabase::new().await;
+    let latest_job = Uuid::new_v4();
+
+    for job_id in [Uuid::new_v4(), latest_job] {
+        db::record_deployment(
+            &db.pool,
+            &new_deployment(
+                job_id,
+                "website",
+                None,
+                "nullislabs-website",
+                "nullislabs.io",
+            ),
+        )
+        .await
+        .expect("Failed to record deployment");
+    }
+
+    let active = db::list_active_deployments(&db.pool, "nullisLabs", "website")
+        .await
+        .expect("Failed to list deployments");
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].job_id, latest_job);
+}
+
+#[tokio::test]
+async fn test_dry_run_leaves_live_deployment_current() {
+    let db = TestDatabase::new().await;
+    let site_id = "nullislabs-website-pr-42";
+    let live_job = Uuid::new_v4();
+    let dry_run_job = Uuid::new_v4();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            live_job,
+            "website",
+            Some(42),
+            site_id,
+            "pr-42.nullislabs.io",
+        ),
+    )
+    .await
+    .expect("Failed to record deployment");
+    db::record_deployment(
+        &db.pool,
+        &db::NewDeployment {
+            dry_run: true,
+            ..new_deployment(
+                dry_run_job,
+                "website",
+                Some(42),
+                site_id,
+                "pr-42.nullislabs.io",
+            )
+        },
+    )
+    .await
+    .expect("Failed to record dry run");
+
+    let dry_run = db::get_deployment(&db.pool, dry_run_job)
+        .await
+        .expect("Failed to get deployment")
+        .expect("Dry run not found");
+    assert!(dry_run.dry_run);
+
+    // The newer dry run didn't replace what the site serves
+    let latest = db::get_latest_site_deployment(&db.pool, site_id)
+        .await
+        .expect("Failed to get deployment")
+        .expect("Deployment not found");
+    assert_eq!(latest.job_id, live_job);
+    let active = db::list_active_deployments(&db.pool, "nullisLabs", "website")
+        .await
+        .expect("Failed to list deployments");
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].job_id, live_job);
+    let previews = db::list_active_previews(&db.pool)
+        .await
+        .expect("Failed to list previews");
+    assert_eq!(previews.len(), 1);
+    assert_eq!(previews[0].job_id, live_job);
+}
+
+#[tokio::test]
+async fn test_manual_preview_cleaned_with_repo() {
+    let db = TestDatabase::new().await;
+    let site_id = "nullislabs-website-commit-0123456";
+
+    db::record_deployment(
+        &db.pool,
+        &db::NewDeployment {
+            deployment_type: db::DeploymentType::Manual,
+            ..new_deployment(
+                Uuid::new_v4(),
+                "website",
+                None,
+                site_id,
+                "commit-0123456-website.nullislabs.io",
+            )
+        },
+    )
+    .await
+    .expect("Failed to record deployment");
+
+    // Repo removal and uninstall cleanup pick up manual previews too
+    let active = db::list_active_deployments(&db.pool, "nullisLabs", "website")
+        .await
+        .expect("Failed to list deployments");
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].site_id, site_id);
+    assert_eq!(active[0].deployment_type, "manual");
+    assert_eq!(active[0].pr_number, None);
+
+    let cleaned = db::mark_site_cleaned(&db.pool, site_id)
+        .await
+        .expect("Failed to mark site cleaned");
+    assert_eq!(cleaned, 1);
+}
+
+#[tokio::test]
+async fn test_list_active_deployments_matching_filters() {
+    let db = TestDatabase::new().await;
+
+    let deployments = [
+        ("website", None, "nullislabs-website", JobStatus::Success),
+        (
+            "website",
+            Some(1),
+            "nullislabs-website-pr-1",
+            JobStatus::Failed,
+        ),
+        (
+            "website",
+            Some(2),
+            "nullislabs-website-pr-2",
+            JobStatus::Success,
+        ),
+        ("docs", None, "nullislabs-docs", JobStatus::Failed),
+    ];
+    for (repo, pr, site_id, status) in deployments {
+        let job_id = Uuid::new_v4();
+        db::record_deployment(
+            &db.pool,
+            &new_deployment(job_id, repo, pr, site_id, "nullislabs.io"),
+        )
+        .await
+        .expect("Failed to record deployment");
+        db::update_deployment_status(&db.pool, job_id, status)
+            .await
+            .unwrap();
+    }
+    db::mark_site_cleaned(&db.pool, "nullislabs-website-pr-2")
+        .await
+        .unwrap();
+    sqlx::query(
+        "UPDATE deployment_history SET created_at = NOW() - INTERVAL '2 days' \
+         WHERE site_id = 'nullislabs-docs'",
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    let sites = |filter: db::DeploymentFilter| {
+        let pool = db.pool.clone();
+        async move {
+            db::list_active_deployments_matching(&pool, &filter)
+                .await
+                .expect("Failed to list deployments")
+                .into_iter()
+                .map(|d| d.site_id)
+                .collect::<Vec<_>>()
+        }
+    };
+
+    assert_eq!(
+        sites(db::DeploymentFilter {
+            repo: Some("WEBSITE".to_string()),
+            ..Default::default()
+        })
+        .await,
+        ["nullislabs-website", "nullislabs-website-pr-1"]
+    );
+    assert_eq!(
+        sites(db::DeploymentFilter {
+            org: Some("nullislabs".to_string()),
+            status: Some(JobStatus::Failed),
+            ..Default::default()
+        })
+        .await,
+        ["nullislabs-docs", "nullislabs-website-pr-1"]
+    );
+    assert_eq!(
+        sites(db::DeploymentFilter {
+            created_before: Some(chrono::Utc::now() - chrono::Duration::days(1)),
+            ..Default::default()
+        })
+        .await,
+        ["nullislabs-docs"]
+    );
+    assert!(
+        sites(db::DeploymentFilter {
+            org: Some("acme".to_string()),
+            ..Default::default()
+        })
+        .await
+        .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_latest_site_deployment_domain() {
+    let db = TestDatabase::new().await;
+    let site_id = "nullislabs-website-pr-42";
+
+    let missing = db::get_latest_site_deployment(&db.pool, site_id)
+        .await
+        .expect("Failed to get deployment");
+    assert!(missing.is_none());
+
+    // The domain pattern changed between two pushes to the PR
+    for domain in ["pr-42.nullislabs.io", "pr-42-website.nullislabs.io"] {
+        db::record_deployment(
+            &db.pool,
+            &new_deployment(Uuid::new_v4(), "website", Some(42), site_id, domain),
+        )
+        .await
+        .expect("Failed to record deployment");
+    }
+
+    let latest = db::get_latest_site_deployment(&db.pool, site_id)
+        .await
+        .expect("Failed to get deployment")
+        .expect("Deployment not found");
+    assert_eq!(latest.domain, "pr-42-website.nullislabs.io");
+}
+
+#[tokio::test]
+async fn test_list_active_deployments_for_org_excludes_cleaned() {
+    let db = TestDatabase::new().await;
+
+    let deployments = [
+        ("website", None, "nullislabs-website", "nullislabs.io"),
+        (
+            "website",
+            Some(42),
+            "nullislabs-website-pr-42",
+            "pr-42.nullislabs.io",
+        ),
+        ("docs", None, "nullislabs-docs", "docs.nullislabs.io"),
+    ];
+    for (repo, pr, site_id, domain) in deployments {
+        db::record_deployment(
+            &db.pool,
+            &new_deployment(Uuid::new_v4(), repo, pr, site_id, domain),
+        )
+        .await
+        .expect("Failed to record deployment");
+    }
+
+    // PR closed
+    let cleaned = db::mark_site_cleaned(&db.pool, "nullislabs-website-pr-42")
+        .await
+        .expect("Failed to mark site cleaned");
+    assert_eq!(cleaned, 1);
+
+    // App uninstalled: every remaining site in the org is returned
+    let active = db::list_active_deployments_for_org(&db.pool, "NullisLabs")
+        .await
+        .expect("Failed to list deployments");
+    let sites: Vec<_> = active.iter().map(|d| d.site_id.as_str()).collect();
+    assert_eq!(sites, vec!["nullislabs-docs", "nullislabs-website"]);
+
+    // Repo archived: only that repo's sites are returned
+    let active = db::list_active_deployments(&db.pool, "nullisLabs", "docs")
+        .await
+        .expect("Failed to list deployments");
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].domain, "docs.nullislabs.io");
+
+    // Marking again is a no-op
+    let cleaned = db::mark_site_cleaned(&db.pool, "nullislabs-website-pr-42")
+        .await
+        .expect("Failed to mark site cleaned");
+    assert_eq!(cleaned, 0);
+}
+
+#[tokio::test]
+async fn test_deployment_events_recorded_in_order() {
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            Some(42),
+            "nullislabs-website-pr-42",
+            "pr-42.nullislabs.io",
+        ),
+    )
+    .await
+    .expect("Failed to record deployment");
+
+    let phases = [
+        DeploymentPhase::Cloned,
+        DeploymentPhase::Building,
+        DeploymentPhase::Deployed,
+        DeploymentPhase::CaddyConfigured,
+        DeploymentPhase::CloudflareConfigured,
+    ];
+    for phase in phases {
+        db::record_deployment_event(&db.pool, job_id, phase)
+            .await
+            .expect("Failed to record event");
+    }
+
+    // Events for other jobs don't leak into the timeline
+    db::record_deployment_event(&db.pool, Uuid::new_v4(), DeploymentPhase::Cloned)
+        .await
+        .expect("Failed to record event");
+
+    let events = db::list_deployment_events(&db.pool, job_id)
+        .await
+        .expect("Failed to list events");
+    let recorded: Vec<&str> = events.iter().map(|e| e.phase.as_str()).collect();
+    assert_eq!(
+        recorded,
+        [
+            "cloned",
+            "building",
+            "deployed",
+            "caddy_configured",
+            "cloudflare_configured"
+        ]
+    );
+    assert!(
+        events
+            .windows(2)
+            .all(|w| w[0].created_at <= w[1].created_at)
+    );
+
+    let deployment = db::get_deployment(&db.pool, job_id)
+        .await
+        .expect("Failed to get deployment")
+        .expect("Deployment not found");
+    assert_eq!(deployment.site_id, "nullislabs-website-pr-42");
+    assert!(
+        db::get_deployment(&db.pool, Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+// ==================== Pending Approval Tests ====================
+
+fn held_job(job_id: Uuid) -> BuildJob {
+    BuildJob {
+        job_id,
+        repo_url: "https://github.com/nullisLabs/website.git".to_string(),
+        git_token: "ghs_secret".to_string(),
+        branch: "main".to_string(),
+        commit_sha: "abc123".to_string(),
+        pr_number: None,
+        site_id: None,
+        domain: "nullislabs.io".to_string(),
+        aliases: Vec::new(),
+        site_type: SiteType::Vite,
+        callback_url: "http://central/api/status".to_string(),
+        repo_name: "website".to_string(),
+        org_name: "nullisLabs".to_string(),
+        subdomain: None,
+        build_args: Default::default(),
+        basic_auth: None,
+        precompress: true,
+        git_lfs: false,
+        index_names: Vec::new(),
+        browse: false,
+        caddy_extra: None,
+        isolation: None,
+        dry_run: false,
+        worker_id: None,
+        memory_mb: None,
+        cpu_cores: None,
+        pids_limit: None,
+        node_version: Some("22".to_string()),
+        environment: Some("nullislabs".to_string()),
+        artifact: None,
+        canary_percent: None,
+        immutable_paths: None,
+        purge_cache: None,
+        maintenance_page: None,
+    }
+}
+
+async fn deployment_status(db: &TestDatabase, job_id: Uuid) -> String {
+    db::get_deployment(&db.pool, job_id)
+        .await
+        .expect("Failed to get deployment")
+        .expect("Deployment not found")
+        .status
+}
+
+#[tokio::test]
+async fn test_pending_approval_approve() {
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_pending_approval(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            None,
+            "nullislabs-website",
+            "nullislabs.io",
+        ),
+        12345,
+        &held_job(job_id),
+        Duration::from_secs(3600),
+    )
+    .await
+    .expect("Failed to record pending approval");
+    assert_eq!(deployment_status(&db, job_id).await, "pending_approval");
+
+    // Unexpired approvals survive the sweep
+    let expired = db::expire_pending_approvals(&db.pool)
+        .await
+        .expect("Failed to expire approvals");
+    assert!(expired.is_empty());
+
+    let approval = db::take_pending_approval(&db.pool, job_id)
+        .await
+        .expect("Failed to take approval")
+        .expect("Approval not found");
+    assert_eq!(approval.installation_id, 12345);
+    assert_eq!(approval.job.job_id, job_id);
+    assert_eq!(approval.job.node_version.as_deref(), Some("22"));
+    assert!(approval.job.precompress);
+    // The clone token is never persisted
+    assert!(approval.job.git_token.is_empty());
+    assert_eq!(deployment_status(&db, job_id).await, "pending");
+
+    // A deployment can only be approved once
+    assert!(
+        db::take_pending_approval(&db.pool, job_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        db::take_pending_approval(&db.pool, Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_pending_approval_expires() {
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_pending_approval(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            None,
+            "nullislabs-website",
+            "nullislabs.io",
+        ),
+        12345,
+        &held_job(job_id),
+        Duration::ZERO,
+    )
+    .await
+    .expect("Failed to record pending approval");
+
+    // Past the window the approval can't be taken, even before the sweep
+    assert!(
+        db::take_pending_approval(&db.pool, job_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(deployment_status(&db, job_id).await, "pending_approval");
+
+    let expired = db::expire_pending_approvals(&db.pool)
+        .await
+        .expect("Failed to expire approvals");
+    assert_eq!(expired, vec![job_id]);
+    assert_eq!(
+        deployment_status(&db, job_id).await,
+        JobStatus::Expired.to_string()
+    );
+
+    // Already expired approvals aren't reported again
+    let expired = db::expire_pending_approvals(&db.pool)
+        .await
+        .expect("Failed to expire approvals");
+    assert!(expired.is_empty());
+}
+
+#[tokio::test]
+async fn test_central_status_changes_queue_outbox_events() {
+    let db = TestDatabase::new().await;
+    let (approved, expired, cleaned) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    for (job_id, expires_in) in [
+        (approved, Duration::from_secs(3600)),
+        (expired, Duration::ZERO),
+    ] {
+        db::record_pending_approval(
+            &db.pool,
+            &new_deployment(
+                job_id,
+                "website",
+                None,
+                "nullislabs-website",
+                "nullislabs.io",
+            ),
+            12345,
+            &held_job(job_id),
+            expires_in,
+        )
+        .await
+        .expect("Failed to record pending approval");
+    }
+    db::take_pending_approval(&db.pool, approved)
+        .await
+        .unwrap()
+        .expect("Approval not found");
+    db::expire_pending_approvals(&db.pool).await.unwrap();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            cleaned,
+            "website",
+            Some(42),
+            "nullislabs-website-pr-42",
+            "pr-42.nullislabs.io",
+        ),
+    )
+    .await
+    .unwrap();
+    db::mark_site_cleaned(&db.pool, "nullislabs-website-pr-42")
+        .await
+        .unwrap();
+
+    let events = db::claim_outbox_events(&db.pool, 10, Duration::from_secs(300))
+        .await
+        .unwrap();
+    let changes: Vec<_> = events
+        .iter()
+        .map(|event| {
+            (
+                event.job_id,
+                event.payload["previous_status"].as_str(),
+                event.payload["status"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (approved, None, "pending_approval"),
+            (expired, None, "pending_approval"),
+            (approved, Some("pending_approval"), "pending"),
+            (expired, Some("pending_approval"), "expired"),
+            (cleaned, None, "pending"),
+            (cleaned, Some("pending"), "cleaned"),
+        ]
+    );
+}
+
+fn inventory_site(site_id: &str, size_bytes: u64) -> InventorySite {
+    InventorySite {
+        site_id: site_id.to_string(),
+        domain: format!("{}.nxm.rs", site_id),
+        size_bytes,
+        deployed_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_worker_inventory_replaces_sites() {
+    let db = TestDatabase::new().await;
+
+    let removed = db::replace_worker_sites(
+        &db.pool,
+        "nxm",
+        &[
+            inventory_site("nullislabs-website-main", 1000),
+            inventory_site("nullislabs-website-pr-1", 2000),
+        ],
+    )
+    .await
+    .expect("Failed to store inventory");
+    assert_eq!(removed, 0);
+
+    // Another zone's inventory is kept separately
+    db::replace_worker_sites(&db.pool, "staging", &[inventory_site("staging-site", 10)])
+        .await
+        .expect("Failed to store inventory");
+
+    // The PR site vanished, the main site was redeployed and a new PR appeared
+    let removed = db::replace_worker_sites(
+        &db.pool,
+        "nxm",
+        &[
+            inventory_site("nullislabs-website-main", 1500),
+            inventory_site("nullislabs-website-pr-2", 3000),
+        ],
+    )
+    .await
+    .expect("Failed to store inventory");
+    assert_eq!(removed, 1);
+
+    let sites = db::list_worker_sites(&db.pool, "nxm")
+        .await
+        .expect("Failed to list sites");
+    let sites: Vec<(&str, i64)> = sites
+        .iter()
+        .map(|site| (site.site_id.as_str(), site.size_bytes))
+        .collect();
+    assert_eq!(
+        sites,
+        [
+            ("nullislabs-website-main", 1500),
+            ("nullislabs-website-pr-2", 3000)
+        ]
+    );
+
+    // An empty report means the worker serves nothing
+    let removed = db::replace_worker_sites(&db.pool, "nxm", &[])
+        .await
+        .expect("Failed to store inventory");
+    assert_eq!(removed, 2);
+    assert!(
+        db::list_worker_sites(&db.pool, "nxm")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(
+        db::list_worker_sites(&db.pool, "staging")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_org_upsert_audited() {
+    let db = TestDatabase::new().await;
+
+    let (status, body) = admin_request(
+        db.app_state(),
+        "POST",
+        "/api/admin/auth",
+        Some(serde_json::json!({
+            "github_org": "nullisLabs",
+            "zones": ["nxm"],
+            "domain_patterns": ["*.nxm.rs"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["github_org"], "nullisLabs");
+
+    let entries = db::list_admin_audit(&db.pool, None, 50)
+        .await
+        .expect("Failed to list audit log");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].actor, "admin");
+    assert_eq!(entries[0].action, "auth.upsert");
+    assert_eq!(entries[0].target, "nullisLabs");
+    let payload = entries[0].payload.as_ref().expect("Missing audit payload");
+    assert_eq!(payload["zones"], serde_json::json!(["nxm"]));
+    assert_eq!(payload["domain_patterns"], serde_json::json!(["*.nxm.rs"]));
+}
+
+#[tokio::test]
+async fn test_admin_audit_pagination() {
+    let db = TestDatabase::new().await;
+
+    for zone in ["nxm", "staging", "production"] {
+        db::record_admin_action(
+            &db.pool,
+            "admin",
+            db::AdminAction::WorkerDisable,
+            zone,
+            None,
+        )
+        .await
+        .expect("Failed to record admin action");
+    }
+
+    // Newest first, continuing below the last ID of the previous page
+    let first = db::list_admin_audit(&db.pool, None, 2).await.unwrap();
+    let targets: Vec<&str> = first.iter().map(|e| e.target.as_str()).collect();
+    assert_eq!(targets, ["production", "staging"]);
+    assert!(first[0].payload.is_none());
+
+    let second = db::list_admin_audit(&db.pool, Some(first[1].id), 2)
+        .await
+        .unwrap();
+    let targets: Vec<&str> = second.iter().map(|e| e.target.as_str()).collect();
+    assert_eq!(targets, ["nxm"]);
+}
+
+#[tokio::test]
+async fn test_status_change_queues_outbox_event() {
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            Some(42),
+            "nullislabs-website-pr-42",
+            "pr-42.nullislabs.io",
+        ),
+    )
+    .await
+    .unwrap();
+
+    // Recording queues the first event; repeated progress reports queue one
+    for status in [JobStatus::Building, JobStatus::Building, JobStatus::Success] {
+        db::update_deployment_status(&db.pool, job_id, status)
+            .await
+            .unwrap();
+    }
+    db::update_deployment_status(&db.pool, Uuid::new_v4(), JobStatus::Cleaned)
+        .await
+        .unwrap();
+
+    let events = db::claim_outbox_events(&db.pool, 10, Duration::from_secs(300))
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().all(|event| event.job_id == job_id));
+    assert_eq!(
+        events[0].payload["previous_status"],
+        serde_json::Value::Null
+    );
+    assert_eq!(events[0].payload["status"], "pending");
+    assert_eq!(events[1].payload["previous_status"], "pending");
+    assert_eq!(events[1].payload["status"], "building");
+    assert_eq!(events[2].payload["previous_status"], "building");
+    assert_eq!(events[2].payload["status"], "success");
+    assert_eq!(events[2].payload["pr_number"], 42);
+    assert_eq!(events[2].payload["domain"], "pr-42.nullislabs.io");
+
+    // Claimed events aren't handed out again while their lease lasts
+    let claimed_again = db::claim_outbox_events(&db.pool, 10, Duration::from_secs(300))
+        .await
+        .unwrap();
+    assert!(claimed_again.is_empty());
+}
+
+#[tokio::test]
+async fn test_outbox_event_retried_until_delivered() {
+    use catapult::central::outbox::{EventSink, publish_due_events};
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let db = TestDatabase::new().await;
+    let job_id = Uuid::new_v4();
+
+    db::record_deployment(
+        &db.pool,
+        &new_deployment(
+            job_id,
+            "website",
+            None,
+            "nullislabs-website",
+            "nullislabs.io",
+        ),
+    )
+    .await
+    .unwrap();
+
+    // The sink is down for the first delivery only
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let sinks = [EventSink::Webhook(
+        EventSink::parse_url(&format!("{}/events", server.uri())).unwrap(),
+    )];
+    let client = reqwest::Client::new();
+
+    let delivered = publish_due_events(&db.pool, &client, &sinks).await.unwrap();
+    assert_eq!(delivered, 0);
+
+    let (attempts, last_error): (i32, Option<String>) =
+        sqlx::query_as("SELECT attempts, last_error FROM deployment_outbox")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+    assert_eq!(attempts, 1);
+    assert!(last_error.unwrap().contains("503"));
+
+    // Not due again until the backoff passes
+    let delivered = publish_due_events(&db.pool, &client, &sinks).await.unwrap();
+    assert_eq!(delivered, 0);
+
+    sqlx::query("UPDATE deployment_outbox SET next_attempt_at = NOW()")
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    let delivered = publish_due_events(&db.pool, &client, &sinks).await.unwrap();
+    assert_eq!(delivered, 1);
+
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM deployment_outbox")
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+}
@@ -1,9 +1,13 @@
 //! Common test utilities and fixtures
 
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use catapult::central::server::AppState;
 use sqlx::PgPool;
 use testcontainers::ContainerAsync;
 use testcontainers::runners::AsyncRunner;
 use testcontainers_modules::postgres::Postgres;
+use tower::util::ServiceExt;
 
 /// Test database container with connection pool
 pub struct TestDatabase {
@@ -44,13 +48,22 @@ impl TestDatabase {
         }
     }
 
+    /// Central state whose handlers use this database
+    pub fn app_state(&self) -> AppState {
+        AppState {
+            db: self.pool.clone(),
+            ..AppState::for_tests()
+        }
+    }
+
     /// Create a test worker in the database (needed for FK constraint)
+    #[allow(dead_code)] // Unused by the API tests
     pub async fn create_test_worker(&self, environment: &str) {
         sqlx::query(
             r#"
             INSERT INTO workers (environment, endpoint, enabled)
             VALUES ($1, $2, $3)
-            ON CONFLICT (environment) DO NOTHING
+            ON CONFLICT (environment, endpoint) DO NOTHING
             "#,
         )
         .bind(environment)
@@ -62,15 +75,8 @@ impl TestDatabase {
     }
 }
 
-/// Create a test HTTP client for API testing
-pub fn test_http_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .expect("Failed to create HTTP client")
-}
-
 /// Generate a random test secret
+#[allow(dead_code)] // Unused by the database tests
 pub fn random_secret() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let timestamp = SystemTime::now()
@@ -79,3 +85,31 @@ pub fn random_secret() -> String {
         .as_nanos();
     format!("test-secret-{}", timestamp)
 }
+
+/// Send a request to the admin API with the test admin key, returning the
+/// status and JSON body
+#[allow(dead_code)] // Unused by the API tests
+pub async fn admin_request(
+    state: AppState,
+    method: &str,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(path)
+        .header("authorization", "Bearer test-admin-key")
+        .header("content-type", "application/json");
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+
+    let response = catapult::central::server::admin_routes()
+        .with_state(state)
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
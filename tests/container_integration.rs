@@ -607,10 +607,18 @@ async fn test_build_network_blocks_rfc1918() {
     let image = build_image();
 
     // Ensure build network exists (will fail in rootless mode due to iptables)
-    if let Err(e) = ensure_build_network(&docker).await {
-        eprintln!("Skipping RFC1918 test: {}", e);
-        eprintln!("This test requires rootful Podman with iptables access");
-        return;
+    match ensure_build_network(&docker, false).await {
+        Ok(status) if status.active => {}
+        Ok(status) => {
+            eprintln!("Skipping RFC1918 test: {}", status.summary());
+            eprintln!("This test requires rootful Podman with iptables access");
+            return;
+        }
+        Err(e) => {
+            eprintln!("Skipping RFC1918 test: {}", e);
+            eprintln!("This test requires rootful Podman with iptables access");
+            return;
+        }
     }
 
     // Pull image if needed
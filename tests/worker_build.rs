@@ -1,9 +1,10 @@
 //! Integration tests for Worker build operations
 
-mod common;
-
 use catapult::shared::{DeployConfig, SiteType};
-use catapult::worker::builder::types::{BuildContext, detect_site_type, load_deploy_config};
+use catapult::worker::builder::types::{
+    BuildContext, PackageManager, ResourceLimits, detect_package_manager, detect_site_type,
+    load_deploy_config, resolve_root_dir, resolve_site_type,
+};
 use std::fs;
 use tempfile::TempDir;
 
@@ -113,6 +114,76 @@ async fn test_sveltekit_takes_priority_over_vite() {
     assert_eq!(site_type, SiteType::SvelteKit);
 }
 
+#[tokio::test]
+async fn test_detect_docusaurus() {
+    let dir = create_test_repo();
+    fs::write(dir.path().join("package.json"), "{}").unwrap();
+    fs::write(dir.path().join("docusaurus.config.ts"), "// docusaurus").unwrap();
+
+    let site_type = detect_site_type(dir.path()).await;
+    assert_eq!(site_type, SiteType::Docusaurus);
+}
+
+#[tokio::test]
+async fn test_detect_vitepress() {
+    let dir = create_test_repo();
+    fs::create_dir(dir.path().join(".vitepress")).unwrap();
+    fs::write(dir.path().join(".vitepress/config.mts"), "// vitepress").unwrap();
+
+    let site_type = detect_site_type(dir.path()).await;
+    assert_eq!(site_type, SiteType::VitePress);
+}
+
+#[tokio::test]
+async fn test_detect_vitepress_in_docs() {
+    let dir = create_test_repo();
+    fs::write(dir.path().join("package.json"), "{}").unwrap();
+    fs::create_dir_all(dir.path().join("docs/.vitepress")).unwrap();
+    fs::write(dir.path().join("docs/.vitepress/config.ts"), "// vitepress").unwrap();
+
+    let site_type = detect_site_type(dir.path()).await;
+    assert_eq!(site_type, SiteType::VitePress);
+
+    // The build runs against docs/ and its output is picked up there
+    let mut config = DeployConfig::default();
+    let site_type = resolve_site_type(SiteType::Auto, &mut config, dir.path())
+        .await
+        .unwrap();
+    let context =
+        BuildContext::with_package_manager(site_type, PackageManager::Npm, Some(config)).unwrap();
+    assert_eq!(context.output_dir, "docs/.vitepress/dist");
+    assert_eq!(
+        context.build_command,
+        "npm ci && npx vitepress build 'docs'"
+    );
+    assert_eq!(
+        context.offline_build_command(),
+        "npx vitepress build 'docs'"
+    );
+
+    // An explicit output directory is kept
+    let mut config = DeployConfig {
+        output_dir: Some("site".to_string()),
+        ..Default::default()
+    };
+    resolve_site_type(SiteType::Auto, &mut config, dir.path())
+        .await
+        .unwrap();
+    assert_eq!(config.output_dir.as_deref(), Some("site"));
+}
+
+#[tokio::test]
+async fn test_vitepress_takes_priority_over_vite() {
+    let dir = create_test_repo();
+    fs::create_dir(dir.path().join(".vitepress")).unwrap();
+    fs::write(dir.path().join(".vitepress/config.js"), "// vitepress").unwrap();
+    fs::write(dir.path().join("vite.config.ts"), "// vite").unwrap();
+    fs::write(dir.path().join("package.json"), "{}").unwrap();
+
+    let site_type = detect_site_type(dir.path()).await;
+    assert_eq!(site_type, SiteType::VitePress);
+}
+
 #[tokio::test]
 async fn test_load_deploy_config_missing() {
     let dir = create_test_repo();
@@ -152,9 +223,83 @@ async fn test_load_deploy_config_invalid_json() {
     assert!(config.is_none());
 }
 
+#[tokio::test]
+async fn test_deploy_config_auto_build_type_detects() {
+    let dir = create_test_repo();
+    fs::write(dir.path().join("svelte.config.js"), "// svelte config").unwrap();
+    fs::write(dir.path().join(".deploy.json"), r#"{"build_type": "auto"}"#).unwrap();
+
+    let mut config = load_deploy_config(dir.path()).await.unwrap();
+    assert_eq!(config.build_type, Some(SiteType::Auto));
+
+    let site_type = resolve_site_type(SiteType::Auto, &mut config, dir.path())
+        .await
+        .unwrap();
+    assert_eq!(site_type, SiteType::SvelteKit);
+
+    // The explicit auto no longer overrides the detected type
+    let context =
+        BuildContext::with_package_manager(site_type, PackageManager::Npm, Some(config)).unwrap();
+    assert_eq!(context.site_type, SiteType::SvelteKit);
+    assert_eq!(context.output_dir, "build");
+}
+
+#[tokio::test]
+async fn test_auto_detected_type_resolves_default_immutable_paths() {
+    let dir = create_test_repo();
+    fs::write(dir.path().join("svelte.config.js"), "// svelte config").unwrap();
+
+    // Nothing configured: the detected type's defaults apply
+    let mut config = DeployConfig::default();
+    let site_type = resolve_site_type(SiteType::Auto, &mut config, dir.path())
+        .await
+        .unwrap();
+    assert_eq!(
+        config.resolve_immutable_paths(site_type),
+        vec!["/_app/immutable/*".to_string()]
+    );
+
+    // An explicit empty list turns them off
+    let mut config = DeployConfig {
+        immutable_paths: Some(Vec::new()),
+        ..Default::default()
+    };
+    let site_type = resolve_site_type(SiteType::Auto, &mut config, dir.path())
+        .await
+        .unwrap();
+    assert!(config.resolve_immutable_paths(site_type).is_empty());
+}
+
+#[tokio::test]
+async fn test_resolve_site_type_explicit_and_undetectable() {
+    let dir = create_test_repo();
+    fs::write(dir.path().join("svelte.config.js"), "// svelte config").unwrap();
+
+    // An explicit deploy config type wins over detection
+    let mut config = DeployConfig {
+        build_type: Some(SiteType::Zola),
+        ..Default::default()
+    };
+    let site_type = resolve_site_type(SiteType::Auto, &mut config, dir.path())
+        .await
+        .unwrap();
+    assert_eq!(site_type, SiteType::Zola);
+
+    let empty = create_test_repo();
+    let mut config = DeployConfig {
+        build_type: Some(SiteType::Auto),
+        ..Default::default()
+    };
+    let err = resolve_site_type(SiteType::Auto, &mut config, empty.path())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Could not auto-detect"));
+}
+
 #[test]
 fn test_build_context_sveltekit_defaults() {
-    let context = BuildContext::new(SiteType::SvelteKit, None);
+    let context =
+        BuildContext::with_package_manager(SiteType::SvelteKit, PackageManager::Npm, None).unwrap();
 
     assert_eq!(context.site_type, SiteType::SvelteKit);
     assert_eq!(context.build_command, "npm ci && npm run build");
@@ -167,7 +312,8 @@ fn test_build_context_sveltekit_defaults() {
 
 #[test]
 fn test_build_context_vite_defaults() {
-    let context = BuildContext::new(SiteType::Vite, None);
+    let context =
+        BuildContext::with_package_manager(SiteType::Vite, PackageManager::Npm, None).unwrap();
 
     assert_eq!(context.site_type, SiteType::Vite);
     assert_eq!(context.build_command, "npm ci && npm run build");
@@ -180,7 +326,8 @@ fn test_build_context_vite_defaults() {
 
 #[test]
 fn test_build_context_zola_defaults() {
-    let context = BuildContext::new(SiteType::Zola, None);
+    let context =
+        BuildContext::with_package_manager(SiteType::Zola, PackageManager::Npm, None).unwrap();
 
     assert_eq!(context.site_type, SiteType::Zola);
     assert_eq!(context.build_command, "zola build");
@@ -191,9 +338,147 @@ fn test_build_context_zola_defaults() {
     );
 }
 
+#[test]
+fn test_build_context_docusaurus_defaults() {
+    let context =
+        BuildContext::with_package_manager(SiteType::Docusaurus, PackageManager::Npm, None)
+            .unwrap();
+
+    assert_eq!(context.build_command, "npm ci && npm run build");
+    assert_eq!(context.output_dir, "build");
+    assert_eq!(
+        context.flake_ref,
+        Some("github:nullisLabs/catapult#docusaurus".to_string())
+    );
+}
+
+#[test]
+fn test_build_context_vitepress_defaults() {
+    let context =
+        BuildContext::with_package_manager(SiteType::VitePress, PackageManager::Npm, None).unwrap();
+
+    assert_eq!(context.build_command, "npm ci && npx vitepress build");
+    assert_eq!(context.output_dir, ".vitepress/dist");
+    assert_eq!(
+        context.flake_ref,
+        Some("github:nullisLabs/catapult#vitepress".to_string())
+    );
+
+    let context =
+        BuildContext::with_package_manager(SiteType::VitePress, PackageManager::Pnpm, None)
+            .unwrap();
+    assert_eq!(
+        context.build_command,
+        "pnpm install --frozen-lockfile && pnpm exec vitepress build"
+    );
+    assert_eq!(context.offline_build_command(), "pnpm exec vitepress build");
+}
+
+#[tokio::test]
+async fn test_detect_package_manager() {
+    let dir = create_test_repo();
+    fs::write(dir.path().join("package.json"), "{}").unwrap();
+    assert_eq!(
+        detect_package_manager(dir.path()).await,
+        PackageManager::Npm
+    );
+
+    fs::write(dir.path().join("yarn.lock"), "").unwrap();
+    assert_eq!(
+        detect_package_manager(dir.path()).await,
+        PackageManager::Yarn
+    );
+
+    let dir = create_test_repo();
+    fs::write(dir.path().join("pnpm-lock.yaml"), "lockfileVersion: '9.0'").unwrap();
+    assert_eq!(
+        detect_package_manager(dir.path()).await,
+        PackageManager::Pnpm
+    );
+}
+
+#[test]
+fn test_build_context_package_manager_defaults() {
+    let command = |site_type, package_manager| {
+        BuildContext::with_package_manager(site_type, package_manager, None)
+            .unwrap()
+            .build_command
+    };
+
+    assert_eq!(
+        command(SiteType::SvelteKit, PackageManager::Npm),
+        "npm ci && npm run build"
+    );
+    assert_eq!(
+        command(SiteType::SvelteKit, PackageManager::Pnpm),
+        "pnpm install --frozen-lockfile && pnpm run build"
+    );
+    assert_eq!(
+        command(SiteType::Vite, PackageManager::Yarn),
+        "yarn install --immutable && yarn build"
+    );
+    // Only Node.js sites install packages
+    assert_eq!(command(SiteType::Zola, PackageManager::Pnpm), "zola build");
+    assert_eq!(
+        SiteType::Vite.default_build_command(),
+        Some(PackageManager::Npm.default_build_command())
+    );
+}
+
+#[test]
+fn test_build_context_package_manager_explicit_command() {
+    let deploy_config = DeployConfig {
+        build_command: Some("npm run build:docs".to_string()),
+        ..Default::default()
+    };
+
+    let context = BuildContext::with_package_manager(
+        SiteType::Vite,
+        PackageManager::Pnpm,
+        Some(deploy_config),
+    )
+    .unwrap();
+    assert_eq!(context.build_command, "npm run build:docs");
+}
+
+#[test]
+fn test_build_context_offline_build_command() {
+    let context =
+        BuildContext::with_package_manager(SiteType::SvelteKit, PackageManager::Pnpm, None)
+            .unwrap();
+    assert_eq!(
+        context.install_command.as_deref(),
+        Some("pnpm install --frozen-lockfile")
+    );
+    assert_eq!(context.offline_build_command(), "pnpm run build");
+
+    // Explicit commands can't be split, so they run offline in full
+    let deploy_config = DeployConfig {
+        build_command: Some("npm ci && npm run build:docs".to_string()),
+        ..Default::default()
+    };
+    let context = BuildContext::with_package_manager(
+        SiteType::Vite,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap();
+    assert!(context.install_command.is_none());
+    assert_eq!(
+        context.offline_build_command(),
+        "npm ci && npm run build:docs"
+    );
+
+    let context =
+        BuildContext::with_package_manager(SiteType::Zola, PackageManager::Npm, None).unwrap();
+    assert!(context.install_command.is_none());
+    assert_eq!(context.offline_build_command(), "zola build");
+}
+
 #[test]
 fn test_build_context_custom_no_flake() {
-    let context = BuildContext::new(SiteType::Custom, None);
+    let context =
+        BuildContext::with_package_manager(SiteType::Custom, PackageManager::Npm, None).unwrap();
 
     assert_eq!(context.site_type, SiteType::Custom);
     assert!(context.flake_ref.is_none());
@@ -209,7 +494,12 @@ fn test_build_context_with_deploy_config_override() {
     };
 
     // Start with SvelteKit but deploy config overrides to Vite
-    let context = BuildContext::new(SiteType::SvelteKit, Some(deploy_config));
+    let context = BuildContext::with_package_manager(
+        SiteType::SvelteKit,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap();
 
     assert_eq!(context.site_type, SiteType::Vite);
     assert_eq!(context.build_command, "yarn build");
@@ -225,13 +515,234 @@ fn test_build_context_partial_deploy_config() {
         ..Default::default()
     };
 
-    let context = BuildContext::new(SiteType::SvelteKit, Some(deploy_config));
+    let context = BuildContext::with_package_manager(
+        SiteType::SvelteKit,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap();
 
     assert_eq!(context.site_type, SiteType::SvelteKit);
     assert_eq!(context.build_command, "custom build"); // overridden
     assert_eq!(context.output_dir, "build"); // default
 }
 
+#[test]
+fn test_build_context_build_env() {
+    let deploy_config = DeployConfig {
+        build_args: Some(
+            [
+                ("THEME".to_string(), "dark".to_string()),
+                ("ENABLE_DOCS".to_string(), "true".to_string()),
+                ("not valid".to_string(), "x".to_string()),
+                ("1BAD".to_string(), "x".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        ),
+        ..Default::default()
+    };
+
+    let context = BuildContext::with_package_manager(
+        SiteType::Vite,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap();
+
+    // Sorted by name, invalid names skipped
+    assert_eq!(
+        context.build_env(),
+        vec!["ENABLE_DOCS=true".to_string(), "THEME=dark".to_string()]
+    );
+}
+
+#[test]
+fn test_build_context_no_build_args() {
+    let context =
+        BuildContext::with_package_manager(SiteType::Vite, PackageManager::Npm, None).unwrap();
+
+    assert!(context.build_args.is_empty());
+    assert!(context.build_env().is_empty());
+}
+
+const DEFAULT_LIMITS: ResourceLimits = ResourceLimits {
+    memory_bytes: 4 * 1024 * 1024 * 1024,
+    cpu_quota: 200_000,
+    pids_limit: 1000,
+};
+
+const MAX_LIMITS: ResourceLimits = ResourceLimits {
+    memory_bytes: 8 * 1024 * 1024 * 1024,
+    cpu_quota: 400_000,
+    pids_limit: 4096,
+};
+
+#[test]
+fn test_resource_limits_default_when_unspecified() {
+    let context =
+        BuildContext::with_package_manager(SiteType::Vite, PackageManager::Npm, None).unwrap();
+
+    assert_eq!(
+        context.resource_limits(DEFAULT_LIMITS, MAX_LIMITS),
+        DEFAULT_LIMITS
+    );
+}
+
+#[test]
+fn test_resource_limits_override_within_max() {
+    let deploy_config = DeployConfig {
+        memory_mb: Some(6144),
+        cpu_cores: Some(1.5),
+        pids_limit: Some(2000),
+        ..Default::default()
+    };
+
+    let context = BuildContext::with_package_manager(
+        SiteType::Vite,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap();
+    let limits = context.resource_limits(DEFAULT_LIMITS, MAX_LIMITS);
+
+    assert_eq!(limits.memory_bytes, 6144 * 1024 * 1024);
+    assert_eq!(limits.cpu_quota, 150_000);
+    assert_eq!(limits.pids_limit, 2000);
+}
+
+#[test]
+fn test_resource_limits_clamped_to_max() {
+    let deploy_config = DeployConfig {
+        memory_mb: Some(u64::MAX),
+        cpu_cores: Some(64.0),
+        pids_limit: Some(1_000_000),
+        ..Default::default()
+    };
+
+    let context = BuildContext::with_package_manager(
+        SiteType::Vite,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap();
+
+    assert_eq!(
+        context.resource_limits(DEFAULT_LIMITS, MAX_LIMITS),
+        MAX_LIMITS
+    );
+}
+
+#[test]
+fn test_resource_limits_cannot_disable_limits() {
+    // Zero would mean "unlimited" to Podman, so tiny requests are raised to a floor
+    let deploy_config = DeployConfig {
+        memory_mb: Some(0),
+        cpu_cores: Some(0.0),
+        pids_limit: Some(-1),
+        ..Default::default()
+    };
+
+    let context = BuildContext::with_package_manager(
+        SiteType::Vite,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap();
+    let limits = context.resource_limits(DEFAULT_LIMITS, MAX_LIMITS);
+
+    assert!(limits.memory_bytes > 0);
+    assert!(limits.cpu_quota > 0);
+    assert!(limits.pids_limit > 0);
+}
+
+#[tokio::test]
+async fn test_load_deploy_config_build_args() {
+    let dir = create_test_repo();
+    fs::write(
+        dir.path().join(".deploy.json"),
+        r#"{"build_args": {"THEME": "light"}}"#,
+    )
+    .unwrap();
+
+    let config = load_deploy_config(dir.path()).await.unwrap();
+    assert_eq!(config.build_args.unwrap()["THEME"], "light");
+}
+
+#[tokio::test]
+async fn test_resolve_root_dir_nested_app() {
+    let dir = create_test_repo();
+    fs::write(dir.path().join("package.json"), "{}").unwrap();
+    fs::create_dir_all(dir.path().join("apps/web")).unwrap();
+    fs::write(dir.path().join("apps/web/svelte.config.js"), "// svelte").unwrap();
+    fs::write(
+        dir.path().join(".deploy.json"),
+        r#"{"root_dir": "apps/web", "output_dir": "out"}"#,
+    )
+    .unwrap();
+
+    let config = load_deploy_config(dir.path()).await.unwrap();
+    let app_dir = resolve_root_dir(dir.path(), config.root_dir.as_deref())
+        .await
+        .unwrap();
+    assert_eq!(app_dir, dir.path().join("apps/web"));
+
+    // Detection looks at the app, not the repo root
+    assert_eq!(detect_site_type(&app_dir).await, SiteType::SvelteKit);
+    assert_eq!(detect_site_type(dir.path()).await, SiteType::Vite);
+
+    let context =
+        BuildContext::with_package_manager(SiteType::SvelteKit, PackageManager::Npm, Some(config))
+            .unwrap();
+    assert_eq!(context.root_dir.as_deref(), Some("apps/web"));
+    assert_eq!(context.output_dir, "out");
+}
+
+#[tokio::test]
+async fn test_resolve_root_dir_defaults_to_repo() {
+    let dir = create_test_repo();
+    let app_dir = resolve_root_dir(dir.path(), None).await.unwrap();
+    assert_eq!(app_dir, dir.path());
+}
+
+#[tokio::test]
+async fn test_resolve_root_dir_rejects_escape() {
+    let dir = create_test_repo();
+    let repo = dir.path().join("repo");
+    fs::create_dir_all(repo.join("apps")).unwrap();
+    fs::create_dir_all(dir.path().join("outside")).unwrap();
+
+    for root_dir in ["../outside", "apps/../../outside", "/etc"] {
+        assert!(
+            resolve_root_dir(&repo, Some(root_dir)).await.is_err(),
+            "{} should be rejected",
+            root_dir
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_resolve_root_dir_rejects_symlink_escape() {
+    let dir = create_test_repo();
+    let repo = dir.path().join("repo");
+    fs::create_dir_all(&repo).unwrap();
+    fs::create_dir_all(dir.path().join("outside")).unwrap();
+    std::os::unix::fs::symlink(dir.path().join("outside"), repo.join("app")).unwrap();
+
+    let err = resolve_root_dir(&repo, Some("app")).await.unwrap_err();
+    assert!(err.to_string().contains("escapes the repository"));
+}
+
+#[tokio::test]
+async fn test_resolve_root_dir_missing() {
+    let dir = create_test_repo();
+    assert!(
+        resolve_root_dir(dir.path(), Some("apps/missing"))
+            .await
+            .is_err()
+    );
+}
+
 #[test]
 fn test_site_type_flake_refs() {
     assert_eq!(
@@ -250,6 +761,80 @@ fn test_site_type_flake_refs() {
     assert_eq!(SiteType::Auto.flake_ref(), None);
 }
 
+#[test]
+fn test_site_type_node_flake_refs() {
+    assert_eq!(
+        SiteType::SvelteKit.node_flake_ref("20").unwrap().as_deref(),
+        Some("github:nullisLabs/catapult#sveltekit-node20")
+    );
+    assert_eq!(
+        SiteType::Vite.node_flake_ref("22").unwrap().as_deref(),
+        Some("github:nullisLabs/catapult#vite-node22")
+    );
+    // A leading "v" is accepted
+    assert_eq!(
+        SiteType::Vite.node_flake_ref("v20").unwrap().as_deref(),
+        Some("github:nullisLabs/catapult#vite-node20")
+    );
+
+    // Non-Node site types have no versioned shell
+    assert_eq!(SiteType::Zola.node_flake_ref("20").unwrap(), None);
+    assert_eq!(SiteType::Custom.node_flake_ref("20").unwrap(), None);
+
+    let err = SiteType::Vite.node_flake_ref("16").unwrap_err();
+    assert!(err.contains("Unsupported node_version '16'"));
+    assert!(err.contains("20, 22"));
+}
+
+#[test]
+fn test_build_context_node_version() {
+    let deploy_config = DeployConfig {
+        node_version: Some("20".to_string()),
+        ..Default::default()
+    };
+    let context = BuildContext::with_package_manager(
+        SiteType::SvelteKit,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap();
+    assert_eq!(
+        context.flake_ref.as_deref(),
+        Some("github:nullisLabs/catapult#sveltekit-node20")
+    );
+
+    // Ignored for Zola, which doesn't use Node.js
+    let deploy_config = DeployConfig {
+        node_version: Some("20".to_string()),
+        ..Default::default()
+    };
+    let context = BuildContext::with_package_manager(
+        SiteType::Zola,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap();
+    assert_eq!(
+        context.flake_ref.as_deref(),
+        Some("github:nullisLabs/catapult#zola")
+    );
+}
+
+#[test]
+fn test_build_context_unsupported_node_version() {
+    let deploy_config = DeployConfig {
+        node_version: Some("14".to_string()),
+        ..Default::default()
+    };
+    let err = BuildContext::with_package_manager(
+        SiteType::Vite,
+        PackageManager::Npm,
+        Some(deploy_config),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Unsupported node_version '14'"));
+}
+
 #[test]
 fn test_site_type_default_build_commands() {
     assert_eq!(